@@ -5,5 +5,21 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("benchmark", |b| b.iter(|| benchmark()));
 }
 
-criterion_group!(benches, criterion_benchmark);
+// Compares the binding strategies: substitution sets and a trail.
+pub fn trail_benchmark(c: &mut Criterion) {
+    set_binding_strategy(BindingStrategy::Trail);
+    c.bench_function("benchmark, trail", |b| b.iter(|| benchmark()));
+    set_binding_strategy(BindingStrategy::Persistent);
+}
+
+// Compares queries to a large fact base, with and without indexing.
+pub fn index_benchmark(c: &mut Criterion) {
+    let n = 20000;
+    let kb = make_fact_base(n, false);
+    c.bench_function("lookup, no index", |b| b.iter(|| lookup_benchmark(&kb, n)));
+    let kb = make_fact_base(n, true);
+    c.bench_function("lookup, indexed", |b| b.iter(|| lookup_benchmark(&kb, n)));
+}
+
+criterion_group!(benches, criterion_benchmark, trail_benchmark, index_benchmark);
 criterion_main!(benches);
@@ -4,6 +4,9 @@
 //! to measure the speed of the inference engine, and to compare
 //! it with other implementations.
 //!
+//! It also measures queries to a large fact base, with and without
+//! indexing. See [set_indexing()](../knowledge_base/struct.KnowledgeBase.html#method.set_indexing).
+//!
 //! Run the following command from the CLI:
 //! <pre>
 //! > cargo bench
@@ -23,6 +26,7 @@ use super::s_complex::*;
 use super::solutions::*;
 use super::rule_reader::*;
 use super::knowledge_base::*;
+use super::rule::*;
 
 /// Reads in a qsort algorithm and data from a file, then runs the algorithm.
 ///
@@ -54,3 +58,43 @@ pub fn benchmark() {
     } // match
 
 }  // benchmark
+
+/// Makes a knowledge base of facts for the indexing benchmark.
+///
+/// The facts have the form: `account(id_17, owner(name_17, city_2), 1700)`.
+///
+/// # Arguments
+/// * number of facts
+/// * true to turn on indexing
+/// # Return
+/// * knowledge base
+pub fn make_fact_base(n: usize, indexed: bool) -> KnowledgeBase {
+    let mut kb = KnowledgeBase::new();
+    let facts: Vec<Rule> = (0..n).map(|i| {
+        let s = format!("account(id_{}, owner(name_{}, city_{}), {}).",
+                        i, i, i % 10, i * 100);
+        parse_rule(&s).unwrap()
+    }).collect();
+    add_rules(&mut kb, facts);
+    kb.set_indexing(indexed);
+    return kb;
+} // make_fact_base()
+
+/// Looks up facts in a knowledge base made by
+/// [make_fact_base()](fn.make_fact_base.html), by a deep argument
+/// (the owner's name) and by the first argument.
+///
+/// # Arguments
+/// * knowledge base
+/// * number of facts
+pub fn lookup_benchmark(kb: &KnowledgeBase, n: usize) {
+    for i in (0..n).step_by(n / 10 + 1) {
+        let queries = [format!("account($Id, owner(name_{}, $City), $Balance)", i),
+                       format!("account(id_{}, $Owner, $Balance)", i)];
+        for q in queries {
+            let query = parse_query(&q).unwrap();
+            let sn = make_base_node(Rc::new(query), kb);
+            solve(sn);
+        }
+    }
+} // lookup_benchmark()
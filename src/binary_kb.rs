@@ -0,0 +1,635 @@
+//! Functions to save a knowledge base in a compact binary format,
+//! and to load it again.
+//!
+//! Loading a large knowledge base from Suiron source code requires
+//! parsing every fact and rule. A knowledge base which has been saved
+//! with [save_kb()](fn.save_kb.html) can be loaded much faster with
+//! [load_kb()](fn.load_kb.html), because its terms are stored in their
+//! parsed form.
+//!
+//! The format begins with the signature `SKB` and a version number.
+//! The text of each atom is stored once. Later occurrences refer to
+//! it by number. Integers are stored as variable-length numbers.
+//!
+//! The format is intended for caching. A file should be loaded by the
+//! same version of Suiron which saved it. A file which is truncated or
+//! corrupt is rejected, and nothing is loaded from it. Terms and goals
+//! can be nested up to 1000 levels deep. (The items of a list are not
+//! nested, so a list can have any length.)
+//
+// Cleve Lendon 2023
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::error::SuironError;
+use super::goal::Goal;
+use super::knowledge_base::*;
+use super::operator::Operator;
+use super::built_in_predicates::BuiltInPredicate;
+use super::rule::Rule;
+use super::symbol::Symbol;
+use super::unifiable::Unifiable;
+
+const SIGNATURE: &[u8; 4] = b"SKB\x03";
+
+// Tags for terms.
+const NIL: u8 = 0;
+const ANONYMOUS: u8 = 1;
+const NEW_ATOM: u8 = 2;
+const ATOM: u8 = 3;
+const SSTRING: u8 = 4;
+const SFLOAT: u8 = 5;
+const SINTEGER: u8 = 6;
+const SRATIONAL: u8 = 7;
+const SBIGINT: u8 = 8;
+const LOGIC_VAR: u8 = 9;
+const SCOMPLEX: u8 = 10;
+const SLINKED_LIST: u8 = 11;
+const SFUNCTION: u8 = 12;
+
+// Maximum nesting of terms and goals.
+const MAX_DEPTH: usize = 1000;
+
+// Tags for goals.
+const GOAL_NIL: u8 = 0;
+const COMPLEX_GOAL: u8 = 1;
+const BUILT_IN_GOAL: u8 = 2;
+const AND: u8 = 3;
+const OR: u8 = 4;
+const TIME: u8 = 5;
+const NOT: u8 = 6;
+
+/// Saves a knowledge base to a file, in binary format.
+///
+/// # Arguments
+/// * knowledge base
+/// * file name
+/// # Return
+/// * Ok or error
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// load_kb_from_file(&mut kb, "SUIRON_TEST_DIR/kings.txt");
+/// let path = std::env::temp_dir().join("kings.skb");
+/// if let Err(err) = save_kb(&kb, path.to_str().unwrap()) { println!("{}", err); }
+/// ```
+pub fn save_kb(kb: &KnowledgeBase, file_name: &str) -> Result<(), SuironError> {
+    let file = File::create(file_name)?;
+    let mut writer = BufWriter::new(file);
+    write_kb(kb, &mut writer)?;
+    writer.flush()?;
+    return Ok(());
+} // save_kb()
+
+/// Loads facts and rules from a binary file, which was written by
+/// [save_kb()](fn.save_kb.html), into a knowledge base.
+///
+/// The facts and rules are added after any which the knowledge base
+/// already contains.
+///
+/// # Arguments
+/// * knowledge base
+/// * file name
+/// # Return
+/// * Ok or error
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// let path = std::env::temp_dir().join("kings.skb");
+/// if let Err(err) = load_kb(&mut kb, path.to_str().unwrap()) { println!("{}", err); }
+/// ```
+pub fn load_kb(kb: &mut KnowledgeBase, file_name: &str) -> Result<(), SuironError> {
+    let file = File::open(file_name)?;
+    return read_kb(kb, BufReader::new(file));
+} // load_kb()
+
+/// Writes a knowledge base in binary format.
+///
+/// # Arguments
+/// * knowledge base
+/// * writer
+/// # Return
+/// * Ok or error
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let mut bytes: Vec<u8> = vec![];
+/// write_kb(&kb, &mut bytes).unwrap();
+///
+/// let mut kb2 = KnowledgeBase::new();
+/// read_kb(&mut kb2, &bytes[..]).unwrap();
+/// assert_eq!(format_kb(&kb), format_kb(&kb2));
+/// ```
+pub fn write_kb(kb: &KnowledgeBase, writer: &mut impl Write) -> Result<(), SuironError> {
+
+    let mut out = Encoder{ bytes: vec![], atoms: HashMap::new() };
+    out.bytes.extend_from_slice(SIGNATURE);
+
+    let dynamic: Vec<&str> = kb.dynamic_predicates().collect();
+    out.number(dynamic.len() as u64);
+    for name in dynamic { out.text(name); }
+
+    let tabled: Vec<&str> = kb.tabled_predicates().collect();
+    out.number(tabled.len() as u64);
+    for name in tabled { out.text(name); }
+
+    let operators = kb.operators();
+    out.number(operators.len() as u64);
+    for op in operators {
+        out.number(op.precedence as u64);
+        out.text(&op.op_type.to_string());
+        out.text(&op.name);
+    }
+
+    let names: Vec<&str> = kb.predicates().collect();
+    out.number(names.len() as u64);
+    for name in names {
+        out.text(name);
+        out.number(kb.rules(name).len() as u64);
+        for rule in kb.rules(name) {
+            out.term(&rule.head)?;
+            out.goal(&rule.body)?;
+        }
+    }
+    writer.write_all(&out.bytes)?;
+    return Ok(());
+
+} // write_kb()
+
+/// Reads facts and rules in binary format into a knowledge base.
+///
+/// The whole input is decoded before anything is added to the knowledge
+/// base. If the input is invalid, the knowledge base is unchanged.
+///
+/// # Arguments
+/// * knowledge base
+/// * reader
+/// # Return
+/// * Ok or error
+pub fn read_kb(kb: &mut KnowledgeBase, mut reader: impl Read) -> Result<(), SuironError> {
+
+    let mut bytes: Vec<u8> = vec![];
+    reader.read_to_end(&mut bytes)?;
+    if !bytes.starts_with(SIGNATURE) {
+        return Err(format_error("Not a Suiron knowledge base."));
+    }
+    let mut input = Decoder{ bytes: &bytes, position: SIGNATURE.len(),
+                             atoms: vec![], depth: 0 };
+    let loaded = decode_kb(&mut input)?;
+    kb.append(loaded);
+    return Ok(());
+
+} // read_kb()
+
+// Decodes a knowledge base.
+//
+// Arguments
+//    decoder, positioned after the signature
+// Return
+//    knowledge base or error
+fn decode_kb(input: &mut Decoder) -> Result<KnowledgeBase, SuironError> {
+
+    let mut kb = KnowledgeBase::new();
+
+    let n = input.number()?;
+    for _ in 0..n { kb.set_dynamic(&input.text()?); }
+
+    let n = input.number()?;
+    for _ in 0..n { kb.set_tabled(&input.text()?); }
+
+    let n = input.number()?;
+    for _ in 0..n {
+        let precedence = input.number()? as i64;
+        let op_type = input.text()?;
+        let name = input.text()?;
+        kb.add_operator(precedence, &op_type, &name)
+          .map_err(|err| format_error(&err))?;
+    }
+
+    let n = input.number()?;
+    for _ in 0..n {
+        let name = input.text()?;
+        let count = input.number()? as usize;
+        let mut rules: Vec<Rule> = Vec::with_capacity(count.min(input.bytes.len()));
+        for _ in 0..count {
+            let head = input.term()?;
+            let body = input.goal()?;
+            rules.push(Rule{ head, body });
+        }
+        match kb.rules_mut(&name) {
+            Some(existing) => { existing.extend(rules); },
+            None => { kb.insert(name, Rc::new(rules)); },
+        }
+    }
+    if input.position != input.bytes.len() {
+        return Err(format_error("Unexpected data at end."));
+    }
+    return Ok(kb);
+
+} // decode_kb()
+
+// Creates an error for an invalid file.
+fn format_error(msg: &str) -> SuironError {
+    return SuironError::IoError(format!("read_kb() - {}", msg));
+}
+
+// Writes terms and goals into a byte vector.
+struct Encoder {
+    bytes: Vec<u8>,
+    atoms: HashMap<Symbol, u64>,  // atom numbers
+}
+
+impl Encoder {
+
+    // Writes an unsigned number, 7 bits per byte.
+    fn number(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.bytes.push((n as u8) | 0x80);
+            n >>= 7;
+        }
+        self.bytes.push(n as u8);
+    }
+
+    // Writes a signed number. Small negative numbers remain short.
+    fn signed(&mut self, n: i64) {
+        self.number(((n << 1) ^ (n >> 63)) as u64);
+    }
+
+    fn text(&mut self, s: &str) {
+        self.number(s.len() as u64);
+        self.bytes.extend_from_slice(s.as_bytes());
+    }
+
+    fn term(&mut self, term: &Unifiable) -> Result<(), SuironError> {
+        match term {
+            Unifiable::Nil => { self.bytes.push(NIL); },
+            Unifiable::Anonymous => { self.bytes.push(ANONYMOUS); },
+            Unifiable::Atom(s) => {
+                match self.atoms.get(s) {
+                    Some(n) => {
+                        let n = *n;
+                        self.bytes.push(ATOM);
+                        self.number(n);
+                    },
+                    None => {
+                        self.atoms.insert(s.clone(), self.atoms.len() as u64);
+                        self.bytes.push(NEW_ATOM);
+                        self.text(s);
+                    },
+                }
+            },
+            Unifiable::SString(s) => {
+                self.bytes.push(SSTRING);
+                self.text(s);
+            },
+            Unifiable::SFloat(f) => {
+                self.bytes.push(SFLOAT);
+                self.bytes.extend_from_slice(&f.to_le_bytes());
+            },
+            Unifiable::SInteger(i) => {
+                self.bytes.push(SINTEGER);
+                self.signed(*i);
+            },
+            Unifiable::SRational(n, d) => {
+                self.bytes.push(SRATIONAL);
+                self.signed(*n);
+                self.signed(*d);
+            },
+            #[cfg(feature = "bigint")]
+            Unifiable::SBigInt(b) => {
+                self.bytes.push(SBIGINT);
+                self.text(&b.to_string());
+            },
+            Unifiable::LogicVar{id, name} => {
+                self.bytes.push(LOGIC_VAR);
+                self.number(*id as u64);
+                self.text(name);
+            },
+            Unifiable::SComplex(terms) => {
+                self.bytes.push(SCOMPLEX);
+                self.terms(terms)?;
+            },
+            Unifiable::SLinkedList{..} => {
+                // The nodes of a list are written one after another,
+                // followed by the end of the list.
+                let mut node = term;
+                while let Unifiable::SLinkedList{term, next, count, tail_var} = node {
+                    self.bytes.push(SLINKED_LIST);
+                    self.number(*count as u64);
+                    self.bytes.push(*tail_var as u8);
+                    self.term(term)?;
+                    node = next;
+                }
+                self.term(node)?;
+            },
+            Unifiable::SFunction{name, terms} => {
+                self.bytes.push(SFUNCTION);
+                self.text(name);
+                self.terms(terms)?;
+            },
+        }
+        return Ok(());
+    } // term()
+
+    fn terms(&mut self, terms: &[Unifiable]) -> Result<(), SuironError> {
+        self.number(terms.len() as u64);
+        for t in terms { self.term(t)?; }
+        return Ok(());
+    }
+
+    fn goal(&mut self, goal: &Goal) -> Result<(), SuironError> {
+        match goal {
+            Goal::Nil => { self.bytes.push(GOAL_NIL); },
+            Goal::ComplexGoal(term) => {
+                self.bytes.push(COMPLEX_GOAL);
+                self.term(term)?;
+            },
+            Goal::BuiltInGoal(bip) => {
+                self.bytes.push(BUILT_IN_GOAL);
+                self.text(&bip.functor);
+                match &bip.terms {
+                    Some(terms) => {
+                        self.bytes.push(1);
+                        self.terms(terms)?;
+                    },
+                    None => { self.bytes.push(0); },
+                }
+            },
+            Goal::OperatorGoal(op) => {
+                let (tag, goals) = match op {
+                    Operator::And(goals)  => { (AND, goals) },
+                    Operator::Or(goals)   => { (OR, goals) },
+                    Operator::Time(goals) => { (TIME, goals) },
+                    Operator::Not(goals)  => { (NOT, goals) },
+                };
+                self.bytes.push(tag);
+                self.number(goals.len() as u64);
+                for g in goals { self.goal(g)?; }
+            },
+        }
+        return Ok(());
+    } // goal()
+
+} // impl Encoder
+
+// Reads terms and goals from a byte slice.
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    atoms: Vec<Symbol>,  // atoms, by number
+    depth: usize,        // nesting of the current term or goal
+}
+
+impl<'a> Decoder<'a> {
+
+    fn byte(&mut self) -> Result<u8, SuironError> {
+        let b = *self.bytes.get(self.position)
+                           .ok_or_else(|| format_error("Unexpected end of data."))?;
+        self.position += 1;
+        return Ok(b);
+    }
+
+    fn number(&mut self) -> Result<u64, SuironError> {
+        let mut n: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let b = self.byte()?;
+            if shift > 63 { return Err(format_error("Invalid number.")); }
+            n |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 { return Ok(n); }
+            shift += 7;
+        }
+    }
+
+    fn signed(&mut self) -> Result<i64, SuironError> {
+        let n = self.number()?;
+        return Ok(((n >> 1) as i64) ^ -((n & 1) as i64));
+    }
+
+    fn text(&mut self) -> Result<String, SuironError> {
+        let len = self.number()? as usize;
+        let end = self.position.checked_add(len)
+                      .filter(|end| *end <= self.bytes.len())
+                      .ok_or_else(|| format_error("Unexpected end of data."))?;
+        let s = std::str::from_utf8(&self.bytes[self.position..end])
+                    .map_err(|_| format_error("Invalid UTF-8."))?;
+        self.position = end;
+        return Ok(s.to_string());
+    }
+
+    // Counts a level of nesting. A file which is nested too deeply
+    // is rejected, rather than overflowing the stack.
+    fn enter(&mut self) -> Result<(), SuironError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format_error("Terms are nested too deeply."));
+        }
+        return Ok(());
+    }
+
+    fn term(&mut self) -> Result<Unifiable, SuironError> {
+        self.enter()?;
+        let term = match self.byte()? {
+            NIL => { Unifiable::Nil },
+            ANONYMOUS => { Unifiable::Anonymous },
+            NEW_ATOM => {
+                let s = Symbol::new(&self.text()?);
+                self.atoms.push(s.clone());
+                Unifiable::Atom(s)
+            },
+            ATOM => {
+                let n = self.number()? as usize;
+                let s = self.atoms.get(n).ok_or_else(|| format_error("Invalid atom."))?;
+                Unifiable::Atom(s.clone())
+            },
+            SSTRING => { Unifiable::SString(self.text()?) },
+            SFLOAT => {
+                let mut b = [0u8; 8];
+                for x in b.iter_mut() { *x = self.byte()?; }
+                Unifiable::SFloat(f64::from_le_bytes(b))
+            },
+            SINTEGER => { Unifiable::SInteger(self.signed()?) },
+            SRATIONAL => {
+                let n = self.signed()?;
+                let d = self.signed()?;
+                Unifiable::SRational(n, d)
+            },
+            #[cfg(feature = "bigint")]
+            SBIGINT => {
+                let b = self.text()?.parse::<num_bigint::BigInt>()
+                            .map_err(|_| format_error("Invalid big integer."))?;
+                Unifiable::SBigInt(b)
+            },
+            #[cfg(not(feature = "bigint"))]
+            SBIGINT => {
+                return Err(format_error("Big integers require the bigint feature."));
+            },
+            LOGIC_VAR => {
+                let id = self.number()? as usize;
+                let name = self.text()?;
+                Unifiable::LogicVar{ id, name }
+            },
+            SCOMPLEX => { Unifiable::SComplex(self.terms()?) },
+            SLINKED_LIST => {
+                // Read the nodes of the list, then the end of the list.
+                let mut nodes: Vec<(usize, bool, Unifiable)> = vec![];
+                loop {
+                    let count = self.number()? as usize;
+                    let tail_var = self.byte()? != 0;
+                    nodes.push((count, tail_var, self.term()?));
+                    if self.bytes.get(self.position) != Some(&SLINKED_LIST) { break; }
+                    self.position += 1;
+                }
+                let mut list = self.term()?;
+                for (count, tail_var, term) in nodes.into_iter().rev() {
+                    list = Unifiable::SLinkedList{ term: Rc::new(term), next: Rc::new(list),
+                                                   count, tail_var };
+                }
+                list
+            },
+            SFUNCTION => {
+                let name = self.text()?;
+                let terms = self.terms()?;
+                Unifiable::SFunction{ name, terms }
+            },
+            _ => { return Err(format_error("Invalid term.")); },
+        };
+        self.depth -= 1;
+        return Ok(term);
+    } // term()
+
+    fn terms(&mut self) -> Result<Vec<Unifiable>, SuironError> {
+        let n = self.number()? as usize;
+        let mut terms = Vec::with_capacity(n.min(self.bytes.len()));
+        for _ in 0..n { terms.push(self.term()?); }
+        return Ok(terms);
+    }
+
+    fn goal(&mut self) -> Result<Goal, SuironError> {
+        self.enter()?;
+        let goal = match self.byte()? {
+            GOAL_NIL => { Goal::Nil },
+            COMPLEX_GOAL => { Goal::ComplexGoal(self.term()?) },
+            BUILT_IN_GOAL => {
+                let functor = self.text()?;
+                let terms = if self.byte()? != 0 { Some(self.terms()?) } else { None };
+                Goal::BuiltInGoal(BuiltInPredicate{ functor, terms })
+            },
+            tag @ (AND | OR | TIME | NOT) => {
+                let n = self.number()? as usize;
+                let mut goals = Vec::with_capacity(n.min(self.bytes.len()));
+                for _ in 0..n { goals.push(self.goal()?); }
+                let op = match tag {
+                    AND => { Operator::And(goals) },
+                    OR => { Operator::Or(goals) },
+                    TIME => { Operator::Time(goals) },
+                    _ => { Operator::Not(goals) },
+                };
+                Goal::OperatorGoal(op)
+            },
+            _ => { return Err(format_error("Invalid goal.")); },
+        };
+        self.depth -= 1;
+        return Ok(goal);
+    } // goal()
+
+} // impl Decoder
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    #[test]
+    fn test_save_and_load() {
+
+        let mut kb = KnowledgeBase::new();
+        let path = format!("{}/kings.txt", env!("SUIRON_TEST_DIR"));
+        load_kb_from_file(&mut kb, &path);
+        let rules = ["test($X, $Y) :- not($X = [a, b | $T]), $Y = \"str\", \
+                                     ($X < 1.5; $X > 2r3), !, print($Y, -7).",
+                     "big($N) :- $N = 123456789012, time(print(done)), $Z = add($N, 1)."];
+        for r in rules { add_rules!(&mut kb, parse_rule(r).unwrap()); }
+        kb.set_dynamic("score/2");
+        kb.set_tabled("path/2");
+        kb.add_operator(700, "xfx", "likes").unwrap();
+
+        let mut bytes: Vec<u8> = vec![];
+        write_kb(&kb, &mut bytes).unwrap();
+        let mut kb2 = KnowledgeBase::new();
+        read_kb(&mut kb2, &bytes[..]).unwrap();
+        assert_eq!(format_kb(&kb), format_kb(&kb2));
+        assert!(kb2.is_dynamic("score/2"));
+        assert!(kb2.is_tabled("path/2"));
+        assert_eq!(kb.operators(), kb2.operators());
+
+        let solutions = |kb: &KnowledgeBase| {
+            let query = parse_query("father($F, $C)").unwrap();
+            solve_all(make_base_node(Rc::new(query), kb))
+        };
+        assert_eq!(solutions(&kb), solutions(&kb2));
+
+        let path = std::env::temp_dir().join("suiron_test_kings.skb");
+        let path = path.to_str().unwrap();
+        save_kb(&kb, path).unwrap();
+        let mut kb3 = KnowledgeBase::new();
+        load_kb(&mut kb3, path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(format_kb(&kb), format_kb(&kb3));
+        assert!(load_kb(&mut kb3, path).is_err());
+
+        // Loading appends to existing facts and rules.
+        read_kb(&mut kb2, &bytes[..]).unwrap();
+        assert_eq!(2, count_rules(&kb2, "test/2"));
+
+        assert!(read_kb(&mut kb2, &b"SKB"[..]).is_err());
+        assert!(read_kb(&mut kb2, &bytes[..bytes.len() - 1]).is_err());
+
+    } // test_save_and_load()
+
+    // A corrupt file does not change the knowledge base.
+    #[test]
+    fn test_invalid_file() {
+
+        let mut kb = KnowledgeBase::new();
+        add_rules!(&mut kb, parse_rule("color(red).").unwrap());
+        kb.set_dynamic("score/2");
+        kb.add_operator(700, "xfx", "likes").unwrap();
+        let mut bytes: Vec<u8> = vec![];
+        write_kb(&kb, &mut bytes).unwrap();
+
+        let mut kb2 = KnowledgeBase::new();
+        assert!(read_kb(&mut kb2, &bytes[..bytes.len() - 1]).is_err());
+        assert!(!kb2.is_dynamic("score/2"));
+        assert!(kb2.operators().is_empty());
+        assert_eq!(0, count_rules(&kb2, "color/1"));
+
+        // Deeply nested terms are rejected.
+        let mut bytes = b"SKB\x03\x00\x00\x00\x01\x03p/1\x01".to_vec();
+        for _ in 0..100_000 { bytes.extend_from_slice(&[10, 1]); }
+        let err = read_kb(&mut kb2, &bytes[..]).unwrap_err();
+        assert_eq!("read_kb() - Terms are nested too deeply.", err);
+
+        // Long lists are not nested.
+        let items: Vec<Unifiable> = (0..10_000).map(|i| SInteger(i)).collect();
+        let list = make_linked_list(false, items);
+        add_rules!(&mut kb, make_fact(scomplex!(atom!("numbers"), list)));
+        let mut bytes: Vec<u8> = vec![];
+        write_kb(&kb, &mut bytes).unwrap();
+        read_kb(&mut kb2, &bytes[..]).unwrap();
+        assert_eq!(format_kb(&kb), format_kb(&kb2));
+
+    } // test_invalid_file()
+
+} // test
@@ -53,18 +53,22 @@ pub fn next_solution_append<'a>(bip: BuiltInPredicate,
                 Unifiable::Nil |
                 Unifiable::Anonymous |
                 Unifiable::Atom(_) |
+                Unifiable::SString(_) |
                 Unifiable::SInteger(_) |
                 Unifiable::SFloat(_) |
+                Unifiable::SRational(_, _) |
                 Unifiable::SFunction{name: _, terms: _} |
                 Unifiable::SComplex(_) => { out_terms.push(t); },
+                #[cfg(feature = "bigint")]
+                Unifiable::SBigInt(_) => { out_terms.push(t); },
                 Unifiable::SLinkedList{term: _, next: _, count: _, tail_var: _} => {
                     let mut list = t;
                     loop {
                         if let Unifiable::SLinkedList{term, next,
                                           count: _, tail_var: _} = list {
                             if *term == Unifiable::Nil { break; }
-                            out_terms.push(*term);
-                            list = *next;
+                            out_terms.push(Rc::unwrap_or_clone(term));
+                            list = Rc::unwrap_or_clone(next);
                         }
                     }
                 },
@@ -1,16 +1,97 @@
-//! Suiron's arithmetic functions: add, subtract, multiply, divide.
+//! Suiron's arithmetic functions: add, subtract, multiply, divide, int_divide,
+//! mod, rem, pow, min, max, bit_and, bit_or, xor, shift_left, shift_right.
+//!
+//! Math functions such as sqrt() and round() are defined in
+//! [built_in_math.rs](../built_in_math/index.html).
 //!
 //! The functions defined in this module support Suiron's built-in
 //! arithmetic functions.<br>
 //! They are called from
 //! [unify_sfunction()](../built_in_functions/fn.unify_sfunction.html#)
 //! in built_in_functions.rs.
+//!
+//! Arithmetic errors, such as a non-numeric argument, integer overflow
+//! or division by zero, do not panic. The evaluate functions return an
+//! error message, and the goal which called them fails.
+//!
+//! By default, divide (/) truncates when both arguments are integers, so
+//! 7 / 3 gives 2. After set_float_division(true), it gives 2.3333333333333335,
+//! as in Prolog. For an integer result, use int_divide (//), which always
+//! truncates toward zero.
+//!
+//! If an argument is a rational number (eg. 1r3), add, subtract, multiply,
+//! divide, min, max and pow keep the result exact. See
+//! [built_in_rational.rs](../built_in_rational/index.html).
+//!
+//! With the `bigint` feature, add, subtract, multiply, divide and pow
+//! do not overflow. If a result is too big for an SInteger, it is
+//! promoted to an arbitrary-precision SBigInt.
 //
 // Cleve Lendon 2023
 
 use std::rc::Rc;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use super::error::SuironError;
 use super::substitution_set::*;
 use super::unifiable::Unifiable;
+use super::built_in_math::*;
+use super::built_in_rational::*;
+
+#[cfg(feature = "bigint")]
+use num_bigint::BigInt;
+#[cfg(feature = "bigint")]
+use num_traits::{ToPrimitive, Zero, One};
+
+// Maximum size of a big integer which pow() or shift_left() can
+// produce, in bits. A larger result is reported as an overflow.
+#[cfg(feature = "bigint")]
+const MAX_BIGINT_BITS: u64 = 1 << 20;
+
+thread_local! {
+    // If true, dividing two integers with / gives a float.
+    static FLOAT_DIVISION: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets the behavior of integer division with divide() (/).
+///
+/// By default, when both arguments of divide() are integers, the result
+/// is truncated to an integer: 7 / 3 gives 2. If float division is set,
+/// the result is a float: 7 / 3 gives 2.3333333333333335, which is what
+/// most Prologs do. The integer division function, int_divide() (//),
+/// is not affected.
+///
+/// The setting is local to the thread. A query which has its own
+/// [QueryConfig](../query_config/struct.QueryConfig.html) uses the
+/// setting of its configuration instead.
+///
+/// # Arguments
+/// * true for float division, false for integer division
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let arguments = parse_arguments("7, 2").unwrap();
+/// set_float_division(true);
+/// assert_eq!(SFloat(3.5), evaluate_divide(&arguments, &ss).unwrap());
+/// set_float_division(false);
+/// assert_eq!(SInteger(3), evaluate_divide(&arguments, &ss).unwrap());
+/// ```
+pub fn set_float_division(on: bool) {
+    FLOAT_DIVISION.with(|fd| fd.set(on));
+} // set_float_division()
+
+/// Determines whether divide() (/) gives a float for integer arguments.
+///
+/// See [set_float_division()](fn.set_float_division.html).
+///
+/// # Return
+/// * true or false
+pub fn float_division() -> bool {
+    return FLOAT_DIVISION.with(|fd| fd.get());
+} // float_division()
 
 /// Add arguments together.
 ///
@@ -27,9 +108,10 @@ use super::unifiable::Unifiable;
 /// # Returns
 /// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger) or
 ///   [SFloat](../unifiable/enum.Unifiable.html#variant.SFloat)
-/// # Panics
+/// # Errors
 /// * If a logic variable in the list of terms is not grounded.
 /// * If one of the ground terms is not an SInteger or SFloat.
+/// * If an integer result overflows.
 /// # Usage
 /// ```
 /// use std::rc::Rc;
@@ -37,23 +119,31 @@ use super::unifiable::Unifiable;
 ///
 /// let ss = empty_ss!();
 /// let arguments = parse_arguments("1, 2, 3").unwrap();
-/// let result = evaluate_add(&arguments, &ss);
+/// let result = evaluate_add(&arguments, &ss).unwrap();
 /// println!("{}", result);
 /// // Prints: 6
 /// ```
-pub fn evaluate_add<'a>(arguments: &'a Vec<Unifiable>,
-                    ss: &'a Rc<SubstitutionSet<'a>>) -> Unifiable {
+pub fn evaluate_add<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
 
-    let (numbers, has_float) = get_numbers(arguments, ss);
+    let (numbers, has_float) = get_numbers(arguments, ss)?;
     if has_float {
         let f = get_floats(&numbers);
         let sum = f.iter().fold(0.0, |mut sum, &x| {sum += x; sum});
-        return Unifiable::SFloat(sum);
+        return Ok(Unifiable::SFloat(sum));
+    }
+    else if has_rational(&numbers) {
+        return rational_arithmetic("add", &numbers);
     }
     else {
         let i = get_integers(&numbers);
-        let sum = i.iter().fold(0, |mut sum, &x| {sum += x; sum});
-        return Unifiable::SInteger(sum);
+        let sum = i.iter().try_fold(0i64, |sum, &x| sum.checked_add(x));
+        #[cfg(feature = "bigint")]
+        if sum.is_none() || has_bigint(&numbers) {
+            let b = get_bigints(&numbers);
+            return Ok(from_bigint(b.into_iter().fold(BigInt::zero(), |sum, x| sum + x)));
+        }
+        return overflow_check("add", sum);
     }
 } // evaluate_add
 
@@ -73,9 +163,10 @@ pub fn evaluate_add<'a>(arguments: &'a Vec<Unifiable>,
 /// # Returns
 /// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger) or
 ///   [SFloat](../unifiable/enum.Unifiable.html#variant.SFloat)
-/// # Panics
+/// # Errors
 /// * If a logic variable in the list of terms is not grounded.
 /// * If one of the ground terms is not an SInteger or SFloat.
+/// * If an integer result overflows.
 /// # Usage
 /// ```
 /// use std::rc::Rc;
@@ -83,25 +174,35 @@ pub fn evaluate_add<'a>(arguments: &'a Vec<Unifiable>,
 ///
 /// let ss = empty_ss!();
 /// let arguments = parse_arguments("5.2, 1, 2").unwrap();
-/// let result = evaluate_subtract(&arguments, &ss);
+/// let result = evaluate_subtract(&arguments, &ss).unwrap();
 /// println!("{}", result);
 /// // Prints: 2.2
 /// ```
-pub fn evaluate_subtract<'a>(arguments: &'a Vec<Unifiable>,
-                    ss: &'a Rc<SubstitutionSet<'a>>) -> Unifiable {
+pub fn evaluate_subtract<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
 
-    let (numbers, has_float) = get_numbers(arguments, ss);
+    let (numbers, has_float) = get_numbers(arguments, ss)?;
+    if numbers.is_empty() {
+        return Err("evaluate_subtract() - Requires at least 1 argument.".to_string());
+    }
     if has_float {
         let mut f = get_floats(&numbers);
         let first = f.remove(0);
         let result = f.iter().fold(first, |mut result, &x| {result -= x; result});
-        return Unifiable::SFloat(result);
+        return Ok(Unifiable::SFloat(result));
+    }
+    else if has_rational(&numbers) {
+        return rational_arithmetic("subtract", &numbers);
     }
     else {
+        #[cfg(feature = "bigint")]
+        if has_bigint(&numbers) { return subtract_bigints(&numbers); }
         let mut i = get_integers(&numbers);
         let first = i.remove(0);
-        let result = i.iter().fold(first, |mut result, &x| {result -= x; result});
-        return Unifiable::SInteger(result);
+        let result = i.iter().try_fold(first, |result, &x| result.checked_sub(x));
+        #[cfg(feature = "bigint")]
+        if result.is_none() { return subtract_bigints(&numbers); }
+        return overflow_check("subtract", result);
     }
 } // evaluate_subtract
 
@@ -121,9 +222,10 @@ pub fn evaluate_subtract<'a>(arguments: &'a Vec<Unifiable>,
 /// # Returns
 /// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger) or
 ///   [SFloat](../unifiable/enum.Unifiable.html#variant.SFloat)
-/// # Panics
+/// # Errors
 /// * If a logic variable in the list of terms is not grounded.
 /// * If one of the ground terms is not an SInteger or SFloat.
+/// * If an integer result overflows.
 /// # Usage
 /// ```
 /// use std::rc::Rc;
@@ -131,24 +233,32 @@ pub fn evaluate_subtract<'a>(arguments: &'a Vec<Unifiable>,
 ///
 /// let ss = empty_ss!();
 /// let arguments = parse_arguments("3, 4, 0.25").unwrap();
-/// let result = evaluate_multiply(&arguments, &ss);
+/// let result = evaluate_multiply(&arguments, &ss).unwrap();
 /// println!("{}", result);
 /// // Prints: 3
 /// ```
 ///
-pub fn evaluate_multiply<'a>(arguments: &'a Vec<Unifiable>,
-                    ss: &'a Rc<SubstitutionSet<'a>>) -> Unifiable {
+pub fn evaluate_multiply<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
 
-    let (numbers, has_float) = get_numbers(arguments, ss);
+    let (numbers, has_float) = get_numbers(arguments, ss)?;
     if has_float {
         let f = get_floats(&numbers);
         let result = f.iter().fold(1.0, |mut result, &x| {result *= x; result});
-        return Unifiable::SFloat(result);
+        return Ok(Unifiable::SFloat(result));
+    }
+    else if has_rational(&numbers) {
+        return rational_arithmetic("multiply", &numbers);
     }
     else {
         let i = get_integers(&numbers);
-        let result = i.iter().fold(1, |mut result, &x| {result *= x; result});
-        return Unifiable::SInteger(result);
+        let result = i.iter().try_fold(1i64, |result, &x| result.checked_mul(x));
+        #[cfg(feature = "bigint")]
+        if result.is_none() || has_bigint(&numbers) {
+            let b = get_bigints(&numbers);
+            return Ok(from_bigint(b.into_iter().fold(BigInt::one(), |result, x| result * x)));
+        }
+        return overflow_check("multiply", result);
     }
 } // evaluate_multiply
 
@@ -174,9 +284,15 @@ pub fn evaluate_multiply<'a>(arguments: &'a Vec<Unifiable>,
 /// # Returns
 /// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger) or
 ///   [SFloat](../unifiable/enum.Unifiable.html#variant.SFloat)
-/// # Panics
+/// # Errors
 /// * If a logic variable in the list of terms is not grounded.
 /// * If one of the ground terms is not an SInteger or SFloat.
+/// * If an integer is divided by 0. (Floating point division by 0
+///   gives infinity.)
+/// # Notes
+/// * When all arguments are integers, the result is truncated, unless
+///   float division is set. See
+///   [set_float_division()](fn.set_float_division.html).
 /// # Usage
 /// ```
 /// use std::rc::Rc;
@@ -185,41 +301,803 @@ pub fn evaluate_multiply<'a>(arguments: &'a Vec<Unifiable>,
 /// // Floating point division.
 /// let ss = empty_ss!();
 /// let arguments = parse_arguments("12.6, 3, 3").unwrap();
-/// let result = evaluate_divide(&arguments, &ss);
+/// let result = evaluate_divide(&arguments, &ss).unwrap();
 /// println!("{}", result);
 /// // Prints: 1.4000000000000001
 ///
 /// // Integer division.
 /// let ss = empty_ss!();
 /// let arguments = parse_arguments("13, 3, 3").unwrap();
-/// let result = evaluate_divide(&arguments, &ss);
+/// let result = evaluate_divide(&arguments, &ss).unwrap();
 /// println!("{}", result);
 /// // Prints: 1
 /// ```
-pub fn evaluate_divide<'a>(arguments: &'a Vec<Unifiable>,
-                    ss: &'a Rc<SubstitutionSet<'a>>) -> Unifiable {
+pub fn evaluate_divide<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
 
-    let (numbers, has_float) = get_numbers(arguments, ss);
+    let (numbers, has_float) = get_numbers(arguments, ss)?;
+    if numbers.is_empty() {
+        return Err("evaluate_divide() - Requires at least 1 argument.".to_string());
+    }
     if has_float {
         let mut f = get_floats(&numbers);
         let first = f.remove(0);
         let result = f.iter().fold(first, |mut result, &x| {result /= x; result});
-        return Unifiable::SFloat(result);
+        return Ok(Unifiable::SFloat(result));
+    }
+    else if has_rational(&numbers) {
+        return rational_arithmetic("divide", &numbers);
     }
     else {
+        if numbers.iter().skip(1).any(|n| matches!(n, SNumber::SInteger(0))) {
+            return Err("evaluate_divide() - Division by zero.".to_string());
+        }
+        if float_division() {
+            let mut f = get_floats(&numbers);
+            let first = f.remove(0);
+            let result = f.iter().fold(first, |mut result, &x| {result /= x; result});
+            return Ok(Unifiable::SFloat(result));
+        }
+        #[cfg(feature = "bigint")]
+        if has_bigint(&numbers) { return divide_bigints(&numbers); }
         let mut i = get_integers(&numbers);
         let first = i.remove(0);
-        let result = i.iter().fold(first, |mut result, &x| {result /= x; result});
-        return Unifiable::SInteger(result);
+        let result = i.iter().try_fold(first, |result, &x| result.checked_div(x));
+        #[cfg(feature = "bigint")]
+        if result.is_none() { return divide_bigints(&numbers); }
+        return overflow_check("divide", result);
     }
 } // evaluate_divide
 
 
+/// Divides one integer by another. The result is truncated toward zero.
+///
+/// This is the same as Prolog's // operator:
+/// <blockquote>
+/// 7 // 3 => 2<br>
+/// -7 // 3 => -2
+/// </blockquote>
+///
+/// Unlike divide(), int_divide() is not affected by
+/// [set_float_division()](fn.set_float_division.html).
+///
+/// This method is called by
+/// [unify_sfunction()](../built_in_functions/fn.unify_sfunction.html#).
+///
+/// # Arguments
+/// * list of two [Unifiable](../unifiable/enum.Unifiable.html) terms
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger)
+/// # Errors
+/// * If there are not two arguments.
+/// * If an argument is not grounded, or not an integer.
+/// * If the divisor is 0.
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let arguments = parse_arguments("-7, 2").unwrap();
+/// let result = evaluate_int_divide(&arguments, &ss).unwrap();
+/// println!("{}", result);
+/// // Prints: -3
+/// ```
+pub fn evaluate_int_divide<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
+    let (a, b) = match get_integer_pair("int_divide", arguments, ss)? {
+        IntegerPair::Small(a, b) => { (a, b) },
+        #[cfg(feature = "bigint")]
+        IntegerPair::Big(a, b) => {
+            if b.is_zero() {
+                return Err("evaluate_int_divide() - Division by zero.".to_string());
+            }
+            return Ok(from_bigint(a / b));
+        },
+    };
+    if b == 0 { return Err("evaluate_int_divide() - Division by zero.".to_string()); }
+    let result = a.checked_div(b);
+    #[cfg(feature = "bigint")]
+    if result.is_none() { return Ok(from_bigint(BigInt::from(a) / BigInt::from(b))); }
+    return overflow_check("int_divide", result);
+} // evaluate_int_divide
+
+/// Calculates the modulo of two integers.
+///
+/// The result has the same sign as the divisor (second argument).
+/// This is the same as Prolog's mod:
+/// <blockquote>
+/// 7 mod 3 => 1<br>
+/// -7 mod 3 => 2<br>
+/// 7 mod -3 => -2
+/// </blockquote>
+///
+/// This method is called by
+/// [unify_sfunction()](../built_in_functions/fn.unify_sfunction.html#).
+///
+/// # Arguments
+/// * list of two [Unifiable](../unifiable/enum.Unifiable.html) terms
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger)
+/// # Errors
+/// * If there are not two arguments.
+/// * If an argument is not grounded, or not an integer.
+/// * If the divisor is 0.
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let arguments = parse_arguments("-7, 3").unwrap();
+/// let result = evaluate_mod(&arguments, &ss).unwrap();
+/// println!("{}", result);
+/// // Prints: 2
+/// ```
+pub fn evaluate_mod<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
+    let (a, b) = match get_integer_pair("mod", arguments, ss)? {
+        IntegerPair::Small(a, b) => { (a, b) },
+        #[cfg(feature = "bigint")]
+        IntegerPair::Big(a, b) => {
+            if b.is_zero() { return Err("evaluate_mod() - Division by zero.".to_string()); }
+            let r = &a % &b;
+            // Adjust the sign to match the divisor.
+            if !r.is_zero() && r.sign() != b.sign() { return Ok(from_bigint(r + b)); }
+            return Ok(from_bigint(r));
+        },
+    };
+    if b == 0 { return Err("evaluate_mod() - Division by zero.".to_string()); }
+    // i64::MIN mod -1 overflows.
+    let r = overflow_check("mod", a.checked_rem(b))?;
+    if let Unifiable::SInteger(r) = r {
+        // Adjust the sign to match the divisor.
+        if r != 0 && (r < 0) != (b < 0) { return Ok(Unifiable::SInteger(r + b)); }
+    }
+    return Ok(r);
+} // evaluate_mod
+
+/// Calculates the remainder of integer division.
+///
+/// The result has the same sign as the dividend (first argument).
+/// <blockquote>
+/// 7 rem 3 => 1<br>
+/// -7 rem 3 => -1<br>
+/// 7 rem -3 => 1
+/// </blockquote>
+///
+/// This method is called by
+/// [unify_sfunction()](../built_in_functions/fn.unify_sfunction.html#).
+///
+/// # Arguments
+/// * list of two [Unifiable](../unifiable/enum.Unifiable.html) terms
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger)
+/// # Errors
+/// * If there are not two arguments.
+/// * If an argument is not grounded, or not an integer.
+/// * If the divisor is 0.
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let arguments = parse_arguments("-7, 3").unwrap();
+/// let result = evaluate_rem(&arguments, &ss).unwrap();
+/// println!("{}", result);
+/// // Prints: -1
+/// ```
+pub fn evaluate_rem<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
+    let (a, b) = match get_integer_pair("rem", arguments, ss)? {
+        IntegerPair::Small(a, b) => { (a, b) },
+        #[cfg(feature = "bigint")]
+        IntegerPair::Big(a, b) => {
+            if b.is_zero() { return Err("evaluate_rem() - Division by zero.".to_string()); }
+            return Ok(from_bigint(a % b));
+        },
+    };
+    if b == 0 { return Err("evaluate_rem() - Division by zero.".to_string()); }
+    return overflow_check("rem", a.checked_rem(b));
+} // evaluate_rem
+
+/// Raises a number to a power.
+///
+/// If both arguments are integers, and the exponent is not negative,
+/// the result is an integer. If either argument is a float, or the
+/// exponent is negative, the result is a float.
+/// <blockquote>
+/// pow(2, 10) => 1024<br>
+/// pow(2, -1) => 0.5<br>
+/// pow(9, 0.5) => 3
+/// </blockquote>
+///
+/// In Suiron source code, pow can also be written as an infix: `2 ** 10`
+///
+/// This method is called by
+/// [unify_sfunction()](../built_in_functions/fn.unify_sfunction.html#).
+///
+/// # Arguments
+/// * list of two [Unifiable](../unifiable/enum.Unifiable.html) terms
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger) or
+/// [SFloat](../unifiable/enum.Unifiable.html#variant.SFloat)
+/// # Errors
+/// * If there are not two arguments.
+/// * If an argument is not grounded, or not a number.
+/// * If an integer result overflows. (With the bigint feature, if the
+///   result would have more than 2<sup>20</sup> bits.)
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let arguments = parse_arguments("2, -2").unwrap();
+/// let result = evaluate_pow(&arguments, &ss).unwrap();
+/// println!("{}", result);
+/// // Prints: 0.25
+/// ```
+pub fn evaluate_pow<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
+
+    if arguments.len() != 2 {
+        return Err("evaluate_pow() - Requires 2 arguments.".to_string());
+    }
+
+    let (numbers, _) = get_numbers(arguments, ss)?;
+
+    if let (SNumber::SRational(n, d), SNumber::SInteger(exponent)) = (&numbers[0], &numbers[1]) {
+        return rational_pow(*n, *d, *exponent);
+    }
+
+    #[cfg(feature = "bigint")]
+    if let (SNumber::SInteger(_) | SNumber::SBigInt(_),
+            SNumber::SInteger(exponent)) = (&numbers[0], &numbers[1]) {
+        if *exponent >= 0 {
+            let base = get_bigints(&numbers).remove(0);
+            // The result has at most exponent * bits(base) bits.
+            let bits = base.bits();
+            if bits > 1 && (*exponent as u64).saturating_mul(bits) > MAX_BIGINT_BITS {
+                return Err("evaluate_pow() - Integer overflow.".to_string());
+            }
+            match u32::try_from(*exponent) {
+                Ok(e) => { return Ok(from_bigint(base.pow(e))); },
+                Err(_) => {
+                    return Err("evaluate_pow() - Exponent is too big.".to_string());
+                },
+            }
+        }
+    }
+
+    if let (SNumber::SInteger(base), SNumber::SInteger(exponent)) = (&numbers[0], &numbers[1]) {
+        if *exponent >= 0 {
+            let exp = u32::try_from(*exponent).ok();
+            return overflow_check("pow", exp.and_then(|e| base.checked_pow(e)));
+        }
+    }
+
+    let f = get_floats(&numbers);
+    return Ok(Unifiable::SFloat(f[0].powf(f[1])));
+
+} // evaluate_pow
+
+/// Finds the minimum of its arguments.
+///
+/// The function takes one or more arguments.<br>
+/// If all the arguments are SIntegers, the function returns an SInteger.<br>
+/// If there is at least 1 SFloat in the list of arguments, the function
+/// returns an SFloat.
+///
+/// This method is called by
+/// [unify_sfunction()](../built_in_functions/fn.unify_sfunction.html#).
+///
+/// # Arguments
+/// * list of [Unifiable](../unifiable/enum.Unifiable.html) terms
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger) or
+///   [SFloat](../unifiable/enum.Unifiable.html#variant.SFloat)
+/// # Errors
+/// * If there are no arguments.
+/// * If a logic variable in the list of terms is not grounded.
+/// * If one of the ground terms is not an SInteger or SFloat.
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let arguments = parse_arguments("4, 9, 2.5").unwrap();
+/// let result = evaluate_min(&arguments, &ss).unwrap();
+/// println!("{}", result);
+/// // Prints: 2.5
+/// ```
+pub fn evaluate_min<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
+
+    if arguments.is_empty() {
+        return Err("evaluate_min() - Requires at least 1 argument.".to_string());
+    }
+
+    let (numbers, has_float) = get_numbers(arguments, ss)?;
+    if has_float {
+        let f = get_floats(&numbers);
+        let result = f.iter().skip(1).fold(f[0], |result, &x| result.min(x));
+        return Ok(Unifiable::SFloat(result));
+    }
+    else if has_rational(&numbers) {
+        return rational_arithmetic("min", &numbers);
+    }
+    else {
+        #[cfg(feature = "bigint")]
+        if has_bigint(&numbers) {
+            let b = get_bigints(&numbers);
+            return Ok(from_bigint(b.into_iter().reduce(|result, x| result.min(x)).unwrap()));
+        }
+        let i = get_integers(&numbers);
+        let result = i.iter().skip(1).fold(i[0], |result, &x| result.min(x));
+        return Ok(Unifiable::SInteger(result));
+    }
+} // evaluate_min
+
+/// Finds the maximum of its arguments.
+///
+/// The function takes one or more arguments.<br>
+/// If all the arguments are SIntegers, the function returns an SInteger.<br>
+/// If there is at least 1 SFloat in the list of arguments, the function
+/// returns an SFloat.
+///
+/// This method is called by
+/// [unify_sfunction()](../built_in_functions/fn.unify_sfunction.html#).
+///
+/// # Arguments
+/// * list of [Unifiable](../unifiable/enum.Unifiable.html) terms
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger) or
+///   [SFloat](../unifiable/enum.Unifiable.html#variant.SFloat)
+/// # Errors
+/// * If there are no arguments.
+/// * If a logic variable in the list of terms is not grounded.
+/// * If one of the ground terms is not an SInteger or SFloat.
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let arguments = parse_arguments("4, 9, 2.5").unwrap();
+/// let result = evaluate_max(&arguments, &ss).unwrap();
+/// println!("{}", result);
+/// // Prints: 9
+/// ```
+pub fn evaluate_max<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
+
+    if arguments.is_empty() {
+        return Err("evaluate_max() - Requires at least 1 argument.".to_string());
+    }
+
+    let (numbers, has_float) = get_numbers(arguments, ss)?;
+    if has_float {
+        let f = get_floats(&numbers);
+        let result = f.iter().skip(1).fold(f[0], |result, &x| result.max(x));
+        return Ok(Unifiable::SFloat(result));
+    }
+    else if has_rational(&numbers) {
+        return rational_arithmetic("max", &numbers);
+    }
+    else {
+        #[cfg(feature = "bigint")]
+        if has_bigint(&numbers) {
+            let b = get_bigints(&numbers);
+            return Ok(from_bigint(b.into_iter().reduce(|result, x| result.max(x)).unwrap()));
+        }
+        let i = get_integers(&numbers);
+        let result = i.iter().skip(1).fold(i[0], |result, &x| result.max(x));
+        return Ok(Unifiable::SInteger(result));
+    }
+} // evaluate_max
+
+/// Calculates the bitwise and of two integers.
+///
+/// In Suiron source code, bit_and can also be written as an infix: `$X /\ $Y`
+///
+/// This method is called by
+/// [unify_sfunction()](../built_in_functions/fn.unify_sfunction.html#).
+///
+/// # Arguments
+/// * list of two [Unifiable](../unifiable/enum.Unifiable.html) terms
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger)
+/// # Errors
+/// * If there are not two arguments.
+/// * If an argument is not grounded, or not an integer.
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let arguments = parse_arguments("12, 10").unwrap();
+/// let result = evaluate_bit_and(&arguments, &ss).unwrap();
+/// println!("{}", result);
+/// // Prints: 8
+/// ```
+pub fn evaluate_bit_and<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
+    match get_integer_pair("bit_and", arguments, ss)? {
+        IntegerPair::Small(a, b) => { return Ok(Unifiable::SInteger(a & b)); },
+        #[cfg(feature = "bigint")]
+        IntegerPair::Big(a, b) => { return Ok(from_bigint(a & b)); },
+    }
+} // evaluate_bit_and
+
+/// Calculates the bitwise or of two integers.
+///
+/// In Suiron source code, bit_or can also be written as an infix: `$X \/ $Y`
+///
+/// This method is called by
+/// [unify_sfunction()](../built_in_functions/fn.unify_sfunction.html#).
+///
+/// # Arguments
+/// * list of two [Unifiable](../unifiable/enum.Unifiable.html) terms
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger)
+/// # Errors
+/// * If there are not two arguments.
+/// * If an argument is not grounded, or not an integer.
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let arguments = parse_arguments("12, 10").unwrap();
+/// let result = evaluate_bit_or(&arguments, &ss).unwrap();
+/// println!("{}", result);
+/// // Prints: 14
+/// ```
+pub fn evaluate_bit_or<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
+    match get_integer_pair("bit_or", arguments, ss)? {
+        IntegerPair::Small(a, b) => { return Ok(Unifiable::SInteger(a | b)); },
+        #[cfg(feature = "bigint")]
+        IntegerPair::Big(a, b) => { return Ok(from_bigint(a | b)); },
+    }
+} // evaluate_bit_or
+
+/// Calculates the bitwise exclusive or of two integers.
+///
+/// In Suiron source code, xor can also be written as an infix: `$X xor $Y`
+///
+/// This method is called by
+/// [unify_sfunction()](../built_in_functions/fn.unify_sfunction.html#).
+///
+/// # Arguments
+/// * list of two [Unifiable](../unifiable/enum.Unifiable.html) terms
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger)
+/// # Errors
+/// * If there are not two arguments.
+/// * If an argument is not grounded, or not an integer.
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let arguments = parse_arguments("12, 10").unwrap();
+/// let result = evaluate_xor(&arguments, &ss).unwrap();
+/// println!("{}", result);
+/// // Prints: 6
+/// ```
+pub fn evaluate_xor<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
+    match get_integer_pair("xor", arguments, ss)? {
+        IntegerPair::Small(a, b) => { return Ok(Unifiable::SInteger(a ^ b)); },
+        #[cfg(feature = "bigint")]
+        IntegerPair::Big(a, b) => { return Ok(from_bigint(a ^ b)); },
+    }
+} // evaluate_xor
+
+/// Shifts the bits of an integer to the left.
+///
+/// In Suiron source code, shift_left can also be written as an infix: `$X << $N`
+///
+/// This method is called by
+/// [unify_sfunction()](../built_in_functions/fn.unify_sfunction.html#).
+///
+/// # Arguments
+/// * list of two [Unifiable](../unifiable/enum.Unifiable.html) terms
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger)
+/// # Errors
+/// * If there are not two arguments.
+/// * If an argument is not grounded, or not an integer.
+/// * If the shift is negative.
+/// * If the result overflows. (With the bigint feature, the result is
+///   promoted to an SBigInt, unless it would have more than 2<sup>20</sup> bits.)
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let arguments = parse_arguments("3, 4").unwrap();
+/// let result = evaluate_shift_left(&arguments, &ss).unwrap();
+/// println!("{}", result);
+/// // Prints: 48
+/// ```
+pub fn evaluate_shift_left<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
+    match get_integer_pair("shift_left", arguments, ss)? {
+        IntegerPair::Small(a, b) => {
+            let shift = match u32::try_from(b) {
+                Ok(shift) => { shift },
+                Err(_) => { return Err(format!("evaluate_shift_left() - Invalid shift: {}", b)); },
+            };
+            #[cfg(feature = "bigint")]
+            if shift_i64(a, shift).is_none() {
+                return shift_bigint(BigInt::from(a), shift);
+            }
+            return overflow_check("shift_left", shift_i64(a, shift));
+        },
+        #[cfg(feature = "bigint")]
+        IntegerPair::Big(a, b) => {
+            match b.to_u32() {
+                Some(shift) => { return shift_bigint(a, shift); },
+                None => { return Err(format!("evaluate_shift_left() - Invalid shift: {}", b)); },
+            }
+        },
+    }
+} // evaluate_shift_left
+
+// Shifts an integer to the left. If any bits are lost, including the
+// sign bit, the shift overflows.
+//
+// Arguments
+//    integer
+//    shift
+// Return
+//    shifted integer, or None on overflow
+fn shift_i64(a: i64, shift: u32) -> Option<i64> {
+    if a == 0 { return Some(0); }
+    return a.checked_shl(shift).filter(|r| r >> shift == a);
+} // shift_i64()
+
+// Shifts a big integer to the left.
+//
+// Arguments
+//    integer
+//    shift
+// Return
+//    SInteger, SBigInt or error message, if the result is too big
+#[cfg(feature = "bigint")]
+fn shift_bigint(a: BigInt, shift: u32) -> Result<Unifiable, String> {
+    if !a.is_zero() && a.bits() + shift as u64 > MAX_BIGINT_BITS {
+        return Err("evaluate_shift_left() - Integer overflow.".to_string());
+    }
+    return Ok(from_bigint(a << shift));
+} // shift_bigint()
+
+/// Shifts the bits of an integer to the right (arithmetic shift).
+///
+/// In Suiron source code, shift_right can also be written as an infix: `$X >> $N`
+///
+/// This method is called by
+/// [unify_sfunction()](../built_in_functions/fn.unify_sfunction.html#).
+///
+/// # Arguments
+/// * list of two [Unifiable](../unifiable/enum.Unifiable.html) terms
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger)
+/// # Errors
+/// * If there are not two arguments.
+/// * If an argument is not grounded, or not an integer.
+/// * If the shift is negative, or greater than 63.
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let arguments = parse_arguments("-48, 4").unwrap();
+/// let result = evaluate_shift_right(&arguments, &ss).unwrap();
+/// println!("{}", result);
+/// // Prints: -3
+/// ```
+pub fn evaluate_shift_right<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
+    match get_integer_pair("shift_right", arguments, ss)? {
+        IntegerPair::Small(a, b) => {
+            let shifted = u32::try_from(b).ok().and_then(|b| a.checked_shr(b));
+            match shifted {
+                Some(i) => { return Ok(Unifiable::SInteger(i)); },
+                None => { return Err(format!("evaluate_shift_right() - Invalid shift: {}", b)); },
+            }
+        },
+        #[cfg(feature = "bigint")]
+        IntegerPair::Big(a, b) => {
+            match b.to_u32() {
+                Some(shift) => { return Ok(from_bigint(a >> shift)); },
+                None => { return Err(format!("evaluate_shift_right() - Invalid shift: {}", b)); },
+            }
+        },
+    }
+} // evaluate_shift_right
+
+/// Evaluates an arithmetic expression.
+///
+/// The expression can be a number, a logic variable bound to a number,
+/// or an arithmetic function (SFunction). The arguments of a function
+/// can themselves be expressions, eg. `add($X, multiply($Y, 2))`.
+///
+/// # Arguments
+/// * expression - [Unifiable](../unifiable/enum.Unifiable.html) term
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger),
+///   [SFloat](../unifiable/enum.Unifiable.html#variant.SFloat)
+///   or [ArithmeticError](../error/enum.SuironError.html#variant.ArithmeticError)
+/// # Errors
+/// * If a logic variable in the expression is not grounded.
+/// * If a term in the expression is not a number or arithmetic function.
+/// * If an arithmetic function fails, eg. on division by zero.
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let expression = parse_term("2 * add(3, 4)").unwrap();
+/// let result = evaluate_expression(&expression, &ss).unwrap();
+/// println!("{}", result);
+/// // Prints: 14
+/// ```
+pub fn evaluate_expression<'a>(expression: &Unifiable,
+                               ss: &'a Rc<SubstitutionSet<'a>>)
+                               -> Result<Unifiable, SuironError> {
+
+    let term = match get_ground_term(expression, ss) {
+        Some(term) => { term },
+        None => {
+            let err = number_error("Argument is not grounded", expression);
+            return Err(SuironError::ArithmeticError(err));
+        },
+    };
+
+    match term {
+        Unifiable::SInteger(_) | Unifiable::SFloat(_) |
+        Unifiable::SRational(_, _) => { return Ok(term.clone()); },
+        #[cfg(feature = "bigint")]
+        Unifiable::SBigInt(_) => { return Ok(term.clone()); },
+        Unifiable::SFunction{name, terms} => {
+            return evaluate_function(name, terms, ss).map_err(SuironError::ArithmeticError);
+        },
+        _ => {
+            let err = number_error("Argument is not a number", term);
+            return Err(SuironError::ArithmeticError(err));
+        },
+    }
+
+} // evaluate_expression()
+
+/// Evaluates an arithmetic function, such as add() or divide().
+///
+/// # Arguments
+/// * name of function
+/// * list of [Unifiable](../unifiable/enum.Unifiable.html) terms
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * result of function, or error message
+/// # Errors
+/// * If the function is not an arithmetic function.
+/// * If a logic variable in the list of terms is not grounded.
+/// * If one of the ground terms is not a number.
+/// * If the calculation fails, eg. on division by zero.
+pub fn evaluate_function<'a>(name: &str, arguments: &Vec<Unifiable>,
+                             ss: &'a Rc<SubstitutionSet<'a>>)
+                             -> Result<Unifiable, String> {
+    match name {
+        "add"      => { evaluate_add(arguments, ss) },
+        "subtract" => { evaluate_subtract(arguments, ss) },
+        "multiply" => { evaluate_multiply(arguments, ss) },
+        "divide"   => { evaluate_divide(arguments, ss) },
+        "int_divide" => { evaluate_int_divide(arguments, ss) },
+        "mod"      => { evaluate_mod(arguments, ss) },
+        "rem"      => { evaluate_rem(arguments, ss) },
+        "rdiv"     => { evaluate_rdiv(arguments, ss) },
+        "pow"      => { evaluate_pow(arguments, ss) },
+        "min"      => { evaluate_min(arguments, ss) },
+        "max"      => { evaluate_max(arguments, ss) },
+        "bit_and"  => { evaluate_bit_and(arguments, ss) },
+        "bit_or"   => { evaluate_bit_or(arguments, ss) },
+        "xor"      => { evaluate_xor(arguments, ss) },
+        "shift_left"  => { evaluate_shift_left(arguments, ss) },
+        "shift_right" => { evaluate_shift_right(arguments, ss) },
+        _ => { evaluate_math(name, arguments, ss) },
+    }
+} // evaluate_function()
+
+/// Compares the values of two arithmetic expressions.
+///
+/// Both expressions are evaluated. If one of the results is a float,
+/// the comparison is done in floating point. Otherwise, the comparison
+/// is exact.
+///
+/// This function supports the arithmetic comparison predicates,
+/// =:= and =\\=.
+///
+/// # Arguments
+/// * left [Unifiable](../unifiable/enum.Unifiable.html) term
+/// * right [Unifiable](../unifiable/enum.Unifiable.html) term
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * [Ordering](https://doc.rust-lang.org/std/cmp/enum.Ordering.html)
+///   or error message
+/// # Errors
+/// * If an expression cannot be evaluated.
+/// * If a result is not a number (NaN).
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use std::cmp::Ordering;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let left  = parse_term("multiply(2, 3)").unwrap();
+/// let right = parse_term("6.0").unwrap();
+/// let result = compare_numbers(&left, &right, &ss).unwrap();
+/// assert_eq!(Ordering::Equal, result);
+/// ```
+pub fn compare_numbers<'a>(left: &Unifiable, right: &Unifiable,
+                           ss: &'a Rc<SubstitutionSet<'a>>)
+                           -> Result<Ordering, String> {
+
+    let terms = vec![left.clone(), right.clone()];
+    let (numbers, has_float) = get_numbers(&terms, ss)?;
+    #[cfg(feature = "bigint")]
+    let has_float = has_float || (has_bigint(&numbers) && has_rational(&numbers));
+    if has_float {
+        let f = get_floats(&numbers);
+        return match f[0].partial_cmp(&f[1]) {
+            Some(ordering) => { Ok(ordering) },
+            None => { Err("compare_numbers() - Not a number: NaN".to_string()) },
+        };
+    }
+    else if has_rational(&numbers) {
+        return Ok(rational_compare(&numbers[0], &numbers[1]));
+    }
+    #[cfg(feature = "bigint")]
+    if has_bigint(&numbers) {
+        let b = get_bigints(&numbers);
+        return Ok(b[0].cmp(&b[1]));
+    }
+    let i = get_integers(&numbers);
+    return Ok(i[0].cmp(&i[1]));
+
+} // compare_numbers()
+
 /// In Suiron, a number can be an SInteger (i64) or an SFloat (f64).
 #[derive(Debug)]
 pub enum SNumber {
     SFloat(f64),
     SInteger(i64),
+    SRational(i64, i64),
+    #[cfg(feature = "bigint")]
+    SBigInt(BigInt),
 } // SNumeric
 
 /// Gets the numbers (integers and floats) from a vector of unifiable terms.
@@ -233,12 +1111,12 @@ pub enum SNumber {
 /// * vector of Unifiable terms
 /// * SubstitutionSet
 /// # Return
-/// * (vector of SNumbers, has_float)
-/// # Panics
+/// * (vector of SNumbers, has_float) or error message
+/// # Errors
 /// * If a logic variable in the list of terms is not grounded.
 /// * If one of the terms is not an SInteger or SFloat.
 fn get_numbers<'a>(terms: &Vec<Unifiable>, ss: &'a Rc<SubstitutionSet<'a>>)
-                   -> (Vec<SNumber>, bool) {
+                   -> Result<(Vec<SNumber>, bool), String> {
 
     let mut numbers: Vec<SNumber> = vec![];
     let mut has_float = false;
@@ -255,17 +1133,95 @@ fn get_numbers<'a>(terms: &Vec<Unifiable>, ss: &'a Rc<SubstitutionSet<'a>>)
                         has_float = true;
                         numbers.push(SNumber::SFloat(*f));
                     },
+                    Unifiable::SRational(n, d) => {
+                        numbers.push(SNumber::SRational(*n, *d));
+                    },
+                    #[cfg(feature = "bigint")]
+                    Unifiable::SBigInt(b) => {
+                        numbers.push(SNumber::SBigInt(b.clone()));
+                    },
+                    // Nested function, eg. add(1, multiply(2, 3))
+                    Unifiable::SFunction{name: _, terms: _} => {
+                        match evaluate_expression(gt, ss)? {
+                            Unifiable::SFloat(f) => {
+                                has_float = true;
+                                numbers.push(SNumber::SFloat(f));
+                            },
+                            Unifiable::SInteger(i) => {
+                                numbers.push(SNumber::SInteger(i));
+                            },
+                            Unifiable::SRational(n, d) => {
+                                numbers.push(SNumber::SRational(n, d));
+                            },
+                            #[cfg(feature = "bigint")]
+                            Unifiable::SBigInt(b) => {
+                                numbers.push(SNumber::SBigInt(b));
+                            },
+                            _ => {
+                                return Err(number_error("Argument is not a number", term));
+                            },
+                        }
+                    },
                     _ => {
-                        number_panic("Argument is not a number", term);
+                        return Err(number_error("Argument is not a number", term));
                     },
                 } // match
             },
-            None => { number_panic("Argument is not grounded", term); },
+            None => { return Err(number_error("Argument is not grounded", term)); },
         } // match
     } // for
-    return (numbers, has_float);
+    return Ok((numbers, has_float));
 } // get_numbers()
 
+/// Two integer arguments, for int_divide, mod, rem, and the bitwise
+/// and shift functions.
+///
+/// With the `bigint` feature, if either argument is an SBigInt, both
+/// are returned as BigInts.
+enum IntegerPair {
+    Small(i64, i64),
+    #[cfg(feature = "bigint")]
+    Big(BigInt, BigInt),
+}
+
+/// Gets two integers from a list of arguments. With the `bigint` feature,
+/// this function accepts big integers.
+///
+/// # Arguments
+/// * name of function, for error messages
+/// * vector of Unifiable terms
+/// * SubstitutionSet
+/// # Return
+/// * IntegerPair or error message
+/// # Errors
+/// * If there are not two arguments.
+/// * If an argument is not an integer.
+fn get_integer_pair<'a>(name: &str, terms: &Vec<Unifiable>,
+                        ss: &'a Rc<SubstitutionSet<'a>>)
+                        -> Result<IntegerPair, String> {
+    if terms.len() != 2 {
+        return Err(format!("evaluate_{}() - Requires 2 arguments.", name));
+    }
+    let (numbers, _) = get_numbers(terms, ss)?;
+    match (&numbers[0], &numbers[1]) {
+        (SNumber::SInteger(a), SNumber::SInteger(b)) => {
+            return Ok(IntegerPair::Small(*a, *b));
+        },
+        #[cfg(feature = "bigint")]
+        (SNumber::SInteger(_) | SNumber::SBigInt(_),
+         SNumber::SInteger(_) | SNumber::SBigInt(_)) => {
+            let mut b = get_bigints(&numbers);
+            let second = b.remove(1);
+            return Ok(IntegerPair::Big(b.remove(0), second));
+        },
+        _ => {
+            let err = format!("evaluate_{}() - Arguments must be integers: {:?}",
+                              name, numbers);
+            return Err(err);
+        },
+    }
+} // get_integer_pair()
+
 /// Gets the integers (i64) from a list of numbers.
 ///
 /// # Argument
@@ -293,19 +1249,114 @@ fn get_floats<'a>(numbers: &Vec<SNumber>) -> Vec<f64> {
         else {
             // Convert int to float.
             if let SNumber::SInteger(i) = n { floats.push(*i as f64); }
+            if let SNumber::SRational(n, d) = n { floats.push(*n as f64 / *d as f64); }
+            #[cfg(feature = "bigint")]
+            if let SNumber::SBigInt(b) = n { floats.push(b.to_f64().unwrap_or(f64::NAN)); }
         }
     }
     return floats;
 }  // get_floats()
 
-/// Creates an error message for get_numbers() and panics.
+/// Determines whether a list of numbers contains an SBigInt.
+///
+/// # Argument
+/// * vector of numbers - (SNumber)
+/// # Return
+/// * true or false
+#[cfg(feature = "bigint")]
+fn has_bigint(numbers: &Vec<SNumber>) -> bool {
+    return numbers.iter().any(|n| matches!(n, SNumber::SBigInt(_)));
+} // has_bigint()
+
+/// Gets the integers from a list of numbers, as BigInts.
+///
+/// # Argument
+/// * vector of numbers - (SNumber)
+/// # Return
+/// * vector of BigInt
+#[cfg(feature = "bigint")]
+fn get_bigints(numbers: &Vec<SNumber>) -> Vec<BigInt> {
+    let mut ints: Vec<BigInt> = vec![];
+    for n in numbers {
+        match n {
+            SNumber::SInteger(i) => { ints.push(BigInt::from(*i)); },
+            SNumber::SBigInt(b) => { ints.push(b.clone()); },
+            SNumber::SFloat(_) | SNumber::SRational(_, _) => {},
+        }
+    }
+    return ints;
+} // get_bigints()
+
+/// Subtracts the following numbers from the first, with arbitrary precision.
+///
+/// # Argument
+/// * vector of numbers - (SNumber)
+/// # Return
+/// * SInteger, SBigInt or error message
+#[cfg(feature = "bigint")]
+fn subtract_bigints(numbers: &Vec<SNumber>) -> Result<Unifiable, String> {
+    let mut b = get_bigints(numbers);
+    let first = b.remove(0);
+    return Ok(from_bigint(b.into_iter().fold(first, |result, x| result - x)));
+} // subtract_bigints()
+
+/// Divides the first number by the following numbers, with arbitrary precision.
+///
+/// # Argument
+/// * vector of numbers - (SNumber)
+/// # Return
+/// * SInteger, SBigInt or error message
+#[cfg(feature = "bigint")]
+fn divide_bigints(numbers: &Vec<SNumber>) -> Result<Unifiable, String> {
+    let mut b = get_bigints(numbers);
+    let first = b.remove(0);
+    if b.iter().any(|x| x.is_zero()) {
+        return Err("evaluate_divide() - Division by zero.".to_string());
+    }
+    return Ok(from_bigint(b.into_iter().fold(first, |result, x| result / x)));
+} // divide_bigints()
+
+/// Converts a BigInt to a Unifiable number.
+///
+/// If the number fits in an i64, the function returns an SInteger.
+/// Otherwise it returns an SBigInt.
+///
+/// # Argument
+/// * BigInt
+/// # Return
+/// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger) or
+///   [SBigInt](../unifiable/enum.Unifiable.html#variant.SBigInt)
+#[cfg(feature = "bigint")]
+pub fn from_bigint(b: BigInt) -> Unifiable {
+    match i64::try_from(&b) {
+        Ok(i) => { return Unifiable::SInteger(i); },
+        Err(_) => { return Unifiable::SBigInt(b); },
+    }
+} // from_bigint()
+
+/// Creates an error message for get_numbers().
 ///
 /// # Arguments
 /// * err - error description
 /// * term - term which caused the error
-fn number_panic(err: &str, term: &Unifiable) {
-    let msg = format!("get_numbers() - {}: {}", err, term);
-    panic!("{}", msg);
+/// # Return
+/// * error message
+fn number_error(err: &str, term: &Unifiable) -> String {
+    return format!("get_numbers() - {}: {}", err, term);
+}
+
+/// Converts the result of a checked integer operation to an SInteger.
+///
+/// # Arguments
+/// * name of function, for error messages
+/// * result of checked operation (None means overflow)
+/// # Return
+/// * SInteger or error message
+fn overflow_check(name: &str, result: Option<i64>) -> Result<Unifiable, String> {
+    match result {
+        Some(i) => { return Ok(Unifiable::SInteger(i)); },
+        None => { return Err(format!("evaluate_{}() - Integer overflow.", name)); },
+    }
 }
 
 #[cfg(test)]
@@ -339,12 +1390,12 @@ mod test {
         let ss = get_ss();
 
         let arguments = vec![SInteger(2), x(), y()];
-        let result = evaluate_add(&arguments, &ss);
+        let result = evaluate_add(&arguments, &ss).unwrap();
         let result = format!("{:?}", result);
         assert_eq!("SInteger(9)", result);
 
         let arguments = vec![SInteger(2), x(), z()];
-        let result = evaluate_add(&arguments, &ss);
+        let result = evaluate_add(&arguments, &ss).unwrap();
         let result = format!("{:?}", result);
         assert_eq!("SFloat(10.7)", result);
 
@@ -356,12 +1407,12 @@ mod test {
         let ss = get_ss();
 
         let arguments = vec![x(), SInteger(10), y()];
-        let result = evaluate_subtract(&arguments, &ss);
+        let result = evaluate_subtract(&arguments, &ss).unwrap();
         let result = format!("{:?}", result);
         assert_eq!("SInteger(-11)", result);
 
         let arguments = vec![z(), SInteger(10), y()];
-        let result = evaluate_subtract(&arguments, &ss);
+        let result = evaluate_subtract(&arguments, &ss).unwrap();
         let result = format!("{:?}", result);
         assert_eq!("SFloat(-8.3)", result);
 
@@ -373,12 +1424,12 @@ mod test {
         let ss = get_ss();
 
         let arguments = vec![x(), y(), SInteger(-3)];
-        let result = evaluate_multiply(&arguments, &ss);
+        let result = evaluate_multiply(&arguments, &ss).unwrap();
         let result = format!("{:?}", result);
         assert_eq!("SInteger(-36)", result);
 
         let arguments = vec![x(), y(), SFloat(-3.0)];
-        let result = evaluate_multiply(&arguments, &ss);
+        let result = evaluate_multiply(&arguments, &ss).unwrap();
         let result = format!("{:?}", result);
         assert_eq!("SFloat(-36.0)", result);
 
@@ -390,50 +1441,320 @@ mod test {
         let ss = get_ss();
 
         let arguments = vec![SInteger(12), x(), y()];
-        let result = evaluate_divide(&arguments, &ss);
+        let result = evaluate_divide(&arguments, &ss).unwrap();
         let result = format!("{:?}", result);
         assert_eq!("SInteger(1)", result);
 
         // Integer division truncates.
         let arguments = vec![SInteger(13), x(), y()];
-        let result = evaluate_divide(&arguments, &ss);
+        let result = evaluate_divide(&arguments, &ss).unwrap();
         let result = format!("{:?}", result);
         assert_eq!("SInteger(1)", result);
 
         let arguments = vec![SFloat(12.0), x(), y()];
-        let result = evaluate_divide(&arguments, &ss);
+        let result = evaluate_divide(&arguments, &ss).unwrap();
         let result = format!("{:?}", result);
         assert_eq!("SFloat(1.0)", result);
 
         let arguments = vec![SFloat(13.0), x(), y()];
-        let result = evaluate_divide(&arguments, &ss);
+        let result = evaluate_divide(&arguments, &ss).unwrap();
         let result = format!("{:?}", result);
         assert_eq!("SFloat(1.0833333333333333)", result);
 
         // Divide by zero.
         let arguments = vec![SFloat(13.0), SInteger(0), y()];
-        let result = evaluate_divide(&arguments, &ss);
+        let result = evaluate_divide(&arguments, &ss).unwrap();
         let result = format!("{:?}", result);
         assert_eq!("SFloat(inf)", result);
 
     } // test_evaluate_divide()
 
+    #[test]
+    fn test_evaluate_int_divide() {
+
+        let ss = get_ss();
+
+        let arguments = vec![SInteger(7), SInteger(2)];
+        assert_eq!(Ok(SInteger(3)), evaluate_int_divide(&arguments, &ss));
+        let arguments = vec![SInteger(-7), SInteger(2)];
+        assert_eq!(Ok(SInteger(-3)), evaluate_int_divide(&arguments, &ss));
+        let arguments = vec![SInteger(7), SFloat(2.0)];
+        assert!(evaluate_int_divide(&arguments, &ss).is_err());
+        let arguments = vec![SInteger(7), SInteger(0)];
+        assert!(evaluate_int_divide(&arguments, &ss).is_err());
+
+        let expression = parse_term("7 // 2").unwrap();
+        assert_eq!(Ok(SInteger(3)), evaluate_expression(&expression, &ss));
+
+        // With float division, / does not truncate. // still does.
+        set_float_division(true);
+        let expression = parse_term("7 / 2").unwrap();
+        assert_eq!(Ok(SFloat(3.5)), evaluate_expression(&expression, &ss));
+        let expression = parse_term("7 // 2").unwrap();
+        assert_eq!(Ok(SInteger(3)), evaluate_expression(&expression, &ss));
+        let arguments = vec![SInteger(7), SInteger(0)];
+        assert!(evaluate_divide(&arguments, &ss).is_err());
+        set_float_division(false);
+
+        let expression = parse_term("7 / 2").unwrap();
+        assert_eq!(Ok(SInteger(3)), evaluate_expression(&expression, &ss));
+
+    } // test_evaluate_int_divide()
+
+    #[test]
+    fn test_evaluate_expression() {
+
+        let ss = get_ss();
+
+        // $X * ($Y + 2) - 1
+        let expression = sfunction!("subtract",
+                                    sfunction!("multiply", x(), sfunction!("add", y(), SInteger(2))),
+                                    SInteger(1));
+        let result = evaluate_expression(&expression, &ss).unwrap();
+        assert_eq!(SInteger(17), result);
+
+        let expression = sfunction!("add", z(), sfunction!("divide", SInteger(7), SInteger(2)));
+        let result = evaluate_expression(&expression, &ss).unwrap();
+        assert_eq!(SFloat(8.7), result);
+
+        assert_eq!(SInteger(3), evaluate_expression(&x(), &ss).unwrap());
+
+    } // test_evaluate_expression()
+
     // Test with ungrounded variable in argument list.
     #[test]
-    #[should_panic]
-    fn test_evaluate_ungrounded_panic() {
+    fn test_evaluate_ungrounded_error() {
         let ss = get_ss();
         let arguments = vec![SInteger(12), x(), w()];
-        evaluate_add(&arguments, &ss);
+        let err = evaluate_add(&arguments, &ss).unwrap_err();
+        assert_eq!("get_numbers() - Argument is not grounded: $W_1", err);
     }
 
+    #[test]
+    fn test_evaluate_mod_rem() {
+
+        let ss = empty_ss!();
+        let cases = [(7, 3, 1, 1), (-7, 3, 2, -1), (7, -3, -2, 1),
+                     (-7, -3, -1, -1), (6, 3, 0, 0), (-6, 3, 0, 0)];
+
+        for (a, b, m, r) in cases {
+            let arguments = vec![SInteger(a), SInteger(b)];
+            assert_eq!(SInteger(m), evaluate_mod(&arguments, &ss).unwrap(),
+                       "{} mod {}", a, b);
+            assert_eq!(SInteger(r), evaluate_rem(&arguments, &ss).unwrap(),
+                       "{} rem {}", a, b);
+        }
+
+        // Infix
+        let ss = get_ss();
+        let expression = parse_term("$X mod 2").unwrap();
+        assert_eq!("mod($X, 2)", expression.to_string());
+        let expression = sfunction!("rem", SInteger(10), x());
+        assert_eq!(SInteger(1), evaluate_expression(&expression, &ss).unwrap());
+
+    } // test_evaluate_mod_rem()
+
+    #[test]
+    fn test_evaluate_pow() {
+
+        let ss = empty_ss!();
+
+        let arguments = vec![SInteger(2), SInteger(10)];
+        assert_eq!(SInteger(1024), evaluate_pow(&arguments, &ss).unwrap());
+
+        let arguments = vec![SInteger(-3), SInteger(3)];
+        assert_eq!(SInteger(-27), evaluate_pow(&arguments, &ss).unwrap());
+
+        let arguments = vec![SInteger(7), SInteger(0)];
+        assert_eq!(SInteger(1), evaluate_pow(&arguments, &ss).unwrap());
+
+        // Negative exponent gives a float.
+        let arguments = vec![SInteger(2), SInteger(-2)];
+        assert_eq!(SFloat(0.25), evaluate_pow(&arguments, &ss).unwrap());
+
+        // Fractional exponent.
+        let arguments = vec![SInteger(9), SFloat(0.5)];
+        assert_eq!(SFloat(3.0), evaluate_pow(&arguments, &ss).unwrap());
+
+        let arguments = vec![SFloat(1.5), SInteger(2)];
+        assert_eq!(SFloat(2.25), evaluate_pow(&arguments, &ss).unwrap());
+
+        // Infix
+        let expression = parse_term("$X ** 2").unwrap();
+        assert_eq!("pow($X, 2)", expression.to_string());
+        let expression = sfunction!("pow", x(), SInteger(4));
+        let ss = get_ss();
+        assert_eq!(SInteger(81), evaluate_expression(&expression, &ss).unwrap());
+
+    } // test_evaluate_pow()
+
+    #[test]
+    fn test_evaluate_min_max() {
+
+        let ss = get_ss();   // $X = 3, $Y = 4, $Z = 5.7
+
+        let arguments = vec![SInteger(8), x(), y()];
+        assert_eq!(SInteger(3), evaluate_min(&arguments, &ss).unwrap());
+        assert_eq!(SInteger(8), evaluate_max(&arguments, &ss).unwrap());
+
+        // One float makes the result a float.
+        let arguments = vec![x(), z(), SInteger(-2)];
+        assert_eq!(SFloat(-2.0), evaluate_min(&arguments, &ss).unwrap());
+        assert_eq!(SFloat(5.7), evaluate_max(&arguments, &ss).unwrap());
+
+        let arguments = vec![SInteger(12)];
+        assert_eq!(SInteger(12), evaluate_max(&arguments, &ss).unwrap());
+
+        // Nested in an expression.
+        let expression = parse_term("max(1, 2) + min(10, 20)").unwrap();
+        assert_eq!(SInteger(12), evaluate_expression(&expression, &ss).unwrap());
+
+    } // test_evaluate_min_max()
+
+    #[test]
+    fn test_evaluate_bitwise() {
+
+        let ss = empty_ss!();
+        let arguments = vec![SInteger(12), SInteger(10)];   // 1100, 1010
+        assert_eq!(SInteger(8), evaluate_bit_and(&arguments, &ss).unwrap());
+        assert_eq!(SInteger(14), evaluate_bit_or(&arguments, &ss).unwrap());
+        assert_eq!(SInteger(6), evaluate_xor(&arguments, &ss).unwrap());
+
+        let arguments = vec![SInteger(3), SInteger(4)];
+        assert_eq!(SInteger(48), evaluate_shift_left(&arguments, &ss).unwrap());
+        let arguments = vec![SInteger(-48), SInteger(4)];
+        assert_eq!(SInteger(-3), evaluate_shift_right(&arguments, &ss).unwrap());
+        let arguments = vec![SInteger(-1), SInteger(63)];
+        assert_eq!(SInteger(i64::MIN), evaluate_shift_left(&arguments, &ss).unwrap());
+        let arguments = vec![SInteger(0), SInteger(100)];
+        assert_eq!(SInteger(0), evaluate_shift_left(&arguments, &ss).unwrap());
+
+        // Infixes
+        let ss = get_ss();   // $X = 3, $Y = 4
+        let expressions = [("$X /\\ 1", "bit_and($X, 1)", 1, 1),
+                           ("$X \\/ 4", "bit_or($X, 4)", 4, 7),
+                           ("$X xor 1", "xor($X, 1)", 1, 2),
+                           ("$X << 2", "shift_left($X, 2)", 2, 12),
+                           ("$X >> 1", "shift_right($X, 1)", 1, 1)];
+        for (s, display, right, result) in expressions {
+            let expression = parse_term(s).unwrap();
+            assert_eq!(display, expression.to_string());
+            if let SFunction{name, terms: _} = expression {
+                let expression = sfunction!(&name, x(), SInteger(right));
+                assert_eq!(SInteger(result), evaluate_expression(&expression, &ss).unwrap());
+            }
+        }
+
+    } // test_evaluate_bitwise()
+
     // Test with non-number in argument list.
     #[test]
-    #[should_panic]
-    fn test_evaluate_nonnumber_panic() {
+    fn test_evaluate_nonnumber_error() {
         let ss = get_ss();
         let arguments = vec![SInteger(12), x(), atom!("Oh no.")];
-        evaluate_add(&arguments, &ss);
+        let err = evaluate_add(&arguments, &ss).unwrap_err();
+        assert_eq!("get_numbers() - Argument is not a number: Oh no.", err);
     }
 
+    // Overflow and division by zero are errors, not panics.
+    #[test]
+    fn test_evaluate_errors() {
+
+        let ss = empty_ss!();
+        let min = SInteger(i64::MIN);
+
+        // With the bigint feature, these do not overflow.
+        #[cfg(not(feature = "bigint"))]
+        {
+            let max = SInteger(i64::MAX);
+            let err = evaluate_add(&vec![max.clone(), SInteger(1)], &ss).unwrap_err();
+            assert_eq!("evaluate_add() - Integer overflow.", err);
+            let err = evaluate_subtract(&vec![min.clone(), SInteger(1)], &ss).unwrap_err();
+            assert_eq!("evaluate_subtract() - Integer overflow.", err);
+            let err = evaluate_multiply(&vec![max.clone(), SInteger(2)], &ss).unwrap_err();
+            assert_eq!("evaluate_multiply() - Integer overflow.", err);
+            let err = evaluate_divide(&vec![min.clone(), SInteger(-1)], &ss).unwrap_err();
+            assert_eq!("evaluate_divide() - Integer overflow.", err);
+            let err = evaluate_pow(&vec![SInteger(10), SInteger(30)], &ss).unwrap_err();
+            assert_eq!("evaluate_pow() - Integer overflow.", err);
+            // Bits which are shifted out are an overflow.
+            let err = evaluate_shift_left(&vec![SInteger(3), SInteger(62)], &ss).unwrap_err();
+            assert_eq!("evaluate_shift_left() - Integer overflow.", err);
+            assert!(evaluate_shift_left(&vec![SInteger(1), SInteger(64)], &ss).is_err());
+        }
+
+        let err = evaluate_divide(&vec![SInteger(12), SInteger(0)], &ss).unwrap_err();
+        assert_eq!("evaluate_divide() - Division by zero.", err);
+        let err = evaluate_mod(&vec![SInteger(12), SInteger(0)], &ss).unwrap_err();
+        assert_eq!("evaluate_mod() - Division by zero.", err);
+        let err = evaluate_rem(&vec![min.clone(), SInteger(-1)], &ss).unwrap_err();
+        assert_eq!("evaluate_rem() - Integer overflow.", err);
+        assert!(evaluate_bit_and(&vec![SInteger(12), SFloat(1.0)], &ss).is_err());
+        assert!(evaluate_shift_left(&vec![SInteger(1), SInteger(-1)], &ss).is_err());
+
+        // Nested errors are passed up.
+        let expression = parse_term("1 + divide(3, 0)").unwrap();
+        assert!(evaluate_expression(&expression, &ss).is_err());
+
+        // Not an arithmetic function.
+        let expression = sfunction!("join", SInteger(1), SInteger(2));
+        assert!(evaluate_expression(&expression, &ss).is_err());
+
+    } // test_evaluate_errors()
+
+    // With the bigint feature, overflow promotes to SBigInt.
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_evaluate_bigint() {
+
+        let ss = empty_ss!();
+        let max = SInteger(i64::MAX);
+
+        let result = evaluate_add(&vec![max.clone(), SInteger(1)], &ss).unwrap();
+        assert_eq!("9223372036854775808", result.to_string());
+        assert!(matches!(result, SBigInt(_)));
+
+        // Back to SInteger when the result fits.
+        let result = evaluate_subtract(&vec![result, SInteger(10)], &ss).unwrap();
+        assert_eq!(SInteger(i64::MAX - 9), result);
+
+        let result = evaluate_pow(&vec![SInteger(2), SInteger(100)], &ss).unwrap();
+        assert_eq!("1267650600228229401496703205376", result.to_string());
+
+        let big = evaluate_multiply(&vec![max.clone(), max.clone()], &ss).unwrap();
+        let result = evaluate_divide(&vec![big.clone(), max.clone()], &ss).unwrap();
+        assert_eq!(max, result);
+        let result = evaluate_max(&vec![SInteger(3), big.clone()], &ss).unwrap();
+        assert_eq!(big, result);
+
+        let err = evaluate_divide(&vec![big.clone(), SInteger(0)], &ss).unwrap_err();
+        assert_eq!("evaluate_divide() - Division by zero.", err);
+
+        // Shifts are promoted too.
+        let result = evaluate_shift_left(&vec![SInteger(1), SInteger(70)], &ss).unwrap();
+        assert_eq!("1180591620717411303424", result.to_string());
+        let result = evaluate_shift_left(&vec![SInteger(3), SInteger(62)], &ss).unwrap();
+        assert_eq!("13835058055282163712", result.to_string());
+        let result = evaluate_shift_right(&vec![result, SInteger(62)], &ss).unwrap();
+        assert_eq!(SInteger(3), result);
+
+        // Results which are too big are not computed.
+        let err = evaluate_pow(&vec![SInteger(10), SInteger(4_000_000_000)], &ss).unwrap_err();
+        assert_eq!("evaluate_pow() - Integer overflow.", err);
+        let err = evaluate_shift_left(&vec![SInteger(1), SInteger(1 << 30)], &ss).unwrap_err();
+        assert_eq!("evaluate_shift_left() - Integer overflow.", err);
+        let result = evaluate_pow(&vec![SInteger(-1), SInteger(4_000_000_000)], &ss).unwrap();
+        assert_eq!(SInteger(1), result);
+
+        // Mixed with a float.
+        let result = evaluate_add(&vec![big, SFloat(1.0)], &ss).unwrap();
+        assert!(matches!(result, SFloat(_)));
+
+        // Parsing
+        let term = parse_term("123456789012345678901234567890").unwrap();
+        assert!(matches!(term, SBigInt(_)));
+        assert_eq!("123456789012345678901234567890", term.to_string());
+
+    } // test_evaluate_bigint()
+
 } // test
@@ -0,0 +1,133 @@
+//! Functions to support the built-in predicates upcase_atom() and downcase_atom().
+//!
+//! These predicates convert the case of the letters in an atom.
+//! Conversion is done according to Unicode rules, so accented
+//! and non-Latin letters are handled correctly.
+//
+// Cleve Lendon  2023
+
+use std::rc::Rc;
+
+use super::unifiable::Unifiable::*;
+use super::substitution_set::*;
+use super::built_in_predicates::*;
+
+/// Converts the letters of an atom to upper case.
+///
+/// In Suiron source code, the upcase_atom() predicate requires two arguments.
+/// <pre>
+///     upcase_atom(Ärger, $Upper)    # $Upper = ÄRGER
+/// </pre>
+///
+/// The first argument must be an atom, string or number, or a logic variable
+/// which is bound to one. The second argument unifies with the
+/// converted atom. If the first argument is not grounded, the
+/// predicate fails.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+///
+pub fn bip_upcase_atom<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                           -> Option<Rc<SubstitutionSet<'a>>> {
+    return convert_case(bip, ss, true);
+} // bip_upcase_atom()
+
+/// Converts the letters of an atom to lower case.
+///
+/// In Suiron source code, the downcase_atom() predicate requires two arguments.
+/// <pre>
+///     downcase_atom(ΣΟΦΙΑ, $Lower)    # $Lower = σοφια
+/// </pre>
+///
+/// The first argument must be an atom, string or number, or a logic variable
+/// which is bound to one. The second argument unifies with the
+/// converted atom. If the first argument is not grounded, the
+/// predicate fails.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+///
+pub fn bip_downcase_atom<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                             -> Option<Rc<SubstitutionSet<'a>>> {
+    return convert_case(bip, ss, false);
+} // bip_downcase_atom()
+
+// Converts the case of the first argument, and unifies the
+// result with the second argument.
+// Arguments:
+//   bip  - built-in predicate
+//   ss   - substitution set
+//   upper - true for upper case, false for lower case
+// Return:
+//   substitution set or None
+fn convert_case<'a>(bip: BuiltInPredicate,
+                    ss: &'a Rc<SubstitutionSet<'a>>,
+                    upper: bool)
+                    -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 2 { return None; }
+
+        let text = get_text(&terms[0], ss)?;
+
+        let converted = if upper { text.to_uppercase() }
+                        else { text.to_lowercase() };
+
+        let out = &terms[1];
+        return out.unify(&Atom(converted.into()), ss);
+    }
+    return None;
+
+} // convert_case()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    // Test upcase_atom() and downcase_atom() predicates.
+    #[test]
+    fn test_convert_case() {
+
+        let mut kb = KnowledgeBase::new();
+
+        fn x() -> Unifiable { logic_var!("$X") }
+
+        let head = scomplex!(atom!("test_upcase"), x());
+        let body = pred!("upcase_atom", atom!("Straße über"), x());
+        let rule1 = make_rule(head, body);
+
+        let head = scomplex!(atom!("test_downcase"), x());
+        let body = pred!("downcase_atom", atom!("ΣΟΦΙΑ Über"), x());
+        let rule2 = make_rule(head, body);
+
+        add_rules!(&mut kb, rule1, rule2);
+
+        let query = query!(atom!("test_upcase"), logic_var!("$Y"));
+        let sn = make_base_node(query, &kb);
+        assert_eq!("$Y = STRASSE ÜBER", solve(sn));
+
+        let query = query!(atom!("test_downcase"), logic_var!("$Y"));
+        let sn = make_base_node(query, &kb);
+        assert_eq!("$Y = σοφια über", solve(sn));
+
+    } // test_convert_case()
+
+} // test
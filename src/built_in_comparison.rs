@@ -1,4 +1,8 @@
-//! Suiron's comparison functions: equal, less_than, greater_than, etc.
+//! Suiron's comparison functions: equal, less_than, greater_than, etc.,
+//! and the arithmetic comparisons =:= and =\\=.
+//!
+//! less_than, greater_than, etc. evaluate arithmetic expressions, so
+//! a goal such as `$X * 2 + 1 > $Y - 3` compares the values of both sides.
 //!
 //! The functions defined in this module support Suiron's built-in
 //! arithmetic functions.<br>
@@ -12,14 +16,16 @@ use std::rc::Rc;
 use std::cmp::Ordering;
 use super::substitution_set::*;
 use super::built_in_predicates::*;
+use super::built_in_arithmetic::*;
 use super::unifiable::{*, Unifiable::*};
 
 /// Compares two strings or two numbers. Succeeds if equal.
 ///
 /// If one argument is an integer, and the other is a float,
-/// the integer is converted to float for the comparison.
+/// the integer is converted to float for the comparison. Big integers
+/// are compared numerically.
 ///
-/// Arguments must be Atoms, SFloats or SIntegers. If one of
+/// Arguments must be Atoms, SStrings, SFloats or SIntegers. If one of
 /// the arguments is a LogicVar, the function fetches the
 /// ground term, if there is one.
 ///
@@ -34,12 +40,19 @@ pub fn bip_equal<'a>(bip: BuiltInPredicate,
 
     if let Some(terms) = bip.terms {
 
+        if has_exact_number(&terms, ss) {
+            return compare_expressions(&terms, ss, Ordering::is_eq);
+        }
+
         let two_terms = get_two_constants(terms, ss)?;
 
         match two_terms {
             (Atom(s1), Atom(s2)) => {
                 if s1 == s2 { return Some(Rc::clone(&ss)); }
             },
+            (SString(s1), SString(s2)) => {
+                if s1 == s2 { return Some(Rc::clone(&ss)); }
+            },
             (SInteger(i1), SInteger(i2)) => {
                 if i1 == i2 { return Some(Rc::clone(&ss)); }
             },
@@ -66,10 +79,14 @@ pub fn bip_equal<'a>(bip: BuiltInPredicate,
 /// If one argument is an integer, and the other is a float,
 /// the integer is converted to float for the comparison.
 ///
-/// Arguments must be Atoms, SFloats or SIntegers. If one of
+/// Arguments must be Atoms, SStrings, SFloats or SIntegers. If one of
 /// the arguments is a LogicVar, the function fetches the
 /// ground term, if there is one.
 ///
+/// If one of the arguments is an arithmetic expression, such as
+/// `$X * 2 + 1`, both arguments are evaluated before they are compared.
+/// If an argument cannot be evaluated, the comparison fails.
+///
 /// # Arguments
 /// * `args` - vector of [Unifiable](../unifiable/enum.Unifiable.html) terms (2)
 /// * `ss` - [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
@@ -83,6 +100,10 @@ pub fn bip_less_than<'a>(bip: BuiltInPredicate,
 
     if let Some(terms) = bip.terms {
 
+        if has_expression(&terms, ss) {
+            return compare_expressions(&terms, ss, Ordering::is_lt);
+        }
+
         let two_terms = get_two_constants(terms, ss)?;
 
         match two_terms {
@@ -91,6 +112,11 @@ pub fn bip_less_than<'a>(bip: BuiltInPredicate,
                     return Some(Rc::clone(&ss));
                 }
             },
+            (SString(s1), SString(s2)) => {
+                if s1.cmp(&s2) == Ordering::Less {
+                    return Some(Rc::clone(&ss));
+                }
+            },
             (SInteger(i1), SInteger(i2)) => {
                 if i1.cmp(&i2) == Ordering::Less {
                     return Some(Rc::clone(&ss));
@@ -120,10 +146,14 @@ pub fn bip_less_than<'a>(bip: BuiltInPredicate,
 /// If one argument is an integer, and the other is a float,
 /// the integer is converted to float for the comparison.
 ///
-/// Arguments must be Atoms, SFloats or SIntegers. If one of
+/// Arguments must be Atoms, SStrings, SFloats or SIntegers. If one of
 /// the arguments is a LogicVar, the function fetches the
 /// ground term, if there is one.
 ///
+/// If one of the arguments is an arithmetic expression, such as
+/// `$X * 2 + 1`, both arguments are evaluated before they are compared.
+/// If an argument cannot be evaluated, the comparison fails.
+///
 /// # Arguments
 /// * `args` - vector of [Unifiable](../unifiable/enum.Unifiable.html) terms (2)
 /// * `ss` - [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
@@ -137,6 +167,10 @@ pub fn bip_less_than_or_equal<'a>(bip: BuiltInPredicate,
 
     if let Some(terms) = bip.terms {
 
+        if has_expression(&terms, ss) {
+            return compare_expressions(&terms, ss, Ordering::is_le);
+        }
+
         let two_terms = get_two_constants(terms, ss)?;
 
         match two_terms {
@@ -147,6 +181,13 @@ pub fn bip_less_than_or_equal<'a>(bip: BuiltInPredicate,
                     return Some(Rc::clone(&ss));
                 }
             },
+            (SString(s1), SString(s2)) => {
+                let res = s1.cmp(&s2);
+                if  res == Ordering::Less ||
+                    res == Ordering::Equal {
+                    return Some(Rc::clone(&ss));
+                }
+            },
             (SInteger(i1), SInteger(i2)) => {
                 let res = i1.cmp(&i2);
                 if  res == Ordering::Less ||
@@ -178,10 +219,14 @@ pub fn bip_less_than_or_equal<'a>(bip: BuiltInPredicate,
 /// If one argument is an integer, and the other is a float,
 /// the integer is converted to float for the comparison.
 ///
-/// Arguments must be Atoms, SFloats or SIntegers. If one of
+/// Arguments must be Atoms, SStrings, SFloats or SIntegers. If one of
 /// the arguments is a LogicVar, the function fetches the
 /// ground term, if there is one.
 ///
+/// If one of the arguments is an arithmetic expression, such as
+/// `$X * 2 + 1`, both arguments are evaluated before they are compared.
+/// If an argument cannot be evaluated, the comparison fails.
+///
 /// # Arguments
 /// * `args` - vector of [Unifiable](../unifiable/enum.Unifiable.html) terms (2)
 /// * `ss` - [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
@@ -195,6 +240,10 @@ pub fn bip_greater_than<'a>(bip: BuiltInPredicate,
 
     if let Some(terms) = bip.terms {
 
+        if has_expression(&terms, ss) {
+            return compare_expressions(&terms, ss, Ordering::is_gt);
+        }
+
         let two_terms = get_two_constants(terms, ss)?;
 
         match two_terms {
@@ -203,6 +252,11 @@ pub fn bip_greater_than<'a>(bip: BuiltInPredicate,
                     return Some(Rc::clone(&ss));
                 }
             },
+            (SString(s1), SString(s2)) => {
+                if s1.cmp(&s2) == Ordering::Greater {
+                    return Some(Rc::clone(&ss));
+                }
+            },
             (SInteger(i1), SInteger(i2)) => {
                 if i1.cmp(&i2) == Ordering::Greater {
                     return Some(Rc::clone(&ss));
@@ -231,10 +285,14 @@ pub fn bip_greater_than<'a>(bip: BuiltInPredicate,
 /// If one argument is an integer, and the other is a float,
 /// the integer is converted to float for the comparison.
 ///
-/// Arguments must be Atoms, SFloats or SIntegers. If one of
+/// Arguments must be Atoms, SStrings, SFloats or SIntegers. If one of
 /// the arguments is a LogicVar, the function fetches the
 /// ground term, if there is one.
 ///
+/// If one of the arguments is an arithmetic expression, such as
+/// `$X * 2 + 1`, both arguments are evaluated before they are compared.
+/// If an argument cannot be evaluated, the comparison fails.
+///
 /// # Arguments
 /// * `args` - vector of [Unifiable](../unifiable/enum.Unifiable.html) terms (2)
 /// * `ss` - [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
@@ -248,6 +306,10 @@ pub fn bip_greater_than_or_equal<'a>(bip: BuiltInPredicate,
 
     if let Some(terms) = bip.terms {
 
+        if has_expression(&terms, ss) {
+            return compare_expressions(&terms, ss, Ordering::is_ge);
+        }
+
         let two_terms = get_two_constants(terms, ss)?;
 
         match two_terms {
@@ -258,6 +320,13 @@ pub fn bip_greater_than_or_equal<'a>(bip: BuiltInPredicate,
                     return Some(Rc::clone(&ss));
                 }
             },
+            (SString(s1), SString(s2)) => {
+                let res = s1.cmp(&s2);
+                if  res == Ordering::Greater ||
+                    res == Ordering::Equal {
+                    return Some(Rc::clone(&ss));
+                }
+            },
             (SInteger(i1), SInteger(i2)) => {
                 let res = i1.cmp(&i2);
                 if  res == Ordering::Greater ||
@@ -283,6 +352,125 @@ pub fn bip_greater_than_or_equal<'a>(bip: BuiltInPredicate,
 
 } // bip_greater_than_or_equal()
 
+/// Evaluates two arithmetic expressions. Succeeds if their values are equal.
+///
+/// In Suiron source code, this predicate is written with the =:= infix:
+/// <pre>
+///     $X * 2 =:= $Y + 1
+/// </pre>
+///
+/// Unlike unification (=) and strict equality (==), both sides are
+/// evaluated first, so `2 + 2 =:= 4.0` succeeds. If either side cannot
+/// be evaluated, the predicate fails.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+/// # Note
+/// * If the number of arguments is not 2, the predicate fails.
+pub fn bip_arithmetic_equal<'a>(bip: BuiltInPredicate,
+                                ss: &'a Rc<SubstitutionSet<'a>>)
+                                -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+        if terms.len() != 2 { return None; }
+        return compare_expressions(&terms, ss, Ordering::is_eq);
+    }
+    return None;
+
+} // bip_arithmetic_equal()
+
+/// Evaluates two arithmetic expressions. Succeeds if their values are not equal.
+///
+/// In Suiron source code, this predicate is written with the =\\= infix:
+/// <pre>
+///     $X mod 2 =\\= 0
+/// </pre>
+///
+/// If either side cannot be evaluated, the predicate fails.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+/// # Note
+/// * If the number of arguments is not 2, the predicate fails.
+pub fn bip_arithmetic_not_equal<'a>(bip: BuiltInPredicate,
+                                    ss: &'a Rc<SubstitutionSet<'a>>)
+                                    -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+        if terms.len() != 2 { return None; }
+        return compare_expressions(&terms, ss, Ordering::is_ne);
+    }
+    return None;
+
+} // bip_arithmetic_not_equal()
+
+/// Evaluates two arithmetic expressions, and tests the ordering of their values.
+///
+/// # Arguments
+/// * vector of two unifiable terms
+/// * substitution set
+/// * test, eg. Ordering::is_lt
+/// # Return
+/// * substitution set, or None if the test fails or an expression
+///   cannot be evaluated
+fn compare_expressions<'a>(terms: &Vec<Unifiable>,
+                           ss: &'a Rc<SubstitutionSet<'a>>,
+                           test: fn(Ordering) -> bool)
+                           -> Option<Rc<SubstitutionSet<'a>>> {
+    match compare_numbers(&terms[0], &terms[1], ss) {
+        Ok(ordering) if test(ordering) => { return Some(Rc::clone(ss)); },
+        _ => { return None; },
+    }
+} // compare_expressions()
+
+/// Determines whether one of two terms must be evaluated before
+/// it can be compared. This is true for arithmetic expressions
+/// (SFunctions), and for numbers which get_two_constants() does
+/// not handle, such as rational numbers.
+///
+/// # Arguments
+/// * vector of two unifiable terms
+/// * substitution set
+/// # Return
+/// * true or false
+fn has_expression<'a>(terms: &Vec<Unifiable>, ss: &'a Rc<SubstitutionSet<'a>>) -> bool {
+    return terms.iter().take(2).any(|term| {
+        match get_ground_term(term, ss) {
+            Some(SFunction{name: _, terms: _}) | Some(SRational(_, _)) => { true },
+            #[cfg(feature = "bigint")]
+            Some(SBigInt(_)) => { true },
+            _ => { false },
+        }
+    });
+} // has_expression()
+
+/// Determines whether one of two terms is a number which
+/// get_two_constants() does not handle, such as a rational number
+/// or a big integer.
+/// These are compared numerically by equal().
+///
+/// # Arguments
+/// * vector of two unifiable terms
+/// * substitution set
+/// # Return
+/// * true or false
+fn has_exact_number<'a>(terms: &Vec<Unifiable>, ss: &'a Rc<SubstitutionSet<'a>>) -> bool {
+    return terms.iter().take(2).any(|term| {
+        match get_ground_term(term, ss) {
+            Some(SRational(_, _)) => { true },
+            #[cfg(feature = "bigint")]
+            Some(SBigInt(_)) => { true },
+            _ => { false },
+        }
+    });
+} // has_exact_number()
+
 /// Gets two constants (atoms, floats, ints) from a vector of unifiable terms.
 ///
 /// If a term in the given vector is a logic variable, the function will get
@@ -37,7 +37,7 @@ pub fn bip_count<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
 
     if let Some(terms) = bip.terms {
 
-        if terms.len() != 2 { panic!("bip_count() - Requires 2 arguments."); }
+        if terms.len() != 2 { return None; }
 
         let count = count_terms(&terms[0], &Rc::clone(&ss));
         let count = SInteger(count);
@@ -47,7 +47,7 @@ pub fn bip_count<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
 
         return ss;
     }
-    panic!("bip_count() - Requires 2 arguments.");
+    return None;
 
 } // bip_count()
 
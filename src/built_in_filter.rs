@@ -40,12 +40,12 @@ pub fn bip_include<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
 
     if let Some(terms) = bip.terms {
 
-        if terms.len() != 3 { panic!("bip_include() - Requires 3 arguments."); }
+        if terms.len() != 3 { return None; }
         let filtered_list = filter(&terms[0], &terms[1], ss, true)?;
         let out = &terms[2];
         return out.unify(&filtered_list, &Rc::clone(&ss));
     }
-    panic!("bip_include() - Requires 3 arguments.");
+    return None;
 
 } // bip_include()
 
@@ -76,11 +76,11 @@ pub fn bip_exclude<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
 
     if let Some(terms) = bip.terms {
 
-        if terms.len() != 3 { panic!("bip_exclude() - Requires 3 arguments."); }
+        if terms.len() != 3 { return None; }
         let filtered_list = filter(&terms[0], &terms[1], ss, false)?;
         let out = &terms[2];
         return out.unify(&filtered_list, &Rc::clone(&ss));
     }
-    panic!("bip_exclude() - Requires 3 arguments.");
+    return None;
 
 } // bip_exclude()
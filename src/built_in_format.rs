@@ -0,0 +1,721 @@
+//! Functions to support the built-in predicates format(), format_atom()
+//! and format_number().
+//!
+//! The format predicate writes formatted text, in the style of Prolog's
+//! format/2. A format string contains directives, which begin with a
+//! tilde (~). Each directive consumes an argument from the argument list.
+//!
+//! <pre>
+//!     format("~w is ~d years old.~n", [$Name, $Age])
+//! </pre>
+//!
+//! The format_atom predicate does the same formatting, but unifies the
+//! result with an atom, instead of writing it.
+//!
+//! <pre>
+//!     format_atom("id_~a_~d", [user, 7], $ID)   # $ID = id_user_7
+//! </pre>
+//!
+//! The format_number predicate formats a number with a fixed number of
+//! decimal places.
+//!
+//! <pre>
+//!     format_number(1.4000000000000001, 2, $A)   # $A = 1.40
+//! </pre>
+//!
+//! The following directives are supported:
+//!
+//! <pre>
+//! ~w   write any term
+//! ~a   write an atom (or other constant)
+//! ~d   write an integer
+//! ~Nd  write an integer, with a decimal point inserted N digits from the right
+//! ~f   write a number as a float, with 6 digits after the decimal point
+//! ~Nf  write a number as a float, with N digits after the decimal point
+//! ~n   write a new line (~Nn writes N new lines)
+//! ~~   write a tilde
+//! ~t   insert fill characters here when padding a column (~`ct fills with c)
+//! ~N|  set a column stop at column N
+//! ~N+  set a column stop N characters past the previous stop (default 8)
+//! </pre>
+//!
+//! Column stops are used to align text. Text between two column stops is
+//! padded to fill the column. If the text contains no ~t fill points, it
+//! is left aligned. For example, `~t~w~10|` right-aligns a term in a
+//! column 10 characters wide.
+//!
+//! Numeric arguments, such as column stops and counts of new lines,
+//! cannot be greater than 10000. This limits the size of the padding
+//! which a format string can produce.
+//
+// Cleve Lendon  2023
+
+use std::rc::Rc;
+
+use super::unifiable::{*, Unifiable::*};
+use super::s_linked_list::*;
+use super::substitution_set::*;
+use super::built_in_predicates::*;
+use super::built_in_stream::*;
+
+use crate::atom;
+use crate::str_to_chars;
+
+// Maximum value of a numeric argument of a directive, eg. ~N|.
+const MAX_NUMERIC: usize = 10_000;
+
+/// Writes formatted text for the built-in predicate format().
+///
+/// In Suiron source code, format() takes one, two or three arguments.
+/// <pre>
+///     format(FormatString)
+///     format(FormatString, Arguments)
+///     format(Stream, FormatString, Arguments)
+/// </pre>
+///
+/// Arguments is a list of terms. If it is not a list, it is treated as
+/// a list of one term. The stream can be `user_output` (standard output),
+/// `user_error` (standard error), or a stream opened by
+/// [open()](../built_in_stream/fn.bip_open.html).
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+/// # Note
+/// * The predicate fails if the number of arguments is wrong, if the
+///   format string is invalid or does not match the arguments, or if
+///   the stream is unknown or not open for writing.
+///
+pub fn bip_format<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                      -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        let (stream, format, args) = match terms.len() {
+            1 => { (None, &terms[0], None) },
+            2 => { (None, &terms[0], Some(&terms[1])) },
+            3 => { (Some(&terms[0]), &terms[1], Some(&terms[2])) },
+            _ => { return None; },
+        };
+
+        let text = format_from_terms(format, args, ss).ok()?;
+
+        let stream = match stream {
+            Some(s) => { s.clone() },
+            None => { atom!("user_output") },
+        };
+
+        write_to_stream(&stream, &text, ss).ok()?;
+        return Some(Rc::clone(ss));
+    }
+    return None;
+
+} // bip_format()
+
+/// Formats text, and unifies the result with an atom.
+///
+/// In Suiron source code, the format_atom() predicate requires three arguments.
+/// <pre>
+///     format_atom(FormatString, Arguments, $Atom)
+/// </pre>
+///
+/// The format string and arguments are the same as for format(), but the
+/// formatted text is unified with the last argument, instead of being
+/// written to standard output. This is useful for constructing messages
+/// and identifiers.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+/// # Note
+/// * The predicate fails if the number of arguments is wrong, or if the
+///   format string is invalid or does not match the arguments.
+///
+pub fn bip_format_atom<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                           -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 3 { return None; }
+
+        let text = format_from_terms(&terms[0], Some(&terms[1]), ss).ok()?;
+
+        let out = &terms[2];
+        return out.unify(&Atom(text.into()), ss);
+    }
+    return None;
+
+} // bip_format_atom()
+
+/// Formats a number with a fixed number of decimal places.
+///
+/// In Suiron source code, the format_number() predicate requires three arguments.
+/// <pre>
+///     format_number($Number, Decimals, $Atom)
+/// </pre>
+///
+/// The number (an integer or float) is rounded to the given number of
+/// decimal places, and the resulting text is unified with the last
+/// argument, as an atom. If Decimals is 0, there is no decimal point.
+/// If the number is not grounded, or is not a number, the predicate fails.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+/// # Note
+/// * The predicate fails if the number of arguments is not 3, or if
+///   Decimals is not a non-negative integer.
+///
+pub fn bip_format_number<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                             -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 3 { return None; }
+
+        let number = match get_constant(&terms[0], ss)? {
+            SInteger(i) => { *i as f64 },
+            SFloat(f) => { *f },
+            _ => { return None; },
+        };
+
+        let decimals = match get_constant(&terms[1], ss) {
+            Some(SInteger(d)) if *d >= 0 => { *d as usize },
+            _ => { return None; },
+        };
+
+        let text = format_number(number, decimals);
+        return terms[2].unify(&Atom(text.into()), ss);
+    }
+    return None;
+
+} // bip_format_number()
+
+/// Formats a number with a fixed number of decimal places.
+///
+/// # Arguments
+/// * number
+/// * number of decimal places
+/// # Return
+/// * formatted number
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let s = format_number(0.1 + 0.2, 2);
+/// println!("{}", s);  // Prints: 0.30
+/// ```
+pub fn format_number(number: f64, decimals: usize) -> String {
+    format!("{:.*}", decimals, number)
+} // format_number()
+
+/// Formats text from a format string and a list of arguments.
+///
+/// This function gets the format string and arguments from Suiron terms.
+/// The format string can be an atom or string. The arguments should be a
+/// list. (If they are not a list, they are treated as a list of one term.)
+/// Logic variables are replaced by their ground terms.
+///
+/// # Arguments
+/// * format string - Atom or SString
+/// * optional arguments - list of terms
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * formatted text or error message
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let format = SString("~w is ~d.".to_string());
+/// let args = slist!(false, atom!("Zeno"), SInteger(7));
+/// let ss = empty_ss!();
+/// let text = format_from_terms(&format, Some(&args), &ss).unwrap();
+/// println!("{}", text);  // Prints: Zeno is 7.
+/// ```
+pub fn format_from_terms(format: &Unifiable, args: Option<&Unifiable>,
+                         ss: &Rc<SubstitutionSet>) -> Result<String, String> {
+
+    let format = match get_text(format, ss) {
+        Some(text) => { text },
+        None => {
+            let err = format!("format() - Invalid format string: {}", format);
+            return Err(err);
+        },
+    };
+
+    let mut arg_terms: Vec<Unifiable> = vec![];
+    if let Some(args) = args {
+        if get_list(args, ss).is_some() {
+            for term in get_terms(args, ss) {
+                arg_terms.push(term.replace_variables(ss));
+            }
+        }
+        else { arg_terms.push(args.replace_variables(ss)); }
+    }
+
+    return format_terms(&format, &arg_terms);
+
+} // format_from_terms()
+
+/// Formats a list of terms according to a format string.
+///
+/// See the [module documentation](../built_in_format/index.html)
+/// for a list of directives.
+///
+/// # Arguments
+/// * format string
+/// * vector of [Unifiable](../unifiable/enum.Unifiable.html) terms
+/// # Return
+/// * formatted text or error message
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let args = vec![atom!("Apples"), SFloat(1.5)];
+/// let text = format_terms("~w~t~10|~t~2f~8+", &args).unwrap();
+/// println!("[{}]", text);  // Prints: [Apples        1.50]
+/// ```
+pub fn format_terms(format: &str, args: &[Unifiable]) -> Result<String, String> {
+
+    let chrs = str_to_chars!(format);
+    let length = chrs.len();
+
+    let mut out = String::new();
+    let mut columns = Columns::new();
+    let mut arg_index = 0;
+
+    let mut i = 0;
+    while i < length {
+
+        let ch = chrs[i];
+        if ch != '~' {
+            columns.segment.push(ch);
+            i += 1;
+            continue;
+        }
+
+        i += 1;
+        if i >= length { return Err(ft_error("Missing directive", format)); }
+
+        // Get fill character, eg. ~`-t
+        let mut fill_char = ' ';
+        if chrs[i] == '`' {
+            if i + 1 >= length {
+                return Err(ft_error("Missing fill character", format));
+            }
+            fill_char = chrs[i + 1];
+            i += 2;
+        }
+        // Get numeric argument, eg. ~2f or ~*f
+        let mut numeric: Option<usize> = None;
+        if i < length && chrs[i] == '*' {
+            if arg_index >= args.len() {
+                return Err(ft_error("Not enough arguments", format));
+            }
+            match &args[arg_index] {
+                SInteger(n) if *n >= 0 => { numeric = Some(*n as usize); },
+                _ => {
+                    return Err(ft_error("~* requires a positive integer", format));
+                },
+            }
+            arg_index += 1;
+            i += 1;
+        }
+        else {
+            let start = i;
+            while i < length && chrs[i].is_ascii_digit() { i += 1; }
+            if i > start {
+                let digits: String = chrs[start..i].iter().collect();
+                match digits.parse::<usize>() {
+                    Ok(n) => { numeric = Some(n); },
+                    Err(_) => {
+                        return Err(ft_error("Invalid numeric argument", format));
+                    },
+                }
+            }
+        }
+
+        if numeric.is_some_and(|n| n > MAX_NUMERIC) {
+            return Err(ft_error("Numeric argument too large", format));
+        }
+
+        if i >= length { return Err(ft_error("Missing directive", format)); }
+        let directive = chrs[i];
+        i += 1;
+
+        match directive {
+            'w' | 'a' | 'd' | 'f' => {
+                if arg_index >= args.len() {
+                    return Err(ft_error("Not enough arguments", format));
+                }
+                let arg = &args[arg_index];
+                arg_index += 1;
+                let text = match directive {
+                    'w' => { format_w(arg) },
+                    'a' => { format_a(arg, format)? },
+                    'd' => { format_d(arg, numeric, format)? },
+                    _   => { format_f(arg, numeric, format)? },
+                };
+                columns.segment.push_str(&text);
+            },
+            'n' => {
+                columns.flush(&mut out);
+                for _ in 0..numeric.unwrap_or(1) { out.push('\n'); }
+                columns.previous_stop = 0;
+            },
+            '~' => { columns.segment.push('~'); },
+            't' => {
+                let position = columns.segment.chars().count();
+                columns.fill_points.push((position, fill_char));
+            },
+            '|' => {
+                let target = match numeric {
+                    Some(n) => { n },
+                    None => { columns.current_column(&out) },
+                };
+                columns.stop(&mut out, target);
+            },
+            '+' => {
+                let target = columns.previous_stop + numeric.unwrap_or(8);
+                columns.stop(&mut out, target);
+            },
+            _ => {
+                let err = format!("Unknown directive ~{}", directive);
+                return Err(ft_error(&err, format));
+            },
+        }
+
+    } // while
+
+    if arg_index < args.len() {
+        return Err(ft_error("Too many arguments", format));
+    }
+
+    columns.flush(&mut out);
+    return Ok(out);
+
+} // format_terms()
+
+// Keeps track of text which has not yet been aligned in a column.
+//
+//   segment - text written since the previous column stop
+//   fill_points - positions in segment where padding can be
+//                 inserted, with fill characters
+//   previous_stop - column of the previous column stop
+struct Columns {
+    segment: String,
+    fill_points: Vec<(usize, char)>,
+    previous_stop: usize,
+}
+
+impl Columns {
+
+    fn new() -> Self {
+        Columns {
+            segment: String::new(),
+            fill_points: vec![],
+            previous_stop: 0,
+        }
+    }
+
+    // Gets the column at the end of the pending segment.
+    // Arguments:
+    //   out - text output so far
+    // Return:
+    //   column number (starting from 0)
+    fn current_column(&self, out: &str) -> usize {
+        let text = format!("{}{}", out, self.segment);
+        match text.rfind('\n') {
+            Some(index) => { text[index + 1..].chars().count() },
+            None => { text.chars().count() },
+        }
+    }
+
+    // Writes the pending segment to the output, without padding.
+    // Arguments:
+    //   out - text output so far
+    fn flush(&mut self, out: &mut String) {
+        out.push_str(&self.segment);
+        self.segment.clear();
+        self.fill_points.clear();
+    }
+
+    // Pads the pending segment to reach the target column, and writes
+    // it to the output. Padding is divided between the fill points.
+    // If there are no fill points, the segment is padded on the right.
+    // Arguments:
+    //   out - text output so far
+    //   target - column to pad to
+    fn stop(&mut self, out: &mut String, target: usize) {
+
+        let column = self.current_column(out);
+
+        if column < target {
+            let pad = target - column;
+            if self.fill_points.len() == 0 {
+                self.fill_points.push((self.segment.chars().count(), ' '));
+            }
+            let n = self.fill_points.len();
+            let mut chrs: Vec<char> = self.segment.chars().collect();
+            // Insert from the last fill point, so positions stay valid.
+            // Any remainder goes to the last fill point.
+            for (k, (position, fill)) in self.fill_points.iter().enumerate().rev() {
+                let mut count = pad / n;
+                if k == n - 1 { count += pad % n; }
+                for _ in 0..count { chrs.insert(*position, *fill); }
+            }
+            self.segment = chrs.into_iter().collect();
+            self.previous_stop = target;
+        }
+        else { self.previous_stop = column; }
+
+        self.flush(out);
+
+    } // stop()
+
+} // impl Columns
+
+// Formats a term for the ~w directive.
+// Strings are written without quotation marks.
+fn format_w(arg: &Unifiable) -> String {
+    match arg {
+        SString(s) => { s.to_string() },
+        _ => { arg.to_string() },
+    }
+} // format_w()
+
+// Formats a constant for the ~a directive.
+fn format_a(arg: &Unifiable, format: &str) -> Result<String, String> {
+    match arg {
+        Atom(s)    => { Ok(s.to_string()) },
+        SString(s) => { Ok(s.to_string()) },
+        SInteger(_) | SFloat(_) => { Ok(arg.to_string()) },
+        _ => {
+            let err = format!("~a requires an atom: {}", arg);
+            Err(ft_error(&err, format))
+        },
+    }
+} // format_a()
+
+// Formats an integer for the ~d directive. If there is a numeric
+// argument, a decimal point is inserted that many digits from the
+// right. Eg. ~2d formats 1234 as 12.34
+fn format_d(arg: &Unifiable, numeric: Option<usize>,
+            format: &str) -> Result<String, String> {
+    let i = match arg {
+        SInteger(i) => { *i },
+        _ => {
+            let err = format!("~d requires an integer: {}", arg);
+            return Err(ft_error(&err, format));
+        },
+    };
+    let digits = match numeric {
+        Some(n) if n > 0 => { n },
+        _ => { return Ok(i.to_string()); },
+    };
+    let sign = if i < 0 { "-" } else { "" };
+    let mut abs = i.unsigned_abs().to_string();
+    while abs.len() <= digits { abs.insert(0, '0'); }
+    let (whole, fraction) = abs.split_at(abs.len() - digits);
+    return Ok(format!("{}{}.{}", sign, whole, fraction));
+} // format_d()
+
+// Formats a number for the ~f directive. The numeric argument
+// is the number of digits after the decimal point. Default is 6.
+fn format_f(arg: &Unifiable, numeric: Option<usize>,
+            format: &str) -> Result<String, String> {
+    let f = match arg {
+        SFloat(f) => { *f },
+        SInteger(i) => { *i as f64 },
+        _ => {
+            let err = format!("~f requires a number: {}", arg);
+            return Err(ft_error(&err, format));
+        },
+    };
+    return Ok(format_number(f, numeric.unwrap_or(6)));
+} // format_f()
+
+// Formats an error message for format_terms().
+// Arguments:
+//   err - error description
+//   bad - format string which caused the error
+// Return:
+//   error message (String)
+fn ft_error(err: &str, bad: &str) -> String {
+    format!("format() - {}: {}", err, bad)
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    // Test basic directives.
+    #[test]
+    fn test_format_directives() {
+        let args = [atom!("Cleve"), SInteger(42), SFloat(1.23456), SInteger(5)];
+        let s = format_terms("~w is ~d. ~f, ~2f.~n~~", &args);
+        assert_eq!("Cleve is 42. 1.234560, 5.00.\n~", s.unwrap());
+
+        let s = format_terms("~a ~w", &[SString("text".to_string()),
+                                        parse_term("f(a, \"b\")").unwrap()]);
+        assert_eq!("text f(a, \"b\")", s.unwrap());
+
+        let s = format_terms("~2d ~2d ~0d", &[SInteger(1234), SInteger(-5),
+                                              SInteger(7)]);
+        assert_eq!("12.34 -0.05 7", s.unwrap());
+    }
+
+    // Test column control.
+    #[test]
+    fn test_format_columns() {
+        let args = [atom!("Apples"), SFloat(1.5)];
+        let s = format_terms("~w~t~10|~t~2f~8+", &args).unwrap();
+        assert_eq!("Apples        1.50", s);
+
+        let s = format_terms("[~t~w~t~12|]", &[atom!("mid")]).unwrap();
+        assert_eq!("[    mid    ]", s);
+
+        let s = format_terms("~`-t~30|~n~w~`.t~12|end", &[atom!("abc")]).unwrap();
+        assert_eq!("------------------------------\nabc.........end", s);
+
+        // Text longer than column is not truncated.
+        let s = format_terms("~w~4|x", &[atom!("abcdef")]).unwrap();
+        assert_eq!("abcdefx", s);
+    }
+
+    // Test format_atom() predicate.
+    #[test]
+    fn test_format_atom() {
+
+        let mut kb = KnowledgeBase::new();
+
+        let rules = ["make_id($ID) :- format_atom(\"id_~a_~3d\", [user, 7], $ID).",
+                     "message($M) :- $N = 3, format_atom(\"~w items~t~12|.\", $N, $M).",
+                     "check($X) :- format_atom(\"~w\", [abc], abc), $X = yes."];
+
+        for r in rules {
+            match parse_rule(r) {
+                Ok(rule) => { add_rules!(&mut kb, rule); },
+                Err(err) => { panic!("Should be no parsing errors. {}", err); },
+            }
+        }
+
+        let query = parse_query("make_id($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = id_user_0.007", solve(sn));
+
+        let query = parse_query("message($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = 3 items     .", solve(sn));
+
+        let query = parse_query("check($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = yes", solve(sn));
+
+    } // test_format_atom()
+
+    // Test format_number() predicate, and the ~Nf directive.
+    #[test]
+    fn test_format_number() {
+
+        assert_eq!("1.40", format_number(1.4000000000000001, 2));
+        assert_eq!("3", format_number(2.718, 0));
+        assert_eq!("-0.125", format_number(-0.125, 3));
+
+        let s = format_terms("~1f ~0f ~3f", &[SFloat(2.25), SFloat(9.6), SInteger(4)]);
+        assert_eq!("2.2 10 4.000", s.unwrap());
+
+        let mut kb = KnowledgeBase::new();
+        let rules = ["price($A) :- $P = 1.4000000000000001, format_number($P, 2, $A).",
+                     "whole($A) :- format_number(42, 1, $A).",
+                     "unbound($A) :- format_number($X, 1, $A)."];
+        for r in rules {
+            match parse_rule(r) {
+                Ok(rule) => { add_rules!(&mut kb, rule); },
+                Err(err) => { panic!("Should be no parsing errors. {}", err); },
+            }
+        }
+
+        let query = parse_query("price($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = 1.40", solve(sn));
+
+        let query = parse_query("whole($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = 42.0", solve(sn));
+
+        let query = parse_query("unbound($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("No more.", solve(sn));
+
+    } // test_format_number()
+
+    // Invalid format strings and arguments make the predicates fail.
+    #[test]
+    fn test_format_failures() {
+
+        let mut kb = KnowledgeBase::new();
+        let rules = ["bad($X) :- format(\"~w ~w\", [a]), $X = 1.",
+                     "bad($X) :- format(\"~q\", [a]), $X = 2.",
+                     "bad($X) :- format(\"~2f\", [abc]), $X = 3.",
+                     "bad($X) :- format(no_such_stream, \"~w\", [a]), $X = 4.",
+                     "bad($X) :- format_atom(\"~d\", [a], $A), $X = 5.",
+                     "bad($X) :- format_number(abc, 2, $A), $X = 6.",
+                     "bad($X) :- format_number(1.5, -1, $A), $X = 7.",
+                     "bad($X) :- format_number(1.5, two, $A), $X = 8.",
+                     "bad($X) :- $X = done."];
+        for r in rules {
+            match parse_rule(r) {
+                Ok(rule) => { add_rules!(&mut kb, rule); },
+                Err(err) => { panic!("Should be no parsing errors. {}", err); },
+            }
+        }
+
+        let query = parse_query("bad($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!(vec!["$X = done"], solve_all(sn));
+
+    } // test_format_failures()
+
+    // Test formatting errors.
+    #[test]
+    fn test_format_errors() {
+        let err = format_terms("~w ~w", &[atom!("a")]).unwrap_err();
+        assert_eq!("format() - Not enough arguments: ~w ~w", err);
+        let err = format_terms("~w", &[atom!("a"), atom!("b")]).unwrap_err();
+        assert_eq!("format() - Too many arguments: ~w", err);
+        let err = format_terms("~d", &[atom!("a")]).unwrap_err();
+        assert_eq!("format() - ~d requires an integer: a: ~d", err);
+        let err = format_terms("~z", &[]).unwrap_err();
+        assert_eq!("format() - Unknown directive ~z: ~z", err);
+        // Padding is limited.
+        let err = format_terms("~t~1000000000000|", &[]).unwrap_err();
+        assert_eq!("format() - Numeric argument too large: ~t~1000000000000|", err);
+        let err = format_terms("~w~*+", &[atom!("a"), SInteger(20_000)]).unwrap_err();
+        assert_eq!("format() - Numeric argument too large: ~w~*+", err);
+        assert!(format_terms("~t~10000|", &[]).is_ok());
+    }
+
+} // test
@@ -2,6 +2,7 @@
 //!
 
 use std::rc::Rc;
+use super::error::SuironError;
 use super::s_complex::*;
 use super::parse_terms::*;
 use super::parse_goals::*;
@@ -35,7 +36,7 @@ use crate::chars_to_string;
 /// use suiron::*;
 ///
 /// // Try:  $X = add(1, 2, 3)
-/// let x = logic_var!(next_id(), "$X");
+/// let x = logic_var!(1, "$X");
 /// let ss = empty_ss!();
 /// let terms = vec![SInteger(1), SInteger(2), SInteger(3)];
 ///
@@ -59,20 +60,8 @@ pub fn unify_sfunction<'a>(name: &str, terms: &'a Vec<Unifiable>,
         let result = evaluate_join(terms, ss);
         return result.unify(other, ss);
     }
-    else if name.eq("add") {
-        let result = evaluate_add(terms, ss);
-        return result.unify(other, ss);
-    }
-    else if name.eq("subtract") {
-        let result = evaluate_subtract(terms, ss);
-        return result.unify(other, ss);
-    }
-    else if name.eq("multiply") {
-        let result = evaluate_multiply(terms, ss);
-        return result.unify(other, ss);
-    }
-    else if name.eq("divide") {
-        let result = evaluate_divide(terms, ss);
+    // If the evaluation fails (eg. division by zero), the unification fails.
+    else if let Ok(result) = evaluate_function(&name, terms, ss) {
         return result.unify(other, ss);
     }
 
@@ -100,7 +89,7 @@ pub fn unify_sfunction<'a>(name: &str, terms: &'a Vec<Unifiable>,
 /// }
 /// ```
 /// Should print: add(7, 9, 4)
-pub fn parse_function(to_parse: &str) -> Result<Unifiable, String> {
+pub fn parse_function(to_parse: &str) -> Result<Unifiable, SuironError> {
 
     let s = to_parse.trim();
     let chrs = str_to_chars!(s);
@@ -108,7 +97,7 @@ pub fn parse_function(to_parse: &str) -> Result<Unifiable, String> {
     // Built-in functions have the same form as complex terms.
     // That is: name(term1, term2...)
     match validate_complex(s, &chrs) {
-        Some(error_message) => { return Err(error_message); },
+        Some(error_message) => { return Err(error_message.into()); },
         None => {},
     }
 
@@ -122,7 +111,7 @@ pub fn parse_function(to_parse: &str) -> Result<Unifiable, String> {
                     let terms_str = chars_to_string!(chrs[left + 1..right]);
                     if terms_str.len() == 0 {
                         let err = format!("parse_function - No arguments: {}", s);
-                        return Err(err);
+                        return Err(err.into());
                     }
 
                     let mut new_terms: Vec<Unifiable> = vec![];
@@ -134,17 +123,17 @@ pub fn parse_function(to_parse: &str) -> Result<Unifiable, String> {
                         },
                         Err(err) => {
                             let err = format!("{}{}", err, s);
-                            return Err(err);
+                            return Err(err.into());
                         },
                     }
                 },
                 None => {
                     let err = format!("parse_function() - Invalid function: {}", s);
-                    return Err(err);
+                    return Err(err.into());
                 },
             } // match
         },
-        Err(err) => { return Err(err); }
+        Err(err) => { return Err(err.into()); }
     } // match
 
 } // parse_function()
@@ -0,0 +1,186 @@
+//! Functions to support the built-in predicate is().
+//!
+//! The is predicate evaluates an arithmetic expression, and unifies
+//! the result with its left operand. For example:
+//!
+//! <pre>
+//!     $Area is $Width * $Height
+//!     $Total is add($A, multiply($B, 2))
+//! </pre>
+//
+// Cleve Lendon  2023
+
+use std::rc::Rc;
+
+use super::substitution_set::*;
+use super::built_in_predicates::*;
+use super::built_in_arithmetic::*;
+
+/// Evaluates an arithmetic expression, and unifies the result.
+///
+/// In Suiron source code, the is() predicate is usually written as an infix:
+/// <pre>
+///     $X is $Expression
+/// </pre>
+///
+/// It can also be written as: `is($X, $Expression)`
+///
+/// The expression can be a number, a logic variable bound to a number,
+/// or an arithmetic function, such as `$Y * 2 + 1`. Functions can be
+/// nested. The result is unified with the left operand.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+/// # Note
+/// * If the expression cannot be evaluated, because it contains
+///   an unbound variable or a term which is not a number, or because
+///   of overflow or division by zero, the predicate fails.
+/// * If the number of arguments is not 2, the predicate fails.
+///
+pub fn bip_is<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                  -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 2 { return None; }
+
+        match evaluate_expression(&terms[1], ss) {
+            Ok(result) => { return terms[0].unify(&result, ss); },
+            // The ArithmeticError is not passed up. The goal fails,
+            // as documented above.
+            Err(_) => { return None; },
+        }
+    }
+    return None;
+
+} // bip_is()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    // Test the is() predicate.
+    #[test]
+    fn test_bip_is() {
+
+        let mut kb = KnowledgeBase::new();
+
+        let rules = ["area($A) :- $W = 3, $H = 4.5, $A is $W * $H.",
+                     "nested($X) :- $Y = 2, $X is add(1, multiply($Y, subtract(10, $Y))).",
+                     "check($X) :- 6 is 2 * 3, $X = yes.",
+                     "wrong($X) :- 7 is 2 * 3, $X = yes.",
+                     "prefix($X) :- is($X, divide(7, 2)).",
+                     "modulo($X, $Y) :- $X = -7 mod 3, $Y is -7 rem 3.",
+                     "zero($X) :- $X is 5 / 0.",
+                     "zero($X) :- $X = 5 mod 0.",
+                     "zero($X) :- $X is $Y + 1.",
+                     "zero($X) :- $X = none.",
+                     "never($X) :- $X is 5 / 0."];
+
+        for r in rules {
+            match parse_rule(r) {
+                Ok(rule) => { add_rules!(&mut kb, rule); },
+                Err(err) => { panic!("Should be no parsing errors. {}", err); },
+            }
+        }
+
+        let query = parse_query("area($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = 13.5", solve(sn));
+
+        let query = parse_query("nested($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = 17", solve(sn));
+
+        let query = parse_query("check($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = yes", solve(sn));
+
+        let query = parse_query("wrong($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("No more.", solve(sn));
+
+        let query = parse_query("prefix($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = 3", solve(sn));
+
+        let query = parse_query("modulo($X, $Y)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = 2, $Y = -1", solve(sn));
+
+        // Arithmetic errors cause failure, not a panic.
+        let query = parse_query("zero($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = none", solve(sn));
+
+        // The error is not reported by the solver.
+        let query = parse_query("never($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!(Ok(None), try_solve(sn));
+
+        // Display
+        let goal = parse_subgoal("$X is $Y + 1").unwrap();
+        assert_eq!("$X is add($Y, 1)", goal.to_string());
+
+    } // test_bip_is()
+
+    // With the bigint feature, is() evaluates big integers.
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_bip_is_bigint() {
+
+        let mut kb = KnowledgeBase::new();
+
+        let rules = ["big($X) :- $X is 2 ** 100 - 2 ** 100.",
+                     "big($X) :- $X is 2 ** 64 - 2 ** 64 + 5.",
+                     "big($X) :- $X is 2 ** 100 // 2 ** 98.",
+                     "big($X) :- $X is 2 ** 100 // 3.",
+                     "big($X) :- $X is (2 ** 100 + 7) mod 2 ** 64.",
+                     "big($X) :- $X is -(2 ** 100) mod 3.",
+                     "big($X) :- $X is (2 ** 100 + 7) rem 2 ** 64.",
+                     "big($X) :- $X is (2 ** 100 + 6) /\\ 7.",
+                     "big($X) :- $X is 2 ** 100 \\/ 1.",
+                     "big($X) :- $X is 2 ** 100 xor 2 ** 100.",
+                     "big($X) :- $X is sqrt(2 ** 100).",
+                     "big($X) :- $X is abs(-(2 ** 100)).",
+                     "big($X) :- $X is sign(-(2 ** 100)).",
+                     "same($X) :- $Y is 2 ** 100, $Y == $Y, $X = yes.",
+                     "same($X) :- $Y is 2 ** 100, $Y == 1267650600228229401496703205376, $X = yes.",
+                     "same($X) :- $Y is 2 ** 100, $Y == 1267650600228229401496703205377, $X = no.",
+                     "same($X) :- $Y is 2 ** 100, $Y == 1.0, $X = no."];
+
+        for r in rules {
+            match parse_rule(r) {
+                Ok(rule) => { add_rules!(&mut kb, rule); },
+                Err(err) => { panic!("Should be no parsing errors. {}", err); },
+            }
+        }
+
+        let query = Rc::new(parse_query("big($X)").unwrap());
+        let sn = make_base_node(Rc::clone(&query), &kb);
+        let results = solve_all(sn);
+        let expected = ["$X = 0", "$X = 5", "$X = 4",
+                        "$X = 422550200076076467165567735125",
+                        "$X = 7", "$X = 2", "$X = 7", "$X = 6",
+                        "$X = 1267650600228229401496703205377", "$X = 0",
+                        "$X = 1125899906842624", "$X = 1267650600228229401496703205376",
+                        "$X = -1"];
+        assert_eq!(expected.to_vec(), results);
+
+        let query = Rc::new(parse_query("same($X)").unwrap());
+        let sn = make_base_node(Rc::clone(&query), &kb);
+        assert_eq!(vec!["$X = yes", "$X = yes"], solve_all(sn));
+
+    } // test_bip_is_bigint()
+
+} // test
@@ -13,12 +13,22 @@
 //!
 //! $X is bound to the atom: `coffee, tea or juice`
 //!
+//! There is also a join() predicate, which joins the items of a list
+//! with a separator:
+//!
+//! <pre>
+//!   join([red, green, blue], ", ", $X).
+//! </pre>
+//!
+//! $X is bound to the atom: `red, green, blue`
+//!
 // Cleve Lendon  2023
 
 use std::rc::Rc;
 use super::unifiable::Unifiable;
 use super::s_linked_list::*;
 use super::substitution_set::*;
+use super::built_in_predicates::*;
 
 use crate::atom;
 
@@ -58,7 +68,7 @@ pub fn evaluate_join<'a>(in_terms: &'a Vec<Unifiable>,
     let mut out = "".to_string();
     let mut first = true;
     for term in all_terms {
-        let s = format!("{}", term);
+        let s = get_text(&term, ss).unwrap_or_else(|| format!("{}", term));
         if is_punctuation(&s) {
             out += &s;
             first = false;
@@ -76,9 +86,114 @@ pub fn evaluate_join<'a>(in_terms: &'a Vec<Unifiable>,
 
 } // evaluate_join
 
+/// Joins the items of a list, with a separator, for the join() predicate.
+///
+/// In Suiron source code, the join() predicate requires three arguments.
+/// <pre>
+///     join($List, Separator, $Out)
+/// </pre>
+///
+/// The first argument is a list of terms (or a single term). The text of
+/// each term is joined, with the separator between items, and the result
+/// is unified with the last argument, as an atom. Strings are joined
+/// without quotation marks. If the list or separator is not grounded,
+/// the predicate fails.
+///
+/// Note: When join() is used as an operand, eg. `$X = join(a, b)`, it is
+/// a function, which is evaluated by
+/// [evaluate_join()](../built_in_join/fn.evaluate_join.html).
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+///
+pub fn bip_join<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                    -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 3 { return None; }
+
+        let separator = get_text(&terms[1], ss)?;
+        let items = if get_list(&terms[0], ss).is_some() {
+                        get_terms(&terms[0], ss)
+                    }
+                    else { vec![get_ground_term(&terms[0], ss)?.clone()] };
+
+        let mut texts: Vec<String> = vec![];
+        for item in items {
+            let item = item.replace_variables(ss);
+            if let Unifiable::LogicVar{id: _, name: _} = item { return None; }
+            texts.push(get_text(&item, ss).unwrap_or_else(|| item.to_string()));
+        }
+
+        let out = &terms[2];
+        return out.unify(&atom!(texts.join(&separator)), ss);
+    }
+    return None;
+
+} // bip_join()
+
 // Checks if the given string slice is punctuation.
 fn is_punctuation(s: &str) -> bool {
     if s == "," || s == "." ||
        s == "?" || s == "!" { return true; }
     return false;
 }
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    // Test join() predicate, with a separator.
+    #[test]
+    fn test_bip_join() {
+
+        let mut kb = KnowledgeBase::new();
+
+        let rules = ["colors($X) :- join([red, green, blue], \", \", $X).",
+                     "path($X) :- $L = [\"usr\", local, bin], join($L, /, $X).",
+                     "numbers($X) :- join([1, 2.5, f(3)], \"\", $X).",
+                     "single($X) :- join(alone, -, $X).",
+                     "function($X) :- $X = join(coffee, \\,, tea)."];
+
+        for r in rules {
+            match parse_rule(r) {
+                Ok(rule) => { add_rules!(&mut kb, rule); },
+                Err(err) => { panic!("Should be no parsing errors. {}", err); },
+            }
+        }
+
+        let query = parse_query("colors($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = red, green, blue", solve(sn));
+
+        let query = parse_query("path($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = usr/local/bin", solve(sn));
+
+        let query = parse_query("numbers($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = 12.5f(3)", solve(sn));
+
+        let query = parse_query("single($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = alone", solve(sn));
+
+        // join() as a function still works.
+        let query = parse_query("function($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = coffee, tea", solve(sn));
+
+    } // test_bip_join()
+
+} // test
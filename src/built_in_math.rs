@@ -0,0 +1,298 @@
+//! Suiron's math functions: abs, sign, sqrt, truncate, round, floor,
+//! ceiling, sin, cos, tan, log, exp.
+//!
+//! Each of these functions takes one numeric argument, which may be
+//! a nested arithmetic expression. For example:
+//!
+//! <pre>
+//!   $Hypotenuse is sqrt(add(multiply($A, $A), multiply($B, $B)))
+//!   $Rounded is round(divide($Total, 3))
+//! </pre>
+//!
+//! abs and sign return an integer for an integer argument, and a float
+//! for a float argument. truncate, round, floor and ceiling always return
+//! an integer. The remaining functions always return a float.
+//!
+//! Math functions are evaluated by
+//! [evaluate_function()](../built_in_arithmetic/fn.evaluate_function.html)
+//! in built_in_arithmetic.rs.
+//
+// Cleve Lendon  2023
+
+use std::rc::Rc;
+use super::substitution_set::*;
+use super::unifiable::Unifiable;
+use super::built_in_arithmetic::*;
+
+#[cfg(feature = "bigint")]
+use num_bigint::BigInt;
+#[cfg(feature = "bigint")]
+use num_traits::{FromPrimitive, Signed, ToPrimitive};
+
+/// Names of the math functions.
+pub static MATH_FUNCTIONS: [&str; 12] = ["abs", "sign", "sqrt", "truncate",
+                                         "round", "floor", "ceiling", "sin",
+                                         "cos", "tan", "log", "exp"];
+
+/// Evaluates a math function, such as sqrt() or round().
+///
+/// # Arguments
+/// * name of function
+/// * list of [Unifiable](../unifiable/enum.Unifiable.html) terms
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger),
+///   [SFloat](../unifiable/enum.Unifiable.html#variant.SFloat),
+///   or error message
+/// # Errors
+/// * If the name is not a math function.
+/// * If there is not exactly one argument.
+/// * If the argument is not grounded, or not a number.
+/// * If the result is undefined, eg. sqrt(-1) or log(0).
+/// * If an integer result overflows. (With the `bigint` feature, the
+///   result of abs, truncate, round, floor or ceiling is promoted to
+///   an SBigInt instead.)
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let arguments = parse_arguments("2.5").unwrap();
+/// let result = evaluate_math("floor", &arguments, &ss).unwrap();
+/// println!("{}", result);
+/// // Prints: 2
+/// ```
+pub fn evaluate_math<'a>(name: &str, arguments: &Vec<Unifiable>,
+                         ss: &'a Rc<SubstitutionSet<'a>>)
+                         -> Result<Unifiable, String> {
+
+    if !MATH_FUNCTIONS.contains(&name) {
+        return Err(format!("evaluate_math() - Unknown function: {}", name));
+    }
+
+    if arguments.len() != 1 {
+        return Err(format!("evaluate_math() - {}() requires 1 argument.", name));
+    }
+
+    let number = evaluate_expression(&arguments[0], ss)?;
+
+    // Functions which preserve integers.
+    if let Unifiable::SInteger(i) = number {
+        match name {
+            "abs"  => {
+                if let Some(a) = i.checked_abs() { return Ok(Unifiable::SInteger(a)); }
+                #[cfg(feature = "bigint")]
+                return Ok(from_bigint(BigInt::from(i).abs()));
+                #[cfg(not(feature = "bigint"))]
+                return Err(overflow_error(name, &number));
+            },
+            "sign" => { return Ok(Unifiable::SInteger(i.signum())); },
+            "truncate" | "round" | "floor" | "ceiling" => {
+                return Ok(Unifiable::SInteger(i));
+            },
+            _ => {},
+        }
+    }
+
+    // Big integers: abs and sign are exact. Rounding changes nothing.
+    #[cfg(feature = "bigint")]
+    if let Unifiable::SBigInt(b) = &number {
+        match name {
+            "abs"  => { return Ok(from_bigint(b.abs())); },
+            "sign" => { return Ok(Unifiable::SInteger(b.signum().to_i64().unwrap_or(0))); },
+            "truncate" | "round" | "floor" | "ceiling" => { return Ok(number.clone()); },
+            _ => {},
+        }
+    }
+
+    // Rational numbers: abs and sign are exact. Rounding gives an integer.
+    if let Unifiable::SRational(n, d) = number {
+        match name {
+            "abs"  => {
+                match n.checked_abs() {
+                    Some(a) => { return Ok(Unifiable::SRational(a, d)); },
+                    None => { return Err(overflow_error(name, &number)); },
+                }
+            },
+            "sign" => { return Ok(Unifiable::SInteger(n.signum())); },
+            "truncate" => { return Ok(Unifiable::SInteger(n / d)); },
+            "floor"    => { return Ok(Unifiable::SInteger(n.div_euclid(d))); },
+            _ => {},
+        }
+        // Calculate in i128, so that negating i64::MIN cannot overflow.
+        let (n, d) = (n as i128, d as i128);
+        let r = match name {
+            "ceiling" => { Some(-(-n).div_euclid(d)) },
+            // Half rounds away from zero.
+            "round"   => { Some(n.signum() * ((2 * n.abs() + d) / (2 * d))) },
+            _ => { None },
+        };
+        if let Some(r) = r {
+            match i64::try_from(r) {
+                Ok(i) => { return Ok(Unifiable::SInteger(i)); },
+                Err(_) => { return Err(overflow_error(name, &number)); },
+            }
+        }
+    }
+
+    let f = match number {
+        Unifiable::SInteger(i) => { i as f64 },
+        Unifiable::SRational(n, d) => { n as f64 / d as f64 },
+        Unifiable::SFloat(f) => { f },
+        #[cfg(feature = "bigint")]
+        Unifiable::SBigInt(ref b) => { b.to_f64().unwrap_or(f64::NAN) },
+        _ => { return Err(format!("evaluate_math() - Not a number: {}", number)); },
+    };
+
+    let result = match name {
+        "abs"      => { f.abs() },
+        "sign"     => { if f == 0.0 { 0.0 } else { f.signum() } },
+        "truncate" => { return float_to_integer(name, &number, f.trunc()); },
+        "round"    => { return float_to_integer(name, &number, f.round()); },
+        "floor"    => { return float_to_integer(name, &number, f.floor()); },
+        "ceiling"  => { return float_to_integer(name, &number, f.ceil()); },
+        "sqrt"     => { f.sqrt() },
+        "sin"      => { f.sin() },
+        "cos"      => { f.cos() },
+        "tan"      => { f.tan() },
+        "log"      => { f.ln() },
+        _          => { f.exp() },
+    };
+
+    if result.is_nan() || result.is_infinite() {
+        let err = format!("evaluate_math() - Undefined result: {}({})", name, number);
+        return Err(err);
+    }
+    return Ok(Unifiable::SFloat(result));
+
+} // evaluate_math()
+
+// Converts a whole float, such as the result of round(), to an integer.
+// Arguments:
+//   name - name of math function
+//   number - argument of math function
+//   f - float with no fraction
+// Return:
+//   SInteger (or SBigInt) or error message
+fn float_to_integer(name: &str, number: &Unifiable, f: f64) -> Result<Unifiable, String> {
+    // i64::MIN is -2^63, which is exact as a float. i64::MAX is not.
+    if (-9223372036854775808.0..9223372036854775808.0).contains(&f) {
+        return Ok(Unifiable::SInteger(f as i64));
+    }
+    #[cfg(feature = "bigint")]
+    if let Some(b) = BigInt::from_f64(f) { return Ok(from_bigint(b)); }
+    return Err(overflow_error(name, number));
+} // float_to_integer()
+
+// Creates an error message for an integer result which is out of range.
+// Arguments:
+//   name - name of math function
+//   number - argument of math function
+// Return:
+//   error message
+fn overflow_error(name: &str, number: &Unifiable) -> String {
+    return format!("evaluate_math() - Integer overflow: {}({})", name, number);
+} // overflow_error()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    // Evaluates a function with one argument.
+    fn eval(name: &str, argument: Unifiable) -> Unifiable {
+        let ss = empty_ss!();
+        return evaluate_math(name, &vec![argument], &ss).unwrap();
+    }
+
+    #[test]
+    fn test_evaluate_math() {
+
+        assert_eq!(SInteger(7), eval("abs", SInteger(-7)));
+        assert_eq!(SFloat(2.5), eval("abs", SFloat(-2.5)));
+        assert_eq!(SInteger(-1), eval("sign", SInteger(-12)));
+        assert_eq!(SInteger(0), eval("sign", SInteger(0)));
+        assert_eq!(SFloat(1.0), eval("sign", SFloat(0.3)));
+
+        assert_eq!(SInteger(2), eval("truncate", SFloat(2.7)));
+        assert_eq!(SInteger(-2), eval("truncate", SFloat(-2.7)));
+        assert_eq!(SInteger(3), eval("round", SFloat(2.5)));
+        assert_eq!(SInteger(-3), eval("floor", SFloat(-2.5)));
+        assert_eq!(SInteger(-2), eval("ceiling", SFloat(-2.5)));
+        assert_eq!(SInteger(9), eval("round", SInteger(9)));
+
+        assert_eq!(SFloat(4.0), eval("sqrt", SInteger(16)));
+        assert_eq!(SFloat(0.0), eval("sin", SInteger(0)));
+        assert_eq!(SFloat(1.0), eval("cos", SFloat(0.0)));
+        assert_eq!(SFloat(0.0), eval("tan", SInteger(0)));
+        assert_eq!(SFloat(0.0), eval("log", SInteger(1)));
+        assert_eq!(SFloat(1.0), eval("exp", SInteger(0)));
+
+        // Rational numbers.
+        assert_eq!(SRational(1, 3), eval("abs", SRational(-1, 3)));
+        assert_eq!(SInteger(-3), eval("floor", SRational(-5, 2)));
+        assert_eq!(SInteger(-2), eval("ceiling", SRational(-5, 2)));
+        assert_eq!(SInteger(-2), eval("truncate", SRational(-5, 2)));
+        assert_eq!(SInteger(-3), eval("round", SRational(-5, 2)));
+        assert_eq!(SInteger(2), eval("round", SRational(5, 3)));
+        assert_eq!(SFloat(0.5), eval("sqrt", SRational(1, 4)));
+
+        // Not a math function.
+        let ss = empty_ss!();
+        assert!(evaluate_math("add", &vec![SInteger(1)], &ss).is_err());
+
+        // Nested expression.
+        let ss = empty_ss!();
+        let expression = parse_term("sqrt(add(multiply(3, 3), 16)) + abs(-1)").unwrap();
+        assert_eq!(Ok(SFloat(6.0)), evaluate_expression(&expression, &ss));
+
+    } // test_evaluate_math()
+
+    #[test]
+    fn test_evaluate_math_undefined() {
+        let ss = empty_ss!();
+        let err = evaluate_math("sqrt", &vec![SInteger(-4)], &ss).unwrap_err();
+        assert_eq!("evaluate_math() - Undefined result: sqrt(-4)", err);
+        assert!(evaluate_math("log", &vec![SInteger(0)], &ss).is_err());
+    }
+
+    // Integer results which do not fit in an i64 are errors, or
+    // with the bigint feature, big integers.
+    #[test]
+    fn test_evaluate_math_overflow() {
+
+        let ss = empty_ss!();
+        let min = SInteger(i64::MIN);
+
+        assert_eq!(SInteger(i64::MIN), eval("ceiling", SRational(i64::MIN, 1)));
+        assert_eq!(SInteger(i64::MIN), eval("round", SRational(i64::MIN, 1)));
+        assert_eq!(SInteger(i64::MIN), eval("floor", SFloat(-9223372036854775808.0)));
+        let err = evaluate_math("abs", &vec![SRational(i64::MIN, 3)], &ss).unwrap_err();
+        assert_eq!("evaluate_math() - Integer overflow: abs(-9223372036854775808r3)", err);
+        assert!(evaluate_math("round", &vec![SFloat(f64::NAN)], &ss).is_err());
+
+        #[cfg(not(feature = "bigint"))]
+        {
+            let err = evaluate_math("abs", &vec![min.clone()], &ss).unwrap_err();
+            assert_eq!("evaluate_math() - Integer overflow: abs(-9223372036854775808)", err);
+            for name in ["truncate", "round", "floor", "ceiling"] {
+                assert!(evaluate_math(name, &vec![SFloat(1.0e19)], &ss).is_err());
+                assert!(evaluate_math(name, &vec![SFloat(-1.0e19)], &ss).is_err());
+            }
+        }
+
+        #[cfg(feature = "bigint")]
+        {
+            let result = evaluate_math("abs", &vec![min.clone()], &ss).unwrap();
+            assert_eq!("9223372036854775808", result.to_string());
+            let result = evaluate_math("round", &vec![SFloat(1.0e19)], &ss).unwrap();
+            assert_eq!("10000000000000000000", result.to_string());
+            assert!(evaluate_math("floor", &vec![SFloat(f64::INFINITY)], &ss).is_err());
+        }
+
+    } // test_evaluate_math_overflow()
+
+} // test
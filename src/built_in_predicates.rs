@@ -14,6 +14,19 @@ use super::unifiable::*;
 use super::solution_node::*;
 use super::built_in_print::*;
 use super::built_in_count::*;
+use super::built_in_case::*;
+use super::built_in_split_string::*;
+use super::built_in_string::*;
+use super::built_in_format::*;
+use super::built_in_write::*;
+use super::built_in_read::*;
+use super::built_in_stream::*;
+use super::built_in_join::*;
+use super::built_in_is::*;
+use super::built_in_random::*;
+use super::output::*;
+#[cfg(feature = "regex")]
+use super::built_in_regex::*;
 use super::built_in_append::*;
 use super::built_in_filter::*;
 use super::built_in_functor::*;
@@ -28,6 +41,7 @@ use super::substitution_set::*;
 /// functor(term1, term2, ...)
 /// </blockquote>
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BuiltInPredicate {
     pub functor: String,
     pub terms: Option<Vec<Unifiable>>,
@@ -140,9 +154,22 @@ pub fn next_solution_bip<'a>(sn: Rc<RefCell<SolutionNode<'a>>>,
             }
             return None;
         },
+        "unify_with_occurs_check" => {
+            if let Some(terms) = &bip.terms {
+                if terms.len() != 2 { return None; }
+                return terms[0].unify_with_occurs_check(&terms[1], &sn_ref.ss);
+            }
+            return None;
+        },
         "equal" => {
             return bip_equal(bip, &sn_ref.ss);
         },
+        "arithmetic_equal" => {
+            return bip_arithmetic_equal(bip, &sn_ref.ss);
+        },
+        "arithmetic_not_equal" => {
+            return bip_arithmetic_not_equal(bip, &sn_ref.ss);
+        },
         "less_than" => {
             return bip_less_than(bip, &sn_ref.ss);
         },
@@ -156,7 +183,7 @@ pub fn next_solution_bip<'a>(sn: Rc<RefCell<SolutionNode<'a>>>,
             return bip_greater_than_or_equal(bip, &sn_ref.ss);
         },
         "nl" => { // New Line. This cannot fail.
-            print!("\n");
+            write_output("\n");
             return Some(Rc::clone(&sn_ref.ss));
         },
         "!" => { // !
@@ -166,9 +193,79 @@ pub fn next_solution_bip<'a>(sn: Rc<RefCell<SolutionNode<'a>>>,
         "count" => { // count terms in list
             return bip_count(bip, &sn_ref.ss);
         },
+        "upcase_atom" => {
+            return bip_upcase_atom(bip, &sn_ref.ss);
+        },
+        "downcase_atom" => {
+            return bip_downcase_atom(bip, &sn_ref.ss);
+        },
+        "split_string" => {
+            return bip_split_string(bip, &sn_ref.ss);
+        },
+        "atom_string" => {
+            return bip_atom_string(bip, &sn_ref.ss);
+        },
+        "number_string" => {
+            return bip_number_string(bip, &sn_ref.ss);
+        },
+        "char_code" => {
+            return bip_char_code(bip, &sn_ref.ss);
+        },
+        "is" => {
+            return bip_is(bip, &sn_ref.ss);
+        },
+        "join" => {
+            return bip_join(bip, &sn_ref.ss);
+        },
+        "term_to_atom" => {
+            return bip_term_to_atom(bip, &sn_ref.ss, &sn_ref.var_ids);
+        },
+        "format" => {
+            return bip_format(bip, &sn_ref.ss);
+        },
+        "format_atom" => {
+            return bip_format_atom(bip, &sn_ref.ss);
+        },
+        "format_number" => {
+            return bip_format_number(bip, &sn_ref.ss);
+        },
+        "tab" => {
+            return bip_tab(bip, &sn_ref.ss);
+        },
+        "write_canonical" => {
+            return bip_write_canonical(bip, &sn_ref.ss);
+        },
+        "read" | "read_term" => {
+            return bip_read(bip, &sn_ref.ss, &sn_ref.var_ids);
+        },
+        "open" => {
+            return bip_open(bip, &sn_ref.ss);
+        },
+        "close" => {
+            return bip_close(bip, &sn_ref.ss);
+        },
+        "read_line" => {
+            return bip_read_line(bip, &sn_ref.ss);
+        },
+        "write" => {
+            return bip_write(bip, &sn_ref.ss);
+        },
+        "random" => {
+            return bip_random(bip, &sn_ref.ss);
+        },
+        "random_between" => {
+            return bip_random_between(bip, &sn_ref.ss);
+        },
+        "set_random_seed" => {
+            return bip_set_random_seed(bip, &sn_ref.ss);
+        },
+        #[cfg(feature = "regex")]
+        "regex_match" => {
+            return bip_regex_match(bip, &sn_ref.ss);
+        },
         "fail" => { return None; }, // always fails
-        _ => { panic!("next_solution_bip() - Not implemented yet: {}",
-                       bip.functor.as_str()); },
+        // An unknown predicate fails, like a predicate without rules.
+        _ => { return None; },
     }
 } // next_solution_bip()
 
@@ -215,6 +312,18 @@ impl fmt::Display for BuiltInPredicate {
                         let (left, right) = (&terms[0], &terms[1]);
                         return write!(f, "{} = {}", left, right);
                     },
+                    "is" if terms.len() == 2 => {
+                        let (left, right) = (&terms[0], &terms[1]);
+                        return write!(f, "{} is {}", left, right);
+                    },
+                    "arithmetic_equal" if terms.len() == 2 => {
+                        let (left, right) = (&terms[0], &terms[1]);
+                        return write!(f, "{} =:= {}", left, right);
+                    },
+                    "arithmetic_not_equal" if terms.len() == 2 => {
+                        let (left, right) = (&terms[0], &terms[1]);
+                        return write!(f, "{} =\\= {}", left, right);
+                    },
                     _ => {
                         let out = format_built_in(func, terms);
                         return write!(f, "{}", out);
@@ -261,4 +370,27 @@ mod test {
         assert_eq!("$X = Klivo", format!("{}", unify_pred));
     }
 
+    // A built-in predicate with the wrong number of arguments fails.
+    #[test]
+    fn test_wrong_arity() {
+
+        let mut kb = KnowledgeBase::new();
+        let rules = ["a($X) :- count($X).", "b($X) :- upcase_atom($X).",
+                     "c($X) :- $X is 1, join($X).", "d :- tab(1, 2).",
+                     "e($X) :- random_between(1, $X).", "f :- 1 =:= 1 =:= 1."];
+        for r in rules {
+            add_rules!(&mut kb, parse_rule(r).unwrap());
+        }
+        for q in ["a([1])", "b(abc)", "c($X)", "d", "e(5)", "f"] {
+            let query = parse_query(q).unwrap();
+            let sn = make_base_node(std::rc::Rc::new(query), &kb);
+            assert_eq!("No more.", solve(sn), "{} should fail.", q);
+        }
+
+        let functor = "no_such_predicate".to_string();
+        let bip = BuiltInPredicate::new(functor, Some(two_terms()));
+        let sn = make_base_node(std::rc::Rc::new(Goal::BuiltInGoal(bip)), &kb);
+        assert_eq!("No more.", solve(sn));
+    }
+
 } // test
\ No newline at end of file
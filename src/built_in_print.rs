@@ -2,8 +2,10 @@
 
 use std::rc::Rc;
 
+use super::unifiable::*;
 use super::substitution_set::*;
 use super::built_in_predicates::*;
+use super::output::*;
 
 static FORMAT_SPECIFIER: &str = "%s";
 
@@ -41,11 +43,13 @@ pub fn next_solution_print<'a>(bip: BuiltInPredicate,
         // Collect ground terms into v.
         for term in terms {
             match get_ground_term(&term, &ss) {
+                // Strings are printed without quotation marks.
+                Some(Unifiable::SString(s)) => { v.push(s.to_string()); },
                 Some(ground_term) => { v.push(format!("{}", ground_term)); },
                 None              => { v.push(format!("{}", term)); },
             }
         }
-        print!("{}", format_for_print_pred(&v));
+        write_output(&format_for_print_pred(&v));
     }
 } // next_solution_print()
 
@@ -81,9 +85,7 @@ pub fn next_solution_print<'a>(bip: BuiltInPredicate,
 /// // Prints: Hello, Dave. You're looking well today.
 /// ```
 pub fn format_for_print_pred(the_strings: &Vec<String>) -> String {
-    if the_strings.len() == 0 {
-        panic!("format_for_print_pred() - No strings to format.");
-    }
+    if the_strings.len() == 0 { return "".to_string(); }
     let format_string = the_strings[0].to_string();
     let split: Vec<_> = format_string.split(FORMAT_SPECIFIER).collect();
     let mut out: String = split[0].to_string();
@@ -1,9 +1,34 @@
 //! Support functions for the built-in predicate print_list().
+//!
+//! The output of print_list() can be controlled by a list of options,
+//! given as the last argument:
+//!
+//! <pre>
+//!     print_list($L, [separator(" | "), prefix("["), suffix("]")])
+//! </pre>
+//!
+//! The options are:
+//!
+//! <pre>
+//! separator(S)   separator between items (default ", ")
+//! prefix(P)      text written before the list
+//! suffix(S)      text written after the list
+//! max_width(N)   start a new line when a line would exceed N characters
+//! one_per_line   write each item on its own line
+//! pretty         write nested lists and complex terms on several lines,
+//!                with indentation
+//! indent(N)      indentation for pretty printing (default 4)
+//! </pre>
+//!
+//! The last argument is treated as options only if every item in it
+//! is a valid option.
 
 use std::rc::Rc;
 
 use super::substitution_set::*;
 use super::built_in_predicates::*;
+use super::output::*;
+use super::s_linked_list::*;
 use super::unifiable::Unifiable;
 
 /// Prints out terms of an SLinkedList for the predicate print_list().
@@ -36,10 +61,22 @@ use super::unifiable::Unifiable;
 /// ```
 pub fn next_solution_print_list<'a>(bip: BuiltInPredicate,
                                     ss: &'a Rc<SubstitutionSet<'a>>) {
-    if let Some(terms) = bip.terms {
+    if let Some(mut terms) = bip.terms {
 
         if terms.len() == 0 { return; };
 
+        // Are there formatting options?
+        if terms.len() > 1 {
+            if let Some(options) = get_options(&terms[terms.len() - 1], ss) {
+                terms.pop();
+                for term in terms {
+                    let term = term.replace_variables(ss);
+                    write_output(&format_with_options(&term, &options));
+                }
+                return;
+            }
+        }
+
         // Iterate through arguments
         let mut first = true;
         for term in terms {
@@ -59,14 +96,14 @@ pub fn next_solution_print_list<'a>(bip: BuiltInPredicate,
             // If argument is an SLinkedList
             if let Unifiable::SLinkedList{term: _, next: _,
                               tail_var: _, count: _} = term {
-                if !first { print!(",\n"); }
+                if !first { write_output(",\n"); }
                 if let Some(term) = get_ground_term(&term, &ss) {
                     let s = format_slist(term, &ss);
-                    println!("{}", s);
+                    write_output(&format!("{}\n", s));
                 }
-                else { println!("{}", term); }
+                else { write_output(&format!("{}\n", term)); }
             }
-            else { println!("{}", term); } // Not a list.
+            else { write_output(&format!("{}\n", term)); } // Not a list.
             first = false;
         } // for...
     };
@@ -147,12 +184,205 @@ pub fn format_slist<'a>(the_list: &'a Unifiable,
 
 } // format_slist()
 
+/// Formatting options for print_list().
+///
+/// See the [module documentation](../built_in_print_list/index.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintListOptions {
+    pub separator: String,
+    pub prefix: String,
+    pub suffix: String,
+    pub max_width: Option<usize>,
+    pub one_per_line: bool,
+    pub pretty: bool,
+    pub indent: usize,
+}
+
+impl Default for PrintListOptions {
+    fn default() -> Self {
+        PrintListOptions {
+            separator: ", ".to_string(),
+            prefix: "".to_string(),
+            suffix: "".to_string(),
+            max_width: None,
+            one_per_line: false,
+            pretty: false,
+            indent: 4,
+        }
+    }
+}
+
+/// Formats a term for print_list(), according to the given options.
+///
+/// If the term is a list, its items are joined by the separator, and
+/// enclosed by the prefix and suffix. Otherwise, the term is written
+/// by itself. The output ends with a new line.
+///
+/// The term should not contain bound logic variables. (Call
+/// [replace_variables()](../unifiable/enum.Unifiable.html#method.replace_variables)
+/// first.)
+///
+/// # Arguments
+/// * [Unifiable](../unifiable/enum.Unifiable.html) term
+/// * [PrintListOptions](../built_in_print_list/struct.PrintListOptions.html)
+/// # Return
+/// * formatted text
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let list = parse_term("[a, b, c]").unwrap();
+/// let options = PrintListOptions{ separator: " | ".to_string(),
+///                                 ..PrintListOptions::default() };
+/// print!("{}", format_with_options(&list, &options));
+/// // Prints: a | b | c
+/// ```
+pub fn format_with_options(term: &Unifiable, options: &PrintListOptions) -> String {
+
+    let items = match term {
+        Unifiable::SLinkedList{term: _, next: _, tail_var: _, count: _} => {
+            list_items(term)
+        },
+        _ => {
+            return format!("{}\n", format_item(term, options, 0));
+        },
+    };
+
+    let n = items.len();
+    let mut lines: Vec<String> = vec![];
+    let mut line = options.prefix.to_string();
+
+    for (i, item) in items.iter().enumerate() {
+
+        let mut text = format_item(item, options, 0);
+        if i + 1 < n {
+            if options.one_per_line || text.contains('\n') {
+                text += options.separator.trim_end();
+            }
+            else { text += &options.separator; }
+        }
+
+        if let Some(max) = options.max_width {
+            let length = line.chars().count() + text.chars().count();
+            if length > max && line.trim().len() > 0 {
+                lines.push(line.trim_end().to_string());
+                line = "".to_string();
+            }
+        }
+        line += &text;
+
+        if (options.one_per_line || text.contains('\n')) && i + 1 < n {
+            lines.push(line);
+            line = "".to_string();
+        }
+    }
+
+    line += &options.suffix;
+    lines.push(line);
+    return lines.join("\n") + "\n";
+
+} // format_with_options()
+
+// Formats an item of a list. If the pretty option is set, lists and
+// complex terms which contain other lists or complex terms are written
+// on several lines, with indentation.
+// Arguments:
+//   term    - item to format
+//   options - print list options
+//   depth   - level of nesting
+// Return:
+//   formatted item
+fn format_item(term: &Unifiable, options: &PrintListOptions, depth: usize) -> String {
+
+    if !options.pretty { return term.to_string(); }
+
+    let (open, close, args) = match term {
+        Unifiable::SComplex(terms) => {
+            (format!("{}(", terms[0]), ")", terms[1..].to_vec())
+        },
+        Unifiable::SLinkedList{term: _, next: _, tail_var: _, count: _} => {
+            ("[".to_string(), "]", list_items(term))
+        },
+        _ => { return term.to_string(); },
+    };
+
+    let nested = args.iter().any(|a| matches!(a,
+                     Unifiable::SComplex(_) |
+                     Unifiable::SLinkedList{term: _, next: _, tail_var: _, count: _}));
+    if !nested || args.len() == 0 { return term.to_string(); }
+
+    let inner = " ".repeat(options.indent * (depth + 1));
+    let outer = " ".repeat(options.indent * depth);
+    let args: Vec<String> = args.iter()
+                                .map(|a| format!("{}{}", inner,
+                                                 format_item(a, options, depth + 1)))
+                                .collect();
+    return format!("{}\n{}\n{}{}", open, args.join(",\n"), outer, close);
+
+} // format_item()
+
+// Gets the items of a Suiron list. A tail variable is included as an item.
+// Arguments:
+//   list - SLinkedList
+// Return:
+//   vector of items
+fn list_items(list: &Unifiable) -> Vec<Unifiable> {
+    let mut items: Vec<Unifiable> = vec![];
+    let mut t = list;
+    while let Unifiable::SLinkedList{term, next, tail_var: _, count: _} = t {
+        if **term == Unifiable::Nil { break; }
+        items.push((**term).clone());
+        t = next;
+    }
+    return items;
+} // list_items()
+
+// Gets print_list() options from a list of option terms.
+// Arguments:
+//   term - list of options
+//   ss   - substitution set
+// Return:
+//   print list options, or None if the term is not a list of options
+fn get_options(term: &Unifiable, ss: &Rc<SubstitutionSet>) -> Option<PrintListOptions> {
+
+    get_list(term, ss)?;
+    let items = get_terms(term, ss);
+    if items.len() == 0 { return None; }
+
+    let mut options = PrintListOptions::default();
+    for item in items {
+        match item.replace_variables(ss) {
+            Unifiable::Atom(a) if a == "one_per_line" => { options.one_per_line = true; },
+            Unifiable::Atom(a) if a == "pretty" => { options.pretty = true; },
+            Unifiable::SComplex(args) if args.len() == 2 => {
+                let name = args[0].to_string();
+                let value = &args[1];
+                match (name.as_str(), value) {
+                    ("separator", _) => { options.separator = get_text(value, ss)?; },
+                    ("prefix", _) => { options.prefix = get_text(value, ss)?; },
+                    ("suffix", _) => { options.suffix = get_text(value, ss)?; },
+                    ("max_width", Unifiable::SInteger(n)) if *n > 0 => {
+                        options.max_width = Some(*n as usize);
+                    },
+                    ("indent", Unifiable::SInteger(n)) if *n >= 0 => {
+                        options.indent = *n as usize;
+                    },
+                    _ => { return None; },
+                }
+            },
+            _ => { return None; },
+        }
+    }
+    return Some(options);
+
+} // get_options()
+
 #[cfg(test)]
 mod test {
 
+    use std::rc::Rc;
     use crate::*;
     use super::*;
-    use serial_test::serial;
 
     // Test format_slist() function.
     #[test]
@@ -165,10 +395,8 @@ mod test {
 
     // Test next_solution_print_list() function.
     #[test]
-    #[serial]
     fn test_next_solution_print_list() {
 
-        clear_id();  // Clear logic variable ID.
         let mut kb = KnowledgeBase::new();
         let rule = parse_rule("test :- $X = Δ, $L = [Α, Β, Γ, $X], \
                                print_list($L), nl.").unwrap();
@@ -192,4 +420,39 @@ mod test {
 
     } // test_next_solution_print_list()
 
-} // test
\ No newline at end of file
+    // Test print_list() with formatting options.
+    #[test]
+    fn test_print_list_options() {
+
+        let mut kb = KnowledgeBase::new();
+
+        let rules = ["bars :- print_list([a, b, c], [separator(\" | \"), \
+                      prefix(\"<\"), suffix(\">\")]).",
+                     "lines :- print_list([a, b], [one_per_line]).",
+                     "narrow :- print_list([alpha, beta, gamma, delta], [max_width(14)]).",
+                     "pretty :- print_list([p(1), q(r(2), [3, s(4)])], \
+                      [pretty, indent(2), one_per_line]).",
+                     "plain :- print_list([a], [b])."];
+
+        for r in rules {
+            match parse_rule(r) {
+                Ok(rule) => { add_rules!(&mut kb, rule); },
+                Err(err) => { panic!("Should be no parsing errors. {}", err); },
+            }
+        }
+
+        let run = |q: &str| {
+            let query = parse_query(q).unwrap();
+            with_captured_output(|| solve(make_base_node(Rc::new(query), &kb))).0
+        };
+
+        assert_eq!("<a | b | c>\n", run("bars"));
+        assert_eq!("a,\nb\n", run("lines"));
+        assert_eq!("alpha, beta,\ngamma, delta\n", run("narrow"));
+        assert_eq!("p(1),\nq(\n  r(2),\n  [\n    3,\n    s(4)\n  ]\n)\n", run("pretty"));
+        // [b] is not a list of options.
+        assert_eq!("a\n,\nb\n", run("plain"));
+
+    } // test_print_list_options()
+
+} // test
@@ -0,0 +1,290 @@
+//! Functions to support the built-in predicates random() and random_between().
+//!
+//! The pseudo-random numbers are generated by a small, fast generator
+//! (SplitMix64). The generator is local to the thread. By default, it is
+//! seeded from the system clock. To make a test reproducible, call
+//! set_random_seed() from Rust, give the query a seed in its
+//! [QueryConfig](../query_config/struct.QueryConfig.html), or call
+//! set_random_seed() in a rule:
+//!
+//! <pre>
+//!   roll($D) :- set_random_seed(42), random_between(1, 6, $D).
+//! </pre>
+//
+// Cleve Lendon  2023
+
+use std::rc::Rc;
+use std::cell::Cell;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::substitution_set::*;
+use super::built_in_predicates::*;
+use super::unifiable::Unifiable::*;
+
+thread_local! {
+    // State of the generator. None means not yet seeded.
+    static RANDOM_STATE: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Sets the seed of the random number generator.
+///
+/// The same seed produces the same sequence of random numbers.
+///
+/// # Arguments
+/// * seed
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// set_random_seed(7);
+/// let a = random_float();
+/// set_random_seed(7);
+/// assert_eq!(a, random_float());
+/// ```
+pub fn set_random_seed(seed: u64) {
+    RANDOM_STATE.with(|state| state.set(Some(seed)));
+} // set_random_seed()
+
+// Gets the state of the generator. (For QueryConfig.)
+pub(crate) fn random_state() -> Option<u64> {
+    RANDOM_STATE.with(|state| state.get())
+}
+
+// Sets the state of the generator. None means not yet seeded.
+pub(crate) fn set_random_state(seed: Option<u64>) {
+    RANDOM_STATE.with(|state| state.set(seed));
+}
+
+/// Generates a random floating point number, from 0.0 up to (but not including) 1.0.
+///
+/// # Return
+/// * random number
+pub fn random_float() -> f64 {
+    // Use the top 53 bits, the precision of an f64.
+    return (next_random() >> 11) as f64 / (1u64 << 53) as f64;
+} // random_float()
+
+/// Generates a random integer between two limits (inclusive).
+///
+/// # Arguments
+/// * low limit
+/// * high limit
+/// # Return
+/// * random integer, or None if low is greater than high
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let n = random_integer(1, 6).unwrap();
+/// assert!(n >= 1 && n <= 6);
+/// ```
+pub fn random_integer(low: i64, high: i64) -> Option<i64> {
+    if low > high { return None; }
+    let range = (high as i128 - low as i128 + 1) as u128;
+    // Scale a 64 bit random number to the range, without modulo bias.
+    let offset = (next_random() as u128 * range) >> 64;
+    return Some((low as i128 + offset as i128) as i64);
+} // random_integer()
+
+/// Generates a random float between 0.0 and 1.0.
+///
+/// In Suiron source code, the random() predicate requires one argument:
+/// <pre>
+///     random($X)
+/// </pre>
+///
+/// $X is unified with a floating point number from 0.0 up to,
+/// but not including, 1.0.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+/// # Note
+/// * If the number of arguments is not 1, the predicate fails.
+///
+pub fn bip_random<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                      -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+        if terms.len() != 1 { return None; }
+        return terms[0].unify(&SFloat(random_float()), ss);
+    }
+    return None;
+
+} // bip_random()
+
+/// Generates a random integer between two limits.
+///
+/// In Suiron source code, the random_between() predicate requires three arguments:
+/// <pre>
+///     random_between(Low, High, $X)
+/// </pre>
+///
+/// $X is unified with an integer from Low to High, inclusive.
+/// If Low or High is not an integer, or Low is greater than High,
+/// the predicate fails.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+/// # Note
+/// * If the number of arguments is not 3, the predicate fails.
+///
+pub fn bip_random_between<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                              -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 3 { return None; }
+
+        let low = match get_constant(&terms[0], ss)? {
+            SInteger(i) => { *i },
+            _ => { return None; },
+        };
+        let high = match get_constant(&terms[1], ss)? {
+            SInteger(i) => { *i },
+            _ => { return None; },
+        };
+
+        let n = random_integer(low, high)?;
+        return terms[2].unify(&SInteger(n), ss);
+    }
+    return None;
+
+} // bip_random_between()
+
+/// Sets the seed of the random number generator.
+///
+/// In Suiron source code, the set_random_seed() predicate requires one argument,
+/// an integer:
+/// <pre>
+///     set_random_seed(42)
+/// </pre>
+///
+/// If the argument is not an integer, the predicate fails.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+/// # Note
+/// * If the number of arguments is not 1, the predicate fails.
+///
+pub fn bip_set_random_seed<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                               -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+        if terms.len() != 1 { return None; }
+        if let SInteger(seed) = get_constant(&terms[0], ss)? {
+            set_random_seed(*seed as u64);
+            return Some(Rc::clone(ss));
+        }
+        return None;
+    }
+    return None;
+
+} // bip_set_random_seed()
+
+// Generates the next 64 bit random number (SplitMix64).
+// If the generator has not been seeded, the system clock is used.
+// Return
+//    random number
+fn next_random() -> u64 {
+    RANDOM_STATE.with(|state| {
+        let seed = match state.get() {
+            Some(seed) => { seed },
+            None => {
+                match SystemTime::now().duration_since(UNIX_EPOCH) {
+                    Ok(d) => { d.as_nanos() as u64 },
+                    Err(_) => { 0 },
+                }
+            },
+        };
+        let next = seed.wrapping_add(0x9E3779B97F4A7C15);
+        state.set(Some(next));
+        let mut z = next;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        return z ^ (z >> 31);
+    })
+} // next_random()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    #[test]
+    fn test_random_numbers() {
+
+        set_random_seed(12345);
+        let first: Vec<i64> = (0..20).map(|_| random_integer(1, 6).unwrap()).collect();
+        set_random_seed(12345);
+        let second: Vec<i64> = (0..20).map(|_| random_integer(1, 6).unwrap()).collect();
+        assert_eq!(first, second, "Same seed, same sequence.");
+
+        for n in first { assert!((1..=6).contains(&n)); }
+
+        for _ in 0..100 {
+            let f = random_float();
+            assert!((0.0..1.0).contains(&f));
+        }
+
+        assert_eq!(Some(5), random_integer(5, 5));
+        assert_eq!(None, random_integer(6, 5));
+        assert!(random_integer(i64::MIN, i64::MAX).is_some());
+
+    } // test_random_numbers()
+
+    #[test]
+    fn test_bip_random() {
+
+        let mut kb = KnowledgeBase::new();
+
+        let rules = ["roll($A, $B) :- set_random_seed(42), random_between(1, 6, $A), \
+                      set_random_seed(42), random_between(1, 6, $B).",
+                     "fraction($X) :- random($X), $X >= 0.0, $X < 1.0.",
+                     "bad($X) :- random_between(6, 1, $X)."];
+
+        for r in rules {
+            match parse_rule(r) {
+                Ok(rule) => { add_rules!(&mut kb, rule); },
+                Err(err) => { panic!("Should be no parsing errors. {}", err); },
+            }
+        }
+
+        // Same seed, same result.
+        let query = parse_query("roll($A, $A)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert!(solve(sn).starts_with("$A = "));
+
+        let query = parse_query("fraction($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert!(solve(sn).starts_with("$X = 0."));
+
+        let query = parse_query("bad($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("No more.", solve(sn));
+
+    } // test_bip_random()
+
+} // test
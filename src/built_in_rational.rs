@@ -0,0 +1,378 @@
+//! Functions to support rational numbers.
+//!
+//! A rational number is an exact fraction, such as one third.
+//! In Suiron source code, it is written with an 'r' between the
+//! numerator and the denominator: `1r3`
+//!
+//! Division of integers normally truncates (7 / 2 => 3). To keep the
+//! result exact, use the rdiv function, or its infix form:
+//!
+//! <pre>
+//!   $X = 1 rdiv 3, $Y is $X + 1r6   % $Y = 1r2
+//! </pre>
+//!
+//! Rational numbers can be added, subtracted, multiplied and divided
+//! with integers and other rational numbers, and the result remains exact.
+//! If a rational number is mixed with a float, the result is a float.
+//! A rational number whose denominator reduces to 1 becomes an integer.
+//
+// Cleve Lendon  2023
+
+use std::rc::Rc;
+use std::cmp::Ordering;
+use super::substitution_set::*;
+use super::unifiable::Unifiable;
+use super::built_in_arithmetic::*;
+
+// A fraction: (numerator, denominator). Calculations are done
+// with 128 bit integers, to avoid overflow of intermediate results.
+type Fraction = (i128, i128);
+
+/// Creates a rational number from a numerator and a denominator.
+///
+/// The fraction is reduced, and the sign is moved to the numerator.
+/// If the denominator reduces to 1, the function returns an SInteger.
+///
+/// # Arguments
+/// * numerator
+/// * denominator
+/// # Return
+/// * [SRational](../unifiable/enum.Unifiable.html#variant.SRational),
+///   [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger)
+///   or error message
+/// # Errors
+/// * If the denominator is 0.
+/// * If the reduced numerator or denominator does not fit in an i64.
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let r = make_rational(4, -6).unwrap();
+/// println!("{}", r);  // Prints: -2r3
+/// ```
+pub fn make_rational(numerator: i128, denominator: i128) -> Result<Unifiable, String> {
+
+    if denominator == 0 {
+        return Err("make_rational() - Division by zero.".to_string());
+    }
+
+    let g = gcd(numerator, denominator);
+    let mut n = numerator / g;
+    let mut d = denominator / g;
+    if d < 0 { n = -n; d = -d; }
+
+    let n = match i64::try_from(n) {
+        Ok(n) => { n },
+        Err(_) => { return Err("make_rational() - Integer overflow.".to_string()); },
+    };
+    if d == 1 { return Ok(Unifiable::SInteger(n)); }
+
+    match i64::try_from(d) {
+        Ok(d) => { return Ok(Unifiable::SRational(n, d)); },
+        Err(_) => { return Err("make_rational() - Integer overflow.".to_string()); },
+    }
+
+} // make_rational()
+
+/// Parses a rational number, such as 2r3.
+///
+/// # Arguments
+/// * string to parse
+/// # Return
+/// * [SRational](../unifiable/enum.Unifiable.html#variant.SRational),
+///   [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger)
+///   or None, if the string is not a rational number
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let r = parse_rational("-6r8").unwrap();
+/// println!("{}", r);  // Prints: -3r4
+/// ```
+pub fn parse_rational(to_parse: &str) -> Option<Unifiable> {
+    let (n, d) = to_parse.split_once('r')?;
+    if d.starts_with(['-', '+']) { return None; }
+    let n = n.parse::<i64>().ok()?;
+    let d = d.parse::<i64>().ok()?;
+    return make_rational(n as i128, d as i128).ok();
+} // parse_rational()
+
+/// Divides two numbers, keeping the result exact.
+///
+/// If both arguments are integers or rational numbers, the result is
+/// a rational number (or an integer, if the division is exact).
+/// If either argument is a float, the result is a float.
+///
+/// In Suiron source code, rdiv can also be written as an infix: `1 rdiv 3`
+///
+/// This method is called by
+/// [unify_sfunction()](../built_in_functions/fn.unify_sfunction.html#).
+///
+/// # Arguments
+/// * list of two [Unifiable](../unifiable/enum.Unifiable.html) terms
+/// * [SubstitutionSet](../substitution_set/index.html)
+/// # Returns
+/// * [SRational](../unifiable/enum.Unifiable.html#variant.SRational),
+///   [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger),
+///   [SFloat](../unifiable/enum.Unifiable.html#variant.SFloat)
+///   or error message
+/// # Errors
+/// * If there are not two arguments.
+/// * If an argument is not grounded, or not a number.
+/// * If the divisor is 0.
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// let arguments = parse_arguments("2, 6").unwrap();
+/// let result = evaluate_rdiv(&arguments, &ss).unwrap();
+/// println!("{}", result);
+/// // Prints: 1r3
+/// ```
+pub fn evaluate_rdiv<'a>(arguments: &Vec<Unifiable>,
+                    ss: &'a Rc<SubstitutionSet<'a>>) -> Result<Unifiable, String> {
+
+    if arguments.len() != 2 {
+        return Err("evaluate_rdiv() - Requires 2 arguments.".to_string());
+    }
+
+    let first  = evaluate_expression(&arguments[0], ss)?;
+    let second = evaluate_expression(&arguments[1], ss)?;
+
+    match (to_fraction(&first), to_fraction(&second)) {
+        (Some(a), Some(b)) => { return divide_fractions(a, b); },
+        _ => { return evaluate_divide(&vec![first, second], ss); },
+    }
+
+} // evaluate_rdiv()
+
+/// Determines whether a list of numbers contains a rational number.
+///
+/// # Argument
+/// * vector of numbers - (SNumber)
+/// # Return
+/// * true or false
+pub fn has_rational(numbers: &Vec<SNumber>) -> bool {
+    return numbers.iter().any(|n| matches!(n, SNumber::SRational(_, _)));
+} // has_rational()
+
+/// Does arithmetic on a list of integers and rational numbers.
+///
+/// This function is called by the arithmetic functions (add, subtract,
+/// multiply, divide, min, max) when one of the arguments is rational.
+///
+/// # Arguments
+/// * name of function
+/// * vector of numbers - (SNumber)
+/// # Return
+/// * [SRational](../unifiable/enum.Unifiable.html#variant.SRational),
+///   [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger)
+///   or error message
+/// # Errors
+/// * If a number is not an integer or rational number.
+/// * On division by zero or overflow.
+pub fn rational_arithmetic(name: &str, numbers: &Vec<SNumber>)
+                           -> Result<Unifiable, String> {
+
+    let mut fractions: Vec<Fraction> = vec![];
+    for n in numbers {
+        match n {
+            SNumber::SInteger(i) => { fractions.push((*i as i128, 1)); },
+            SNumber::SRational(n, d) => { fractions.push((*n as i128, *d as i128)); },
+            _ => {
+                let err = format!("evaluate_{}() - Invalid rational argument: {:?}", name, n);
+                return Err(err);
+            },
+        }
+    }
+
+    let mut result = fractions[0];
+    for f in &fractions[1..] {
+        result = match name {
+            "add"      => { reduce(result.0 * f.1 + f.0 * result.1, result.1 * f.1)? },
+            "subtract" => { reduce(result.0 * f.1 - f.0 * result.1, result.1 * f.1)? },
+            "multiply" => { reduce(result.0 * f.0, result.1 * f.1)? },
+            "divide"   => {
+                if f.0 == 0 {
+                    return Err("evaluate_divide() - Division by zero.".to_string());
+                }
+                reduce(result.0 * f.1, result.1 * f.0)?
+            },
+            "min" => { if compare(*f, result) == Ordering::Less { *f } else { result } },
+            "max" => { if compare(*f, result) == Ordering::Greater { *f } else { result } },
+            _ => {
+                let err = format!("rational_arithmetic() - Invalid function: {}", name);
+                return Err(err);
+            },
+        };
+    }
+
+    return make_rational(result.0, result.1);
+
+} // rational_arithmetic()
+
+/// Compares two numbers, which are integers or rational numbers.
+///
+/// # Arguments
+/// * first number - (SNumber)
+/// * second number - (SNumber)
+/// # Return
+/// * [Ordering](https://doc.rust-lang.org/std/cmp/enum.Ordering.html)
+pub fn rational_compare(a: &SNumber, b: &SNumber) -> Ordering {
+    let fraction = |n: &SNumber| -> Fraction {
+        match n {
+            SNumber::SInteger(i) => { (*i as i128, 1) },
+            SNumber::SRational(n, d) => { (*n as i128, *d as i128) },
+            _ => { (0, 1) },
+        }
+    };
+    return compare(fraction(a), fraction(b));
+} // rational_compare()
+
+/// Raises a rational number to an integer power.
+///
+/// # Arguments
+/// * numerator
+/// * denominator
+/// * exponent
+/// # Return
+/// * [SRational](../unifiable/enum.Unifiable.html#variant.SRational),
+///   [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger)
+///   or error message
+/// # Errors
+/// * On division by zero or overflow.
+pub fn rational_pow(numerator: i64, denominator: i64, exponent: i64)
+                    -> Result<Unifiable, String> {
+    let mut result: Fraction = (1, 1);
+    for _ in 0..exponent.unsigned_abs() {
+        result = reduce(result.0 * numerator as i128, result.1 * denominator as i128)?;
+    }
+    if exponent < 0 {
+        if result.0 == 0 {
+            return Err("evaluate_pow() - Division by zero.".to_string());
+        }
+        return make_rational(result.1, result.0);
+    }
+    return make_rational(result.0, result.1);
+} // rational_pow()
+
+// Gets the fraction of an integer or a rational number.
+// Arguments
+//    Unifiable term
+// Return
+//    fraction or None
+fn to_fraction(term: &Unifiable) -> Option<Fraction> {
+    match term {
+        Unifiable::SInteger(i) => { return Some((*i as i128, 1)); },
+        Unifiable::SRational(n, d) => { return Some((*n as i128, *d as i128)); },
+        _ => { return None; },
+    }
+} // to_fraction()
+
+// Divides one fraction by another.
+// Arguments
+//    dividend
+//    divisor
+// Return
+//    rational number, integer or error message
+fn divide_fractions(a: Fraction, b: Fraction) -> Result<Unifiable, String> {
+    if b.0 == 0 { return Err("evaluate_rdiv() - Division by zero.".to_string()); }
+    return make_rational(a.0 * b.1, a.1 * b.0);
+} // divide_fractions()
+
+// Reduces a fraction. The result must fit in i64, so that the
+// next calculation cannot overflow.
+// Arguments
+//    numerator
+//    denominator
+// Return
+//    reduced fraction or error message
+fn reduce(numerator: i128, denominator: i128) -> Result<Fraction, String> {
+    let g = gcd(numerator, denominator);
+    let (n, d) = (numerator / g, denominator / g);
+    if i64::try_from(n).is_err() || i64::try_from(d).is_err() {
+        return Err("rational_arithmetic() - Integer overflow.".to_string());
+    }
+    return Ok((n, d));
+} // reduce()
+
+// Compares two fractions. Denominators must be positive.
+fn compare(a: Fraction, b: Fraction) -> Ordering {
+    return (a.0 * b.1).cmp(&(b.0 * a.1));
+}
+
+// Greatest common divisor. Never returns 0.
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 { (a, b) = (b, a % b); }
+    if a == 0 { 1 } else { a }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    #[test]
+    fn test_make_rational() {
+        assert_eq!(SRational(1, 3), make_rational(2, 6).unwrap());
+        assert_eq!(SRational(-2, 3), make_rational(4, -6).unwrap());
+        assert_eq!(SInteger(4), make_rational(8, 2).unwrap());
+        assert!(make_rational(1, 0).is_err());
+        assert_eq!("-2r3", make_rational(-2, 3).unwrap().to_string());
+    }
+
+    #[test]
+    fn test_rational_arithmetic() {
+
+        let ss = empty_ss!();
+
+        let arguments = parse_arguments("1, 3").unwrap();
+        let third = evaluate_rdiv(&arguments, &ss).unwrap();
+        assert_eq!(SRational(1, 3), third);
+
+        // 1r3 + 1r6 = 1r2
+        let arguments = vec![third.clone(), SRational(1, 6)];
+        assert_eq!(SRational(1, 2), evaluate_add(&arguments, &ss).unwrap());
+
+        // 1r3 * 3 = 1
+        let arguments = vec![third.clone(), SInteger(3)];
+        assert_eq!(SInteger(1), evaluate_multiply(&arguments, &ss).unwrap());
+
+        // 1 - 1r3 = 2r3
+        let arguments = vec![SInteger(1), third.clone()];
+        assert_eq!(SRational(2, 3), evaluate_subtract(&arguments, &ss).unwrap());
+
+        // 1r3 / 2 = 1r6
+        let arguments = vec![third.clone(), SInteger(2)];
+        assert_eq!(SRational(1, 6), evaluate_divide(&arguments, &ss).unwrap());
+
+        let arguments = vec![third.clone(), SRational(1, 4), SInteger(1)];
+        assert_eq!(SRational(1, 4), evaluate_min(&arguments, &ss).unwrap());
+        assert_eq!(SInteger(1), evaluate_max(&arguments, &ss).unwrap());
+
+        // (2r3) ** -2 = 9r4
+        let arguments = vec![SRational(2, 3), SInteger(-2)];
+        assert_eq!(SRational(9, 4), evaluate_pow(&arguments, &ss).unwrap());
+
+        // Mixed with a float.
+        let arguments = vec![SRational(1, 4), SFloat(0.5)];
+        assert_eq!(SFloat(0.75), evaluate_add(&arguments, &ss).unwrap());
+
+        // Division by zero.
+        let arguments = vec![third, SInteger(0)];
+        assert!(evaluate_divide(&arguments, &ss).is_err());
+
+        // Parsing
+        assert_eq!(SRational(3, 4), parse_term("6r8").unwrap());
+        let term = parse_term("1 rdiv 3").unwrap();
+        assert_eq!("rdiv(1, 3)", term.to_string());
+        assert_eq!(SRational(1, 3), evaluate_expression(&term, &ss).unwrap());
+
+    } // test_rational_arithmetic()
+
+} // test
@@ -0,0 +1,135 @@
+//! Functions to support the built-in predicate read().
+//!
+//! The read predicate reads a term from standard input. This allows
+//! Suiron programs to carry on simple dialogues with the user:
+//!
+//! <pre>
+//!     ask($Answer) :- print("What is your name? "), read($Answer).
+//! </pre>
+//
+// Cleve Lendon  2023
+
+use std::io::{self, BufRead};
+use std::rc::Rc;
+
+use super::unifiable::*;
+use super::logic_var::*;
+use super::parse_terms::*;
+use super::substitution_set::*;
+use super::built_in_predicates::*;
+use super::output::*;
+
+use crate::atom;
+
+/// Reads a term from standard input.
+///
+/// In Suiron source code, the read() predicate requires one argument.
+/// It can also be written as read_term().
+/// <pre>
+///     read($Term)
+/// </pre>
+///
+/// One line of text is read and parsed as a term. A final period is
+/// optional. The term is unified with the argument. At the end of input,
+/// the argument is unified with the atom `end_of_file`.
+///
+/// If the text cannot be parsed, the predicate fails.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// * [VarIds](../logic_var/struct.VarIds.html) of the query
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+///
+pub fn bip_read<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>,
+                    ids: &VarIds) -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 1 { return None; }
+
+        // Make sure a prompt is displayed before reading.
+        flush_output();
+
+        let stdin = io::stdin();
+        let term = read_term_from(&mut stdin.lock()).ok()?;
+        // Number the variables after those of the query.
+        let term = term.recreate_variables(&mut VarMap::with_ids(ids));
+        return terms[0].unify(&term, ss);
+    }
+    return None;
+
+} // bip_read()
+
+/// Reads a line of text and parses it as a term.
+///
+/// A final period is optional. Logic variables in the term are given
+/// unique IDs. At the end of input, the atom `end_of_file` is returned.
+///
+/// # Arguments
+/// * reader (implements BufRead)
+/// # Return
+/// * [Unifiable](../unifiable/enum.Unifiable.html) term or error message
+/// # Usage
+/// ```
+/// use std::io::Cursor;
+/// use suiron::*;
+///
+/// let mut input = Cursor::new("color(red).\n");
+/// let term = read_term_from(&mut input).unwrap();
+/// println!("{}", term);  // Prints: color(red)
+/// ```
+pub fn read_term_from(reader: &mut impl BufRead) -> Result<Unifiable, String> {
+
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) => { return Ok(atom!("end_of_file")); },
+        Ok(_) => {},
+        Err(err) => { return Err(format!("read_term_from() - {}", err)); },
+    }
+
+    let mut text = line.trim();
+    if let Some(stripped) = text.strip_suffix('.') { text = stripped.trim_end(); }
+
+    let term = parse_term(text)?;
+    let mut vars = VarMap::new();
+    return Ok(term.recreate_variables(&mut vars));
+
+} // read_term_from()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::io::Cursor;
+    use crate::*;
+
+    // Test reading terms from a reader.
+    #[test]
+    fn test_read_term_from() {
+
+        let mut input = Cursor::new("point(1, 2).\n  hello  \n[a, b | $T]\n");
+
+        let term = read_term_from(&mut input).unwrap();
+        assert_eq!("point(1, 2)", term.to_string());
+        let term = read_term_from(&mut input).unwrap();
+        assert_eq!(atom!("hello"), term);
+
+        // Variables must be given unique IDs.
+        let term = read_term_from(&mut input).unwrap();
+        if let SLinkedList{term: _, next: _, count: _, tail_var: _} = &term {
+            assert!(term.to_string().starts_with("[a, b | $T_"));
+        }
+        else { panic!("Should be a list: {}", term); }
+
+        let term = read_term_from(&mut input).unwrap();
+        assert_eq!(atom!("end_of_file"), term);
+
+    } // test_read_term_from()
+
+} // test
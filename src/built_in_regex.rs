@@ -0,0 +1,138 @@
+//! Functions to support the built-in predicate regex_match().
+//!
+//! The regex_match predicate matches an atom against a regular expression,
+//! and extracts capture groups. It is only available when Suiron is built
+//! with the `regex` feature:
+//!
+//! <pre>
+//! cargo build --features regex</pre>
+//
+// Cleve Lendon  2023
+
+use std::rc::Rc;
+
+use regex::Regex;
+
+use super::unifiable::{*, Unifiable::*};
+use super::s_linked_list::*;
+use super::substitution_set::*;
+use super::built_in_predicates::*;
+use super::diagnostics::warn;
+
+/// Matches an atom against a regular expression.
+///
+/// In Suiron source code, the regex_match() predicate requires three arguments.
+/// <pre>
+///     regex_match(Pattern, Text, $Captures)
+/// </pre>
+///
+/// The first argument is a regular expression, as defined by the
+/// [regex](https://docs.rs/regex) crate. The second is the text to match.
+/// If the pattern matches the text, the third argument unifies with a list.
+/// The first item of the list is the matched text. It is followed by the
+/// capture groups, in order. Groups which did not participate in the match
+/// become empty atoms. If the pattern does not match, the predicate fails.
+/// <pre>
+///     regex_match("(\w+)@(\w+)\.com", "mail klivo@yahoo.com", $C)
+///     # $C = [klivo@yahoo.com, klivo, yahoo]
+/// </pre>
+///
+/// The predicate also fails if the pattern or text is not grounded.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+/// # Note
+/// * If the number of arguments is not 3, or if the regular expression
+///   is invalid, the predicate fails. An invalid regular expression is
+///   reported as a warning. See [warn()](../diagnostics/fn.warn.html).
+///
+pub fn bip_regex_match<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                           -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 3 { return None; }
+
+        let pattern = get_text(&terms[0], ss)?;
+        let text = get_text(&terms[1], ss)?;
+
+        let re = match Regex::new(&pattern) {
+            Ok(re) => { re },
+            Err(err) => {
+                warn(&format!("regex_match() - Invalid regular expression: {}\n{}",
+                              pattern, err));
+                return None;
+            },
+        };
+
+        let captures = re.captures(&text)?;
+        let groups: Vec<Unifiable> = captures.iter().map(|group| {
+            match group {
+                Some(m) => { Atom(m.as_str().into()) },
+                None => { Atom("".into()) },
+            }
+        }).collect();
+
+        let list = make_linked_list(false, groups);
+        let out = &terms[2];
+        return out.unify(&list, ss);
+    }
+    return None;
+
+} // bip_regex_match()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    // Test regex_match() predicate.
+    #[test]
+    fn test_regex_match() {
+
+        let mut kb = KnowledgeBase::new();
+
+        let rules = ["email($C) :- regex_match(\"(\\w+)@(\\w+)\\.com\", \
+                                              \"mail klivo@yahoo.com\", $C).",
+                     "optional($C) :- regex_match(\"a(x)?b\", ab, $C).",
+                     "no_match($C) :- regex_match(\"^z\", abc, $C).",
+                     "invalid($C) :- regex_match(\"(\", abc, $C)."];
+
+        for r in rules {
+            match parse_rule(r) {
+                Ok(rule) => { add_rules!(&mut kb, rule); },
+                Err(err) => { panic!("Should be no parsing errors. {}", err); },
+            }
+        }
+
+        let query = parse_query("email($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = [klivo@yahoo.com, klivo, yahoo]", solve(sn));
+
+        let query = parse_query("optional($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = [ab, '']", solve(sn));
+
+        let query = parse_query("no_match($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("No more.", solve(sn));
+
+        // An invalid pattern fails, with a warning.
+        let query = parse_query("invalid($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        let (warnings, result) = with_captured_warnings(|| solve(sn));
+        assert_eq!("No more.", result);
+        assert!(warnings[0].starts_with("regex_match() - Invalid regular expression: ("));
+
+    } // test_regex_match()
+
+} // test
@@ -0,0 +1,170 @@
+//! Functions to support the built-in predicate split_string().
+//!
+//! The split_string predicate divides an atom into substrings,
+//! and returns them as a Suiron list of atoms.
+//
+// Cleve Lendon  2023
+
+use std::rc::Rc;
+
+use crate::str_to_chars;
+
+use super::unifiable::{*, Unifiable::*};
+use super::s_linked_list::*;
+use super::substitution_set::*;
+use super::built_in_predicates::*;
+
+/// Splits an atom into a list of atoms.
+///
+/// In Suiron source code, the split_string() predicate requires four arguments.
+/// <pre>
+///     split_string(Text, SepChars, PadChars, $SubStrings)
+/// </pre>
+///
+/// The first argument is the text to split. It is divided into fields
+/// wherever one of the separator characters (SepChars) occurs. Pad
+/// characters (PadChars) are then trimmed from both ends of each field.
+/// The last argument unifies with a list of the resulting fields.
+///
+/// SepChars and PadChars can be atoms, or lists of characters.
+/// An empty list, [], means no characters. For example:
+/// <pre>
+///     split_string("a, b ,c", ",", " ", $L)  # $L = [a, b, c]
+///     split_string("/home//user", "/", [], $L)  # $L = [, home, , user]
+///     split_string("  padded  ", [], " ", $L)  # $L = [padded]
+/// </pre>
+///
+/// If SepChars is empty, the text is not split. Pad characters are
+/// removed from its ends, and a list of one item is returned.
+///
+/// If the text, or either set of characters, is not grounded, the
+/// predicate fails.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+///
+pub fn bip_split_string<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                            -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 4 { return None; }
+
+        let text = get_text(&terms[0], ss)?;
+        let sep_chars = get_chars(&terms[1], ss)?;
+        let pad_chars = get_chars(&terms[2], ss)?;
+
+        let fields = split_string(&text, &sep_chars, &pad_chars);
+        let atoms: Vec<Unifiable> = fields.into_iter()
+                                          .map(|f| Atom(f.into())).collect();
+        let list = make_linked_list(false, atoms);
+
+        let out = &terms[3];
+        return out.unify(&list, ss);
+    }
+    return None;
+
+} // bip_split_string()
+
+/// Splits a string at separator characters, and trims pad characters.
+///
+/// # Arguments
+/// * string to split
+/// * separator characters
+/// * pad characters
+/// # Return
+/// * vector of fields
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let fields = split_string(" 12, 34 ,56", &[','], &[' ']);
+/// println!("{:?}", fields);  // Prints: ["12", "34", "56"]
+/// ```
+pub fn split_string(text: &str, sep_chars: &[char],
+                    pad_chars: &[char]) -> Vec<String> {
+    let fields: Vec<&str> = if sep_chars.len() == 0 { vec![text] }
+                            else { text.split(sep_chars).collect() };
+    return fields.into_iter()
+                 .map(|f| f.trim_matches(pad_chars).to_string())
+                 .collect();
+} // split_string()
+
+// Gets a set of characters from an atom, or from a list of atoms.
+// Arguments:
+//   term - atom, list, or logic variable bound to one
+//   ss   - substitution set
+// Return:
+//   vector of characters or None
+fn get_chars(term: &Unifiable, ss: &Rc<SubstitutionSet>) -> Option<Vec<char>> {
+    if get_list(term, ss).is_some() {
+        let mut chars: Vec<char> = vec![];
+        for t in get_terms(term, ss) {
+            let text = get_text(&t, ss)?;
+            chars.append(&mut str_to_chars!(text));
+        }
+        return Some(chars);
+    }
+    let text = get_text(term, ss)?;
+    return Some(str_to_chars!(text));
+} // get_chars()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+    use super::*;
+
+    // Test the split_string() function.
+    #[test]
+    fn test_split_string() {
+        let fields = split_string("a, b ,c", &[','], &[' ']);
+        assert_eq!(vec!["a", "b", "c"], fields);
+        let fields = split_string("/home//user", &['/'], &[]);
+        assert_eq!(vec!["", "home", "", "user"], fields);
+        let fields = split_string("  padded  ", &[], &[' ']);
+        assert_eq!(vec!["padded"], fields);
+        let fields = split_string("x=1;y=2", &['=', ';'], &[]);
+        assert_eq!(vec!["x", "1", "y", "2"], fields);
+    }
+
+    // Test split_string() predicate.
+    #[test]
+    fn test_bip_split_string() {
+
+        let mut kb = KnowledgeBase::new();
+
+        fn x() -> Unifiable { logic_var!("$X") }
+
+        let head = scomplex!(atom!("test_split"), x());
+        let body = pred!("split_string", atom!("red, green ,blue"),
+                         atom!(","), atom!(" "), x());
+        let rule1 = make_rule(head, body);
+
+        let head = scomplex!(atom!("test_split2"), x());
+        let seps = slist!(false, atom!("-"), atom!(":"));
+        let body = pred!("split_string", atom!("1-2:3"), seps, slist!(), x());
+        let rule2 = make_rule(head, body);
+
+        add_rules!(&mut kb, rule1, rule2);
+
+        let query = query!(atom!("test_split"), logic_var!("$Y"));
+        let sn = make_base_node(query, &kb);
+        assert_eq!("$Y = [red, green, blue]", solve(sn));
+
+        let query = query!(atom!("test_split2"), logic_var!("$Y"));
+        let sn = make_base_node(query, &kb);
+        assert_eq!("$Y = ['1', '2', '3']", solve(sn));
+
+    } // test_bip_split_string()
+
+} // test
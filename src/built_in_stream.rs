@@ -0,0 +1,383 @@
+//! Functions to support file stream predicates: open(), close(),
+//! read_line() and write().
+//!
+//! These predicates allow Suiron programs to read and write text files.
+//! A file is opened with open(), which binds a stream handle. The handle
+//! is an opaque term, which is passed to the other stream predicates.
+//!
+//! <pre>
+//!     copy_line($In, $Out) :- open($In, read, $S1), read_line($S1, $Line),
+//!                             close($S1), open($Out, write, $S2),
+//!                             write($S2, $Line), close($S2).
+//! </pre>
+//!
+//! The stream names `user_input`, `user_output` and `user_error` refer
+//! to standard input, standard output and standard error.
+//!
+//! Open streams are kept in a table, which is local to the thread.
+//
+// Cleve Lendon  2023
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::rc::Rc;
+
+use super::unifiable::{*, Unifiable::*};
+use super::substitution_set::*;
+use super::built_in_predicates::*;
+use super::output::*;
+
+use crate::atom;
+
+/// Functor of stream handles, eg. $stream(1)
+pub static STREAM_FUNCTOR: &str = "$stream";
+
+// An open file stream.
+enum Stream {
+    Input(BufReader<File>),
+    Output(BufWriter<File>),
+}
+
+thread_local! {
+    static STREAMS: RefCell<HashMap<i64, Stream>> = RefCell::new(HashMap::new());
+    static NEXT_STREAM_ID: Cell<i64> = const { Cell::new(1) };
+}
+
+/// Opens a file stream.
+///
+/// In Suiron source code, the open() predicate requires three arguments.
+/// <pre>
+///     open(FileName, Mode, $Stream)
+/// </pre>
+///
+/// The mode is `read`, `write` or `append`. The last argument is
+/// unified with a handle for the stream. If the mode is invalid, or
+/// the file cannot be opened, the predicate fails.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+///
+pub fn bip_open<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                    -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 3 { return None; }
+
+        let file_name = get_text(&terms[0], ss)?;
+        let mode = get_text(&terms[1], ss)?;
+
+        let stream = match mode.as_str() {
+            "read" => {
+                let file = File::open(&file_name).ok()?;
+                Stream::Input(BufReader::new(file))
+            },
+            "write" | "append" => {
+                let file = OpenOptions::new()
+                               .write(true).create(true)
+                               .append(mode == "append")
+                               .truncate(mode == "write")
+                               .open(&file_name).ok()?;
+                Stream::Output(BufWriter::new(file))
+            },
+            _ => { return None; },
+        };
+
+        let id = NEXT_STREAM_ID.with(|next| {
+            let id = next.get();
+            next.set(id + 1);
+            id
+        });
+        STREAMS.with(|streams| { streams.borrow_mut().insert(id, stream); });
+
+        let handle = SComplex(vec![atom!(STREAM_FUNCTOR), SInteger(id)]);
+        return terms[2].unify(&handle, ss);
+    }
+    return None;
+
+} // bip_open()
+
+/// Closes a file stream.
+///
+/// In Suiron source code, the close() predicate requires one argument.
+/// <pre>
+///     close($Stream)
+/// </pre>
+///
+/// Output is flushed before the file is closed. If the argument is not
+/// an open stream, the predicate fails.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+///
+pub fn bip_close<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                     -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 1 { return None; }
+
+        let id = stream_id(&terms[0], ss)?;
+        let stream = STREAMS.with(|streams| streams.borrow_mut().remove(&id));
+        match stream? {
+            Stream::Output(mut writer) => { let _ = writer.flush(); },
+            Stream::Input(_) => {},
+        }
+        return Some(Rc::clone(ss));
+    }
+    return None;
+
+} // bip_close()
+
+/// Reads a line of text from a stream.
+///
+/// In Suiron source code, the read_line() predicate requires two arguments.
+/// <pre>
+///     read_line($Stream, $Line)
+/// </pre>
+///
+/// The line is unified with the second argument as a string, without
+/// the line terminator. At the end of the file, the second argument is
+/// unified with the atom `end_of_file`. If the stream is not open for
+/// reading, the predicate fails.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+///
+pub fn bip_read_line<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                         -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 2 { return None; }
+
+        let mut line = String::new();
+        let result = if get_text(&terms[0], ss).as_deref() == Some("user_input") {
+            flush_output();
+            io::stdin().lock().read_line(&mut line)
+        }
+        else {
+            let id = stream_id(&terms[0], ss)?;
+            STREAMS.with(|streams| {
+                match streams.borrow_mut().get_mut(&id) {
+                    Some(Stream::Input(reader)) => { Some(reader.read_line(&mut line)) },
+                    _ => { None },
+                }
+            })?
+        };
+
+        let term = match result {
+            Ok(0) => { atom!("end_of_file") },
+            Ok(_) => {
+                if line.ends_with('\n') { line.pop(); }
+                if line.ends_with('\r') { line.pop(); }
+                SString(line)
+            },
+            Err(_) => { return None; },
+        };
+        return terms[1].unify(&term, ss);
+    }
+    return None;
+
+} // bip_read_line()
+
+/// Writes a term to a stream, or to standard output.
+///
+/// In Suiron source code, the write() predicate takes one or two arguments.
+/// <pre>
+///     write($Term)
+///     write($Stream, $Term)
+/// </pre>
+///
+/// Logic variables are replaced by their bindings. Strings are
+/// written without quotation marks. If the stream is not open for
+/// writing, the predicate fails.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+///
+pub fn bip_write<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                     -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        let (stream, term) = match terms.len() {
+            1 => { (atom!("user_output"), &terms[0]) },
+            2 => { (terms[0].clone(), &terms[1]) },
+            _ => { return None; },
+        };
+
+        let text = match term.replace_variables(ss) {
+            SString(s) => { s },
+            other => { other.to_string() },
+        };
+
+        write_to_stream(&stream, &text, ss).ok()?;
+        return Some(Rc::clone(ss));
+    }
+    return None;
+
+} // bip_write()
+
+/// Writes text to a stream.
+///
+/// The stream can be a handle from open(), or one of the names
+/// `user_output` or `user_error`. Text for `user_output` goes to the
+/// output sink set by [set_output()](../output/fn.set_output.html).
+///
+/// # Arguments
+/// * stream - handle or name
+/// * text to write
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * Ok or error message
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = empty_ss!();
+/// write_to_stream(&atom!("user_output"), "Hello\n", &ss).unwrap();
+/// ```
+pub fn write_to_stream(stream: &Unifiable, text: &str,
+                       ss: &SubstitutionSet) -> Result<(), String> {
+
+    match get_text(stream, ss).as_deref() {
+        Some("user_output") => { write_output(text); return Ok(()); },
+        Some("user_error") => {
+            eprint!("{}", text);
+            let _ = io::stderr().flush();
+            return Ok(());
+        },
+        _ => {},
+    }
+
+    let id = match stream_id(stream, ss) {
+        Some(id) => { id },
+        None => { return Err(format!("Unknown stream: {}", stream)); },
+    };
+
+    return STREAMS.with(|streams| {
+        match streams.borrow_mut().get_mut(&id) {
+            Some(Stream::Output(writer)) => {
+                writer.write_all(text.as_bytes()).map_err(|e| e.to_string())
+            },
+            _ => { Err(format!("Stream is not open for writing: {}", stream)) },
+        }
+    });
+
+} // write_to_stream()
+
+// Gets the ID number from a stream handle, eg. $stream(3) ➔ 3
+// Arguments:
+//   term - stream handle, or logic variable bound to one
+//   ss   - substitution set
+// Return:
+//   stream ID or None
+fn stream_id(term: &Unifiable, ss: &SubstitutionSet) -> Option<i64> {
+    if let SComplex(args) = get_complex(term, ss)? {
+        if args.len() == 2 && args[0] == atom!(STREAM_FUNCTOR) {
+            if let SInteger(id) = args[1] { return Some(id); }
+        }
+    }
+    return None;
+} // stream_id()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    // Test writing to a file, and reading it back.
+    #[test]
+    fn test_file_streams() {
+
+        let name = format!("suiron_stream_test_{}_{:?}.txt", std::process::id(),
+                           std::thread::current().id());
+        let path = std::env::temp_dir().join(name);
+        let path = path.to_string_lossy().to_string();
+
+        let mut kb = KnowledgeBase::new();
+
+        let rules = ["save($F) :- open($F, write, $S), write($S, \"line 1\\n\"), \
+                      write($S, point(1, 2)), close($S).",
+                     "more($F) :- open($F, append, $S), write($S, \"\\nlast\"), close($S).",
+                     "load($F, $L1, $L2, $L3, $L4) :- open($F, read, $S), \
+                      read_line($S, $L1), read_line($S, $L2), read_line($S, $L3), \
+                      read_line($S, $L4), close($S).",
+                     "missing($S) :- open(\"/no/such/file.txt\", read, $S).",
+                     "bad($X) :- open(\"x.txt\", badmode, $S), $X = 1.",
+                     "bad($X) :- close(foo), $X = 2.",
+                     "bad($X) :- write(1, a), $X = 3.",
+                     "bad($X) :- write($S, a), $X = 4.",
+                     "bad($X) :- read_line(foo, $L), $X = 5.",
+                     "bad($X) :- close($S), $X = 6.",
+                     "bad($X) :- $X = done."];
+
+        for r in rules {
+            match parse_rule(r) {
+                Ok(rule) => { add_rules!(&mut kb, rule); },
+                Err(err) => { panic!("Should be no parsing errors. {}", err); },
+            }
+        }
+
+        let query = parse_query(&format!("save(\"{}\")", path)).unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        solve(sn);
+
+        let query = parse_query(&format!("more(\"{}\")", path)).unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        solve(sn);
+
+        let q = format!("load(\"{}\", $A, $B, $C, $D)", path);
+        let query = parse_query(&q).unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$A = \"line 1\", $B = \"point(1, 2)\", \
+                    $C = \"last\", $D = end_of_file", solve(sn));
+
+        let query = parse_query("missing($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("No more.", solve(sn));
+
+        // Invalid modes and streams make the predicates fail.
+        let query = parse_query("bad($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!(vec!["$X = done"], solve_all(sn));
+
+        let _ = std::fs::remove_file(&path);
+
+    } // test_file_streams()
+
+} // test
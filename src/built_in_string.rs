@@ -0,0 +1,366 @@
+//! Functions to support text conversion predicates: atom_string(),
+//! number_string(), char_code() and term_to_atom().
+//!
+//! These predicates convert between strings, atoms, numbers, characters
+//! and terms. In Suiron source code, strings are enclosed in double
+//! quotes:
+//!
+//! <pre>
+//!     atom_string(Hello, $S)     # $S = "Hello"
+//!     atom_string($A, "Hello")   # $A = Hello
+//!     number_string($N, " 42 ")  # $N = 42
+//!     char_code(é, $C)           # $C = 233
+//!     term_to_atom(f(1.0, $X), $A)   # $A = f(1.0, $X_1)
+//! </pre>
+//
+// Cleve Lendon  2023
+
+use std::rc::Rc;
+
+use super::unifiable::{*, Unifiable::*};
+use super::logic_var::*;
+use super::parse_terms::*;
+use super::substitution_set::*;
+use super::built_in_predicates::*;
+use super::built_in_write::*;
+
+/// Converts between an atom and a string.
+///
+/// In Suiron source code, the atom_string() predicate requires two arguments.
+/// <pre>
+///     atom_string($Atom, $String)
+/// </pre>
+///
+/// If the first argument is grounded, its text is converted to a string,
+/// which is unified with the second argument. (The first argument may be
+/// an atom, a number or a string.) Otherwise, if the second argument is
+/// grounded, its text is converted to an atom, which is unified with the
+/// first argument. If neither argument is grounded, the predicate fails.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+///
+pub fn bip_atom_string<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                           -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 2 { return None; }
+
+        if let Some(text) = get_text(&terms[0], ss) {
+            return terms[1].unify(&SString(text), ss);
+        }
+        let text = get_text(&terms[1], ss)?;
+        return terms[0].unify(&Atom(text.into()), ss);
+    }
+    return None;
+
+} // bip_atom_string()
+
+/// Converts between a number and a string.
+///
+/// In Suiron source code, the number_string() predicate requires two arguments.
+/// <pre>
+///     number_string($Number, $String)
+/// </pre>
+///
+/// If the second argument is grounded, its text is parsed as a number,
+/// which is unified with the first argument. Leading and trailing spaces
+/// are ignored. If the text is not a valid number, the predicate fails.
+///
+/// Otherwise, if the first argument is a number (or bound to a number),
+/// it is converted to a string, which is unified with the second argument.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+///
+pub fn bip_number_string<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                             -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 2 { return None; }
+
+        if let Some(text) = get_text(&terms[1], ss) {
+            let number = text_to_number(&text)?;
+            return terms[0].unify(&number, ss);
+        }
+
+        match get_constant(&terms[0], ss)? {
+            SInteger(i) => {
+                return terms[1].unify(&SString(i.to_string()), ss);
+            },
+            SFloat(f) => {
+                return terms[1].unify(&SString(f.to_string()), ss);
+            },
+            _ => { return None; },
+        }
+    }
+    return None;
+
+} // bip_number_string()
+
+/// Converts between a character and its Unicode code point.
+///
+/// In Suiron source code, the char_code() predicate requires two arguments.
+/// <pre>
+///     char_code($Char, $Code)
+/// </pre>
+///
+/// If the first argument is grounded, it must be an atom (or string)
+/// of exactly one character. Its code point is unified with the second
+/// argument. Otherwise, if the second argument is an integer (or bound
+/// to one), it is converted to a one-character atom, which is unified
+/// with the first argument.
+///
+/// The predicate fails if neither argument is grounded, if the first
+/// argument has more than one character, or if the code is not a
+/// valid Unicode code point.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+///
+pub fn bip_char_code<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                         -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 2 { return None; }
+
+        let text = match get_constant(&terms[0], ss) {
+            Some(Atom(s)) => { Some(s.as_str()) },
+            Some(SString(s)) => { Some(s.as_str()) },
+            Some(_) => { return None; },
+            None => { None },
+        };
+        if let Some(s) = text {
+            let mut chrs = s.chars();
+            let ch = chrs.next()?;
+            if chrs.next().is_some() { return None; } // too long
+            return terms[1].unify(&SInteger(ch as i64), ss);
+        }
+
+        if let SInteger(code) = get_constant(&terms[1], ss)? {
+            let code = u32::try_from(*code).ok()?;
+            let ch = char::from_u32(code)?;
+            return terms[0].unify(&Atom(ch.to_string().into()), ss);
+        }
+        return None;
+    }
+    return None;
+
+} // bip_char_code()
+
+/// Converts between a term and its text.
+///
+/// In Suiron source code, the term_to_atom() predicate requires two arguments.
+/// <pre>
+///     term_to_atom($Term, $Atom)
+/// </pre>
+///
+/// If the second argument is grounded, its text is parsed as a term,
+/// which is unified with the first argument. Logic variables in the text
+/// are given unique IDs. If the text cannot be parsed, the predicate fails.
+///
+/// Otherwise, the first argument is written in canonical form (see
+/// [format_canonical()](../built_in_write/fn.format_canonical.html)),
+/// and the text is unified with the second argument, as an atom. This
+/// allows terms to be stored as text, and read back later.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// * [VarIds](../logic_var/struct.VarIds.html) of the query
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+///
+pub fn bip_term_to_atom<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>,
+                            ids: &VarIds)
+                            -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 2 { return None; }
+
+        if let Some(text) = get_text(&terms[1], ss) {
+            let term = parse_term(&text).ok()?;
+            let mut vars = VarMap::with_ids(ids);
+            let term = term.recreate_variables(&mut vars);
+            return terms[0].unify(&term, ss);
+        }
+
+        let term = terms[0].replace_variables(ss);
+        if let LogicVar{id: _, name: _} = term { return None; }
+        let text = format_canonical(&term);
+        return terms[1].unify(&Atom(text.into()), ss);
+    }
+    return None;
+
+} // bip_term_to_atom()
+
+// Converts text to an SInteger or SFloat.
+// Arguments:
+//   text - string to convert
+// Return:
+//   SInteger, SFloat or None
+fn text_to_number(text: &str) -> Option<Unifiable> {
+    let text = text.trim();
+    if let Ok(i) = text.parse::<i64>() { return Some(SInteger(i)); }
+    if let Ok(f) = text.parse::<f64>() { return Some(SFloat(f)); }
+    return None;
+} // text_to_number()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    // Test atom_string() and number_string() predicates.
+    #[test]
+    fn test_string_conversion() {
+
+        let mut kb = KnowledgeBase::new();
+
+        let rules = ["to_string($S) :- atom_string(Koala, $S).",
+                     "to_atom($A) :- atom_string($A, \"Koala\").",
+                     "from_number($S) :- number_string(3.5, $S).",
+                     "to_number($N) :- number_string($N, \" 42 \").",
+                     "not_number($N) :- number_string($N, \"abc\")."];
+
+        for r in rules {
+            match parse_rule(r) {
+                Ok(rule) => { add_rules!(&mut kb, rule); },
+                Err(err) => { panic!("Should be no parsing errors. {}", err); },
+            }
+        }
+
+        let query = parse_query("to_string($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = \"Koala\"", solve(sn));
+
+        let query = parse_query("to_atom($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = Koala", solve(sn));
+
+        let query = parse_query("from_number($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = \"3.5\"", solve(sn));
+
+        let query = parse_query("to_number($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = 42", solve(sn));
+
+        let query = parse_query("not_number($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("No more.", solve(sn));
+
+    } // test_string_conversion()
+
+    // Test char_code() predicate.
+    #[test]
+    fn test_char_code() {
+
+        let mut kb = KnowledgeBase::new();
+
+        let rules = ["code($C) :- char_code(é, $C).",
+                     "char($A) :- char_code($A, 955).",
+                     "too_long($C) :- char_code(ab, $C).",
+                     "invalid($A) :- char_code($A, -1)."];
+
+        for r in rules {
+            match parse_rule(r) {
+                Ok(rule) => { add_rules!(&mut kb, rule); },
+                Err(err) => { panic!("Should be no parsing errors. {}", err); },
+            }
+        }
+
+        let query = parse_query("code($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = 233", solve(sn));
+
+        let query = parse_query("char($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = λ", solve(sn));
+
+        let query = parse_query("too_long($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("No more.", solve(sn));
+
+        let query = parse_query("invalid($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("No more.", solve(sn));
+
+    } // test_char_code()
+
+    // Test term_to_atom() predicate.
+    #[test]
+    fn test_term_to_atom() {
+
+        let mut kb = KnowledgeBase::new();
+
+        let rules = ["to_atom($A) :- term_to_atom(point(2.0, [a, \"b\"]), $A).",
+                     "to_term($T) :- term_to_atom($T, \"job(programmer, $Name)\").",
+                     "round_trip($Y) :- term_to_atom(f(1.5, $X), $A), \
+                      term_to_atom(f($Y, $_), $A).",
+                     "quoted($Y) :- term_to_atom(g('42', 'x(y)', 'a, b'), $A), \
+                      term_to_atom($Y, $A).",
+                     "both_unbound($A) :- term_to_atom($T, $A)."];
+
+        for r in rules {
+            match parse_rule(r) {
+                Ok(rule) => { add_rules!(&mut kb, rule); },
+                Err(err) => { panic!("Should be no parsing errors. {}", err); },
+            }
+        }
+
+        let query = parse_query("to_atom($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = point(2.0, [a, \"b\"])", solve(sn));
+
+        let query = parse_query("to_term($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        let result = solve(sn);
+        assert!(result.starts_with("$X = job(programmer, $Name_"), "{}", result);
+
+        let query = parse_query("round_trip($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = 1.5", solve(sn));
+
+        let query = parse_query("quoted($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = g('42', 'x(y)', 'a, b')", solve(sn));
+
+        let query = parse_query("both_unbound($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("No more.", solve(sn));
+
+    } // test_term_to_atom()
+
+} // test
@@ -0,0 +1,226 @@
+//! Functions to support the output predicates tab() and write_canonical().
+//!
+//! The tab predicate writes spaces, for aligning output. The
+//! write_canonical predicate writes a term in a form which can be read
+//! back by the Suiron parser. This is useful for generating Suiron
+//! source code from Suiron.
+//!
+//! <pre>
+//!     tab(4), write_canonical(point(2.0, "x y")), nl
+//!     # Prints:     point(2.0, "x y")
+//! </pre>
+//
+// Cleve Lendon  2023
+
+use std::rc::Rc;
+
+use super::unifiable::{*, Unifiable::*};
+use super::substitution_set::*;
+use super::built_in_predicates::*;
+use super::built_in_arithmetic::*;
+use super::output::*;
+
+// Maximum number of spaces which tab() writes at once.
+const TAB_CHUNK: usize = 1024;
+
+/// Writes spaces to standard output.
+///
+/// In Suiron source code, the tab() predicate requires one argument,
+/// the number of spaces to write.
+/// <pre>
+///     tab(8)
+/// </pre>
+///
+/// The argument can also be an arithmetic function, such as `$N + 2`.
+/// The predicate fails if the argument is not a non-negative integer.
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
+/// # Note
+/// * If the number of arguments is not 1, the predicate fails.
+///
+pub fn bip_tab<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                   -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 1 { return None; }
+
+        let n = match evaluate_expression(&terms[0], ss) {
+            Ok(SInteger(n)) if n >= 0 => { n },
+            _ => { return None; },
+        };
+
+        // Write the spaces in chunks, so that a large count does
+        // not allocate a large string.
+        let spaces = " ".repeat(TAB_CHUNK);
+        let mut remaining = n as usize;
+        while remaining > 0 {
+            let count = remaining.min(TAB_CHUNK);
+            write_output(&spaces[..count]);
+            remaining -= count;
+        }
+        return Some(Rc::clone(ss));
+    }
+    return None;
+
+} // bip_tab()
+
+/// Writes a term to standard output, in a form which can be parsed.
+///
+/// In Suiron source code, the write_canonical() predicate requires one argument.
+/// <pre>
+///     write_canonical($Term)
+/// </pre>
+///
+/// Logic variables are replaced by their bindings. Strings are written
+/// in double quotes, with special characters escaped, and floating point
+/// numbers are always written with a decimal point. See
+/// [format_canonical()](../built_in_write/fn.format_canonical.html).
+///
+/// This function is called by
+/// [next_solution_bip()](../built_in_predicates/fn.next_solution_bip.html)
+/// in built_in_predicates.rs.
+///
+/// # Arguments
+/// * [BuiltInPredicate](../built_in_predicates/struct.BuiltInPredicate.html)
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+/// # Return
+/// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+///
+pub fn bip_write_canonical<'a>(bip: BuiltInPredicate, ss: &'a Rc<SubstitutionSet<'a>>)
+                               -> Option<Rc<SubstitutionSet<'a>>> {
+
+    if let Some(terms) = bip.terms {
+
+        if terms.len() != 1 { return None; }
+
+        let term = terms[0].replace_variables(ss);
+        write_output(&format_canonical(&term));
+        return Some(Rc::clone(ss));
+    }
+    return None;
+
+} // bip_write_canonical()
+
+/// Formats a term so that it can be read back by the Suiron parser.
+///
+/// This is the same as the term's Display format, except that floating
+/// point numbers always have a decimal point. (Display writes 2.0 as 2,
+/// which would be parsed as an integer.) Atoms such as `'42'` or
+/// `'x(y)'`, which would not be read back as the same atom, are quoted.
+///
+/// # Arguments
+/// * [Unifiable](../unifiable/enum.Unifiable.html) term
+/// # Return
+/// * canonical text
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let term = parse_term("point(2.0, \"x\\ty\", [1, $T])").unwrap();
+/// println!("{}", format_canonical(&term));
+/// // Prints: point(2.0, "x\ty", [1, $T])
+/// ```
+pub fn format_canonical(term: &Unifiable) -> String {
+    match term {
+        SFloat(f) => {
+            let s = f.to_string();
+            if f.is_finite() && !s.contains('.') { return format!("{}.0", s); }
+            return s;
+        },
+        SComplex(args) => {
+            let terms: Vec<String> = args[1..].iter().map(format_canonical).collect();
+            return format!("{}({})", args[0], terms.join(", "));
+        },
+        SLinkedList{term: _, next: _, count: _, tail_var: _} => {
+            let mut items: Vec<String> = vec![];
+            let mut tail = "".to_string();
+            let mut t = term;
+            while let SLinkedList{term: head, next, count: _, tail_var} = t {
+                if **head == Nil { break; }
+                if *tail_var { tail = format_canonical(head); break; }
+                items.push(format_canonical(head));
+                t = next;
+            }
+            if tail.len() == 0 { return format!("[{}]", items.join(", ")); }
+            return format!("[{} | {}]", items.join(", "), tail);
+        },
+        SFunction{name, terms} => {
+            let args: Vec<String> = terms.iter().map(format_canonical).collect();
+            return format!("{}({})", name, args.join(", "));
+        },
+        _ => { return term.to_string(); },
+    }
+} // format_canonical()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    // Test that canonical terms can be parsed back.
+    #[test]
+    fn test_format_canonical() {
+        let terms = ["point(2.0, -3.5)",
+                     "text(\"tab\\there\", \"quote\\\"\")",
+                     "[a, b | $T]",
+                     "[1.0, [2.0], f(3.0)]",
+                     "add(1.0, $X)"];
+        for t in terms {
+            let term = parse_term(t).unwrap();
+            let canonical = format_canonical(&term);
+            let reparsed = parse_term(&canonical).unwrap();
+            assert_eq!(term, reparsed, "{}", canonical);
+        }
+        let term = parse_term("p(2.0, \"a\\nb\")").unwrap();
+        assert_eq!("p(2.0, \"a\\nb\")", format_canonical(&term));
+        assert_eq!("p(2, \"a\\nb\")", term.to_string());
+
+        // Atoms which would not be read back as the same atom are quoted.
+        let atoms = ["42", "x(y)", "a, b", "a\nb", "it's", ""];
+        for a in atoms {
+            let term = scomplex!(atom!("f"), atom!(a));
+            let canonical = format_canonical(&term);
+            let reparsed = parse_term(&canonical).unwrap();
+            assert_eq!(term, reparsed, "{}", canonical);
+        }
+        let term = scomplex!(atom!("f"), atom!("x(y)"), atom!("a\nb"), atom!("ok"));
+        assert_eq!("f('x(y)', 'a\\nb', ok)", format_canonical(&term));
+    }
+
+    // tab() fails if its argument is not a non-negative integer.
+    #[test]
+    fn test_bip_tab() {
+
+        let mut kb = KnowledgeBase::new();
+        let rules = ["indent($N) :- tab($N), print(x).",
+                     "twice($N) :- tab($N + $N), print(x).",
+                     "unbound :- tab($X)."];
+        for r in rules { add_rules!(&mut kb, parse_rule(r).unwrap()); }
+
+        let (out, results) = with_captured_output(|| {
+            let mut results = vec![];
+            for q in ["indent(3)", "twice(1)", "indent(-1)", "indent(a)", "unbound",
+                      "indent(2500)"] {
+                let query = parse_query(q).unwrap();
+                results.push(solve(make_base_node(Rc::new(query), &kb)));
+            }
+            results
+        });
+
+        assert_eq!(format!("   x  x{}x", " ".repeat(2500)), out);
+        assert_eq!(vec!["", "", "No more.", "No more.", "No more.", ""], results);
+
+    } // test_bip_tab()
+
+} // test
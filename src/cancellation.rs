@@ -0,0 +1,254 @@
+//! Defines CancellationToken, which allows a query to be cancelled.
+//!
+//! A token is obtained from the base solution node of a query. It can be
+//! cloned and sent to another thread, or to a ctrl-c handler. Calling
+//! cancel() makes the search for solutions stop at the next step, and
+//! the solve functions report that the query was cancelled.
+//!
+//! The token also holds the timeout state of its query. The query timer
+//! (see [start_query_timer()](../time_out/fn.start_query_timer.html))
+//! stops only the query which it was started for, so independent queries
+//! can run in the same process.
+//!
+// Cleve Lendon 2023
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use super::solution_node::SolutionNode;
+
+// States of a query.
+const RUNNING: u8 = 0;
+const CANCELLED: u8 = 1;
+const TIMED_OUT: u8 = 2;
+
+/// A handle which cancels a query.
+///
+/// Clones of a token share the same state, so any clone can cancel the
+/// query. Tokens can be sent between threads.
+///
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use std::thread;
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// add_rules!(&mut kb, parse_rule("loop :- loop.").unwrap());
+/// let query = parse_query("loop").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+///
+/// let token = cancellation_token(&sn);
+/// thread::spawn(move || { token.cancel(); });
+///
+/// match try_solve(sn) {
+///     Err(SuironError::Cancelled(msg)) => { println!("{}", msg); },
+///     _ => {},  // The query timed out before it was cancelled.
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    state: Arc<AtomicU8>,
+}
+
+impl CancellationToken {
+
+    /// Creates a token which has not been cancelled.
+    pub fn new() -> Self {
+        CancellationToken{ state: Arc::new(AtomicU8::new(RUNNING)) }
+    }
+
+    /// Cancels the query. The search stops at the next step.
+    pub fn cancel(&self) {
+        self.state.store(CANCELLED, Ordering::Relaxed);
+    }
+
+    /// Determines whether the query has been cancelled.
+    ///
+    /// # Return
+    /// * true/false
+    pub fn is_cancelled(&self) -> bool {
+        self.state.load(Ordering::Relaxed) == CANCELLED
+    }
+
+    /// Stops the query because it ran out of time. The query timer
+    /// calls this method.
+    pub fn time_out(&self) {
+        // A cancelled query stays cancelled.
+        let _ = self.state.compare_exchange(RUNNING, TIMED_OUT,
+                                            Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    /// Determines whether the query timed out.
+    ///
+    /// # Return
+    /// * true/false
+    pub fn timed_out(&self) -> bool {
+        self.state.load(Ordering::Relaxed) == TIMED_OUT
+    }
+
+    /// Clears the timeout, so that the search for more solutions can
+    /// continue. A cancelled query cannot be continued.
+    pub fn clear_time_out(&self) {
+        let _ = self.state.compare_exchange(TIMED_OUT, RUNNING,
+                                            Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    /// Determines whether the query was cancelled or timed out.
+    ///
+    /// # Return
+    /// * true/false
+    pub fn is_stopped(&self) -> bool {
+        self.state.load(Ordering::Relaxed) != RUNNING
+    }
+
+} // impl CancellationToken
+
+/// Gets the cancellation token of a query, from its base solution node.
+///
+/// If the node does not have a token yet, one is created.
+///
+/// # Arguments
+/// * `sn` - reference to a base [SolutionNode](../solution_node/struct.SolutionNode.html)
+/// # Return
+/// * [CancellationToken](struct.CancellationToken.html)
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let query = parse_query("loves($Who, $Whom)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+///
+/// let token = cancellation_token(&sn);
+/// token.cancel();
+/// assert!(next_solution(sn).is_none());
+/// ```
+pub fn cancellation_token(sn: &Rc<RefCell<SolutionNode>>) -> CancellationToken {
+    if let Some(token) = &sn.borrow().cancellation {
+        return token.clone();
+    }
+    let token = CancellationToken::new();
+    set_cancellation_token(sn, token.clone());
+    return token;
+} // cancellation_token()
+
+/// Sets the cancellation token of a query, on its base solution node.
+///
+/// This allows a token to be created before the query is, for example
+/// by a thread which hands the query to another thread.
+///
+/// # Arguments
+/// * `sn` - reference to a base [SolutionNode](../solution_node/struct.SolutionNode.html)
+/// * [CancellationToken](struct.CancellationToken.html)
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let token = CancellationToken::new();
+/// token.cancel();
+///
+/// let kb = test_kb();
+/// let query = parse_query("loves($Who, $Whom)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+/// set_cancellation_token(&sn, token);
+/// assert!(query_cancelled(&sn));
+/// ```
+pub fn set_cancellation_token(sn: &Rc<RefCell<SolutionNode>>, token: CancellationToken) {
+    let mut sn_ref = sn.borrow_mut();
+    if let Some(solver) = &sn_ref.trail_solver {
+        solver.borrow_mut().set_cancellation(token.clone());
+    }
+    if let Some(solver) = &sn_ref.deepening_solver {
+        solver.borrow_mut().set_cancellation(token.clone());
+    }
+    sn_ref.cancellation = Some(token);
+} // set_cancellation_token()
+
+/// Determines whether the query of a solution node has been cancelled.
+///
+/// # Arguments
+/// * `sn` - reference to a base [SolutionNode](../solution_node/struct.SolutionNode.html)
+/// # Return
+/// * true/false
+pub fn query_cancelled(sn: &Rc<RefCell<SolutionNode>>) -> bool {
+    match &sn.borrow().cancellation {
+        Some(token) => { token.is_cancelled() },
+        None => { false },
+    }
+} // query_cancelled()
+
+/// Determines whether the query of a solution node timed out.
+///
+/// # Arguments
+/// * `sn` - reference to a base [SolutionNode](../solution_node/struct.SolutionNode.html)
+/// # Return
+/// * true/false
+pub fn query_timed_out(sn: &Rc<RefCell<SolutionNode>>) -> bool {
+    match &sn.borrow().cancellation {
+        Some(token) => { token.timed_out() },
+        None => { false },
+    }
+} // query_timed_out()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::*;
+
+    // An endless loop is cancelled from another thread, long
+    // before the query would time out.
+    #[test]
+    fn test_cancellation_token() {
+
+        let mut kb = KnowledgeBase::new();
+        add_rules!(&mut kb, parse_rule("loop :- loop.").unwrap(),
+                            parse_rule("loop($X) :- loop($X).").unwrap());
+
+        for strategy in [BindingStrategy::Persistent, BindingStrategy::Trail] {
+
+            set_binding_strategy(strategy);
+
+            let query = parse_query("loop").unwrap();
+            let sn = make_base_node(Rc::new(query), &kb);
+            let token = cancellation_token(&sn);
+            assert!(!token.is_cancelled());
+
+            let start = Instant::now();
+            let canceller = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                token.cancel();
+            });
+            match try_solve(Rc::clone(&sn)) {
+                Err(SuironError::Cancelled(msg)) => {
+                    assert_eq!("Query was cancelled.", msg);
+                },
+                other => { panic!("Expected Cancelled: {:?}", other); },
+            }
+            assert!(start.elapsed() < Duration::from_millis(500));
+            canceller.join().unwrap();
+            assert!(query_cancelled(&sn));
+            assert_eq!(SolveResult::Cancelled, solve_result(sn));
+
+            // A query which is cancelled before it starts finds nothing.
+            let query = parse_query("loop(1)").unwrap();
+            let sn = make_base_node(Rc::new(query), &kb);
+            cancellation_token(&sn).cancel();
+            assert_eq!("Query was cancelled.", solve(Rc::clone(&sn)));
+            assert!(try_solve_all(sn, None).is_err());
+        }
+
+        set_binding_strategy(BindingStrategy::Persistent);
+
+    } // test_cancellation_token()
+
+} // test
@@ -0,0 +1,222 @@
+//! Functions to load facts from CSV files. Requires the `csv` feature.
+//!
+//! Each row of a CSV file becomes a fact, whose arguments are the fields
+//! of the row:
+//!
+//! <pre>
+//!   name,age,height
+//!   June,61,1.62         person(June, 61, 1.62).
+//!   Ola,23,1.80          person(Ola, 23, 1.8).
+//! </pre>
+//!
+//! By default, the type of each column is inferred. If every field of a
+//! column is an integer, the column becomes SIntegers. If every field is
+//! a number, it becomes SFloats. Otherwise, the fields become atoms (or
+//! SStrings). See [CsvOptions](struct.CsvOptions.html).
+//
+// Cleve Lendon 2023
+
+use std::fs::File;
+use std::io::Read;
+
+use super::error::SuironError;
+use super::knowledge_base::KnowledgeBase;
+use super::unifiable::Unifiable;
+
+use crate::atom;
+
+/// Defines how a CSV file is read and converted into facts.
+///
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let options = CsvOptions{ delimiter: b';', ..CsvOptions::default() };
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    /// If true, the first row holds column names, and is skipped.
+    pub has_headers: bool,
+    /// Field delimiter, usually a comma.
+    pub delimiter: u8,
+    /// If true, numeric columns become SIntegers or SFloats.
+    pub infer_types: bool,
+    /// If true, text fields become SStrings. Otherwise, they become atoms.
+    pub strings: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions{ has_headers: true, delimiter: b',',
+                    infer_types: true, strings: false }
+    }
+}
+
+// Inferred types of columns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColumnType { Integer, Float, Text }
+
+/// Adds facts from CSV data to a knowledge base.
+///
+/// Rows may have different numbers of fields. Each row becomes a
+/// fact of the corresponding arity.
+///
+/// # Arguments
+/// * knowledge base
+/// * predicate functor, eg. \"person\"
+/// * reader of CSV data
+/// * options
+/// # Return
+/// * number of facts added, or error
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// let csv = "name,age\nJune,61\nOla,23\n";
+/// load_csv_facts(&mut kb, "age", csv.as_bytes(), &CsvOptions::default()).unwrap();
+/// print_kb(&kb);  // age(June, 61).  age(Ola, 23).
+/// ```
+pub fn load_csv_facts(kb: &mut KnowledgeBase, functor: &str, reader: impl Read,
+                      options: &CsvOptions) -> Result<usize, SuironError> {
+
+    let mut csv_reader = csv::ReaderBuilder::new()
+                             .has_headers(options.has_headers)
+                             .delimiter(options.delimiter)
+                             .flexible(true)
+                             .from_reader(reader);
+
+    let mut rows: Vec<Vec<String>> = vec![];
+    for record in csv_reader.records() {
+        match record {
+            Ok(record) => {
+                rows.push(record.iter().map(|s| s.to_string()).collect());
+            },
+            Err(err) => {
+                let msg = format!("load_csv_facts() - {}", err);
+                return Err(SuironError::ParseError(msg));
+            },
+        }
+    }
+
+    let types = column_types(&rows, options);
+    let facts = rows.into_iter().map(|row| {
+        row.into_iter().enumerate()
+           .map(|(i, field)| make_term(field, types[i], options))
+           .collect::<Vec<Unifiable>>()
+    });
+    return Ok(kb.add_facts(functor, facts));
+
+} // load_csv_facts()
+
+/// Adds facts from a CSV file to a knowledge base.
+///
+/// See [load_csv_facts()](fn.load_csv_facts.html).
+///
+/// # Arguments
+/// * knowledge base
+/// * predicate functor, eg. \"person\"
+/// * file name
+/// * options
+/// # Return
+/// * number of facts added, or error
+pub fn load_csv_file(kb: &mut KnowledgeBase, functor: &str, file_name: &str,
+                     options: &CsvOptions) -> Result<usize, SuironError> {
+    let file = File::open(file_name)?;
+    return load_csv_facts(kb, functor, file, options);
+} // load_csv_file()
+
+// Infers the type of each column. A column is numeric only if all
+// of its fields are numeric.
+//
+// Arguments
+//    rows
+//    options
+// Return
+//    column types
+fn column_types(rows: &[Vec<String>], options: &CsvOptions) -> Vec<ColumnType> {
+
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    if !options.infer_types { return vec![ColumnType::Text; width]; }
+    let mut types = vec![ColumnType::Integer; width];
+
+    for row in rows {
+        for (i, field) in row.iter().enumerate() {
+            let field = field.trim();
+            if types[i] == ColumnType::Integer && field.parse::<i64>().is_err() {
+                types[i] = ColumnType::Float;
+            }
+            if types[i] == ColumnType::Float && field.parse::<f64>().is_err() {
+                types[i] = ColumnType::Text;
+            }
+        }
+    }
+    return types;
+
+} // column_types()
+
+// Converts a field into a term.
+//
+// Arguments
+//    field
+//    column type
+//    options
+// Return
+//    term
+fn make_term(field: String, column_type: ColumnType, options: &CsvOptions) -> Unifiable {
+    match column_type {
+        ColumnType::Integer => { Unifiable::SInteger(field.trim().parse().unwrap()) },
+        ColumnType::Float => { Unifiable::SFloat(field.trim().parse().unwrap()) },
+        ColumnType::Text => {
+            if options.strings { Unifiable::SString(field) }
+            else { atom!(field.as_str()) }
+        },
+    }
+} // make_term()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    #[test]
+    fn test_load_csv_facts() {
+
+        let mut kb = KnowledgeBase::new();
+        let csv = "name,age,height,id\n\
+                   June,61,1.62,007\n\
+                   Ola,23,1.8,x12\n\
+                   \"Smith, Ann\",40,2,9\n";
+
+        let n = load_csv_facts(&mut kb, "person", csv.as_bytes(), &CsvOptions::default());
+        assert_eq!(Ok(3), n);
+        let facts: Vec<String> = kb.rules("person/4").map(|r| r.to_string()).collect();
+        assert_eq!(vec!["person(June, 61, 1.62, '007').",
+                        "person(Ola, 23, 1.8, x12).",
+                        "person('Smith, Ann', 40, 2, '9')."], facts);
+        if let SComplex(terms) = get_rule(&kb, "person/4", 2).unwrap().head {
+            assert_eq!(SFloat(2.0), terms[3]);
+        } else { panic!("Expected a complex term."); }
+
+        let query = parse_query("person($Name, $Age, $_, $_)").unwrap();
+        add_rules!(&mut kb, parse_rule("old($N) :- person($N, $A, $_, $_), $A > 50.").unwrap());
+        let query2 = parse_query("old($Who)").unwrap();
+        assert_eq!("$Name = June, $Age = 61", solve(make_base_node(Rc::new(query), &kb)));
+        assert_eq!("$Who = June", solve(make_base_node(Rc::new(query2), &kb)));
+
+        // No headers, no type inference, semicolons, ragged rows.
+        let options = CsvOptions{ has_headers: false, delimiter: b';',
+                                  infer_types: false, strings: true };
+        let csv = "a;1\nb;2;3\n";
+        assert_eq!(Ok(2), load_csv_facts(&mut kb, "raw", csv.as_bytes(), &options));
+        assert_eq!("raw(\"a\", \"1\").", get_rule(&kb, "raw/2", 0).unwrap().to_string());
+        assert_eq!("raw(\"b\", \"2\", \"3\").", get_rule(&kb, "raw/3", 0).unwrap().to_string());
+
+        let err = load_csv_file(&mut kb, "none", "no_such_file.csv", &CsvOptions::default());
+        assert!(matches!(err, Err(SuironError::IoError(_))));
+
+    } // test_load_csv_facts()
+
+} // test
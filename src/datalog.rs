@@ -0,0 +1,1253 @@
+//! Bottom-up evaluation of Datalog rules.
+//!
+//! Suiron normally solves a query top down: it starts from the goal, and
+//! works backwards through the rules. For dense recursive relations, such
+//! as reachability in a graph, a top-down search proves the same subgoals
+//! over and over again. (And a left-recursive rule, such as
+//! `path($X, $Y) :- path($X, $Z), edge($Z, $Y).`, never terminates.)
+//!
+//! [materialize()](fn.materialize.html) evaluates the rules bottom up
+//! instead. It starts from the facts, and applies the rules to derive new
+//! facts, until no more can be derived. The result is a knowledge base in
+//! which every rule has been replaced by the facts which it derives.
+//! Queries to that knowledge base are simple lookups.
+//!
+//! Evaluation is semi-naive: after the first round, a rule is only applied
+//! to combinations of facts which include at least one fact which was
+//! derived in the previous round.
+//!
+//! A [Materialized](struct.Materialized.html) knowledge base keeps its
+//! derived facts up to date as facts are asserted and retracted, without
+//! evaluating the rules from scratch.
+//!
+//! The rules must be Datalog rules:
+//! <ul>
+//! <li>Arguments are variables or constants. There are no functions,
+//! such as s($X), and no lists with variables.</li>
+//! <li>The body is a conjunction of goals and negated goals, not(...).
+//! Built-in predicates, disjunctions and cuts are not allowed.</li>
+//! <li>Every variable in the head, or in a negated goal, also appears
+//! in a goal of the body which is not negated.</li>
+//! <li>The rules are stratified: a predicate does not depend on its own
+//! negation, directly or indirectly.</li>
+//! </ul>
+//! Facts must be ground. Rule sets which break these conditions are
+//! rejected with a
+//! [NotDatalog](../error/enum.SuironError.html#variant.NotDatalog) error.
+//!
+// Cleve Lendon 2023
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use super::error::SuironError;
+use super::goal::Goal;
+use super::knowledge_base::*;
+use super::operator::Operator;
+use super::rule::Rule;
+use super::unifiable::{Unifiable, has_variables};
+
+// Marks a variable which has not been bound.
+const UNBOUND: usize = usize::MAX;
+
+// A fact, as a tuple of constant numbers.
+type Row = Vec<usize>;
+
+// Facts by predicate name.
+type Relations = HashMap<String, Relation>;
+
+// Numbers the constants of the facts and rules, so that they can be
+// compared and hashed quickly.
+#[derive(Debug, Default)]
+struct Constants {
+    numbers: HashMap<String, usize>,
+    terms: Vec<Unifiable>,
+}
+
+impl Constants {
+    // Gets the number of a constant.
+    fn number(&mut self, term: &Unifiable) -> usize {
+        let key = format!("{:?}", term);
+        if let Some(&n) = self.numbers.get(&key) { return n; }
+        let n = self.terms.len();
+        self.numbers.insert(key, n);
+        self.terms.push(term.clone());
+        return n;
+    }
+} // impl Constants
+
+// The facts of a predicate, in the order in which they were derived,
+// with indexes on some columns.
+#[derive(Debug, Default)]
+struct Relation {
+    rows: Vec<Row>,
+    set: HashSet<Row>,
+    // Row numbers, by column and constant.
+    index: HashMap<usize, HashMap<usize, Vec<usize>>>,
+}
+
+impl Relation {
+
+    // Creates an empty relation, indexed on the given columns.
+    fn with_index(columns: Option<&HashSet<usize>>) -> Self {
+        let mut relation = Relation::default();
+        if let Some(columns) = columns {
+            for col in columns { relation.index.insert(*col, HashMap::new()); }
+        }
+        return relation;
+    }
+
+    // Adds a row, unless it is already present.
+    // Returns true if the row was added.
+    fn insert(&mut self, row: Row) -> bool {
+        if self.set.contains(&row) { return false; }
+        let n = self.rows.len();
+        for (col, index) in self.index.iter_mut() {
+            index.entry(row[*col]).or_default().push(n);
+        }
+        self.set.insert(row.clone());
+        self.rows.push(row);
+        return true;
+    }
+
+    // Removes rows, and rebuilds the indexes.
+    fn remove(&mut self, rows: &HashSet<Row>) {
+        if rows.is_empty() { return; }
+        self.rows.retain(|row| !rows.contains(row));
+        for row in rows { self.set.remove(row); }
+        for (col, index) in self.index.iter_mut() {
+            index.clear();
+            for (n, row) in self.rows.iter().enumerate() {
+                index.entry(row[*col]).or_default().push(n);
+            }
+        }
+    }
+
+    // Removes a row. Returns true if it was present.
+    fn remove_row(&mut self, row: &Row) -> bool {
+        if !self.set.contains(row) { return false; }
+        self.remove(&HashSet::from([row.clone()]));
+        return true;
+    }
+
+} // impl Relation
+
+// An argument of a goal or head, in a compiled rule.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Arg {
+    Var(usize),   // Variable number.
+    Const(usize), // Constant number.
+    Any,          // Anonymous variable.
+}
+
+// A goal of a compiled rule.
+#[derive(Debug)]
+struct Literal {
+    key: String,
+    args: Vec<Arg>,
+    // Column to look up in the index, if an argument is bound.
+    lookup: Option<usize>,
+}
+
+// A rule, with its variables and constants numbered.
+#[derive(Debug)]
+struct CompiledRule {
+    head: Vec<Arg>,
+    positive: Vec<Literal>,
+    negative: Vec<Literal>,
+    n_vars: usize,
+}
+
+/// Facts which were added to, or removed from, a
+/// [Materialized](struct.Materialized.html) knowledge base by an update.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Changes {
+    /// Facts which were added, asserted or derived.
+    pub added: Vec<Unifiable>,
+    /// Facts which were removed, retracted or no longer derivable.
+    pub removed: Vec<Unifiable>,
+}
+
+/// A knowledge base whose rules have been evaluated bottom up, and which
+/// is kept up to date as facts are asserted and retracted.
+///
+/// When facts change, the derived facts are updated incrementally,
+/// instead of being recomputed from scratch. New facts are propagated
+/// through the rules semi-naively. When facts are retracted, the derived
+/// facts which depended on them are removed, and then those which can
+/// still be derived in another way are put back ('delete and rederive').
+/// A stratum whose negated goals are affected by a change is evaluated
+/// again.
+///
+/// Each update returns the facts which were added and removed, so that
+/// an application can react to them.
+///
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// add_rules!(&mut kb,
+///     parse_rule("edge(a, b).").unwrap(),
+///     parse_rule("path($X, $Y) :- edge($X, $Y).").unwrap(),
+///     parse_rule("path($X, $Y) :- path($X, $Z), edge($Z, $Y).").unwrap());
+///
+/// let mut facts = Materialized::new(&kb).unwrap();
+/// let changes = facts.assert_fact(&parse_complex("edge(b, c)").unwrap()).unwrap();
+/// assert_eq!(3, changes.added.len());  // edge(b, c), path(b, c), path(a, c)
+///
+/// let changes = facts.retract_fact(&parse_complex("edge(a, b)").unwrap()).unwrap();
+/// assert_eq!(3, changes.removed.len());  // edge(a, b), path(a, b), path(a, c)
+/// assert_eq!(1, count_rules(facts.knowledge_base(), "path/2"));
+/// ```
+#[derive(Debug)]
+pub struct Materialized {
+    constants: Constants,
+    // Compiled rules, by predicate name.
+    rules: HashMap<String, Vec<CompiledRule>>,
+    strata: Vec<Vec<String>>,
+    // Columns which are looked up, by predicate name.
+    columns: HashMap<String, HashSet<usize>>,
+    functors: HashMap<String, Unifiable>,
+    // Asserted facts of the predicates which have rules.
+    base: Relations,
+    // All facts, asserted and derived.
+    total: Relations,
+    // Knowledge base of facts.
+    kb: KnowledgeBase,
+}
+
+impl Materialized {
+
+    /// Evaluates the rules of a knowledge base bottom up.
+    ///
+    /// # Arguments
+    /// * knowledge base
+    /// # Return
+    /// * Materialized, or [SuironError](../error/enum.SuironError.html)
+    pub fn new(kb: &KnowledgeBase) -> Result<Self, SuironError> {
+
+        let mut constants = Constants::default();
+        let mut rules: HashMap<String, Vec<CompiledRule>> = HashMap::new();
+        let mut functors: HashMap<String, Unifiable> = HashMap::new();
+        let mut facts: Vec<(String, Row)> = vec![];
+
+        let mut keys: Vec<&String> = kb.keys().collect();
+        keys.sort();
+
+        for key in keys {
+            for rule in kb[key].iter() {
+                if rule.body == Goal::Nil {
+                    let (_, row) = fact_row(&rule.head, &mut constants, &mut functors)?;
+                    facts.push((key.clone(), row));
+                }
+                else {
+                    let compiled = compile_rule(rule, &mut constants)?;
+                    rules.entry(key.clone()).or_default().push(compiled);
+                    if let Unifiable::SComplex(terms) = &rule.head {
+                        functors.insert(key.clone(), terms[0].clone());
+                    }
+                }
+            }
+        }
+
+        // Check that every goal can be enumerated.
+        for compiled in rules.values().flatten() {
+            for literal in compiled.positive.iter().chain(compiled.negative.iter()) {
+                if !kb.contains(&literal.key) && kb.external(&literal.key).is_some() {
+                    let err = format!("materialize() - External predicate: {}", literal.key);
+                    return Err(SuironError::NotDatalog(err));
+                }
+            }
+        }
+
+        let mut columns: HashMap<String, HashSet<usize>> = HashMap::new();
+        for compiled in rules.values().flatten() {
+            for literal in compiled.positive.iter().chain(compiled.negative.iter()) {
+                if let Some(col) = literal.lookup {
+                    columns.entry(literal.key.clone()).or_default().insert(col);
+                }
+            }
+        }
+
+        let strata = stratify(&rules)?;
+
+        let mut base: Relations = HashMap::new();
+        let mut total: Relations = HashMap::new();
+        for (key, row) in facts {
+            if rules.contains_key(&key) {
+                base.entry(key.clone()).or_default().insert(row.clone());
+            }
+            relation(&mut total, &key, &columns).insert(row);
+        }
+        for key in rules.keys() { relation(&mut total, key, &columns); }
+
+        for stratum in &strata {
+            evaluate_stratum(stratum, &rules, &columns, &mut total);
+        }
+
+        let mut materialized = Materialized{ constants, rules, strata, columns,
+                                             functors, base, total, kb: kb.clone() };
+        let keys: Vec<String> = materialized.rules.keys().cloned().collect();
+        materialized.refresh(keys.iter());
+        return Ok(materialized);
+
+    } // new()
+
+    /// Gets the knowledge base of facts. Each rule has been replaced by
+    /// the facts which it derives.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * [KnowledgeBase](../knowledge_base/struct.KnowledgeBase.html)
+    pub fn knowledge_base(&self) -> &KnowledgeBase { &self.kb }
+
+    /// Asserts a fact, and updates the derived facts.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * fact, eg. edge(b, c)
+    /// # Return
+    /// * [Changes](struct.Changes.html), or
+    ///   [SuironError](../error/enum.SuironError.html)
+    pub fn assert_fact(&mut self, fact: &Unifiable) -> Result<Changes, SuironError> {
+        return self.update(std::slice::from_ref(fact), &[]);
+    }
+
+    /// Retracts a fact, and updates the derived facts.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * fact, eg. edge(a, b)
+    /// # Return
+    /// * [Changes](struct.Changes.html), or
+    ///   [SuironError](../error/enum.SuironError.html)
+    pub fn retract_fact(&mut self, fact: &Unifiable) -> Result<Changes, SuironError> {
+        return self.update(&[], std::slice::from_ref(fact));
+    }
+
+    /// Asserts and retracts facts, and updates the derived facts.
+    /// Facts are retracted before they are asserted. Facts which are
+    /// already present are not asserted again.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * facts to assert
+    /// * facts to retract
+    /// # Return
+    /// * [Changes](struct.Changes.html), or
+    ///   [SuironError](../error/enum.SuironError.html)
+    pub fn update(&mut self, asserted: &[Unifiable], retracted: &[Unifiable])
+                  -> Result<Changes, SuironError> {
+
+        // Changes to the asserted facts of predicates which have rules.
+        let mut seeds_added: Relations = HashMap::new();
+        let mut seeds_removed: Relations = HashMap::new();
+        // Changes to all facts.
+        let mut added: Relations = HashMap::new();
+        let mut removed: Relations = HashMap::new();
+
+        let mut rows = vec![];
+        for fact in retracted {
+            rows.push(fact_row(fact, &mut self.constants, &mut self.functors)?);
+        }
+        for (key, row) in rows {
+            if self.rules.contains_key(&key) {
+                if self.base.get_mut(&key).is_some_and(|base| base.remove_row(&row)) {
+                    relation(&mut seeds_removed, &key, &self.columns).insert(row);
+                }
+            }
+            else if self.total.get(&key).is_some_and(|r| r.set.contains(&row)) {
+                relation(&mut removed, &key, &self.columns).insert(row);
+            }
+        }
+
+        let mut rows = vec![];
+        for fact in asserted {
+            rows.push(fact_row(fact, &mut self.constants, &mut self.functors)?);
+        }
+        for (key, row) in rows {
+            if self.rules.contains_key(&key) {
+                if self.base.entry(key.clone()).or_default().insert(row.clone()) {
+                    relation(&mut seeds_added, &key, &self.columns).insert(row);
+                }
+            }
+            else if removed.get_mut(&key).is_some_and(|r| r.remove_row(&row)) {
+                // Retracted, and asserted again.
+            }
+            else if !self.total.get(&key).is_some_and(|r| r.set.contains(&row)) {
+                relation(&mut added, &key, &self.columns).insert(row);
+            }
+        }
+        removed.retain(|_, r| !r.rows.is_empty());
+
+        // Update the predicates which have no rules.
+        for (key, rows) in &removed {
+            self.total.get_mut(key).unwrap().remove(&rows.set);
+        }
+        for (key, rows) in &added {
+            let all = relation(&mut self.total, key, &self.columns);
+            for row in &rows.rows { all.insert(row.clone()); }
+        }
+
+        for stratum in &self.strata {
+            let (stratum_added, stratum_removed) =
+                update_stratum(stratum, &self.rules, &self.columns, &self.base,
+                               &mut self.total, &seeds_added, &seeds_removed,
+                               &added, &removed);
+            added.extend(stratum_added);
+            removed.extend(stratum_removed);
+        }
+        added.retain(|_, r| !r.rows.is_empty());
+        removed.retain(|_, r| !r.rows.is_empty());
+
+        let mut keys: Vec<&String> = added.keys().chain(removed.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        self.refresh(keys.iter().copied());
+
+        let mut changes = Changes::default();
+        for key in keys {
+            if let Some(rows) = added.get(key) {
+                changes.added.extend(rows.rows.iter().map(|row| self.make_fact(key, row)));
+            }
+            if let Some(rows) = removed.get(key) {
+                changes.removed.extend(rows.rows.iter().map(|row| self.make_fact(key, row)));
+            }
+        }
+        return Ok(changes);
+
+    } // update()
+
+    // Makes a fact from a row.
+    fn make_fact(&self, key: &str, row: &Row) -> Unifiable {
+        let mut terms = vec![self.functors[key].clone()];
+        terms.extend(row.iter().map(|n| self.constants.terms[*n].clone()));
+        return Unifiable::SComplex(terms);
+    }
+
+    // Copies the facts of the given predicates to the knowledge base.
+    fn refresh<'k>(&mut self, keys: impl Iterator<Item = &'k String>) {
+        for key in keys {
+            let facts: Vec<Rule> = self.total[key].rows.iter().map(|row| {
+                Rule{head: self.make_fact(key, row), body: Goal::Nil}
+            }).collect();
+            self.kb.insert(key.clone(), Rc::new(facts));
+        }
+    }
+
+} // impl Materialized
+
+/// Evaluates the rules of a knowledge base bottom up, and returns a
+/// knowledge base in which each rule has been replaced by the facts
+/// which it derives.
+///
+/// See the [module documentation](index.html) for the conditions which
+/// the rules must satisfy. To keep the facts up to date as facts are
+/// asserted and retracted, use [Materialized](struct.Materialized.html).
+///
+/// # Arguments
+/// * knowledge base
+/// # Return
+/// * knowledge base of facts, or
+///   [SuironError](../error/enum.SuironError.html)
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// add_rules!(&mut kb,
+///     parse_rule("edge(a, b).").unwrap(),
+///     parse_rule("edge(b, c).").unwrap(),
+///     parse_rule("path($X, $Y) :- edge($X, $Y).").unwrap(),
+///     parse_rule("path($X, $Y) :- path($X, $Z), edge($Z, $Y).").unwrap());
+///
+/// let facts = materialize(&kb).unwrap();
+/// assert_eq!(3, count_rules(&facts, "path/2"));
+///
+/// let query = parse_query("path(a, $Y)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &facts);
+/// println!("{:?}", solve_all(sn));  // Prints: ["$Y = b", "$Y = c"]
+/// ```
+pub fn materialize(kb: &KnowledgeBase) -> Result<KnowledgeBase, SuironError> {
+    return Ok(Materialized::new(kb)?.kb);
+} // materialize()
+
+// Gets the relation of a predicate, or creates it.
+fn relation<'r>(relations: &'r mut Relations, key: &String,
+                columns: &HashMap<String, HashSet<usize>>) -> &'r mut Relation {
+    return relations.entry(key.clone())
+                    .or_insert_with(|| Relation::with_index(columns.get(key)));
+}
+
+// Converts a fact to a row of constant numbers.
+//
+// Arguments
+//    fact
+//    constants
+//    functors, by predicate name
+// Return
+//    predicate name and row, or error if the fact is not ground
+fn fact_row(fact: &Unifiable, constants: &mut Constants,
+            functors: &mut HashMap<String, Unifiable>)
+            -> Result<(String, Row), SuironError> {
+    let terms = match fact {
+        Unifiable::SComplex(terms) if !terms.is_empty() => { terms },
+        _ => { return Err(not_datalog("Invalid fact", fact)); },
+    };
+    let mut row = Row::with_capacity(terms.len() - 1);
+    for term in &terms[1..] {
+        if is_variable(term) { return Err(not_datalog("Fact is not ground", fact)); }
+        row.push(constants.number(term));
+    }
+    let key = fact.key()?;
+    functors.entry(key.clone()).or_insert_with(|| terms[0].clone());
+    return Ok((key, row));
+} // fact_row()
+
+// Makes an error for a rule or fact which is not Datalog.
+fn not_datalog(problem: &str, term: &dyn std::fmt::Display) -> SuironError {
+    let err = format!("materialize() - {}: {}", problem, term);
+    return SuironError::NotDatalog(err);
+}
+
+// Determines whether a term is or contains a variable.
+fn is_variable(term: &Unifiable) -> bool {
+    return *term == Unifiable::Anonymous || has_variables(term);
+}
+
+// Numbers the variables and constants of a rule, and checks that
+// it is a Datalog rule.
+//
+// Arguments
+//    rule
+//    constants
+// Return
+//    compiled rule, or error
+fn compile_rule(rule: &Rule, constants: &mut Constants)
+                -> Result<CompiledRule, SuironError> {
+
+    let mut vars: HashMap<String, usize> = HashMap::new();
+
+    let mut goals: Vec<&Goal> = vec![&rule.body];
+    let mut positive: Vec<Literal> = vec![];
+    let mut negative: Vec<Literal> = vec![];
+
+    while let Some(goal) = goals.pop() {
+        match goal {
+            Goal::ComplexGoal(term) => {
+                positive.push(compile_literal(term, &mut vars, constants, rule)?);
+            },
+            Goal::OperatorGoal(Operator::And(subgoals)) => {
+                goals.extend(subgoals.iter().rev());
+            },
+            Goal::OperatorGoal(Operator::Not(subgoals)) => {
+                match subgoals.first() {
+                    Some(Goal::ComplexGoal(term)) => {
+                        negative.push(compile_literal(term, &mut vars,
+                                                      constants, rule)?);
+                    },
+                    _ => { return Err(not_datalog("Invalid negation", rule)); },
+                }
+            },
+            _ => { return Err(not_datalog("Invalid goal", rule)); },
+        }
+    }
+
+    let head = match &rule.head {
+        Unifiable::SComplex(terms) => {
+            let mut head = vec![];
+            for term in &terms[1..] {
+                head.push(compile_arg(term, &mut vars, constants, rule)?);
+            }
+            head
+        },
+        _ => { return Err(not_datalog("Invalid head", rule)); },
+    };
+
+    // Variables are bound by the positive goals, from left to right.
+    let mut bound: HashSet<usize> = HashSet::new();
+    for literal in positive.iter_mut() {
+        literal.lookup = lookup_column(&literal.args, &bound);
+        for arg in &literal.args {
+            if let Arg::Var(v) = arg { bound.insert(*v); }
+        }
+    }
+    for literal in negative.iter_mut() {
+        literal.lookup = lookup_column(&literal.args, &bound);
+    }
+
+    // Check that the rule is safe.
+    let negative_args = negative.iter().flat_map(|l| l.args.iter());
+    for arg in head.iter().chain(negative_args) {
+        match arg {
+            Arg::Var(v) if !bound.contains(v) => {
+                return Err(not_datalog("Unsafe variable", rule));
+            },
+            _ => {},
+        }
+    }
+    if head.contains(&Arg::Any) {
+        return Err(not_datalog("Unsafe variable", rule));
+    }
+
+    return Ok(CompiledRule{ head, positive, negative, n_vars: vars.len() });
+
+} // compile_rule()
+
+// Compiles a goal of the body of a rule.
+fn compile_literal(term: &Unifiable, vars: &mut HashMap<String, usize>,
+                   constants: &mut Constants, rule: &Rule)
+                   -> Result<Literal, SuironError> {
+    let key = term.key().map_err(|_| not_datalog("Invalid goal", rule))?;
+    let mut args = vec![];
+    if let Unifiable::SComplex(terms) = term {
+        for t in &terms[1..] { args.push(compile_arg(t, vars, constants, rule)?); }
+    }
+    return Ok(Literal{ key, args, lookup: None });
+} // compile_literal()
+
+// Compiles an argument of a head or goal. It must be a variable
+// or a constant.
+fn compile_arg(term: &Unifiable, vars: &mut HashMap<String, usize>,
+               constants: &mut Constants, rule: &Rule)
+               -> Result<Arg, SuironError> {
+    match term {
+        Unifiable::LogicVar{id: _, name} => {
+            let n = vars.len();
+            return Ok(Arg::Var(*vars.entry(name.clone()).or_insert(n)));
+        },
+        Unifiable::Anonymous => { return Ok(Arg::Any); },
+        _ => {
+            if has_variables(term) { return Err(not_datalog("Function", rule)); }
+            return Ok(Arg::Const(constants.number(term)));
+        },
+    }
+} // compile_arg()
+
+// Chooses the column of a goal to look up in an index. This is the
+// first argument which is a constant, or a variable which is bound.
+fn lookup_column(args: &[Arg], bound: &HashSet<usize>) -> Option<usize> {
+    return args.iter().position(|arg| match arg {
+        Arg::Const(_) => { true },
+        Arg::Var(v) => { bound.contains(v) },
+        Arg::Any => { false },
+    });
+} // lookup_column()
+
+// Divides the derived predicates into strata. Each stratum is a set of
+// mutually recursive predicates. A stratum comes after the strata which
+// it depends on.
+//
+// Arguments
+//    compiled rules, by predicate name
+// Return
+//    strata, or an error if the rules are not stratified
+fn stratify(rules: &HashMap<String, Vec<CompiledRule>>)
+            -> Result<Vec<Vec<String>>, SuironError> {
+
+    let mut keys: Vec<&String> = rules.keys().collect();
+    keys.sort();
+
+    let mut tarjan = Tarjan::default();
+    for key in keys {
+        if !tarjan.number.contains_key(key.as_str()) {
+            tarjan.visit(key, rules);
+        }
+    }
+
+    for stratum in &tarjan.strata {
+        for key in stratum {
+            for literal in rules[key].iter().flat_map(|r| r.negative.iter()) {
+                if stratum.contains(&literal.key) {
+                    let err = format!("materialize() - Not stratified: {} depends on not({})",
+                                      key, literal.key);
+                    return Err(SuironError::NotDatalog(err));
+                }
+            }
+        }
+    }
+    return Ok(tarjan.strata);
+
+} // stratify()
+
+// Finds the strongly connected components of the dependency graph,
+// with Tarjan's algorithm. Components are found after the components
+// which they depend on.
+#[derive(Default)]
+struct Tarjan<'r> {
+    number: HashMap<&'r str, usize>,
+    low: HashMap<&'r str, usize>,
+    stack: Vec<&'r str>,
+    on_stack: HashSet<&'r str>,
+    strata: Vec<Vec<String>>,
+}
+
+impl<'r> Tarjan<'r> {
+
+    fn visit(&mut self, key: &'r str, rules: &'r HashMap<String, Vec<CompiledRule>>) {
+
+        let n = self.number.len();
+        self.number.insert(key, n);
+        self.low.insert(key, n);
+        self.stack.push(key);
+        self.on_stack.insert(key);
+
+        let literals = rules[key].iter()
+                                 .flat_map(|r| r.positive.iter().chain(r.negative.iter()));
+        for literal in literals {
+            let next = literal.key.as_str();
+            if !rules.contains_key(next) { continue; } // Facts only.
+            if !self.number.contains_key(next) {
+                self.visit(next, rules);
+                let low = self.low[key].min(self.low[next]);
+                self.low.insert(key, low);
+            }
+            else if self.on_stack.contains(next) {
+                let low = self.low[key].min(self.number[next]);
+                self.low.insert(key, low);
+            }
+        }
+
+        if self.low[key] == self.number[key] {
+            let mut stratum = vec![];
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(member);
+                stratum.push(member.to_string());
+                if member == key { break; }
+            }
+            self.strata.push(stratum);
+        }
+
+    } // visit()
+
+} // impl Tarjan
+
+// Derives the facts of a stratum, semi-naively.
+//
+// Arguments
+//    predicate names of the stratum
+//    compiled rules
+//    indexed columns, by predicate name
+//    all facts
+fn evaluate_stratum(stratum: &[String],
+                    rules: &HashMap<String, Vec<CompiledRule>>,
+                    columns: &HashMap<String, HashSet<usize>>,
+                    total: &mut Relations) {
+
+    let new_delta = |derived: Vec<(&String, Row)>, total: &Relations| -> Relations {
+        let mut delta: Relations = HashMap::new();
+        for (key, row) in derived {
+            if total[key].set.contains(&row) { continue; }
+            relation(&mut delta, key, columns).insert(row);
+        }
+        return delta;
+    };
+
+    // First round: apply every rule to all facts.
+    let mut derived: Vec<(&String, Row)> = vec![];
+    for key in stratum {
+        for rule in &rules[key] {
+            let mut bindings = vec![UNBOUND; rule.n_vars];
+            let mut rows = vec![];
+            join(rule, 0, None, total, &HashMap::new(), &mut bindings, &mut rows);
+            derived.extend(rows.into_iter().map(|row| (key, row)));
+        }
+    }
+    let mut delta = new_delta(derived, total);
+
+    while !delta.is_empty() {
+        for (key, relation) in &delta {
+            let all = total.get_mut(key).unwrap();
+            for row in &relation.rows { all.insert(row.clone()); }
+        }
+        // Apply the rules to combinations which include new facts.
+        let derived = apply_rules(stratum, rules, total, &delta);
+        delta = new_delta(derived, total);
+    }
+
+} // evaluate_stratum()
+
+// Updates the facts of a stratum, after facts of lower strata, or
+// asserted facts of the stratum, have changed.
+//
+// Arguments
+//    predicate names of the stratum
+//    compiled rules
+//    indexed columns, by predicate name
+//    asserted facts of predicates which have rules
+//    all facts
+//    facts asserted to the stratum
+//    facts retracted from the stratum
+//    facts added to lower strata
+//    facts removed from lower strata
+// Return
+//    facts added to and removed from the stratum
+#[allow(clippy::too_many_arguments)]
+fn update_stratum(stratum: &[String],
+                  rules: &HashMap<String, Vec<CompiledRule>>,
+                  columns: &HashMap<String, HashSet<usize>>,
+                  base: &Relations, total: &mut Relations,
+                  seeds_added: &Relations, seeds_removed: &Relations,
+                  added: &Relations, removed: &Relations) -> (Relations, Relations) {
+
+    let changed = |key: &String| added.contains_key(key) || removed.contains_key(key);
+    let stratum_rules = || stratum.iter().flat_map(|key| rules[key].iter());
+
+    if stratum_rules().flat_map(|r| r.negative.iter()).any(|l| changed(&l.key)) {
+        return recompute_stratum(stratum, rules, columns, base, total);
+    }
+
+    let seeded = stratum.iter().any(|key| seeds_added.contains_key(key) ||
+                                          seeds_removed.contains_key(key));
+    if !seeded && !stratum_rules().flat_map(|r| r.positive.iter()).any(|l| changed(&l.key)) {
+        return (HashMap::new(), HashMap::new());
+    }
+
+    let in_stratum = |key: &String| stratum.contains(key);
+    let mut lower_removed: Vec<&String> = stratum_rules()
+        .flat_map(|r| r.positive.iter())
+        .map(|l| &l.key)
+        .filter(|key| removed.contains_key(*key))
+        .collect();
+    lower_removed.sort();
+    lower_removed.dedup();
+
+    // Delete the facts which might depend on removed facts. The removed
+    // facts are put back for now, because they were used to derive them.
+    let mut overdeleted: Relations = HashMap::new();
+    for key in &lower_removed {
+        let all = total.get_mut(*key).unwrap();
+        for row in &removed[*key].rows { all.insert(row.clone()); }
+    }
+    let mut delta: Relations = HashMap::new();
+    for key in &lower_removed {
+        let rows = relation(&mut delta, key, columns);
+        for row in &removed[*key].rows { rows.insert(row.clone()); }
+    }
+    for (key, rows) in seeds_removed.iter().filter(|(key, _)| in_stratum(key)) {
+        for row in &rows.rows {
+            if total[key].set.contains(row) {
+                relation(&mut delta, key, columns).insert(row.clone());
+                relation(&mut overdeleted, key, columns).insert(row.clone());
+            }
+        }
+    }
+    while !delta.is_empty() {
+        let derived = apply_rules(stratum, rules, total, &delta);
+        let mut next: Relations = HashMap::new();
+        for (key, row) in derived {
+            if !total[key].set.contains(&row) { continue; }
+            if relation(&mut overdeleted, key, columns).insert(row.clone()) {
+                relation(&mut next, key, columns).insert(row);
+            }
+        }
+        delta = next;
+    }
+    for key in &lower_removed {
+        total.get_mut(*key).unwrap().remove(&removed[*key].set);
+    }
+    for (key, rows) in &overdeleted {
+        total.get_mut(key).unwrap().remove(&rows.set);
+    }
+
+    // Put back the deleted facts which can still be derived, and add
+    // the asserted facts.
+    let mut delta: Relations = HashMap::new();
+    for key in stratum {
+        if let Some(rows) = overdeleted.get(key) {
+            for row in &rows.rows {
+                let asserted = base.get(key).is_some_and(|b| b.set.contains(row));
+                if asserted || rules[key].iter().any(|rule| derives(rule, row, total)) {
+                    relation(&mut delta, key, columns).insert(row.clone());
+                }
+            }
+        }
+        if let Some(rows) = seeds_added.get(key) {
+            for row in &rows.rows {
+                if !total[key].set.contains(row) {
+                    relation(&mut delta, key, columns).insert(row.clone());
+                }
+            }
+        }
+    }
+    let mut inserted: Relations = HashMap::new();
+    for (key, rows) in &delta {
+        for row in &rows.rows {
+            total.get_mut(key).unwrap().insert(row.clone());
+            relation(&mut inserted, key, columns).insert(row.clone());
+        }
+    }
+
+    // Propagate the new facts.
+    for (key, rows) in added.iter() {
+        if stratum_rules().any(|r| r.positive.iter().any(|l| l.key == *key)) {
+            let new_rows = relation(&mut delta, key, columns);
+            for row in &rows.rows { new_rows.insert(row.clone()); }
+        }
+    }
+    while !delta.is_empty() {
+        let derived = apply_rules(stratum, rules, total, &delta);
+        let mut next: Relations = HashMap::new();
+        for (key, row) in derived {
+            if total.get_mut(key).unwrap().insert(row.clone()) {
+                relation(&mut inserted, key, columns).insert(row.clone());
+                relation(&mut next, key, columns).insert(row);
+            }
+        }
+        delta = next;
+    }
+
+    // Facts which were deleted and put back have not changed.
+    let mut stratum_added: Relations = HashMap::new();
+    let mut stratum_removed: Relations = HashMap::new();
+    for (key, rows) in &inserted {
+        for row in &rows.rows {
+            if !overdeleted.get(key).is_some_and(|r| r.set.contains(row)) {
+                relation(&mut stratum_added, key, columns).insert(row.clone());
+            }
+        }
+    }
+    for (key, rows) in &overdeleted {
+        for row in &rows.rows {
+            if !total[key].set.contains(row) {
+                relation(&mut stratum_removed, key, columns).insert(row.clone());
+            }
+        }
+    }
+    return (stratum_added, stratum_removed);
+
+} // update_stratum()
+
+// Evaluates a stratum again, from its asserted facts.
+//
+// Arguments
+//    predicate names of the stratum
+//    compiled rules
+//    indexed columns, by predicate name
+//    asserted facts of predicates which have rules
+//    all facts
+// Return
+//    facts added to and removed from the stratum
+fn recompute_stratum(stratum: &[String],
+                     rules: &HashMap<String, Vec<CompiledRule>>,
+                     columns: &HashMap<String, HashSet<usize>>,
+                     base: &Relations, total: &mut Relations) -> (Relations, Relations) {
+
+    let mut old: Relations = HashMap::new();
+    for key in stratum {
+        let mut fresh = Relation::with_index(columns.get(key));
+        if let Some(rows) = base.get(key) {
+            for row in &rows.rows { fresh.insert(row.clone()); }
+        }
+        old.insert(key.clone(), total.insert(key.clone(), fresh).unwrap_or_default());
+    }
+
+    evaluate_stratum(stratum, rules, columns, total);
+
+    let mut stratum_added: Relations = HashMap::new();
+    let mut stratum_removed: Relations = HashMap::new();
+    for key in stratum {
+        let (old, new) = (&old[key], &total[key]);
+        for row in new.rows.iter().filter(|row| !old.set.contains(*row)) {
+            relation(&mut stratum_added, key, columns).insert(row.clone());
+        }
+        for row in old.rows.iter().filter(|row| !new.set.contains(*row)) {
+            relation(&mut stratum_removed, key, columns).insert(row.clone());
+        }
+    }
+    return (stratum_added, stratum_removed);
+
+} // recompute_stratum()
+
+// Applies the rules of a stratum to combinations of facts which include
+// at least one fact from delta.
+//
+// Arguments
+//    predicate names of the stratum
+//    compiled rules
+//    all facts
+//    new (or removed) facts
+// Return
+//    derived facts, with their predicate names
+fn apply_rules<'s>(stratum: &'s [String],
+                   rules: &HashMap<String, Vec<CompiledRule>>,
+                   total: &Relations, delta: &Relations) -> Vec<(&'s String, Row)> {
+    let mut derived: Vec<(&String, Row)> = vec![];
+    for key in stratum {
+        for rule in &rules[key] {
+            for (i, literal) in rule.positive.iter().enumerate() {
+                if !delta.contains_key(&literal.key) { continue; }
+                let mut bindings = vec![UNBOUND; rule.n_vars];
+                let mut rows = vec![];
+                join(rule, 0, Some(i), total, delta, &mut bindings, &mut rows);
+                derived.extend(rows.into_iter().map(|row| (key, row)));
+            }
+        }
+    }
+    return derived;
+} // apply_rules()
+
+// Determines whether a rule derives a fact from the current facts.
+fn derives(rule: &CompiledRule, row: &Row, total: &Relations) -> bool {
+    let mut bindings = vec![UNBOUND; rule.n_vars];
+    if !bind(&rule.head, row, &mut bindings, &mut vec![]) { return false; }
+    let mut rows = vec![];
+    join(rule, 0, None, total, &HashMap::new(), &mut bindings, &mut rows);
+    return !rows.is_empty();
+} // derives()
+
+// Joins the positive goals of a rule, from goal i onwards, and
+// checks the negated goals. Collects the instantiated heads.
+//
+// Arguments
+//    compiled rule
+//    index of the goal
+//    index of the goal which uses the new facts (delta), if any
+//    all facts
+//    new facts
+//    bindings of the rule's variables
+//    derived facts
+fn join(rule: &CompiledRule, i: usize, delta_at: Option<usize>,
+        total: &Relations, delta: &Relations,
+        bindings: &mut [usize], derived: &mut Vec<Row>) {
+
+    if i == rule.positive.len() {
+        for literal in &rule.negative {
+            if let Some(relation) = total.get(&literal.key) {
+                let mut found = false;
+                for_candidates(literal, relation, bindings, |row| {
+                    found = found || matches(&literal.args, row, bindings);
+                });
+                if found { return; }
+            }
+        }
+        let head = rule.head.iter().map(|arg| match arg {
+            Arg::Var(v) => { bindings[*v] },
+            Arg::Const(c) => { *c },
+            Arg::Any => { UNBOUND },
+        }).collect();
+        derived.push(head);
+        return;
+    }
+
+    let literal = &rule.positive[i];
+    let relation = if delta_at == Some(i) { delta.get(&literal.key) }
+                   else { total.get(&literal.key) };
+    let relation = match relation {
+        Some(relation) => { relation },
+        None => { return; },
+    };
+
+    // Bindings are copied, because the index lookup borrows them.
+    let mut inner = bindings.to_vec();
+    let mut newly_bound: Vec<usize> = vec![];
+    for_candidates(literal, relation, bindings, |row| {
+        if bind(&literal.args, row, &mut inner, &mut newly_bound) {
+            join(rule, i + 1, delta_at, total, delta, &mut inner, derived);
+        }
+        for v in newly_bound.drain(..) { inner[v] = UNBOUND; }
+    });
+
+} // join()
+
+// Calls a function on each row of a relation which might match a goal.
+// If an argument is bound, the index is used.
+//
+// Arguments
+//    goal
+//    relation
+//    bindings of the rule's variables
+//    function
+fn for_candidates(literal: &Literal, relation: &Relation,
+                  bindings: &[usize], mut f: impl FnMut(&Row)) {
+    let col = match literal.lookup {
+        Some(col) => { col },
+        None => {
+            for row in &relation.rows { f(row); }
+            return;
+        },
+    };
+    let value = match literal.args[col] {
+        Arg::Var(v) => { bindings[v] },
+        Arg::Const(c) => { c },
+        Arg::Any => { UNBOUND },
+    };
+    if let Some(rows) = relation.index[&col].get(&value) {
+        for n in rows { f(&relation.rows[*n]); }
+    }
+} // for_candidates()
+
+// Binds the variables of a goal to a row. Returns false if they do
+// not match. The variables which were bound are recorded, so that
+// they can be unbound.
+fn bind(args: &[Arg], row: &Row, bindings: &mut [usize],
+        newly_bound: &mut Vec<usize>) -> bool {
+    for (arg, value) in args.iter().zip(row) {
+        match arg {
+            Arg::Var(v) => {
+                if bindings[*v] == UNBOUND {
+                    bindings[*v] = *value;
+                    newly_bound.push(*v);
+                }
+                else if bindings[*v] != *value { return false; }
+            },
+            Arg::Const(c) => { if c != value { return false; } },
+            Arg::Any => {},
+        }
+    }
+    return true;
+} // bind()
+
+// Determines whether a row matches a goal whose variables are bound.
+fn matches(args: &[Arg], row: &Row, bindings: &[usize]) -> bool {
+    return args.iter().zip(row).all(|(arg, value)| match arg {
+        Arg::Var(v) => { bindings[*v] == *value },
+        Arg::Const(c) => { c == value },
+        Arg::Any => { true },
+    });
+} // matches()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+
+    use crate::*;
+
+    fn solve_sorted(kb: &KnowledgeBase, query: &str) -> Vec<String> {
+        let query = parse_query(query).unwrap();
+        let sn = make_base_node(Rc::new(query), kb);
+        let mut results = solve_all(sn);
+        results.sort();
+        return results;
+    }
+
+    #[test]
+    fn test_materialize() {
+
+        let mut kb = KnowledgeBase::new();
+        // A chain of 30 nodes, with a cycle back to the start.
+        for i in 0..30 {
+            let fact = parse_rule(&format!("edge({}, {}).", i, (i + 1) % 30)).unwrap();
+            add_rules!(&mut kb, fact);
+        }
+        for rule in ["node(a).", "node(99).", "node(5).",
+                     "path($X, $Y) :- edge($X, $Y).",
+                     "path($X, $Y) :- path($X, $Z), edge($Z, $Y).",
+                     "cycle($X) :- path($X, $X).",
+                     "isolated($X) :- node($X), not(path($X, $_)).",
+                     "pair($X, $Y) :- node($X), node($Y), not(edge($X, $Y))."] {
+            add_rules!(&mut kb, parse_rule(rule).unwrap());
+        }
+
+        let facts = materialize(&kb).unwrap();
+        assert_eq!(900, count_rules(&facts, "path/2"));
+        assert_eq!(30, count_rules(&facts, "cycle/1"));
+        assert!(facts.has_only_facts("path/2"));
+        assert_eq!(30, count_rules(&facts, "edge/2"));
+        // The original knowledge base is not changed.
+        assert_eq!(2, count_rules(&kb, "path/2"));
+
+        assert_eq!(vec!["$X = 99", "$X = a"], solve_sorted(&facts, "isolated($X)"));
+        assert_eq!(9, count_rules(&facts, "pair/2"));
+        let results = solve_sorted(&facts, "path(29, $Y)");
+        assert_eq!(30, results.len());
+        assert_eq!("$Y = 0", results[0]);
+
+        // Rules which are not Datalog.
+        for rule in ["nat(s($X)) :- nat($X).",
+                     "win($X) :- edge($X, $Y), not(win($Y)).",
+                     "far($X, $Y) :- edge($X, $Z).",
+                     "small($X) :- edge($X, $Y), $Y < 10.",
+                     "free($X) :- not(node($X))."] {
+            let mut kb2 = kb.clone();
+            add_rules!(&mut kb2, parse_rule(rule).unwrap());
+            let err = materialize(&kb2).unwrap_err();
+            assert!(matches!(err, SuironError::NotDatalog(_)), "{}", rule);
+        }
+
+    } // test_materialize()
+
+    fn sorted_facts(kb: &KnowledgeBase, key: &str) -> Vec<String> {
+        let mut facts: Vec<String> = kb.get(key).map_or(vec![], |rules| {
+            rules.iter().map(|r| r.to_string()).collect()
+        });
+        facts.sort();
+        return facts;
+    }
+
+    // Incremental updates give the same facts as evaluating from scratch.
+    #[test]
+    fn test_materialized_updates() {
+
+        let rules = ["path($X, $Y) :- edge($X, $Y).",
+                     "path($X, $Y) :- path($X, $Z), edge($Z, $Y).",
+                     "cycle($X) :- path($X, $X).",
+                     "start($X) :- node($X), not(path($_, $X)).",
+                     "node(1).", "node(2).", "node(3).", "node(4).",
+                     "start(4)."];
+        let make_kb = |edges: &Vec<(u32, u32)>| {
+            let mut kb = KnowledgeBase::new();
+            for rule in rules { add_rules!(&mut kb, parse_rule(rule).unwrap()); }
+            for (a, b) in edges {
+                add_rules!(&mut kb, parse_rule(&format!("edge({}, {}).", a, b)).unwrap());
+            }
+            kb
+        };
+
+        let mut edges = vec![(1, 2)];
+        let mut materialized = Materialized::new(&make_kb(&edges)).unwrap();
+
+        let changes = materialized.assert_fact(&parse_complex("edge(2, 3)").unwrap()).unwrap();
+        let added: Vec<String> = changes.added.iter().map(|f| f.to_string()).collect();
+        assert_eq!(vec!["edge(2, 3)", "path(2, 3)", "path(1, 3)"], added);
+        assert_eq!(vec!["start(3)"],
+                   changes.removed.iter().map(|f| f.to_string()).collect::<Vec<_>>());
+        edges.push((2, 3));
+
+        type Edges = Vec<(u32, u32)>;
+        let steps: Vec<(Edges, Edges)> = vec![
+            (vec![(3, 1)], vec![]),          // A cycle.
+            (vec![(3, 4), (4, 2)], vec![]),
+            (vec![], vec![(3, 1)]),
+            (vec![], vec![(2, 3), (4, 2)]),
+            (vec![(2, 3)], vec![(1, 2)]),
+            (vec![], vec![(9, 9)]),          // Not present.
+            (vec![(1, 2)], vec![(1, 2)]),    // Retracted, and asserted again.
+        ];
+        for (asserted, retracted) in steps {
+            let to_fact = |(a, b): &(u32, u32)| {
+                parse_complex(&format!("edge({}, {})", a, b)).unwrap()
+            };
+            let a: Vec<Unifiable> = asserted.iter().map(to_fact).collect();
+            let r: Vec<Unifiable> = retracted.iter().map(to_fact).collect();
+            materialized.update(&a, &r).unwrap();
+            edges.retain(|e| !retracted.contains(e));
+            for e in asserted { if !edges.contains(&e) { edges.push(e); } }
+
+            let expected = materialize(&make_kb(&edges)).unwrap();
+            for key in ["edge/2", "path/2", "cycle/1", "start/1"] {
+                assert_eq!(sorted_facts(&expected, key),
+                           sorted_facts(materialized.knowledge_base(), key), "{:?}", edges);
+            }
+        }
+
+        // Asserted facts of a predicate which has rules.
+        let changes = materialized.retract_fact(&parse_complex("start(4)").unwrap()).unwrap();
+        assert_eq!("start(4)", changes.removed[0].to_string());
+        let start_1 = parse_complex("start(1)").unwrap();
+        let changes = materialized.assert_fact(&start_1).unwrap();
+        assert_eq!(Changes::default(), changes);  // Already derived.
+        let changes = materialized.retract_fact(&start_1).unwrap();
+        assert_eq!(Changes::default(), changes);  // Still derived.
+
+        let err = materialized.assert_fact(&parse_complex("edge($X, 1)").unwrap());
+        assert!(matches!(err, Err(SuironError::NotDatalog(_))));
+
+    } // test_materialized_updates()
+
+} // test
@@ -0,0 +1,344 @@
+//! Functions to support Definite Clause Grammars (DCG).
+//!
+//! A grammar rule is written with an arrow (-->) instead of a neck (:-).
+//! The parser translates each grammar rule into an ordinary rule, by
+//! adding two arguments to every nonterminal: the list of words to parse,
+//! and the list of words which remain after parsing. For example,
+//!
+//! <pre>
+//!   sentence --> noun_phrase, verb_phrase.
+//!   noun_phrase --> [the], noun.
+//!   noun --> [dog] ; [cat].
+//! </pre>
+//!
+//! is translated to:
+//!
+//! <pre>
+//!   sentence($DCG_0, $DCG_1) :- noun_phrase($DCG_0, $DCG_2),
+//!                               verb_phrase($DCG_2, $DCG_1).
+//!   noun_phrase($DCG_0, $DCG_1) :- $DCG_0 = [the | $DCG_2],
+//!                                  noun($DCG_2, $DCG_1).
+//!   noun($DCG_0, $DCG_1) :- ($DCG_0 = [dog | $DCG_1] ; $DCG_0 = [cat | $DCG_1]).
+//! </pre>
+//!
+//! The body of a grammar rule may contain nonterminals, lists of
+//! terminals, goals in braces, such as `{ $N is $N0 + 1 }`, the cut (!),
+//! parentheses, and disjunctions (;).
+//!
+//! A grammar is called with phrase/2 or phrase/3:
+//!
+//! <pre>
+//!   phrase(sentence, [the, dog, barks])
+//!   phrase(noun_phrase, [the, dog, barks], $Rest)
+//! </pre>
+//!
+//! phrase() is expanded when it is parsed, so its first argument
+//! must be a nonterminal, not a variable.
+//
+// Cleve Lendon 2023
+
+use crate::str_to_chars;
+use crate::chars_to_string;
+
+use super::error::SuironError;
+use super::parse_terms::quoted_text;
+use super::rule::*;
+use super::s_linked_list::*;
+use super::unifiable::Unifiable;
+
+/// Finds the index of the DCG arrow (-->) in a vector of characters.
+///
+/// # Arguments
+/// * vector of characters
+/// # Return
+/// * Some(index) or None
+pub fn index_of_arrow(chrs: &[char]) -> Option<usize> {
+    let quoted = quoted_text(chrs);
+    for i in 0..chrs.len() {
+        let ch = chrs[i];
+        if quoted[i] || i + 2 >= chrs.len() { continue; }
+        if ch == '-' && chrs[i + 1] == '-' && chrs[i + 2] == '>' {
+            return Some(i);
+        }
+    }
+    return None;
+} // index_of_arrow()
+
+/// Translates a grammar rule into an ordinary rule.
+///
+/// # Arguments
+/// * head of grammar rule (text before -->)
+/// * body of grammar rule (text after -->)
+/// # Return
+/// * [Rule](../rule/struct.Rule.html) or error message
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let rule = parse_dcg_rule("greeting", "[hello], name").unwrap();
+/// println!("{}", rule);
+/// // Prints: greeting($DCG_0, $DCG_1) :- $DCG_0 = [hello | $DCG_2], name($DCG_2, $DCG_1).
+/// ```
+pub fn parse_dcg_rule(head: &str, body: &str) -> Result<Rule, SuironError> {
+
+    let head = head.trim();
+    let body = body.trim();
+    if body.is_empty() {
+        return Err(dcg_error("Missing body", head).into());
+    }
+    if split_top_level(head, ',').len() > 1 {
+        return Err(dcg_error("Pushback is not supported", head).into());
+    }
+
+    let mut counter = 2;
+    let new_head = nonterminal(head, "$DCG_0", "$DCG_1")?;
+    let new_body = translate_body(body, "$DCG_0", "$DCG_1", &mut counter)?;
+    return parse_rule(&format!("{} :- {}.", new_head, new_body));
+
+} // parse_dcg_rule()
+
+/// Expands phrase/2 and phrase/3 into a goal.
+///
+/// The goal `phrase(sentence, $Words)` is expanded to
+/// `sentence($Words, [])`, and `phrase(sentence, $Words, $Rest)` is
+/// expanded to `sentence($Words, $Rest)`.
+///
+/// # Arguments
+/// * arguments of phrase()
+/// # Return
+/// * [SComplex](../unifiable/enum.Unifiable.html#variant.SComplex)
+///   or None, if the arguments are invalid
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let args = parse_arguments("noun(singular), [dog]").unwrap();
+/// let goal = expand_phrase(&args).unwrap();
+/// println!("{}", goal);  // Prints: noun(singular, [dog], [])
+/// ```
+pub fn expand_phrase(args: &[Unifiable]) -> Option<Unifiable> {
+
+    if args.len() != 2 && args.len() != 3 { return None; }
+
+    let mut terms = match &args[0] {
+        Unifiable::Atom(_) => { vec![args[0].clone()] },
+        Unifiable::SComplex(terms) => { terms.clone() },
+        _ => { return None; },
+    };
+    terms.push(args[1].clone());
+    if args.len() == 3 { terms.push(args[2].clone()); }
+    else { terms.push(make_linked_list(false, vec![])); }
+    return Some(Unifiable::SComplex(terms));
+
+} // expand_phrase()
+
+// Translates the body of a grammar rule. The body consumes the words
+// in the first list, leaving the words in the second list.
+//
+// Arguments
+//    body of grammar rule
+//    name of input list variable
+//    name of output list variable
+//    counter for new variables
+// Return
+//    body of ordinary rule, or error message
+fn translate_body(body: &str, s0: &str, s: &str, counter: &mut usize)
+                  -> Result<String, String> {
+
+    // Each alternative consumes words from s0 to s.
+    let alternatives = split_top_level(body, ';');
+    if alternatives.len() > 1 {
+        let mut translated: Vec<String> = vec![];
+        for alternative in alternatives {
+            translated.push(translate_body(&alternative, s0, s, counter)?);
+        }
+        return Ok(format!("({})", translated.join(" ; ")));
+    }
+
+    let items = split_top_level(body, ',');
+    let last = items.len() - 1;
+    let mut translated: Vec<String> = vec![];
+    let mut input = s0.to_string();
+
+    for (i, item) in items.iter().enumerate() {
+        let output = if i == last { s.to_string() }
+                     else { *counter += 1; format!("$DCG_{}", *counter - 1) };
+        translated.push(translate_item(item, &input, &output, counter)?);
+        input = output;
+    }
+    return Ok(translated.join(", "));
+
+} // translate_body()
+
+// Translates one item of the body of a grammar rule.
+//
+// Arguments
+//    item: nonterminal, list, {goal}, ! or (body)
+//    name of input list variable
+//    name of output list variable
+//    counter for new variables
+// Return
+//    goal or error message
+fn translate_item(item: &str, s0: &str, s: &str, counter: &mut usize)
+                  -> Result<String, String> {
+
+    let item = item.trim();
+    if item.is_empty() {
+        return Err(dcg_error("Missing item", item));
+    }
+
+    if item == "!" {
+        return Ok(format!("!, {} = {}", s0, s));
+    }
+    if item == "[]" {
+        return Ok(format!("{} = {}", s0, s));
+    }
+    if let Some(words) = item.strip_prefix('[').and_then(|w| w.strip_suffix(']')) {
+        return Ok(format!("{} = [{} | {}]", s0, words.trim(), s));
+    }
+    if let Some(goal) = item.strip_prefix('{').and_then(|g| g.strip_suffix('}')) {
+        let goal = goal.trim();
+        if split_top_level(goal, ';').len() > 1 {
+            return Ok(format!("({}), {} = {}", goal, s0, s));
+        }
+        return Ok(format!("{}, {} = {}", goal, s0, s));
+    }
+    if let Some(body) = item.strip_prefix('(').and_then(|b| b.strip_suffix(')')) {
+        return translate_body(body, s0, s, counter);
+    }
+    return nonterminal(item, s0, s);
+
+} // translate_item()
+
+// Adds the two list arguments to a nonterminal.
+// For example, noun(singular) becomes noun(singular, $DCG_0, $DCG_1).
+//
+// Arguments
+//    nonterminal
+//    name of input list variable
+//    name of output list variable
+// Return
+//    complex term (string) or error message
+fn nonterminal(item: &str, s0: &str, s: &str) -> Result<String, String> {
+
+    let chrs = str_to_chars!(item);
+    if !chrs.first().is_some_and(|ch| ch.is_alphabetic()) {
+        return Err(dcg_error("Invalid nonterminal", item));
+    }
+    if chrs[chrs.len() - 1] == ')' {
+        let front = chars_to_string!(chrs[0..chrs.len() - 1]);
+        return Ok(format!("{}, {}, {})", front, s0, s));
+    }
+    if item.contains(['(', ' ']) {
+        return Err(dcg_error("Invalid nonterminal", item));
+    }
+    return Ok(format!("{}({}, {})", item, s0, s));
+
+} // nonterminal()
+
+// Splits a string at a separator, ignoring separators within
+// parentheses, brackets, braces and quotes.
+//
+// Arguments
+//    string to split
+//    separator, eg. comma
+// Return
+//    vector of strings
+fn split_top_level(to_split: &str, separator: char) -> Vec<String> {
+
+    let chrs = str_to_chars!(to_split);
+    let mut parts: Vec<String> = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    let quoted = quoted_text(&chrs);
+
+    for (i, ch) in chrs.iter().enumerate() {
+        if quoted[i] { continue; }
+        if *ch == '(' || *ch == '[' || *ch == '{' { depth += 1; }
+        else if *ch == ')' || *ch == ']' || *ch == '}' { depth -= 1; }
+        else if *ch == separator && depth == 0 {
+            parts.push(chars_to_string!(chrs[start..i]).trim().to_string());
+            start = i + 1;
+        }
+    }
+    parts.push(chars_to_string!(chrs[start..]).trim().to_string());
+    return parts;
+
+} // split_top_level()
+
+// Formats an error message for parse_dcg_rule().
+fn dcg_error(err: &str, bad: &str) -> String {
+    format!("parse_dcg_rule() - {}: {}", err, bad)
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    #[test]
+    fn test_parse_dcg_rule() {
+
+        let parse = |s: &str| parse_rule(s).unwrap().to_string();
+
+        assert_eq!("sentence($DCG_0, $DCG_1) :- noun_phrase($DCG_0, $DCG_2), \
+                    verb_phrase($DCG_2, $DCG_1).",
+                   parse("sentence --> noun_phrase, verb_phrase."));
+        assert_eq!("noun(singular, $DCG_0, $DCG_1) :- $DCG_0 = [dog | $DCG_1].",
+                   parse("noun(singular) --> [dog]."));
+        assert_eq!("digits($DCG_0, $DCG_1) :- $DCG_0 = $DCG_1.",
+                   parse("digits --> []."));
+        assert_eq!("tally($N, $DCG_0, $DCG_1) :- $DCG_0 = [a | $DCG_2], \
+                    tally($N0, $DCG_2, $DCG_3), $N = add($N0, 1), $DCG_3 = $DCG_1.",
+                   parse("tally($N) --> [a], tally($N0), { $N = $N0 + 1 }."));
+
+        assert!(parse_rule("a, [b] --> c.").is_err());
+        assert!(parse_rule("a --> $X.").is_err());
+
+    } // test_parse_dcg_rule()
+
+    #[test]
+    fn test_phrase() {
+
+        let mut kb = KnowledgeBase::new();
+        let rules = ["sentence --> noun_phrase($N), verb_phrase($N).",
+                     "noun_phrase($N) --> [the], noun($N).",
+                     "noun(singular) --> [dog] ; [cat].",
+                     "noun(plural) --> [dogs] ; [cats].",
+                     "verb_phrase(singular) --> [barks].",
+                     "verb_phrase(plural) --> ([bark] ; [sleep]), !.",
+                     "as(0) --> [].",
+                     "as($N) --> [a], as($N0), { $N is $N0 + 1 }.",
+                     "check($W) :- phrase(sentence, $W)."];
+        for r in rules {
+            match parse_rule(r) {
+                Ok(rule) => { add_rules!(&mut kb, rule); },
+                Err(err) => { panic!("Should be no parsing errors. {}", err); },
+            }
+        }
+
+        let query = parse_query("check([the, dog, barks])").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("", solve(sn));
+
+        let query = parse_query("check([the, dogs, barks])").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("No more.", solve(sn));
+
+        let query = parse_query("phrase(sentence, [the, cats, sleep])").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("", solve(sn));
+
+        let query = parse_query("phrase(noun_phrase($N), [the, cat, sleeps], $Rest)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$N = singular, $Rest = [sleeps]", solve(sn));
+
+        let query = parse_query("phrase(as($N), [a, a, a])").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$N = 3", solve(sn));
+
+    } // test_phrase()
+
+} // test
@@ -0,0 +1,319 @@
+//! Analyzes which predicates call which.
+//!
+//! The dependency graph of a knowledge base has an edge from each
+//! predicate to the predicates which are called in the bodies of its
+//! rules. Calls to built-in predicates, such as append() and print(),
+//! are not included.
+//!
+//! The graph can be exported in GraphViz dot format, for example:
+//!
+//! <pre>
+//!   dot -Tsvg rules.dot -o rules.svg
+//! </pre>
+//!
+//! Recursive predicates are drawn in bold, and predicates which are
+//! called but not defined are drawn with dashed lines.
+//
+// Cleve Lendon 2023
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+
+use super::diagnostics::collect_complex_goals;
+use super::error::SuironError;
+use super::goal::Goal;
+use super::knowledge_base::KnowledgeBase;
+
+/// The predicates of a knowledge base, and the predicates which they call.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DependencyGraph {
+    /// Called predicates, by calling predicate. Every predicate of the
+    /// knowledge base has an entry. Names are in alphabetical order.
+    pub edges: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl DependencyGraph {
+
+    /// Gets the predicates which a predicate calls.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"grandfather/2\"
+    /// # Return
+    /// * iterator over predicate names
+    pub fn calls(&self, predicate_name: &str) -> impl Iterator<Item = &str> {
+        self.edges.get(predicate_name).into_iter().flatten().map(|s| s.as_str())
+    }
+
+    /// Gets the predicates which call a predicate.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"father/2\"
+    /// # Return
+    /// * predicate names, in alphabetical order
+    pub fn callers(&self, predicate_name: &str) -> Vec<&str> {
+        self.edges.iter()
+                  .filter(|(_, called)| called.contains(predicate_name))
+                  .map(|(caller, _)| caller.as_str())
+                  .collect()
+    }
+
+    /// Determines whether a predicate is called, but not defined.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name
+    /// # Return
+    /// * true if undefined
+    pub fn is_undefined(&self, predicate_name: &str) -> bool {
+        !self.edges.contains_key(predicate_name) &&
+        self.edges.values().any(|called| called.contains(predicate_name))
+    }
+
+    /// Finds the groups of mutually recursive predicates.
+    ///
+    /// Each group is a set of predicates which can call each other,
+    /// directly or indirectly. A predicate which calls itself forms
+    /// a group of one.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * groups of predicate names, sorted
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let mut kb = KnowledgeBase::new();
+    /// add_rules!(&mut kb, parse_rule("even($N) :- $M = $N - 1, odd($M).").unwrap(),
+    ///                     parse_rule("odd($N) :- $M = $N - 1, even($M).").unwrap());
+    /// let graph = dependency_graph(&kb);
+    /// println!("{:?}", graph.recursive_groups());  // Prints: [["even/1", "odd/1"]]
+    /// ```
+    pub fn recursive_groups(&self) -> Vec<Vec<String>> {
+
+        let mut tarjan = Tarjan{ graph: self, index: BTreeMap::new(),
+                                 low: BTreeMap::new(), stack: vec![],
+                                 on_stack: BTreeSet::new(), groups: vec![] };
+        for name in self.edges.keys() {
+            if !tarjan.index.contains_key(name.as_str()) { tarjan.visit(name); }
+        }
+
+        let mut groups: Vec<Vec<String>> = tarjan.groups.into_iter().filter(|group| {
+            group.len() > 1 || self.calls(&group[0]).any(|c| c == group[0])
+        }).collect();
+        for group in groups.iter_mut() { group.sort(); }
+        groups.sort();
+        return groups;
+
+    } // recursive_groups()
+
+    /// Determines whether a predicate can call itself, directly or indirectly.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name
+    /// # Return
+    /// * true if recursive
+    pub fn is_recursive(&self, predicate_name: &str) -> bool {
+        self.recursive_groups().iter()
+            .any(|group| group.iter().any(|name| name == predicate_name))
+    }
+
+    /// Formats the graph in GraphViz dot format.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * dot text
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let graph = dependency_graph(&test_kb());
+    /// print!("{}", graph.to_dot());
+    /// // Prints:
+    /// // digraph predicates {
+    /// //     "father/2";
+    /// //     "grandfather/2";
+    /// //     "loves/2";
+    /// //     "grandfather/2" -> "father/2";
+    /// // }
+    /// ```
+    pub fn to_dot(&self) -> String {
+
+        let recursive: BTreeSet<String> = self.recursive_groups()
+                                              .into_iter().flatten().collect();
+        let mut undefined: BTreeSet<&str> = BTreeSet::new();
+
+        let mut out = "digraph predicates {\n".to_string();
+        for name in self.edges.keys() {
+            if recursive.contains(name) {
+                out += &format!("    {} [style=bold];\n", quote(name));
+            } else {
+                out += &format!("    {};\n", quote(name));
+            }
+        }
+        for called in self.edges.values().flatten() {
+            if !self.edges.contains_key(called) { undefined.insert(called); }
+        }
+        for name in undefined {
+            out += &format!("    {} [style=dashed];\n", quote(name));
+        }
+        for (caller, called) in &self.edges {
+            for c in called {
+                out += &format!("    {} -> {};\n", quote(caller), quote(c));
+            }
+        }
+        out += "}\n";
+        return out;
+
+    } // to_dot()
+
+    /// Writes the graph to a file in GraphViz dot format.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * file name
+    /// # Return
+    /// * Ok or error
+    pub fn write_dot(&self, file_name: &str) -> Result<(), SuironError> {
+        fs::write(file_name, self.to_dot())?;
+        return Ok(());
+    }
+
+} // impl DependencyGraph
+
+/// Builds the dependency graph of a knowledge base.
+///
+/// # Arguments
+/// * knowledge base
+/// # Return
+/// * [DependencyGraph](struct.DependencyGraph.html)
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let graph = dependency_graph(&kb);
+/// let called: Vec<&str> = graph.calls("grandfather/2").collect();
+/// println!("{:?}", called);  // Prints: ["father/2"]
+/// ```
+pub fn dependency_graph(kb: &KnowledgeBase) -> DependencyGraph {
+
+    let mut graph = DependencyGraph::default();
+    for name in kb.predicates() {
+        let mut called: BTreeSet<String> = BTreeSet::new();
+        for rule in kb.rules(name) {
+            let mut goals: Vec<&Goal> = vec![];
+            collect_complex_goals(&rule.body, &mut goals);
+            for goal in goals {
+                if let Ok(key) = goal.key() { called.insert(key); }
+            }
+        }
+        graph.edges.insert(name.to_string(), called);
+    }
+    return graph;
+
+} // dependency_graph()
+
+// Quotes a predicate name for dot format.
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// State of Tarjan's algorithm, which finds the strongly connected
+// components of the graph.
+struct Tarjan<'a> {
+    graph: &'a DependencyGraph,
+    index: BTreeMap<&'a str, usize>,
+    low: BTreeMap<&'a str, usize>,
+    stack: Vec<&'a str>,
+    on_stack: BTreeSet<&'a str>,
+    groups: Vec<Vec<String>>,
+}
+
+impl<'a> Tarjan<'a> {
+
+    // Visits a predicate, and the predicates which it calls.
+    fn visit(&mut self, name: &'a str) {
+
+        let n = self.index.len();
+        self.index.insert(name, n);
+        self.low.insert(name, n);
+        self.stack.push(name);
+        self.on_stack.insert(name);
+
+        for called in self.graph.calls(name) {
+            if !self.index.contains_key(called) {
+                self.visit(called);
+                let low = self.low[name].min(self.low[called]);
+                self.low.insert(name, low);
+            } else if self.on_stack.contains(called) {
+                let low = self.low[name].min(self.index[called]);
+                self.low.insert(name, low);
+            }
+        }
+
+        if self.low[name] == self.index[name] {
+            let mut group: Vec<String> = vec![];
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(member);
+                group.push(member.to_string());
+                if member == name { break; }
+            }
+            self.groups.push(group);
+        }
+
+    } // visit()
+
+} // impl Tarjan
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::*;
+
+    #[test]
+    fn test_dependency_graph() {
+
+        let mut kb = test_kb();
+        add_rules!(&mut kb,
+            parse_rule("ancestor($X, $Y) :- father($X, $Y).").unwrap(),
+            parse_rule("ancestor($X, $Y) :- father($X, $Z), ancestor($Z, $Y).").unwrap(),
+            parse_rule("even(0).").unwrap(),
+            parse_rule("even($N) :- $M = $N - 1, odd($M).").unwrap(),
+            parse_rule("odd($N) :- $M = $N - 1, even($M), not(mother($N)).").unwrap());
+
+        let graph = dependency_graph(&kb);
+        let called: Vec<&str> = graph.calls("ancestor/2").collect();
+        assert_eq!(vec!["ancestor/2", "father/2"], called);
+        assert_eq!(vec!["ancestor/2", "grandfather/2"], graph.callers("father/2"));
+        assert_eq!(0, graph.calls("loves/2").count());
+        assert_eq!(0, graph.calls("nothing/0").count());
+        assert!(graph.is_undefined("mother/1"));
+        assert!(!graph.is_undefined("father/2"));
+
+        let expected = vec![vec!["ancestor/2".to_string()],
+                            vec!["even/1".to_string(), "odd/1".to_string()]];
+        assert_eq!(expected, graph.recursive_groups());
+        assert!(graph.is_recursive("odd/1"));
+        assert!(!graph.is_recursive("grandfather/2"));
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph predicates {\n"));
+        assert!(dot.contains("    \"ancestor/2\" [style=bold];\n"));
+        assert!(dot.contains("    \"loves/2\";\n"));
+        assert!(dot.contains("    \"mother/1\" [style=dashed];\n"));
+        assert!(dot.contains("    \"odd/1\" -> \"even/1\";\n"));
+        assert!(dot.ends_with("}\n"));
+
+        let empty = dependency_graph(&KnowledgeBase::new());
+        assert!(empty.recursive_groups().is_empty());
+        assert_eq!("digraph predicates {\n}\n", empty.to_dot());
+
+    } // test_dependency_graph()
+
+} // test
@@ -0,0 +1,388 @@
+//! Functions for reporting warnings about Suiron source code.
+//!
+//! When a knowledge base is loaded from a file, Suiron checks each
+//! rule for common mistakes, such as a variable which occurs only
+//! once. A misspelled variable ($Chlid instead of $Child) is usually
+//! a singleton, and causes the rule to fail silently.
+//!
+//! By default, warnings are written to standard error. An application
+//! can send them elsewhere by calling set_diagnostics(). The diagnostics
+//! sink is local to the thread.
+//!
+//! To suppress the warning for a variable which is deliberately unused,
+//! use the anonymous variable, $_. (In Prolog syntax mode, variables
+//! which begin with an underscore, such as _Name, are not reported.)
+//!
+//! After a file has been loaded, Suiron also warns about goals which
+//! call a predicate which has no facts or rules, and is not a built-in
+//! predicate. Such goals simply fail at runtime.
+//
+// Cleve Lendon 2023
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::goal::Goal;
+use super::knowledge_base::KnowledgeBase;
+use super::operator::Operator;
+use super::rule::Rule;
+use super::unifiable::Unifiable;
+
+// A function which receives warnings.
+type Sink = Box<dyn FnMut(&str)>;
+
+thread_local! {
+    static DIAGNOSTICS: RefCell<Option<Sink>> = RefCell::new(None);
+}
+
+// Puts a sink back into DIAGNOSTICS when it is dropped, even if the
+// code which took the sink out panics.
+struct SinkGuard(Option<Sink>);
+
+impl Drop for SinkGuard {
+    fn drop(&mut self) {
+        let sink = self.0.take();
+        let _ = DIAGNOSTICS.try_with(|d| { *d.borrow_mut() = sink; });
+    }
+} // Drop for SinkGuard
+
+/// Sets the sink which receives warnings.
+///
+/// # Arguments
+/// * function which receives each warning
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// set_diagnostics(|warning| { println!("Suiron: {}", warning); });
+/// let mut kb = KnowledgeBase::new();
+/// load_kb_from_file(&mut kb, "SUIRON_TEST_DIR/kings.txt");
+/// reset_diagnostics();
+/// ```
+pub fn set_diagnostics(sink: impl FnMut(&str) + 'static) {
+    DIAGNOSTICS.with(|d| { *d.borrow_mut() = Some(Box::new(sink)); });
+} // set_diagnostics()
+
+/// Restores the default diagnostics sink, which is standard error.
+pub fn reset_diagnostics() {
+    DIAGNOSTICS.with(|d| { *d.borrow_mut() = None; });
+} // reset_diagnostics()
+
+/// Reports a warning to the diagnostics sink.
+///
+/// # Note
+/// While the sink is running, any warnings which it reports are
+/// written to standard error.
+///
+/// # Arguments
+/// * warning
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// warn("Something looks wrong.");
+/// ```
+pub fn warn(warning: &str) {
+    // The sink is taken out of DIAGNOSTICS while it runs, so that it can
+    // call warn() itself.
+    let mut guard = SinkGuard(DIAGNOSTICS.with(|d| d.borrow_mut().take()));
+    match guard.0.as_mut() {
+        Some(sink) => { sink(warning); },
+        None => { eprintln!("Warning: {}", warning); },
+    }
+} // warn()
+
+/// Runs a function, and collects the warnings which it reports.
+///
+/// Afterwards, the previous diagnostics sink is restored, even if
+/// the function panics.
+///
+/// # Arguments
+/// * function to run
+/// # Return
+/// * (warnings, result of the function)
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let (warnings, _) = with_captured_warnings(|| {
+///     let rule = parse_rule("grandparent($X, $Z) :- parent($X, $Y), \
+///                            parent($Y, $Chlid).").unwrap();
+///     warn_singletons(&rule);
+/// });
+/// assert_eq!(2, warnings.len());
+/// ```
+pub fn with_captured_warnings<T>(f: impl FnOnce() -> T) -> (Vec<String>, T) {
+
+    let warnings = Rc::new(RefCell::new(vec![]));
+    let collector = Rc::clone(&warnings);
+    let previous = SinkGuard(DIAGNOSTICS.with(|d| d.borrow_mut().take()));
+    set_diagnostics(move |w| { collector.borrow_mut().push(w.to_string()); });
+
+    let result = f();
+    drop(previous);
+
+    let warnings = warnings.borrow().clone();
+    return (warnings, result);
+
+} // with_captured_warnings()
+
+/// Finds the variables which occur only once in a rule.
+///
+/// # Arguments
+/// * rule
+/// # Return
+/// * names of singleton variables, in order of occurrence
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let rule = parse_rule("parent($X, $Child) :- father($X, $Chlid).").unwrap();
+/// println!("{:?}", singleton_variables(&rule));
+/// // Prints: ["$Child", "$Chlid"]
+/// ```
+pub fn singleton_variables(rule: &Rule) -> Vec<String> {
+    let mut counts: Vec<(String, usize)> = vec![];
+    count_term_vars(&rule.head, &mut counts);
+    count_goal_vars(&rule.body, &mut counts);
+    return counts.into_iter()
+                 .filter(|(name, n)| *n == 1 && !name.starts_with("$_"))
+                 .map(|(name, _)| name)
+                 .collect();
+} // singleton_variables()
+
+/// Reports a warning for each singleton variable in a rule.
+///
+/// This function is called for each rule which is loaded from a file.
+///
+/// # Arguments
+/// * rule
+pub fn warn_singletons(rule: &Rule) {
+    for name in singleton_variables(rule) {
+        warn(&format!("Singleton variable {} in: {}", name, rule));
+    }
+} // warn_singletons()
+
+/// Finds predicates which are called in the bodies of rules, but are
+/// not defined in the knowledge base.
+///
+/// Built-in predicates, such as append() and print(), are not included.
+///
+/// # Arguments
+/// * knowledge base
+/// # Return
+/// * (undefined predicate, calling predicate), sorted by name, eg.:
+///   ("parnet/2", "grandparent/2")
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// let rule = parse_rule("grandparent($X, $Z) :- parnet($X, $Y), \
+///                        parent($Y, $Z).").unwrap();
+/// add_rules!(&mut kb, rule);
+/// let rule = parse_rule("parent(Godwin, Harold).").unwrap();
+/// add_rules!(&mut kb, rule);
+/// println!("{:?}", undefined_predicates(&kb));
+/// // Prints: [("parnet/2", "grandparent/2")]
+/// ```
+pub fn undefined_predicates(kb: &KnowledgeBase) -> Vec<(String, String)> {
+
+    let mut undefined: Vec<(String, String)> = vec![];
+    for (caller, rules) in kb.iter() {
+        for rule in rules.iter() {
+            let mut goals: Vec<&Goal> = vec![];
+            collect_complex_goals(&rule.body, &mut goals);
+            for goal in goals {
+                if let Ok(key) = goal.key() {
+                    let pair = (key, caller.to_string());
+                    if !kb.contains_key(&pair.0) && !undefined.contains(&pair) {
+                        undefined.push(pair);
+                    }
+                }
+            }
+        }
+    }
+    undefined.sort();
+    return undefined;
+
+} // undefined_predicates()
+
+/// Reports a warning for each undefined predicate in a knowledge base.
+///
+/// This function is called after a knowledge base is loaded from a file.
+/// See [undefined_predicates()](fn.undefined_predicates.html).
+///
+/// # Arguments
+/// * knowledge base
+pub fn warn_undefined(kb: &KnowledgeBase) {
+    for (predicate, caller) in undefined_predicates(kb) {
+        warn(&format!("Undefined predicate {}, called by {}", predicate, caller));
+    }
+} // warn_undefined()
+
+// Collects the complex goals (calls to facts and rules) in a goal.
+//
+// Arguments
+//    goal
+//    complex goals
+pub(crate) fn collect_complex_goals<'a>(goal: &'a Goal, goals: &mut Vec<&'a Goal>) {
+    match goal {
+        Goal::OperatorGoal(op) => {
+            let subgoals = match op {
+                Operator::And(g) | Operator::Or(g) |
+                Operator::Time(g) | Operator::Not(g) => { g },
+            };
+            for g in subgoals { collect_complex_goals(g, goals); }
+        },
+        Goal::ComplexGoal(_) => { goals.push(goal); },
+        _ => {},
+    }
+} // collect_complex_goals()
+
+// Counts the occurrences of logic variables in a term.
+//
+// Arguments
+//    term
+//    counts - (name, count) of variables
+fn count_term_vars(term: &Unifiable, counts: &mut Vec<(String, usize)>) {
+    match term {
+        Unifiable::LogicVar{id: _, name} => {
+            match counts.iter_mut().find(|(n, _)| n == name) {
+                Some((_, count)) => { *count += 1; },
+                None => { counts.push((name.to_string(), 1)); },
+            }
+        },
+        Unifiable::SComplex(terms) |
+        Unifiable::SFunction{name: _, terms} => {
+            for t in terms { count_term_vars(t, counts); }
+        },
+        Unifiable::SLinkedList{term, next, count: _, tail_var: _} => {
+            count_term_vars(term, counts);
+            count_term_vars(next, counts);
+        },
+        _ => {},
+    }
+} // count_term_vars()
+
+// Counts the occurrences of logic variables in a goal.
+//
+// Arguments
+//    goal
+//    counts - (name, count) of variables
+fn count_goal_vars(goal: &Goal, counts: &mut Vec<(String, usize)>) {
+    match goal {
+        Goal::OperatorGoal(op) => {
+            let goals = match op {
+                Operator::And(goals) | Operator::Or(goals) |
+                Operator::Time(goals) | Operator::Not(goals) => { goals },
+            };
+            for g in goals { count_goal_vars(g, counts); }
+        },
+        Goal::BuiltInGoal(bip) => {
+            if let Some(terms) = &bip.terms {
+                for t in terms { count_term_vars(t, counts); }
+            }
+        },
+        Goal::ComplexGoal(term) => { count_term_vars(term, counts); },
+        Goal::Nil => {},
+    }
+} // count_goal_vars()
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::*;
+
+    #[test]
+    fn test_singleton_variables() {
+
+        let rule = parse_rule("grandparent($X, $Z) :- parent($X, $Y), \
+                               parent($Y, $Chlid).").unwrap();
+        assert_eq!(vec!["$Z", "$Chlid"], singleton_variables(&rule));
+
+        let rule = parse_rule("last([$_ | $T], $L) :- last($T, $L).").unwrap();
+        assert!(singleton_variables(&rule).is_empty());
+
+        let rule = parse_rule("test($X) :- not(f($Y)), $Z = [$X | $W], \
+                               $W = g(add($Z, 1)), print($Q).").unwrap();
+        assert_eq!(vec!["$Y", "$Q"], singleton_variables(&rule));
+
+        set_prolog_syntax(true);
+        let rule = parse_rule("first([H | _Rest], H).").unwrap();
+        set_prolog_syntax(false);
+        assert!(singleton_variables(&rule).is_empty());
+
+        let rule = parse_rule("color(red).").unwrap();
+        assert!(singleton_variables(&rule).is_empty());
+
+    } // test_singleton_variables()
+
+    #[test]
+    fn test_captured_warnings() {
+
+        let (warnings, _) = with_captured_warnings(|| {
+            let rule = parse_rule("parent($X, $Child) :- father($X, $Chlid).").unwrap();
+            warn_singletons(&rule);
+        });
+        assert_eq!(vec!["Singleton variable $Child in: \
+                         parent($X, $Child) :- father($X, $Chlid).",
+                        "Singleton variable $Chlid in: \
+                         parent($X, $Child) :- father($X, $Chlid)."], warnings);
+
+    } // test_captured_warnings()
+
+    #[test]
+    fn test_sink_guard() {
+
+        // A sink can report warnings.
+        let (warnings, _) = with_captured_warnings(|| {
+            set_diagnostics(|w| { if w == "outer" { warn("inner"); } });
+            warn("outer");
+            warn("outer");
+        });
+        assert!(warnings.is_empty());
+
+        // The previous sink is restored after a panic.
+        let (warnings, _) = with_captured_warnings(|| {
+            let result = std::panic::catch_unwind(|| {
+                with_captured_warnings(|| { panic!("boom"); })
+            });
+            assert!(result.is_err());
+            warn("after panic");
+        });
+        assert_eq!(vec!["after panic"], warnings);
+
+        let (warnings, _) = with_captured_warnings(|| {
+            set_diagnostics(|_| { panic!("boom"); });
+            let result = std::panic::catch_unwind(|| { warn("first"); });
+            assert!(result.is_err());
+            let result = std::panic::catch_unwind(|| { warn("second"); });
+            assert!(result.is_err());
+        });
+        assert!(warnings.is_empty());
+
+    } // test_sink_guard()
+
+    #[test]
+    fn test_undefined_predicates() {
+
+        let mut kb = KnowledgeBase::new();
+        let rules = ["grandparent($X, $Z) :- parnet($X, $Y), parent($Y, $Z).",
+                     "parent(Godwin, Harold).",
+                     "sibling($X, $Y) :- parent($P, $X), parent($P, $Y), \
+                                         not($X == $Y), print($X).",
+                     "uncle($U, $N) :- sibling($U, $P), (parent($P, $N); \
+                                       parnet($P, $N); male($U))."];
+        for r in rules { add_rules!(&mut kb, parse_rule(r).unwrap()); }
+
+        let (warnings, _) = with_captured_warnings(|| { warn_undefined(&kb); });
+        assert_eq!(vec!["Undefined predicate male/1, called by uncle/2",
+                        "Undefined predicate parnet/2, called by grandparent/2",
+                        "Undefined predicate parnet/2, called by uncle/2"],
+                   warnings);
+
+    } // test_undefined_predicates()
+
+} // test
@@ -0,0 +1,281 @@
+//! A discrimination tree, which indexes the heads of facts and rules.
+//!
+//! The arguments of each head are flattened into a sequence of symbols,
+//! in prefix order. For example, the head `owns(Mary, car(red, $Year))`
+//! becomes:
+//!
+//! <pre>
+//!   Mary  car/2  red  *
+//! </pre>
+//!
+//! where `*` stands for a variable, or any term which is not indexed
+//! (lists, floats, functions). The sequences are stored in a tree, whose
+//! leaves hold the indices of the facts and rules.
+//!
+//! To find the facts and rules which may match a goal, the goal is
+//! flattened in the same way, and the tree is searched. A variable in
+//! a head matches any subterm of the goal, and an unbound variable in
+//! the goal matches any subterm of a head. The candidates must still be
+//! unified with the goal, but a fully or partially ground goal skips the
+//! facts and rules which cannot possibly match.
+//!
+//! Indexing is enabled with
+//! [set_indexing()](../knowledge_base/struct.KnowledgeBase.html#method.set_indexing).
+//
+// Cleve Lendon 2023
+
+use std::collections::HashMap;
+
+use super::rule::Rule;
+use super::symbol::Symbol;
+use super::substitution_set::*;
+use super::unifiable::Unifiable;
+
+// A key in a flattened term.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Atom(Symbol),
+    SString(String),
+    SInteger(i64),
+    SRational(i64, i64),
+    Functor(String, usize),   // name and arity
+    Var,                      // variable, or any term which is not indexed
+}
+
+impl Key {
+    // Number of arguments which follow the symbol.
+    fn arity(&self) -> usize {
+        match self {
+            Key::Functor(_, arity) => { *arity },
+            _ => { 0 },
+        }
+    }
+} // impl Key
+
+// A node of the tree. Leaf nodes hold indices of facts and rules.
+#[derive(Debug, Clone, Default)]
+struct Node {
+    children: HashMap<Key, Node>,
+    rules: Vec<usize>,
+}
+
+/// Indexes the facts and rules of one predicate by their heads.
+#[derive(Debug, Clone, Default)]
+pub struct DiscriminationTree {
+    root: Node,
+}
+
+impl DiscriminationTree {
+
+    /// Creates a discrimination tree from a list of facts and rules.
+    ///
+    /// The facts and rules should all belong to the same predicate.
+    ///
+    /// # Arguments
+    /// * facts and rules
+    /// # Return
+    /// * discrimination tree
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let rules = vec![parse_rule("owns(Mary, car(red, 2019)).").unwrap(),
+    ///                  parse_rule("owns(John, bike(blue)).").unwrap()];
+    /// let tree = DiscriminationTree::new(&rules);
+    /// ```
+    pub fn new(rules: &[Rule]) -> Self {
+        let mut tree = DiscriminationTree::default();
+        for (index, rule) in rules.iter().enumerate() {
+            tree.insert(&rule.head, index);
+        }
+        return tree;
+    } // new()
+
+    /// Adds the head of a fact or rule to the tree.
+    ///
+    /// # Arguments
+    /// * head of fact or rule
+    /// * index of fact or rule
+    pub fn insert(&mut self, head: &Unifiable, index: usize) {
+        let mut node = &mut self.root;
+        for (symbol, _) in flatten_arguments(head, None) {
+            node = node.children.entry(symbol).or_default();
+        }
+        node.rules.push(index);
+    } // insert()
+
+    /// Finds the facts and rules which may match a goal.
+    ///
+    /// If the goal has no ground arguments, the tree can't eliminate
+    /// any facts or rules, and the function returns None.
+    ///
+    /// # Arguments
+    /// * goal (complex term)
+    /// * substitution set
+    /// # Return
+    /// * indices of facts and rules, in ascending order, or None
+    /// # Usage
+    /// ```
+    /// use std::rc::Rc;
+    /// use suiron::*;
+    ///
+    /// let rules = vec![parse_rule("owns(Mary, car(red, 2019)).").unwrap(),
+    ///                  parse_rule("owns(John, bike(blue)).").unwrap(),
+    ///                  parse_rule("owns($X, car($C, 2020)) :- rich($X).").unwrap()];
+    /// let tree = DiscriminationTree::new(&rules);
+    ///
+    /// let goal = parse_complex("owns($Who, car($Color, $Year))").unwrap();
+    /// let ss = empty_ss!();
+    /// println!("{:?}", tree.candidates(&goal, &ss));  // Prints: Some([0, 2])
+    /// ```
+    pub fn candidates(&self, goal: &Unifiable, ss: &SubstitutionSet) -> Option<Vec<usize>> {
+        let query = flatten_arguments(goal, Some(ss));
+        if query.iter().all(|(symbol, _)| *symbol == Key::Var) { return None; }
+        let mut indices: Vec<usize> = vec![];
+        collect(&self.root, &query, &mut indices);
+        indices.sort_unstable();
+        return Some(indices);
+    } // candidates()
+
+} // impl DiscriminationTree
+
+// Collects the indices of the facts and rules which match a flattened query.
+//
+// Arguments
+//    node
+//    query - remaining symbols, with the size of each subterm
+//    indices of facts and rules
+fn collect(node: &Node, query: &[(Key, usize)], indices: &mut Vec<usize>) {
+    match query.first() {
+        None => { indices.extend(&node.rules); },
+        Some((Key::Var, _)) => {
+            // A variable in the goal matches any subterm.
+            let mut nodes: Vec<&Node> = vec![];
+            skip_term(node, 1, &mut nodes);
+            for n in nodes { collect(n, &query[1..], indices); }
+        },
+        Some((symbol, size)) => {
+            // A variable in the head matches the whole subterm.
+            if let Some(child) = node.children.get(&Key::Var) {
+                collect(child, &query[*size..], indices);
+            }
+            if let Some(child) = node.children.get(symbol) {
+                collect(child, &query[1..], indices);
+            }
+        },
+    }
+} // collect()
+
+// Finds the nodes which follow a number of complete subterms.
+//
+// Arguments
+//    node
+//    number of subterms to skip
+//    nodes after the subterms
+fn skip_term<'a>(node: &'a Node, n: usize, nodes: &mut Vec<&'a Node>) {
+    if n == 0 { nodes.push(node); return; }
+    for (symbol, child) in &node.children {
+        skip_term(child, n - 1 + symbol.arity(), nodes);
+    }
+} // skip_term()
+
+// Flattens the arguments of a complex term into a list of symbols.
+// If a substitution set is given, bound variables are replaced by
+// their values.
+//
+// Arguments
+//    complex term
+//    substitution set (optional)
+// Return
+//    symbols, with the size of the subterm which each one begins
+fn flatten_arguments(term: &Unifiable, ss: Option<&SubstitutionSet>)
+                     -> Vec<(Key, usize)> {
+    let mut symbols: Vec<(Key, usize)> = vec![];
+    if let Unifiable::SComplex(terms) = term {
+        for t in &terms[1..] { flatten(t, ss, &mut symbols); }
+    }
+    return symbols;
+} // flatten_arguments()
+
+// Flattens a term into a list of symbols.
+//
+// Arguments
+//    term
+//    substitution set (optional)
+//    symbols
+fn flatten(term: &Unifiable, ss: Option<&SubstitutionSet>,
+           symbols: &mut Vec<(Key, usize)>) {
+
+    let term = match (term, ss) {
+        (Unifiable::LogicVar{ .. }, Some(ss)) => {
+            get_ground_term(term, ss).unwrap_or(term)
+        },
+        _ => { term },
+    };
+
+    let start = symbols.len();
+    let symbol = match term {
+        Unifiable::Atom(s)    => { Key::Atom(s.clone()) },
+        Unifiable::SString(s) => { Key::SString(s.to_string()) },
+        Unifiable::SInteger(i) => { Key::SInteger(*i) },
+        Unifiable::SRational(n, d) => { Key::SRational(*n, *d) },
+        Unifiable::SComplex(terms) => {
+            match &terms[0] {
+                Unifiable::Atom(functor) => {
+                    symbols.push((Key::Functor(functor.to_string(), terms.len() - 1), 0));
+                    for t in &terms[1..] { flatten(t, ss, symbols); }
+                    symbols[start].1 = symbols.len() - start;
+                    return;
+                },
+                _ => { Key::Var },
+            }
+        },
+        _ => { Key::Var },
+    };
+    symbols.push((symbol, 1));
+
+} // flatten()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    #[test]
+    fn test_candidates() {
+
+        let rules: Vec<Rule> = ["owns(Mary, car(red, 2019)).",
+                                "owns(John, bike(blue)).",
+                                "owns($X, car($C, 2020)) :- rich($X).",
+                                "owns(Mary, [a, b]).",
+                                "owns(Alice, car(blue, 2019)).",
+                                "owns($X, $Y) :- gift($X, $Y)."]
+                               .iter().map(|r| parse_rule(r).unwrap()).collect();
+        let tree = DiscriminationTree::new(&rules);
+        let ss = empty_ss!();
+
+        let candidates = |goal: &str| {
+            let goal = parse_complex(goal).unwrap();
+            tree.candidates(&goal, &ss)
+        };
+
+        assert_eq!(Some(vec![0, 2, 3, 5]), candidates("owns(Mary, $X)"));
+        assert_eq!(Some(vec![0, 2, 3, 4, 5]), candidates("owns($W, car($C, $Y))"));
+        assert_eq!(Some(vec![2, 3, 5]), candidates("owns($W, car(blue, 2020))"));
+        assert_eq!(Some(vec![3, 4, 5]), candidates("owns($W, car(blue, 2019))"));
+        assert_eq!(Some(vec![1, 2, 5]), candidates("owns(John, $What)"));
+        // Lists are not indexed.
+        assert_eq!(None, candidates("owns($W, [a, b])"));
+        assert_eq!(None, candidates("owns($W, $What)"));
+
+        // Bound variables are replaced by their values.
+        let x = logic_var!(1, "$X");
+        let ss = x.unify(&atom!("Alice"), &ss).unwrap();
+        let goal = scomplex!(atom!("owns"), x, logic_var!(2, "$Y"));
+        assert_eq!(Some(vec![2, 4, 5]), tree.candidates(&goal, &ss));
+
+    } // test_candidates()
+
+} // test
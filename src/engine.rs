@@ -0,0 +1,850 @@
+//! A thread-safe engine, which lets several threads query one knowledge base.
+//!
+//! Terms, solution nodes and knowledge bases are built with Rc, so they
+//! cannot be shared between threads. An [Engine](struct.Engine.html) holds
+//! a knowledge base in [binary format](../binary_kb/index.html). It is
+//! Send and Sync, so it can be cloned, or put in a static, and shared by
+//! the threads of a web server, for example. (Clones share the same data.)
+//!
+//! The first time a thread queries the engine, the knowledge base is
+//! decoded into a copy for that thread. The copy is kept, and reused by
+//! the thread's later queries. When an engine (and all of its clones)
+//! has been dropped, a thread discards its copy the next time it uses
+//! any engine. Queries are passed as text, and solutions
+//! are returned as strings, so that they can be sent between threads.
+//!
+//! External predicates cannot be copied. Instead, they can be registered
+//! in each thread's copy by an initializer. See
+//! [with_initializer()](struct.Engine.html#method.with_initializer).
+//!
+//! Each engine has a [QueryConfig](../query_config/struct.QueryConfig.html),
+//! which holds the maximum proof depth, the maximum number of solutions,
+//! the float division setting and the random seed. It applies to all of
+//! the engine's queries, on every thread, and is not affected by the
+//! settings of the thread. See [with_config()](struct.Engine.html#method.with_config).
+//! Other settings, such as the
+//! [binding strategy](../trail/fn.set_binding_strategy.html), belong to
+//! each thread. They apply to the queries which the thread runs.
+//!
+//! Queries are parsed with the [operators](../user_operators/index.html)
+//! of the knowledge base. Each thread's copy keeps a cache of the engine's
+//! parsed queries, so that a query which is asked repeatedly is parsed
+//! only once.
+//!
+//! An engine can also run queries on a pool of worker threads. See
+//! [spawn_pool()](struct.Engine.html#method.spawn_pool). Each query which
+//! is submitted to the pool is answered through a channel, and can be
+//! cancelled, or given its own time limit.
+//!
+// Cleve Lendon 2023
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, Weak};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use crate::*;
+
+// Default time limit of a query which is submitted to a pool.
+const TIME_LIMIT: u64 = 1000; // milliseconds
+
+// Maximum number of queries in the query cache of a thread's copy.
+const QUERY_CACHE_SIZE: usize = 256;
+
+// Sets up external predicates in a thread's copy of the knowledge base.
+type Initializer = dyn Fn(&mut KnowledgeBase) + Send + Sync;
+
+// Parsed queries, by query text and Prolog syntax setting.
+type QueryCache = HashMap<(String, bool), Goal>;
+
+// A thread's copy of a knowledge base, with its engine and query cache.
+type KbCopy = (Weak<Shared>, Rc<KnowledgeBase>, Rc<RefCell<QueryCache>>);
+
+// Each engine has a unique ID, which identifies its copies.
+static NEXT_ENGINE_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    // This thread's copies of knowledge bases, by engine ID.
+    static COPIES: RefCell<HashMap<u64, KbCopy>> = RefCell::new(HashMap::new());
+}
+
+// The result of a query which was submitted to a pool.
+type QueryResult = Result<Vec<String>, SuironError>;
+
+// The data which clones of an engine share.
+struct Shared {
+    id: u64,
+    bytes: Vec<u8>,
+    indexing: bool,
+    initializer: Option<Box<Initializer>>,
+}
+
+/// A knowledge base which can be queried from several threads.
+///
+/// See the [module documentation](index.html).
+///
+/// # Usage
+/// ```
+/// use std::thread;
+/// use suiron::*;
+///
+/// let engine = Engine::new(&test_kb()).unwrap();
+///
+/// let handles: Vec<_> = ["Leonard", "Penny"].iter().map(|name| {
+///     let engine = engine.clone();
+///     let query = format!("loves({}, $Whom)", name);
+///     thread::spawn(move || engine.solve_all(&query, None))
+/// }).collect();
+///
+/// for handle in handles {
+///     println!("{:?}", handle.join().unwrap());
+/// }
+/// // Prints:
+/// // Ok(["$Whom = Penny"])
+/// // Ok(["$Whom = Leonard"])
+/// ```
+#[derive(Clone)]
+pub struct Engine {
+    shared: Arc<Shared>,
+    config: QueryConfig,
+}
+
+impl Engine {
+
+    /// Creates an engine from a knowledge base.
+    ///
+    /// # Arguments
+    /// * knowledge base
+    /// # Return
+    /// * Engine, or [SuironError](../error/enum.SuironError.html)
+    pub fn new(kb: &KnowledgeBase) -> Result<Self, SuironError> {
+        return Engine::make(kb, None);
+    }
+
+    /// Creates an engine from a knowledge base, with an initializer.
+    ///
+    /// The initializer is called for each thread's copy of the knowledge
+    /// base, after it has been decoded. It can register external
+    /// predicates, which cannot be copied.
+    ///
+    /// # Arguments
+    /// * knowledge base
+    /// * initializer
+    /// # Return
+    /// * Engine, or [SuironError](../error/enum.SuironError.html)
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// #[derive(Debug)]
+    /// struct Ages;
+    ///
+    /// impl ExternalPredicate for Ages {
+    ///     fn fetch(&self, _goal: &Unifiable) -> Result<Vec<Rule>, SuironError> {
+    ///         Ok(vec![make_fact(fact_head("age", ("Ann", 34))),
+    ///                 make_fact(fact_head("age", ("Ben", 12)))])
+    ///     }
+    /// }
+    ///
+    /// let mut kb = KnowledgeBase::new();
+    /// add_rules!(&mut kb, parse_rule("adult($X) :- age($X, $A), $A >= 18.").unwrap());
+    /// let engine = Engine::with_initializer(&kb, |kb| {
+    ///     kb.add_external("age/2", Ages);
+    /// }).unwrap();
+    ///
+    /// let solutions = engine.solve_all("adult($Who)", None).unwrap();
+    /// assert_eq!(vec!["$Who = Ann"], solutions);
+    /// ```
+    pub fn with_initializer(kb: &KnowledgeBase,
+                            initializer: impl Fn(&mut KnowledgeBase) + Send + Sync + 'static)
+                            -> Result<Self, SuironError> {
+        return Engine::make(kb, Some(Box::new(initializer)));
+    }
+
+    // Encodes the knowledge base.
+    fn make(kb: &KnowledgeBase, initializer: Option<Box<Initializer>>)
+            -> Result<Self, SuironError> {
+        let mut bytes: Vec<u8> = vec![];
+        write_kb(kb, &mut bytes)?;
+        let id = NEXT_ENGINE_ID.fetch_add(1, Ordering::Relaxed);
+        let shared = Shared{ id, bytes, indexing: kb.indexing(), initializer };
+        return Ok(Engine{ shared: Arc::new(shared), config: QueryConfig::default() });
+    } // make()
+
+    /// Sets the configuration of the engine's queries: the maximum proof
+    /// depth, the maximum number of solutions, float division and the
+    /// random seed. The settings of the calling thread are not used.
+    ///
+    /// Clones of the engine which are made afterwards have the same
+    /// configuration.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * [QueryConfig](../query_config/struct.QueryConfig.html)
+    /// # Return
+    /// * Engine
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let mut kb = KnowledgeBase::new();
+    /// add_rules!(&mut kb, parse_rule("half($X) :- $X is 7 / 2.").unwrap());
+    /// let config = QueryConfig{ float_division: true, ..Default::default() };
+    /// let engine = Engine::new(&kb).unwrap().with_config(config);
+    ///
+    /// assert_eq!(Some("$X = 3.5".to_string()), engine.solve("half($X)").unwrap());
+    /// ```
+    pub fn with_config(mut self, config: QueryConfig) -> Self {
+        self.config = config;
+        return self;
+    }
+
+    /// Gets the configuration of the engine's queries.
+    pub fn config(&self) -> QueryConfig { self.config }
+
+    /// Gets this thread's copy of the knowledge base. The copy is made
+    /// the first time it is needed.
+    ///
+    /// The copy can be queried like any other knowledge base.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * knowledge base, or error if it cannot be decoded
+    /// # Usage
+    /// ```
+    /// use std::rc::Rc;
+    /// use suiron::*;
+    ///
+    /// let engine = Engine::new(&test_kb()).unwrap();
+    /// let kb = engine.knowledge_base().unwrap();
+    /// let query = Query::predicate("loves").var("Who").arg("Penny").build();
+    /// let sn = make_base_node(Rc::new(query), &kb);
+    /// println!("{}", solve(sn));  // Prints: $Who = Leonard
+    /// ```
+    pub fn knowledge_base(&self) -> Result<Rc<KnowledgeBase>, SuironError> {
+        return Ok(self.copy()?.0);
+    }
+
+    // Gets this thread's copy of the knowledge base, and its query cache.
+    // The copies of engines which have been dropped are discarded.
+    fn copy(&self) -> Result<(Rc<KnowledgeBase>, Rc<RefCell<QueryCache>>), SuironError> {
+
+        let shared = &self.shared;
+        let (copy, stale) = COPIES.with(|copies| {
+            let mut copies = copies.borrow_mut();
+            let copy = copies.get(&shared.id)
+                             .map(|(_, kb, cache)| (Rc::clone(kb), Rc::clone(cache)));
+            let dropped: Vec<u64> = copies.iter()
+                .filter(|(_, (engine, _, _))| engine.strong_count() == 0)
+                .map(|(id, _)| *id).collect();
+            let stale: Vec<KbCopy> = dropped.iter()
+                .filter_map(|id| copies.remove(id)).collect();
+            (copy, stale)
+        });
+        // The stale copies are dropped after COPIES is released.
+        drop(stale);
+        if let Some(copy) = copy { return Ok(copy); }
+
+        let mut kb = KnowledgeBase::new();
+        read_kb(&mut kb, &shared.bytes[..])?;
+        kb.set_indexing(shared.indexing);
+        if let Some(initializer) = &shared.initializer { initializer(&mut kb); }
+        let kb = Rc::new(kb);
+        let cache = Rc::new(RefCell::new(QueryCache::new()));
+
+        COPIES.with(|copies| {
+            copies.borrow_mut().insert(shared.id, (Arc::downgrade(shared),
+                                                   Rc::clone(&kb), Rc::clone(&cache)));
+        });
+        return Ok((kb, cache));
+
+    } // copy()
+
+    // Parses a query with the operators of the knowledge base, and makes
+    // its base solution node, which has the engine's configuration.
+    // Queries are cached by their text. If a query has been parsed before
+    // on this thread, a copy of the cached query is used.
+    //
+    // Arguments
+    //    query text
+    //    knowledge base
+    // Return
+    //    base solution node, or parse error
+    fn make_query_node<'a>(&self, query: &str, kb: &'a KnowledgeBase,
+                           cache: &RefCell<QueryCache>)
+                           -> Result<Rc<RefCell<SolutionNode<'a>>>, SuironError> {
+
+        let key = (query.to_string(), prolog_syntax());
+        let mut cache = cache.borrow_mut();
+        let query = match cache.get(&key) {
+            Some(query) => { query.clone() },
+            None => {
+                let query = with_operators(kb.operators(), || parse_query(query))?;
+                if cache.len() >= QUERY_CACHE_SIZE { cache.clear(); }
+                cache.insert(key, query.clone());
+                query
+            },
+        };
+
+        let sn = make_base_node(Rc::new(query), kb);
+        set_query_config(&sn, self.config);
+        return Ok(sn);
+
+    } // make_query_node()
+
+    /// Finds the first solution of a query.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * query, eg. \"loves(Leonard, $Whom)\"
+    /// # Return
+    /// * Ok(Some(solution)), Ok(None) if there are no solutions, or error
+    pub fn solve(&self, query: &str) -> Result<Option<String>, SuironError> {
+        let (kb, cache) = self.copy()?;
+        let sn = self.make_query_node(query, &kb, &cache)?;
+        return try_solve(sn);
+    }
+
+    /// Finds all solutions of a query.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * query, eg. \"loves($Who, $Whom)\"
+    /// * `max` - maximum number of solutions, or None
+    /// # Return
+    /// * vector of solutions (possibly empty), or error
+    pub fn solve_all(&self, query: &str, max: Option<usize>)
+                     -> Result<Vec<String>, SuironError> {
+        let (kb, cache) = self.copy()?;
+        let sn = self.make_query_node(query, &kb, &cache)?;
+        return try_solve_all(sn, max);
+    }
+
+    /// Starts a pool of worker threads, which solve queries with this
+    /// engine's knowledge base.
+    ///
+    /// The workers use the engine's configuration, and the other settings
+    /// of the calling thread, such as the binding strategy. When the pool is
+    /// dropped, the workers finish the queries which were submitted, and
+    /// stop.
+    ///
+    /// If a query panics, its result is a
+    /// [PoolError](../error/enum.SuironError.html#variant.PoolError), and
+    /// the worker goes on to the next query. (This requires panics to
+    /// unwind. With `panic = "abort"`, a panic ends the process.)
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * number of worker threads (at least 1)
+    /// # Return
+    /// * [QueryPool](struct.QueryPool.html)
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let engine = Engine::new(&test_kb()).unwrap();
+    /// let pool = engine.spawn_pool(4);
+    ///
+    /// let first = pool.submit("grandfather($X, $Y)").unwrap();
+    /// let second = pool.submit("loves($X, Penny)").unwrap();
+    ///
+    /// println!("{:?}", first.wait());   // Prints: Ok(["$X = Alfred, $Y = Aethelstan"])
+    /// println!("{:?}", second.wait());  // Prints: Ok(["$X = Leonard"])
+    /// ```
+    pub fn spawn_pool(&self, n: usize) -> QueryPool {
+
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let settings = Settings::current();
+
+        let workers = (0..n.max(1)).map(|_| {
+            let engine = self.clone();
+            let jobs = Arc::clone(&receiver);
+            thread::spawn(move || {
+                settings.apply();
+                run_worker(&engine, &jobs);
+            })
+        }).collect();
+
+        return QueryPool{ sender: Some(sender), workers };
+
+    } // spawn_pool()
+
+    // Solves a query for a pool. The query is stopped by its token, or
+    // when it runs out of time.
+    //
+    // Arguments
+    //    query
+    //    time limit in milliseconds
+    //    cancellation token
+    // Return
+    //    solutions, or error
+    fn solve_job(&self, query: &str, time_limit: u64,
+                 token: CancellationToken) -> QueryResult {
+        let (kb, cache) = self.copy()?;
+        let sn = self.make_query_node(query, &kb, &cache)?;
+        set_cancellation_token(&sn, token);
+        return SolutionPage::new(sn).time_limit(time_limit).strings();
+    } // solve_job()
+
+} // impl Engine
+
+impl fmt::Debug for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Engine {{ id: {}, bytes: {} }}",
+               self.shared.id, self.shared.bytes.len())
+    }
+} // fmt::Debug
+
+// A query which is waiting for a worker.
+struct Job {
+    query: String,
+    time_limit: u64,
+    token: CancellationToken,
+    sender: Sender<QueryResult>,
+}
+
+// Takes queries from the pool's channel and solves them, until the
+// pool is dropped. If a query panics, its result is an error, and the
+// worker goes on to the next query.
+fn run_worker(engine: &Engine, jobs: &Mutex<Receiver<Job>>) {
+    loop {
+        // The lock is released before the query is solved.
+        let job = jobs.lock().unwrap().recv();
+        let job = match job {
+            Ok(job) => { job },
+            Err(_) => { return; },
+        };
+        let Job{ query, time_limit, token, sender } = job;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            engine.solve_job(&query, time_limit, token)
+        }));
+        let result = match result {
+            Ok(result) => { result },
+            Err(payload) => {
+                let msg = match payload.downcast_ref::<&str>() {
+                    Some(msg) => { msg.to_string() },
+                    None => {
+                        match payload.downcast_ref::<String>() {
+                            Some(msg) => { msg.clone() },
+                            None => { "unknown error".to_string() },
+                        }
+                    },
+                };
+                Err(SuironError::PoolError(format!("Query panicked: {}", msg)))
+            },
+        };
+        // The receiver may have been dropped.
+        let _ = sender.send(result);
+    }
+} // run_worker()
+
+/// A pool of worker threads, which solve queries for an
+/// [Engine](struct.Engine.html).
+///
+/// A pool is made by [spawn_pool()](struct.Engine.html#method.spawn_pool).
+/// The queries are independent. They are solved in the order in which
+/// they were submitted, as workers become free.
+#[derive(Debug)]
+pub struct QueryPool {
+    sender: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl QueryPool {
+
+    /// Submits a query, which times out after one second.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * query, eg. \"loves($Who, $Whom)\"
+    /// # Return
+    /// * [PendingQuery](struct.PendingQuery.html) or
+    ///   [PoolError](../error/enum.SuironError.html#variant.PoolError)
+    /// # Errors
+    /// * If the worker threads have stopped.
+    pub fn submit(&self, query: &str) -> Result<PendingQuery, SuironError> {
+        return self.submit_with_time_limit(query, TIME_LIMIT);
+    }
+
+    /// Submits a query, with a time limit. The time is counted from
+    /// when a worker starts to solve the query, not from when it was
+    /// submitted.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * query, eg. \"loves($Who, $Whom)\"
+    /// * time limit in milliseconds
+    /// # Return
+    /// * [PendingQuery](struct.PendingQuery.html) or
+    ///   [PoolError](../error/enum.SuironError.html#variant.PoolError)
+    /// # Errors
+    /// * If the worker threads have stopped.
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let mut kb = KnowledgeBase::new();
+    /// add_rules!(&mut kb, parse_rule("loop :- loop.").unwrap());
+    /// let pool = Engine::new(&kb).unwrap().spawn_pool(1);
+    ///
+    /// let pending = pool.submit_with_time_limit("loop", 50).unwrap();
+    /// match pending.wait() {
+    ///     Err(err) => { println!("{}", err); },
+    ///     Ok(_) => {},
+    /// }
+    /// // Prints: Query timed out after 50 milliseconds.
+    /// ```
+    pub fn submit_with_time_limit(&self, query: &str, milliseconds: u64)
+                                  -> Result<PendingQuery, SuironError> {
+        let (sender, receiver) = mpsc::channel();
+        let token = CancellationToken::new();
+        let job = Job{ query: query.to_string(), time_limit: milliseconds,
+                       token: token.clone(), sender };
+        let sent = match &self.sender {
+            Some(jobs) => { jobs.send(job).is_ok() },
+            None => { false },
+        };
+        if !sent {
+            let msg = "The worker threads of the pool have stopped.";
+            return Err(SuironError::PoolError(msg.to_string()));
+        }
+        return Ok(PendingQuery{ receiver, token });
+    } // submit_with_time_limit()
+
+    /// Gets the number of worker threads.
+    pub fn size(&self) -> usize { self.workers.len() }
+
+} // impl QueryPool
+
+impl Drop for QueryPool {
+    // Closes the channel, and waits for the workers to finish.
+    fn drop(&mut self) {
+        self.sender = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+} // Drop
+
+/// A query which was submitted to a [QueryPool](struct.QueryPool.html).
+///
+/// Its solutions, or an error, are sent to a channel when the query has
+/// been solved.
+#[derive(Debug)]
+pub struct PendingQuery {
+    receiver: Receiver<QueryResult>,
+    token: CancellationToken,
+}
+
+impl PendingQuery {
+
+    /// Gets the receiver of the channel, which receives the result.
+    ///
+    /// This allows the result to be polled, with try_recv(), or
+    /// waited for with a timeout, with recv_timeout().
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * receiver of solutions, or error
+    pub fn receiver(&self) -> &Receiver<Result<Vec<String>, SuironError>> {
+        return &self.receiver;
+    }
+
+    /// Gets the query's token. The query can be cancelled from any thread.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * [CancellationToken](../cancellation/struct.CancellationToken.html)
+    pub fn cancellation_token(&self) -> CancellationToken {
+        return self.token.clone();
+    }
+
+    /// Cancels the query. If it has not started yet, it will not be
+    /// solved. Its result is a
+    /// [Cancelled](../error/enum.SuironError.html#variant.Cancelled) error.
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Waits for the result of the query.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * vector of solutions (possibly empty), or error
+    pub fn wait(self) -> Result<Vec<String>, SuironError> {
+        match self.receiver.recv() {
+            Ok(result) => { return result; },
+            Err(_) => {
+                let msg = "The worker thread stopped before the query was solved.";
+                return Err(SuironError::Cancelled(msg.to_string()));
+            },
+        }
+    } // wait()
+
+} // impl PendingQuery
+
+// Search settings, which are kept in thread-local storage. They are
+// copied to worker threads. (An engine's queries use its QueryConfig
+// instead of the thread's maximum depth, maximum solutions and float
+// division.)
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Settings {
+    binding_strategy: BindingStrategy,
+    search_strategy: SearchStrategy,
+    max_depth: Option<usize>,
+    max_solutions: Option<usize>,
+    query_planner: bool,
+    occurs_check: bool,
+    float_division: bool,
+    prolog_syntax: bool,
+}
+
+impl Settings {
+
+    // Gets the settings of the current thread.
+    pub(crate) fn current() -> Self {
+        Settings {
+            binding_strategy: binding_strategy(),
+            search_strategy: search_strategy(),
+            max_depth: max_depth(),
+            max_solutions: max_solutions(),
+            query_planner: query_planner(),
+            occurs_check: occurs_check(),
+            float_division: float_division(),
+            prolog_syntax: prolog_syntax(),
+        }
+    }
+
+    // Applies the settings to the current thread.
+    pub(crate) fn apply(&self) {
+        set_binding_strategy(self.binding_strategy);
+        set_search_strategy(self.search_strategy);
+        set_max_depth(self.max_depth);
+        set_max_solutions(self.max_solutions);
+        set_query_planner(self.query_planner);
+        set_occurs_check(self.occurs_check);
+        set_float_division(self.float_division);
+        set_prolog_syntax(self.prolog_syntax);
+    }
+
+} // impl Settings
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::*;
+
+    fn is_send_sync<T: Send + Sync>(_: &T) -> bool { true }
+
+    #[derive(Debug)]
+    struct Count;
+
+    impl ExternalPredicate for Count {
+        fn fetch(&self, _goal: &Unifiable) -> Result<Vec<Rule>, SuironError> {
+            Ok(vec![make_fact(fact_head("count", (1,)))])
+        }
+    }
+
+    #[derive(Debug)]
+    struct Boom;
+
+    impl ExternalPredicate for Boom {
+        fn fetch(&self, _goal: &Unifiable) -> Result<Vec<Rule>, SuironError> {
+            panic!("boom");
+        }
+    }
+
+    #[test]
+    fn test_engine() {
+
+        let mut kb = test_kb();
+        kb.set_indexing(true);
+        let engine = Engine::new(&kb).unwrap();
+        assert!(is_send_sync(&engine));
+
+        // Each thread gets its own copy, which is reused.
+        let kb1 = engine.knowledge_base().unwrap();
+        let kb2 = engine.knowledge_base().unwrap();
+        assert!(Rc::ptr_eq(&kb1, &kb2));
+        assert!(kb1.indexing());
+        assert_eq!(format_kb(&kb), format_kb(&kb1));
+
+        let engine = Arc::new(engine);
+        let handles: Vec<_> = (0..4).map(|i| {
+            let engine = Arc::clone(&engine);
+            thread::spawn(move || {
+                let query = if i % 2 == 0 { "grandfather($X, $Y)" } else { "loves($X, Penny)" };
+                let first = engine.solve(query).unwrap();
+                let all = engine.solve_all(query, None).unwrap();
+                (first, all)
+            })
+        }).collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            let (first, all) = handle.join().unwrap();
+            if i % 2 == 0 {
+                assert_eq!(Some("$X = Alfred, $Y = Aethelstan".to_string()), first);
+                assert_eq!(1, all.len());
+            }
+            else {
+                assert_eq!(vec!["$X = Leonard"], all);
+            }
+        }
+
+        let err = engine.solve("loves($X").unwrap_err();
+        assert!(matches!(err, SuironError::ParseError(_)));
+
+        // An initializer runs for each copy.
+        let engine = Engine::with_initializer(&KnowledgeBase::new(), |kb| {
+            kb.add_external("count/1", Count);
+        }).unwrap();
+        let result = thread::spawn(move || engine.solve_all("count($N)", None))
+                        .join().unwrap();
+        assert_eq!(vec!["$N = 1"], result.unwrap());
+
+    } // test_engine()
+
+    // A thread discards its copies of dropped engines. A knowledge base
+    // which cannot be decoded is reported as an error.
+    #[test]
+    fn test_engine_copies() {
+
+        let engine = Engine::new(&test_kb()).unwrap();
+        let other = Engine::new(&test_kb()).unwrap();
+        other.solve("loves($X, $Y)").unwrap();
+        let copy = Rc::downgrade(&engine.knowledge_base().unwrap());
+        assert!(copy.upgrade().is_some());
+        drop(engine);
+        assert!(copy.upgrade().is_some());
+        other.solve("loves($X, $Y)").unwrap();
+        assert!(copy.upgrade().is_none());
+
+        let shared = super::Shared{ id: u64::MAX, bytes: b"SKB".to_vec(),
+                                    indexing: false, initializer: None };
+        let engine = Engine{ shared: Arc::new(shared), config: QueryConfig::default() };
+        assert!(engine.knowledge_base().is_err());
+        assert!(engine.solve("loves($X, $Y)").is_err());
+
+    } // test_engine_copies()
+
+    // Two engines on one thread keep their own configurations.
+    #[test]
+    fn test_engine_config() {
+
+        let mut kb = test_kb();
+        add_rules!(&mut kb, parse_rule("half($X) :- $X is 7 / 2.").unwrap(),
+                            parse_rule("loop($X) :- loop($X), $X = 1.").unwrap());
+        let plain = Engine::new(&kb).unwrap();
+        let config = QueryConfig{ max_depth: Some(50), max_solutions: Some(1),
+                                  float_division: true, random_seed: None };
+        let limited = plain.clone().with_config(config);
+        assert_eq!(config, limited.config());
+        assert_eq!(QueryConfig::default(), plain.config());
+
+        assert_eq!(Some("$X = 3.5".to_string()), limited.solve("half($X)").unwrap());
+        assert_eq!(Some("$X = 3".to_string()), plain.solve("half($X)").unwrap());
+        assert_eq!(1, limited.solve_all("loves($X, $Y)", None).unwrap().len());
+        assert_eq!(2, plain.solve_all("loves($X, $Y)", None).unwrap().len());
+        let result = limited.solve("loop(1)");
+        assert!(matches!(result, Err(SuironError::DepthLimitExceeded(_))));
+
+        // The thread's settings do not apply to an engine's queries.
+        set_float_division(true);
+        assert_eq!(Some("$X = 3".to_string()), plain.solve("half($X)").unwrap());
+        set_float_division(false);
+        assert_eq!(None, max_depth());
+
+        // Queries are parsed with the operators of the knowledge base,
+        // not those of the thread.
+        assert!(plain.solve("Leonard loves $X").is_err());
+        add_operator(700, "xfx", "loves").unwrap();
+        let result = plain.solve("Leonard loves $X");
+        clear_operators();
+        assert!(result.is_err());
+        kb.add_operator(700, "xfx", "loves").unwrap();
+        let engine = Engine::new(&kb).unwrap();
+        let result = engine.solve("Leonard loves $X");
+        assert_eq!(Some("$X = Penny".to_string()), result.unwrap());
+
+        // Queries are cached. Prolog syntax changes their meaning.
+        set_prolog_syntax(true);
+        let result = plain.solve_all("loves(X, Y)", None);
+        set_prolog_syntax(false);
+        assert_eq!(2, result.unwrap().len());
+        assert_eq!(0, plain.solve_all("loves(X, Y)", None).unwrap().len());
+
+    } // test_engine_config()
+
+    #[test]
+    fn test_query_pool() {
+
+        let mut kb = test_kb();
+        add_rules!(&mut kb, parse_rule("loop :- loop.").unwrap(),
+                            parse_rule("ancestor($X, $Y) :- ancestor($X, $Z), father($Z, $Y).").unwrap());
+        let engine = Engine::new(&kb).unwrap();
+
+        let config = QueryConfig{ max_depth: Some(50), ..Default::default() };
+        let pool = engine.with_config(config).spawn_pool(3);
+        assert_eq!(3, pool.size());
+
+        let pending: Vec<_> = (0..12).map(|i| {
+            if i % 2 == 0 { pool.submit("grandfather($X, $Y)").unwrap() }
+            else { pool.submit("loves($X, Penny)").unwrap() }
+        }).collect();
+        for (i, p) in pending.into_iter().enumerate() {
+            let result = p.wait().unwrap();
+            if i % 2 == 0 { assert_eq!(vec!["$X = Alfred, $Y = Aethelstan"], result); }
+            else { assert_eq!(vec!["$X = Leonard"], result); }
+        }
+
+        // The engine's configuration is used by the workers.
+        let result = pool.submit("ancestor(Alfred, $Y)").unwrap().wait();
+        assert!(matches!(result, Err(SuironError::DepthLimitExceeded(_))));
+
+        let result = pool.submit_with_time_limit("loop", 20).unwrap().wait();
+        assert_eq!(Err(SuironError::TimeoutError(
+                   "Query timed out after 20 milliseconds.".to_string())), result);
+
+        // A query is cancelled while it runs.
+        let pending = pool.submit_with_time_limit("loop", 60000).unwrap();
+        thread::sleep(Duration::from_millis(20));
+        pending.cancel();
+        let result = pending.receiver().recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(matches!(result, Err(SuironError::Cancelled(_))));
+
+        let result = pool.submit("loves($X").unwrap().wait();
+        assert!(matches!(result, Err(SuironError::ParseError(_))));
+
+        // Submitted queries are finished when the pool is dropped.
+        let pending = pool.submit("loves(Penny, $Y)").unwrap();
+        drop(pool);
+        assert_eq!(Ok(vec!["$Y = Leonard".to_string()]), pending.wait());
+
+        // A query which panics does not stop its worker.
+        let engine = Engine::with_initializer(&KnowledgeBase::new(), |kb| {
+            kb.add_external("boom/1", Boom);
+        }).unwrap();
+        let pool = engine.spawn_pool(1);
+        let result = pool.submit("boom($X)").unwrap().wait();
+        assert_eq!(Err(SuironError::PoolError("Query panicked: boom".to_string())), result);
+        let result = pool.submit("boom($X)").unwrap().wait();
+        assert!(matches!(result, Err(SuironError::PoolError(_))));
+
+    } // test_query_pool()
+
+} // test
@@ -0,0 +1,151 @@
+//! Defines SuironError, the error type of the parser, the rule reader
+//! and the solver.
+//!
+//! Each kind of error holds a message, which describes the error.
+//! Callers can match on the kind of error:
+//!
+//! ```
+//! use suiron::*;
+//!
+//! match parse_rule("father($X, $Y :- parent($X, $Y).") {
+//!     Err(SuironError::ParseError(msg)) => { println!("Syntax: {}", msg); },
+//!     Err(err) => { println!("Other: {}", err); },
+//!     Ok(rule) => { println!("{}", rule); },
+//! }
+//! ```
+//!
+//! An error can be compared to a string, which is convenient for tests.
+//! Only the message is compared.
+//
+// Cleve Lendon 2023
+
+use std::fmt;
+use std::io;
+
+/// Kinds of errors which Suiron reports.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SuironError {
+    /// Invalid source text: a term, goal, rule, query or directive.
+    ParseError(String),
+    /// An arithmetic expression could not be evaluated by
+    /// [evaluate_expression()](../built_in_arithmetic/fn.evaluate_expression.html).
+    /// Within a query, the goal which evaluates the expression fails
+    /// instead, so the solve functions do not report this error.
+    ArithmeticError(String),
+    /// A query did not finish within the time limit.
+    TimeoutError(String),
+    /// A query nested goals more deeply than the maximum proof depth.
+    DepthLimitExceeded(String),
+    /// A query was cancelled by its
+    /// [CancellationToken](../cancellation/struct.CancellationToken.html).
+    Cancelled(String),
+    /// A query refers to a predicate which is not defined.
+    UnknownPredicate(String),
+    /// A file could not be opened or read.
+    IoError(String),
+    /// A term could not be converted to the requested Rust type.
+    TypeError(String),
+    /// Rules could not be evaluated bottom up, because they are not
+    /// [Datalog](../datalog/index.html) rules.
+    NotDatalog(String),
+    /// A [query pool](../engine/struct.QueryPool.html) could not solve
+    /// a query, because the query panicked, or the workers have stopped.
+    PoolError(String),
+}
+
+impl SuironError {
+
+    /// Gets the error message.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * message
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let err = SuironError::ArithmeticError("Division by zero.".to_string());
+    /// println!("{}", err.message());  // Prints: Division by zero.
+    /// ```
+    pub fn message(&self) -> &str {
+        match self {
+            SuironError::ParseError(msg) |
+            SuironError::ArithmeticError(msg) |
+            SuironError::TimeoutError(msg) |
+            SuironError::DepthLimitExceeded(msg) |
+            SuironError::Cancelled(msg) |
+            SuironError::UnknownPredicate(msg) |
+            SuironError::IoError(msg) |
+            SuironError::TypeError(msg) |
+            SuironError::NotDatalog(msg) |
+            SuironError::PoolError(msg) => { msg },
+        }
+    } // message()
+
+} // impl SuironError
+
+impl fmt::Display for SuironError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+} // fmt::Display
+
+impl std::error::Error for SuironError {}
+
+// Most of the parsing functions produce error messages as strings.
+// These are parse errors.
+impl From<String> for SuironError {
+    fn from(msg: String) -> Self { SuironError::ParseError(msg) }
+}
+
+impl From<SuironError> for String {
+    fn from(err: SuironError) -> Self { err.message().to_string() }
+}
+
+impl From<io::Error> for SuironError {
+    fn from(err: io::Error) -> Self { SuironError::IoError(err.to_string()) }
+}
+
+impl PartialEq<str> for SuironError {
+    fn eq(&self, other: &str) -> bool { self.message() == other }
+}
+
+impl PartialEq<&str> for SuironError {
+    fn eq(&self, other: &&str) -> bool { self.message() == *other }
+}
+
+impl PartialEq<String> for SuironError {
+    fn eq(&self, other: &String) -> bool { self.message() == other }
+}
+
+impl PartialEq<SuironError> for &str {
+    fn eq(&self, other: &SuironError) -> bool { *self == other.message() }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::*;
+
+    #[test]
+    fn test_suiron_error() {
+
+        let err = parse_complex("$aaaa").unwrap_err();
+        assert!(matches!(err, SuironError::ParseError(_)));
+
+        let err = SuironError::TimeoutError("Query timed out.".to_string());
+        assert_eq!("Query timed out.", err.to_string());
+        assert_eq!(err, "Query timed out.");
+        assert_eq!("Query timed out.", err);
+
+        let msg: String = err.into();
+        assert_eq!("Query timed out.", msg);
+
+        let err = load_kb_from_file(&mut KnowledgeBase::new(), "no_such_file.txt");
+        assert!(matches!(err, Some(SuironError::IoError(_))));
+
+    } // test_suiron_error()
+
+} // test
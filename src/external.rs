@@ -0,0 +1,60 @@
+//! External predicates, whose facts are not stored in the knowledge base.
+//!
+//! An external predicate supplies its facts (or rules) on demand, each
+//! time a goal refers to it. This allows an application to reason over
+//! data which is too large to hold in memory, such as a database table.
+//! See [SqlPredicate](../sql_store/struct.SqlPredicate.html), which
+//! requires the `sqlite` feature.
+//!
+//! External predicates are registered with
+//! [add_external()](../knowledge_base/struct.KnowledgeBase.html#method.add_external).
+//! If a predicate has facts or rules in the knowledge base, these take
+//! precedence over the external predicate.
+//
+// Cleve Lendon 2023
+
+use std::fmt::Debug;
+
+use super::error::SuironError;
+use super::rule::Rule;
+use super::unifiable::Unifiable;
+
+/// A source of facts and rules for one predicate.
+///
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// // Squares of the integers from 1 to 10.
+/// #[derive(Debug)]
+/// struct Squares;
+///
+/// impl ExternalPredicate for Squares {
+///     fn fetch(&self, _goal: &Unifiable) -> Result<Vec<Rule>, SuironError> {
+///         Ok((1..=10).map(|i| make_fact(fact_head("square", (i, i * i)))).collect())
+///     }
+/// }
+///
+/// let mut kb = KnowledgeBase::new();
+/// kb.add_external("square/2", Squares);
+/// let query = parse_query("square(7, $S)").unwrap();
+/// println!("{}", solve(make_base_node(Rc::new(query), &kb)));  // Prints: $S = 49
+/// ```
+pub trait ExternalPredicate: Debug {
+
+    /// Fetches the facts and rules which may match a goal.
+    ///
+    /// The variables of the goal which are bound have been replaced by
+    /// their values, so that the predicate can select only the matching
+    /// facts. It may also return facts which do not match; these will
+    /// fail to unify. An error is treated as failure.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * goal (complex term), eg. person(June, $Age)
+    /// # Return
+    /// * facts and rules, or error
+    fn fetch(&self, goal: &Unifiable) -> Result<Vec<Rule>, SuironError>;
+
+} // ExternalPredicate
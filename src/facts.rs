@@ -0,0 +1,337 @@
+//! Functions for constructing facts directly from Rust values.
+//!
+//! Applications which generate large numbers of facts, for example from
+//! a database, can add them to a knowledge base without formatting and
+//! parsing each fact. See
+//! [add_facts()](../knowledge_base/struct.KnowledgeBase.html#method.add_facts).
+//!
+//! The arguments of a fact can be given as a tuple, an array or a
+//! vector of [Unifiable](../unifiable/enum.Unifiable.html) terms, or of
+//! common Rust values, which are converted as follows:
+//!
+//! <pre>
+//!   &str, String, Symbol    Atom
+//!   i64, i32, u32           SInteger
+//!   f64                     SFloat
+//!   Vec                     SLinkedList
+//! </pre>
+//!
+//! Terms can be converted back into Rust values with TryFrom, for example:
+//!
+//! <pre>
+//!   let n = i64::try_from(term)?;
+//! </pre>
+//!
+//! Conversion fails with a
+//! [TypeError](../error/enum.SuironError.html#variant.TypeError)
+//! if the term has the wrong type. An SInteger can be converted to f64,
+//! and an SString or an Atom can be converted to String.
+//!
+//! Simple facts can also be constructed with a
+//! [FactBuilder](struct.FactBuilder.html):
+//!
+//! <pre>
+//!   kb.fact("age").args(("June", 61)).add();
+//! </pre>
+//
+// Cleve Lendon 2023
+
+use super::error::SuironError;
+use super::knowledge_base::*;
+use super::s_linked_list::make_linked_list;
+use super::symbol::Symbol;
+use super::unifiable::Unifiable;
+
+use crate::atom;
+
+impl From<&str> for Unifiable {
+    fn from(s: &str) -> Unifiable { Unifiable::Atom(Symbol::new(s)) }
+}
+
+impl From<String> for Unifiable {
+    fn from(s: String) -> Unifiable { Unifiable::Atom(Symbol::new(&s)) }
+}
+
+impl From<Symbol> for Unifiable {
+    fn from(s: Symbol) -> Unifiable { Unifiable::Atom(s) }
+}
+
+impl From<i64> for Unifiable {
+    fn from(i: i64) -> Unifiable { Unifiable::SInteger(i) }
+}
+
+impl From<i32> for Unifiable {
+    fn from(i: i32) -> Unifiable { Unifiable::SInteger(i as i64) }
+}
+
+impl From<u32> for Unifiable {
+    fn from(i: u32) -> Unifiable { Unifiable::SInteger(i as i64) }
+}
+
+impl From<f64> for Unifiable {
+    fn from(f: f64) -> Unifiable { Unifiable::SFloat(f) }
+}
+
+impl<T: Into<Unifiable>> From<Vec<T>> for Unifiable {
+    fn from(v: Vec<T>) -> Unifiable {
+        make_linked_list(false, v.into_iter().map(Into::into).collect())
+    }
+}
+
+impl TryFrom<Unifiable> for i64 {
+    type Error = SuironError;
+    fn try_from(term: Unifiable) -> Result<i64, SuironError> {
+        match term {
+            Unifiable::SInteger(i) => { Ok(i) },
+            _ => { Err(type_error("an integer", &term)) },
+        }
+    }
+}
+
+impl TryFrom<Unifiable> for f64 {
+    type Error = SuironError;
+    fn try_from(term: Unifiable) -> Result<f64, SuironError> {
+        match term {
+            Unifiable::SFloat(f) => { Ok(f) },
+            Unifiable::SInteger(i) => { Ok(i as f64) },
+            _ => { Err(type_error("a number", &term)) },
+        }
+    }
+}
+
+impl TryFrom<Unifiable> for String {
+    type Error = SuironError;
+    fn try_from(term: Unifiable) -> Result<String, SuironError> {
+        match term {
+            Unifiable::Atom(s) => { Ok(s.to_string()) },
+            Unifiable::SString(s) => { Ok(s) },
+            _ => { Err(type_error("an atom or a string", &term)) },
+        }
+    }
+}
+
+impl<T: TryFrom<Unifiable, Error = SuironError>> TryFrom<Unifiable> for Vec<T> {
+    type Error = SuironError;
+    fn try_from(term: Unifiable) -> Result<Vec<T>, SuironError> {
+        let mut items: Vec<T> = vec![];
+        let mut node = &term;
+        loop {
+            match node {
+                Unifiable::SLinkedList{term: t, next, count: _, tail_var} => {
+                    if **t == Unifiable::Nil { break; }  // Empty list.
+                    // The last node of [a | $T] holds the tail.
+                    if *tail_var { node = t; continue; }
+                    items.push(T::try_from((**t).clone())?);
+                    node = next;
+                },
+                Unifiable::Nil => { break; },
+                _ => { return Err(type_error("a list", &term)); },
+            }
+        }
+        return Ok(items);
+    }
+}
+
+// Makes an error message for a failed conversion.
+//
+// Arguments
+//    expected type, eg. "an integer"
+//    term
+// Return
+//    TypeError
+fn type_error(expected: &str, term: &Unifiable) -> SuironError {
+    SuironError::TypeError(format!("Expected {}: {}", expected, term))
+}
+
+/// Converts a group of values into the arguments of a fact.
+///
+/// This trait is implemented for vectors and arrays, and for tuples of
+/// up to eight values, which can be converted into terms.
+///
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let args = (atom!("Mary"), SInteger(29)).into_arguments();
+/// assert_eq!(2, args.len());
+/// ```
+pub trait FactArguments {
+    /// Converts self into a vector of terms.
+    fn into_arguments(self) -> Vec<Unifiable>;
+}
+
+impl<T: Into<Unifiable>> FactArguments for Vec<T> {
+    fn into_arguments(self) -> Vec<Unifiable> {
+        self.into_iter().map(Into::into).collect()
+    }
+}
+
+impl<T: Into<Unifiable>, const N: usize> FactArguments for [T; N] {
+    fn into_arguments(self) -> Vec<Unifiable> {
+        self.into_iter().map(Into::into).collect()
+    }
+}
+
+// Implements FactArguments for a tuple.
+macro_rules! tuple_arguments {
+    ($($t:ident $v:ident),+) => {
+        impl<$($t: Into<Unifiable>),+> FactArguments for ($($t,)+) {
+            fn into_arguments(self) -> Vec<Unifiable> {
+                let ($($v,)+) = self;
+                vec![$($v.into()),+]
+            }
+        }
+    };
+}
+
+tuple_arguments!(A a);
+tuple_arguments!(A a, B b);
+tuple_arguments!(A a, B b, C c);
+tuple_arguments!(A a, B b, C c, D d);
+tuple_arguments!(A a, B b, C c, D d, E e);
+tuple_arguments!(A a, B b, C c, D d, E e, F f);
+tuple_arguments!(A a, B b, C c, D d, E e, F f, G g);
+tuple_arguments!(A a, B b, C c, D d, E e, F f, G g, H h);
+
+/// Makes a fact from a functor and its arguments.
+///
+/// # Arguments
+/// * functor, eg. \"edge\"
+/// * arguments
+/// # Return
+/// * head of fact
+///   ([SComplex](../unifiable/enum.Unifiable.html#variant.SComplex))
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let head = fact_head("edge", (atom!("a"), atom!("b")));
+/// println!("{}", head);  // Prints: edge(a, b)
+/// ```
+pub fn fact_head(functor: &str, args: impl FactArguments) -> Unifiable {
+    let args = args.into_arguments();
+    let mut terms = Vec::with_capacity(args.len() + 1);
+    terms.push(atom!(functor));
+    terms.extend(args);
+    return Unifiable::SComplex(terms);
+} // fact_head()
+
+/// Constructs a fact, one argument at a time.
+///
+/// A fact builder is created by
+/// [KnowledgeBase::fact()](../knowledge_base/struct.KnowledgeBase.html#method.fact).
+///
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// kb.fact("age").args(("June", 61)).add();
+/// kb.fact("likes").arg("June").arg(vec!["tea", "jazz"]).add();
+/// print_kb(&kb);
+/// ```
+pub struct FactBuilder<'a> {
+    kb: &'a mut KnowledgeBase,
+    functor: String,
+    args: Vec<Unifiable>,
+}
+
+impl<'a> FactBuilder<'a> {
+
+    /// Creates a fact builder for the given knowledge base.
+    ///
+    /// # Arguments
+    /// * knowledge base
+    /// * functor, eg. \"age\"
+    /// # Return
+    /// * fact builder
+    pub fn new(kb: &'a mut KnowledgeBase, functor: &str) -> Self {
+        FactBuilder{ kb, functor: functor.to_string(), args: vec![] }
+    }
+
+    /// Adds one argument to the fact.
+    pub fn arg(mut self, arg: impl Into<Unifiable>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Adds several arguments to the fact.
+    pub fn args(mut self, args: impl FactArguments) -> Self {
+        self.args.extend(args.into_arguments());
+        self
+    }
+
+    /// Adds the fact to the knowledge base, after the other facts
+    /// and rules of its predicate.
+    pub fn add(self) {
+        let head = fact_head(&self.functor, self.args);
+        add_rules(self.kb, vec![make_fact(head)]);
+    }
+
+} // impl FactBuilder
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(atom!("June"), Unifiable::from("June"));
+        assert_eq!(atom!("June"), Unifiable::from("June".to_string()));
+        assert_eq!(SInteger(61), Unifiable::from(61));
+        assert_eq!(SInteger(61), Unifiable::from(61_i64));
+        assert_eq!(SFloat(1.62), Unifiable::from(1.62));
+        let list: Unifiable = vec![1, 2, 3].into();
+        assert_eq!("[1, 2, 3]", list.to_string());
+        let args = ("June", 61, vec!["tea", "jazz"], SString("hi".to_string()));
+        let head = fact_head("test", args);
+        assert_eq!("test(June, 61, [tea, jazz], \"hi\")", head.to_string());
+    } // test_conversions()
+
+    #[test]
+    fn test_try_from() {
+        assert_eq!(Ok(61), i64::try_from(SInteger(61)));
+        assert_eq!(Ok(61.0), f64::try_from(SInteger(61)));
+        assert_eq!(Ok(1.62), f64::try_from(SFloat(1.62)));
+        assert_eq!(Ok("June".to_string()), String::try_from(atom!("June")));
+        assert_eq!(Ok("hi".to_string()), String::try_from(SString("hi".to_string())));
+        let err = i64::try_from(SFloat(1.5)).unwrap_err();
+        assert!(matches!(err, SuironError::TypeError(_)));
+        assert_eq!("Expected an integer: 1.5", err.to_string());
+        assert!(String::try_from(SInteger(1)).is_err());
+
+        let list = parse_term("[1, 2, 3]").unwrap();
+        assert_eq!(Ok(vec![1, 2, 3]), Vec::<i64>::try_from(list));
+        let empty = parse_term("[]").unwrap();
+        assert_eq!(Ok(vec![]), Vec::<String>::try_from(empty));
+        let nested = parse_term("[[a, b], [c]]").unwrap();
+        let expected = vec![vec!["a".to_string(), "b".to_string()], vec!["c".to_string()]];
+        assert_eq!(Ok(expected), Vec::<Vec<String>>::try_from(nested));
+        assert!(Vec::<i64>::try_from(parse_term("[1, a]").unwrap()).is_err());
+        assert!(Vec::<i64>::try_from(atom!("a")).is_err());
+    } // test_try_from()
+
+    #[test]
+    fn test_fact_builder() {
+
+        let mut kb = KnowledgeBase::new();
+        kb.fact("age").args(("June", 61)).add();
+        kb.fact("age").arg("Ola").arg(23).add();
+        kb.fact("likes").arg("June").args([vec!["tea", "jazz"]]).add();
+        kb.fact("height").args(("June", 1.62)).add();
+        assert_eq!(2, count_rules(&kb, "age/2"));
+        add_rules!(&mut kb, parse_rule("young($X) :- age($X, $A), $A < 30.").unwrap());
+
+        let query = parse_query("young($Who)").unwrap();
+        assert_eq!("$Who = Ola", solve(make_base_node(Rc::new(query), &kb)));
+        let query = parse_query("likes(June, $L)").unwrap();
+        assert_eq!("$L = [tea, jazz]", solve(make_base_node(Rc::new(query), &kb)));
+        let query = parse_query("height(June, $H)").unwrap();
+        assert_eq!("$H = 1.62", solve(make_base_node(Rc::new(query), &kb)));
+
+    } // test_fact_builder()
+
+} // test
@@ -0,0 +1,488 @@
+//! Functions to format Suiron source code.
+//!
+//! The formatter parses a source file and re-emits it in a canonical
+//! layout. Each fact, rule or directive begins on a new line. Facts are
+//! written on one line. Each rule is a block, separated from other facts
+//! and rules by a blank line. The body of a rule is written below its head,
+//! one subgoal per line, indented by four spaces. A group of goals in
+//! parentheses begins on the line of its first goal, and the goals which
+//! follow are aligned with it:
+//!
+//! <pre>
+//! uncle($U, $N) :-
+//!     sibling($U, $P),
+//!     (father($P, $N);
+//!      mother($P, $N)).
+//! </pre>
+//!
+//! Every line of a formatted rule ends with a comma, semicolon or
+//! period (or the neck), so that the rule reader can load it.
+//!
+//! Spacing within terms is normalized: runs of spaces are reduced to one,
+//! a comma is followed by a space, and there are no spaces inside
+//! parentheses and brackets. Text between quotes is not changed.
+//!
+//! Comments which occupy a whole line are kept. A comment at the end of
+//! a line is moved to the line before its fact or rule. Consecutive blank
+//! lines are reduced to one.
+//!
+//! The query program formats a file with the `--fmt` option:
+//!
+//! <pre>
+//! cargo run -- --fmt tests/kings.txt
+//! </pre>
+//
+// Cleve Lendon 2023
+
+use std::fs;
+
+use super::error::SuironError;
+use super::knowledge_base::KnowledgeBase;
+use super::parse_terms::QuoteScanner;
+use super::rule::parse_rule;
+use super::rule_reader::*;
+use super::token::{Token, TokenType};
+use super::tokenizer::*;
+use super::user_operators::*;
+
+// Indentation of the body of a rule.
+const INDENT: usize = 4;
+
+/// Formats Suiron source code.
+///
+/// Operators which are declared by op/3 directives in the source are
+/// used to parse the facts and rules which follow them. The operators
+/// of the thread are not used, and are not changed.
+///
+/// # Arguments
+/// * source text
+/// # Return
+/// * formatted text, or error
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let source = "% Royal family\nfather(Godwin,Harold).  \n\n\n\
+///               grandfather($X,$Z):-father($X,$Y),father($Y,$Z).";
+/// let formatted = format_source(source).unwrap();
+/// print!("{}", formatted);
+/// // Prints:
+/// // % Royal family
+/// // father(Godwin, Harold).
+/// //
+/// // grandfather($X, $Z) :-
+/// //     father($X, $Y),
+/// //     father($Y, $Z).
+/// ```
+pub fn format_source(text: &str) -> Result<String, SuironError> {
+    return format_lines(text);
+} // format_source()
+
+/// Formats a Suiron source file.
+///
+/// See [format_source()](fn.format_source.html).
+///
+/// # Arguments
+/// * file name
+/// # Return
+/// * formatted text, or error
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// match format_file("SUIRON_TEST_DIR/kings.txt") {
+///     Ok(text) => { print!("{}", text); },
+///     Err(err) => { println!("{}", err); },
+/// }
+/// ```
+pub fn format_file(file_name: &str) -> Result<String, SuironError> {
+    let bytes = match fs::read(file_name) {
+        Ok(bytes) => { bytes },
+        Err(msg) => {
+            let msg = format!("{}: {}", msg, file_name);
+            return Err(SuironError::IoError(msg));
+        },
+    };
+    return format_source(&decode_source(&bytes)?);
+} // format_file()
+
+/// Formats a fact, rule or directive.
+///
+/// # Arguments
+/// * fact, rule or directive, with or without the final period
+/// # Return
+/// * formatted text, or error
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let rule = format_clause("sibling($X,$Y):-parent($P,$X),parent($P,$Y).").unwrap();
+/// println!("{}", rule);
+/// // Prints:
+/// // sibling($X, $Y) :-
+/// //     parent($P, $X),
+/// //     parent($P, $Y).
+/// ```
+pub fn format_clause(clause: &str) -> Result<String, SuironError> {
+
+    let s = clause.trim();
+    let s = s.strip_suffix('.').unwrap_or(s).trim_end();
+
+    if let Some(directive) = s.strip_prefix(":-") {
+        generate_goal(directive)?;
+        return Ok(format!(":- {}.", normalize_spacing(directive)));
+    }
+
+    // Make sure the fact or rule is valid.
+    parse_rule(&format!("{}.", s))?;
+
+    let (head, neck, body) = match split_neck(s) {
+        Some(parts) => { parts },
+        None => { return Ok(format!("{}.", normalize_spacing(s))); },
+    };
+
+    let head = normalize_spacing(head);
+    let pad = " ".repeat(INDENT);
+
+    // The body of a grammar rule is kept on one line.
+    if neck == "-->" {
+        return Ok(format!("{} -->\n{}{}.", head, pad, normalize_spacing(body)));
+    }
+
+    let tree = parse_token_tree(body)?;
+    let mut lines = String::new();
+    for child in tree.get_children() {
+        lines += &layout_goals(&child, body, INDENT);
+    }
+    return Ok(format!("{} :-\n{}.", head, lines));
+
+} // format_clause()
+
+// Formats the lines of source text. This is the body of format_source().
+//
+// Arguments
+//    source text
+// Return
+//    formatted text, or error
+fn format_lines(text: &str) -> Result<String, SuironError> {
+
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+
+    let mut out: Vec<String> = vec![];
+    let mut pending = "".to_string();     // text of an unfinished clause
+    let mut comments: Vec<String> = vec![];  // comments within the clause
+    let mut first_line = 1;               // line where the clause begins
+    // If the previous output was a clause, true if it was a rule.
+    let mut previous_rule: Option<bool> = None;
+    // Operators declared by the source.
+    let mut operators = KnowledgeBase::new();
+
+    for (index, line) in text.lines().enumerate() {
+
+        let line_number = index + 1;
+        let code = strip_comments(line);
+        let trimmed = line.trim();
+        let comment = trimmed[code.len()..].trim();
+
+        if pending.is_empty() {
+            if code.is_empty() {
+                if !comment.is_empty() {
+                    if previous_rule == Some(true) { out.push("".to_string()); }
+                    out.push(comment.to_string());
+                }
+                else if out.last().is_some_and(|l| !l.is_empty()) {
+                    out.push("".to_string());
+                }
+                previous_rule = None;
+                continue;
+            }
+            first_line = line_number;
+        }
+
+        if !comment.is_empty() { comments.push(comment.to_string()); }
+        if !code.is_empty() {
+            if !pending.is_empty() { pending.push(' '); }
+            pending += &code;
+        }
+
+        let (clauses, _) = locate_rules(&pending);
+        let mut end = 0;   // byte index after the last clause
+        for (_, clause) in clauses {
+            end += clause.len();
+            if clause.trim().is_empty() { continue; }
+            let formatted = with_operators(operators.operators(), || {
+                format_clause(&clause)
+            }).map_err(|err| {
+                SuironError::ParseError(format!("Line {}: {}", first_line, err))
+            })?;
+            // Each rule is a block, separated from other clauses by a blank line.
+            let is_rule = formatted.contains('\n');
+            if previous_rule.is_some_and(|r| r || is_rule) { out.push("".to_string()); }
+            out.append(&mut comments);
+            out.push(formatted);
+            previous_rule = Some(is_rule);
+            apply_operator(&mut operators, &clause)?;
+            first_line = line_number;
+        }
+        pending = pending[end..].trim().to_string();
+
+    } // for
+
+    if !pending.is_empty() {
+        let (_, unmatched) = locate_rules(&pending);
+        let err = match unmatched {
+            Some(err) => { err.to_string() },
+            None => { format!("Missing period: {}", pending) },
+        };
+        let msg = format!("Line {}: format_source() - {}", first_line, err);
+        return Err(SuironError::ParseError(msg));
+    }
+
+    out.append(&mut comments);
+    while out.last().is_some_and(|l| l.is_empty()) { out.pop(); }
+
+    let mut formatted = out.join("\n");
+    if !formatted.is_empty() { formatted.push('\n'); }
+    return Ok(formatted);
+
+} // format_lines()
+
+// If a clause is an op/3 directive, the operator is defined, so that
+// the facts and rules which follow can be parsed.
+//
+// Arguments
+//    knowledge base which holds the operators of the source
+//    clause
+// Return
+//    Ok or error
+fn apply_operator(operators: &mut KnowledgeBase, clause: &str) -> Result<(), SuironError> {
+    if let Some(directive) = clause.trim().strip_prefix(":-") {
+        if directive.trim_start().starts_with("op(") {
+            return execute_directive(operators, directive);
+        }
+    }
+    return Ok(());
+} // apply_operator()
+
+// Divides a rule into its head, neck (:- or -->) and body.
+//
+// Arguments
+//    rule, without the final period
+// Return
+//    (head, neck, body) or None, for a fact
+fn split_neck(rule: &str) -> Option<(&str, &str, &str)> {
+    let mut previous = ' ';
+    for sc in QuoteScanner::new(rule.chars()) {
+        if sc.quoted { previous = ' '; continue; }
+        if previous == ':' && sc.ch == '-' {
+            return Some((&rule[..sc.byte - 1], ":-", &rule[sc.byte + 1..]));
+        }
+        if previous == '-' && sc.ch == '-' && rule[sc.byte + 1..].starts_with('>') {
+            return Some((&rule[..sc.byte - 1], "-->", &rule[sc.byte + 2..]));
+        }
+        previous = sc.ch;
+    }
+    return None;
+} // split_neck()
+
+// Lays out the goals of a token tree, one subgoal per line. The text
+// of each subgoal is taken from the body of the rule, by its span.
+//
+// Arguments
+//    token
+//    body of rule
+//    indentation
+// Return
+//    formatted goals
+fn layout_goals(token: &Token, body: &str, indent: usize) -> String {
+
+    let pad = " ".repeat(indent);
+    match token {
+        Token::Leaf{ .. } => {
+            let text = &body[token.get_span().byte_range(body)];
+            return format!("{}{}", pad, normalize_spacing(text));
+        },
+        Token::Branch{ token_type, children, .. } => {
+            if *token_type == TokenType::Group {
+                // The opening parenthesis replaces the padding of the
+                // first goal, and the closing parenthesis follows the last.
+                let goals: Vec<String> = children.iter().map(|child| {
+                    layout_goals(child, body, indent + 1)
+                }).collect();
+                let goals = goals.join("\n");
+                return format!("{}({})", pad, &goals[indent + 1..]);
+            }
+            let separator = if *token_type == TokenType::Or { ";\n" } else { ",\n" };
+            let goals: Vec<String> = children.iter().map(|child| {
+                layout_goals(child, body, indent)
+            }).collect();
+            return goals.join(separator);
+        },
+    }
+
+} // layout_goals()
+
+/// Normalizes the spacing of a term or goal.
+///
+/// Runs of spaces are reduced to one space. A comma is followed by one
+/// space, and a list bar is surrounded by spaces. Spaces after an opening
+/// parenthesis or bracket, and before a closing parenthesis, bracket or
+/// comma, are removed. Text between quotes is not changed.
+///
+/// # Arguments
+/// * term or goal
+/// # Return
+/// * normalized text
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let s = normalize_spacing("append( [a,b|$T] ,  \"x  y\", $Out )");
+/// println!("{}", s);  // Prints: append([a, b | $T], "x  y", $Out)
+/// ```
+pub fn normalize_spacing(text: &str) -> String {
+
+    let mut out = String::new();
+    let mut space = false;     // a space is pending
+    let mut open = false;      // previous character opened a term or list
+    let mut escaped = false;   // previous character was a backslash
+
+    for sc in QuoteScanner::new(text.trim().chars()) {
+
+        let ch = sc.ch;
+        if !sc.quoted && !escaped {
+            if ch.is_whitespace() { space = true; continue; }
+            if ch == ')' || ch == ']' || ch == ',' {
+                out.push(ch);
+                space = ch == ',';
+                open = false;
+                continue;
+            }
+            if ch == '|' {
+                out.push_str(" |");
+                space = true;
+                open = false;
+                continue;
+            }
+        }
+
+        if space && !open { out.push(' '); }
+        space = false;
+        out.push(ch);
+
+        open = !sc.quoted && !escaped && (ch == '(' || ch == '[');
+        escaped = !sc.quoted && !escaped && ch == '\\';
+
+    } // for
+
+    return out;
+
+} // normalize_spacing()
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::*;
+
+    #[test]
+    fn test_normalize_spacing() {
+        assert_eq!("f(a, b)", normalize_spacing("f(a,b)"));
+        assert_eq!("f(a, b)", normalize_spacing("  f( a ,   b )  "));
+        assert_eq!("[1, 2 | $T]", normalize_spacing("[ 1,2|$T ]"));
+        assert_eq!("$X is ($Y + 1) * 2", normalize_spacing("$X  is ( $Y + 1 )  *  2"));
+        assert_eq!("print(\"a,b  c\", 'x  y')", normalize_spacing("print( \"a,b  c\",'x  y' )"));
+        assert_eq!("f(\\,, 0' )", normalize_spacing("f( \\, , 0' )"));
+    } // test_normalize_spacing()
+
+    #[test]
+    fn test_format_source() {
+
+        let source = "% Test\n\
+                      father( Godwin,Harold ).  % Harold II\n\
+                      \n\n\n\
+                      uncle($U, $N) :- sibling($U, $P),\n\
+                      \x20  (father($P, $N); mother($P, $N)).\n\
+                      test($X) :- ($X + 1) * 2 > 3, print(\"a, b\").\n\
+                      greeting --> [hello],name.\n\
+                      :- op(700, xfx, likes).\n\
+                      john likes mary.\n";
+
+        let expected = "% Test\n\
+                        % Harold II\n\
+                        father(Godwin, Harold).\n\
+                        \n\
+                        uncle($U, $N) :-\n\
+                        \x20   sibling($U, $P),\n\
+                        \x20   (father($P, $N);\n\
+                        \x20    mother($P, $N)).\n\
+                        \n\
+                        test($X) :-\n\
+                        \x20   ($X + 1) * 2 > 3,\n\
+                        \x20   print(\"a, b\").\n\
+                        \n\
+                        greeting -->\n\
+                        \x20   [hello], name.\n\
+                        \n\
+                        :- op(700, xfx, likes).\n\
+                        john likes mary.\n";
+
+        let formatted = format_source(source).unwrap();
+        assert_eq!(expected, formatted);
+        // Formatting is idempotent.
+        assert_eq!(expected, format_source(&formatted).unwrap());
+        // The operator does not remain defined.
+        assert!(get_operators().is_empty());
+
+        let err = format_source("a(1).\nb(2) :-\n  c(3)").unwrap_err();
+        assert_eq!("Line 2: format_source() - Missing period: b(2) :- c(3)", err);
+        let err = format_source("a(1).\nb(2) :- c(3)).").unwrap_err();
+        assert!(err.message().starts_with("Line 2: "));
+
+    } // test_format_source()
+
+    // The formatted source can be loaded, and gives the same knowledge base.
+    #[test]
+    fn test_format_reload() {
+
+        let source = "w($X) :- ($X = 1 ; $X = 2), v($X).\n\
+                      uncle($U, $N) :- sibling($U, $P), (father($P, $N); mother($P, $N)).\n\
+                      nested($X) :- ((a($X), b($X)) ; (c($X) ; d($X))), !, e($X).\n\
+                      v(1).\n";
+
+        let formatted = format_source(source).unwrap();
+        assert!(formatted.contains("    ($X = 1;\n     $X = 2),\n"), "{}", formatted);
+
+        let name = format!("suiron_format_test_{}.txt", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        let path = path.to_string_lossy().to_string();
+        std::fs::write(&path, &formatted).unwrap();
+
+        let mut kb = KnowledgeBase::new();
+        let result = load_kb_from_file(&mut kb, &path);
+        let _ = std::fs::remove_file(&path);
+        assert!(result.is_none(), "{}", result.unwrap());
+
+        let mut original = KnowledgeBase::new();
+        assert!(load_kb_from_reader(&mut original, source.as_bytes()).is_none());
+        assert_eq!(format_kb(&original), format_kb(&kb));
+
+    } // test_format_reload()
+
+    #[test]
+    fn test_format_file() {
+
+        let path = format!("{}/kings.txt", env!("SUIRON_TEST_DIR"));
+        let formatted = format_file(&path).unwrap();
+        let rules = read_facts_and_rules(&path).unwrap();
+
+        // The formatted source has the same facts and rules.
+        let mut kb = KnowledgeBase::new();
+        load_kb_from_reader(&mut kb, formatted.as_bytes());
+        let mut count = 0;
+        for (_, rules) in kb.iter() { count += rules.len(); }
+        assert_eq!(rules.len(), count);
+
+    } // test_format_file()
+
+} // test
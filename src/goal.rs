@@ -18,14 +18,17 @@ use std::cell::RefCell;
 use crate::*;
 
 use super::logic_var::*;
+use super::proof::capturing_proofs;
 use super::solution_node::*;
 use super::operator::Operator;
 use super::unifiable::Unifiable;
+use super::error::SuironError;
 use super::knowledge_base::*;
 use super::substitution_set::*;
 use super::built_in_predicates::*;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Goal {
     /// Holds an [Operator](../operator/enum.Operator.html),
     /// such as And, Or, Time etc.
@@ -56,8 +59,6 @@ impl Goal {
     /// ```
     /// use suiron::*;
     ///
-    /// clear_id();
-    ///
     /// // Create an And goal.
     /// let goal = generate_goal("father($X, $Z), mother($Z, $Y)");
     ///
@@ -79,15 +80,12 @@ impl Goal {
                return Goal::OperatorGoal(op.recreate_variables(vars));
             },
             Goal::ComplexGoal(u) => {
-                if let Unifiable::SComplex(_) = u {
-                    return Goal::ComplexGoal(u.recreate_variables(vars));
-                }
-                else { panic!("recreate_variables() - Unifiable must be SComplex."); }
+                return Goal::ComplexGoal(u.recreate_variables(vars));
             },
             Goal::BuiltInGoal(bipred) => {
                 return Goal::BuiltInGoal(bipred.recreate_variables(vars));
             },
-            _ => { panic!("{}", "goal.rs - Unknown goal."); },
+            Goal::Nil => { return Goal::Nil; },
         } // match
 
     } // recreate_variables()
@@ -144,32 +142,23 @@ impl Goal {
     /// the functor is `loves` and the arity is 2, therefore the name of the
     /// predicate is `loves/2`.
     ///
-    /// # Panics
-    /// * If self is not a
-    /// [complex](../unifiable/enum.Unifiable.html#variant.SComplex) term.
+    /// # Return
+    /// * key or error, if self is not a complex goal.
     /// # Usage
     /// ```
     /// use suiron::*;
     ///
     /// let goal = parse_subgoal("loves(Chandler, Monica)").unwrap();
-    /// let key = goal.key();
+    /// let key = goal.key().unwrap();
     /// println!("{}", key);  // Should print: loves/2
     /// ```
-    pub fn key(&self) -> String {
+    pub fn key(&self) -> Result<String, SuironError> {
         match self {
-            Goal::ComplexGoal(unifiable_term) => {
-                match unifiable_term {
-                    Unifiable::SComplex(terms) => {
-                        let functor = &terms[0];
-                        let arity = terms.len() - 1;
-                        return format!("{}/{}", functor, arity);
-                    },
-                    _ => { panic!("Goal::key() - \
-                           Valid only for SComplex. {}", unifiable_term); }
-                }
+            Goal::ComplexGoal(unifiable_term) => { unifiable_term.key() },
+            _ => {
+                let err = format!("Goal::key() - Valid only for ComplexGoal: {}", self);
+                Err(SuironError::ParseError(err))
             },
-            _ => { panic!("Goal::key() - \
-                           Valid only for ComplexGoal: {}", self); },
         } // match
     } // key()
 
@@ -237,9 +226,11 @@ impl Goal {
 /// # Return
 /// * reference to a [SolutionNode](../solution_node/struct.SolutionNode.html)
 ///
-/// # Panics
-/// * If the goal is not a
-/// [ComplexGoal](../goal/enum.Goal.html#variant.ComplexGoal)
+/// # Note
+/// * The goal should be a
+/// [ComplexGoal](../goal/enum.Goal.html#variant.ComplexGoal).
+/// Other goals have no solutions, except for Goal::Nil, which
+/// succeeds once.
 /// # Usage
 /// ```
 /// use std::rc::Rc;
@@ -253,17 +244,125 @@ impl Goal {
 pub fn make_base_node<'a>(goal: Rc<Goal>, kb: &'a KnowledgeBase)
                          -> Rc<RefCell<SolutionNode<'a>>> {
 
-    // Get predicate key for knowledge base.
-    let key = match &*goal {
-        Goal::ComplexGoal(cmplx) => { cmplx.key() },
-        _ => { panic!("make_base_node() - Goal must be a ComplexGoal."); },
-    };
+    if search_strategy() == SearchStrategy::IterativeDeepening {
+        let var_ids = VarIds::starting_at(max_var_id(&goal));
+        let mut node = SolutionNode::with_var_ids(Rc::clone(&goal), kb, var_ids);
+        node.deepening_solver = Some(rc_cell!(DeepeningSolver::new(goal, kb)));
+        return rc_cell!(node);
+    }
+    if binding_strategy() == BindingStrategy::Trail {
+        let var_ids = VarIds::starting_at(max_var_id(&goal));
+        let mut node = SolutionNode::with_var_ids(Rc::clone(&goal), kb, var_ids);
+        node.trail_solver = Some(rc_cell!(TrailSolver::new(goal, kb)));
+        return rc_cell!(node);
+    }
+    return make_depth_first_node(goal, kb);
 
-    let mut node = SolutionNode::new(goal, kb);
-    node.number_facts_rules = count_rules(kb, &key);
+} // make_base_node()
+
+// Makes a base solution node, which is solved depth first with
+// persistent substitution sets, regardless of the search and binding
+// strategies.
+//
+// Arguments
+//    goal to be proven
+//    knowledge base
+// Return
+//    reference to a solution node
+pub(crate) fn make_depth_first_node<'a>(goal: Rc<Goal>, kb: &'a KnowledgeBase)
+                                        -> Rc<RefCell<SolutionNode<'a>>> {
+
+    // Variables of rules are numbered after the variables of the query.
+    let var_ids = VarIds::starting_at(max_var_id(&goal));
+    let mut node = SolutionNode::with_var_ids(Rc::clone(&goal), kb, var_ids);
+    if let Goal::ComplexGoal(cmplx) = &*goal {
+        // Get predicate key for knowledge base.
+        let key = cmplx.key().unwrap_or_default();
+        count_candidates(&mut node, &key, cmplx);
+    }
     return rc_cell!(node);
 
-} // make_base_node()
+} // make_depth_first_node()
+
+// Gets the highest logic variable ID in a goal.
+//
+// Arguments
+//    goal
+// Return
+//    ID, or 0 if the goal has no variables
+pub(crate) fn max_var_id(goal: &Goal) -> usize {
+    match goal {
+        Goal::OperatorGoal(op) => {
+            let goals = match op {
+                Operator::And(goals) | Operator::Or(goals) |
+                Operator::Time(goals) | Operator::Not(goals) => { goals },
+            };
+            return goals.iter().map(max_var_id).max().unwrap_or(0);
+        },
+        Goal::BuiltInGoal(bip) => {
+            return bip.terms.as_ref().map_or(0, |terms| {
+                terms.iter().map(max_term_var_id).max().unwrap_or(0)
+            });
+        },
+        Goal::ComplexGoal(term) => { return max_term_var_id(term); },
+        Goal::Nil => { return 0; },
+    }
+} // max_var_id()
+
+// Gets the highest logic variable ID in a term.
+fn max_term_var_id(term: &Unifiable) -> usize {
+    match term {
+        Unifiable::LogicVar{id, name: _} => { return *id; },
+        Unifiable::SComplex(terms) |
+        Unifiable::SFunction{name: _, terms} => {
+            return terms.iter().map(max_term_var_id).max().unwrap_or(0);
+        },
+        Unifiable::SLinkedList{term: _, next: _, count: _, tail_var: _} => {
+            let mut max = 0;
+            let mut this_list = term;
+            while let Unifiable::SLinkedList{term, next, count: _, tail_var: _} = this_list {
+                max = max.max(max_term_var_id(term));
+                this_list = next;
+            }
+            return max.max(max_term_var_id(this_list));
+        },
+        _ => { return 0; },
+    }
+} // max_term_var_id()
+
+// Counts the facts and rules which may match a complex goal. If the
+// knowledge base is indexed, the indices of the candidates are saved
+// in the solution node. If the predicate is external, its facts and
+// rules are fetched and saved in the solution node. The answers of
+// a tabled predicate are saved in the same way.
+//
+// Arguments
+//    solution node
+//    predicate name
+//    goal (complex term)
+fn count_candidates(node: &mut SolutionNode, key: &str, cmplx: &Unifiable) {
+    if node.kb.is_tabled(key) {
+        let answers = tabled_answers(node.kb, &cmplx.replace_variables(&node.ss));
+        node.number_facts_rules = answers.len();
+        node.external_rules = Some(answers);
+        return;
+    }
+    node.number_facts_rules = count_rules(node.kb, key);
+    if node.number_facts_rules == 0 {
+        if let Some(external) = node.kb.external(key) {
+            let goal = cmplx.replace_variables(&node.ss);
+            if let Ok(rules) = external.fetch(&goal) {
+                node.number_facts_rules = rules.len();
+                node.external_rules = Some(Rc::new(rules));
+            }
+        }
+        return;
+    }
+    if let Some(candidates) = node.kb.candidates(key, cmplx, &node.ss) {
+        node.number_facts_rules = candidates.len();
+        node.candidates = Some(Rc::new(candidates));
+    }
+} // count_candidates()
 
 /// Makes a solution node according to the type of goal.
 ///
@@ -295,46 +394,114 @@ pub fn make_solution_node<'a>(goal: Rc<Goal>,
                               ss: Rc<SubstitutionSet<'a>>,
                               parent_node: Rc<RefCell<SolutionNode<'a>>>)
                               -> Rc<RefCell<SolutionNode<'a>>> {
+    let var_ids = parent_node.borrow().var_ids.clone();
+    return make_child_node(goal, kb, ss, parent_node, var_ids);
+} // make_solution_node()
+
+// Makes a solution node according to the type of goal. This function
+// is the same as make_solution_node(), but it does not borrow the
+// parent node, which may be borrowed by the caller.
+//
+// Arguments
+//    goal to be proven
+//    knowledge base
+//    parent substitution set
+//    parent solution node
+//    variable ID counter of the query
+// Return
+//    reference to a solution node
+pub(crate) fn make_child_node<'a>(goal: Rc<Goal>,
+                                  kb: &'a KnowledgeBase,
+                                  ss: Rc<SubstitutionSet<'a>>,
+                                  parent_node: Rc<RefCell<SolutionNode<'a>>>,
+                                  var_ids: VarIds)
+                                  -> Rc<RefCell<SolutionNode<'a>>> {
 
     // Make a solution node with defaults.
-    let mut node = SolutionNode::new(Rc::clone(&goal), kb);
+    let mut node = SolutionNode::with_var_ids(goal, kb, var_ids);
+    node.parent_node = Some(parent_node);
+    // Reused nodes would discard the steps of a proof.
+    node.reusable = !capturing_proofs();
+    let rc_node = rc_cell!(node);
+    set_up_node(&rc_node, ss);
+    return rc_node;
+
+} // make_child_node()
+
+/// Replaces the goal of a solution node, for last-call optimization.
+///
+/// When a solution node has only one goal left to solve, and no other
+/// alternatives, the node can be reused for that goal, instead of making
+/// a child node. Thus, deterministic recursive rules do not grow the
+/// proof tree. The node keeps its parent, except for complex goals,
+/// which have no parent node. (See make_solution_node().)
+///
+/// # Arguments
+/// * solution node
+/// * new goal
+/// * substitution set
+pub(crate) fn reuse_solution_node<'a>(sn: &Rc<RefCell<SolutionNode<'a>>>,
+                                      goal: Rc<Goal>,
+                                      ss: Rc<SubstitutionSet<'a>>) {
+    {
+        let mut sn_ref = sn.borrow_mut();
+        let var_ids = sn_ref.var_ids.clone();
+        let mut node = SolutionNode::with_var_ids(goal, sn_ref.kb, var_ids);
+        node.parent_node = sn_ref.parent_node.take();
+        node.reusable = true;
+        *sn_ref = node;
+    }
+    set_up_node(sn, ss);
+} // reuse_solution_node()
+
+// Sets up a new solution node according to the type of its goal.
+//
+// Arguments
+//    solution node
+//    parent substitution set
+fn set_up_node<'a>(rc_node: &Rc<RefCell<SolutionNode<'a>>>,
+                   ss: Rc<SubstitutionSet<'a>>) {
+
+    let goal = get_node_goal(rc_node);
+    let mut node = rc_node.borrow_mut();
+    let kb = node.kb;
 
     match &*goal {
 
         Goal::OperatorGoal(op) => {
 
-            node.parent_node = Some(parent_node);
+            node.ss = Rc::clone(&ss);
 
             match op {
 
                 Operator::Or(_) | Operator::And(_) => {
 
-                    node.ss = Rc::clone(&ss);
-
-                    let (head, tail) = op.split_head_tail();
+                    let (head, tail) = match op {
+                        Operator::And(goals) if query_planner() => {
+                            let goals = plan_conjunction(goals.clone(), kb,
+                                                         |t| t.replace_variables(&ss));
+                            Operator::And(goals).split_head_tail()
+                        },
+                        _ => { op.split_head_tail() },
+                    };
                     node.operator_tail = Some(tail);
-
-                    let rc_node = rc_cell!(node);
+                    drop(node);
 
                     // Solution node of first goal.
                     let head_node = make_solution_node(Rc::new(head), kb,
                                                        ss,
-                                                       Rc::clone(&rc_node));
+                                                       Rc::clone(rc_node));
 
-                    set_head_node(&rc_node, head_node);
-                    return rc_node;
+                    set_head_node(rc_node, head_node);
                 },
                 Operator::Time(goals) | Operator::Not(goals) => {
 
-                    node.ss = Rc::clone(&ss);
-
                     let goal = goals[0].clone();
-                    let rc_node = rc_cell!(node);
+                    drop(node);
                     let head_node = make_solution_node(Rc::new(goal), kb,
                                                        ss,
-                                                       Rc::clone(&rc_node));
-                    set_head_node(&rc_node, head_node);
-                    return rc_node;
+                                                       Rc::clone(rc_node));
+                    set_head_node(rc_node, head_node);
                 },
 
             } // match op
@@ -345,21 +512,20 @@ pub fn make_solution_node<'a>(goal: Rc<Goal>,
             node.parent_node = None;
 
             // Count the number of rules or facts which match the goal.
-            node.number_facts_rules = count_rules(kb, &cmplx.key());
-            return rc_cell!(node);
+            count_candidates(&mut node, &cmplx.key().unwrap_or_default(), cmplx);
 
         },
-        Goal::BuiltInGoal(_) => {
-
+        Goal::BuiltInGoal(_) | Goal::Nil => {
             node.ss = ss;
-            node.parent_node = Some(parent_node);
-            return rc_cell!(node);
-
         },
-        Goal::Nil => { panic!("goal.rs - Implement later."); },
 
     } // match
-} // make_solution_node()
+} // set_up_node()
+
+// Gets the goal of a solution node.
+fn get_node_goal(sn: &Rc<RefCell<SolutionNode>>) -> Rc<Goal> {
+    return Rc::clone(&sn.borrow().goal);
+}
 
 /// This helper function sets the head_sn field of the given and/or node.
 ///
@@ -387,26 +553,18 @@ impl fmt::Display for Goal {
 #[cfg(test)]
 mod test {
 
-    // Why run tests serially?
-    // LOGIC_VAR_ID is a global variable, which is unsafe.
-    // For testing purposes, logic variable IDs should always be consistent.
-    use serial_test::serial;
-
     use std::rc::Rc;
     use crate::*;
 
     // goals1 - Test key(), recreate_variables() and replace_variables().
     #[test]
-    #[serial]
     fn test_goals1() {
 
-        start_query();  // SUIRON_STOP_QUERY = false, LOGIC_VAR_ID = 0
-
         let goal = parse_subgoal("grandfather($Who, Aethelstan)").unwrap();
         let s1 = format!("{}", goal);
         assert_eq!("grandfather($Who, Aethelstan)", s1);
 
-        let predicate_name = goal.key();
+        let predicate_name = goal.key().unwrap();
         assert_eq!("grandfather/2", predicate_name);
 
         // var_map lists previously recreated logic variables.
@@ -419,7 +577,7 @@ mod test {
         // Set up substitution set.
         let mut ss = SubstitutionSet::new();
         ss.push(None);
-        ss.push(Some(Rc::new(Atom("Alfred".to_string()))));
+        ss.push(Some(Rc::new(Atom("Alfred".into()))));
 
         let result = query.replace_variables(&ss);
         let s1 = format!("{}", result);
@@ -429,11 +587,8 @@ mod test {
 
     // goals2 - Test base_node() and get_sn().
     #[test]
-    #[serial]
     fn test_goals2() {
 
-        start_query();  // SUIRON_STOP_QUERY = false, LOGIC_VAR_ID = 0
-
         let kb = test_kb();
 
         // Make a base node from a query: grandfather($X, $Y)
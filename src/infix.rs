@@ -4,6 +4,8 @@
 
 use std::fmt;
 
+use super::parse_terms::quoted_text;
+
 //-----------Infixes-----------
 #[derive(Debug)]
 #[derive(PartialEq)]
@@ -13,6 +15,10 @@ pub enum Infix {
     Unify,
     /// == Equal. No unification. Simply compares.
     Equal,
+    /// =:= Arithmetic equal. Evaluates and compares two expressions.
+    ArithmeticEqual,
+    /// =\\= Arithmetic not equal.
+    ArithmeticNotEqual,
     /// &gt;
     GreaterThan,
     /// &lt;
@@ -29,6 +35,28 @@ pub enum Infix {
     Multiply,
     /// &#47;
     Divide,
+    /// // - Integer division.
+    IntDivide,
+    /// is - Arithmetic evaluation.
+    Is,
+    /// mod - Modulo.
+    Mod,
+    /// rem - Remainder.
+    Rem,
+    /// rdiv - Rational division.
+    RDiv,
+    /// ** - Power.
+    Power,
+    /// /\\ - Bitwise and.
+    BitAnd,
+    /// \\/ - Bitwise or.
+    BitOr,
+    /// xor - Bitwise exclusive or.
+    Xor,
+    /// << - Shift left.
+    ShiftLeft,
+    /// >> - Shift right.
+    ShiftRight,
 }
 
 /// Determines whether a string contains an infix: >=, ==, etc.
@@ -47,7 +75,7 @@ pub enum Infix {
 ///
 /// # Notes
 /// * An infix must be preceded and followed by a space. This is invalid: `$X<6`
-/// * The function ignores characters between double quotes and parentheses.<br>
+/// * The function ignores characters between quotes and parentheses.<br>
 /// For example, for the the string of characters `" <= "` (double quotes included),<br>
 /// the function will return (Infix::None, 0).
 ///
@@ -63,6 +91,7 @@ pub fn check_infix(chrs: &Vec<char>) -> (Infix, usize) {
 
     let length = chrs.len();
     let mut prev   = '#';  // not a space
+    let quoted = quoted_text(chrs);
 
     let mut i = 0;
     while i < length {
@@ -73,24 +102,16 @@ pub fn check_infix(chrs: &Vec<char>) -> (Infix, usize) {
         let mut c3 = '#';
         if i + 2 < length { c3 = chrs[i + 2]; }
 
-        // Skip past quoted text: ">>>>>"
-        if c1 == '"' {
-            let mut j = i + 1;
-            while j < length  {
-                let cx = chrs[j];
-                if cx == '"' {
-                    i = j;
-                    break;
-                }
-                j += 1;
-            }
+        // Skip past quoted text: ">>>>>" or '>>>>>'
+        if quoted[i] {
+            while i + 1 < length && quoted[i + 1] { i += 1; }
         }
         else if c1 == '(' {
             // Skip past text within parentheses: (...)
             let mut j = i + 1;
             while j < length {
                 let cx = chrs[j];
-                if cx == ')' {
+                if cx == ')' && !quoted[j] {
                     i = j;
                     break;
                 }
@@ -133,10 +154,19 @@ pub fn check_infix(chrs: &Vec<char>) -> (Infix, usize) {
                         return (Infix::Equal, i);
                     }
                 }
+                else if (c2 == ':' || c2 == '\\') && c3 == '=' {
+                    if i + 3 < length && chrs[i + 3] == ' ' {
+                        if c2 == ':' { return (Infix::ArithmeticEqual, i); }
+                        return (Infix::ArithmeticNotEqual, i);
+                    }
+                }
                 else if c2 == ' ' {
                     return (Infix::Unify, i);
                 }
             }
+            else if c1 == 'i' && c2 == 's' && c3 == ' ' {
+                return (Infix::Is, i);
+            }
 
         } // else
 
@@ -149,7 +179,8 @@ pub fn check_infix(chrs: &Vec<char>) -> (Infix, usize) {
 
 } // check_infix
 
-/// Determines whether a string contains an arithmetic infix: +, -, *, /
+/// Determines whether a string contains an arithmetic infix: +, -, *, /, //,
+/// mod, rem, rdiv, **, /\\, \\/, xor, <<, >>
 ///
 /// This function returns the type and index of the arithmetic infix.<br>
 /// For example, <code>$X * 6</code> contains Infix::Multiply, at index 3.
@@ -161,7 +192,7 @@ pub fn check_infix(chrs: &Vec<char>) -> (Infix, usize) {
 ///
 /// # Notes
 /// * An infix must be preceded and followed by a space. This is invalid:  `$X*6`
-/// * The function ignores characters between double quotes and parentheses.<br>
+/// * The function ignores characters between quotes and parentheses.<br>
 /// For example, for the the string of characters `" * "` (double quotes included),<br>
 /// the function will return (Infix::None, 0).
 ///
@@ -177,6 +208,7 @@ pub fn check_arithmetic_infix(chrs: &Vec<char>) -> (Infix, usize) {
 
     let length = chrs.len();
     let mut prev   = '#';  // not a space
+    let quoted = quoted_text(chrs);
 
     let mut i = 0;
     while i < length {
@@ -184,24 +216,19 @@ pub fn check_arithmetic_infix(chrs: &Vec<char>) -> (Infix, usize) {
         let c1 = chrs[i];
         let mut c2 = '#';
         if i + 1 < length { c2 = chrs[i + 1]; }
+        let mut c3 = '#';
+        if i + 2 < length { c3 = chrs[i + 2]; }
 
-        // Skip past quoted text: ">>>>>"
-        if c1 == '"' {
-            let mut j = i + 1;
-            while j < length  {
-                let cx = chrs[j];
-                if cx == '"' {
-                    i = j; break;
-                }
-                j += 1;
-            }
+        // Skip past quoted text: ">>>>>" or '>>>>>'
+        if quoted[i] {
+            while i + 1 < length && quoted[i + 1] { i += 1; }
         }
         else if c1 == '(' {
             // Skip past text within parentheses: (...)
             let mut j = i + 1;
             while j < length {
                 let cx = chrs[j];
-                if cx == ')' {
+                if cx == ')' && !quoted[j] {
                     i = j; break;
                 }
                 j += 1;
@@ -222,9 +249,31 @@ pub fn check_arithmetic_infix(chrs: &Vec<char>) -> (Infix, usize) {
             else
             if c1 == '-' { if c2 == ' ' { return (Infix::Minus, i); } }
             else
-            if c1 == '*' { if c2 == ' ' { return (Infix::Multiply, i); } }
+            if c1 == '*' {
+                if c2 == ' ' { return (Infix::Multiply, i); }
+                if c2 == '*' && c3 == ' ' { return (Infix::Power, i); }
+            }
+            else
+            if c1 == '/' {
+                if c2 == ' ' { return (Infix::Divide, i); }
+                if c2 == '\\' && c3 == ' ' { return (Infix::BitAnd, i); }
+                if c2 == '/' && c3 == ' ' { return (Infix::IntDivide, i); }
+            }
+            else
+            if c1 == '\\' { if c2 == '/' && c3 == ' ' { return (Infix::BitOr, i); } }
+            else
+            if c1 == '<' { if c2 == '<' && c3 == ' ' { return (Infix::ShiftLeft, i); } }
+            else
+            if c1 == '>' { if c2 == '>' && c3 == ' ' { return (Infix::ShiftRight, i); } }
             else
-            if c1 == '/' { if c2 == ' ' { return (Infix::Divide, i); } }
+            if c1 == 'm' || c1 == 'r' || c1 == 'x' {
+                let word: String = chrs[i..length.min(i + 4)].iter().collect();
+                if word == "mod " { return (Infix::Mod, i); }
+                if word == "rem " { return (Infix::Rem, i); }
+                if word == "xor " { return (Infix::Xor, i); }
+                let word: String = chrs[i..length.min(i + 5)].iter().collect();
+                if word == "rdiv " { return (Infix::RDiv, i); }
+            }
         } // else
 
         prev = c1;
@@ -242,6 +291,8 @@ impl fmt::Display for Infix {
             Infix::None => write!(f, "None"),
             Infix::Unify => write!(f, "="),
             Infix::Equal => write!(f, "=="),
+            Infix::ArithmeticEqual => write!(f, "=:="),
+            Infix::ArithmeticNotEqual => write!(f, "=\\="),
             Infix::GreaterThan => write!(f, ">"),
             Infix::LessThan => write!(f, "<"),
             Infix::GreaterThanOrEqual => write!(f, ">="),
@@ -250,6 +301,17 @@ impl fmt::Display for Infix {
             Infix::Minus => write!(f, "-"),
             Infix::Multiply => write!(f, "*"),
             Infix::Divide => write!(f, "/"),
+            Infix::IntDivide => write!(f, "//"),
+            Infix::Is => write!(f, "is"),
+            Infix::Mod => write!(f, "mod"),
+            Infix::Rem => write!(f, "rem"),
+            Infix::RDiv => write!(f, "rdiv"),
+            Infix::Power => write!(f, "**"),
+            Infix::BitAnd => write!(f, "/\\"),
+            Infix::BitOr => write!(f, "\\/"),
+            Infix::Xor => write!(f, "xor"),
+            Infix::ShiftLeft => write!(f, "<<"),
+            Infix::ShiftRight => write!(f, ">>"),
         };
     } // fmt
 } // fmt::Display
@@ -298,6 +360,27 @@ mod test {
         assert_eq!(inf, Infix::Equal);
         assert_eq!(ind, 4, "Equal");
 
+        let chrs = str_to_chars!("$X is $Y + 1");
+        let (inf, ind) = check_infix(&chrs);
+        assert_eq!(inf, Infix::Is);
+        assert_eq!(ind, 3, "Is");
+
+        let chrs = str_to_chars!("$X * 2 =:= $Y");
+        let (inf, ind) = check_infix(&chrs);
+        assert_eq!(inf, Infix::ArithmeticEqual);
+        assert_eq!(ind, 7, "ArithmeticEqual");
+
+        let chrs = str_to_chars!("$X =\\= 0");
+        let (inf, ind) = check_infix(&chrs);
+        assert_eq!(inf, Infix::ArithmeticNotEqual);
+        assert_eq!(ind, 3, "ArithmeticNotEqual");
+
+        // 'is' must be a separate word.
+        let chrs = str_to_chars!("$X = this");
+        let (inf, ind) = check_infix(&chrs);
+        assert_eq!(inf, Infix::Unify);
+        assert_eq!(ind, 3, "Unify");
+
         let chrs = str_to_chars!("\" <= \"");
         let (inf, ind) = check_infix(&chrs);
         assert_eq!(inf, Infix::None);
@@ -350,6 +433,11 @@ mod test {
         assert_eq!(inf, Infix::Divide);
         assert_eq!(ind, 3, "Divide operator");
 
+        let chrs = str_to_chars!("$X // 7");
+        let (inf, ind) = check_arithmetic_infix(&chrs);
+        assert_eq!(inf, Infix::IntDivide);
+        assert_eq!(ind, 3, "IntDivide operator");
+
         let chrs = str_to_chars!("$X + 7");
         let (inf, ind) = check_arithmetic_infix(&chrs);
         assert_eq!(inf, Infix::Plus);
@@ -360,6 +448,51 @@ mod test {
         assert_eq!(inf, Infix::Minus);
         assert_eq!(ind, 3, "Minus operator");
 
+        let chrs = str_to_chars!("$X mod 7");
+        let (inf, ind) = check_arithmetic_infix(&chrs);
+        assert_eq!(inf, Infix::Mod);
+        assert_eq!(ind, 3, "Mod operator");
+
+        let chrs = str_to_chars!("$X rem 7");
+        let (inf, ind) = check_arithmetic_infix(&chrs);
+        assert_eq!(inf, Infix::Rem);
+        assert_eq!(ind, 3, "Rem operator");
+
+        let chrs = str_to_chars!("$X rdiv 3");
+        let (inf, ind) = check_arithmetic_infix(&chrs);
+        assert_eq!(inf, Infix::RDiv);
+        assert_eq!(ind, 3, "Rational division operator");
+
+        let chrs = str_to_chars!("$X ** 2");
+        let (inf, ind) = check_arithmetic_infix(&chrs);
+        assert_eq!(inf, Infix::Power);
+        assert_eq!(ind, 3, "Power operator");
+
+        let chrs = str_to_chars!("$X /\\ $Y");
+        let (inf, ind) = check_arithmetic_infix(&chrs);
+        assert_eq!(inf, Infix::BitAnd);
+        assert_eq!(ind, 3, "Bitwise and operator");
+
+        let chrs = str_to_chars!("$X \\/ $Y");
+        let (inf, ind) = check_arithmetic_infix(&chrs);
+        assert_eq!(inf, Infix::BitOr);
+        assert_eq!(ind, 3, "Bitwise or operator");
+
+        let chrs = str_to_chars!("$X xor $Y");
+        let (inf, ind) = check_arithmetic_infix(&chrs);
+        assert_eq!(inf, Infix::Xor);
+        assert_eq!(ind, 3, "Xor operator");
+
+        let chrs = str_to_chars!("$X << 2");
+        let (inf, ind) = check_arithmetic_infix(&chrs);
+        assert_eq!(inf, Infix::ShiftLeft);
+        assert_eq!(ind, 3, "Shift left operator");
+
+        let chrs = str_to_chars!("$X >> 2");
+        let (inf, ind) = check_arithmetic_infix(&chrs);
+        assert_eq!(inf, Infix::ShiftRight);
+        assert_eq!(ind, 3, "Shift right operator");
+
         // Not an arithmetic operator.
         let chrs = str_to_chars!("$X < 7");
         let (inf, ind) = check_arithmetic_infix(&chrs);
@@ -0,0 +1,282 @@
+//! An iterative-deepening search strategy.
+//!
+//! By default, Suiron searches depth first. If the first rule of a
+//! predicate leads down an infinite branch, the search never reaches
+//! the other rules, even if they have solutions. For example:
+//! <pre>
+//!     nat(s($X)) :- nat($X).
+//!     nat(0).
+//! </pre>
+//!
+//! The query `nat($N)` never finds `nat(0)` with a depth-first search.
+//! Iterative deepening searches depth first, but cuts off goals which are
+//! nested more deeply than a bound. If the search was cut off, it is
+//! repeated with a deeper bound, until it finishes without being cut off.
+//! Thus, every solution is found eventually. (An infinite branch which is
+//! tail recursive is not cut off, because a last call reuses its solution
+//! node, and does not go deeper.)
+//!
+//! The strategy is chosen with
+//! [set_search_strategy()](fn.set_search_strategy.html). A query which is
+//! set up by [make_base_node()](../goal/fn.make_base_node.html) is then
+//! solved by a [DeepeningSolver](struct.DeepeningSolver.html).
+//!
+//! Each solution is returned once, even though the search is repeated.
+//! (Solutions which bind the variables of the query to the same terms are
+//! considered to be the same.) Solutions are not found in depth-first
+//! order. If a goal of not() is cut off, the search is repeated with a
+//! deeper bound, because the goal cannot be shown to fail. A search which
+//! reaches the [maximum proof depth](../solution_node/fn.set_max_depth.html)
+//! stops with a
+//! [DepthLimitExceeded](../error/enum.SuironError.html#variant.DepthLimitExceeded)
+//! error. A search which never finishes, because the query has infinitely
+//! many solutions or an infinite branch, is stopped by the query timer.
+//!
+// Cleve Lendon 2023
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use crate::*;
+
+use super::goal::Goal;
+use super::tabling::variant;
+
+// Depth bound of the first search.
+const INITIAL_BOUND: usize = 16;
+
+/// Defines the order in which the proof tree is searched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchStrategy {
+    /// Depth-first search. (Default.)
+    DepthFirst,
+    /// Depth-first search with an increasing depth bound.
+    IterativeDeepening,
+}
+
+thread_local! {
+    static SEARCH_STRATEGY: Cell<SearchStrategy> = const { Cell::new(SearchStrategy::DepthFirst) };
+}
+
+/// Sets the search strategy for queries which are set up by
+/// [make_base_node()](../goal/fn.make_base_node.html).
+///
+/// Iterative deepening uses persistent substitution sets, regardless of
+/// the [binding strategy](../trail/fn.set_binding_strategy.html).
+///
+/// # Arguments
+/// * [SearchStrategy](enum.SearchStrategy.html)
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// add_rules!(&mut kb, parse_rule("nat(s($X)) :- nat($X).").unwrap(),
+///                     parse_rule("nat(0).").unwrap());
+///
+/// set_search_strategy(SearchStrategy::IterativeDeepening);
+/// let query = parse_query("nat($N)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+/// let solutions = try_solve_all(sn, Some(3)).unwrap();
+/// assert_eq!(3, solutions.len());
+/// set_search_strategy(SearchStrategy::DepthFirst);
+/// ```
+pub fn set_search_strategy(strategy: SearchStrategy) {
+    SEARCH_STRATEGY.with(|s| s.set(strategy));
+}
+
+/// Gets the search strategy.
+/// See [set_search_strategy()](fn.set_search_strategy.html).
+///
+/// # Return
+/// * [SearchStrategy](enum.SearchStrategy.html)
+pub fn search_strategy() -> SearchStrategy {
+    SEARCH_STRATEGY.with(|s| s.get())
+}
+
+/// Solves a query by iterative deepening.
+///
+/// Usually, a DeepeningSolver is made by
+/// [make_base_node()](../goal/fn.make_base_node.html). See
+/// [set_search_strategy()](fn.set_search_strategy.html).
+///
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// add_rules!(&mut kb, parse_rule("nat(s($X)) :- nat($X).").unwrap(),
+///                     parse_rule("nat(0).").unwrap());
+///
+/// let query = Rc::new(parse_query("nat($N)").unwrap());
+/// let mut solver = DeepeningSolver::new(Rc::clone(&query), &kb);
+/// let ss = solver.next_solution().unwrap();
+/// println!("{}", query.replace_variables(&ss));
+/// ```
+#[derive(Debug)]
+pub struct DeepeningSolver<'a> {
+    query: Rc<Goal>,
+    kb: &'a KnowledgeBase,
+    // Solution node of the current search.
+    sn: Rc<RefCell<SolutionNode<'a>>>,
+    bound: usize,
+    // True if a goal of the current search was cut off.
+    cut_off: bool,
+    finished: bool,
+    // Variant keys of the solutions which have been returned.
+    found: HashSet<String>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl<'a> DeepeningSolver<'a> {
+
+    /// Creates a solver for a query.
+    ///
+    /// # Arguments
+    /// * query (goal)
+    /// * knowledge base
+    /// # Return
+    /// * DeepeningSolver
+    pub fn new(query: Rc<Goal>, kb: &'a KnowledgeBase) -> Self {
+        let sn = make_depth_first_node(Rc::clone(&query), kb);
+        DeepeningSolver{ query, kb, sn, bound: INITIAL_BOUND, cut_off: false,
+                         finished: false, found: HashSet::new(), cancellation: None }
+    } // new()
+
+    /// Sets the token which cancels the search.
+    /// See [cancellation_token()](../cancellation/fn.cancellation_token.html).
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * [CancellationToken](../cancellation/struct.CancellationToken.html)
+    pub fn set_cancellation(&mut self, token: CancellationToken) {
+        self.sn.borrow_mut().cancellation = Some(token.clone());
+        self.cancellation = Some(token);
+    }
+
+    /// Gets the depth bound of the current search.
+    pub fn bound(&self) -> usize { self.bound }
+
+    /// Finds the next solution.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * substitution set, or None if there are no more solutions
+    pub fn next_solution(&mut self) -> Option<Rc<SubstitutionSet<'a>>> {
+
+        let max = max_depth().unwrap_or(usize::MAX);
+
+        while !self.finished {
+
+            let previous = depth_bound();
+            set_depth_bound(Some(self.bound));
+            set_depth_bound_reached(false);
+            let solution = next_solution(Rc::clone(&self.sn));
+            self.cut_off = self.cut_off || depth_bound_reached();
+            set_depth_bound(previous);
+
+            if let Some(token) = &self.cancellation {
+                if token.is_stopped() { return None; }
+            }
+
+            match solution {
+                Some(ss) => {
+                    let result = self.query.replace_variables(&ss);
+                    if self.found.insert(variant(&result, &mut vec![]).to_string()) {
+                        return Some(ss);
+                    }
+                },
+                None => {
+                    if !self.cut_off { self.finished = true; }
+                    else if self.bound >= max {
+                        self.finished = true;
+                        set_depth_limit_exceeded(true);
+                    }
+                    else {
+                        // Search again, with a deeper bound.
+                        self.bound = self.bound.saturating_mul(2).min(max);
+                        self.cut_off = false;
+                        self.sn = make_depth_first_node(Rc::clone(&self.query), self.kb);
+                        self.sn.borrow_mut().cancellation = self.cancellation.clone();
+                    }
+                },
+            }
+        }
+        return None;
+
+    } // next_solution()
+
+} // impl DeepeningSolver
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+
+    use crate::*;
+
+    fn solve_deepening(kb: &KnowledgeBase, query: &str, max: Option<usize>)
+                       -> Result<Vec<String>, SuironError> {
+        let query = parse_query(query).unwrap();
+        let sn = make_base_node(Rc::new(query), kb);
+        let mut results = try_solve_all(sn, max)?;
+        results.sort();
+        return Ok(results);
+    }
+
+    // Iterative deepening finds the solutions which a depth-first
+    // search misses, because it goes down an infinite branch.
+    #[test]
+    fn test_iterative_deepening() {
+
+        let mut kb = test_kb();
+        for rule in ["nat(s($X)) :- nat($X).", "nat(0).",
+                     "ancestor($X, $Y) :- ancestor($X, $Z), father($Z, $Y).",
+                     "ancestor($X, $Y) :- father($X, $Y).",
+                     "safe :- not(ancestor(Alfred, Nobody))."] {
+            add_rules!(&mut kb, parse_rule(rule).unwrap());
+        }
+
+        set_search_strategy(SearchStrategy::IterativeDeepening);
+
+        // Deeper solutions are found first. Each is found once.
+        let mut results = solve_deepening(&kb, "nat($N)", Some(20)).unwrap();
+        assert!(results.contains(&"$N = 0".to_string()));
+        results.dedup();
+        assert_eq!(20, results.len());
+
+        // A query which is not infinite gives the usual solutions.
+        let results = solve_deepening(&kb, "loves($Who, $Whom)", None).unwrap();
+        assert_eq!(vec!["$Who = Leonard, $Whom = Penny",
+                        "$Who = Penny, $Whom = Leonard"], results);
+
+        let query = parse_query("ancestor(Alfred, $Y)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert!(sn.borrow().deepening_solver.is_some());
+        let mut results = vec![try_solve(Rc::clone(&sn)).unwrap().unwrap(),
+                               try_solve(sn).unwrap().unwrap()];
+        results.sort();
+        assert_eq!(vec!["$Y = Aethelstan", "$Y = Edward"], results);
+
+        // The search stops at the maximum depth. The goal of not()
+        // cannot be shown to fail.
+        set_max_depth(Some(200));
+        let result = solve_deepening(&kb, "safe", None);
+        assert!(matches!(result, Err(SuironError::DepthLimitExceeded(_))));
+        let result = solve_deepening(&kb, "ancestor(Alfred, $Y)", None);
+        assert!(matches!(result, Err(SuironError::DepthLimitExceeded(_))));
+        set_max_depth(None);
+
+        set_search_strategy(SearchStrategy::DepthFirst);
+        let query = parse_query("nat($N)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert!(sn.borrow().deepening_solver.is_none());
+
+    } // test_iterative_deepening()
+
+} // test
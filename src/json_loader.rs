@@ -0,0 +1,258 @@
+//! Functions to load facts from JSON documents. Requires the `json` feature.
+//!
+//! A JSON array becomes a list of facts, one for each element:
+//!
+//! <pre>
+//!   [["June", 61], ["Ola", 23]]     person(June, 61).  person(Ola, 23).
+//! </pre>
+//!
+//! An object becomes a fact whose arguments are the values of its fields.
+//! The fields are chosen by a [JsonMapping](struct.JsonMapping.html).
+//! If no fields are given, each field becomes a complex term, in
+//! alphabetical order:
+//!
+//! <pre>
+//!   [{"name": "June", "age": 61}]   person(age(61), name(June)).
+//! </pre>
+//!
+//! Within a fact, values are converted as follows:
+//!
+//! <pre>
+//!   string        Atom (or SString)
+//!   integer       SInteger
+//!   other number  SFloat
+//!   true, false   Atom
+//!   null          Atom (null)
+//!   array         SLinkedList
+//!   object        SLinkedList of complex terms, eg. [age(61), name(June)]
+//! </pre>
+//!
+//! A top-level value which is not an array becomes a single fact.
+//
+// Cleve Lendon 2023
+
+use std::fs;
+
+use serde_json::Value;
+
+use super::error::SuironError;
+use super::knowledge_base::KnowledgeBase;
+use super::s_linked_list::make_linked_list;
+use super::unifiable::Unifiable;
+
+use crate::atom;
+
+/// Defines how JSON values are converted into facts and terms.
+///
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mapping = JsonMapping{ fields: Some(vec!["name".to_string(), "age".to_string()]),
+///                            ..JsonMapping::default() };
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonMapping {
+    /// The fields of a top-level object which become the arguments of
+    /// a fact, in order. A missing field becomes the null atom.
+    /// If None, each field becomes a complex term, eg. age(61).
+    pub fields: Option<Vec<String>>,
+    /// If true, JSON strings become SStrings. Otherwise, they become atoms.
+    pub strings: bool,
+    /// The atom which represents null.
+    pub null: String,
+}
+
+impl Default for JsonMapping {
+    fn default() -> Self {
+        JsonMapping{ fields: None, strings: false, null: "null".to_string() }
+    }
+}
+
+/// Converts a JSON value into a term.
+///
+/// # Arguments
+/// * JSON value
+/// * mapping
+/// # Return
+/// * term
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let value: serde_json::Value = serde_json::from_str(r#"{"a": [1, 2.5, "x"]}"#).unwrap();
+/// let term = json_to_term(&value, &JsonMapping::default());
+/// println!("{}", term);  // Prints: [a([1, 2.5, x])]
+/// ```
+pub fn json_to_term(value: &Value, mapping: &JsonMapping) -> Unifiable {
+    match value {
+        Value::Null => { atom!(mapping.null.as_str()) },
+        Value::Bool(b) => { atom!(b) },
+        Value::Number(n) => {
+            match n.as_i64() {
+                Some(i) => { Unifiable::SInteger(i) },
+                None => { Unifiable::SFloat(n.as_f64().unwrap_or(f64::NAN)) },
+            }
+        },
+        Value::String(s) => {
+            if mapping.strings { Unifiable::SString(s.to_string()) }
+            else { atom!(s.as_str()) }
+        },
+        Value::Array(values) => {
+            let terms = values.iter().map(|v| json_to_term(v, mapping)).collect();
+            make_linked_list(false, terms)
+        },
+        Value::Object(_) => {
+            make_linked_list(false, field_terms(value, mapping))
+        },
+    }
+} // json_to_term()
+
+/// Adds facts from a JSON document to a knowledge base.
+///
+/// # Arguments
+/// * knowledge base
+/// * predicate functor, eg. \"person\"
+/// * JSON text
+/// * mapping
+/// # Return
+/// * number of facts added, or error
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// let json = r#"[{"name": "June", "age": 61}, {"name": "Ola", "age": 23}]"#;
+/// let mapping = JsonMapping{ fields: Some(vec!["name".to_string(), "age".to_string()]),
+///                            ..JsonMapping::default() };
+/// load_json_facts(&mut kb, "person", json, &mapping).unwrap();
+/// print_kb(&kb);  // person(June, 61).  person(Ola, 23).
+/// ```
+pub fn load_json_facts(kb: &mut KnowledgeBase, functor: &str, json: &str,
+                       mapping: &JsonMapping) -> Result<usize, SuironError> {
+
+    let value: Value = match serde_json::from_str(json) {
+        Ok(value) => { value },
+        Err(err) => {
+            let msg = format!("load_json_facts() - Invalid JSON: {}", err);
+            return Err(SuironError::ParseError(msg));
+        },
+    };
+
+    let facts = match &value {
+        Value::Array(values) => {
+            values.iter().map(|v| fact_arguments(v, mapping)).collect()
+        },
+        _ => { vec![fact_arguments(&value, mapping)] },
+    };
+    return Ok(kb.add_facts(functor, facts));
+
+} // load_json_facts()
+
+/// Adds facts from a JSON file to a knowledge base.
+///
+/// See [load_json_facts()](fn.load_json_facts.html).
+///
+/// # Arguments
+/// * knowledge base
+/// * predicate functor, eg. \"person\"
+/// * file name
+/// * mapping
+/// # Return
+/// * number of facts added, or error
+pub fn load_json_file(kb: &mut KnowledgeBase, functor: &str, file_name: &str,
+                      mapping: &JsonMapping) -> Result<usize, SuironError> {
+    let json = fs::read_to_string(file_name)?;
+    return load_json_facts(kb, functor, &json, mapping);
+} // load_json_file()
+
+// Gets the arguments of a fact from a JSON value.
+//
+// Arguments
+//    JSON value
+//    mapping
+// Return
+//    arguments
+fn fact_arguments(value: &Value, mapping: &JsonMapping) -> Vec<Unifiable> {
+    match (value, &mapping.fields) {
+        (Value::Array(values), _) => {
+            values.iter().map(|v| json_to_term(v, mapping)).collect()
+        },
+        (Value::Object(map), Some(fields)) => {
+            fields.iter().map(|field| {
+                match map.get(field) {
+                    Some(v) => { json_to_term(v, mapping) },
+                    None => { atom!(mapping.null.as_str()) },
+                }
+            }).collect()
+        },
+        (Value::Object(_), None) => { field_terms(value, mapping) },
+        _ => { vec![json_to_term(value, mapping)] },
+    }
+} // fact_arguments()
+
+// Converts the fields of a JSON object into complex terms, eg. age(61).
+//
+// Arguments
+//    JSON object
+//    mapping
+// Return
+//    complex terms, in alphabetical order of the keys
+fn field_terms(object: &Value, mapping: &JsonMapping) -> Vec<Unifiable> {
+    let mut terms: Vec<Unifiable> = vec![];
+    if let Value::Object(map) = object {
+        let mut keys: Vec<&String> = map.keys().collect();
+        keys.sort();
+        for key in keys {
+            let value = json_to_term(&map[key], mapping);
+            terms.push(Unifiable::SComplex(vec![atom!(key.as_str()), value]));
+        }
+    }
+    return terms;
+} // field_terms()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    #[test]
+    fn test_load_json_facts() {
+
+        let mut kb = KnowledgeBase::new();
+        let json = r#"[{"name": "June", "age": 61, "tags": ["tea", "jazz"]},
+                       {"name": "Ola", "age": 23.5, "home": {"city": "Oslo"}},
+                       {"age": null}]"#;
+
+        let mapping = JsonMapping{ fields: Some(vec!["name".to_string(), "age".to_string()]),
+                                   ..JsonMapping::default() };
+        assert_eq!(Ok(3), load_json_facts(&mut kb, "person", json, &mapping));
+        let facts: Vec<String> = kb.rules("person/2").map(|r| r.to_string()).collect();
+        assert_eq!(vec!["person(June, 61).", "person(Ola, 23.5).", "person(null, null)."],
+                   facts);
+
+        let mapping = JsonMapping{ strings: true, null: "none".to_string(),
+                                   ..JsonMapping::default() };
+        assert_eq!(Ok(3), load_json_facts(&mut kb, "record", json, &mapping));
+        let facts: Vec<String> = kb.rules("record/3").map(|r| r.to_string()).collect();
+        assert_eq!(vec!["record(age(61), name(\"June\"), tags([\"tea\", \"jazz\"])).",
+                        "record(age(23.5), home([city(\"Oslo\")]), name(\"Ola\"))."],
+                   facts);
+        assert_eq!("record(age(none)).", get_rule(&kb, "record/1", 0).unwrap().to_string());
+
+        let json = r#"[["a", "b"], ["b", "c"], [true, 7]]"#;
+        load_json_facts(&mut kb, "edge", json, &JsonMapping::default()).unwrap();
+        let query = parse_query("edge(b, $X)").unwrap();
+        assert_eq!("$X = c", solve(make_base_node(Rc::new(query), &kb)));
+        let query = parse_query("edge(true, $X)").unwrap();
+        assert_eq!("$X = 7", solve(make_base_node(Rc::new(query), &kb)));
+
+        let err = load_json_facts(&mut kb, "bad", "[1, 2", &JsonMapping::default());
+        assert!(matches!(err, Err(SuironError::ParseError(_))));
+        assert_eq!(Ok(1), load_json_facts(&mut kb, "single", "42", &JsonMapping::default()));
+
+    } // test_load_json_facts()
+
+} // test
@@ -13,19 +13,700 @@
 //!
 //! the predicate name is: `grandfather/2`.
 //!
+//! Knowledge bases are cheap to clone. Each predicate's list of facts and
+//! rules is shared, and copied only when the clone modifies it. See
+//! [fork()](struct.KnowledgeBase.html#method.fork).
+//!
+//! Predicates are static, unless they are declared to be dynamic, with
+//! the directive `:- dynamic(score/2).` or with
+//! [set_dynamic()](struct.KnowledgeBase.html#method.set_dynamic).
+//! The functions [assert_rule()](fn.assert_rule.html) and
+//! [retract_rule()](fn.retract_rule.html) refuse to modify static
+//! predicates, which protects the core rule set from accidental changes
+//! at runtime.
+//!
+//! Predicates which are declared with the directive `:- table path/2.`
+//! remember their answers. See [tabling](../tabling/index.html).
+//!
+//! Operators which are declared with the directive `:- op(700, xfx, likes).`
+//! belong to the knowledge base. See
+//! [user-defined operators](../user_operators/index.html).
+//!
+//! Facts can also be supplied on demand by an
+//! [external predicate](../external/index.html), such as a database table.
+//!
+//! For very large fact bases, the knowledge base can index the heads of
+//! facts and rules with a [discrimination tree](../discrimination_tree/index.html).
+//! See [set_indexing()](struct.KnowledgeBase.html#method.set_indexing).
+//!
 // Cleve Lendon 2023
 
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
 
 use crate::*;
 
 use super::rule::*;
 use super::goal::*;
-use super::time_out::*;
 use super::unifiable::Unifiable;
 use super::logic_var::*;
+use super::discrimination_tree::DiscriminationTree;
+use super::external::ExternalPredicate;
+use super::tabling::Tables;
+use super::user_operators::{UserOperator, define_operator};
+
+// Predicates with fewer facts and rules than this are not indexed.
+const MIN_INDEXED: usize = 8;
+
+// Discrimination trees, by predicate name.
+type Index = RefCell<HashMap<String, Rc<DiscriminationTree>>>;
+
+/// A dictionary of predicates (facts and rules), indexed by predicate name.
+///
+/// The knowledge base dereferences to a HashMap, so the usual map methods,
+/// such as get(), iter() and contains_key(), can be called on it directly.
+/// The facts and rules of each predicate are held in an Rc, so that they
+/// can be shared by clones of the knowledge base.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KnowledgeBase {
+    rules: HashMap<String, Rc<Vec<Rule>>>,
+    // Discrimination trees are built when they are first needed.
+    // None means that indexing is off. The setting is not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    index: Option<Index>,
+    // Names of dynamic predicates.
+    dynamic: HashSet<String>,
+    // Names of tabled predicates.
+    #[cfg_attr(feature = "serde", serde(default))]
+    tabled: HashSet<String>,
+    // Operators declared by the op/3 directive.
+    #[cfg_attr(feature = "serde", serde(default))]
+    operators: Vec<UserOperator>,
+    // Answers of tabled goals. These are not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    tables: RefCell<Tables>,
+    // Whether each predicate consists of facts only, for the query
+    // planner. Computed when first needed.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    facts_only: RefCell<HashMap<String, bool>>,
+    // External predicates, by predicate name. These are not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    externals: HashMap<String, Rc<dyn ExternalPredicate>>,
+}
+
+impl KnowledgeBase {
+
+    /// Creates an empty knowledge base.
+    pub fn new() -> Self {
+        KnowledgeBase::default()
+    }
+
+    /// Turns indexing of facts and rules on or off. By default, it is off.
+    ///
+    /// When indexing is on, the heads of the facts and rules of each
+    /// predicate are stored in a
+    /// [discrimination tree](../discrimination_tree/index.html),
+    /// so that a goal with ground arguments only tries the facts and rules
+    /// which could match it. This speeds up queries to large fact bases,
+    /// at the cost of memory. Solutions are found in the same order.
+    ///
+    /// A tree is built the first time its predicate is queried, and
+    /// discarded whenever the knowledge base is modified.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * on - true to index facts and rules
+    /// # Usage
+    /// ```
+    /// use std::rc::Rc;
+    /// use suiron::*;
+    ///
+    /// let mut kb = KnowledgeBase::new();
+    /// for i in 0..1000 {
+    ///     let fact = parse_rule(&format!("square({}, {}).", i, i * i)).unwrap();
+    ///     add_rules!(&mut kb, fact);
+    /// }
+    /// kb.set_indexing(true);
+    ///
+    /// let query = parse_query("square(999, $S)").unwrap();
+    /// let sn = make_base_node(Rc::new(query), &kb);
+    /// println!("{}", solve(sn));  // Prints: $S = 998001
+    /// ```
+    pub fn set_indexing(&mut self, on: bool) {
+        self.index = if on { Some(Index::default()) } else { None };
+    }
+
+    /// Determines whether indexing is on.
+    pub fn indexing(&self) -> bool {
+        self.index.is_some()
+    }
+
+    /// Finds the facts and rules which may match a goal, if indexing is on.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"loves/2\"
+    /// * goal (complex term)
+    /// * substitution set
+    /// # Return
+    /// * indices of facts and rules, in ascending order, or None if
+    ///   the predicate is not indexed
+    pub fn candidates(&self, predicate_name: &str, goal: &Unifiable,
+                      ss: &SubstitutionSet) -> Option<Vec<usize>> {
+        let index = self.index.as_ref()?;
+        let rules = self.rules.get(predicate_name)?;
+        if rules.len() < MIN_INDEXED { return None; }
+        let tree = Rc::clone(index.borrow_mut()
+                                  .entry(predicate_name.to_string())
+                                  .or_insert_with(|| Rc::new(DiscriminationTree::new(rules))));
+        return tree.candidates(goal, ss);
+    } // candidates()
+
+    /// Makes a copy of the knowledge base, which can be modified without
+    /// affecting the original.
+    ///
+    /// The fork shares its facts and rules with the original. A predicate's
+    /// list of facts and rules is copied only when one of the knowledge
+    /// bases modifies it, so forking a large knowledge base to test a
+    /// hypothesis is cheap. This is the same as clone().
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * new knowledge base
+    /// # Usage
+    /// ```
+    /// use std::rc::Rc;
+    /// use suiron::*;
+    ///
+    /// let kb = test_kb();
+    /// let mut hypothesis = kb.fork();
+    /// add_rules!(&mut hypothesis, parse_rule("mother(Edward, Edmund).").unwrap());
+    ///
+    /// let query = parse_query("grandfather(Alfred, $Y)").unwrap();
+    /// let sn = make_base_node(Rc::new(query), &hypothesis);
+    /// println!("{}", solve_all(sn).join(", "));  // Prints: $Y = Aethelstan, $Y = Edmund
+    /// ```
+    pub fn fork(&self) -> KnowledgeBase {
+        self.clone()
+    }
+
+    /// Gets the facts and rules of a predicate, for modification.
+    ///
+    /// If the list is shared with another knowledge base, it is copied first.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"loves/2\"
+    /// # Return
+    /// * facts and rules, or None if the predicate is not defined
+    pub fn rules_mut(&mut self, predicate_name: &str) -> Option<&mut Vec<Rule>> {
+        return self.deref_mut().get_mut(predicate_name).map(Rc::make_mut);
+    }
+
+    /// Adds a fact or rule before the other facts and rules of its
+    /// predicate. This is similar to Prolog's asserta/1.
+    /// ([add_rules()](fn.add_rules.html) adds rules at the end, like assertz/1.)
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * `rule` - [Rule](../rule/index.html)
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let mut kb = test_kb();
+    /// kb.add_rule_first(parse_rule("loves(Amy, Sheldon).").unwrap());
+    /// let fact = get_rule(&kb, "loves/2", 0).unwrap();
+    /// println!("{}", fact); // Prints: loves(Amy, Sheldon).
+    /// ```
+    pub fn add_rule_first(&mut self, rule: Rule) {
+        let key = rule.key();
+        match self.rules_mut(&key) {
+            Some(rules) => { rules.insert(0, rule); },
+            None => { self.insert(key, Rc::new(vec![rule])); },
+        }
+    } // add_rule_first()
+
+    /// Inserts a fact or rule at the given position in the list of
+    /// facts and rules of its predicate. The order of facts and rules
+    /// determines the order of solutions.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * `index` - from 0 to the number of facts and rules
+    /// * `rule` - [Rule](../rule/index.html)
+    /// # Return
+    /// * Ok or error message
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let mut kb = test_kb();
+    /// let fact = parse_rule("father(Edward, Edmund).").unwrap();
+    /// kb.insert_rule_at(1, fact).unwrap();
+    /// ```
+    pub fn insert_rule_at(&mut self, index: usize, rule: Rule) -> Result<(), String> {
+        let key = rule.key();
+        let count = self.rules.get(&key).map_or(0, |rules| rules.len());
+        if index > count {
+            return Err(format!("insert_rule_at() - Index out of range: {}", index));
+        }
+        match self.rules_mut(&key) {
+            Some(rules) => { rules.insert(index, rule); },
+            None => { self.insert(key, Rc::new(vec![rule])); },
+        }
+        return Ok(());
+    } // insert_rule_at()
+
+    /// Adds many facts of one predicate to the knowledge base.
+    ///
+    /// The facts are constructed directly from their arguments, which
+    /// is much faster than formatting and parsing each one. Storage is
+    /// reserved in advance, if the number of facts is known.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * functor, eg. \"edge\"
+    /// * arguments of each fact, as tuples, arrays or vectors of terms
+    ///   (see [FactArguments](../facts/trait.FactArguments.html))
+    /// # Return
+    /// * number of facts added
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let edges = [("a", "b"), ("b", "c"), ("c", "d")];
+    /// let mut kb = KnowledgeBase::new();
+    /// kb.add_facts("edge", edges.iter().map(|(a, b)| (atom!(a), atom!(b))));
+    /// println!("{}", count_rules(&kb, "edge/2"));  // Prints: 3
+    /// ```
+    pub fn add_facts<I>(&mut self, functor: &str, facts: I) -> usize
+        where I: IntoIterator, I::Item: FactArguments {
+
+        let facts = facts.into_iter();
+        let (expected, _) = facts.size_hint();
+        let mut count = 0;
+
+        for args in facts {
+            let head = fact_head(functor, args);
+            let key = match &head {
+                Unifiable::SComplex(terms) => { format!("{}/{}", functor, terms.len() - 1) },
+                _ => { continue; },
+            };
+            let rules = match self.rules_mut(&key) {
+                Some(rules) => { rules },
+                None => {
+                    self.insert(key.clone(), Rc::new(vec![]));
+                    self.rules_mut(&key).unwrap()  // Can't fail.
+                },
+            };
+            if count == 0 { rules.reserve(expected); }
+            rules.push(make_fact(head));
+            count += 1;
+        }
+        return count;
+
+    } // add_facts()
+
+    /// Starts the construction of a fact. Rust values are converted into
+    /// terms automatically. (See the [facts](../facts/index.html) module.)
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * functor, eg. \"age\"
+    /// # Return
+    /// * [FactBuilder](../facts/struct.FactBuilder.html)
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let mut kb = KnowledgeBase::new();
+    /// kb.fact("age").args(("June", 61)).add();
+    /// kb.fact("height").arg("June").arg(1.62).add();
+    /// ```
+    pub fn fact(&mut self, functor: &str) -> FactBuilder<'_> {
+        FactBuilder::new(self, functor)
+    }
+
+    /// Removes all facts and rules from the knowledge base.
+    ///
+    /// The indexing setting is kept.
+    ///
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let mut kb = test_kb();
+    /// kb.clear();
+    /// assert!(kb.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.deref_mut().clear();
+    }
+
+    /// Removes all facts and rules of a predicate.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"loves/2\"
+    /// # Return
+    /// * removed facts and rules, or None if the predicate is not defined
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let mut kb = test_kb();
+    /// let rules = kb.remove_predicate("loves/2").unwrap();
+    /// println!("{}", rules.len());  // Prints: 2
+    /// ```
+    pub fn remove_predicate(&mut self, predicate_name: &str) -> Option<Vec<Rule>> {
+        let rules = self.deref_mut().remove(predicate_name)?;
+        return Some(Rc::try_unwrap(rules).unwrap_or_else(|rules| (*rules).clone()));
+    }
+
+    /// Declares a predicate to be dynamic, so that it can be modified by
+    /// [assert_rule()](fn.assert_rule.html) and
+    /// [retract_rule()](fn.retract_rule.html).
+    ///
+    /// This is equivalent to the directive `:- dynamic(score/2).`
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"score/2\"
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let mut kb = test_kb();
+    /// kb.set_dynamic("loves/2");
+    /// let fact = parse_rule("loves(Amy, Sheldon).").unwrap();
+    /// assert_rule(&mut kb, fact).unwrap();
+    /// ```
+    pub fn set_dynamic(&mut self, predicate_name: &str) {
+        self.dynamic.insert(predicate_name.to_string());
+    }
+
+    /// Determines whether a predicate has been declared dynamic.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"score/2\"
+    /// # Return
+    /// * true if dynamic
+    pub fn is_dynamic(&self, predicate_name: &str) -> bool {
+        self.dynamic.contains(predicate_name)
+    }
+
+    /// Gets the names of the dynamic predicates, in alphabetical order.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * iterator over predicate names
+    pub fn dynamic_predicates(&self) -> impl Iterator<Item = &str> {
+        let mut names: Vec<&str> = self.dynamic.iter().map(|k| k.as_str()).collect();
+        names.sort_unstable();
+        return names.into_iter();
+    }
 
-pub type KnowledgeBase = HashMap<String, Vec<Rule>>;
+    /// Declares a predicate to be tabled, so that the answers of its
+    /// goals are remembered. Left-recursive rules terminate if they are
+    /// tabled. See [tabling](../tabling/index.html).
+    ///
+    /// This is equivalent to the directive `:- table path/2.`
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"path/2\"
+    /// # Usage
+    /// ```
+    /// use std::rc::Rc;
+    /// use suiron::*;
+    ///
+    /// let mut kb = KnowledgeBase::new();
+    /// add_rules!(&mut kb, parse_rule("edge(a, b).").unwrap(),
+    ///                     parse_rule("edge(b, a).").unwrap(),
+    ///                     parse_rule("path($X, $Y) :- path($X, $Z), edge($Z, $Y).").unwrap(),
+    ///                     parse_rule("path($X, $Y) :- edge($X, $Y).").unwrap());
+    /// kb.set_tabled("path/2");
+    ///
+    /// let query = parse_query("path(a, $Y)").unwrap();
+    /// let sn = make_base_node(Rc::new(query), &kb);
+    /// println!("{}", solve_all(sn).join(", "));  // Prints: $Y = b, $Y = a
+    /// ```
+    pub fn set_tabled(&mut self, predicate_name: &str) {
+        self.tabled.insert(predicate_name.to_string());
+    }
+
+    /// Determines whether a predicate has been declared tabled.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"path/2\"
+    /// # Return
+    /// * true if tabled
+    pub fn is_tabled(&self, predicate_name: &str) -> bool {
+        self.tabled.contains(predicate_name)
+    }
+
+    /// Gets the names of the tabled predicates, in alphabetical order.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * iterator over predicate names
+    pub fn tabled_predicates(&self) -> impl Iterator<Item = &str> {
+        let mut names: Vec<&str> = self.tabled.iter().map(|k| k.as_str()).collect();
+        names.sort_unstable();
+        return names.into_iter();
+    }
+
+    /// Defines an operator for the knowledge base. The operator is used
+    /// to parse the rest of the source which is being loaded, and the
+    /// queries of an [Engine](../engine/struct.Engine.html). It does not
+    /// affect other knowledge bases.
+    ///
+    /// This is equivalent to the directive `:- op(700, xfx, likes).`
+    /// See [add_operator()](../user_operators/fn.add_operator.html).
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * precedence, from 0 to 1200 (0 removes the operator)
+    /// * operator type, eg. xfx
+    /// * name of operator
+    /// # Return
+    /// * Ok or error message
+    pub fn add_operator(&mut self, precedence: i64,
+                        op_type: &str, name: &str) -> Result<(), String> {
+        define_operator(&mut self.operators, precedence, op_type, name)
+    }
+
+    /// Gets the operators of the knowledge base, in the order in which
+    /// they were defined.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * operators
+    pub fn operators(&self) -> &[UserOperator] {
+        &self.operators
+    }
+
+    // Moves the facts, rules, declarations and operators of another
+    // knowledge base into this one. Facts and rules are added after
+    // the existing facts and rules of their predicates.
+    //
+    // Arguments
+    //    `self`
+    //    other knowledge base
+    pub(crate) fn append(&mut self, other: KnowledgeBase) {
+        self.dynamic.extend(other.dynamic);
+        self.tabled.extend(other.tabled);
+        for op in other.operators {
+            // As in define_operator(), a definition replaces one of the same kind.
+            self.operators.retain(|o| o.name != op.name ||
+                                      o.op_type.is_infix() != op.op_type.is_infix());
+            self.operators.push(op);
+        }
+        for (name, rules) in other.rules {
+            let rules = Rc::try_unwrap(rules).unwrap_or_else(|rules| (*rules).clone());
+            match self.rules_mut(&name) {
+                Some(existing) => { existing.extend(rules); },
+                None => { self.insert(name, Rc::new(rules)); },
+            }
+        }
+    } // append()
+
+    /// Discards the answers of all tabled goals. This is similar to
+    /// Prolog's abolish_all_tables/0. The tables are also discarded
+    /// whenever the facts and rules are modified.
+    ///
+    /// # Arguments
+    /// * `self`
+    pub fn abolish_all_tables(&self) {
+        self.tables.borrow_mut().clear();
+    }
+
+    // Gets the answers of tabled goals.
+    pub(crate) fn tables(&self) -> &RefCell<Tables> {
+        &self.tables
+    }
+
+    /// Determines whether a predicate is static. A predicate is static
+    /// if it has facts or rules, and has not been declared dynamic.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"score/2\"
+    /// # Return
+    /// * true if static
+    pub fn is_static(&self, predicate_name: &str) -> bool {
+        self.contains(predicate_name) && !self.is_dynamic(predicate_name)
+    }
+
+    /// Determines whether a predicate has facts or rules in the knowledge base.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"loves/2\"
+    /// # Return
+    /// * true if the predicate is defined
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let kb = test_kb();
+    /// assert!(kb.contains("loves/2"));
+    /// assert!(!kb.contains("loves/3"));
+    /// ```
+    pub fn contains(&self, predicate_name: &str) -> bool {
+        self.rules.contains_key(predicate_name)
+    }
+
+    /// Determines whether a predicate consists of facts only (no rules).
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"loves/2\"
+    /// # Return
+    /// * true if the predicate is defined, and has no rules
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let kb = test_kb();
+    /// assert!(kb.has_only_facts("loves/2"));
+    /// assert!(!kb.has_only_facts("grandfather/2"));
+    /// ```
+    pub fn has_only_facts(&self, predicate_name: &str) -> bool {
+        if let Some(&facts) = self.facts_only.borrow().get(predicate_name) {
+            return facts;
+        }
+        let facts = match self.rules.get(predicate_name) {
+            Some(rules) => { rules.iter().all(|r| r.body == Goal::Nil) },
+            None => { false },
+        };
+        self.facts_only.borrow_mut().insert(predicate_name.to_string(), facts);
+        return facts;
+    }
+
+    /// Gets the names of the predicates in the knowledge base, in
+    /// alphabetical order.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * iterator over predicate names, eg. \"loves/2\"
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let kb = test_kb();
+    /// let names: Vec<&str> = kb.predicates().collect();
+    /// println!("{:?}", names);  // Prints: ["father/2", "grandfather/2", "loves/2"]
+    /// ```
+    pub fn predicates(&self) -> impl Iterator<Item = &str> {
+        let mut names: Vec<&str> = self.rules.keys().map(|k| k.as_str()).collect();
+        names.sort_unstable();
+        return names.into_iter();
+    }
+
+    /// Registers an external predicate, which supplies facts on demand.
+    ///
+    /// The external predicate is consulted only if the knowledge base
+    /// has no facts or rules for the predicate name.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"person/2\"
+    /// * external predicate
+    /// # Usage
+    /// See [ExternalPredicate](../external/trait.ExternalPredicate.html).
+    pub fn add_external(&mut self, predicate_name: &str,
+                        external: impl ExternalPredicate + 'static) {
+        self.externals.insert(predicate_name.to_string(), Rc::new(external));
+    }
+
+    /// Removes an external predicate.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"person/2\"
+    /// # Return
+    /// * true if the external predicate was registered
+    pub fn remove_external(&mut self, predicate_name: &str) -> bool {
+        self.externals.remove(predicate_name).is_some()
+    }
+
+    /// Gets an external predicate.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"person/2\"
+    /// # Return
+    /// * external predicate, or None
+    pub fn external(&self, predicate_name: &str) -> Option<Rc<dyn ExternalPredicate>> {
+        self.externals.get(predicate_name).cloned()
+    }
+
+    /// Determines whether any external predicates are registered.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * true if there are external predicates
+    pub fn has_externals(&self) -> bool {
+        !self.externals.is_empty()
+    }
+
+    /// Gets the facts and rules of a predicate, in order.
+    ///
+    /// If the predicate is not defined, the iterator is empty.
+    /// Unlike [get_rule()](fn.get_rule.html), this function does not
+    /// recreate the variables of the rules.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * predicate name, eg. \"loves/2\"
+    /// # Return
+    /// * iterator over facts and rules
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let kb = test_kb();
+    /// for rule in kb.rules("loves/2") {
+    ///     println!("{}", rule);
+    /// }
+    /// ```
+    pub fn rules(&self, predicate_name: &str) -> std::slice::Iter<'_, Rule> {
+        match self.rules.get(predicate_name) {
+            Some(rules) => { rules.iter() },
+            None => { [].iter() },
+        }
+    }
+
+} // impl KnowledgeBase
+
+impl Deref for KnowledgeBase {
+    type Target = HashMap<String, Rc<Vec<Rule>>>;
+    fn deref(&self) -> &Self::Target { &self.rules }
+}
+
+// Any change to the facts and rules discards the discrimination trees
+// and the tables.
+impl DerefMut for KnowledgeBase {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if let Some(index) = &mut self.index { index.get_mut().clear(); }
+        self.tables.get_mut().clear();
+        self.facts_only.get_mut().clear();
+        &mut self.rules
+    }
+}
 
 /// Makes a rule.
 ///
@@ -93,11 +774,11 @@ pub fn make_fact(head: Unifiable) -> Rule {
 pub fn add_rules(kb: &mut KnowledgeBase, rules: Vec<Rule>) {
     for rule in rules {
         let key = rule.key();
-        match kb.get_mut(&key) {
+        match kb.rules_mut(&key) {
             Some(rules_from_kb) => { rules_from_kb.push(rule); },
             None => {
                 let new_rules = vec![rule];
-                kb.insert(key, new_rules);
+                kb.insert(key, Rc::new(new_rules));
             },
         } // match
     } // for
@@ -105,9 +786,6 @@ pub fn add_rules(kb: &mut KnowledgeBase, rules: Vec<Rule>) {
 
 /// Counts the number of facts and rules for the given predicate.
 ///
-/// When the maximum execution time has been exceeded, this function returns 0.<br>
-/// This allows the inference engine to back out of the search for a solution.
-///
 /// # Arguments
 /// * `kb` - Knowledge Base
 /// * `predicate_name` - eg. \"loves/2\"
@@ -122,8 +800,6 @@ pub fn add_rules(kb: &mut KnowledgeBase, rules: Vec<Rule>) {
 /// ```
 pub fn count_rules(kb: &KnowledgeBase, predicate_name: &str) -> usize {
 
-    if query_stopped() { return 0; }
-
     match kb.get(predicate_name) {
         Some(list) => { return list.len(); },
         None => { return 0; }
@@ -137,41 +813,227 @@ pub fn count_rules(kb: &KnowledgeBase, predicate_name: &str) -> usize {
 ///
 /// This function calls
 /// [recreate_variables()](../rule/struct.Rule.html#method.recreate_variables)
-/// to make the variables unique.
+/// to make the variables unique. The variables are numbered from 1. To
+/// number them with the counter of a query, use
+/// [fetch_rule()](fn.fetch_rule.html).
 ///
 /// # Arguments
 /// * `kb` - Knowledge Base
 /// * `predicate_name` - eg. \"loves/2\"
 /// * `index`
 /// # Return
-/// * [Rule](../rule/index.html)
-/// # Panics
-/// * When the required fact/rule does not exist.
-/// * When the index is out of range.
+/// * [Rule](../rule/index.html), or None if the predicate does not
+///   exist or the index is out of range
 /// # Usage
 /// ```
 /// use suiron::*;
 ///
 /// let kb = test_kb();
-/// let fact = get_rule(&kb, "loves/2", 1);
+/// let fact = get_rule(&kb, "loves/2", 1).unwrap();
 /// println!("{}", fact); // Prints: loves(Penny, Leonard).
 /// ```
-pub fn get_rule(kb: &KnowledgeBase, predicate_name: &str, index: usize) -> Rule {
+pub fn get_rule(kb: &KnowledgeBase, predicate_name: &str, index: usize) -> Option<Rule> {
+    return fetch_rule(kb, predicate_name, index, &VarIds::new());
+} // get_rule()
 
-    match kb.get(predicate_name) {
-        Some(rules) => {
-            if index >= rules.len() {  // Should never happen.
-                panic!("get_rule() - Index out of range: {}", index);
-            }
-            let fact_or_rule = rules[index].clone();  // Can't fail.
-            return fact_or_rule.recreate_variables(&mut VarMap::new());
-        },
+/// Fetches a rule (or fact) from the knowledge base, and gives its
+/// variables new IDs from the counter of a query.
+///
+/// # Arguments
+/// * `kb` - Knowledge Base
+/// * `predicate_name` - eg. \"loves/2\"
+/// * `index`
+/// * `ids` - [VarIds](../logic_var/struct.VarIds.html) of the query
+/// # Return
+/// * [Rule](../rule/index.html), or None if the predicate does not
+///   exist or the index is out of range
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// add_rules!(&mut kb, parse_rule("parent($X, $Y) :- father($X, $Y).").unwrap());
+/// let ids = VarIds::starting_at(5);
+/// let rule = fetch_rule(&kb, "parent/2", 0, &ids).unwrap();
+/// println!("{}", rule); // Prints: parent($X_6, $Y_7) :- father($X_6, $Y_7).
+/// ```
+pub fn fetch_rule(kb: &KnowledgeBase, predicate_name: &str,
+                  index: usize, ids: &VarIds) -> Option<Rule> {
+    let fact_or_rule = kb.get(predicate_name)?.get(index)?.clone();
+    return Some(fact_or_rule.recreate_variables(&mut VarMap::with_ids(ids)));
+} // fetch_rule()
+
+/// Removes a rule (or fact) from the knowledge base.
+///
+/// If the predicate has no more facts or rules, its entry is removed.
+///
+/// # Arguments
+/// * `kb` - Knowledge Base
+/// * `predicate_name` - eg. \"loves/2\"
+/// * `index`
+/// # Return
+/// * removed [Rule](../rule/index.html), or None if it does not exist
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = test_kb();
+/// let fact = remove_rule(&mut kb, "loves/2", 0).unwrap();
+/// println!("{}", fact); // Prints: loves(Leonard, Penny).
+/// ```
+pub fn remove_rule(kb: &mut KnowledgeBase, predicate_name: &str,
+                   index: usize) -> Option<Rule> {
+
+    let rules = kb.rules_mut(predicate_name)?;
+    if index >= rules.len() { return None; }
+    let rule = rules.remove(index);
+    if rules.is_empty() { kb.remove(predicate_name); }
+    return Some(rule);
+
+} // remove_rule()
+
+/// Removes all facts and rules whose heads unify with a pattern.
+///
+/// This is similar to Prolog's retractall/1, except that rules are
+/// removed as well as facts.
+///
+/// # Arguments
+/// * `kb` - Knowledge Base
+/// * `head` - pattern ([SComplex](../unifiable/enum.Unifiable.html#variant.SComplex))
+/// # Return
+/// * number of facts and rules removed
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = test_kb();
+/// let head = parse_complex("father(Alfred, $Child)").unwrap();
+/// let n = retract_matching(&mut kb, &head);  // n == 1
+/// ```
+pub fn retract_matching(kb: &mut KnowledgeBase, head: &Unifiable) -> usize {
+
+    let key = match head.key() {
+        Ok(key) => { key },
+        Err(_) => { return 0; },
+    };
+    let pattern = head.clone().recreate_variables(&mut VarMap::new());
+
+    let rules = match kb.rules_mut(&key) {
+        Some(rules) => { rules },
+        None => { return 0; },
+    };
+
+    let before = rules.len();
+    rules.retain(|rule| {
+        let rule_head = rule.head.clone().recreate_variables(&mut VarMap::new());
+        pattern.unify(&rule_head, &empty_ss!()).is_none()
+    });
+    let removed = before - rules.len();
+    if rules.is_empty() { kb.remove(&key); }
+    return removed;
+
+} // retract_matching()
+
+/// Replaces a rule (or fact) in the knowledge base.
+///
+/// The new rule must belong to the same predicate as the old one.
+///
+/// # Arguments
+/// * `kb` - Knowledge Base
+/// * `predicate_name` - eg. \"loves/2\"
+/// * `index`
+/// * `rule` - new [Rule](../rule/index.html)
+/// # Return
+/// * replaced rule, or error message
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = test_kb();
+/// let fact = parse_rule("loves(Leonard, Amy).").unwrap();
+/// let old = replace_rule(&mut kb, "loves/2", 0, fact).unwrap();
+/// println!("{}", old); // Prints: loves(Leonard, Penny).
+/// ```
+pub fn replace_rule(kb: &mut KnowledgeBase, predicate_name: &str,
+                    index: usize, rule: Rule) -> Result<Rule, String> {
+
+    if rule.key() != predicate_name {
+        return Err(format!("replace_rule() - Rule does not belong to {}: {}",
+                           predicate_name, rule));
+    }
+    let rules = match kb.rules_mut(predicate_name) {
+        Some(rules) => { rules },
         None => {
-            panic!("get_rule() - Rule does not exist: {}", predicate_name);
+            return Err(format!("replace_rule() - Rule does not exist: {}",
+                               predicate_name));
         },
-    } // match
+    };
+    if index >= rules.len() {
+        return Err(format!("replace_rule() - Index out of range: {}", index));
+    }
+    return Ok(std::mem::replace(&mut rules[index], rule));
 
-} // get_rule()
+} // replace_rule()
+
+/// Adds a fact or rule to a dynamic predicate. This is similar to
+/// Prolog's assertz/1.
+///
+/// If the predicate is not yet defined, it is declared dynamic.
+/// A static predicate can not be modified.
+///
+/// # Arguments
+/// * `kb` - Knowledge Base
+/// * `rule` - [Rule](../rule/index.html)
+/// # Return
+/// * Ok or error message
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = test_kb();
+/// let fact = parse_rule("score(Penny, 7).").unwrap();
+/// assert_rule(&mut kb, fact).unwrap();  // Creates score/2.
+///
+/// let fact = parse_rule("loves(Amy, Sheldon).").unwrap();
+/// assert!(assert_rule(&mut kb, fact).is_err());  // loves/2 is static.
+/// ```
+pub fn assert_rule(kb: &mut KnowledgeBase, rule: Rule) -> Result<(), String> {
+    let key = rule.key();
+    if kb.is_static(&key) {
+        return Err(format!("assert_rule() - Static predicate: {}", key));
+    }
+    kb.set_dynamic(&key);
+    add_rules(kb, vec![rule]);
+    return Ok(());
+} // assert_rule()
+
+/// Removes the facts and rules of a dynamic predicate whose heads unify
+/// with a pattern.
+///
+/// A static predicate can not be modified. See also
+/// [retract_matching()](fn.retract_matching.html).
+///
+/// # Arguments
+/// * `kb` - Knowledge Base
+/// * `head` - pattern ([SComplex](../unifiable/enum.Unifiable.html#variant.SComplex))
+/// # Return
+/// * number of facts and rules removed, or error message
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = test_kb();
+/// kb.set_dynamic("father/2");
+/// let head = parse_complex("father(Alfred, $Child)").unwrap();
+/// let n = retract_rule(&mut kb, &head).unwrap();  // n == 1
+/// ```
+pub fn retract_rule(kb: &mut KnowledgeBase, head: &Unifiable) -> Result<usize, String> {
+    let key = head.key().map_err(|err| err.to_string())?;
+    if kb.is_static(&key) {
+        return Err(format!("retract_rule() - Static predicate: {}", key));
+    }
+    return Ok(retract_matching(kb, head));
+} // retract_rule()
 
 /// Formats the knowledge base for display. Use for debugging.
 ///
@@ -179,8 +1041,6 @@ pub fn get_rule(kb: &KnowledgeBase, predicate_name: &str, index: usize) -> Rule
 ///
 /// # Note
 /// * The keys (predicate names) are sorted.
-/// * KnowledgeBase is a type alias. Because of this, it is not
-///   possible to implement the Display trait.
 /// # Arguments
 /// * `kb` - Knowledge Base
 /// # Return
@@ -212,16 +1072,10 @@ pub fn format_kb(kb: &KnowledgeBase) -> String {
     // header
     let mut out = "_____ Contents of Knowledge Base _____\n".to_string();
 
-    // Sort the keys.
-    let mut keys: Vec<String> = vec![];
-    for (k, _) in kb.iter() { keys.push(k.clone()); }
-    keys.sort();
-
-    // Format each entry.
-    for key in keys {
+    // Format each entry. The keys are sorted.
+    for key in kb.predicates() {
         out += &format!("{}\n", key);
-        let rules = kb.get(&key).unwrap();
-        for rule in rules { out += &format!("\t{}\n", rule); }
+        for rule in kb.rules(key) { out += &format!("\t{}\n", rule); }
     }
 
     out += "______________________________________";
@@ -343,7 +1197,7 @@ pub fn test_kb() -> KnowledgeBase {
 #[cfg(test)]
 mod test {
 
-    use serial_test::serial;
+    use std::rc::Rc;
     use crate::*;
 
     // Create logic vars for testing.
@@ -392,11 +1246,8 @@ mod test {
 
     // Test add_rules(), count_rules() and format_kb().
     #[test]
-    #[serial]
     fn test_add_rules() {
 
-        start_query();  // Set SUIRON_STOP_QUERY to false.
-
         // For testing, create a rule and two facts:
         //   grandfather($X, $Y) :- father($X, $Z), father($Z, $Y).
         //   father(Anakin, Luke).
@@ -423,48 +1274,290 @@ mod test {
 
         // father($X, $Y)
         let c1 = scomplex!(atom!("father"), x(), y());
-        let n = count_rules(&kb, &c1.key());
+        let n = count_rules(&kb, &c1.key().unwrap());
         assert_eq!(n, 2);
 
     } // test_add_rules()
 
     // Test the get_rule() function.
     #[test]
-    #[serial]
     fn test_get_rule() {
 
-        clear_id();
         let kb = test_kb();
 
         // Fetch the grandfather rule, index 0.
-        let rule = get_rule(&kb, "grandfather/2", 0);
+        let rule = get_rule(&kb, "grandfather/2", 0).unwrap();
         let rule_str = format!("{}", rule);
         let s = "grandfather($X_1, $Y_2) :- father($X_1, $Z_3), father($Z_3, $Y_2).";
         assert_eq!(s, rule_str);
 
-        let rule = get_rule(&kb, "loves/2", 1);
+        let rule = get_rule(&kb, "loves/2", 1).unwrap();
         let rule_str = format!("{}", rule);
         let s = "loves(Penny, Leonard).";
         assert_eq!(s, rule_str);
 
     } // test_get_rule()
 
-    // get_rule() should panic if predicate name is invalid.
+    // get_rule() should return None if predicate name is invalid.
     #[test]
-    #[serial]
-    #[should_panic]
-    fn test_get_rule_panic1() {
+    fn test_get_rule_none1() {
         let kb = test_kb();
-        get_rule(&kb, "luvs/2", 0);
-    } // test_get_rule_panic1()
+        assert!(get_rule(&kb, "luvs/2", 0).is_none());
+    } // test_get_rule_none1()
 
-    // get_rule() should panic if index is invalid.
+    // get_rule() should return None if index is invalid.
     #[test]
-    #[serial]
-    #[should_panic]
-    fn test_get_rule_panic2() {
+    fn test_get_rule_none2() {
         let kb = test_kb();
-        get_rule(&kb, "loves/2", 20);
-    } // test_get_rule_panic2()
+        assert!(get_rule(&kb, "loves/2", 20).is_none());
+    } // test_get_rule_none2()
+
+    // Test remove_rule(), retract_matching() and replace_rule().
+    #[test]
+    fn test_remove_rules() {
+
+        let mut kb = test_kb();
+
+        let fact = remove_rule(&mut kb, "loves/2", 1).unwrap();
+        assert_eq!("loves(Penny, Leonard).", fact.to_string());
+        assert_eq!(1, count_rules(&kb, "loves/2"));
+        assert!(remove_rule(&mut kb, "loves/2", 1).is_none());
+        assert!(remove_rule(&mut kb, "luvs/2", 0).is_none());
+        // Removing the last rule removes the predicate.
+        remove_rule(&mut kb, "loves/2", 0).unwrap();
+        assert!(!kb.contains_key("loves/2"));
+
+        let head = parse_complex("father($X, Aethelstan)").unwrap();
+        assert_eq!(1, retract_matching(&mut kb, &head));
+        assert_eq!(1, count_rules(&kb, "father/2"));
+        // Variables in the pattern and in the rules are independent.
+        let head = parse_complex("grandfather($Z, $X)").unwrap();
+        assert_eq!(2, retract_matching(&mut kb, &head));
+        assert!(!kb.contains_key("grandfather/2"));
+        let head = parse_complex("father(Edward, $Y)").unwrap();
+        assert_eq!(0, retract_matching(&mut kb, &head));
+
+        let fact = parse_rule("father(Alfred, Aethelflaed).").unwrap();
+        let old = replace_rule(&mut kb, "father/2", 0, fact).unwrap();
+        assert_eq!("father(Alfred, Edward).", old.to_string());
+        let query = parse_query("father(Alfred, $C)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$C = Aethelflaed", solve(sn));
+
+        let fact = parse_rule("mother(Ealhswith, Edward).").unwrap();
+        assert!(replace_rule(&mut kb, "father/2", 0, fact.clone()).is_err());
+        assert!(replace_rule(&mut kb, "mother/2", 0, fact).is_err());
+
+    } // test_remove_rules()
+
+    // assert_rule() and retract_rule() should only modify dynamic predicates.
+    #[test]
+    fn test_dynamic() {
+
+        let mut kb = test_kb();
+        assert!(kb.is_static("father/2"));
+        let fact = parse_rule("father(Edward, Edmund).").unwrap();
+        assert!(assert_rule(&mut kb, fact.clone()).is_err());
+        let head = parse_complex("father(Alfred, $C)").unwrap();
+        assert!(retract_rule(&mut kb, &head).is_err());
+        assert_eq!(2, count_rules(&kb, "father/2"));
+
+        kb.set_dynamic("father/2");
+        assert!(!kb.is_static("father/2"));
+        assert_rule(&mut kb, fact).unwrap();
+        assert_eq!(Ok(1), retract_rule(&mut kb, &head));
+        assert_eq!(2, count_rules(&kb, "father/2"));
+
+        // Asserting a new predicate makes it dynamic.
+        assert!(!kb.is_static("score/2"));
+        assert_rule(&mut kb, parse_rule("score(Penny, 7).").unwrap()).unwrap();
+        assert!(kb.is_dynamic("score/2"));
+        assert_rule(&mut kb, parse_rule("score(Amy, 9).").unwrap()).unwrap();
+        let head = parse_complex("score($_, $_)").unwrap();
+        assert_eq!(Ok(2), retract_rule(&mut kb, &head));
+        assert!(!kb.contains("score/2"));
+        assert!(kb.is_dynamic("score/2"));
+
+    } // test_dynamic()
+
+    // Test add_rule_first() and insert_rule_at().
+    #[test]
+    fn test_insert_rules() {
+
+        let mut kb = test_kb();
+        kb.add_rule_first(parse_rule("father(Alfred, Aethelflaed).").unwrap());
+        kb.insert_rule_at(2, parse_rule("father(Alfred, Aethelgifu).").unwrap()).unwrap();
+        kb.insert_rule_at(4, parse_rule("father(Edward, Edmund).").unwrap()).unwrap();
+        assert!(kb.insert_rule_at(6, parse_rule("father(Edward, Eadred).").unwrap()).is_err());
+        kb.add_rule_first(parse_rule("mother(Ealhswith, Edward).").unwrap());
+        assert!(kb.insert_rule_at(1, parse_rule("sister(Aethelflaed, Edward).").unwrap()).is_err());
+        kb.insert_rule_at(0, parse_rule("sister(Aethelflaed, Edward).").unwrap()).unwrap();
+
+        let query = parse_query("father($F, $C)").unwrap();
+        let results = solve_all(make_base_node(Rc::new(query), &kb));
+        assert_eq!(vec!["$F = Alfred, $C = Aethelflaed", "$F = Alfred, $C = Edward",
+                        "$F = Alfred, $C = Aethelgifu", "$F = Edward, $C = Aethelstan",
+                        "$F = Edward, $C = Edmund"], results);
+        assert_eq!(1, count_rules(&kb, "mother/2"));
+        assert_eq!(1, count_rules(&kb, "sister/2"));
+
+    } // test_insert_rules()
+
+    // Test add_facts().
+    #[test]
+    fn test_add_facts() {
+
+        let mut kb = test_kb();
+        let n = kb.add_facts("father", [("Edward", "Edmund"), ("Edward", "Eadred")]
+                             .iter().map(|(f, c)| (atom!(f), atom!(c))));
+        assert_eq!(2, n);
+        assert_eq!(4, count_rules(&kb, "father/2"));
+
+        let squares = (1..=100).map(|i| [SInteger(i), SInteger(i * i)]);
+        assert_eq!(100, kb.add_facts("square", squares));
+        kb.add_facts("point", vec![vec![SInteger(1), SInteger(2), SInteger(3)],
+                                   vec![SInteger(4), SInteger(5)]]);
+        assert_eq!(1, count_rules(&kb, "point/3"));
+        assert_eq!(1, count_rules(&kb, "point/2"));
+        assert_eq!(0, kb.add_facts("empty", Vec::<(Unifiable,)>::new()));
+        assert!(!kb.contains("empty/1"));
+
+        let query = parse_query("square(12, $S)").unwrap();
+        assert_eq!("$S = 144", solve(make_base_node(Rc::new(query), &kb)));
+        let query = parse_query("grandfather(Alfred, $G)").unwrap();
+        let results = solve_all(make_base_node(Rc::new(query), &kb));
+        assert_eq!(vec!["$G = Aethelstan", "$G = Edmund", "$G = Eadred"], results);
+
+    } // test_add_facts()
+
+    // A knowledge base should survive a round trip through JSON.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+
+        let mut kb = test_kb();
+        let rule = parse_rule("test($X) :- not($X = [a, 1.5 | $T]), print(\"x\"); $X > 1r3.");
+        add_rules!(&mut kb, rule.unwrap());
+        kb.set_dynamic("loves/2");
+
+        let json = serde_json::to_string(&kb).unwrap();
+        let kb2: KnowledgeBase = serde_json::from_str(&json).unwrap();
+        assert_eq!(format_kb(&kb), format_kb(&kb2));
+        assert!(kb2.is_dynamic("loves/2"));
+
+        let query = parse_query("grandfather($X, $Y)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb2);
+        assert_eq!("$X = Alfred, $Y = Aethelstan", solve(sn));
+
+        let term = parse_term("f(Penny, \"text\", $X, [1, 2])").unwrap();
+        let json = serde_json::to_string(&term).unwrap();
+        assert_eq!(term, serde_json::from_str::<Unifiable>(&json).unwrap());
+
+    } // test_serde()
+
+    // Test predicates() and rules().
+    #[test]
+    fn test_iteration() {
+
+        let kb = test_kb();
+        let names: Vec<&str> = kb.predicates().collect();
+        assert_eq!(vec!["father/2", "grandfather/2", "loves/2"], names);
+
+        let facts: Vec<String> = kb.rules("father/2").map(|r| r.to_string()).collect();
+        assert_eq!(vec!["father(Alfred, Edward).", "father(Edward, Aethelstan)."], facts);
+        assert_eq!(2, kb.rules("grandfather/2").filter(|r| r.body != Goal::Nil).count());
+        assert_eq!(0, kb.rules("mother/2").count());
+
+    } // test_iteration()
+
+    // Test clear(), remove_predicate() and contains().
+    #[test]
+    fn test_remove_predicate() {
+
+        let mut kb = test_kb();
+        assert!(kb.contains("father/2"));
+        assert_eq!(2, kb.remove_predicate("father/2").unwrap().len());
+        assert!(!kb.contains("father/2"));
+        assert!(kb.remove_predicate("father/2").is_none());
+        assert!(kb.contains("grandfather/2"));
+
+        kb.set_indexing(true);
+        kb.clear();
+        assert!(!kb.contains("loves/2"));
+        assert!(kb.is_empty());
+        assert!(kb.indexing());
+
+    } // test_remove_predicate()
+
+    // A fork shares its facts and rules until it is modified.
+    #[test]
+    fn test_fork() {
+
+        let kb = test_kb();
+        let mut fork = kb.fork();
+        assert!(Rc::ptr_eq(&kb["father/2"], &fork["father/2"]));
+
+        add_rules!(&mut fork, parse_rule("mother(Edward, Edmund).").unwrap());
+        add_rules!(&mut fork, parse_rule("father(Edward, Edmund).").unwrap());
+        remove_rule(&mut fork, "loves/2", 0).unwrap();
+        assert!(!Rc::ptr_eq(&kb["father/2"], &fork["father/2"]));
+        assert!(Rc::ptr_eq(&kb["grandfather/2"], &fork["grandfather/2"]));
+
+        let grandchildren = |kb: &KnowledgeBase| {
+            let query = parse_query("grandfather(Alfred, $Y)").unwrap();
+            solve_all(make_base_node(Rc::new(query), kb))
+        };
+        assert_eq!(vec!["$Y = Aethelstan", "$Y = Edmund", "$Y = Edmund"],
+                   grandchildren(&fork));
+        assert_eq!(vec!["$Y = Aethelstan"], grandchildren(&kb));
+        assert_eq!(2, count_rules(&kb, "loves/2"));
+        assert!(!kb.contains("mother/2"));
+
+    } // test_fork()
+
+    // Indexed and unindexed knowledge bases should give the same solutions.
+    #[test]
+    fn test_indexing() {
+
+        let mut kb = KnowledgeBase::new();
+        for i in 0..20 {
+            let fact = format!("item({}, color{}, size({})).", i, i % 3, i % 4);
+            add_rules!(&mut kb, parse_rule(&fact).unwrap());
+        }
+        let rules = ["item(100, $C, size($S)) :- $C = color1, $S = 7.",
+                     "item($N, red, $S) :- $N > 15, $S = size(2).",
+                     "pair($X, $Y) :- item($N, color2, $S), item($M, color2, $S), \
+                                     $N < $M, $X = $N, $Y = $M."];
+        for r in rules { add_rules!(&mut kb, parse_rule(r).unwrap()); }
+
+        let queries = ["item(4, $C, $S)", "item($N, color1, size(1))",
+                       "item($N, $C, size(2))", "item($N, red, $S)",
+                       "item(100, $C, $S)", "pair($X, $Y)", "item($N, $C, $S)"];
+
+        let solve_queries = |kb: &KnowledgeBase| -> Vec<Vec<String>> {
+            queries.iter().map(|q| {
+                let query = parse_query(q).unwrap();
+                solve_all(make_base_node(Rc::new(query), kb))
+            }).collect()
+        };
+
+        let expected = solve_queries(&kb);
+        kb.set_indexing(true);
+        assert!(kb.indexing());
+        assert_eq!(expected, solve_queries(&kb));
+        assert_eq!(vec!["$N = 1", "$N = 13"], expected[1]);
+
+        // Modifying the knowledge base discards the index.
+        add_rules!(&mut kb, parse_rule("item(5, color1, size(1)).").unwrap());
+        let query = parse_query("item($N, color1, size(1))").unwrap();
+        let results = solve_all(make_base_node(Rc::new(query), &kb));
+        assert_eq!(vec!["$N = 1", "$N = 13", "$N = 5"], results);
+
+        let goal = parse_complex("item(4, $C, $S)").unwrap();
+        assert_eq!(Some(vec![4, 21]), kb.candidates("item/3", &goal, &empty_ss!()));
+        kb.set_indexing(false);
+        assert_eq!(None, kb.candidates("item/3", &goal, &empty_ss!()));
+
+    } // test_indexing()
 
 } // test
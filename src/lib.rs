@@ -41,6 +41,10 @@
 //!
 //! <pre>voter($P) :- $P = person($_, $Age), $Age >= 18.</pre>
 //!
+//! To load existing Prolog source code, call set_prolog_syntax(). In this mode,
+//! words which begin with a capital letter or an underscore are variables, as
+//! in Prolog, and capitalized atoms must be quoted: `parent('June', X)`.
+//!
 //! <br><hr><br>
 //!
 //! Facts and rules can also be created dynamically within a Rust application program.
@@ -69,6 +73,28 @@
 //! If a float and an integer are compared, the integer will be converted to
 //! a float for the comparison.
 //!
+//! Integers can also be written in hexadecimal, octal or binary notation:
+//! `0xFF`, `0o17`, `0b1010`.
+//!
+//! Floats can be written in scientific notation: `1.5e-3`, `2E6`.
+//! Underscores can separate digits, for readability: `1_000_000`.
+//!
+//! A character code, such as `0'a`, is the integer code point of the
+//! character (97). Escape sequences can be used: `0'\n`, `0'\s` (space).
+//!
+//! Text which is enclosed in double quotes is a string (SString).
+//! Strings are distinct from atoms. The string "June" does not
+//! unify with the atom June.
+//! Strings may contain the escape sequences `\n`, `\t`, `\\`, `\"` and `\uXXXX`.
+//!
+//! <pre>
+//! let name = SString("June".to_string());</pre>
+//!
+//! Text which is enclosed in single quotes is an atom. Quotes allow an atom
+//! to contain commas, parentheses and other special characters:
+//! `'Hello, World'`. A single quote which follows a letter or a digit is
+//! an apostrophe, as in `girl's`.
+//!
 //! Of course, Suiron supports linked lists, which work the same way as Prolog lists.
 //! A linked list can be loaded from a source file:
 //!
@@ -158,10 +184,35 @@
 //! - functor
 //! - print
 //! - print_list
+//! - format, format_atom, format_number
+//! - tab, write_canonical
+//! - read (read_term)
+//! - open, close, read_line, write
 //! - nl (new line)
+//! - random, random_between, set_random_seed
 //! - include, exclude
+//! - upcase_atom, downcase_atom
+//! - split_string
+//! - join (with a separator)
+//! - atom_string, number_string, char_code, term_to_atom
+//! - regex_match (with the `regex` feature)
 //! - greater_than, less_than, etc.
-//! - arithmetic functions: +, -, *, /
+//! - =:= and =\\= (arithmetic comparison)
+//! - arithmetic functions: +, -, *, /, // (int_divide), mod, rem, ** (pow),
+//!   min, max
+//! - math functions: abs, sign, sqrt, truncate, round, floor, ceiling,
+//!   sin, cos, tan, log, exp
+//! - rational numbers: 1r3, rdiv
+//! - big integers (with the `bigint` feature)
+//! - bitwise functions: /\\ (bit_and), \\/ (bit_or), xor, <<, >>
+//! - arithmetic expressions, with precedence and parentheses: ($A + 1) * -2
+//! - user-defined operators: :- op(700, xfx, likes).
+//! - dynamic predicates: :- dynamic(score/2).
+//! - tabled predicates: :- table path/2.
+//! - grammar rules (DCG): sentence --> noun_phrase, verb_phrase.
+//! - phrase (calls a grammar rule)
+//! - is (arithmetic evaluation)
+//! - unify_with_occurs_check
 //!
 //! Please refer to the test programs for examples of how to use these.
 //!
@@ -197,6 +248,8 @@
 //! which you can find here: [LICENSE](../../../LICENSE.txt).
 //!
 
+pub mod error;
+pub mod symbol;
 pub mod unifiable;
 pub mod substitution_set;
 pub mod logic_var;
@@ -204,6 +257,9 @@ pub mod s_linked_list;
 pub mod s_complex;
 pub mod solutions;
 pub mod parse_terms;
+pub mod parse_expressions;
+pub mod user_operators;
+pub mod dcg;
 pub mod parse_goals;
 pub mod rule_reader;
 pub mod token;
@@ -213,6 +269,19 @@ pub mod operator;
 pub mod goal;
 pub mod rule;
 pub mod knowledge_base;
+pub mod facts;
+pub mod external;
+pub mod query;
+pub mod statistics;
+pub mod dependency_graph;
+pub mod binary_kb;
+#[cfg(feature = "json")]
+pub mod json_loader;
+#[cfg(feature = "csv")]
+pub mod csv_loader;
+#[cfg(feature = "sqlite")]
+pub mod sql_store;
+pub mod discrimination_tree;
 pub mod solution_node;
 pub mod solution_node_and_or;
 pub mod built_in_print;
@@ -223,16 +292,45 @@ pub mod built_in_predicates;
 pub mod built_in_functions;
 pub mod built_in_comparison;
 pub mod built_in_arithmetic;
+pub mod built_in_math;
+pub mod built_in_rational;
+pub mod built_in_is;
 pub mod built_in_print_list;
 pub mod built_in_count;
 pub mod built_in_join;
+pub mod built_in_case;
+pub mod built_in_split_string;
+pub mod built_in_string;
+pub mod built_in_format;
+pub mod built_in_write;
+pub mod built_in_read;
+pub mod built_in_stream;
+pub mod built_in_random;
+#[cfg(feature = "regex")]
+pub mod built_in_regex;
 pub mod time_out;
+pub mod cancellation;
+pub mod output;
+pub mod diagnostics;
+pub mod formatter;
 pub mod infix;
 pub mod benchmark;
+pub mod trail;
+pub mod tabling;
+pub mod iterative_deepening;
+pub mod query_planner;
+pub mod datalog;
+#[cfg(feature = "rayon")]
+pub mod or_parallel;
+pub mod query_config;
+pub mod engine;
+pub mod proof;
 
 #[macro_use]
 pub mod macros;
 
+pub use error::*;
+pub use symbol::*;
 pub use unifiable::*;
 pub use unifiable::Unifiable::*;
 pub use substitution_set::*;
@@ -241,6 +339,9 @@ pub use s_linked_list::*;
 pub use s_complex::*;
 pub use solutions::*;
 pub use parse_terms::*;
+pub use parse_expressions::*;
+pub use user_operators::*;
+pub use dcg::*;
 pub use parse_goals::*;
 pub use rule_reader::*;
 pub use token::*;
@@ -250,6 +351,19 @@ pub use operator::*;
 pub use goal::*;
 pub use rule::*;
 pub use knowledge_base::*;
+pub use facts::*;
+pub use external::*;
+pub use query::*;
+pub use statistics::*;
+pub use dependency_graph::*;
+pub use binary_kb::*;
+#[cfg(feature = "json")]
+pub use json_loader::*;
+#[cfg(feature = "csv")]
+pub use csv_loader::*;
+#[cfg(feature = "sqlite")]
+pub use sql_store::*;
+pub use discrimination_tree::*;
 pub use solution_node::*;
 pub use solution_node_and_or::*;
 pub use built_in_print::*;
@@ -260,9 +374,36 @@ pub use built_in_predicates::*;
 pub use built_in_functions::*;
 pub use built_in_comparison::*;
 pub use built_in_arithmetic::*;
+pub use built_in_math::*;
+pub use built_in_rational::*;
+pub use built_in_is::*;
 pub use built_in_print_list::*;
 pub use built_in_count::*;
 pub use built_in_join::*;
+pub use built_in_case::*;
+pub use built_in_split_string::*;
+pub use built_in_string::*;
+pub use built_in_format::*;
+pub use built_in_write::*;
+pub use built_in_read::*;
+pub use built_in_stream::*;
+pub use built_in_random::*;
+#[cfg(feature = "regex")]
+pub use built_in_regex::*;
 pub use time_out::*;
+pub use cancellation::*;
+pub use output::*;
+pub use diagnostics::*;
+pub use formatter::*;
 pub use infix::*;
 pub use benchmark::*;
+pub use trail::*;
+pub use tabling::*;
+pub use iterative_deepening::*;
+pub use query_planner::*;
+pub use datalog::*;
+#[cfg(feature = "rayon")]
+pub use or_parallel::*;
+pub use query_config::*;
+pub use engine::*;
+pub use proof::*;
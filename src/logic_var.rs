@@ -1,4 +1,4 @@
-//! Functions to support logic variables and the global LOGIC_VAR_ID.
+//! Functions to support logic variables, and the IDs of logic variables.
 //!
 //! Logic variables ([LogicVar](../unifiable/enum.Unifiable.html#variant.LogicVar))
 //! consist of a name and an ID.
@@ -8,51 +8,111 @@
 //! Logic variables which are stored in rules can have an ID of 0, but when the
 //! rule is fetched from the knowledge base, its variables must be recreated
 //! to give them unique IDs.
-//! 
+//!
+//! Each query numbers its own variables, with a [VarIds](struct.VarIds.html)
+//! counter. There is no global counter, so queries can be solved
+//! concurrently.
+//!
 //! See [recreate_variables()](../unifiable/enum.Unifiable.html#method.recreate_variables).
 //!
 
 use std::collections::HashMap;
+use std::rc::Rc;
+use std::cell::Cell;
 use super::unifiable::*;
 
-static mut LOGIC_VAR_ID: usize = 0; // Global ID number for logic variables.
+/// Generates the IDs of the logic variables of a query.
+///
+/// The substitution set of a query is as large as its highest variable
+/// ID, so every query has its own counter, which starts after the IDs of
+/// the query's variables. Clones share the same counter.
+///
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let ids = VarIds::new();
+/// assert_eq!(1, ids.next_id());
+/// assert_eq!(2, ids.next_id());
+/// assert_eq!(2, ids.get());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct VarIds(Rc<Cell<usize>>);
 
-/// Increment and return the logic variable ID.
-/// # Return
-/// * LOGIC_VAR_ID
-pub fn next_id() -> usize {
-    unsafe {
-        LOGIC_VAR_ID += 1;
-        LOGIC_VAR_ID
+impl VarIds {
+
+    /// Creates a counter. The first ID is 1.
+    pub fn new() -> Self { VarIds(Rc::new(Cell::new(0))) }
+
+    /// Creates a counter which continues after the given ID.
+    ///
+    /// # Arguments
+    /// * highest ID in use
+    pub fn starting_at(id: usize) -> Self { VarIds(Rc::new(Cell::new(id))) }
+
+    /// Increments and returns the ID.
+    /// # Return
+    /// * new ID
+    pub fn next_id(&self) -> usize {
+        let id = self.0.get() + 1;
+        self.0.set(id);
+        id
     }
-}
 
-/// Gets the logic variable ID.
-/// # Return
-/// * LOGIC_VAR_ID
-pub fn get_var_id() -> usize {
-    unsafe { LOGIC_VAR_ID }
+    /// Gets the highest ID which was generated.
+    /// # Return
+    /// * ID
+    pub fn get(&self) -> usize { self.0.get() }
+
+    /// Sets the highest ID. Variables which were recreated after this
+    /// ID was saved must no longer be in use.
+    /// # Arguments
+    /// * ID
+    pub fn set(&self, id: usize) { self.0.set(id); }
+
+} // impl VarIds
+
+/// Keeps track of recreated variables, for recreate_variables().
+///
+/// A variable which occurs more than once in a term or rule must be
+/// given the same ID each time. The map holds the ID of each variable
+/// name, and the counter which generates new IDs.
+#[derive(Debug, Clone, Default)]
+pub struct VarMap {
+    names: HashMap<String, usize>,
+    ids: VarIds,
 }
 
-/// Sets the logic variable ID to the given value.
-/// # Arguments
-/// * id
-pub fn set_var_id(id: usize) {
-    unsafe {
-        LOGIC_VAR_ID = id;
+impl VarMap {
+
+    /// Creates a map with its own counter. The first ID is 1.
+    pub fn new() -> Self { VarMap{ names: HashMap::new(), ids: VarIds::new() } }
+
+    /// Creates a map which gets new IDs from the counter of a query.
+    ///
+    /// # Arguments
+    /// * [VarIds](struct.VarIds.html)
+    pub fn with_ids(ids: &VarIds) -> Self {
+        VarMap{ names: HashMap::new(), ids: ids.clone() }
     }
-}
 
-/// Sets the logic variable ID to zero.
-pub fn clear_id() {
-    unsafe { LOGIC_VAR_ID = 0; }
-}
+    /// Gets the ID of a variable, or gives the variable a new ID.
+    ///
+    /// # Arguments
+    /// * variable name
+    /// # Return
+    /// * ID
+    pub fn id_of(&mut self, name: &str) -> usize {
+        if let Some(id) = self.names.get(name) { return *id; }
+        let id = self.ids.next_id();
+        self.names.insert(name.to_string(), id);
+        return id;
+    }
+
+    /// Gets the counter of the map.
+    pub fn ids(&self) -> &VarIds { &self.ids }
 
-// VarMap defines a map which is used by recreate_variables(),
-// to keep track of previously recreated variables.
-// Key - variable name
-// Value - ID of variable
-pub type VarMap = HashMap<String, usize>;
+} // impl VarMap
 
 /// Creates a logic variable from a string.
 ///
@@ -63,7 +63,7 @@
 #[macro_export]
 macro_rules! cons_node {
     ($term:expr, $next:expr, $count:expr, $tail_var:expr) => {
-        Unifiable::SLinkedList{term: Box::new($term), next: Box::new($next),
+        Unifiable::SLinkedList{term: std::rc::Rc::new($term), next: std::rc::Rc::new($next),
                                count: $count, tail_var: $tail_var}
     };
 }
@@ -104,7 +104,7 @@ macro_rules! cons_node {
 /// let a = atom!("a");
 /// let b = atom!("b");
 /// let c = atom!("c");
-/// let x = logic_var!(next_id(), "$X");
+/// let x = logic_var!(1, "$X");
 /// let list2 = slist!(true, a, b, c, x);
 /// ```
 ///
@@ -185,7 +185,7 @@ macro_rules! add_rules {
 #[macro_export]
 macro_rules! atom {
     ($the_str:expr) => {
-        Unifiable::Atom($the_str.to_string())
+        Unifiable::Atom($crate::Symbol::new(&$the_str.to_string()))
     };
 }
 
@@ -211,7 +211,7 @@ macro_rules! atom {
 /// ```
 /// use suiron::*;
 ///
-/// let x = logic_var!(next_id(), "$X");
+/// let x = logic_var!(1, "$X");
 /// let y = logic_var!("$Y"); // ID is 0
 /// ```
 #[macro_export]
@@ -428,15 +428,16 @@ macro_rules! rc_cell {
 /// but a term can be any [unifiable term](../suiron/unifiable/enum.Unifiable.html).
 ///
 /// This utility calls [make_query()](../suiron/s_complex/fn.make_query.html),
-/// which [clears](../suiron/logic_var/fn.clear_id.html) the logic variable ID,
-/// and [recreates](../suiron/unifiable/enum.Unifiable.html#method.recreate_variables)
-/// all logic variables within the query.
+/// which [recreates](../suiron/unifiable/enum.Unifiable.html#method.recreate_variables)
+/// all logic variables within the query, numbering them from 1.
 ///
 /// The macro returns an Rc pointer to the newly created query, which can be
 /// passed to the function [make_base_node()](../suiron/goal/fn.make_base_node.html).
 ///
 /// See also: [parse_query()](../suiron/s_complex/fn.parse_query.html)
 ///
+/// # Panics
+/// * If the first term (functor) is not an atom.
 /// # Usage
 /// ```
 /// use std::rc::Rc;
@@ -455,7 +456,10 @@ macro_rules! rc_cell {
 #[macro_export]
 macro_rules! query {
     ($($term:expr),*) => (
-        Rc::new(make_query((vec!($($term),*))))
+        match make_query(vec!($($term),*)) {
+            Ok(query) => { Rc::new(query) },
+            Err(err) => { panic!("query! - {}", err); },
+        }
     );
 }
 
@@ -467,7 +471,7 @@ macro_rules! query {
 /// ```
 /// use suiron::*;
 ///
-/// let x = logic_var!(next_id(), "$X");
+/// let x = logic_var!(1, "$X");
 /// let number = SInteger(7);
 /// let goal = unify!(x, number);   // Goal is: $X = 7
 /// ```
@@ -40,6 +40,13 @@ use suiron::*;
 /// ?-
 /// ```
 ///
+/// # Formatting
+/// The `--fmt` option prints a source file in a canonical layout,
+/// instead of prompting for queries:
+/// ```
+/// cargo run -- --fmt tests/kings.txt
+/// ```
+///
 /// # Tutorial
 /// An on-line tutorial can be found [here](https://klivo.net/suiron/).
 ///
@@ -47,6 +54,18 @@ fn main() {
 
     let args: Vec<String> = env::args().collect();
 
+    // Format a source file.
+    if args.len() > 2 && args[1] == "--fmt" {
+        match format_file(&args[2]) {
+            Ok(text) => { print!("{}", text); },
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(1);
+            },
+        }
+        return;
+    }
+
     // Read file, if a file name was given.
     if args.len() > 1 {
 
@@ -94,7 +113,8 @@ fn main() {
     else {
         println!("\nSuiron - A fast inference engine, by Cleve Lendon, 2023\n");
         println!("Usage:");
-        println!("cargo run -- tests/kings.txt\n");
+        println!("cargo run -- tests/kings.txt");
+        println!("cargo run -- --fmt tests/kings.txt\n");
     }
 
 } // main()
@@ -13,6 +13,7 @@ use super::logic_var::*;
 
 /// Defines logical And, Or, etc. An operator holds a vector of goals.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Operator {
     /// Logical And operator.
     And(Vec<Goal>),
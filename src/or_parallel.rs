@@ -0,0 +1,278 @@
+//! Or-parallel search, which solves the clauses of a predicate on
+//! several threads. Requires the `rayon` feature.
+//!
+//! The facts and rules of a predicate are alternatives. Each clause
+//! gives its own solutions to a query, independently of the others.
+//! [solve_all_parallel()](fn.solve_all_parallel.html) solves the query
+//! once for each clause, on rayon's thread pool, and merges the solutions
+//! in clause order, which is the order of
+//! [try_solve_all()](../solutions/fn.try_solve_all.html).
+//!
+//! However, the search is not the same as a sequential search:
+//! <ul>
+//! <li>Each clause is searched up to the maximum number of solutions,
+//! even if the clauses before it have already found enough.</li>
+//! <li>Each thread works on its own copy of the knowledge base, so
+//! assert and retract do not affect the knowledge base of the caller,
+//! or the other clauses.</li>
+//! <li>The output of each clause, from print() and so on, is collected,
+//! and written to the caller's output sink in clause order, after the
+//! search. (See [set_output()](../output/fn.set_output.html).)</li>
+//! </ul>
+//!
+//! Solution nodes are not thread-safe, so each thread gets its own copy
+//! of the knowledge base, which is passed to it in
+//! [binary format](../binary_kb/index.html). The search settings of the
+//! calling thread (binding strategy, maximum proof depth, query planner,
+//! occurs check, float division, syntax and so on) are copied to each thread.
+//!
+//! Copying the knowledge base takes time, so or-parallel search pays off
+//! for queries which search for a long time, not for simple lookups.
+//!
+//! The query is solved on the calling thread, as usual, if:
+//! <ul>
+//! <li>its predicate has fewer than two clauses,</li>
+//! <li>a clause contains a cut, which would prune the clauses after it,</li>
+//! <li>the predicate is tabled,</li>
+//! <li>the search strategy is iterative deepening, or</li>
+//! <li>the knowledge base has external predicates, which cannot be copied.</li>
+//! </ul>
+//
+// Cleve Lendon 2023
+
+use std::rc::Rc;
+
+use rayon::prelude::*;
+
+use crate::*;
+
+use super::engine::Settings;
+use super::goal::Goal;
+use super::operator::Operator;
+use super::rule::Rule;
+use super::unifiable::Unifiable;
+
+// Predicate names, for the clauses and the query which are passed
+// to the threads.
+const CLAUSE: &str = "$or_parallel_clause";
+const QUERY: &str = "$or_parallel_query";
+
+/// Finds all solutions of a query, solving the clauses of its predicate
+/// on several threads.
+///
+/// See the [module documentation](index.html).
+///
+/// # Arguments
+/// * query (goal)
+/// * knowledge base
+/// * `max` - maximum number of solutions, or None
+/// # Return
+/// * vector of solutions (possibly empty), or error
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let query = parse_query("grandfather($X, $Y)").unwrap();
+/// let solutions = solve_all_parallel(&query, &kb, None).unwrap();
+/// for s in solutions { println!("{}", s); }
+/// // Prints:
+/// // $X = Alfred, $Y = Aethelstan
+/// ```
+pub fn solve_all_parallel(query: &Goal, kb: &KnowledgeBase, max: Option<usize>)
+                          -> Result<Vec<String>, SuironError> {
+
+    let terms = match query {
+        Goal::ComplexGoal(Unifiable::SComplex(terms)) => { terms },
+        _ => { return solve_sequentially(query, kb, max); },
+    };
+    let key = query.key()?;
+    let clauses: Vec<&Rule> = kb.rules(&key).collect();
+
+    if clauses.len() < 2 || clauses.iter().any(|rule| has_cut(&rule.body)) ||
+       kb.is_tabled(&key) || kb.has_externals() ||
+       search_strategy() == SearchStrategy::IterativeDeepening {
+        return solve_sequentially(query, kb, max);
+    }
+
+    // Clause i becomes: $or_parallel_clause(i, head arguments) :- body.
+    let mut extra = KnowledgeBase::new();
+    for (i, clause) in clauses.iter().enumerate() {
+        let mut head = vec![atom!(CLAUSE), Unifiable::SInteger(i as i64)];
+        if let Unifiable::SComplex(head_terms) = &clause.head {
+            head.extend(head_terms[1..].iter().cloned());
+        }
+        let rule = Rule{ head: Unifiable::SComplex(head), body: clause.body.clone() };
+        add_rules!(&mut extra, rule);
+    }
+    let query_fact = Rule{ head: Unifiable::SComplex(vec![atom!(QUERY),
+                                                          Unifiable::SComplex(terms.clone())]),
+                           body: Goal::Nil };
+    add_rules!(&mut extra, query_fact);
+
+    let mut kb_bytes: Vec<u8> = vec![];
+    write_kb(kb, &mut kb_bytes)?;
+    let mut extra_bytes: Vec<u8> = vec![];
+    write_kb(&extra, &mut extra_bytes)?;
+
+    let settings = Settings::current();
+    let indexing = kb.indexing();
+
+    // The output of each clause is captured, and forwarded below.
+    let results: Vec<(String, Result<Vec<String>, SuironError>)> =
+        (0..clauses.len()).into_par_iter().map(|i| {
+            settings.apply();
+            return with_captured_output(|| {
+                solve_clause(&kb_bytes, &extra_bytes, indexing, i, max)
+            });
+        }).collect();
+
+    let mut solutions: Vec<String> = vec![];
+    for (output, result) in results {
+        write_output(&output);
+        solutions.extend(result?);
+        if let Some(max) = max {
+            if solutions.len() >= max {
+                solutions.truncate(max);
+                break;
+            }
+        }
+    }
+    return Ok(solutions);
+
+} // solve_all_parallel()
+
+// Solves a query on the calling thread.
+fn solve_sequentially(query: &Goal, kb: &KnowledgeBase, max: Option<usize>)
+                      -> Result<Vec<String>, SuironError> {
+    let sn = make_base_node(Rc::new(query.clone()), kb);
+    return try_solve_all(sn, max);
+}
+
+// Solves the query with one clause of its predicate. This runs on a
+// thread of the pool.
+//
+// Arguments
+//    knowledge base, in binary format
+//    clauses and query, in binary format
+//    true if the knowledge base is indexed
+//    index of the clause
+//    maximum number of solutions, or None
+// Return
+//    solutions, or error
+fn solve_clause(kb_bytes: &[u8], extra_bytes: &[u8], indexing: bool,
+                i: usize, max: Option<usize>) -> Result<Vec<String>, SuironError> {
+
+    let mut kb = KnowledgeBase::new();
+    read_kb(&mut kb, kb_bytes)?;
+    read_kb(&mut kb, extra_bytes)?;
+    kb.set_indexing(indexing);
+
+    let query_key = format!("{}/1", QUERY);
+    let query_terms = match kb.rules(&query_key).next().map(|rule| &rule.head) {
+        Some(Unifiable::SComplex(terms)) => {
+            match &terms[1] {
+                Unifiable::SComplex(query_terms) => { query_terms.clone() },
+                _ => { vec![] },
+            }
+        },
+        _ => { vec![] },
+    };
+
+    let mut terms = vec![atom!(CLAUSE), Unifiable::SInteger(i as i64)];
+    terms.extend(query_terms.into_iter().skip(1));
+    let query = make_query(terms)?;
+    let sn = make_base_node(Rc::new(query), &kb);
+    return try_solve_all(sn, max);
+
+} // solve_clause()
+
+// Determines whether a goal contains a cut.
+fn has_cut(goal: &Goal) -> bool {
+    match goal {
+        Goal::BuiltInGoal(bip) => { return bip.functor == "!"; },
+        Goal::OperatorGoal(op) => {
+            let goals = match op {
+                Operator::And(goals) | Operator::Or(goals) |
+                Operator::Time(goals) | Operator::Not(goals) => { goals },
+            };
+            return goals.iter().any(has_cut);
+        },
+        _ => { return false; },
+    }
+} // has_cut()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+
+    use crate::*;
+
+    fn solve_both(kb: &KnowledgeBase, query: &str) -> (Vec<String>, Vec<String>) {
+        let query = parse_query(query).unwrap();
+        let sn = make_base_node(Rc::new(query.clone()), kb);
+        let sequential = try_solve_all(sn, None).unwrap();
+        let parallel = solve_all_parallel(&query, kb, None).unwrap();
+        return (sequential, parallel);
+    }
+
+    #[test]
+    fn test_solve_all_parallel() {
+
+        let mut kb = test_kb();
+        for rule in ["digit(0).", "digit(1).", "digit(2).", "digit(3).",
+                     "pair($X, $Y) :- digit($X), digit($Y), $X < $Y.",
+                     "pair($X, $Y) :- digit($X), $Y = none.",
+                     "pair(last, last).",
+                     "first($X) :- digit($X), !."] {
+            add_rules!(&mut kb, parse_rule(rule).unwrap());
+        }
+
+        // Solutions are merged in clause order.
+        let (sequential, parallel) = solve_both(&kb, "pair($A, $B)");
+        assert_eq!(11, sequential.len());
+        assert_eq!(sequential, parallel);
+
+        let (sequential, parallel) = solve_both(&kb, "pair(1, $B)");
+        assert_eq!(sequential, parallel);
+
+        // Settings are copied to the threads.
+        set_binding_strategy(BindingStrategy::Trail);
+        let (sequential, parallel) = solve_both(&kb, "pair($A, 3)");
+        assert_eq!(vec!["$A = 0", "$A = 1", "$A = 2"], parallel);
+        assert_eq!(sequential, parallel);
+        set_binding_strategy(BindingStrategy::Persistent);
+
+        // Solved on the calling thread.
+        let (sequential, parallel) = solve_both(&kb, "first($X)");
+        assert_eq!(vec!["$X = 0"], parallel);
+        assert_eq!(sequential, parallel);
+
+        let query = parse_query("pair($A, $B)").unwrap();
+        let solutions = solve_all_parallel(&query, &kb, Some(7)).unwrap();
+        assert_eq!(7, solutions.len());
+        assert_eq!("$A = 0, $B = none", solutions[6]);
+
+    } // test_solve_all_parallel()
+
+    // Output is written to the caller's sink, in clause order.
+    #[test]
+    fn test_parallel_output() {
+
+        let mut kb = KnowledgeBase::new();
+        for rule in ["w(1) :- print(hello).", "w(2) :- print(world).",
+                     "w(3) :- nl."] {
+            add_rules!(&mut kb, parse_rule(rule).unwrap());
+        }
+        let query = parse_query("w($X)").unwrap();
+        let (text, solutions) = with_captured_output(|| {
+            solve_all_parallel(&query, &kb, None).unwrap()
+        });
+        assert_eq!("helloworld\n", text);
+        assert_eq!(vec!["$X = 1", "$X = 2", "$X = 3"], solutions);
+
+    } // test_parallel_output()
+
+} // test
@@ -0,0 +1,206 @@
+//! Functions for redirecting the output of built-in predicates.
+//!
+//! By default, the output predicates print(), print_list(), nl(),
+//! format(), tab(), write() and write_canonical() write to standard
+//! output. An application which embeds Suiron can redirect this output
+//! to a buffer, a log file or a GUI widget, by calling set_output().
+//!
+//! The output sink is local to the thread.
+//!
+//! For testing Suiron programs, with_captured_output() collects the
+//! output produced while a closure runs.
+
+use std::cell::RefCell;
+use std::io::{self, stdout, Write};
+use std::rc::Rc;
+
+thread_local! {
+    static SUIRON_OUTPUT: RefCell<Option<Box<dyn Write>>> = RefCell::new(None);
+}
+
+/// Redirects the output of built-in predicates.
+///
+/// # Arguments
+/// * output sink (implements Write)
+/// # Return
+/// * previous output sink, or None if output was going to stdout
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let path = std::env::temp_dir().join("suiron_log.txt");
+/// let file = std::fs::File::create(path).unwrap();
+/// set_output(file);
+///
+/// let mut kb = KnowledgeBase::new();
+/// let rule = parse_rule("hello :- print(Hello), nl.").unwrap();
+/// add_rules!(&mut kb, rule);
+///
+/// let query = parse_query("hello").unwrap();
+/// solve(make_base_node(Rc::new(query), &kb));  // Hello goes to the file.
+/// reset_output();
+/// ```
+pub fn set_output(sink: impl Write + 'static) -> Option<Box<dyn Write>> {
+    SUIRON_OUTPUT.with(|output| output.borrow_mut().replace(Box::new(sink)))
+} // set_output()
+
+/// Restores output of built-in predicates to standard output.
+///
+/// # Return
+/// * previous output sink, or None if output was going to stdout
+pub fn reset_output() -> Option<Box<dyn Write>> {
+    SUIRON_OUTPUT.with(|output| output.borrow_mut().take())
+} // reset_output()
+
+/// Runs a function, and captures the output of built-in predicates.
+///
+/// Output is collected in a buffer while the function runs, without
+/// touching the process's standard output. Afterwards, the previous
+/// output sink is restored, even if the function panics.
+///
+/// # Arguments
+/// * function to run
+/// # Return
+/// * (captured output, result of the function)
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// let rule = parse_rule("greet($X) :- print(Hello), nl, $X = done.").unwrap();
+/// add_rules!(&mut kb, rule);
+///
+/// let (text, result) = with_captured_output(|| {
+///     let query = parse_query("greet($X)").unwrap();
+///     solve(make_base_node(Rc::new(query), &kb))
+/// });
+/// assert_eq!("Hello\n", text);
+/// assert_eq!("$X = done", result);
+/// ```
+pub fn with_captured_output<T>(f: impl FnOnce() -> T) -> (String, T) {
+
+    let buffer = Rc::new(RefCell::new(vec![]));
+    let guard = RestoreOutput(set_output(SharedBuffer(Rc::clone(&buffer))));
+
+    let result = f();
+    drop(guard);
+
+    let text = String::from_utf8_lossy(&buffer.borrow()).to_string();
+    return (text, result);
+
+} // with_captured_output()
+
+// A sink which shares its buffer, so that output can be read back.
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+// Restores the previous output sink when dropped.
+struct RestoreOutput(Option<Box<dyn Write>>);
+
+impl Drop for RestoreOutput {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        SUIRON_OUTPUT.with(|output| { *output.borrow_mut() = previous; });
+    }
+}
+
+/// Writes text to the output sink.
+///
+/// This function is called by the output predicates. If no sink has
+/// been set, the text is written to standard output. Write errors
+/// are ignored.
+///
+/// # Arguments
+/// * text to write
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// write_output("Hello\n");
+/// ```
+pub fn write_output(text: &str) {
+    SUIRON_OUTPUT.with(|output| {
+        match output.borrow_mut().as_mut() {
+            Some(sink) => { let _ = sink.write_all(text.as_bytes()); },
+            None => { print!("{}", text); },
+        }
+    });
+} // write_output()
+
+/// Flushes the output sink.
+pub fn flush_output() {
+    SUIRON_OUTPUT.with(|output| {
+        match output.borrow_mut().as_mut() {
+            Some(sink) => { let _ = sink.flush(); },
+            None => { let _ = stdout().flush(); },
+        }
+    });
+} // flush_output()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::*;
+    use super::*;
+
+    // Test redirecting output of built-in predicates.
+    #[test]
+    fn test_set_output() {
+
+        let buffer = Rc::new(RefCell::new(vec![]));
+        set_output(SharedBuffer(Rc::clone(&buffer)));
+
+        let mut kb = KnowledgeBase::new();
+        let rule = parse_rule("greet($X) :- print(Hello %s, $X), nl, \
+                               tab(2), print_list([a, b]), \
+                               format(\"~w~t~6|!\", [$X]).").unwrap();
+        add_rules!(&mut kb, rule);
+
+        let query = parse_query("greet(Ann)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        solve(sn);
+
+        assert!(reset_output().is_some());
+        let text = String::from_utf8(buffer.borrow().clone()).unwrap();
+        assert_eq!("Hello Ann\n  a, b\nAnn   !", text);
+
+    } // test_set_output()
+
+    // Test capturing output. Captures can be nested, and the previous
+    // sink is restored afterwards.
+    #[test]
+    fn test_with_captured_output() {
+
+        let mut kb = KnowledgeBase::new();
+        let rule = parse_rule("one($X) :- print(one), nl, $X = 1.").unwrap();
+        add_rules!(&mut kb, rule);
+
+        let (outer, (inner, result)) = with_captured_output(|| {
+            write_output("before ");
+            let captured = with_captured_output(|| {
+                let query = parse_query("one($X)").unwrap();
+                solve(make_base_node(Rc::new(query), &kb))
+            });
+            write_output("after");
+            captured
+        });
+
+        assert_eq!("one\n", inner);
+        assert_eq!("$X = 1", result);
+        assert_eq!("before after", outer);
+        assert!(reset_output().is_none());
+
+    } // test_with_captured_output()
+
+} // test
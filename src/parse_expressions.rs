@@ -0,0 +1,364 @@
+//! Functions for parsing arithmetic expressions, such as `($A + $B) * -2`.
+//!
+//! Expressions are parsed by precedence climbing. From lowest
+//! to highest, the precedence of the arithmetic operators is:
+//!
+//! <pre>
+//!   +  -  /\  \/  xor
+//!   *  /  //  mod  rem  rdiv  <<  >>
+//!   - (unary minus)
+//!   **
+//! </pre>
+//!
+//! Operators of the same precedence are evaluated left to right,
+//! except for **, which is evaluated right to left. Parentheses
+//! can be used to group sub-expressions.
+//!
+//! Each operator is converted to a built-in function (SFunction).
+//! For example, `$A + $B * 2` becomes `add($A, multiply($B, 2))`.
+//!
+//! As elsewhere in Suiron, an infix operator must be preceded and
+//! followed by a space. A minus sign which is directly in front of
+//! a number is part of the number: `-2`. A minus sign which is
+//! directly in front of a variable or a parenthesis is a unary
+//! minus: `-$X`, `-($X + 1)`.
+//
+// Cleve Lendon 2023
+
+use super::error::SuironError;
+use super::infix::*;
+use super::parse_terms::*;
+use super::unifiable::{*, Unifiable::*};
+
+use crate::sfunction;
+use crate::str_to_chars;
+use crate::chars_to_string;
+
+// Tokens of an arithmetic expression.
+#[derive(Debug, PartialEq)]
+enum Token {
+    Operand(String),
+    Operator(Infix),
+    Negate,       // unary minus
+    Open,         // (
+    Close,        // )
+}
+
+// Precedence of unary minus.
+const NEGATE_PRECEDENCE: u8 = 3;
+
+/// Parses an arithmetic expression.
+///
+/// If the given string is not an arithmetic expression, the function
+/// returns None. For example, `$X + 1` is an expression, but
+/// `$X` and `add($X, 1)` are not.
+///
+/// # Arguments
+/// * string to parse
+/// # Return
+/// * Some(Ok([SFunction](../unifiable/enum.Unifiable.html#variant.SFunction))),
+///   Some(error message), or None
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let expression = parse_expression("($A + $B) * -2 / ($C - 1)").unwrap().unwrap();
+/// println!("{}", expression);
+/// // Prints: divide(multiply(add($A, $B), -2), subtract($C, 1))
+/// ```
+pub fn parse_expression(to_parse: &str) -> Option<Result<Unifiable, SuironError>> {
+
+    let chrs = str_to_chars!(to_parse.trim());
+    let tokens = tokenize(&chrs)?;
+
+    let mut position = 0;
+    let result = parse_operation(&tokens, &mut position, 0);
+    if result.is_ok() && position < tokens.len() {
+        return Some(Err(pe_error("Unmatched parenthesis", to_parse).into()));
+    }
+    return Some(result.map_err(|err| pe_error(&err, to_parse).into()));
+
+} // parse_expression()
+
+// Divides an expression into tokens: operands, operators and parentheses.
+//
+// Arguments
+//    vector of chars
+// Return
+//    vector of tokens, or None if the text is not an arithmetic expression
+fn tokenize(chrs: &[char]) -> Option<Vec<Token>> {
+
+    let length = chrs.len();
+    let mut tokens: Vec<Token> = vec![];
+    let mut expect_operand = true;
+
+    let mut i = 0;
+    while i < length {
+
+        let ch = chrs[i];
+        if ch == ' ' { i += 1; continue; }
+
+        if expect_operand {
+            let next = if i + 1 < length { chrs[i + 1] } else { ' ' };
+            if ch == '(' {
+                tokens.push(Token::Open);
+                i += 1;
+            }
+            else if ch == '-' && (next == '$' || next == '(') {
+                tokens.push(Token::Negate);
+                i += 1;
+            }
+            else {
+                let end = end_of_operand(chrs, i);
+                if end > i {
+                    tokens.push(Token::Operand(chars_to_string!(chrs[i..end])));
+                }
+                expect_operand = false;
+                i = end;
+            }
+        }
+        else if ch == ')' {
+            tokens.push(Token::Close);
+            i += 1;
+        }
+        else {
+            // An operator must be followed by a space.
+            let mut end = i;
+            while end < length && chrs[end] != ' ' { end += 1; }
+            if end == length { return None; }
+            let word = chars_to_string!(chrs[i..end]);
+            tokens.push(Token::Operator(operator(&word)?));
+            expect_operand = true;
+            i = end;
+        }
+    } // while
+
+    // Without an operator, this is not an expression.
+    if !tokens.iter().any(|t| matches!(t, Token::Operator(_) | Token::Negate)) {
+        return None;
+    }
+    return Some(tokens);
+
+} // tokenize()
+
+// Finds the end of an operand, which may be a number, a variable,
+// a complex term, a list, etc. The operand ends at a space, or at
+// an unmatched closing parenthesis.
+//
+// Arguments
+//    vector of chars
+//    start index
+// Return
+//    index after the end of the operand
+fn end_of_operand(chrs: &[char], start: usize) -> usize {
+
+    let length = chrs.len();
+    let mut depth = 0;        // depth of () and []
+    let quoted = quoted_text(chrs);
+
+    let mut i = start;
+    while i < length {
+        let ch = chrs[i];
+        if quoted[i] { i += 1; continue; }
+        if ch == '(' || ch == '[' { depth += 1; }
+        else if ch == ')' || ch == ']' {
+            if depth == 0 { return i; }
+            depth -= 1;
+        }
+        else if ch == ' ' && depth == 0 { return i; }
+        i += 1;
+    }
+    return length;
+
+} // end_of_operand()
+
+// Parses operations with a precedence greater than or equal to
+// the given minimum. This is the core of precedence climbing.
+//
+// Arguments
+//    tokens
+//    position of next token
+//    minimum precedence
+// Return
+//    unifiable term or error message
+fn parse_operation(tokens: &[Token], position: &mut usize,
+                   min_precedence: u8) -> Result<Unifiable, String> {
+
+    let mut left = parse_operand(tokens, position)?;
+
+    while let Some(Token::Operator(infix)) = tokens.get(*position) {
+        let prec = precedence(infix);
+        if prec < min_precedence { break; }
+        *position += 1;
+        // ** is right associative. Other operators are left associative.
+        let next_min = if *infix == Infix::Power { prec } else { prec + 1 };
+        let right = parse_operation(tokens, position, next_min)?;
+        left = sfunction!(function_name(infix), left, right);
+    }
+
+    return Ok(left);
+
+} // parse_operation()
+
+// Parses an operand: a term, a negated operand, or an expression
+// in parentheses.
+//
+// Arguments
+//    tokens
+//    position of next token
+// Return
+//    unifiable term or error message
+fn parse_operand(tokens: &[Token], position: &mut usize)
+                 -> Result<Unifiable, String> {
+
+    let token = match tokens.get(*position) {
+        Some(token) => { token },
+        None => { return Err("Missing operand".to_string()); },
+    };
+    *position += 1;
+
+    match token {
+        Token::Operand(s) => { return parse_term(s).map_err(String::from); },
+        Token::Negate => {
+            let operand = parse_operation(tokens, position, NEGATE_PRECEDENCE)?;
+            return Ok(sfunction!("subtract", SInteger(0), operand));
+        },
+        Token::Open => {
+            let inner = parse_operation(tokens, position, 0)?;
+            if tokens.get(*position) != Some(&Token::Close) {
+                return Err("Unmatched parenthesis".to_string());
+            }
+            *position += 1;
+            return Ok(inner);
+        },
+        _ => { return Err("Missing operand".to_string()); },
+    }
+
+} // parse_operand()
+
+// Gets the arithmetic infix which corresponds to the given word.
+//
+// Arguments
+//    word, eg. +, mod
+// Return
+//    infix or None
+fn operator(word: &str) -> Option<Infix> {
+    match word {
+        "+"    => Some(Infix::Plus),
+        "-"    => Some(Infix::Minus),
+        "*"    => Some(Infix::Multiply),
+        "/"    => Some(Infix::Divide),
+        "//"   => Some(Infix::IntDivide),
+        "mod"  => Some(Infix::Mod),
+        "rem"  => Some(Infix::Rem),
+        "rdiv" => Some(Infix::RDiv),
+        "**"   => Some(Infix::Power),
+        "/\\"  => Some(Infix::BitAnd),
+        "\\/"  => Some(Infix::BitOr),
+        "xor"  => Some(Infix::Xor),
+        "<<"   => Some(Infix::ShiftLeft),
+        ">>"   => Some(Infix::ShiftRight),
+        _      => None,
+    }
+} // operator()
+
+// Gets the precedence of an arithmetic infix. Higher binds tighter.
+fn precedence(infix: &Infix) -> u8 {
+    match infix {
+        Infix::Plus | Infix::Minus | Infix::BitAnd |
+        Infix::BitOr | Infix::Xor => 1,
+        Infix::Power => 4,
+        _ => 2,
+    }
+} // precedence()
+
+// Gets the name of the built-in function for an arithmetic infix.
+fn function_name(infix: &Infix) -> &'static str {
+    match infix {
+        Infix::Plus       => "add",
+        Infix::Minus      => "subtract",
+        Infix::Multiply   => "multiply",
+        Infix::Divide     => "divide",
+        Infix::IntDivide  => "int_divide",
+        Infix::Mod        => "mod",
+        Infix::Rem        => "rem",
+        Infix::RDiv       => "rdiv",
+        Infix::Power      => "pow",
+        Infix::BitAnd     => "bit_and",
+        Infix::BitOr      => "bit_or",
+        Infix::Xor        => "xor",
+        Infix::ShiftLeft  => "shift_left",
+        _                 => "shift_right",
+    }
+} // function_name()
+
+// Formats an error message for parse_expression().
+fn pe_error(err: &str, bad: &str) -> String {
+    format!("parse_expression() - {}: {}", err, bad.trim())
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    // Parses an expression and formats the result.
+    fn parse(s: &str) -> String {
+        match parse_expression(s) {
+            Some(Ok(term)) => { term.to_string() },
+            Some(Err(err)) => { err.into() },
+            None => { "None".to_string() },
+        }
+    }
+
+    #[test]
+    fn test_parse_expression() {
+
+        assert_eq!("add($A, multiply($B, 2))", parse("$A + $B * 2"));
+        assert_eq!("add(multiply($A, $B), 2)", parse("$A * $B + 2"));
+        assert_eq!("subtract(subtract(10, 4), 3)", parse("10 - 4 - 3"));
+        assert_eq!("divide(divide(24, 4), 2)", parse("24 / 4 / 2"));
+        assert_eq!("pow(2, pow(3, 2))", parse("2 ** 3 ** 2"));
+        assert_eq!("multiply(add($A, $B), 2)", parse("($A + $B) * 2"));
+        assert_eq!("divide(multiply(add($A, $B), -2), subtract($C, 1))",
+                   parse("($A + $B) * -2 / ($C - 1)"));
+        assert_eq!("add(mod($X, 3), shift_left(1, 4))", parse("$X mod 3 + 1 << 4"));
+
+        // Unary minus.
+        assert_eq!("subtract(0, $X)", parse("-$X"));
+        assert_eq!("multiply(subtract(0, add($X, 1)), 2)", parse("-($X + 1) * 2"));
+        assert_eq!("subtract(0, pow($X, 2))", parse("-$X ** 2"));
+
+        // Terms with spaces and parentheses.
+        assert_eq!("add(sqrt(add($A, 1)), f(a, b))", parse("sqrt($A + 1) + f(a, b)"));
+        assert_eq!("add(\"a b\", 1)", parse("\"a b\" + 1"));
+        assert_eq!("multiply(add(1, 2), add(3, 4))", parse("((1 + 2)) * (3 + 4)"));
+
+        // Not expressions.
+        assert_eq!("None", parse("$X"));
+        assert_eq!("None", parse("-7"));
+        assert_eq!("None", parse("add($X, 1)"));
+        assert_eq!("None", parse("Hello World"));
+        assert_eq!("None", parse("$X +"));
+
+        // Errors.
+        assert_eq!("parse_expression() - Unmatched parenthesis: ($X + 1",
+                   parse("($X + 1"));
+        assert_eq!("parse_expression() - Unmatched parenthesis: $X + 1)",
+                   parse("$X + 1)"));
+        assert_eq!("parse_expression() - Missing operand: $X + ()", parse("$X + ()"));
+
+    } // test_parse_expression()
+
+    #[test]
+    fn test_evaluate_parsed_expression() {
+        let ss = empty_ss!();
+        let expression = parse_term("(2 + 3) * -2 / (6 - 1) - 2 ** 3").unwrap();
+        assert_eq!(Ok(SInteger(-10)), evaluate_expression(&expression, &ss));
+        let expression = parse_term("10 - 4 - 3").unwrap();
+        assert_eq!(Ok(SInteger(3)), evaluate_expression(&expression, &ss));
+    }
+
+} // test
@@ -7,11 +7,14 @@ use crate::pred;
 use crate::str_to_chars;
 use crate::chars_to_string;
 
+use super::error::SuironError;
 use super::goal::*;
 use super::infix::*;
 use super::operator::*;
 use super::s_complex::*;
 use super::parse_terms::*;
+use super::user_operators::*;
+use super::dcg::*;
 use super::unifiable::Unifiable;
 use super::built_in_predicates::*;
 
@@ -50,7 +53,9 @@ pub fn indices_of_parentheses(goal: &Vec<char>)
     let mut count_left  = 0;
     let mut count_right = 0;
 
+    let quoted = quoted_text(goal);
     for (i, ch) in goal.iter().enumerate() {
+        if quoted[i] { continue; }
         if *ch == '(' {
             if left == -1 { left = i as i32; }
             count_left += 1;
@@ -155,13 +160,13 @@ fn split_complex_term(complex: Vec<char>, index1: usize, index2: usize)
 /// * This function does not parse And or Or operators. See
 /// [generate_goal()](../tokenizer/fn.generate_goal.html)
 ///
-pub fn parse_subgoal(to_parse: &str) -> Result<Goal, String> {
+pub fn parse_subgoal(to_parse: &str) -> Result<Goal, SuironError> {
 
     let s = to_parse.trim();
 
     if s.len() == 0 {
         let err = "parse_subgoal() - Empty string.".to_string();
-        return Err(err);
+        return Err(err.into());
     }
 
     let chrs = str_to_chars!(s);
@@ -173,29 +178,30 @@ pub fn parse_subgoal(to_parse: &str) -> Result<Goal, String> {
     }
 
     //--------------------------------------
-    // Handle infixes: = > < >= <= == =
+    // Handle infixes: = > < >= <= == = is =:= =\=
 
     let (infix, index) = check_infix(&chrs);
     if infix != Infix::None {
 
-        // An infix can be 1 or 2 characters, eg: <, <=
+        // An infix can be 1 to 3 characters, eg: <, <=, =:=
         // The last parameter of get_left_and_right() is the
-        // size of the infix. To avoid repeating this call
-        // for each infix, it is called here with an infix size
-        // of 2. Since all infixes must be followed by a space,
-        // this shouldn't be a problem.
-        let (left, right) = get_left_and_right(chrs, index, 2)?;
+        // size of the infix.
+        let size = infix.to_string().len();
+        let (left, right) = get_left_and_right(chrs, index, size)?;
 
         let goal = match infix {
             Infix::Unify => { pred!("unify", left, right) },
             Infix::Equal => { pred!("equal", left, right) },
+            Infix::ArithmeticEqual    => { pred!("arithmetic_equal", left, right) },
+            Infix::ArithmeticNotEqual => { pred!("arithmetic_not_equal", left, right) },
             Infix::LessThan           => { pred!("less_than", left, right) },
             Infix::LessThanOrEqual    => { pred!("less_than_or_equal", left, right) },
             Infix::GreaterThan        => { pred!("greater_than", left, right) },
             Infix::GreaterThanOrEqual => { pred!("greater_than_or_equal", left, right) },
+            Infix::Is => { pred!("is", left, right) },
             _ => {
                 let err = format!("parse_subgoal() - Invalid syntax: {}", s);
-                return Err(err);
+                return Err(err.into());
             },
         }; // let match
 
@@ -203,6 +209,11 @@ pub fn parse_subgoal(to_parse: &str) -> Result<Goal, String> {
 
     } // if infix != Infix::None
 
+    // Check for user-defined operators, eg. john likes mary
+    if let Some(result) = parse_operators(s) {
+        return Ok(Goal::ComplexGoal(result?));
+    }
+
     // Check for parentheses.
     let left_index: usize;
     let right_index: usize;
@@ -216,13 +227,13 @@ pub fn parse_subgoal(to_parse: &str) -> Result<Goal, String> {
                         Ok(c) => { return Ok(Goal::ComplexGoal(c)); },
                         Err(err) => {
                             let err = format!("{}{}", err, to_parse);
-                            return Err(err);
+                            return Err(err.into());
                         },
                     }
                 },
             } // match
         },
-        Err(err) => { return Err(err); },
+        Err(err) => { return Err(err.into()); },
     } // match
 
     let (functor_str, args_str) =
@@ -264,14 +275,34 @@ pub fn make_goal(functor: &str, mut args: Vec<Unifiable>) -> Goal {
     if functor == "print" || functor == "append" || functor == "functor" ||
        functor == "include" || functor == "exclude" ||
        functor == "print_list" || functor == "unify" || functor == "equal" ||
+       functor == "unify_with_occurs_check" ||
+       functor == "arithmetic_equal" || functor == "arithmetic_not_equal" ||
        functor == "less_than"    || functor == "less_than_or_equal" ||
        functor == "greater_than" || functor == "greater_than_or_equal" ||
        functor == "count" || functor == "include" || functor == "exclude" ||
-       functor == "functor" {
+       functor == "functor" || functor == "upcase_atom" ||
+       functor == "downcase_atom" || functor == "split_string" ||
+       functor == "atom_string" || functor == "number_string" ||
+       functor == "char_code" || functor == "term_to_atom" ||
+       functor == "join" || functor == "is" || functor == "format" ||
+       functor == "format_atom" || functor == "format_number" || functor == "tab" ||
+       functor == "write_canonical" || functor == "read" ||
+       functor == "read_term" || functor == "open" ||
+       functor == "close" || functor == "read_line" || functor == "write" ||
+       functor == "random" || functor == "random_between" ||
+       functor == "set_random_seed" ||
+       (cfg!(feature = "regex") && functor == "regex_match") {
         let pred = BuiltInPredicate::new(functor.to_string(), Some(args));
         return Goal::BuiltInGoal(pred);
     }
 
+    // phrase() calls a grammar rule.
+    if functor == "phrase" {
+        if let Some(goal) = expand_phrase(&args) {
+            return Goal::ComplexGoal(goal);
+        }
+    }
+
     // Create a complex term.
     let mut unifiables = vec![atom!(functor)];
     unifiables.append(&mut args);
@@ -323,7 +354,7 @@ pub fn make_goal_no_args(functor: &str) -> Goal {
 /// # Return
 /// * operator goal or error message
 ///
-fn parse_operator_goal(name: &str, args_str: &str) -> Result<Goal, String> {
+fn parse_operator_goal(name: &str, args_str: &str) -> Result<Goal, SuironError> {
     let subgoal = parse_subgoal(&args_str)?;
     match name {
         "time" => {
@@ -334,7 +365,7 @@ fn parse_operator_goal(name: &str, args_str: &str) -> Result<Goal, String> {
         },
         _ => {
            let err = "parse_operator_goal() - Invalid operator.".to_string();
-           return Err(err)
+           return Err(err.into())
         },
     }
 } // parse_operator_goal()
@@ -1,20 +1,74 @@
 //! Functions for parsing unifiable terms and lists of terms.
 //!
+//! In Suiron, logic variables begin with a dollar sign: $X. For importing
+//! Prolog source code, a Prolog syntax mode can be set, in which words
+//! that begin with a capital letter or an underscore are also variables.
+//! See [set_prolog_syntax()](fn.set_prolog_syntax.html).
+//
 // Cleve Lendon 2023
 
-use super::infix::*;
-use super::parse_goals::*;
+use std::cell::Cell;
+
+use super::error::SuironError;
 use super::s_linked_list::*;
 use super::logic_var::*;
 use super::s_complex::*;
 use super::unifiable::{*, Unifiable::*};
 use super::built_in_functions::*;
+use super::built_in_math::*;
+use super::built_in_rational::*;
+use super::parse_expressions::*;
+use super::user_operators::*;
 
 use crate::atom;
-use crate::sfunction;
 use crate::str_to_chars;
 use crate::chars_to_string;
 
+thread_local! {
+    // If true, capitalized words are variables, as in Prolog.
+    static PROLOG_SYNTAX: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets the Prolog syntax mode for parsing.
+///
+/// By default, a Suiron variable begins with a dollar sign ($X), and a
+/// capitalized word (Godwin) is an atom. In Prolog syntax mode, words
+/// which begin with a capital letter or an underscore are variables, as
+/// in classic Prolog. This makes it possible to load Prolog source files
+/// without rewriting them. The variable X is named $X, and _ is the
+/// anonymous variable. Atoms which begin with a capital letter must be
+/// quoted: 'Godwin'.
+///
+/// Variables which begin with a dollar sign are still accepted.
+///
+/// The setting is local to the thread.
+///
+/// # Arguments
+/// * on - true for Prolog syntax, false for Suiron syntax
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// set_prolog_syntax(true);
+/// let rule = parse_rule("grandparent(X, Z) :- parent(X, Y), parent(Y, Z).").unwrap();
+/// println!("{}", rule);
+/// // Prints: grandparent($X, $Z) :- parent($X, $Y), parent($Y, $Z).
+/// set_prolog_syntax(false);
+/// ```
+pub fn set_prolog_syntax(on: bool) {
+    PROLOG_SYNTAX.with(|ps| ps.set(on));
+} // set_prolog_syntax()
+
+/// Determines whether the Prolog syntax mode is set.
+///
+/// See [set_prolog_syntax()](fn.set_prolog_syntax.html).
+///
+/// # Return
+/// * true or false
+pub fn prolog_syntax() -> bool {
+    return PROLOG_SYNTAX.with(|ps| ps.get());
+} // prolog_syntax()
+
 /// Parses a list of terms (arguments).
 ///
 /// Parses a comma separated list of terms to produce a vector
@@ -34,38 +88,30 @@ use crate::chars_to_string;
 /// }
 /// // Should print: [Atom("Argon"), SInteger(18)]
 /// ```
-pub fn parse_arguments(to_parse: &str) -> Result<Vec<Unifiable>, String> {
+pub fn parse_arguments(to_parse: &str) -> Result<Vec<Unifiable>, SuironError> {
 
     let s = to_parse.trim();
-    let chrs = str_to_chars!(s);
-    let length_chrs = chrs.len();
 
-    if length_chrs == 0 {
+    if s.is_empty() {
         let err = "parse_arguments() - Empty argument list: ";
-        return Err(err.to_string());
+        return Err(SuironError::ParseError(err.to_string()));
     }
 
-    if chrs[0] == ',' {
+    if s.starts_with(',') {
         let err = "parse_arguments() - Missing first argument: ";
-        return Err(err.to_string());
+        return Err(SuironError::ParseError(err.to_string()));
     }
 
     // A comma at the end probably indicates a missing argument, but...
     // make sure comma is not escaped, because this is valid: "term1, term2, \,"
-    if chrs[length_chrs - 1] == ',' {
-        // Length must be longer than 1, because comma
-        // is not the first character.
-        let prev = chrs[length_chrs - 2];
-        if prev != '\\' {   // escape character
-           let err = "parse_arguments() - Missing last argument: ";
-           return Err(err.to_string());
-        }
+    if s.ends_with(',') && !s.ends_with("\\,") {
+        let err = "parse_arguments() - Missing last argument: ";
+        return Err(SuironError::ParseError(err.to_string()));
     }
 
     let mut has_digit     = false;
     let mut has_non_digit = false;
     let mut has_period    = false;
-    let mut open_quote    = false;
 
     let mut num_quotes    = 0;
     let mut round_depth   = 0;   // depth of round parentheses (())
@@ -75,21 +121,27 @@ pub fn parse_arguments(to_parse: &str) -> Result<Vec<Unifiable>, String> {
     let mut term_list = Vec::<Unifiable>::new();
 
     let mut start = 0;
+    let mut escaped = false;     // true if previous character was a backslash
+    let mut length_chrs = 0;
 
-    let mut i = start;
-    while i < length_chrs {
+    for sc in QuoteScanner::new(s.chars()) {
 
-        let ch = chrs[i];
+        let ch = sc.ch;
+        length_chrs = sc.index + 1;
 
-        // If this argument is between double quotes,
-        // it must be an Atom.
-        if open_quote {
+        // Text between quotes is part of the argument. Escape
+        // sequences are kept for make_term().
+        if sc.quoted {
             argument.push(ch);
-            if ch == '"' {
-                open_quote = false;
+            if sc.mark && round_depth == 0 && square_depth == 0 {
                 num_quotes += 1;
             }
         }
+        else if escaped {
+            // An escaped character is included in the argument.
+            argument.push(ch);
+            escaped = false;
+        }
         else {
             if ch == '[' {
                 argument.push(ch);
@@ -113,52 +165,37 @@ pub fn parse_arguments(to_parse: &str) -> Result<Vec<Unifiable>, String> {
 
                     let s2 = argument.trim();
                     match check_quotes(s2, num_quotes) {
-                        Some(err) => { return Err(err); },
+                        Some(err) => { return Err(err.into()); },
                         None => {},
                     }
                     num_quotes = 0;
 
-                    let term = make_term(s2, has_digit, has_non_digit, has_period)?;
+                    let term = match parse_operations(s2) {
+                        Some(result) => { result? },
+                        None => { make_term(s2, has_digit, has_non_digit, has_period)? },
+                    };
                     term_list.push(term);
                     argument    = "".to_string();
                     has_digit   = false;
                     has_non_digit = false;
                     has_period  = false;
-                    start = i + 1;    // past comma
+                    start = sc.index + 1;    // past comma
                 }
                 else if ch >= '0' && ch <= '9' {
                     argument.push(ch);
                     has_digit = true
                 }
                 else if ch == '+' || ch == '-' {
-                    argument.push(ch);
                     // Plus or minus might be in front of a number: +7, -3.8
                     // In this case, it is part of the number.
-                    let mut next_ch = 'x';
-                    if i < length_chrs - 1 { next_ch = chrs[i + 1]; }
-                    let mut prev_ch = ' ';
-                    if i > 0 { prev_ch = chrs[i]; }
-                    if prev_ch == ' ' && (next_ch < '0' || next_ch > '9') {
-                        has_non_digit = true;
-                    }
+                    argument.push(ch);
                 }
                 else if ch == '.' {
                     argument.push(ch);
                     has_period = true
                 }
                 else if ch == '\\' {  // escape character, must include next character
-                    if i < length_chrs {
-                        i += 1;
-                        argument.push(chrs[i]);
-                    }
-                    else {  // must be at end of argument string
-                        argument.push(ch);
-                    }
-                }
-                else if ch == '"' {
-                    argument.push(ch);
-                    open_quote = true;  // first quote
-                    num_quotes += 1;
+                    escaped = true;
                 }
                 else {
                     argument.push(ch);
@@ -169,34 +206,38 @@ pub fn parse_arguments(to_parse: &str) -> Result<Vec<Unifiable>, String> {
                 // Must be between () or []. Just add character.
                 argument.push(ch);
             }
-        } // not open_quote
+        } // not quoted
 
-        i += 1;
+    } // for
 
-    } // while
+    // A backslash at the end of the argument string is kept.
+    if escaped { argument.push('\\'); }
 
     if start < length_chrs {
 
         let s2 = argument.trim();
         match check_quotes(s2, num_quotes) {
             Some(err) => {
-                return Err(err);
+                return Err(err.into());
             },
             None => {},
         }
 
-        let term = make_term(s2, has_digit, has_non_digit, has_period)?;
+        let term = match parse_operations(s2) {
+            Some(result) => { result? },
+            None => { make_term(s2, has_digit, has_non_digit, has_period)? },
+        };
         term_list.push(term);
     }
 
     if round_depth != 0 {
         let err = "parse_arguments() - Unmatched parentheses: ";
-        return Err(err.to_string());
+        return Err(SuironError::ParseError(err.to_string()));
     }
 
     if square_depth != 0 {
         let err = "parse_arguments() - Unmatched brackets: ";
-        return Err(err.to_string());
+        return Err(SuironError::ParseError(err.to_string()));
     }
 
     return Ok(term_list);
@@ -204,6 +245,18 @@ pub fn parse_arguments(to_parse: &str) -> Result<Vec<Unifiable>, String> {
 } // parse_arguments()
 
 
+// Parses a term which contains operators: user-defined operators,
+// or arithmetic infixes.
+//
+// Arguments
+//    string to parse
+// Return
+//    Some(unifiable term or error message), or None if there are no operators
+fn parse_operations(to_parse: &str) -> Option<Result<Unifiable, SuironError>> {
+    if let Some(result) = parse_operators(to_parse) { return Some(result); }
+    return parse_expression(to_parse);
+} // parse_operations()
+
 // make_term()
 // Creates a Unifiable term from the given string.
 //
@@ -217,7 +270,7 @@ pub fn parse_arguments(to_parse: &str) -> Result<Vec<Unifiable>, String> {
 fn make_term(to_parse: &str,
              has_digit: bool,
              has_non_digit: bool,
-             has_period: bool) -> Result<Unifiable, String> {
+             has_period: bool) -> Result<Unifiable, SuironError> {
 
     let s = to_parse.trim();
 
@@ -226,7 +279,7 @@ fn make_term(to_parse: &str,
 
     if length_term == 0 {
         let err = mt_error("Length of term is 0", s);
-        return Err(err);
+        return Err(err.into());
     }
 
     let first: char = term_chars[0];
@@ -243,22 +296,38 @@ fn make_term(to_parse: &str,
         }
     }
 
-    // If the argument begins and ends with a quotation mark,
-    // the argument is an Atom. Strip off quotation marks.
+    // In Prolog syntax, X and _Y are variables.
+    if prolog_syntax() && (first.is_uppercase() || first == '_') &&
+       term_chars.iter().all(|ch| ch.is_alphanumeric() || *ch == '_') {
+        if s == "_" { return Ok(Anonymous); }
+        return Ok(LogicVar{id: 0, name: format!("${}", s)});
+    }
+
+    // If the argument begins and ends with a double quotation mark,
+    // the argument is a string. Strip off quotation marks.
     if length_term >= 2 {
         let last = term_chars[length_term - 1];
         if first == '"' {
             if last == '"' {
                 let chars2: Vec<char> = term_chars[1..length_term - 1].to_vec();
-                if chars2.len() == 0 {
-                    let err = mt_error("Invalid term. Length is 0", s);
-                    return Err(err);
-                }
                 let s2 = chars_to_string!(chars2);
-                return Ok(Atom(s2.to_string()));
+                let s2 = unescape_text(&s2)?;
+                return Ok(SString(s2));
             } else {
                 let err = mt_error("Invalid term. Unmatched quote mark", s);
-                return Err(err)
+                return Err(err.into())
+            }
+        } else if first == '\'' {
+            // Text between single quotes is an atom: 'Hello World'
+            // A doubled quote stands for one quote: 'it''s'
+            if last == '\'' {
+                let chars2 = undouble_quotes(&term_chars[1..length_term - 1]);
+                let s2 = chars_to_string!(chars2);
+                let s2 = unescape_text(&s2)?;
+                return Ok(Atom(s2.into()));
+            } else {
+                let err = mt_error("Invalid term. Unmatched quote mark", s);
+                return Err(err.into())
             }
         } else if first == '[' && last == ']' {
             return parse_linked_list(s);
@@ -271,35 +340,203 @@ fn make_term(to_parse: &str,
             if s.starts_with("subtract(") { return parse_function(s); }
             if s.starts_with("multiply(") { return parse_function(s); }
             if s.starts_with("divide(")   { return parse_function(s); }
+            if s.starts_with("int_divide(") { return parse_function(s); }
+            if s.starts_with("mod(")      { return parse_function(s); }
+            if s.starts_with("rem(")      { return parse_function(s); }
+            if s.starts_with("rdiv(")     { return parse_function(s); }
+            if s.starts_with("pow(")      { return parse_function(s); }
+            if s.starts_with("min(")      { return parse_function(s); }
+            if s.starts_with("max(")      { return parse_function(s); }
+            if s.starts_with("bit_and(")  { return parse_function(s); }
+            if s.starts_with("bit_or(")   { return parse_function(s); }
+            if s.starts_with("xor(")      { return parse_function(s); }
+            if s.starts_with("shift_left(")  { return parse_function(s); }
+            if s.starts_with("shift_right(") { return parse_function(s); }
+            // Math functions, eg.: sqrt($X)
+            if let Some(name) = s.split('(').next() {
+                if MATH_FUNCTIONS.contains(&name) { return parse_function(s); }
+            }
             return parse_complex(s);
         }
     } // length >= 2
 
+    // Character code, eg. 0'a
+    if length_term > 2 && s.starts_with("0'") {
+        return char_code(&term_chars[2..], s);
+    }
+
+    // Underscores may separate digits, eg. 1_000_000
+    if has_digit && s.contains('_') {
+        if let Some(number) = without_separators(s) { return Ok(number); }
+    }
+
+    // Hexadecimal, octal or binary integer, eg. 0xFF, 0o17, 0b1010
+    if has_digit {
+        if let Some(result) = radix_integer(s) { return result; }
+    }
+
+    // Float in scientific notation, eg. 1.5e-3, 2E6
+    if has_digit && has_non_digit {
+        if let Some(fl) = scientific_float(s) { return Ok(SFloat(fl)); }
+    }
+
     if has_digit && !has_non_digit { // Must be Integer or Float.
         if has_period {
             match s.parse::<f64>() {
                 Ok(fl) => { return Ok(SFloat(fl)); },
-                Err(_) => { return Err("Invalid float.".to_string()) },
+                Err(_) => { return Err(SuironError::ParseError("Invalid float.".to_string())) },
             }
         } else {
             match s.parse::<i64>() {
                 Ok(i) => { return Ok(SInteger(i)); },
-                Err(_) => { return Err("Invalid integer".to_string()); },
+                Err(_) => {
+                    // Too big for an SInteger?
+                    #[cfg(feature = "bigint")]
+                    if let Ok(b) = s.parse::<num_bigint::BigInt>() {
+                        return Ok(SBigInt(b));
+                    }
+                    return Err(SuironError::ParseError("Invalid integer".to_string()));
+                },
             }
         }
     }
+    // Rational number, eg. 1r3
+    if has_digit { if let Some(r) = parse_rational(s) { return Ok(r); } }
+
     return Ok(atom!(s));
 
 }  // make_term
 
-/// Checks validity of double quote marks in a string.
+// Gets the code point of a character code literal, such as 0'a.
+// The character may be an escape sequence: \n, \t, \s (space).
+// A backslash before any other character is ignored: 0'\, is 0',
+//
+// Arguments
+//    characters after 0'
+//    literal, for error messages
+// Return
+//    SInteger or error message
+fn char_code(chrs: &[char], literal: &str) -> Result<Unifiable, SuironError> {
+    let ch = match chrs {
+        [ch] => { *ch },
+        ['\\', 'n'] => { '\n' },
+        ['\\', 't'] => { '\t' },
+        ['\\', 's'] => { ' ' },
+        ['\\', ch] => { *ch },
+        _ => {
+            let err = mt_error("Invalid character code", literal);
+            return Err(err.into());
+        },
+    };
+    return Ok(SInteger(ch as i64));
+} // char_code()
+
+// Parses an integer in hexadecimal (0x), octal (0o) or binary (0b)
+// notation. The integer may have a sign: -0xFF
+//
+// Arguments
+//    string to parse
+// Return
+//    Some(SInteger or error message), or None if there is no prefix
+fn radix_integer(s: &str) -> Option<Result<Unifiable, SuironError>> {
+
+    let (sign, unsigned) = match s.strip_prefix('-') {
+        Some(rest) => { ("-", rest) },
+        None => { ("", s.strip_prefix('+').unwrap_or(s)) },
+    };
+
+    let radix = match unsigned.get(0..2) {
+        Some("0x") => 16,
+        Some("0o") => 8,
+        Some("0b") => 2,
+        _ => { return None; },
+    };
+    let digits = &unsigned[2..];
+    if digits.is_empty() { return None; }
+
+    let number = format!("{}{}", sign, digits);
+    if let Ok(i) = i64::from_str_radix(&number, radix) {
+        return Some(Ok(SInteger(i)));
+    }
+    // Too big for an SInteger?
+    #[cfg(feature = "bigint")]
+    if let Some(b) = num_bigint::BigInt::parse_bytes(number.as_bytes(), radix) {
+        return Some(Ok(SBigInt(b)));
+    }
+    let err = mt_error("Invalid integer", s);
+    return Some(Err(err.into()));
+
+} // radix_integer()
+
+// Removes the underscores which separate digits in a number, such as
+// 1_000_000 or 0xFF_FF, and parses the number. An underscore must be
+// between two digits.
+//
+// Arguments
+//    string to parse
+// Return
+//    Some(number), or None if the string is not a number
+fn without_separators(s: &str) -> Option<Unifiable> {
+
+    let chrs = str_to_chars!(s);
+    let unsigned = s.strip_prefix(['-', '+']).unwrap_or(s);
+    if !unsigned.starts_with(|c: char| c.is_ascii_digit()) { return None; }
+
+    for (i, ch) in chrs.iter().enumerate() {
+        if *ch == '_' {
+            let before = i > 0 && chrs[i - 1].is_ascii_hexdigit();
+            let after  = i + 1 < chrs.len() && chrs[i + 1].is_ascii_hexdigit();
+            if !before || !after { return None; }
+        }
+    }
+
+    let number = s.replace('_', "");
+    let has_non_digit = number.chars().enumerate().any(|(i, c)| {
+        !(c.is_ascii_digit() || c == '.' || (i == 0 && (c == '-' || c == '+')))
+    });
+    let has_period = number.contains('.');
+    match make_term(&number, true, has_non_digit, has_period) {
+        Ok(Atom(_)) | Err(_) => { return None; },
+        Ok(term) => { return Some(term); },
+    }
+
+} // without_separators()
+
+// Parses a float in scientific notation, such as 1.5e-3 or 2E6.
+// The mantissa must have digits before (and after) the period.
+//
+// Arguments
+//    string to parse
+// Return
+//    Some(float), or None if the string is not in scientific notation
+fn scientific_float(s: &str) -> Option<f64> {
+
+    let (mantissa, exponent) = s.split_once(['e', 'E'])?;
+
+    let mantissa = mantissa.strip_prefix(['-', '+']).unwrap_or(mantissa);
+    let (whole, fraction) = match mantissa.split_once('.') {
+        Some((w, f)) => { (w, f) },
+        None => { (mantissa, "0") },
+    };
+    let exponent = exponent.strip_prefix(['-', '+']).unwrap_or(exponent);
+
+    let all_digits = |t: &str| !t.is_empty() && t.chars().all(|c| c.is_ascii_digit());
+    if !all_digits(whole) || !all_digits(fraction) || !all_digits(exponent) {
+        return None;
+    }
+    return s.parse::<f64>().ok();
+
+} // scientific_float()
+
+/// Checks validity of quote marks in a string.
 ///
-/// An argument may be enclosed in double quotation marks, eg. `"Sophie"`.
-/// If there are unpaired quotation marks, such as in `""Sophie"`, an error
-/// message will be returned. Otherwise, None is returned.
+/// An argument may be enclosed in double quotation marks, eg. `"Sophie"`,
+/// or in single quotation marks, eg. `'Sophie'`. If there are unpaired
+/// quotation marks, such as in `""Sophie"`, an error message will be
+/// returned. Otherwise, None is returned.
 /// # Arguments
 /// * string to check
-/// * number of double quotes in string (previously counted)
+/// * number of quote marks in string (previously counted)
 /// # Return
 /// * error message or None
 /// # Usage
@@ -319,22 +556,209 @@ pub fn check_quotes(to_check: &str, count: usize) -> Option<String> {
     }
     let chrs = str_to_chars!(to_check);
     let first = chrs[0];
-    if first != '"' {
+    if first != '"' && first != '\'' {
         return Some(cq_error("Text before opening quote", to_check));
     }
     let last = chrs[chrs.len() - 1];
-    if last != '"' {
+    if last != first {
         return Some(cq_error("Text after closing quote", to_check));
     }
     None
 } // check_quotes()
 
+/// Finds the quoted text in a vector of characters.
+///
+/// Double quotes enclose strings, and single quotes enclose atoms.
+/// A single quote which follows a letter or a digit, as in `girl's`,
+/// is an apostrophe, not a quote mark. A backslash escapes the next
+/// character. Within an atom, a doubled quote, as in `'it''s'`, is
+/// also quoted text. The character of a character code, such as `0'(`,
+/// is also treated as quoted text.
+///
+/// # Arguments
+/// * vector of characters
+/// # Return
+/// * vector of flags, one for each character. A flag is true if the
+///   character is a quote mark, or is between quote marks.
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let chrs = str_to_chars!("f('a, b', c)");
+/// let quoted = quoted_text(&chrs);
+/// assert!(quoted[4]);    // the comma in 'a, b'
+/// assert!(!quoted[9]);   // the comma after 'a, b'
+/// ```
+pub fn quoted_text(chrs: &[char]) -> Vec<bool> {
+    return scan_quotes(chrs).0;
+} // quoted_text()
+
+/// Finds the opening and closing quote marks in a vector of characters.
+///
+/// See [quoted_text()](../parse_terms/fn.quoted_text.html).
+///
+/// # Arguments
+/// * vector of characters
+/// # Return
+/// * vector of flags, one for each character. A flag is true if the
+///   character is an opening or closing quote mark.
+pub fn quote_marks(chrs: &[char]) -> Vec<bool> {
+    return scan_quotes(chrs).1;
+} // quote_marks()
+
+// Scans a vector of characters for quoted text and quote marks.
+// See quoted_text().
+//
+// Arguments
+//    vector of characters
+// Return
+//    flags for quoted text, flags for quote marks
+fn scan_quotes(chrs: &[char]) -> (Vec<bool>, Vec<bool>) {
+    return QuoteScanner::new(chrs.iter().copied())
+           .map(|sc| (sc.quoted, sc.mark))
+           .unzip();
+} // scan_quotes()
+
+// A character which was read by a QuoteScanner.
+//
+//    byte   - byte index of the character in the source string
+//    index  - character index
+//    ch     - the character
+//    quoted - true if the character is a quote mark, or between quote marks
+//    mark   - true if the character is an opening or closing quote mark
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ScannedChar {
+    pub byte: usize,
+    pub index: usize,
+    pub ch: char,
+    pub quoted: bool,
+    pub mark: bool,
+}
+
+// Iterates over characters, and finds the quoted text, without copying
+// the characters into a vector. See quoted_text() for the rules.
+//
+// Usage:
+//    for sc in QuoteScanner::new(s.chars()) { if sc.quoted { ... } }
+pub(crate) struct QuoteScanner<I: Iterator<Item = char>> {
+    chars: std::iter::Peekable<I>,
+    byte: usize,
+    index: usize,
+    quote: Option<char>,    // the opening quote mark
+    previous: [char; 2],    // the two previous characters
+    skip: usize,            // number of characters to pass over
+    skip_quoted: bool,      // true if passed over characters are quoted
+}
+
+impl<I: Iterator<Item = char>> QuoteScanner<I> {
+    pub fn new(chars: I) -> Self {
+        QuoteScanner{ chars: chars.peekable(), byte: 0, index: 0, quote: None,
+                      previous: [' ', ' '], skip: 0, skip_quoted: false }
+    }
+} // impl QuoteScanner
+
+impl<I: Iterator<Item = char>> Iterator for QuoteScanner<I> {
+
+    type Item = ScannedChar;
+
+    fn next(&mut self) -> Option<ScannedChar> {
+
+        let ch = self.chars.next()?;
+        let mut sc = ScannedChar{ byte: self.byte, index: self.index, ch,
+                                  quoted: false, mark: false };
+        self.byte += ch.len_utf8();
+        self.index += 1;
+
+        let [prev, prev2] = self.previous;
+        self.previous = [ch, prev];
+
+        if self.skip > 0 {
+            // Escaped character, or the character of a character code.
+            self.skip -= 1;
+            sc.quoted = self.skip_quoted;
+            return Some(sc);
+        }
+
+        match self.quote {
+            Some(q) => {
+                sc.quoted = true;
+                if ch == '\\' { self.skip = 1; self.skip_quoted = true; }
+                else if ch == '\'' && q == '\'' && self.chars.peek() == Some(&'\'') {
+                    // A doubled quote in an atom, eg. 'it''s'
+                    self.skip = 1;
+                    self.skip_quoted = true;
+                }
+                else if ch == q {
+                    sc.mark = true;
+                    self.quote = None;
+                }
+            },
+            None => {
+                if ch == '\\' {  // escaped character
+                    self.skip = 1;
+                    self.skip_quoted = false;
+                }
+                else if ch == '\'' && prev == '0' && !is_word_char(prev2) {
+                    // Character code, eg. 0'a. The character
+                    // may be escaped, eg. 0'\n
+                    self.skip = if self.chars.peek() == Some(&'\\') { 2 } else { 1 };
+                    self.skip_quoted = true;
+                }
+                else if ch == '"' || (ch == '\'' && !is_word_char(prev)) {
+                    sc.quoted = true;
+                    sc.mark = true;
+                    self.quote = Some(ch);
+                }
+            },
+        }
+        return Some(sc);
+
+    } // next()
+
+} // impl Iterator for QuoteScanner
+
+// Replaces doubled single quotes ('') in the text of a quoted atom
+// with one quote. Escaped characters are left for unescape_text().
+// Arguments:
+//   chrs - characters between the quote marks
+// Return:
+//   characters
+fn undouble_quotes(chrs: &[char]) -> Vec<char> {
+    let mut out: Vec<char> = vec![];
+    let mut i = 0;
+    while i < chrs.len() {
+        let ch = chrs[i];
+        out.push(ch);
+        if i + 1 < chrs.len() {
+            if ch == '\\' {  // Keep the escaped character.
+                out.push(chrs[i + 1]);
+                i += 1;
+            }
+            else if ch == '\'' && chrs[i + 1] == '\'' { i += 1; }
+        }
+        i += 1;
+    }
+    return out;
+} // undouble_quotes()
+
+// Determines whether a character is a letter, digit or underscore.
+// A single quote which follows such a character is an apostrophe.
+fn is_word_char(ch: char) -> bool {
+    return ch.is_alphanumeric() || ch == '_';
+} // is_word_char()
+
 /// Parses a string to produce a [Unifiable](../unifiable/enum.Unifiable.html) term.
 ///
 /// parse_term(\"$_\") ➔ [Anonymous](../unifiable/enum.Unifiable.html#variant.Anonymous)<br>
 /// parse_term(\"verb\") ➔ [Atom](../unifiable/enum.Unifiable.html#variant.Atom)<br>
+/// parse_term(\"'to be'\") ➔ [Atom](../unifiable/enum.Unifiable.html#variant.Atom)<br>
+/// parse_term(\"\\\"Hello\\\"\") ➔ [SString](../unifiable/enum.Unifiable.html#variant.SString)<br>
 /// parse_term(\"1.7\") ➔ [SFloat](../unifiable/enum.Unifiable.html#variant.SFloat)<br>
 /// parse_term(\"46\") ➔ [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger)<br>
+/// parse_term(\"1.5e-3\") ➔ [SFloat](../unifiable/enum.Unifiable.html#variant.SFloat) (0.0015)<br>
+/// parse_term(\"1_000_000\") ➔ [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger) (1000000)<br>
+/// parse_term(\"0xFF\") ➔ [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger) (255)<br>
+/// parse_term(\"0'a\") ➔ [SInteger](../unifiable/enum.Unifiable.html#variant.SInteger) (97)<br>
 /// parse_term(\"$X\") ➔ [LogicVar](../unifiable/enum.Unifiable.html#variant.LogicVar)<br>
 /// parse_term(\"animal(horse, mammal)\") ➔
 /// [SComplex](../unifiable/enum.Unifiable.html#variant.SComplex)<br>
@@ -357,7 +781,7 @@ pub fn check_quotes(to_check: &str, count: usize) -> Option<String> {
 /// }
 /// // Should print: animal(horse, mammal)
 /// ```
-pub fn parse_term(to_parse: &str) -> Result<Unifiable, String> {
+pub fn parse_term(to_parse: &str) -> Result<Unifiable, SuironError> {
 
     let mut s = to_parse.trim();
 
@@ -367,33 +791,18 @@ pub fn parse_term(to_parse: &str) -> Result<Unifiable, String> {
 
     let chrs = str_to_chars!(&s);
 
-    // First, let's check for an arithmetic function with an infix,
-    // such as $X + 100 or $X / 100.
-    let (infix, index) = check_arithmetic_infix(&chrs);
-
-    if infix == Infix::Plus || infix == Infix::Minus ||
-       infix == Infix::Multiply || infix == Infix::Divide {
-
-        let (left, right) = get_left_and_right(chrs, index, 1)?;
-
-        let sfunc = match infix {
-            Infix::Plus     => { sfunction!("add", left, right) },
-            Infix::Minus    => { sfunction!("subtract", left, right) },
-            Infix::Multiply => { sfunction!("multiply", left, right) },
-            Infix::Divide   => { sfunction!("divide", left, right) },
-            _ => {
-                let s = format!("parse_term() - Invalid infix {}", infix);
-                return Err(s);
-            },
-        };
-        return Ok(sfunc);
-    }
+    // First, let's check for user-defined operators, such as
+    // john likes mary, and arithmetic expressions, such as
+    // $X + 100 or ($X + 1) * 2.
+    if let Some(result) = parse_operations(s) { return result; }
 
-    for ch in &chrs {
+    for (i, ch) in chrs.iter().enumerate() {
         if *ch >= '0' && *ch <= '9' {
             has_digit = true;
         } else if *ch == '.' {
             has_period = true;
+        } else if i == 0 && (*ch == '-' || *ch == '+') {
+            // A sign in front of a number is part of the number: -7
         } else {
             has_non_digit = true;
         }
@@ -406,6 +815,200 @@ pub fn parse_term(to_parse: &str) -> Result<Unifiable, String> {
 
 }  // parse_term
 
+/// Replaces escape sequences in quoted text with the characters they represent.
+///
+/// The following escape sequences are recognized:
+///
+/// <pre>
+/// \n      new line
+/// \t      tab
+/// \\      backslash
+/// \"      double quote
+/// \'      single quote
+/// \uXXXX  Unicode character, where XXXX is four hexadecimal digits
+/// </pre>
+///
+/// A backslash followed by any other character is left unchanged.
+/// This allows regular expressions, such as `"\w+"`, to be written
+/// without doubling the backslash.
+///
+/// # Arguments
+/// * text to unescape (without enclosing quotes)
+/// # Return
+/// * unescaped text or error message
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let s = unescape_text("Line 1\\nLine 2, \\\"quoted\\\"").unwrap();
+/// println!("{}", s);
+/// // Prints:
+/// // Line 1
+/// // Line 2, "quoted"
+/// ```
+pub fn unescape_text(text: &str) -> Result<String, String> {
+
+    let chrs = str_to_chars!(text);
+    let length = chrs.len();
+    let mut out = String::new();
+
+    let mut i = 0;
+    while i < length {
+        let ch = chrs[i];
+        if ch != '\\' || i + 1 >= length {
+            out.push(ch);
+            i += 1;
+            continue;
+        }
+        let next = chrs[i + 1];
+        match next {
+            'n'  => { out.push('\n'); },
+            't'  => { out.push('\t'); },
+            '\\' => { out.push('\\'); },
+            '"'  => { out.push('"'); },
+            '\'' => { out.push('\''); },
+            'u'  => {
+                if i + 6 > length {
+                    return Err(ue_error("Invalid Unicode escape", text));
+                }
+                let hex = chars_to_string!(&chrs[i + 2..i + 6]);
+                let code = match u32::from_str_radix(&hex, 16) {
+                    Ok(code) => { code },
+                    Err(_) => {
+                        return Err(ue_error("Invalid Unicode escape", text));
+                    },
+                };
+                match char::from_u32(code) {
+                    Some(c) => { out.push(c); },
+                    None => {
+                        return Err(ue_error("Invalid Unicode character", text));
+                    },
+                }
+                i += 6;
+                continue;
+            },
+            _ => {  // Not an escape sequence. Keep the backslash.
+                out.push(ch);
+                out.push(next);
+            },
+        }
+        i += 2;
+    } // while
+
+    return Ok(out);
+
+} // unescape_text()
+
+/// Escapes special characters in text, so that it can be parsed again.
+///
+/// This is the inverse of
+/// [unescape_text()](../parse_terms/fn.unescape_text.html).
+/// Backslashes, double quotes, new lines and tabs are escaped.
+/// Other control characters are written as \uXXXX.
+///
+/// # Arguments
+/// * text to escape
+/// # Return
+/// * escaped text
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let s = escape_text("She said \"Hi\"\n");
+/// println!("{}", s);  // Prints: She said \"Hi\"\n
+/// ```
+pub fn escape_text(text: &str) -> String {
+    return escape_quoted(text, '"');
+} // escape_text()
+
+/// Quotes the text of an atom, if it would not be read back as the
+/// same atom.
+///
+/// Most atoms, such as `Zeno`, `Earl Grey` or `Success #1`, are returned
+/// unchanged. Text is enclosed in single quotes, and special characters
+/// are escaped, only if the parser would read it differently: if it
+/// contains commas, parentheses, brackets, quotes, backslashes, control
+/// characters or the neck operator `:-`, if it begins with $, if it begins
+/// or ends with a space, or if it would be read as a number or some other
+/// term, eg. `42`.
+///
+/// # Arguments
+/// * text of atom
+/// # Return
+/// * text, quoted if necessary
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// println!("{}", quote_atom("Earl Grey"));  // Prints: Earl Grey
+/// println!("{}", quote_atom("it's 42"));  // Prints: 'it\'s 42'
+/// ```
+pub fn quote_atom(text: &str) -> String {
+    if !needs_quotes(text) { return text.to_string(); }
+    return format!("'{}'", escape_quoted(text, '\''));
+} // quote_atom()
+
+// Determines whether the text of an atom must be quoted, in order
+// to be read back as the same atom.
+// Arguments:
+//   text - text of atom
+// Return:
+//   true if quotes are needed
+fn needs_quotes(text: &str) -> bool {
+    let mut chrs = text.chars();
+    match chrs.next() {
+        None => { return true; },
+        Some(first) => {
+            // Plain words are the most common case.
+            if first.is_alphabetic() &&
+               chrs.all(|ch| ch.is_alphanumeric() || ch == '_') { return false; }
+        },
+    }
+    if text.starts_with('$') || text.trim() != text { return true; }
+    let special = |ch: char| {
+        matches!(ch, ',' | '(' | ')' | '[' | ']' | '\'' | '"' | '\\') ||
+        ch.is_control()
+    };
+    if text.chars().any(special) || text.contains(":-") { return true; }
+    // Numbers and arithmetic, such as 1.40 or a + b, are read as other terms.
+    return !matches!(parse_term(text), Ok(Unifiable::Atom(a)) if *a == *text);
+} // needs_quotes()
+
+// Escapes special characters in text which is enclosed in quotes.
+// Arguments:
+//   text - text to escape
+//   quote - quote mark, which must be escaped
+// Return:
+//   escaped text
+fn escape_quoted(text: &str, quote: char) -> String {
+    let mut out = String::new();
+    for ch in text.chars() {
+        match ch {
+            '\\' => { out += "\\\\"; },
+            _ if ch == quote => { out.push('\\'); out.push(ch); },
+            '\n' => { out += "\\n"; },
+            '\t' => { out += "\\t"; },
+            _ => {
+                if ch.is_control() {
+                    out += &format!("\\u{:04x}", ch as u32);
+                }
+                else { out.push(ch); }
+            },
+        }
+    }
+    return out;
+} // escape_quoted()
+
+// Formats an error message for unescape_text().
+// Arguments:
+//   err - error description
+//   bad - string which caused the error
+// Return:
+//   error message (String)
+fn ue_error(err: &str, bad: &str) -> String {
+    format!("unescape_text() - {}: {}", err, bad)
+}
+
 // Formats an error message for make_term().
 // Arguments:
 //   err - error description
@@ -503,6 +1106,16 @@ mod test {
             },
             Err(msg) => { panic!("{}", msg); },
         }
+        match parse_term(" \"verb\" ") {
+            Ok(term) => {
+                if let Unifiable::SString(s) = term {
+                    assert_eq!("verb", s);
+                } else {
+                    panic!("Should create an SString: {}", term)
+                }
+            },
+            Err(msg) => { panic!("{}", msg); },
+        }
         match parse_term(" 1.7 ") {
             Ok(term) => {
                 if matches!(term, Unifiable::SFloat(_)) {
@@ -585,4 +1198,161 @@ mod test {
 
     } // test_parse_arguments()
 
+    // Test escape sequences in quoted text.
+    #[test]
+    fn test_escape_sequences() {
+
+        let s = unescape_text("a\\nb\\tc\\\\d\\\"e\\u00e9\\w").unwrap();
+        assert_eq!("a\nb\tc\\d\"e\u{e9}\\w", s);
+
+        match unescape_text("bad \\u00z1") {
+            Ok(s) => { panic!("Should be an error: {}", s); },
+            Err(err) => {
+                assert_eq!("unescape_text() - Invalid Unicode escape: bad \\u00z1", err);
+            },
+        }
+
+        let s = escape_text("He said \"hi\"\n\tC:\\temp\u{7}");
+        assert_eq!("He said \\\"hi\\\"\\n\\tC:\\\\temp\\u0007", s);
+
+        // Parsing and displaying a string should round-trip.
+        let text = "\"Line 1\\nLine\\t2, \\\"quoted\\\" \\\\ end\"";
+        let args = parse_arguments(&format!("{}, next", text)).unwrap();
+        match &args[0] {
+            SString(s) => { assert_eq!("Line 1\nLine\t2, \"quoted\" \\ end", s); },
+            _ => { panic!("Should create an SString: {}", args[0]); },
+        }
+        assert_eq!(2, args.len());
+        assert_eq!(text, args[0].to_string());
+        assert_eq!(args[0], parse_term(&args[0].to_string()).unwrap());
+
+    } // test_escape_sequences()
+
+    // Test atoms in single quotes.
+    #[test]
+    fn test_single_quotes() {
+
+        assert_eq!(Atom("Hello, World (again)".into()),
+                   parse_term("'Hello, World (again)'").unwrap());
+        assert_eq!(Atom("it's".into()), parse_term("'it\\'s'").unwrap());
+        assert_eq!(Atom("it's".into()), parse_term("'it''s'").unwrap());
+        assert_eq!(Atom("''".into()), parse_term("''''''").unwrap());
+        assert_eq!(Atom("a\\''b".into()), parse_term("'a\\\\''''b'").unwrap());
+        assert_eq!(Atom("42".into()), parse_term("'42'").unwrap());
+
+        let args = parse_arguments("'a, b', \"c, d\", girl's").unwrap();
+        assert_eq!(3, args.len());
+        assert_eq!(Atom("a, b".into()), args[0]);
+        assert_eq!(SString("c, d".to_string()), args[1]);
+        assert_eq!(Atom("girl's".into()), args[2]);
+
+        let list = parse_term("['a]', 'b, c' | $T]").unwrap();
+        assert_eq!("['a]', 'b, c' | $T]", list.to_string());
+
+        let rule = crate::parse_rule("say('don''t') :- print('a''s', 'b').").unwrap();
+        assert_eq!("say('don\\'t') :- print('a\\'s', b).", rule.to_string());
+
+        let rule = crate::parse_rule("say('x :- y') :- print('a; b (c').").unwrap();
+        assert_eq!("say('x :- y') :- print('a; b (c').", rule.to_string());
+
+        // Atoms which are written out can be read back.
+        for text in ["42", "a, b", "x(y)", "a\nb", "it's", "back\\slash", "", "Zeno",
+                     "x :- y", "a + b", " a", "$X", "Earl Grey", "Success #1"] {
+            let atom = Atom(text.into());
+            assert_eq!(atom, parse_term(&atom.to_string()).unwrap());
+        }
+        // Quotes are used only when they are needed.
+        assert_eq!("Earl Grey", Atom("Earl Grey".into()).to_string());
+        assert_eq!("'1.40'", Atom("1.40".into()).to_string());
+
+        assert!(parse_term("'unclosed").is_err());
+        assert!(parse_arguments("'a' b, c").is_err());
+
+    } // test_single_quotes()
+
+    // Test character codes, eg. 0'a
+    #[test]
+    fn test_char_codes() {
+        assert_eq!(SInteger(97), parse_term("0'a").unwrap());
+        assert_eq!(SInteger(233), parse_term("0'é").unwrap());
+        assert_eq!(SInteger(10), parse_term("0'\\n").unwrap());
+        assert_eq!(SInteger(32), parse_term("0'\\s").unwrap());
+        assert_eq!(SInteger(39), parse_term("0'\\'").unwrap());
+
+        let args = parse_arguments("0'x, 0',, 0'\\,, 0'(").unwrap();
+        assert_eq!(vec![SInteger(120), SInteger(44), SInteger(44), SInteger(40)], args);
+
+        assert!(parse_term("0'ab").is_err());
+    } // test_char_codes()
+
+    // Test hexadecimal, octal and binary integers.
+    #[test]
+    fn test_radix_integers() {
+        assert_eq!(SInteger(255), parse_term("0xFF").unwrap());
+        assert_eq!(SInteger(-255), parse_term("-0xff").unwrap());
+        assert_eq!(SInteger(15), parse_term("0o17").unwrap());
+        assert_eq!(SInteger(10), parse_term("0b1010").unwrap());
+
+        let args = parse_arguments("0x1F, 0b11, 0x").unwrap();
+        assert_eq!(vec![SInteger(31), SInteger(3), Atom("0x".into())], args);
+
+        assert_eq!("make_term() - Invalid integer: 0b102",
+                   parse_term("0b102").unwrap_err());
+    } // test_radix_integers()
+
+    // Test floats in scientific notation.
+    #[test]
+    fn test_scientific_floats() {
+        assert_eq!(SFloat(0.0015), parse_term("1.5e-3").unwrap());
+        assert_eq!(SFloat(2000000.0), parse_term("2E6").unwrap());
+        assert_eq!(SFloat(-250.0), parse_term("-2.5e+2").unwrap());
+
+        let args = parse_arguments("1.5e-3, 6.02e23, e5").unwrap();
+        assert_eq!(vec![SFloat(0.0015), SFloat(6.02e23), Atom("e5".into())], args);
+
+        // Not floats.
+        assert_eq!(Atom("1e".into()), parse_term("1e").unwrap());
+        assert_eq!(Atom("1.e5".into()), parse_term("1.e5").unwrap());
+        assert_eq!(Atom("1e5x".into()), parse_term("1e5x").unwrap());
+    } // test_scientific_floats()
+
+    // Test underscores which separate digits.
+    #[test]
+    fn test_digit_separators() {
+        assert_eq!(SInteger(1000000), parse_term("1_000_000").unwrap());
+        assert_eq!(SInteger(-1000), parse_term("-1_000").unwrap());
+        assert_eq!(SFloat(3.14159), parse_term("3.141_59").unwrap());
+        assert_eq!(SInteger(65535), parse_term("0xFF_FF").unwrap());
+        assert_eq!(SFloat(1.5e6), parse_term("1_500e3").unwrap());
+
+        let args = parse_arguments("1_000, 2_000.5").unwrap();
+        assert_eq!(vec![SInteger(1000), SFloat(2000.5)], args);
+
+        // Not numbers.
+        assert_eq!(Atom("1_a".into()), parse_term("1_a").unwrap());
+        assert_eq!(Atom("1__000".into()), parse_term("1__000").unwrap());
+        assert_eq!(Atom("1000_".into()), parse_term("1000_").unwrap());
+        assert_eq!(Atom("a_1".into()), parse_term("a_1").unwrap());
+    } // test_digit_separators()
+
+    // Test Prolog syntax, where capitalized words are variables.
+    #[test]
+    fn test_prolog_syntax() {
+        set_prolog_syntax(true);
+        let args = parse_arguments("X, _Y, _, 'Godwin', godwin, $Z, Ab_1").unwrap();
+        set_prolog_syntax(false);
+        let args_str: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        assert_eq!("$X, $_Y, $_, Godwin, godwin, $Z, $Ab_1", args_str.join(", "));
+        assert_eq!(Anonymous, args[2]);
+        assert_eq!(Atom("Godwin".into()), args[3]);
+
+        set_prolog_syntax(true);
+        let list = parse_term("[H | T]").unwrap();
+        set_prolog_syntax(false);
+        assert_eq!("[$H | $T]", list.to_string());
+
+        let args = parse_arguments("X, _Y").unwrap();
+        assert_eq!(vec![Atom("X".into()), Atom("_Y".into())], args);
+    } // test_prolog_syntax()
+
 } // test
@@ -0,0 +1,418 @@
+//! Proof trees, which show how the solutions of a query were derived.
+//!
+//! [solve_with_proofs()](fn.solve_with_proofs.html) finds the solutions of
+//! a query, and for each solution, the tree of goals which proves it. Each
+//! goal of the tree is shown with the terms which its variables were bound
+//! to. A goal which was proven by a fact or rule records the fact or rule,
+//! and the terms which were bound to its variables when it was unified with
+//! the goal. The goals of the rule's body are the children of the goal.
+//!
+//! For example, the proof of grandfather(Alfred, Aethelstan) is:
+//! <pre>
+//! grandfather(Alfred, Aethelstan)  (grandfather/2, clause 0)
+//!     father(Alfred, Edward)  (father/2, clause 0)
+//!     father(Edward, Aethelstan)  (father/2, clause 1)
+//! </pre>
+//!
+//! Proof trees explain why an answer appears, for applications which must
+//! justify their conclusions, and for debugging.
+//!
+//! While proofs are captured, the query is searched depth first, with
+//! substitution sets, regardless of the search and binding strategies,
+//! and solution nodes are not reused for last calls. Thus, a deeply
+//! recursive query uses more memory than usual. The answers of a
+//! [tabled](../tabling/index.html) predicate are taken from its table,
+//! so their proofs are not shown.
+//!
+// Cleve Lendon 2023
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+
+use crate::*;
+
+use super::goal::{Goal, make_depth_first_node};
+use super::logic_var::*;
+use super::operator::Operator;
+use super::rule::Rule;
+use super::solutions::{S_TIMEOUT, check_predicate, find_solutions};
+use super::substitution_set::SubstitutionSet;
+use super::unifiable::Unifiable;
+
+// Indentation of each level of a proof tree, for display.
+const INDENT: &str = "    ";
+
+thread_local! {
+    // True while proofs are being captured.
+    static CAPTURING: Cell<bool> = const { Cell::new(false) };
+}
+
+// Determines whether proofs are being captured. If so, solution
+// nodes must not be reused for last calls.
+pub(crate) fn capturing_proofs() -> bool {
+    CAPTURING.with(|c| c.get())
+}
+
+/// Explains how a goal of a proof tree was proven.
+#[derive(Debug, Clone)]
+pub enum Justification {
+    /// A fact or rule of the knowledge base.
+    Clause {
+        /// Predicate name, eg. \"father/2\"
+        key: String,
+        /// Index of the fact or rule in its predicate.
+        index: usize,
+        /// The fact or rule, as it is in the knowledge base.
+        clause: Rule,
+        /// Terms bound to the variables of the fact or rule.
+        bindings: Solution,
+    },
+    /// A fact or rule of an [external predicate](../external/index.html).
+    External {
+        /// Predicate name
+        key: String,
+        /// The fact or rule, as it was fetched.
+        clause: Rule,
+        /// Terms bound to the variables of the fact or rule.
+        bindings: Solution,
+    },
+    /// An answer of a [tabled](../tabling/index.html) predicate.
+    Tabled {
+        /// Predicate name
+        key: String,
+    },
+    /// A built-in predicate, such as append() or $X > 3.
+    BuiltIn,
+    /// A not() goal, which succeeded because its goal could not be proven.
+    Negation,
+}
+
+/// A goal of a proof tree, with the proofs of its subgoals.
+#[derive(Debug, Clone)]
+pub struct ProofNode {
+    /// The goal, with its variables replaced by the terms they were bound to.
+    pub goal: Goal,
+    /// How the goal was proven.
+    pub justification: Justification,
+    /// Proofs of the goals of the rule's body, in order.
+    pub children: Vec<ProofNode>,
+}
+
+/// A solution of a query, with its proof.
+#[derive(Debug, Clone)]
+pub struct Proof {
+    /// Terms bound to the variables of the query.
+    pub solution: Solution,
+    /// Proofs of the goals of the query.
+    pub goals: Vec<ProofNode>,
+}
+
+/// Finds the solutions of a query, with the proof of each solution.
+///
+/// See the [module documentation](index.html).
+///
+/// Errors are reported as in [try_solve()](../solutions/fn.try_solve.html).
+///
+/// # Arguments
+/// * query (goal)
+/// * knowledge base
+/// * `max` - maximum number of solutions, or None
+/// # Return
+/// * vector of [Proofs](struct.Proof.html) (possibly empty), or error
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let query = parse_query("grandfather(Alfred, $Y)").unwrap();
+///
+/// for proof in solve_with_proofs(&query, &kb, None).unwrap() {
+///     println!("{}", proof.solution);  // Prints: $Y = Aethelstan
+///     let grandfather = &proof.goals[0];
+///     if let Justification::Clause{clause, bindings, ..} = &grandfather.justification {
+///         println!("{}", clause);    // Prints: grandfather($X, $Y) :- father($X, $Z), father($Z, $Y).
+///         println!("{}", bindings);  // Prints: $X = Alfred, $Y = Aethelstan, $Z = Edward
+///     }
+///     print!("{}", proof);
+/// }
+/// ```
+pub fn solve_with_proofs(query: &Goal, kb: &KnowledgeBase, max: Option<usize>)
+                         -> Result<Vec<Proof>, SuironError> {
+
+    let previous = CAPTURING.with(|c| c.replace(true));
+    let sn = make_depth_first_node(Rc::new(query.clone()), kb);
+    let base = Rc::clone(&sn);
+
+    let result = check_predicate("solve_with_proofs", &sn).and_then(|_| {
+        find_solutions(sn, 0, max, S_TIMEOUT, |query, ss| {
+            let mut goals: Vec<ProofNode> = vec![];
+            collect_proofs(&base, ss, &mut goals);
+            Proof{ solution: Solution::new(query, ss), goals }
+        })
+    });
+
+    CAPTURING.with(|c| c.set(previous));
+    let (proofs, _) = result?;
+    return Ok(proofs);
+
+} // solve_with_proofs()
+
+// Collects the proofs of the goals of a solution node, from the state
+// of the node and its children after a solution has been found.
+//
+// Arguments
+//    solution node
+//    substitution set of the solution
+//    proofs
+fn collect_proofs(sn: &Rc<RefCell<SolutionNode>>, ss: &SubstitutionSet,
+                  proofs: &mut Vec<ProofNode>) {
+
+    let node = sn.borrow();
+
+    match &*node.goal {
+        Goal::OperatorGoal(Operator::And(_)) => {
+            // The tail node is made for the solution of the head node.
+            if let Some(head_sn) = &node.head_sn { collect_proofs(head_sn, ss, proofs); }
+            if let Some(tail_sn) = &node.tail_sn { collect_proofs(tail_sn, ss, proofs); }
+        },
+        Goal::OperatorGoal(Operator::Or(_)) => {
+            // After the head node fails, solutions come from the tail node.
+            let branch = node.tail_sn.as_ref().or(node.head_sn.as_ref());
+            if let Some(branch) = branch { collect_proofs(branch, ss, proofs); }
+        },
+        Goal::OperatorGoal(Operator::Time(_)) => {
+            if let Some(head_sn) = &node.head_sn { collect_proofs(head_sn, ss, proofs); }
+        },
+        Goal::OperatorGoal(Operator::Not(_)) => {
+            proofs.push(leaf(&node.goal, ss, Justification::Negation));
+        },
+        Goal::BuiltInGoal(_) => {
+            proofs.push(leaf(&node.goal, ss, Justification::BuiltIn));
+        },
+        Goal::ComplexGoal(term) => {
+            let key = term.key().unwrap_or_default();
+            let (goal, justification) = justify(&node, term, &key, ss);
+            let mut children: Vec<ProofNode> = vec![];
+            if let Some(child) = &node.child { collect_proofs(child, ss, &mut children); }
+            proofs.push(ProofNode{ goal, justification, children });
+        },
+        Goal::Nil => {},
+    }
+
+} // collect_proofs()
+
+// Makes a proof node which has no children.
+fn leaf(goal: &Goal, ss: &SubstitutionSet, justification: Justification) -> ProofNode {
+    return ProofNode{ goal: instantiate(goal, ss), justification, children: vec![] };
+}
+
+// Determines which fact or rule proved a complex goal. The variables of
+// the fact or rule are numbered again, exactly as they were when it was
+// unified with the goal, so that their terms can be found.
+//
+// The goal is taken from the head of the fact or rule, because the head
+// also shows the terms which matched the anonymous variables of the goal.
+//
+// Arguments
+//    solution node of the goal
+//    goal (complex term)
+//    predicate name
+//    substitution set of the solution
+// Return
+//    goal, with its variables replaced
+//    Justification
+fn justify(node: &SolutionNode, term: &Unifiable, key: &str,
+           ss: &SubstitutionSet) -> (Goal, Justification) {
+
+    if node.kb.is_tabled(key) {
+        let goal = Goal::ComplexGoal(instantiate_term(term, ss));
+        return (goal, Justification::Tabled{ key: key.to_string() });
+    }
+
+    // The rule index was incremented after the fact or rule was fetched.
+    let position = node.rule_index.saturating_sub(1);
+    let index = match &node.candidates {
+        Some(candidates) => { candidates[position] },
+        None => { position },
+    };
+    let clause = match &node.external_rules {
+        Some(rules) => { rules[index].clone() },
+        None => { node.kb.rules(key).nth(index).cloned().unwrap_or(make_fact(atom!("?"))) },
+    };
+
+    let ids = VarIds::starting_at(node.rule_var_base);
+    let renamed = clause.clone().recreate_variables(&mut VarMap::with_ids(&ids));
+    let goal = Goal::ComplexGoal(instantiate_term(&renamed.head, ss));
+    let vars = Goal::OperatorGoal(Operator::And(vec![Goal::ComplexGoal(renamed.head),
+                                                     renamed.body]));
+    let bindings = Solution::new(&vars, ss);
+
+    let key = key.to_string();
+    if node.external_rules.is_some() {
+        return (goal, Justification::External{ key, clause, bindings });
+    }
+    return (goal, Justification::Clause{ key, index, clause, bindings });
+
+} // justify()
+
+// Replaces the variables of a goal with the terms which they are bound to.
+fn instantiate(goal: &Goal, ss: &SubstitutionSet) -> Goal {
+    let goals = |goals: &Vec<Goal>| -> Vec<Goal> {
+        goals.iter().map(|g| instantiate(g, ss)).collect()
+    };
+    match goal {
+        Goal::OperatorGoal(op) => {
+            let op = match op {
+                Operator::And(g) => { Operator::And(goals(g)) },
+                Operator::Or(g) => { Operator::Or(goals(g)) },
+                Operator::Time(g) => { Operator::Time(goals(g)) },
+                Operator::Not(g) => { Operator::Not(goals(g)) },
+            };
+            return Goal::OperatorGoal(op);
+        },
+        Goal::ComplexGoal(term) => {
+            return Goal::ComplexGoal(instantiate_term(term, ss));
+        },
+        Goal::BuiltInGoal(bip) => {
+            let terms = bip.terms.as_ref().map(|terms| {
+                terms.iter().map(|t| instantiate_term(t, ss)).collect()
+            });
+            return Goal::BuiltInGoal(BuiltInPredicate::new(bip.functor.clone(), terms));
+        },
+        Goal::Nil => { return Goal::Nil; },
+    }
+} // instantiate()
+
+// Replaces the variables of a term with the terms which they are bound
+// to. Unlike replace_variables(), this function accepts functions, such
+// as $N - 1, which occur in the arguments of built-in predicates.
+fn instantiate_term(term: &Unifiable, ss: &SubstitutionSet) -> Unifiable {
+    match term {
+        Unifiable::SFunction{name, terms} => {
+            let terms = terms.iter().map(|t| instantiate_term(t, ss)).collect();
+            return Unifiable::SFunction{ name: name.to_string(), terms };
+        },
+        Unifiable::SComplex(terms) => {
+            return Unifiable::SComplex(terms.iter().map(|t| instantiate_term(t, ss)).collect());
+        },
+        _ => { return term.replace_variables(ss); },
+    }
+} // instantiate_term()
+
+// Writes a proof node and its children, indented by depth.
+fn write_node(f: &mut fmt::Formatter, node: &ProofNode, depth: usize) -> fmt::Result {
+    let how = match &node.justification {
+        Justification::Clause{key, index, ..} => { format!("{}, clause {}", key, index) },
+        Justification::External{key, ..} => { format!("{}, external", key) },
+        Justification::Tabled{key} => { format!("{}, tabled", key) },
+        Justification::BuiltIn => { "built-in".to_string() },
+        Justification::Negation => { "not provable".to_string() },
+    };
+    writeln!(f, "{}{}  ({})", INDENT.repeat(depth), node.goal, how)?;
+    for child in &node.children { write_node(f, child, depth + 1)?; }
+    Ok(())
+} // write_node()
+
+// Display trait, to display a proof tree, one goal per line.
+impl fmt::Display for ProofNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_node(f, self, 0)
+    }
+} // fmt::Display
+
+// Display trait, to display the proofs of the goals of a query.
+impl fmt::Display for Proof {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for goal in &self.goals { write_node(f, goal, 0)?; }
+        Ok(())
+    }
+} // fmt::Display
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::*;
+
+    fn proof_strings(kb: &KnowledgeBase, query: &str) -> Vec<String> {
+        let query = parse_query(query).unwrap();
+        let proofs = solve_with_proofs(&query, kb, None).unwrap();
+        return proofs.iter().map(|p| p.to_string()).collect();
+    }
+
+    #[test]
+    fn test_solve_with_proofs() {
+
+        let mut kb = test_kb();
+        for rule in ["countdown(0).",
+                     "countdown($N) :- $N > 0, $M = $N - 1, countdown($M).",
+                     "lonely($X) :- loves($X, $_), not(loves($_, $X)).",
+                     "linked($X, $Y) :- father($X, $Y) ; loves($X, $Y).",
+                     "loves(Raj, Penny).",
+                     "first($X) :- father($X, $_), !."] {
+            add_rules!(&mut kb, parse_rule(rule).unwrap());
+        }
+
+        let query = parse_query("grandfather($X, $Y)").unwrap();
+        let proofs = solve_with_proofs(&query, &kb, None).unwrap();
+        assert_eq!(1, proofs.len());
+        assert_eq!("$X = Alfred, $Y = Aethelstan", proofs[0].solution.to_string());
+        let grandfather = "grandfather(Alfred, Aethelstan)  (grandfather/2, clause 0)\n    \
+                           father(Alfred, Edward)  (father/2, clause 0)\n    \
+                           father(Edward, Aethelstan)  (father/2, clause 1)\n";
+        assert_eq!(grandfather, proofs[0].to_string());
+
+        match &proofs[0].goals[0].justification {
+            Justification::Clause{key, index, clause, bindings} => {
+                assert_eq!("grandfather/2", key);
+                assert_eq!(0, *index);
+                assert_eq!("grandfather($X, $Y) :- father($X, $Z), father($Z, $Y).",
+                           clause.to_string());
+                assert_eq!("$X = Alfred, $Y = Aethelstan, $Z = Edward", bindings.to_string());
+            },
+            j => { panic!("Unexpected justification: {:?}", j); },
+        }
+
+        // Recursive rules, and built-in predicates.
+        let results = proof_strings(&kb, "countdown(2)");
+        assert_eq!(1, results.len());
+        let expected = "countdown(2)  (countdown/1, clause 1)\n    \
+                        greater_than(2, 0)  (built-in)\n    \
+                        1 = subtract(2, 1)  (built-in)\n    \
+                        countdown(1)  (countdown/1, clause 1)\n        \
+                        greater_than(1, 0)  (built-in)\n        \
+                        0 = subtract(1, 1)  (built-in)\n        \
+                        countdown(0)  (countdown/1, clause 0)\n";
+        assert_eq!(expected, results[0]);
+
+        // Or, not() and cut.
+        let results = proof_strings(&kb, "lonely($X)");
+        assert_eq!(vec!["lonely(Raj)  (lonely/1, clause 0)\n    \
+                         loves(Raj, Penny)  (loves/2, clause 2)\n    \
+                         not(loves($_, Raj))  (not provable)\n"], results);
+        let results = proof_strings(&kb, "linked($X, $Y)");
+        assert_eq!(5, results.len());
+        assert_eq!("linked(Edward, Aethelstan)  (linked/2, clause 0)\n    \
+                    father(Edward, Aethelstan)  (father/2, clause 1)\n", results[1]);
+        assert_eq!("linked(Leonard, Penny)  (linked/2, clause 0)\n    \
+                    loves(Leonard, Penny)  (loves/2, clause 0)\n", results[2]);
+        let results = proof_strings(&kb, "first($X)");
+        assert_eq!(vec!["first(Alfred)  (first/1, clause 0)\n    \
+                         father(Alfred, Edward)  (father/2, clause 0)\n    \
+                         !  (built-in)\n"], results);
+
+        // Clause indices are found through the index of the knowledge base.
+        kb.set_indexing(true);
+        let results = proof_strings(&kb, "grandfather($X, Aethelstan)");
+        assert_eq!(vec![grandfather], results);
+        let results = proof_strings(&kb, "loves($X, Penny)");
+        assert_eq!("loves(Raj, Penny)  (loves/2, clause 2)\n", results[1]);
+
+        let query = parse_query("unknown($X)").unwrap();
+        let result = solve_with_proofs(&query, &kb, None);
+        assert!(matches!(result, Err(SuironError::UnknownPredicate(_))));
+
+    } // test_solve_with_proofs()
+
+} // test
@@ -0,0 +1,302 @@
+//! A builder for queries.
+//!
+//! Queries which are constructed at runtime, for example from user
+//! input, can be built from terms, rather than by formatting a string
+//! for [parse_query()](../s_complex/fn.parse_query.html). This is faster,
+//! and avoids problems with text which contains commas, parentheses or
+//! other special characters.
+//!
+//! <pre>
+//!   let query = Query::predicate("loves").arg("Leonard").var("Whom").build();
+//! </pre>
+//!
+//! Arguments are converted to terms as described in the
+//! [facts](../facts/index.html) module.
+//!
+//! A query which is run many times with different arguments can be
+//! parsed once, as a [PreparedQuery](struct.PreparedQuery.html). Each
+//! question mark in the query is a placeholder for an argument:
+//!
+//! <pre>
+//!   let prepared = PreparedQuery::new("loves(?, $Whom)")?;
+//!   let query = prepared.bind(("Leonard",))?;
+//! </pre>
+//
+// Cleve Lendon 2023
+
+use std::rc::Rc;
+
+use super::error::SuironError;
+use super::facts::FactArguments;
+use super::goal::Goal;
+use super::s_complex::{make_query, parse_query};
+use super::unifiable::Unifiable;
+
+use crate::atom;
+
+/// Constructs a query, one argument at a time.
+///
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let query = Query::predicate("loves").arg("Leonard").var("Whom").build();
+/// println!("{}", query);  // Prints: loves(Leonard, $Whom_1)
+///
+/// let sn = make_base_node(Rc::new(query), &kb);
+/// println!("{}", solve(sn));  // Prints: $Whom = Penny
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    functor: String,
+    args: Vec<Unifiable>,
+}
+
+impl Query {
+
+    /// Starts a query for the given predicate.
+    ///
+    /// # Arguments
+    /// * functor, eg. \"loves\"
+    /// # Return
+    /// * query builder
+    pub fn predicate(functor: &str) -> Self {
+        Query{ functor: functor.to_string(), args: vec![] }
+    }
+
+    /// Adds one argument to the query.
+    pub fn arg(mut self, arg: impl Into<Unifiable>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Adds several arguments to the query.
+    pub fn args(mut self, args: impl FactArguments) -> Self {
+        self.args.extend(args.into_arguments());
+        self
+    }
+
+    /// Adds a logic variable to the query. The dollar sign is optional,
+    /// so \"Whom\" and \"$Whom\" are the same variable.
+    pub fn var(mut self, name: &str) -> Self {
+        let name = if name.starts_with('$') { name.to_string() }
+                   else { format!("${}", name) };
+        self.args.push(Unifiable::LogicVar{ id: 0, name });
+        self
+    }
+
+    /// Adds the anonymous variable, $_, to the query.
+    pub fn anon(mut self) -> Self {
+        self.args.push(Unifiable::Anonymous);
+        self
+    }
+
+    /// Builds the query. As with
+    /// [make_query()](../s_complex/fn.make_query.html), the logic
+    /// variables are given unique IDs.
+    ///
+    /// # Return
+    /// * query ([ComplexGoal](../goal/enum.Goal.html#variant.ComplexGoal))
+    pub fn build(self) -> Goal {
+        let mut terms = Vec::with_capacity(self.args.len() + 1);
+        terms.push(atom!(self.functor.as_str()));
+        terms.extend(self.args);
+        // The first term is an atom, so make_query() does not fail.
+        match make_query(terms) {
+            Ok(query) => { query },
+            Err(_) => { Goal::Nil },
+        }
+    } // build()
+
+} // impl Query
+
+// A placeholder in a prepared query.
+const PLACEHOLDER: &str = "?";
+
+/// A query which is parsed once, and run with different arguments.
+///
+/// The logic variables of a prepared query are given their IDs when
+/// it is parsed, so binding arguments is much faster than parsing.
+///
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let prepared = PreparedQuery::new("loves(?, $Whom)").unwrap();
+/// for who in ["Leonard", "Penny"] {
+///     let query = prepared.bind((who,)).unwrap();
+///     let sn = make_base_node(Rc::new(query), &kb);
+///     println!("{} loves {}.", who, solve(sn));
+/// }
+/// // Prints:
+/// // Leonard loves $Whom = Penny.
+/// // Penny loves $Whom = Leonard.
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreparedQuery {
+    template: Unifiable,
+    placeholders: usize,
+}
+
+impl PreparedQuery {
+
+    /// Parses a query template. Each question mark (?) is a placeholder.
+    ///
+    /// # Arguments
+    /// * query template, eg. \"loves(?, $Whom)\"
+    /// # Return
+    /// * prepared query, or ParseError
+    pub fn new(template: &str) -> Result<Self, SuironError> {
+        let template = match parse_query(template)? {
+            Goal::ComplexGoal(term) => { term },
+            goal => {
+                let err = format!("PreparedQuery::new() - Invalid query: {}", goal);
+                return Err(SuironError::ParseError(err));
+            },
+        };
+        let placeholders = count_placeholders(&template);
+        return Ok(PreparedQuery{ template, placeholders });
+    } // new()
+
+    /// Gets the number of placeholders.
+    pub fn placeholders(&self) -> usize { self.placeholders }
+
+    /// Makes a query by replacing the placeholders with arguments,
+    /// in order.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * arguments, eg. (\"Leonard\",)
+    /// # Return
+    /// * query, or ParseError if the number of arguments is wrong
+    pub fn bind(&self, args: impl FactArguments) -> Result<Goal, SuironError> {
+        let args = args.into_arguments();
+        if args.len() != self.placeholders {
+            let err = format!("PreparedQuery::bind() - Expected {} arguments, got {}.",
+                              self.placeholders, args.len());
+            return Err(SuironError::ParseError(err));
+        }
+        let mut args = args.into_iter();
+        return Ok(Goal::ComplexGoal(fill_placeholders(&self.template, &mut args)));
+    } // bind()
+
+} // impl PreparedQuery
+
+// Counts the placeholders in a term.
+fn count_placeholders(term: &Unifiable) -> usize {
+    match term {
+        Unifiable::Atom(s) if s.as_str() == PLACEHOLDER => { 1 },
+        Unifiable::SComplex(terms) |
+        Unifiable::SFunction{name: _, terms} => {
+            terms.iter().map(count_placeholders).sum()
+        },
+        Unifiable::SLinkedList{term, next, count: _, tail_var: _} => {
+            count_placeholders(term) + count_placeholders(next)
+        },
+        _ => { 0 },
+    }
+} // count_placeholders()
+
+// Copies a term, replacing its placeholders with arguments.
+//
+// Arguments
+//    term
+//    arguments
+// Return
+//    new term
+fn fill_placeholders(term: &Unifiable,
+                     args: &mut impl Iterator<Item = Unifiable>) -> Unifiable {
+    match term {
+        Unifiable::Atom(s) if s.as_str() == PLACEHOLDER => {
+            args.next().unwrap_or(Unifiable::Anonymous)
+        },
+        Unifiable::SComplex(terms) => {
+            Unifiable::SComplex(terms.iter().map(|t| fill_placeholders(t, args)).collect())
+        },
+        Unifiable::SFunction{name, terms} => {
+            let terms = terms.iter().map(|t| fill_placeholders(t, args)).collect();
+            Unifiable::SFunction{ name: name.to_string(), terms }
+        },
+        Unifiable::SLinkedList{term, next, count, tail_var} => {
+            Unifiable::SLinkedList{ term: Rc::new(fill_placeholders(term, args)),
+                                    next: Rc::new(fill_placeholders(next, args)),
+                                    count: *count, tail_var: *tail_var }
+        },
+        _ => { term.clone() },
+    }
+} // fill_placeholders()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    #[test]
+    fn test_query_builder() {
+
+        let kb = test_kb();
+        let query = Query::predicate("loves").arg("Leonard").var("Whom").build();
+        assert_eq!(parse_query("loves(Leonard, $Whom)").unwrap(), query);
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$Whom = Penny", solve(sn));
+
+        // The same variable twice, and the anonymous variable.
+        let query = Query::predicate("test").var("$X").anon().var("X").build();
+        assert_eq!(parse_query("test($X, $_, $X)").unwrap(), query);
+
+        // Text which would have to be escaped in a query string.
+        let mut kb = KnowledgeBase::new();
+        kb.fact("says").args(("June", "Hello, (world)")).add();
+        let query = Query::predicate("says").args(("June", Unifiable::Anonymous)).build();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!(1, try_solve_all(sn, None).unwrap().len());
+        let query = Query::predicate("says").var("Who").arg("Hello, (world)").build();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$Who = June", solve(sn));
+
+        let query = Query::predicate("list").arg(vec![1, 2]).arg(2.5).build();
+        assert_eq!("list([1, 2], 2.5)", query.to_string());
+
+    } // test_query_builder()
+
+    #[test]
+    fn test_prepared_query() {
+
+        let kb = test_kb();
+        let prepared = PreparedQuery::new("loves(?, $Whom)").unwrap();
+        assert_eq!(1, prepared.placeholders());
+        let query = prepared.bind(("Leonard",)).unwrap();
+        assert_eq!(parse_query("loves(Leonard, $Whom)").unwrap(), query);
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$Whom = Penny", solve(sn));
+        let query = prepared.bind(["Penny"]).unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$Whom = Leonard", solve(sn));
+
+        // Placeholders in lists and complex terms.
+        let mut kb = KnowledgeBase::new();
+        add_rules!(&mut kb, parse_rule("pair([$A, $B], g($A), $B).").unwrap());
+        let prepared = PreparedQuery::new("pair([?, $Y], g(?), $Z)").unwrap();
+        assert_eq!(2, prepared.placeholders());
+        for i in 1..4 {
+            let query = prepared.bind((i, i)).unwrap();
+            let sn = make_base_node(Rc::new(query), &kb);
+            let solution = solve_bindings(sn).unwrap().unwrap();
+            assert_eq!(solution.get("$Y"), solution.get("$Z"));
+        }
+        let query = prepared.bind((1, 2)).unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!(Ok(None), solve_bindings(sn));
+
+        assert!(prepared.bind((1,)).is_err());
+        assert!(PreparedQuery::new("loves(?, $Whom").is_err());
+
+    } // test_prepared_query()
+
+} // test
@@ -0,0 +1,223 @@
+//! Settings which belong to a query, rather than to a thread.
+//!
+//! A [QueryConfig](struct.QueryConfig.html) holds the maximum proof depth,
+//! the maximum number of solutions, the float division setting, and the
+//! seed of the random number generator. An [Engine](../engine/struct.Engine.html)
+//! gives its configuration to each of its queries. (See
+//! [with_config()](../engine/struct.Engine.html#method.with_config).)
+//! A single query can be given a configuration with
+//! [set_query_config()](fn.set_query_config.html).
+//!
+//! While a query which has a configuration is being solved, its settings
+//! are in effect on the thread which solves it. When the search stops,
+//! the thread's own settings are restored. Thus, two engines which are
+//! queried on the same thread do not share their settings.
+//!
+//! A query which has no configuration uses the settings of the thread,
+//! which are set by [set_max_depth()](../solution_node/fn.set_max_depth.html),
+//! [set_max_solutions()](../solutions/fn.set_max_solutions.html),
+//! [set_float_division()](../built_in_arithmetic/fn.set_float_division.html)
+//! and [set_random_seed()](../built_in_random/fn.set_random_seed.html).
+//
+// Cleve Lendon 2023
+
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use super::solution_node::*;
+use super::solutions::*;
+use super::built_in_arithmetic::*;
+use super::built_in_random::*;
+
+/// The settings of a query.
+///
+/// The default configuration has no limits, uses integer division, and
+/// seeds the random number generator from the system clock.
+///
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let query = parse_query("loves($Who, $Whom)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+/// let config = QueryConfig{ max_solutions: Some(1), ..Default::default() };
+/// set_query_config(&sn, config);
+/// let result = solve_all_result(sn, None).unwrap();
+/// assert_eq!(1, result.solutions.len());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct QueryConfig {
+    /// Maximum proof depth, or None for no limit.
+    /// See [set_max_depth()](../solution_node/fn.set_max_depth.html).
+    pub max_depth: Option<usize>,
+    /// Maximum number of solutions, or None for no limit.
+    /// See [set_max_solutions()](../solutions/fn.set_max_solutions.html).
+    pub max_solutions: Option<usize>,
+    /// If true, divide (/) gives a float for integer arguments.
+    /// See [set_float_division()](../built_in_arithmetic/fn.set_float_division.html).
+    pub float_division: bool,
+    /// Seed of the random number generator, or None to seed it from
+    /// the system clock. Each query which is given this configuration
+    /// starts its own sequence of random numbers from the seed.
+    pub random_seed: Option<u64>,
+}
+
+impl QueryConfig {
+
+    // Gets the settings of the current thread.
+    pub(crate) fn current() -> Self {
+        QueryConfig {
+            max_depth: max_depth(),
+            max_solutions: max_solutions(),
+            float_division: float_division(),
+            random_seed: random_state(),
+        }
+    }
+
+    // Applies the settings to the current thread.
+    pub(crate) fn apply(&self) {
+        set_max_depth(self.max_depth);
+        set_max_solutions(self.max_solutions);
+        set_float_division(self.float_division);
+        set_random_state(self.random_seed);
+    }
+
+} // impl QueryConfig
+
+/// Gives a query its own configuration.
+///
+/// The configuration is kept in the base solution node of the query.
+/// See the [module documentation](index.html).
+///
+/// # Arguments
+/// * `sn` - reference to a base [SolutionNode](../solution_node/struct.SolutionNode.html)
+/// * [QueryConfig](struct.QueryConfig.html)
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// add_rules!(&mut kb, parse_rule("half($X) :- $X is 7 / 2.").unwrap());
+///
+/// let query = parse_query("half($X)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+/// set_query_config(&sn, QueryConfig{ float_division: true, ..Default::default() });
+/// println!("{}", solve(sn));  // Prints: $X = 3.5
+/// ```
+pub fn set_query_config(sn: &Rc<RefCell<SolutionNode>>, config: QueryConfig) {
+    sn.borrow_mut().config = Some(config);
+} // set_query_config()
+
+/// Gets the configuration which applies to a query. If the query does
+/// not have a configuration of its own, the settings of the current
+/// thread are returned.
+///
+/// # Arguments
+/// * `sn` - reference to a base [SolutionNode](../solution_node/struct.SolutionNode.html)
+/// # Return
+/// * [QueryConfig](struct.QueryConfig.html)
+pub fn query_config(sn: &Rc<RefCell<SolutionNode>>) -> QueryConfig {
+    match sn.borrow().config {
+        Some(config) => { config },
+        None => { QueryConfig::current() },
+    }
+} // query_config()
+
+// Puts the configuration of a query into effect while the query is
+// searched. When the guard is dropped, the thread's settings are restored,
+// and the state of the query's random number generator is kept, so that
+// the next search of the query continues its sequence.
+pub(crate) struct ConfigGuard<'a> {
+    sn: Rc<RefCell<SolutionNode<'a>>>,
+    saved: Option<QueryConfig>,
+}
+
+impl<'a> ConfigGuard<'a> {
+
+    // Applies the configuration of a query, if it has one.
+    //
+    // Arguments
+    //    base solution node of the query
+    // Return
+    //    guard
+    pub(crate) fn new(sn: &Rc<RefCell<SolutionNode<'a>>>) -> Self {
+        let config = sn.borrow().config;
+        let saved = config.map(|config| {
+            let saved = QueryConfig::current();
+            config.apply();
+            saved
+        });
+        ConfigGuard{ sn: Rc::clone(sn), saved }
+    }
+
+} // impl ConfigGuard
+
+impl<'a> Drop for ConfigGuard<'a> {
+    fn drop(&mut self) {
+        if let Some(saved) = self.saved {
+            if let Some(config) = &mut self.sn.borrow_mut().config {
+                config.random_seed = random_state();
+            }
+            saved.apply();
+        }
+    }
+} // Drop
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    // Two queries on one thread keep their own settings.
+    #[test]
+    fn test_query_config() {
+
+        let mut kb = KnowledgeBase::new();
+        let rules = ["half($X) :- $X is 7 / 2.",
+                     "n(1).", "n(2).",
+                     "roll($N, $X) :- n($N), random_between(1, 1000000, $X).",
+                     "loop($X) :- loop($X), $X = 1."];
+        for r in rules { add_rules!(&mut kb, parse_rule(r).unwrap()); }
+
+        let fractions = QueryConfig{ float_division: true, ..Default::default() };
+        let query = parse_query("half($X)").unwrap();
+        let sn = make_base_node(Rc::new(query.clone()), &kb);
+        set_query_config(&sn, fractions);
+        assert_eq!("$X = 3.5", solve(sn));
+
+        // The thread's setting is not changed.
+        assert!(!float_division());
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = 3", solve(sn));
+
+        // The same seed gives the same numbers. The sequence continues
+        // from one solution of a query to the next.
+        let seeded = QueryConfig{ random_seed: Some(42), ..Default::default() };
+        let rolls = || {
+            let query = parse_query("roll($N, $X)").unwrap();
+            let sn = make_base_node(Rc::new(query), &kb);
+            set_query_config(&sn, seeded);
+            (solve(Rc::clone(&sn)), solve(sn))
+        };
+        let (first, second) = rolls();
+        assert_eq!((first.clone(), second.clone()), rolls());
+        assert_ne!(first.split("$X = ").nth(1), second.split("$X = ").nth(1));
+
+        // The maximum depth of the query is reported.
+        let limited = QueryConfig{ max_depth: Some(50), ..Default::default() };
+        let query = parse_query("loop(1)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        set_query_config(&sn, limited);
+        assert_eq!(50, query_config(&sn).max_depth.unwrap());
+        let result = try_solve(sn);
+        assert!(matches!(result, Err(SuironError::DepthLimitExceeded(_))));
+        assert_eq!(None, max_depth());
+
+    } // test_query_config()
+
+} // test
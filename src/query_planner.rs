@@ -0,0 +1,282 @@
+//! A query planner, which reorders the goals of a conjunction.
+//!
+//! Goals are solved from left to right. A conjunction such as
+//! <pre>
+//!     edge($X, $Y), start($X)
+//! </pre>
+//! tries every edge, and checks whether it starts at the starting node.
+//! If there are many edges but only one starting node, it is much faster
+//! to solve `start($X)` first, and then to look for the edges which begin
+//! at $X, especially if the knowledge base is
+//! [indexed](../knowledge_base/struct.KnowledgeBase.html#method.set_indexing).
+//!
+//! When the planner is turned on, with
+//! [set_query_planner()](fn.set_query_planner.html), each conjunction is
+//! reordered just before it is solved. The goal with the lowest estimated
+//! number of solutions comes first. The estimate is the number of facts of
+//! the goal's predicate, divided by 10 for each argument which is bound.
+//! Variables become bound by the goals which precede them.
+//!
+//! Only goals for predicates which consist of facts, or which are
+//! [external](../external/index.html), are moved. They have no side
+//! effects, and give the same solutions whatever their arguments are bound
+//! to. Other goals, such as rules, built-in predicates and not(), stay
+//! where they are, and goals are not moved past them. Reordering can change
+//! the order of the solutions, but not the solutions themselves.
+//!
+// Cleve Lendon 2023
+
+use std::cell::Cell;
+use std::collections::HashSet;
+
+use super::goal::Goal;
+use super::knowledge_base::*;
+use super::unifiable::Unifiable;
+
+// Each bound argument is assumed to divide the number of solutions by this.
+const SELECTIVITY: f64 = 10.0;
+
+// Estimated number of facts of an external predicate.
+const EXTERNAL_FACTS: usize = 1000;
+
+thread_local! {
+    static QUERY_PLANNER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Turns the query planner on or off. By default, it is off.
+///
+/// # Arguments
+/// * on - true to reorder conjunctions
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// for i in 0..1000 {
+///     add_rules!(&mut kb, parse_rule(&format!("edge({}, {}).", i, i + 1)).unwrap());
+/// }
+/// add_rules!(&mut kb, parse_rule("start(500).").unwrap(),
+///     parse_rule("first_edge($X, $Y) :- edge($X, $Y), start($X).").unwrap());
+/// kb.set_indexing(true);
+///
+/// set_query_planner(true);
+/// // start($X) is solved before edge($X, $Y).
+/// let query = parse_query("first_edge($X, $Y)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+/// let result = solve(sn);
+/// assert_eq!("$X = 500, $Y = 501", result);
+/// set_query_planner(false);
+/// ```
+pub fn set_query_planner(on: bool) {
+    QUERY_PLANNER.with(|p| p.set(on));
+}
+
+/// Determines whether the query planner is on.
+/// See [set_query_planner()](fn.set_query_planner.html).
+///
+/// # Return
+/// * true/false
+pub fn query_planner() -> bool {
+    QUERY_PLANNER.with(|p| p.get())
+}
+
+/// Reorders the goals of a conjunction, so that the goals which are
+/// estimated to have the fewest solutions come first.
+///
+/// # Arguments
+/// * goals of the conjunction
+/// * knowledge base
+/// * function which replaces the bound variables of a term
+/// # Return
+/// * reordered goals
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// for i in 0..100 {
+///     add_rules!(&mut kb, parse_rule(&format!("edge({}, {}).", i, i + 1)).unwrap());
+/// }
+/// add_rules!(&mut kb, parse_rule("start(50).").unwrap());
+///
+/// let goal = generate_goal("edge($X, $Y), start($X)").unwrap();
+/// let goal = goal.recreate_variables(&mut VarMap::new());
+/// if let Goal::OperatorGoal(Operator::And(goals)) = goal {
+///     let goals = plan_conjunction(goals, &kb, |term| term.clone());
+///     println!("{}, {}", goals[0], goals[1]);  // Prints: start($X_1), edge($X_1, $Y_2)
+/// }
+/// ```
+pub fn plan_conjunction(goals: Vec<Goal>, kb: &KnowledgeBase,
+                        resolve: impl Fn(&Unifiable) -> Unifiable) -> Vec<Goal> {
+
+    // Variables which are bound by the goals which have been planned.
+    let mut bound: HashSet<usize> = HashSet::new();
+    let mut planned: Vec<Goal> = Vec::with_capacity(goals.len());
+    // Consecutive goals which can be moved, with their resolved terms.
+    let mut movable: Vec<(Goal, Unifiable)> = vec![];
+
+    for goal in goals {
+        if let Goal::ComplexGoal(term) = &goal {
+            if can_move(term, kb) {
+                let resolved = resolve(term);
+                movable.push((goal, resolved));
+                continue;
+            }
+        }
+        plan_goals(&mut movable, kb, &mut bound, &mut planned);
+        planned.push(goal);
+    }
+    plan_goals(&mut movable, kb, &mut bound, &mut planned);
+    return planned;
+
+} // plan_conjunction()
+
+// Moves goals to the plan, cheapest first. After each goal is chosen,
+// its variables are considered to be bound.
+//
+// Arguments
+//    goals which can be moved, with their resolved terms
+//    knowledge base
+//    variables which are bound
+//    planned goals
+fn plan_goals(movable: &mut Vec<(Goal, Unifiable)>, kb: &KnowledgeBase,
+              bound: &mut HashSet<usize>, planned: &mut Vec<Goal>) {
+    while !movable.is_empty() {
+        let mut best = 0;
+        let mut best_estimate = f64::MAX;
+        for (i, (_, term)) in movable.iter().enumerate() {
+            let estimate = estimate_solutions(term, kb, bound);
+            if estimate < best_estimate {
+                best = i;
+                best_estimate = estimate;
+            }
+        }
+        let (goal, term) = movable.remove(best);
+        collect_vars(&term, bound);
+        planned.push(goal);
+    }
+} // plan_goals()
+
+// Determines whether a goal can be moved. Its predicate must consist
+// of facts, or be external.
+fn can_move(term: &Unifiable, kb: &KnowledgeBase) -> bool {
+    let key = match term.key() {
+        Ok(key) => { key },
+        Err(_) => { return false; },
+    };
+    if kb.contains(&key) { return !kb.is_tabled(&key) && kb.has_only_facts(&key); }
+    return kb.external(&key).is_some();
+} // can_move()
+
+// Estimates the number of solutions of a goal.
+//
+// Arguments
+//    resolved term of the goal
+//    knowledge base
+//    variables which are bound by previous goals
+// Return
+//    estimate
+fn estimate_solutions(term: &Unifiable, kb: &KnowledgeBase,
+                      bound: &HashSet<usize>) -> f64 {
+    let key = term.key().unwrap_or_default();
+    let facts = match count_rules(kb, &key) {
+        0 => { EXTERNAL_FACTS },
+        n => { n },
+    };
+    let mut estimate = facts as f64;
+    if let Unifiable::SComplex(terms) = term {
+        for arg in terms.iter().skip(1) {
+            let is_bound = match arg {
+                Unifiable::LogicVar{id, name: _} => { bound.contains(id) },
+                Unifiable::Anonymous => { false },
+                _ => { true },
+            };
+            if is_bound { estimate /= SELECTIVITY; }
+        }
+    }
+    return estimate;
+} // estimate_solutions()
+
+// Collects the IDs of the logic variables of a term.
+fn collect_vars(term: &Unifiable, vars: &mut HashSet<usize>) {
+    match term {
+        Unifiable::LogicVar{id, name: _} => { vars.insert(*id); },
+        Unifiable::SComplex(terms) | Unifiable::SFunction{name: _, terms} => {
+            for t in terms { collect_vars(t, vars); }
+        },
+        Unifiable::SLinkedList{term, next, count: _, tail_var: _} => {
+            collect_vars(term, vars);
+            collect_vars(next, vars);
+        },
+        _ => {},
+    }
+} // collect_vars()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+
+    use crate::*;
+
+    fn plan(kb: &KnowledgeBase, conjunction: &str) -> String {
+        let goal = generate_goal(conjunction).unwrap();
+        match goal.recreate_variables(&mut VarMap::new()) {
+            Goal::OperatorGoal(Operator::And(goals)) => {
+                let goals = plan_conjunction(goals, kb, |term| term.clone());
+                let goals: Vec<String> = goals.iter().map(|g| g.to_string()).collect();
+                return goals.join(", ");
+            },
+            goal => { return goal.to_string(); },
+        }
+    }
+
+    fn solve_sorted(kb: &KnowledgeBase, query: &str) -> Vec<String> {
+        let query = parse_query(query).unwrap();
+        let sn = make_base_node(Rc::new(query), kb);
+        let mut results = solve_all(sn);
+        results.sort();
+        return results;
+    }
+
+    #[test]
+    fn test_query_planner() {
+
+        let mut kb = KnowledgeBase::new();
+        for i in 0..200 {
+            let fact = parse_rule(&format!("edge({}, {}).", i, i + 1)).unwrap();
+            add_rules!(&mut kb, fact);
+        }
+        add_rules!(&mut kb, parse_rule("start(150).").unwrap(),
+                            parse_rule("start(175).").unwrap(),
+                            parse_rule("near($X) :- start($X).").unwrap(),
+                            parse_rule("path($X, $Z) :- edge($Y, $Z), edge($X, $Y), start($X).").unwrap());
+        kb.set_indexing(true);
+
+        assert_eq!("start($X_3), edge($X_3, $Y_1), edge($Y_1, $Z_2)",
+                   plan(&kb, "edge($Y, $Z), edge($X, $Y), start($X)"));
+        // A goal with a bound argument is more selective.
+        assert_eq!("edge(3, $Y_2), edge($X_1, $Y_2)", plan(&kb, "edge($X, $Y), edge(3, $Y)"));
+        // Goals are not moved past rules or built-in predicates.
+        assert_eq!("edge($X_1, $Y_2), near($X_1), edge($Y_2, $Z_3)",
+                   plan(&kb, "edge($X, $Y), near($X), edge($Y, $Z)"));
+        assert_eq!("edge($X_1, $Y_2), greater_than($Y_2, 3), start($X_1)",
+                   plan(&kb, "edge($X, $Y), $Y > 3, start($X)"));
+
+        let query = "path($X, $Z)";
+        let expected = solve_sorted(&kb, query);
+        assert_eq!(2, expected.len());
+
+        set_query_planner(true);
+        for strategy in [BindingStrategy::Persistent, BindingStrategy::Trail] {
+            set_binding_strategy(strategy);
+            assert_eq!(expected, solve_sorted(&kb, query));
+        }
+        set_binding_strategy(BindingStrategy::Persistent);
+        set_query_planner(false);
+
+    } // test_query_planner()
+
+} // test
@@ -18,8 +18,12 @@ use std::fmt;
 use crate::str_to_chars;
 use crate::chars_to_string;
 
+use super::error::SuironError;
 use super::goal::Goal;
 use super::parse_goals::*;
+use super::parse_terms::quoted_text;
+use super::user_operators::*;
+use super::dcg::*;
 use super::s_complex::*;
 use super::logic_var::*;
 use super::unifiable::Unifiable;
@@ -33,6 +37,7 @@ use super::tokenizer::*;
 /// and the body is a [goal](../goal/enum.Goal.html).<br>
 /// For facts, the body is set to [Nil](../goal/enum.Goal.html#variant.Nil).
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rule {
     pub head: Unifiable, // Must be a Unifiable::SComplex term.
     pub body: Goal,      // For facts, body is Goal::Nil
@@ -45,7 +50,9 @@ pub struct Rule {
 /// * `Option` - Some(index) or None
 fn index_of_neck(chrs: &[char]) -> Option<usize> {
     let mut previous_colon = false;
+    let quoted = quoted_text(chrs);
     for (i, ch) in chrs.iter().enumerate() {
+       if quoted[i] { previous_colon = false; continue; }
        if *ch == '-' {
            if previous_colon == true { return Some(i - 1); }
        }
@@ -78,7 +85,7 @@ fn index_of_neck(chrs: &[char]) -> Option<usize> {
 /// }
 /// // Prints: father($X, $Y) :- parent($X, $Y), male($X).
 /// ```
-pub fn parse_rule(to_parse: &str) -> Result<Rule, String> {
+pub fn parse_rule(to_parse: &str) -> Result<Rule, SuironError> {
 
     let s = to_parse.trim();
 
@@ -88,7 +95,7 @@ pub fn parse_rule(to_parse: &str) -> Result<Rule, String> {
     let mut length = chrs.len();
     if length < 4 {
         let err = pr_error("Invalid string.", s);
-        return Err(err);
+        return Err(err.into());
     }
 
     // Remove final period.
@@ -98,6 +105,13 @@ pub fn parse_rule(to_parse: &str) -> Result<Rule, String> {
         length = length - 1;
     }
 
+    // Grammar rules are translated into ordinary rules.
+    if let Some(index) = index_of_arrow(&chrs) {
+        let head = chars_to_string!(chrs[0..index]);
+        let body = chars_to_string!(chrs[index + 3..length]);
+        return parse_dcg_rule(&head, &body);
+    }
+
     match index_of_neck(&chrs[..]) {
 
         Some(index) => {
@@ -108,7 +122,7 @@ pub fn parse_rule(to_parse: &str) -> Result<Rule, String> {
             // Make sure there is not a second ':-'.
             if let Some(_) = index_of_neck(&body_chrs) {
                 let err = pr_error("Invalid rule.", s);
-                return Err(err);
+                return Err(err.into());
             }
 
             let head: Unifiable;
@@ -116,8 +130,10 @@ pub fn parse_rule(to_parse: &str) -> Result<Rule, String> {
                 Ok(sg) => {
                     match sg {
                         Goal::ComplexGoal(h) => { head = h; },
-                        _ => { panic!("parse_rule() - \
-                               Head of rule must be complex term."); },
+                        _ => {
+                            let err = pr_error("Head of rule must be a complex term", s);
+                            return Err(err.into());
+                        },
                     }
                 },
                 Err(err) => { return Err(err); },
@@ -131,6 +147,10 @@ pub fn parse_rule(to_parse: &str) -> Result<Rule, String> {
         None => {  // Must be a fact, no body.
             let fact: Unifiable;
             let s = chars_to_string!(chrs);
+            // A fact can be written with an operator: john likes mary.
+            if let Some(result) = parse_operators(&s) {
+                return Ok(Rule{head: result?, body: Goal::Nil});
+            }
             match parse_complex(&s) {
                 Ok(f) => { fact = f; },
                 Err(err) => { return Err(err); },
@@ -156,18 +176,25 @@ impl Rule {
     /// * `self`
     /// # Return
     /// * `key` - String
+    /// # Note
+    /// The head of a rule made by [parse_rule()](../rule/fn.parse_rule.html)
+    /// is always a complex term. If the head is not a complex term (see
+    /// [make_rule()](../knowledge_base/fn.make_rule.html)), the key is
+    /// the text of the head, which no query will match.
     /// # Usage
     /// ```
     /// use suiron::*;
     ///
-    /// let query = parse_query("parse($In, $Out, $ErrIn, $ErrOut)");
-    /// match query {
-    ///     Ok(q) => { println!("{}", q.key()); },
+    /// let rule = parse_rule("parse($In, $Out, $ErrIn, $ErrOut).");
+    /// match rule {
+    ///     Ok(r) => { println!("{}", r.key()); },
     ///     Err(msg) => { println!("{}", msg); },
     /// }
     /// // Prints: parse/4
     /// ```
-    pub fn key(&self) -> String { return self.head.key(); }
+    pub fn key(&self) -> String {
+        return self.head.key().unwrap_or_else(|_| self.head.to_string());
+    }
 
     /// Returns the head of this rule.
     ///
@@ -179,10 +206,9 @@ impl Rule {
     /// ```
     /// use suiron::*;
     ///
-    /// clear_id();
     /// let kb = test_kb();
     /// // Get grandfather rule.
-    /// let rule = get_rule(&kb, "grandfather/2", 0);
+    /// let rule = get_rule(&kb, "grandfather/2", 0).unwrap();
     /// let head = rule.get_head();
     /// println!("{}", head); // Prints: grandfather($X_1, $Y_2)
     /// ```
@@ -198,10 +224,9 @@ impl Rule {
     /// ```
     /// use suiron::*;
     ///
-    /// clear_id();
     /// let kb = test_kb();
     /// // Get grandfather rule.
-    /// let rule = get_rule(&kb, "grandfather/2", 0);
+    /// let rule = get_rule(&kb, "grandfather/2", 0).unwrap();
     /// let body = rule.get_body();
     /// println!("{}", body);  // father($X_1, $Z_3), father($Z_3, $Y_2)
     /// ```
@@ -221,7 +246,6 @@ impl Rule {
     /// ```
     /// use suiron::*;
     ///
-    /// clear_id();
     /// match parse_rule("parent($X, $Y) :- mother($X, $Y).") {
     ///     Ok(rule) => {
     ///         let mut var_map = VarMap::new();
@@ -1,19 +1,82 @@
-//! Functions to read Suiron facts and rules from a file.
+//! Functions to read Suiron facts and rules from a file, or from any
+//! other reader.
 
+use std::cell::Cell;
+use std::fmt;
 use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use crate::*;
 
+thread_local! {
+    // If true, source text which is not valid UTF-8 is read as Latin-1.
+    static LATIN1_FALLBACK: Cell<bool> = const { Cell::new(false) };
+}
+
+// Byte order mark, which some editors put at the start of UTF-8 files.
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+/// Sets the Latin-1 fallback for reading source files.
+///
+/// Source files should be encoded in UTF-8. (A byte order mark at the
+/// start of a file is ignored.) By default, a file which is not valid
+/// UTF-8 produces an error. If the Latin-1 fallback is set, such a file
+/// is read as Latin-1 (ISO 8859-1) instead.
+///
+/// The setting is local to the thread.
+///
+/// # Arguments
+/// * on - true to read invalid UTF-8 as Latin-1
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// set_latin1_fallback(true);
+/// let mut kb = KnowledgeBase::new();
+/// // café, in Latin-1
+/// let source = b"drink(caf\xE9).";
+/// load_kb_from_reader(&mut kb, &source[..]);
+/// set_latin1_fallback(false);
+/// ```
+pub fn set_latin1_fallback(on: bool) {
+    LATIN1_FALLBACK.with(|lf| lf.set(on));
+} // set_latin1_fallback()
+
+/// Determines whether the Latin-1 fallback is set.
+///
+/// See [set_latin1_fallback()](fn.set_latin1_fallback.html).
+///
+/// # Return
+/// * true or false
+pub fn latin1_fallback() -> bool {
+    return LATIN1_FALLBACK.with(|lf| lf.get());
+} // latin1_fallback()
+
 /// Loads a knowledge base with facts and rules from a file.
 ///
 /// Reads facts and rules from a source file, parses them to produce Rules,
 /// then adds these Rules to the knowledge base.
 ///
+/// A warning is reported for each variable which occurs only once in
+/// a rule, and for each predicate which is called but not defined.
+/// See [diagnostics](../diagnostics/index.html).
+///
+/// A source file can include other source files with the include
+/// directive. The path of an included file is relative to the directory
+/// of the file which includes it. A file which has already been loaded
+/// is not loaded again, and an include cycle is reported as an error.
+/// <pre>
+///     :- include('family.txt').
+/// </pre>
+///
 /// If a parsing error occurs, the function returns an error message which
 /// includes the offending line.
 ///
+/// If the `gzip` feature is enabled, a file whose name ends in .gz is
+/// decompressed as it is read.
+///
 /// # Arguments
 /// * knowledge base
 /// * file_name
@@ -33,32 +96,303 @@ use crate::*;
 /// }
 /// // Should print out knowledge base.
 /// ```
-pub fn load_kb_from_file(kb: &mut KnowledgeBase, file_name: &str) -> Option<String> {
-
-    let rules: Vec<String>;
+pub fn load_kb_from_file(kb: &mut KnowledgeBase, file_name: &str) -> Option<SuironError> {
     match read_facts_and_rules(file_name) {
-        Ok(r) => { rules = r; },
+        Ok(rules) => { return load_rules(kb, rules); },
         Err(error_message) => { return Some(error_message); },
     }
+} // load_kb_from_file
+
+/// Loads a knowledge base with facts and rules from a reader.
+///
+/// The reader can be a file, a network stream, or a byte slice. This
+/// makes it possible to compile a knowledge base into a binary, with
+/// include_str!() or include_bytes!().
+///
+/// Otherwise, this function is the same as
+/// [load_kb_from_file()](fn.load_kb_from_file.html). The paths of
+/// included files are relative to the current directory.
+///
+/// # Arguments
+/// * knowledge base
+/// * reader
+/// # Return
+/// error message or None
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let source = "parent(Godwin, Harold).\n\
+///               father($X, $Y) :- parent($X, $Y).";
+/// let mut kb = KnowledgeBase::new();
+/// if let Some(err) = load_kb_from_reader(&mut kb, source.as_bytes()) {
+///     println!("{}", err);
+/// }
+/// // Or: load_kb_from_reader(&mut kb, &include_bytes!("kings.txt")[..]);
+/// ```
+pub fn load_kb_from_reader(kb: &mut KnowledgeBase, reader: impl Read) -> Option<SuironError> {
+    let source = source_from_reader(reader).and_then(|source| {
+        expand_includes(source, Path::new(""), &mut Includes::default())
+    });
+    match source.and_then(source_rules) {
+        Ok(rules) => { return load_rules(kb, rules); },
+        Err(error_message) => { return Some(error_message); },
+    }
+} // load_kb_from_reader
+
+// Parses facts and rules, and adds them to the knowledge base.
+// Stops at the first error.
+//
+// Arguments
+//    knowledge base
+//    facts and rules
+// Return
+//    error message or None
+fn load_rules(kb: &mut KnowledgeBase, rules: Vec<String>) -> Option<SuironError> {
 
     let mut previous = "".to_string();
 
     for rule_str in rules {
-        match parse_rule(&rule_str) {
-            Ok(rule) => {
-                previous = rule_str;
-                add_rules!(kb, rule);
-            },
+        match load_rule(kb, &rule_str) {
+            Ok(()) => { previous = rule_str; },
             Err(msg) => {
                 let error_message = load_parse_error(msg, previous);
                 return Some(error_message); 
             },
         }
     }
+    warn_undefined(kb);
     return None;
 
-} // load_kb_from_file
+} // load_rules()
+
+/// Loads a knowledge base from a file, collecting all errors.
+///
+/// Unlike [load_kb_from_file()](fn.load_kb_from_file.html), this function
+/// does not stop at the first bad rule. It continues to parse the file,
+/// adding the valid rules to the knowledge base, and returns all errors
+/// together, so that a large file can be fixed in one pass.
+///
+/// Each error is returned as a [LoadError](struct.LoadError.html), which
+/// holds the line number, and the name of the file if the error is in an
+/// included file. The errors are sorted by file and line.
+///
+/// The error message of a rule which could not be parsed begins with
+/// the number of the line where the rule starts, eg.: `Line 12: ...`<br>
+/// If the rule is in an included file, the message begins with the
+/// name of the file: `family.txt, line 12: ...`
+///
+/// # Arguments
+/// * knowledge base
+/// * file_name
+/// # Return
+/// * vector of errors (empty if all OK)
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// let errors = load_kb_collect_errors(&mut kb, "SUIRON_TEST_DIR/kings.txt");
+/// for err in errors {
+///     println!("{}: {}", err.line, err.error);
+/// }
+/// ```
+pub fn load_kb_collect_errors(kb: &mut KnowledgeBase,
+                              file_name: &str) -> Vec<LoadError> {
+
+    let source = match read_source(file_name) {
+        Ok(source) => { source },
+        Err(error) => { return vec![LoadError{file: None, line: 0, error}]; },
+    };
+
+    let mut errors = source.errors;
+    for rule in source.rules {
+        if let Err(err) = load_rule(kb, &rule.text) {
+            let msg = match &rule.file {
+                Some(file) => { format!("{}, line {}: {}", file, rule.line, err) },
+                None => { format!("Line {}: {}", rule.line, err) },
+            };
+            let error = SuironError::ParseError(msg);
+            errors.push(LoadError{file: rule.file, line: rule.line, error});
+        }
+    }
+    errors.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+    warn_undefined(kb);
+    return errors;
 
+} // load_kb_collect_errors()
+
+/// An error which was found while loading a source file, and where
+/// it was found.
+///
+/// See [load_kb_collect_errors()](fn.load_kb_collect_errors.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadError {
+    /// Name of the included file which has the error, or None if
+    /// the error is in the loaded file.
+    pub file: Option<String>,
+    /// Line number of the error, or 0 if the file could not be read.
+    pub line: usize,
+    /// The error.
+    pub error: SuironError,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+} // Display
+
+impl std::error::Error for LoadError {}
+
+// Parses a fact or rule and adds it to the knowledge base,
+// or executes a directive.
+//
+// Arguments
+//    knowledge base
+//    fact, rule or directive
+// Return
+//    Ok or error
+fn load_rule(kb: &mut KnowledgeBase, rule_str: &str) -> Result<(), SuironError> {
+    // Directives begin with a neck: :- op(700, xfx, likes).
+    if let Some(directive) = rule_str.trim().strip_prefix(":-") {
+        return execute_directive(kb, directive);
+    }
+    let rule = with_operators(kb.operators(), || parse_rule(rule_str))?;
+    warn_singletons(&rule);
+    add_rules!(kb, rule);
+    return Ok(());
+} // load_rule()
+
+
+/// Executes a directive in a source file.
+///
+/// A directive is executed immediately, when it is loaded. The op/3
+/// directive defines an operator of the knowledge base, which is used
+/// to parse the rest of the source. See
+/// [add_operator()](../knowledge_base/struct.KnowledgeBase.html#method.add_operator).
+/// <pre>
+///     :- op(700, xfx, likes).
+/// </pre>
+///
+/// The dynamic directive declares predicates which may be modified at
+/// runtime. See [set_dynamic()](../knowledge_base/struct.KnowledgeBase.html#method.set_dynamic).
+/// <pre>
+///     :- dynamic(score/2, level/1).
+/// </pre>
+///
+/// The table directive declares predicates whose answers are remembered.
+/// See [tabling](../tabling/index.html).
+/// <pre>
+///     :- table path/2.
+/// </pre>
+///
+/// Any other directive is a goal, which is solved once, with the facts,
+/// rules and operators which have been loaded so far. If the goal fails, the
+/// function returns an error.
+/// <pre>
+///     :- set_random_seed(42).
+///     :- print(Knowledge base loaded.), nl.
+/// </pre>
+///
+/// (The include directive is handled by the loader. See
+/// [load_kb_from_file()](fn.load_kb_from_file.html).)
+///
+/// # Arguments
+/// * knowledge base
+/// * directive, without the initial :-
+/// # Return
+/// * Ok or error message
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// execute_directive(&mut kb, "op(700, xfx, likes).").unwrap();
+/// let rule = with_operators(kb.operators(), || parse_rule("john likes mary."));
+/// println!("{}", rule.unwrap());  // Prints: likes(john, mary).
+/// ```
+pub fn execute_directive(kb: &mut KnowledgeBase, directive: &str) -> Result<(), SuironError> {
+
+    let s = directive.trim();
+    let s = s.strip_suffix('.').unwrap_or(s);
+
+    // The table directive may be written without parentheses.
+    if let Some(predicates) = s.strip_prefix("table ") {
+        return execute_directive(kb, &format!("table({})", predicates.trim()));
+    }
+
+    if let Ok(Unifiable::SComplex(terms)) = parse_complex(s) {
+        if terms.len() == 4 && terms[0] == atom!("op") {
+            let name = match &terms[3] {
+                Unifiable::Atom(name) => { name.as_str() },
+                Unifiable::SString(name) => { name.as_str() },
+                _ => { return Err(format!("execute_directive() - Invalid operator: {}", s).into()); },
+            };
+            if let (Unifiable::SInteger(precedence), Unifiable::Atom(op_type)) =
+                   (&terms[1], &terms[2]) {
+                return kb.add_operator(*precedence, op_type, name)
+                       .map_err(SuironError::ParseError);
+            }
+            return Err(format!("execute_directive() - Invalid op/3: {}", s).into());
+        }
+        let dynamic = terms[0] == atom!("dynamic");
+        if terms.len() > 1 && (dynamic || terms[0] == atom!("table")) {
+            for term in &terms[1..] {
+                match term {
+                    Unifiable::Atom(name) if name.contains('/') => {
+                        if dynamic { kb.set_dynamic(name); }
+                        else { kb.set_tabled(name); }
+                    },
+                    _ => {
+                        let err = format!("execute_directive() - Invalid predicate: {}", term);
+                        return Err(err.into());
+                    },
+                }
+            }
+            return Ok(());
+        }
+    }
+    return solve_directive(kb, s);
+
+} // execute_directive()
+
+// Solves a directive once.
+//
+// Arguments
+//    knowledge base
+//    directive, eg. set_random_seed(42)
+// Return
+//    Ok or error message
+fn solve_directive(kb: &KnowledgeBase, directive: &str) -> Result<(), SuironError> {
+    return with_operators(kb.operators(), || solve_goal_directive(kb, directive));
+}
+
+// Solves a directive once, with the operators which are in effect.
+//
+// Arguments
+//    knowledge base
+//    directive
+// Return
+//    Ok or error message
+fn solve_goal_directive(kb: &KnowledgeBase, directive: &str) -> Result<(), SuironError> {
+
+    let goal = generate_goal(directive)?;
+    let goal = goal.recreate_variables(&mut VarMap::new());
+
+    let base_goal = Goal::ComplexGoal(scomplex!(atom!("directive")));
+    let base = make_base_node(Rc::new(base_goal), kb);
+    let sn = make_solution_node(Rc::new(goal), kb, empty_ss!(), base);
+
+    match next_solution(sn) {
+        Some(_) => { return Ok(()); },
+        None => {
+            let err = format!("execute_directive() - Directive failed: {}", directive);
+            return Err(err.into());
+        },
+    }
+
+} // solve_goal_directive()
 
 /// Produces a parsing error message, which includes the previous line.
 ///
@@ -66,12 +400,13 @@ pub fn load_kb_from_file(kb: &mut KnowledgeBase, file_name: &str) -> Option<Stri
 /// * error message
 /// * previous line
 /// # Return
-/// * new error message
-fn load_parse_error(err: String, previous_line: String) -> String {
+/// * parse error
+fn load_parse_error(err: SuironError, previous_line: String) -> SuironError {
     if previous_line.len() == 0 {
-        return format!("{} {}", err, "Check start of file.");
+        return SuironError::ParseError(format!("{} {}", err, "Check start of file."));
     }
-    return format!("{} Error occurs after: {}", err, previous_line);
+    let msg = format!("{} Error occurs after: {}", err, previous_line);
+    return SuironError::ParseError(msg);
 } // load_parse_error
 
 
@@ -95,53 +430,257 @@ fn load_parse_error(err: String, previous_line: String) -> String {
 /// }
 /// // Prints out: ["male(Godwin).", "male(Tostig).",  ...
 /// ```
-pub fn read_facts_and_rules(file_name: &str) -> Result<Vec<String>, String> {
+pub fn read_facts_and_rules(file_name: &str) -> Result<Vec<String>, SuironError> {
 
-    let mut long_line = "".to_string();
-    let mut rules: Vec<String> = vec![];
-
-    match line_reader(file_name) {
-        Ok(lines) => {
-
-            let mut line_number = 1;
-            for line in lines {
-                if let Ok(line) = line {
-                    let line = strip_comments(&line);
-                    if line.len() > 0 {
-                        match check_last_char(&line, line_number) {
-                            Some(msg) => { return Err(msg); },
-                            None => { long_line += &line; },
-                        }
-                        rules.push(line);
-                    }
-                }
-                line_number += 1;
-            }
-            separate_rules(&long_line)
-        },
+    return read_source(file_name).and_then(source_rules);
+} // read_facts_and_rules
+
+// Gets the facts and rules of a source, or the first error
+// which was found while reading.
+fn source_rules(source: Source) -> Result<Vec<String>, SuironError> {
+    if let Some(err) = source.errors.into_iter().next() {
+        return Err(err.error);
+    }
+    return Ok(source.rules.into_iter().map(|rule| rule.text).collect());
+} // source_rules()
+
+// Facts and rules which were read from a source file. Errors which
+// were found while reading (bad line endings, unmatched brackets and
+// bad includes) are collected.
+struct Source {
+    rules: Vec<SourceRule>,
+    errors: Vec<LoadError>,
+}
+
+// A fact or rule, and where it begins. The file is None, unless the
+// fact or rule comes from an included file.
+struct SourceRule {
+    file: Option<String>,
+    line: usize,
+    text: String,
+}
+
+// Files which have been loaded by include directives. Files which
+// are currently being loaded are on the stack.
+#[derive(Default)]
+struct Includes {
+    loading: Vec<PathBuf>,
+    loaded: Vec<PathBuf>,
+}
+
+// Reads facts and rules from a source file, and from the files
+// which it includes.
+//
+// Arguments
+//    file_name
+// Return
+//    source, or error if the file could not be read
+fn read_source(file_name: &str) -> Result<Source, SuironError> {
+    return read_source_file(Path::new(file_name), &mut Includes::default());
+} // read_source()
+
+// Reads a source file, and the files which it includes.
+//
+// Arguments
+//    path
+//    included files
+// Return
+//    source, or error if the file could not be read
+fn read_source_file(path: &Path, includes: &mut Includes) -> Result<Source, SuironError> {
+
+    let file = match File::open(path) {
+        Ok(file) => { file },
         Err(msg) => {
             // Add file name to error message.
-            let msg = format!("{}: {}", msg, file_name);
-            return Err(msg);
+            let msg = format!("{}: {}", msg, path.display());
+            return Err(SuironError::IoError(msg));
         },
-    } // match
+    };
+
+    let canonical = path.canonicalize().unwrap_or(path.to_path_buf());
+    includes.loading.push(canonical.clone());
+    includes.loaded.push(canonical);
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let source = read_file(file, path)
+                 .and_then(|source| expand_includes(source, dir, includes));
+    includes.loading.pop();
+    return source;
+
+} // read_source_file()
+
+// Reads facts and rules from a file. If the file name ends in .gz,
+// the file is decompressed. This requires the gzip feature.
+//
+// Arguments
+//    file
+//    path of file
+// Return
+//    source, or error if the file could not be read
+fn read_file(file: File, path: &Path) -> Result<Source, SuironError> {
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        #[cfg(feature = "gzip")]
+        return source_from_reader(flate2::read::GzDecoder::new(file));
+        #[cfg(not(feature = "gzip"))]
+        {
+            let msg = format!("Reading compressed files requires \
+                               the gzip feature: {}", path.display());
+            return Err(SuironError::IoError(msg));
+        }
+    }
+    return source_from_reader(file);
+} // read_file()
+
+// Replaces include directives with the facts and rules of the
+// included files.
+//
+// Arguments
+//    source
+//    directory of the including file
+//    included files
+// Return
+//    source, or error if an included file could not be read
+fn expand_includes(source: Source, dir: &Path,
+                   includes: &mut Includes) -> Result<Source, SuironError> {
+
+    let mut rules: Vec<SourceRule> = vec![];
+    let mut errors = source.errors;
+
+    for rule in source.rules {
+        let file_name = match include_file(&rule.text) {
+            Some(file_name) => { file_name },
+            None => { rules.push(rule); continue; },
+        };
+        let path = dir.join(&file_name);
+        let canonical = path.canonicalize().unwrap_or(path.clone());
+        if includes.loading.contains(&canonical) {
+            let msg = format!("Include cycle: {}", file_name);
+            let error = SuironError::ParseError(msg);
+            errors.push(LoadError{file: None, line: rule.line, error});
+            continue;
+        }
+        if includes.loaded.contains(&canonical) { continue; }
+        let mut included = match read_source_file(&path, includes) {
+            Ok(included) => { included },
+            Err(error) => {
+                errors.push(LoadError{file: None, line: rule.line, error});
+                continue;
+            },
+        };
+        let included_file = Some(path.display().to_string());
+        for r in included.rules.iter_mut() {
+            if r.file.is_none() { r.file = included_file.clone(); }
+        }
+        for e in included.errors.iter_mut() {
+            if e.file.is_none() { e.file = included_file.clone(); }
+        }
+        rules.append(&mut included.rules);
+        errors.append(&mut included.errors);
+    }
+    return Ok(Source{rules, errors});
+
+} // expand_includes()
+
+// Gets the file name from an include directive: :- include('family.txt').
+//
+// Arguments
+//    fact, rule or directive
+// Return
+//    file name, or None if this is not an include directive
+fn include_file(rule_str: &str) -> Option<String> {
+    let directive = rule_str.trim().strip_prefix(":-")?.trim();
+    let directive = directive.strip_suffix('.').unwrap_or(directive);
+    if !directive.starts_with("include(") { return None; }
+    match parse_complex(directive) {
+        Ok(Unifiable::SComplex(terms)) if terms.len() == 2 => {
+            match &terms[1] {
+                Unifiable::Atom(name) => { return Some(name.to_string()); },
+                Unifiable::SString(name) => { return Some(name.to_string()); },
+                _ => { return None; },
+            }
+        },
+        _ => { return None; },
+    }
+} // include_file()
 
-} // read_facts_and_rules
+// Reads facts and rules from a reader, and records the line
+// numbers where they begin.
+//
+// Arguments
+//    reader
+// Return
+//    source, or error if the text could not be read
+fn source_from_reader(mut reader: impl Read) -> Result<Source, SuironError> {
 
-/// Creates an iterator which reads lines from a file.
-///
-/// # Arguments
-/// * file_name
-/// # Return
-/// * line reader
-/// # Reference
-/// https://doc.rust-lang.org/rust-by-example/std_misc/file/read_lines.html
-///
-fn line_reader<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
-where P: AsRef<Path>, {
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
-}
+    let mut bytes: Vec<u8> = vec![];
+    reader.read_to_end(&mut bytes)?;
+    let text = decode_source(&bytes)?;
+
+    let mut long_line = "".to_string();
+    let mut length = 0;   // length of long_line, in chars
+    let mut errors: Vec<LoadError> = vec![];
+    // Index into long_line where each line starts, and the line number.
+    let mut line_starts: Vec<(usize, usize)> = vec![];
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = strip_comments(line);
+        if line.len() > 0 {
+            if let Some(msg) = check_last_char(&line, line_number) {
+                errors.push(LoadError{file: None, line: line_number, error: msg.into()});
+            }
+            line_starts.push((length, line_number));
+            length += line.chars().count();
+            long_line += &line;
+        }
+    }
+
+    // Finds the line number of an index into long_line.
+    let line_of = |start: usize| {
+        let index = line_starts.partition_point(|(s, _)| *s <= start);
+        if index > 0 { line_starts[index - 1].1 } else { 1 }
+    };
+
+    let (located, unmatched) = locate_rules(&long_line);
+    if let Some(error) = unmatched {
+        // The unmatched bracket is in the text after the last rule.
+        let start = located.last()
+                    .map_or(0, |(start, rule)| start + rule.chars().count());
+        errors.push(LoadError{file: None, line: line_of(start), error});
+    }
+
+    let rules = located.into_iter().map(|(start, rule)| {
+        SourceRule{file: None, line: line_of(start), text: rule}
+    }).collect();
+
+    return Ok(Source{rules, errors});
+
+} // source_from_reader()
+
+// Decodes source text. A byte order mark is removed. If the text is
+// not valid UTF-8, it is decoded as Latin-1, if the fallback is set.
+//
+// Arguments
+//    bytes of source text
+// Return
+//    text or error
+pub(crate) fn decode_source(bytes: &[u8]) -> Result<String, SuironError> {
+
+    let bytes = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => { return Ok(text.to_string()); },
+        Err(err) => {
+            if latin1_fallback() {
+                return Ok(bytes.iter().map(|b| *b as char).collect());
+            }
+            let valid = &bytes[0..err.valid_up_to()];
+            let line = valid.iter().filter(|b| **b == b'\n').count() + 1;
+            let msg = format!("Invalid UTF-8 in line {}. See set_latin1_fallback().", line);
+            return Err(SuironError::IoError(msg));
+        },
+    }
+
+} // decode_source()
 
 /// Strips comments from a line.
 ///
@@ -153,7 +692,7 @@ where P: AsRef<Path>, {
 ///
 /// Any text which occurs after these delimiters is considered a comment,
 /// and removed from the line. However, if these delimiters occur within
-/// braces, they are not treated as comment delimiters.
+/// braces or quotes, they are not treated as comment delimiters.
 /// For example, in the line
 ///
 /// <blockquote>
@@ -167,86 +706,73 @@ where P: AsRef<Path>, {
 /// * `original line`
 /// # Return
 /// * `line without comments`
-fn strip_comments(line: &str) -> String {
+pub(crate) fn strip_comments(line: &str) -> String {
 
     let mut previous = 'x';
     let mut round_depth  = 0;
     let mut square_depth = 0;
 
-    let mut index = 0;
-    let mut has_comment = false;
-
-    let chrs = str_to_chars!(line);
-    for (i, ch) in chrs.iter().enumerate() {
-        if *ch == '(' { round_depth += 1; }
-        else if *ch == '[' { square_depth += 1; }
-        else if *ch == ')' { round_depth -= 1; }
-        else if *ch == ']' { square_depth -= 1; }
+    for sc in QuoteScanner::new(line.chars()) {
+        let ch = sc.ch;
+        if sc.quoted { previous = ch; continue; }
+        if ch == '(' { round_depth += 1; }
+        else if ch == '[' { square_depth += 1; }
+        else if ch == ')' { round_depth -= 1; }
+        else if ch == ']' { square_depth -= 1; }
         else if round_depth == 0 && square_depth == 0 {
-            if *ch == '#' || *ch == '%' {
-                index = i;
-                has_comment = true;
-                break;
-            } else if *ch == '/' && previous == '/' {
-                index = i - 1;
-                has_comment = true;
-                break;
+            if ch == '#' || ch == '%' {
+                return line[0..sc.byte].trim().to_string();
+            } else if ch == '/' && previous == '/' {
+                return line[0..sc.byte - 1].trim().to_string();
             }
         }
-        previous = *ch;
-    }
-
-    if has_comment {
-        return chars_to_string!(chrs[0..index]).trim().to_string();
-    }
-    else {
-        return chars_to_string!(chrs).trim().to_string();
+        previous = ch;
     }
+    return line.trim().to_string();
 
 }  // strip_comments
 
 /// Divides a text string into a list of facts and rules.
 ///
-/// Each rule or fact ends with a period.
+/// Each rule or fact ends with a period. The index (in chars) where
+/// each fact or rule begins is recorded, in order to find its line.
 ///
 /// # Arguments
 /// * text - one long line
 /// # Return
-/// * list of facts/rules or error message
+/// * (list of (index, fact/rule), unmatched bracket error)
 ///
-fn separate_rules(text: &str) -> Result<Vec<String>, String> {
+pub(crate) fn locate_rules(text: &str) -> (Vec<(usize, String)>, Option<SuironError>) {
 
     let mut rule_str = "".to_string();
-    let mut rules: Vec<String> = vec![];
+    let mut start = 0;
+    let mut rules: Vec<(usize, String)> = vec![];
 
     let mut round_depth: i32  = 0;
     let mut square_depth: i32 = 0;
-    let mut num_quotes  = 0;
 
     let chrs = str_to_chars!(text);
-    for ch in chrs {
+    let quoted = quoted_text(&chrs);
+    for (i, ch) in chrs.into_iter().enumerate() {
+        if rule_str.trim().is_empty() { start = i; }
         rule_str.push(ch);
-        if ch == '.' && round_depth == 0 &&
-            square_depth == 0 && num_quotes % 2 == 0 {
-            rules.push(rule_str);
+        if quoted[i] { continue; }
+        if ch == '.' && round_depth == 0 && square_depth == 0 {
+            rules.push((start, rule_str));
             rule_str = "".to_string();
         }
         else if ch == '(' { round_depth += 1; }
         else if ch == '[' { square_depth += 1; }
         else if ch == ')' { round_depth -= 1; }
         else if ch == ']' { square_depth -= 1; }
-        else if ch == '"' { num_quotes += 1; }
     } // for
 
     // Check for unmatched brackets here.
-    match unmatched_bracket(&rule_str, round_depth, square_depth) {
-        None => {},
-        Some(msg) => { return Err(msg); },
-    }
-
-    return Ok(rules);
+    let unmatched = unmatched_bracket(&rule_str, round_depth, square_depth)
+                    .map(SuironError::from);
+    return (rules, unmatched);
 
-} // separate_rules
+} // locate_rules
 
 /// Check that a line ends with a valid character.
 ///
@@ -352,6 +878,7 @@ fn trim_error_line(chrs: &Vec<char>) -> String {
 #[cfg(test)]
 mod test {
 
+    use std::rc::Rc;
     use super::*;
 
     // Produce an error message for unmatched parentheses and brackets.
@@ -433,17 +960,17 @@ mod test {
         let err_message = "Unmatched parenthesis: (\n\
                            Check: mother(Necessity, Invention.";
 
-        match separate_rules(text) {
-            Ok(rules) => {
-                let s = format!("{:?}", rules);
-                assert_eq!(expected, s);
-            },
-            Err(_) => { panic!("There should be no error here."); },
-        }
-
-        match separate_rules(bad_text) {
-            Ok(_) => { panic!("Missing parenthesis should cause an error."); },
-            Err(msg) => { assert_eq!(err_message, msg); },
+        let (rules, unmatched) = locate_rules(text);
+        if unmatched.is_some() { panic!("There should be no error here."); }
+        let starts: Vec<usize> = rules.iter().map(|(start, _)| *start).collect();
+        assert_eq!(vec![0, 53, 83], starts);
+        let rules: Vec<String> = rules.into_iter().map(|(_, rule)| rule).collect();
+        let s = format!("{:?}", rules);
+        assert_eq!(expected, s);
+
+        match locate_rules(bad_text) {
+            (_, None) => { panic!("Missing parenthesis should cause an error."); },
+            (_, Some(msg)) => { assert_eq!(err_message, msg); },
         }
     } // test_separate_rules()
 
@@ -455,7 +982,7 @@ mod test {
         match rules {
             Ok(_rules) => { panic!("The file {} should not exist.", filename); },
             Err(msg) => {
-                if !msg.contains("No such file") {
+                if !msg.message().contains("No such file") {
                     panic!("Invalid error message: {}", msg);
                 }
             },
@@ -530,6 +1057,166 @@ mod test {
         }
     } // test_load_kb_from_file()
 
+    // Continue parsing after errors, and collect all of them.
+    #[test]
+    fn test_load_kb_collect_errors() {
+        let mut kb = KnowledgeBase::new();
+        let path = format!("{}/badrules.txt", env!("SUIRON_TEST_DIR"));
+        let (warnings, errors) = with_captured_warnings(|| {
+            load_kb_collect_errors(&mut kb, &path)
+        });
+        assert_eq!(4, errors.len(), "Should collect 4 errors.");
+        // The errors are in line order.
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(vec![3, 5, 8, 11], lines);
+        assert_eq!("Line 3: parse_arguments() - Missing last argument: male(Godwin, )",
+                   errors[0].error);
+        assert!(errors[1].error.message().starts_with("Line 5: parse_rule()"));
+        assert_eq!("Line 8: add_operator() - Invalid type: abc", errors[2].error);
+        assert_eq!("Check end of line 11: par", errors[3].error);
+        assert_eq!(None, errors[3].file);
+        // The valid facts are loaded.
+        assert_eq!(2, count_rules(&kb, "male/1"));
+        assert_eq!(1, count_rules(&kb, "female/1"));
+        // Singleton variables produce warnings.
+        assert_eq!(3, warnings.len(), "$Z and $Chlid are singletons.");
+        assert_eq!("Singleton variable $Chlid in: \
+                    grandparent($X, $Z) :- parent($X, $Y), parent($Y, $Chlid).",
+                   warnings[1]);
+        // parent/2 is not defined.
+        assert_eq!("Undefined predicate parent/2, called by grandparent/2", warnings[2]);
+
+        let errors = load_kb_collect_errors(&mut kb, "no_such_file.txt");
+        assert!(matches!(errors[0].error, SuironError::IoError(_)));
+        assert_eq!(0, errors[0].line);
+    } // test_load_kb_collect_errors()
+
+    #[test]
+    fn test_include() {
+        let mut kb = KnowledgeBase::new();
+        let path = format!("{}/include_main.txt", env!("SUIRON_TEST_DIR"));
+        if let Some(err) = load_kb_from_file(&mut kb, &path) {
+            panic!("Should be no errors: {}", err);
+        }
+        // include_facts.txt is included twice, but loaded once.
+        assert_eq!(2, count_rules(&kb, "parent/2"));
+        assert_eq!(1, count_rules(&kb, "grandparent/2"));
+
+        let mut kb = KnowledgeBase::new();
+        let path = format!("{}/include_cycle.txt", env!("SUIRON_TEST_DIR"));
+        let errors = load_kb_collect_errors(&mut kb, &path);
+        assert_eq!(1, errors.len());
+        assert_eq!("Include cycle: include_cycle.txt", errors[0].error);
+        assert_eq!(3, errors[0].line);
+        assert_eq!(2, count_rules(&kb, "parent/2"));
+
+        let source = ":- include('no_such_file.txt').";
+        let err = load_kb_from_reader(&mut kb, source.as_bytes());
+        assert!(matches!(err, Some(SuironError::IoError(_))));
+    } // test_include()
+
+    #[test]
+    fn test_load_kb_from_reader() {
+        let mut kb = KnowledgeBase::new();
+        let source = include_str!("../tests/kings.txt");
+        if let Some(err) = load_kb_from_reader(&mut kb, source.as_bytes()) {
+            panic!("Should be no errors: {}", err);
+        }
+        assert_eq!(5, count_rules(&kb, "female/1"));
+
+        let source = "male(Godwin).\nfather($X, $Y) :- parent($X, $Y) male($X)";
+        let err = load_kb_from_reader(&mut kb, source.as_bytes());
+        assert_eq!(Some(SuironError::ParseError("Check end of line 2: \
+                   father($X, $Y) :- parent($X, $Y) male($X)".to_string())), err);
+
+        // Invalid UTF-8.
+        let err = load_kb_from_reader(&mut kb, &[b'a', 0xFF, b'.'][..]);
+        assert!(matches!(err, Some(SuironError::IoError(_))));
+    } // test_load_kb_from_reader()
+
+    // Read a gzip-compressed file.
+    #[test]
+    fn test_read_gzip() {
+        let path = format!("{}/kings.txt.gz", env!("SUIRON_TEST_DIR"));
+        let rules = read_facts_and_rules(&path);
+        #[cfg(feature = "gzip")]
+        assert_eq!(24, rules.unwrap().len(), "Must read all rules.");
+        #[cfg(not(feature = "gzip"))]
+        assert!(matches!(rules, Err(SuironError::IoError(_))));
+    } // test_read_gzip()
+
+    // Test byte order mark, and Latin-1 fallback.
+    #[test]
+    fn test_encodings() {
+        let mut kb = KnowledgeBase::new();
+        let source = b"\xEF\xBB\xBFdrink(caf\xC3\xA9).\r\ndrink(tea).";
+        assert_eq!(None, load_kb_from_reader(&mut kb, &source[..]));
+        assert_eq!("drink(café).", get_rule(&kb, "drink/1", 0).unwrap().to_string());
+
+        let source = b"drink(water).\ndrink(caf\xE9).";
+        let err = load_kb_from_reader(&mut kb, &source[..]).unwrap();
+        assert_eq!("Invalid UTF-8 in line 2. See set_latin1_fallback().", err);
+
+        set_latin1_fallback(true);
+        let err = load_kb_from_reader(&mut kb, &source[..]);
+        set_latin1_fallback(false);
+        assert_eq!(None, err);
+        assert_eq!("drink(café).", get_rule(&kb, "drink/1", 3).unwrap().to_string());
+    } // test_load_kb_from_reader()
+
+    #[test]
+    fn test_load_operators() {
+        let mut kb = KnowledgeBase::new();
+        let path = format!("{}/operators.txt", env!("SUIRON_TEST_DIR"));
+        if let Some(err) = load_kb_from_file(&mut kb, &path) {
+            panic!("Should be no errors: {}", err);
+        }
+        assert_eq!(2, count_rules(&kb, "likes/2"));
+        let query = parse_query("happy($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = mary", solve(sn));
+
+        // The operators belong to the knowledge base, not to the thread.
+        assert_eq!(2, kb.operators().len());
+        assert!(get_operators().is_empty());
+        assert_ne!("likes(ann, tea).", parse_rule("ann likes tea.").unwrap().to_string());
+        let query = with_operators(kb.operators(), || parse_query("$X likes mary"));
+        let sn = make_base_node(Rc::new(query.unwrap()), &kb);
+        assert_eq!("$X = john", solve(sn));
+
+        let mut kb2 = KnowledgeBase::new();
+        load_kb_from_reader(&mut kb2, "ann likes tea.".as_bytes());
+        assert_eq!(0, count_rules(&kb2, "likes/2"));
+        assert!(execute_directive(&mut kb, "op(700, abc, likes).").is_err());
+        assert!(execute_directive(&mut kb, "discontiguous(likes/2).").is_err());
+    } // test_load_operators()
+
+    #[test]
+    fn test_directives() {
+        let mut kb = KnowledgeBase::new();
+        let source = ":- op(700, xfx, likes).\n\
+                      john likes mary.\n\
+                      :- john likes mary, print(Loaded), nl.\n\
+                      :- set_random_seed(7).\n";
+        let (text, err) = with_captured_output(|| {
+            load_kb_from_reader(&mut kb, source.as_bytes())
+        });
+        if let Some(err) = err { panic!("Should be no errors: {}", err); }
+        assert_eq!("Loaded\n", text);
+
+        let source = "mary likes wine.\n:- john likes wine.";
+        let err = load_kb_from_reader(&mut kb, source.as_bytes()).unwrap();
+        assert!(err.message().starts_with("execute_directive() - \
+                                           Directive failed: john likes wine"));
+
+        let source = ":- dynamic(score/2, level/1).\nscore(Penny, 7).";
+        assert_eq!(None, load_kb_from_reader(&mut kb, source.as_bytes()));
+        assert!(kb.is_dynamic("score/2") && kb.is_dynamic("level/1"));
+        assert!(!kb.is_static("score/2"));
+        assert!(kb.is_static("likes/2"));
+        assert!(execute_directive(&mut kb, "dynamic(score).").is_err());
+    } // test_directives()
+
     #[test]
     fn test_trim_error_line() {
         let s = "Just a sentence. This should be trimmed.";
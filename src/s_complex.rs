@@ -20,11 +20,14 @@
 //! [Logic variables](../unifiable/enum.Unifiable.html#variant.LogicVar)
 //! start with a dollar sign and a letter, eg. $F.<br>
 //!
+use super::error::SuironError;
 use super::unifiable::{*, Unifiable::*};
 use super::goal::*;
 use super::logic_var::*;
 use super::parse_terms::*;
 use super::parse_goals::*;
+use super::user_operators::*;
+use super::dcg::*;
 
 use crate::atom;
 use crate::str_to_chars;
@@ -42,9 +45,8 @@ use crate::chars_to_string;
 /// [Unifiable](../unifiable/enum.Unifiable.html) terms
 /// # Return
 /// * [SComplex](../unifiable/enum.Unifiable.html#variant.SComplex)
-/// # Panics
-/// * If vector is empty.
-/// * If first term (functor) is not an atom.
+///   or error, if the vector is empty or the first term (functor)
+///   is not an atom.
 /// # Usage
 /// ```
 /// use suiron::*;
@@ -54,17 +56,22 @@ use crate::chars_to_string;
 /// let t = atom!("Earl Grey");
 ///
 /// let terms = vec![d, p, t];
-/// let cmplx = make_complex(terms);
+/// let cmplx = make_complex(terms).unwrap();
 /// println!("{}", cmplx);  // Prints: drinks(Picard, Earl Grey)
 /// ```
-pub fn make_complex(terms: Vec<Unifiable>) -> Unifiable {
-    if terms.len() == 0 { panic!("make_complex() - Vector is empty."); }
-    let first = &terms[0];
-    match first {
-        Unifiable::Atom(_) => {},
-        _ => { panic!("make_complex() - First term must be an Atom."); },
+pub fn make_complex(terms: Vec<Unifiable>) -> Result<Unifiable, SuironError> {
+    match terms.first() {
+        None => {
+            let err = "make_complex() - Vector is empty.".to_string();
+            return Err(SuironError::ParseError(err));
+        },
+        Some(Unifiable::Atom(_)) => {},
+        Some(first) => {
+            let err = format!("make_complex() - First term must be an Atom: {}", first);
+            return Err(SuironError::ParseError(err));
+        },
     }
-    return Unifiable::SComplex(terms);
+    return Ok(Unifiable::SComplex(terms));
 } // make_complex()
 
 
@@ -73,17 +80,15 @@ pub fn make_complex(terms: Vec<Unifiable>) -> Unifiable {
 /// A query is the same as a complex term, but before it can be
 /// submitted to a solver, its logic variables must be given unique IDs.
 ///
-/// This function resets the global LOGIC_VAR_ID to 0, and recreates the
-/// logic variable terms of the query.
+/// This function recreates the logic variable terms of the query,
+/// numbering them from 1.
 ///
 /// # Arguments
 /// * `terms` - vector of
 /// [Unifiable](../unifiable/enum.Unifiable.html) terms
 /// # Return
-/// * [Goal](../goal/enum.Goal.html)
-/// # Panics
-/// * If vector is empty.
-/// * If first term (functor) is not an atom.
+/// * [Goal](../goal/enum.Goal.html) or error, if the vector is empty
+///   or the first term (functor) is not an atom.
 /// # Usage
 /// ```
 /// use suiron::*;
@@ -93,16 +98,25 @@ pub fn make_complex(terms: Vec<Unifiable>) -> Unifiable {
 /// let y = logic_var!("$Y");
 ///
 /// let terms = vec![functor, x, y];
-/// let qry = make_query(terms);
+/// let qry = make_query(terms).unwrap();
 /// println!("{}", qry);  // Prints: loves($X_1, $Y_2)
 /// ```
-pub fn make_query(terms: Vec<Unifiable>) -> Goal {
+pub fn make_query(terms: Vec<Unifiable>) -> Result<Goal, SuironError> {
 
     // The main bottleneck in Suiron is the time it takes to copy
     // the substitution set. The substitution set is as large as
-    // the highest variable ID (LOGIC_VAR_ID). Therefore LOGIC_VAR_ID
-    // should be set to 0 for every query.
-    clear_id();  // Reset LOGIC_VAR_ID.
+    // the highest variable ID. Therefore the variables of every
+    // query are numbered from 1.
+
+    // phrase() calls a grammar rule.
+    let terms = match terms.first() {
+        Some(Atom(functor)) if functor == "phrase" => {
+            expand_phrase(&terms[1..]).map_or(terms, |goal| {
+                if let SComplex(goal_terms) = goal { goal_terms } else { vec![] }
+            })
+        },
+        _ => { terms },
+    };
 
     let mut new_terms: Vec<Unifiable> = vec![];
     let mut vars = VarMap::new();
@@ -111,8 +125,8 @@ pub fn make_query(terms: Vec<Unifiable>) -> Goal {
         new_terms.push(term.recreate_variables(&mut vars));
     }
 
-    let c = make_complex(new_terms);
-    Goal::ComplexGoal(c)
+    let c = make_complex(new_terms)?;
+    Ok(Goal::ComplexGoal(c))
 
 } // make_query()
 
@@ -154,13 +168,13 @@ pub fn make_query(terms: Vec<Unifiable>) -> Goal {
 /// The backslash is doubled because the Rust compiler also
 /// interprets the backslash.
 ///
-pub fn parse_complex(to_parse: &str) -> Result<Unifiable, String> {
+pub fn parse_complex(to_parse: &str) -> Result<Unifiable, SuironError> {
 
     let s = to_parse.trim();
     let chrs = str_to_chars!(s);
 
     match validate_complex(s, &chrs) {
-        Some(msg) => { return Err(msg); },
+        Some(msg) => { return Err(msg.into()); },
         None => {},
     }
 
@@ -175,7 +189,7 @@ pub fn parse_complex(to_parse: &str) -> Result<Unifiable, String> {
                         Ok(cmplx) => { return Ok(cmplx); }, // OK, return.
                         Err(err) => { // Adjust error message. Add original string.
                             let err = format!("{}{}", err, to_parse);
-                            return Err(err);
+                            return Err(err.into());
                         },
                     }
                 },
@@ -184,13 +198,13 @@ pub fn parse_complex(to_parse: &str) -> Result<Unifiable, String> {
                         Ok(cmplx) => { return Ok(cmplx); }, // OK, return.
                         Err(err) => { // Adjust error message. Add original string.
                             let err = format!("{} {}", err, to_parse);
-                            return Err(err);
+                            return Err(err.into());
                         },
                     }
                 },
             }
         },
-        Err(err) => { return Err(err); }
+        Err(err) => { return Err(err.into()); }
     }
 } // parse_complex
 
@@ -276,22 +290,32 @@ pub fn validate_complex(to_parse: &str, chrs: &Vec<char>) -> Option<String> {
 /// The backslash is doubled because the Rust compiler also
 /// interprets the backslash.
 ///
-pub fn parse_query(to_parse: &str) -> Result<Goal, String> {
+/// An [Engine](../engine/struct.Engine.html) keeps a cache of the
+/// queries which it has parsed.
+///
+pub fn parse_query(to_parse: &str) -> Result<Goal, SuironError> {
 
     // Clean up query.
     // Perhaps there is an unnecessary period at the end.
     let mut parse2 = to_parse.to_string();
-    let ch = parse2.chars().last().unwrap();
-    if ch == '.' { parse2.pop(); }
+    if parse2.ends_with('.') { parse2.pop(); }
+
+    // A query can be written with an operator: $X likes mary
+    let parsed = match parse_operators(&parse2) {
+        Some(result) => { result },
+        None => { parse_complex(&parse2) },
+    };
 
-    match parse_complex(&parse2) {
+    match parsed {
         Ok(q) => {
             match q {
                 Unifiable::SComplex(terms) => {
-                    let query = make_query(terms);
-                    return Ok(query);
+                    return make_query(terms);
+                },
+                _ => {
+                    let err = format!("parse_query() - Invalid query: {}", to_parse);
+                    return Err(SuironError::ParseError(err));
                 },
-                _ => { panic!("parse_query() - Cannot happen.") },
             }
         },
         Err(err) => { Err(err) },
@@ -318,7 +342,7 @@ pub fn parse_query(to_parse: &str) -> Result<Goal, String> {
 /// }
 /// // Prints: Anakin, Luke
 /// ```
-pub fn parse_functor_terms(functor: &str, terms: &str) -> Result<Unifiable, String> {
+pub fn parse_functor_terms(functor: &str, terms: &str) -> Result<Unifiable, SuironError> {
 
     let mut new_terms = vec![atom!(functor.trim())];
     if terms == "" {
@@ -380,7 +404,7 @@ mod test {
         let p = atom!("Picard");
         let t = atom!("Earl Grey");
         let terms = vec![d, p, t];
-        let cmplx = make_complex(terms);
+        let cmplx = make_complex(terms).unwrap();
         let s = format!("{}", cmplx);
         assert_eq!(s, "drinks(Picard, Earl Grey)");
     } // test_make_complex()
@@ -393,27 +417,35 @@ mod test {
         let x = logic_var!("$X");
         let y = logic_var!("$Y");
         let terms = vec![functor, x, y];
-        let qry = make_query(terms);
+        let qry = make_query(terms).unwrap();
         assert_eq!(qry.to_string(), "loves($X_1, $Y_2)");
     } // test_make_query
 
-    /// make_complex() - Panics if the vector is empty.
+    /// make_complex() - Error if the vector is empty.
     #[test]
-    #[should_panic]
-    fn test_make_complex_panic1() {
+    fn test_make_complex_error1() {
         let terms = vec![];
-        make_complex(terms);
+        assert_eq!("make_complex() - Vector is empty.",
+                   make_complex(terms).unwrap_err());
     }
 
-    /// make_complex() - Panics if the first term is not an Atom.
+    /// make_complex() - Error if the first term is not an Atom.
     #[test]
-    #[should_panic]
-    fn test_make_complex_panic2() {
+    fn test_make_complex_error2() {
         let d = SInteger(1);
         let p = atom!("Picard");
         let t = atom!("Earl Grey");
         let terms = vec![d, p, t];
-        make_complex(terms);
+        assert_eq!("make_complex() - First term must be an Atom: 1",
+                   make_complex(terms).unwrap_err());
+    }
+
+    /// Invalid queries and rules should produce errors, not panics.
+    #[test]
+    fn test_invalid_query_and_rule() {
+        assert!(parse_query("").is_err());
+        assert_eq!("parse_rule() - Head of rule must be a complex term: >$X = a :- b.<",
+                   parse_rule("$X = a :- b.").unwrap_err());
     }
 
     /// parse_functor_terms() should create a valid complex term.
@@ -448,7 +480,7 @@ mod test {
         match parse_complex("punctuation(comma, \\,)") {
             Ok(c) => {
                 if matches!(c, Unifiable::SComplex(_)) {
-                    assert_eq!("punctuation(comma, ,)", c.to_string());
+                    assert_eq!("punctuation(comma, ',')", c.to_string());
                 } else {
                     panic!("parse_complex() - Should create a complex term: {}", c);
                 }
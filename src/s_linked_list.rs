@@ -32,8 +32,8 @@
 
 use std::rc::Rc;
 
+use super::error::SuironError;
 use super::unifiable::{*, Unifiable::*};
-use super::logic_var::*;
 use super::parse_terms::*;
 use super::substitution_set::*;
 
@@ -98,7 +98,7 @@ pub fn make_linked_list<'a>(vbar: bool, mut terms: Vec<Unifiable>) -> Unifiable
                 // to add it to the tail.
                 if Nil == *t { tail = Nil; }
                 else {
-                    tail = cons_node!(*t, *n, c, tf);
+                    tail = SLinkedList{term: t, next: n, count: c, tail_var: tf};
                     num = c + 1;
                 }
                 tail_var = false;
@@ -162,12 +162,15 @@ pub fn make_linked_list<'a>(vbar: bool, mut terms: Vec<Unifiable>) -> Unifiable
 ///
 pub fn equal_escape(vec_chars: &Vec<char>, index: usize, ch: char) -> bool {
     if vec_chars[index] == ch {
-        if index > 0 {
-            if vec_chars[index - 1] == '\\' {
-                return false;
-            }
+        // Count preceding backslashes. The character is escaped if
+        // there is an odd number of them. (\\ is an escaped backslash.)
+        let mut count = 0;
+        let mut i = index;
+        while i > 0 && vec_chars[i - 1] == '\\' {
+            count += 1;
+            i -= 1;
         }
-        return true;
+        return count % 2 == 0;
     }
     false
 } // equal_escape()
@@ -185,7 +188,7 @@ pub fn equal_escape(vec_chars: &Vec<char>, index: usize, ch: char) -> bool {
 /// let list = parse_linked_list("[a, b, c | $X]");
 /// </blockquote>
 ///
-pub fn parse_linked_list(to_parse: &str) -> Result<Unifiable, String> {
+pub fn parse_linked_list(to_parse: &str) -> Result<Unifiable, SuironError> {
 
     let s = to_parse.trim();
 
@@ -194,19 +197,19 @@ pub fn parse_linked_list(to_parse: &str) -> Result<Unifiable, String> {
 
     if length_chars < 2 {
         let err = pll_error("String is too short", s);
-        return Err(err);
+        return Err(err.into());
     }
 
     let first = the_chars[0];
     if first != '[' {
         let err = pll_error("Missing opening bracket [", s);
-        return Err(err);
+        return Err(err.into());
     }
 
     let last = the_chars[length_chars - 1];
     if last != ']' {
         let err = pll_error("Missing closing bracket ]", s);
-        return Err(err);
+        return Err(err.into());
     }
 
     let mut list = cons_node!(Nil, Nil, 0, false);  // Make an empty list.
@@ -219,19 +222,20 @@ pub fn parse_linked_list(to_parse: &str) -> Result<Unifiable, String> {
     let mut vbar = false;
     let mut end_index = length_args;
 
-    let mut open_quote   = false;
     let mut num_quotes   = 0;
     let mut round_depth  = 0;   // depth of round parentheses (())
     let mut square_depth = 0;   // depth of square brackets [[]]
 
+    let quoted = quoted_text(&arguments_chars);
+    let marks  = quote_marks(&arguments_chars);
+
     let mut i = (length_args - 1) as i32;
     loop {
 
         let ind = i as usize;
 
-        if open_quote {
-            if equal_escape(&arguments_chars, ind, '"') {
-                open_quote = false;
+        if quoted[ind] {
+            if marks[ind] && round_depth == 0 && square_depth == 0 {
                 num_quotes += 1;
             }
         }
@@ -245,20 +249,17 @@ pub fn parse_linked_list(to_parse: &str) -> Result<Unifiable, String> {
             } else if equal_escape(&arguments_chars, ind, '(') {
                  round_depth -= 1;
             } else if round_depth == 0 && square_depth == 0 {
-                if equal_escape(&arguments_chars, ind, '"') {
-                    open_quote = true;  // first quote
-                    num_quotes += 1;
-                } else if equal_escape(&arguments_chars, ind, ',') {
+                if equal_escape(&arguments_chars, ind, ',') {
 
                     let s2 = chars_to_string!(&arguments_chars[ind + 1..end_index]);
                     let s2 = s2.trim();
 
                     if s2.len() == 0 {
                         let err = pll_error("Missing argument", s);
-                        return Err(err);
+                        return Err(err.into());
                     }
                     if let Some(error_message) = check_quotes(&s2, num_quotes) {
-                        return Err(error_message);
+                        return Err(error_message.into());
                     }
                     match parse_term(s2) {
                         Ok(term) => {
@@ -275,25 +276,28 @@ pub fn parse_linked_list(to_parse: &str) -> Result<Unifiable, String> {
                 if equal_escape(&arguments_chars, ind, '|') {
                     if vbar {
                         let err = pll_error("Too many vertical bars", s);
-                        return Err(err);
+                        return Err(err.into());
                     }
                     let term_str =
                             chars_to_string!(&arguments_chars[ind + 1..end_index]);
                     let term_str2 = term_str.trim();
                     if term_str2.len() == 0 {
                         let err = pll_error("Missing argument", s);
-                        return Err(err);
+                        return Err(err.into());
                     }
 
-                    match make_logic_var(term_str2.to_string()) {
-                        Err(_) => {
+                    // In Prolog syntax, the variable may be capitalized: [H | T]
+                    // The tail may be anonymous: [$H | $_]
+                    match parse_term(term_str2) {
+                        Ok(var @ Unifiable::LogicVar{..}) |
+                        Ok(var @ Unifiable::Anonymous) => {
+                            list = link_front(var, true, list);
+                        },
+                        _ => {
                             let err = pll_error(
                                      "Require variable after vertical bar", s);
-                            return Err(err);
+                            return Err(err.into());
                         },
-                        Ok(var) => {
-                            list = link_front(var, true, list);
-                        }
                     }
                     vbar = true;
                     end_index = ind;
@@ -307,10 +311,10 @@ pub fn parse_linked_list(to_parse: &str) -> Result<Unifiable, String> {
 
             if s2.len() == 0 {
                 let err = pll_error("Missing argument", s);
-                return Err(err);
+                return Err(err.into());
             }
             if let Some(error_message) = check_quotes(&s2, num_quotes) {
-                return Err(error_message);
+                return Err(error_message.into());
             }
             match parse_term(s2) {
                 Ok(term) => { return Ok(link_front(term, false, list)); },
@@ -588,6 +592,7 @@ fn pll_error(err: &str, bad: &str) -> String {
 #[cfg(test)]
 mod test {
 
+    use std::rc::Rc;
     use crate::*;
 
     // pll - Formats an error message with prefix.
@@ -709,7 +714,37 @@ mod test {
             },
         }
 
+        match parse_linked_list("[a | b]") {
+            Err(msg) => {
+                assert_eq!(msg, pll("Require variable after vertical bar: [a | b]"));
+            },
+            _ => { panic!("{}", pll("Tail must be a variable.")); },
+        }
+
     } // test_parse_linked_list()
+
+    // The tail of a list can be an anonymous variable.
+    #[test]
+    fn test_anonymous_tail() {
+
+        let list = parse_linked_list("[a, b | $_]").unwrap();
+        assert_eq!("[a, b | $_]", list.to_string());
+
+        let mut kb = KnowledgeBase::new();
+        add_rules!(&mut kb, parse_rule("g([$X | $_], $X).").unwrap());
+        set_prolog_syntax(true);
+        let rule = parse_rule("first([H|_], H).");
+        set_prolog_syntax(false);
+        add_rules!(&mut kb, rule.unwrap());
+
+        let query = parse_query("g([1, 2, 3], $A)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$A = 1", solve(sn));
+        let query = parse_query("first([x, y], $F)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$F = x", solve(sn));
+
+    } // test_anonymous_tail()
 }
 
 
@@ -17,14 +17,105 @@
 
 use std::fmt;
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::time::Instant;
 
 use crate::*;
 
 use super::goal::Goal;
 use super::substitution_set::*;
+use super::substitution_set::NodePoolGuard;
 use super::knowledge_base::*;
+use super::query_config::ConfigGuard;
+
+thread_local! {
+    // Maximum number of goals which can be nested in a proof. None means no limit.
+    static MAX_DEPTH: Cell<Option<usize>> = const { Cell::new(None) };
+    // Set true when a search is abandoned because it exceeded MAX_DEPTH.
+    static DEPTH_LIMIT_EXCEEDED: Cell<bool> = const { Cell::new(false) };
+    // Goals which are nested more deeply than this fail. (For iterative deepening.)
+    static DEPTH_BOUND: Cell<Option<usize>> = const { Cell::new(None) };
+    // Set true when a goal is cut off by DEPTH_BOUND.
+    static DEPTH_BOUND_REACHED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets the maximum proof depth, the number of goals which can be
+/// nested while searching for a solution.
+///
+/// A query which goes deeper than this, such as a query for a
+/// left-recursive rule, is abandoned, and the solve functions report a
+/// [DepthLimitExceeded](../error/enum.SuironError.html#variant.DepthLimitExceeded)
+/// error. Rules which are tail recursive reuse their solution nodes,
+/// so they do not go deeper with each call. By default, there is no limit.
+///
+/// The setting is local to the thread. A query which has its own
+/// [QueryConfig](../query_config/struct.QueryConfig.html), such as
+/// the queries of an [Engine](../engine/struct.Engine.html), uses the
+/// maximum depth of its configuration instead.
+///
+/// # Arguments
+/// * maximum depth, or None
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// add_rules!(&mut kb, parse_rule("ancestor($X, $Y) :- ancestor($X, $Z), parent($Z, $Y).").unwrap());
+///
+/// set_max_depth(Some(1000));
+/// let query = parse_query("ancestor(a, $Y)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+/// let result = try_solve(sn);
+/// assert!(matches!(result, Err(SuironError::DepthLimitExceeded(_))));
+/// set_max_depth(None);
+/// ```
+pub fn set_max_depth(depth: Option<usize>) {
+    MAX_DEPTH.with(|d| d.set(depth));
+}
+
+/// Gets the maximum proof depth.
+/// See [set_max_depth()](fn.set_max_depth.html).
+///
+/// # Return
+/// * maximum depth, or None
+pub fn max_depth() -> Option<usize> {
+    MAX_DEPTH.with(|d| d.get())
+}
+
+/// Determines whether the last search for a solution was abandoned
+/// because it exceeded the maximum proof depth.
+///
+/// # Return
+/// * true/false
+pub fn depth_limit_exceeded() -> bool {
+    DEPTH_LIMIT_EXCEEDED.with(|e| e.get())
+}
+
+// Records whether a search exceeded the maximum proof depth.
+pub(crate) fn set_depth_limit_exceeded(exceeded: bool) {
+    DEPTH_LIMIT_EXCEEDED.with(|e| e.set(exceeded));
+}
+
+// Sets the depth bound of an iterative-deepening search.
+pub(crate) fn set_depth_bound(bound: Option<usize>) {
+    DEPTH_BOUND.with(|b| b.set(bound));
+}
+
+// Gets the depth bound of an iterative-deepening search.
+pub(crate) fn depth_bound() -> Option<usize> {
+    DEPTH_BOUND.with(|b| b.get())
+}
+
+// Determines whether a goal was cut off by the depth bound.
+pub(crate) fn depth_bound_reached() -> bool {
+    DEPTH_BOUND_REACHED.with(|r| r.get())
+}
+
+// Records whether a goal was cut off by the depth bound.
+pub(crate) fn set_depth_bound_reached(reached: bool) {
+    DEPTH_BOUND_REACHED.with(|r| r.set(reached));
+}
 
 /// Represents a node in a proof tree.
 ///
@@ -54,6 +145,15 @@ pub struct SolutionNode<'a> {
     pub rule_index: usize,
     /// The number of facts and rules for the goal above. (For Complex goals.)
     pub number_facts_rules: usize,
+    /// Indices of the facts and rules which may match the goal, if the
+    /// knowledge base is indexed. (For Complex goals.)
+    pub candidates: Option<Rc<Vec<usize>>>,
+    /// Facts and rules fetched from an external predicate. (For Complex goals.)
+    pub external_rules: Option<Rc<Vec<Rule>>>,
+    /// The highest logic variable ID before the variables of the current
+    /// fact or rule were numbered. (For Complex goals.) This allows the
+    /// variables to be numbered again, to rebuild a proof.
+    pub rule_var_base: usize,
 
     // For And/Or Solution Nodes.
     /// Head solution node.
@@ -66,6 +166,33 @@ pub struct SolutionNode<'a> {
     /// Flag for built-in predicates, which have only 1 solution.
     pub more_solutions: bool,
 
+    /// Solves the goal with a binding trail, instead of substitution sets.
+    /// (See [set_binding_strategy()](../trail/fn.set_binding_strategy.html).)
+    pub trail_solver: Option<Rc<RefCell<TrailSolver<'a>>>>,
+
+    /// Solves the goal by iterative deepening.
+    /// (See [set_search_strategy()](../iterative_deepening/fn.set_search_strategy.html).)
+    pub deepening_solver: Option<Rc<RefCell<DeepeningSolver<'a>>>>,
+
+    /// Flag which allows the node to be reused for its last goal.
+    /// (Last-call optimization.) A base node is not reused, because
+    /// its goal is the query.
+    pub reusable: bool,
+
+    /// Cancels the search. Only the base node has a token.
+    /// (See [cancellation_token()](../cancellation/fn.cancellation_token.html).)
+    pub cancellation: Option<CancellationToken>,
+
+    /// Settings of the query, such as the maximum proof depth. Only the
+    /// base node has a configuration. If there is none, the settings of
+    /// the thread apply.
+    /// (See [set_query_config()](../query_config/fn.set_query_config.html).)
+    pub config: Option<QueryConfig>,
+
+    /// Generates the IDs of logic variables. All the nodes of a
+    /// query share the same counter.
+    pub var_ids: VarIds,
+
 } // SolutionNode
 
 impl<'a> SolutionNode<'a> {
@@ -86,6 +213,14 @@ impl<'a> SolutionNode<'a> {
     /// ```
     #[inline]
     pub fn new(goal: Rc<Goal>, kb: &'a KnowledgeBase) -> Self {
+        SolutionNode::with_var_ids(goal, kb, VarIds::new())
+    } // new()
+
+    // Creates a new SolutionNode, which gets variable IDs from
+    // the given counter.
+    #[inline]
+    pub(crate) fn with_var_ids(goal: Rc<Goal>, kb: &'a KnowledgeBase,
+                               var_ids: VarIds) -> Self {
         SolutionNode {
             goal, kb,
             parent_node: None,
@@ -94,12 +229,21 @@ impl<'a> SolutionNode<'a> {
             child: None,
             rule_index: 0,
             number_facts_rules: 0,
+            candidates: None,
+            external_rules: None,
+            rule_var_base: 0,
             head_sn: None,
             tail_sn: None,
             operator_tail: None,
             more_solutions: true,
+            trail_solver: None,
+            deepening_solver: None,
+            reusable: false,
+            cancellation: None,
+            config: None,
+            var_ids,
         }
-    } // new()
+    } // with_var_ids()
 
     /// Sets the no_backtracking flag to true.
     ///
@@ -203,8 +347,121 @@ fn no_backtracking(sn: &Rc<RefCell<SolutionNode>>) -> bool {
 pub fn next_solution<'a>(sn: Rc<RefCell<SolutionNode<'a>>>)
                          -> Option<Rc<SubstitutionSet<'a>>> {
 
-    if no_backtracking(&sn) { return None; }
-    let goal = get_goal(&sn);
+    // The query's settings are in effect until the search stops.
+    let _config = ConfigGuard::new(&sn);
+    let _pool = NodePoolGuard::new();
+
+    // Instead of calling itself recursively for child nodes, this
+    // function keeps a stack of the nodes which are being solved.
+    // Thus, the depth of recursion in a Suiron program is limited
+    // by the heap, not by the stack of the host program.
+    let mut stack: Vec<Frame<'a>> = vec![Frame{ sn, resume: Resume::Start }];
+    let mut result: Option<Rc<SubstitutionSet<'a>>> = None;
+
+    // Each frame on the stack is a goal which is being solved.
+    let max = max_depth().unwrap_or(usize::MAX);
+    let bound = depth_bound().unwrap_or(usize::MAX);
+    set_depth_limit_exceeded(false);
+
+    // The token is checked before each step.
+    let token = stack[0].sn.borrow().cancellation.clone();
+
+    while let Some(frame) = stack.pop() {
+        if let Some(token) = &token {
+            if token.is_stopped() { return None; }
+        }
+        match step(&frame.sn, frame.resume, result.take()) {
+            Step::Call(child_sn, resume) => {
+                if stack.len() + 2 > bound {
+                    set_depth_bound_reached(true);
+                    // The goal of not() cannot be shown to fail.
+                    if resume == Resume::Not { return None; }
+                    // The child is cut off, so it fails.
+                    stack.push(Frame{ sn: frame.sn, resume });
+                    continue;
+                }
+                if stack.len() + 2 > max {
+                    set_depth_limit_exceeded(true);
+                    return None;
+                }
+                stack.push(Frame{ sn: frame.sn, resume });
+                stack.push(Frame{ sn: child_sn, resume: Resume::Start });
+            },
+            Step::Return(solution) => { result = solution; },
+            // The node was reused for a last call. Solve its new goal.
+            Step::Restart => {
+                stack.push(Frame{ sn: frame.sn, resume: Resume::Start });
+            },
+        }
+    }
+    return result;
+
+} // next_solution()
+
+/// Defines where the search for a solution resumes, when a
+/// child node returns its solution.
+/// (See [next_solution()](fn.next_solution.html).)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Resume {
+    /// Start (or continue) the search of a node.
+    Start,
+    /// After the child node of a complex goal.
+    Child,
+    /// After the head node of an And node.
+    AndHead,
+    /// After the tail node of an And node.
+    AndTail,
+    /// After the head node of an Or node.
+    OrHead,
+    /// Return the solution of the child node.
+    PassOn,
+    /// After the goal of time().
+    Time(Instant),
+    /// After the goal of not().
+    Not,
+}
+
+/// The result of one step in the search for a solution.
+/// (See [next_solution()](fn.next_solution.html).)
+pub(crate) enum Step<'a> {
+    /// Solve a child node, then resume the search of this node.
+    Call(Rc<RefCell<SolutionNode<'a>>>, Resume),
+    /// Return a solution, or None, to the parent node.
+    Return(Option<Rc<SubstitutionSet<'a>>>),
+    /// The node was reused for a new goal (last call); start again.
+    Restart,
+}
+
+// A node on the stack of next_solution().
+struct Frame<'a> {
+    sn: Rc<RefCell<SolutionNode<'a>>>,
+    resume: Resume,
+}
+
+// Does one step in the search for a solution of a node.
+//
+// Arguments
+//    solution node
+//    where to resume the search
+//    solution of the child node which was called, if any
+// Return
+//    Step - call a child, return a solution, or restart
+fn step<'a>(sn: &Rc<RefCell<SolutionNode<'a>>>, resume: Resume,
+            child_solution: Option<Rc<SubstitutionSet<'a>>>) -> Step<'a> {
+
+    if resume == Resume::Start {
+        if no_backtracking(sn) { return Step::Return(None); }
+        let trail_solver = sn.borrow().trail_solver.clone();
+        if let Some(solver) = trail_solver {
+            return Step::Return(solver.borrow_mut().next_solution());
+        }
+        let deepening_solver = sn.borrow().deepening_solver.clone();
+        if let Some(solver) = deepening_solver {
+            return Step::Return(solver.borrow_mut().next_solution());
+        }
+    }
+
+    let goal = get_goal(sn);
 
     match &*goal {
 
@@ -213,27 +470,29 @@ pub fn next_solution<'a>(sn: Rc<RefCell<SolutionNode<'a>>>)
             match op {
 
                 Operator::And(_) => {
-                    return next_solution_and(sn);
+                    return step_and(sn, resume, child_solution);
                 },
                 Operator::Or(_) => {
-                    return next_solution_or(sn);
+                    return step_or(sn, resume, child_solution);
                 },
 
                 Operator::Time(_) => {
 
+                    if let Resume::Time(now) = resume {
+                        print_elapsed(now);
+                        return Step::Return(child_solution);
+                    }
+
                     let mut sn_ref = sn.borrow_mut();
-                    if !sn_ref.more_solutions { return None; };
+                    if !sn_ref.more_solutions { return Step::Return(None); };
                     sn_ref.more_solutions = false;
 
                     match &sn_ref.head_sn {
                         Some(head_sn) => {
                             let now = Instant::now();
-                            let solution = next_solution(Rc::clone(&head_sn));
-                            print_elapsed(now);
-                            return solution;
+                            return Step::Call(Rc::clone(head_sn), Resume::Time(now));
                         },
-                        None => { panic!("next_solution() - \
-                                  Missing solution node. Should not happen."); },
+                        None => { return Step::Return(None); },  // Should not happen.
                     } // match
 
                 }, // Time
@@ -241,21 +500,24 @@ pub fn next_solution<'a>(sn: Rc<RefCell<SolutionNode<'a>>>)
                 Operator::Not(_) => {
 
                     let mut sn_ref = sn.borrow_mut();
-                    if !sn_ref.more_solutions { return None; };
+
+                    if resume == Resume::Not {
+                        match child_solution {
+                            Some(_) => { return Step::Return(None); },
+                            None => {
+                                sn_ref.more_solutions = false;
+                                return Step::Return(Some(Rc::clone(&sn_ref.ss)));
+                            },
+                        }
+                    }
+
+                    if !sn_ref.more_solutions { return Step::Return(None); };
 
                     match &sn_ref.head_sn {
                         Some(head_sn) => {
-                            let solution = next_solution(Rc::clone(&head_sn));
-                            match solution {
-                                Some(_) => return None,
-                                None => {
-                                    sn_ref.more_solutions = false;
-                                    return Some(Rc::clone(&sn_ref.ss));
-                                },
-                            }
+                            return Step::Call(Rc::clone(head_sn), Resume::Not);
                         },
-                        None => { panic!("next_solution() - \
-                                  Missing solution node. Should not happen."); },
+                        None => { return Step::Return(None); },  // Should not happen.
                     } // match
 
                 }, // Not
@@ -268,57 +530,142 @@ pub fn next_solution<'a>(sn: Rc<RefCell<SolutionNode<'a>>>)
 
             let mut sn_ref = sn.borrow_mut();
 
-            // Check for a child solution.
-            match &sn_ref.child {
-                None => {},
-                Some(child_sn) => {
-                    let solution = next_solution(Rc::clone(&child_sn));
-                    if solution.is_some() { return solution; }
+            match resume {
+                // Check for a child solution.
+                Resume::Start => {
+                    if let Some(child_sn) = &sn_ref.child {
+                        return Step::Call(Rc::clone(child_sn), Resume::Child);
+                    }
+                },
+                _ => {
+                    if child_solution.is_some() { return Step::Return(child_solution); }
                 },
             }
 
             sn_ref.child = None;
             loop {
 
-                if sn_ref.rule_index >= sn_ref.number_facts_rules { return None; }
+                if sn_ref.rule_index >= sn_ref.number_facts_rules {
+                    return Step::Return(None);
+                }
 
-                // The fallback_id saves the logic variable ID (LOGIC_VAR_ID),
+                // The fallback_id saves the logic variable ID of the query,
                 // in case the next rule fails. Restoring this id will keep
                 // the length of the substitution set as short as possible.
-                let fallback_id = get_var_id();
-
-                let pred_name = sn_ref.goal.key();
-                let rule = get_rule(sn_ref.kb, &pred_name, sn_ref.rule_index);
+                let fallback_id = sn_ref.var_ids.get();
+
+                let pred_name = match cmplx.key() {
+                    Ok(key) => { key },
+                    Err(_) => { return Step::Return(None); },
+                };
+                let index = match &sn_ref.candidates {
+                    Some(candidates) => { candidates[sn_ref.rule_index] },
+                    None => { sn_ref.rule_index },
+                };
+                let rule = match &sn_ref.external_rules {
+                    Some(rules) => {
+                        let mut vars = VarMap::with_ids(&sn_ref.var_ids);
+                        rules[index].clone().recreate_variables(&mut vars)
+                    },
+                    None => {
+                        match fetch_rule(sn_ref.kb, &pred_name, index, &sn_ref.var_ids) {
+                            Some(rule) => { rule },
+                            None => { return Step::Return(None); },
+                        }
+                    },
+                };
                 sn_ref.rule_index += 1;
 
                 let head = rule.get_head();
                 let solution = head.unify(&cmplx, &sn_ref.ss);
 
                 match solution {
-                    None => { set_var_id(fallback_id); },  // Restore fallback ID.
+                    None => { sn_ref.var_ids.set(fallback_id); },  // Restore fallback ID.
                     Some(ss) => {
+                        sn_ref.rule_var_base = fallback_id;
                         let body = rule.get_body();
-                        if body == Goal::Nil { return Some(ss); }
-                        let child_sn = make_solution_node(Rc::new(body),
-                                                          sn_ref.kb, ss,
-                                                          Rc::clone(&sn));
+                        if body == Goal::Nil { return Step::Return(Some(ss)); }
+                        // If this is the last rule, the node is reused for its body.
+                        if sn_ref.reusable &&
+                           sn_ref.rule_index >= sn_ref.number_facts_rules {
+                            drop(sn_ref);
+                            reuse_solution_node(sn, Rc::new(body), ss);
+                            return Step::Restart;
+                        }
+                        let child_sn = make_child_node(Rc::new(body),
+                                                       sn_ref.kb, ss, Rc::clone(sn),
+                                                       sn_ref.var_ids.clone());
                         sn_ref.child = Some(Rc::clone(&child_sn));
-                        let child_solution = next_solution(child_sn);
-                        if child_solution.is_some() { return child_solution; }
+                        return Step::Call(child_sn, Resume::Child);
                     },
                 } // match
             }
         },
 
         Goal::BuiltInGoal(built_in_predicate) => {
-            return next_solution_bip(sn, built_in_predicate.clone());
+            let solution = next_solution_bip(Rc::clone(sn), built_in_predicate.clone());
+            return Step::Return(solution);
         },
 
-        _ => panic!("next_solution() - Implement this."),
+        // An empty goal (the body of a fact) succeeds once.
+        Goal::Nil => {
+            let mut sn_ref = sn.borrow_mut();
+            if !sn_ref.more_solutions { return Step::Return(None); };
+            sn_ref.more_solutions = false;
+            return Step::Return(Some(Rc::clone(&sn_ref.ss)));
+        },
 
     } // match self
 
-} // next_solution()
+} // step()
+
+/// Determines whether a solution node has no more solutions.
+///
+/// A node which has no more solutions can be replaced by the goals
+/// which follow it. (See next_solution_and().) If this function
+/// returns false, the node may or may not have more solutions.
+/// To keep the function fast, the child nodes of complex goals are
+/// not checked, and the subgoals of And nodes are checked only to a
+/// depth of MAX_CHECK_DEPTH.
+///
+/// # Arguments
+/// * solution node
+/// # Return
+/// * true if the node has no more solutions
+pub fn no_more_solutions(sn: &Rc<RefCell<SolutionNode>>) -> bool {
+    return no_more_solutions_depth(sn, MAX_CHECK_DEPTH);
+} // no_more_solutions()
+
+// How deeply no_more_solutions() checks nested And nodes.
+const MAX_CHECK_DEPTH: usize = 4;
+
+// Determines whether a solution node has no more solutions.
+//
+// Arguments
+//    solution node
+//    depth - how deeply to check nested And nodes
+// Return
+//    true if the node has no more solutions
+fn no_more_solutions_depth(sn: &Rc<RefCell<SolutionNode>>, depth: usize) -> bool {
+    let sn_ref = sn.borrow();
+    if sn_ref.no_backtracking { return true; }
+    if sn_ref.trail_solver.is_some() || sn_ref.deepening_solver.is_some() { return false; }
+    match &*sn_ref.goal {
+        Goal::OperatorGoal(Operator::And(_)) => {
+            if depth == 0 { return false; }
+            let done = |node: &Option<Rc<RefCell<SolutionNode>>>| {
+                node.as_ref().map_or(true, |n| no_more_solutions_depth(n, depth - 1))
+            };
+            return done(&sn_ref.head_sn) && done(&sn_ref.tail_sn);
+        },
+        Goal::OperatorGoal(Operator::Or(_)) => { return false; },
+        Goal::ComplexGoal(_) => {
+            return sn_ref.rule_index >= sn_ref.number_facts_rules &&
+                   sn_ref.child.is_none();
+        },
+        _ => { return !sn_ref.more_solutions; },
+    }
+} // no_more_solutions_depth()
 
 
 /// A utility for printing elapsed time.
@@ -403,7 +750,6 @@ mod test {
 
     use crate::*;
     use std::rc::Rc;
-    use serial_test::serial;
 
     // Test the set_no_backtracking() function.
     // Some rules:
@@ -412,10 +758,8 @@ mod test {
     //    test1($X) :- get_value($X), $X == 2.    // one solution
     //    test2($X) :- get_value($X), !, $X == 2. // no solutions
     #[test]
-    #[serial]
     fn test_set_no_backtracking() {
 
-        start_query();
         let mut kb = KnowledgeBase::new();
         let rule1 = parse_rule("get_value($X) :- $X = 1.").unwrap();
         let rule2 = parse_rule("get_value($X) :- $X = 2.").unwrap();
@@ -445,17 +789,23 @@ mod test {
 
     }  // test_set_no_backtracking()
 
+    // An empty goal should succeed once, rather than panic.
+    #[test]
+    fn test_nil_goal() {
+        let kb = test_kb();
+        let sn = make_base_node(Rc::new(Goal::Nil), &kb);
+        assert!(next_solution(Rc::clone(&sn)).is_some());
+        assert!(next_solution(sn).is_none());
+    }  // test_nil_goal()
+
     // The test knowledge base has two predicates named love/2.
     // This test function makes a query about who loves whom, and
     // a corresponding solution node. The method next_solution()
     // is called three times to confirm that all valid solutions
     // can be found.
     #[test]
-    #[serial]
     fn test_next_solution1() {
 
-        start_query();  // SUIRON_STOP_QUERY = false, LOGIC_VAR_ID = 0
-
         let kb = test_kb();
 
         // Make a solution node for love.
@@ -491,10 +841,8 @@ mod test {
     // called twice to confirm that all valid solutions can be found.
 
     #[test]
-    #[serial]
     fn test_next_solution2() {
 
-        start_query();  // SUIRON_STOP_QUERY = false, LOGIC_VAR_ID = 0
         let kb = test_kb();
 
         // Make a solution node to find grandfathers.
@@ -517,4 +865,88 @@ mod test {
 
     } // test_next_solution2()
 
+    // A deterministic recursive rule reuses its solution nodes
+    // (last-call optimization), so it does not overflow the stack.
+    //     countdown(0).
+    //     countdown($N) :- $N > 0, $M = $N - 1, countdown($M).
+    #[test]
+    fn test_last_call() {
+
+        let mut kb = KnowledgeBase::new();
+        add_rules!(&mut kb,
+            parse_rule("countdown(0).").unwrap(),
+            parse_rule("countdown($N) :- $N > 0, $M = $N - 1, countdown($M).").unwrap());
+
+        let query = parse_query("countdown(20000)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert!(next_solution(Rc::clone(&sn)).is_some());
+        assert!(next_solution(Rc::clone(&sn)).is_none());
+
+        // The query is not replaced by the body of the rule.
+        assert_eq!("countdown(20000)", sn.borrow().goal.to_string());
+
+    } // test_last_call()
+
+    // next_solution() keeps its own stack of nodes, so a recursive
+    // rule which is not tail recursive does not overflow the stack.
+    #[test]
+    fn test_deep_recursion() {
+
+        let mut kb = KnowledgeBase::new();
+        add_rules!(&mut kb,
+            parse_rule("count_up(0, 0).").unwrap(),
+            parse_rule("count_up($N, $S) :- $N > 0, $M = $N - 1, \
+                        count_up($M, $S1), $S = $S1 + 1.").unwrap());
+
+        let query = parse_query("count_up(20000, $S)").unwrap();
+        let q = Rc::new(query);
+        let sn = make_base_node(Rc::clone(&q), &kb);
+        match next_solution(sn) {
+            Some(ss) => {
+                assert_eq!("count_up(20000, 20000)", q.replace_variables(&ss).to_string());
+            },
+            None => { panic!("count_up() should succeed."); },
+        }
+
+    } // test_deep_recursion()
+
+    // A left-recursive rule never finds a solution. When a maximum
+    // proof depth is set, the query is abandoned with an error.
+    #[test]
+    fn test_max_depth() {
+
+        let mut kb = KnowledgeBase::new();
+        add_rules!(&mut kb,
+            parse_rule("parent(a, b).").unwrap(),
+            parse_rule("ancestor($X, $Y) :- ancestor($X, $Z), parent($Z, $Y).").unwrap(),
+            parse_rule("count_up(0, 0).").unwrap(),
+            parse_rule("count_up($N, $S) :- $N > 0, $M = $N - 1, \
+                        count_up($M, $S1), $S = $S1 + 1.").unwrap());
+
+        set_max_depth(Some(1000));
+        for strategy in [BindingStrategy::Persistent, BindingStrategy::Trail] {
+
+            set_binding_strategy(strategy);
+
+            let query = parse_query("ancestor(a, $Y)").unwrap();
+            let sn = make_base_node(Rc::new(query), &kb);
+            match try_solve(sn) {
+                Err(SuironError::DepthLimitExceeded(msg)) => {
+                    assert_eq!("Query exceeded the maximum proof depth of 1000.", msg);
+                },
+                other => { panic!("Expected DepthLimitExceeded: {:?}", other); },
+            }
+
+            // A shallower query is not affected.
+            let query = parse_query("count_up(20, $S)").unwrap();
+            let sn = make_base_node(Rc::new(query), &kb);
+            let result = try_solve(sn).unwrap();
+            assert_eq!(Some("$S = 20".to_string()), result);
+        }
+
+        set_binding_strategy(BindingStrategy::Persistent);
+        set_max_depth(None);
+
+    } // test_max_depth()
+
 } // test
@@ -1,7 +1,8 @@
 //! Functions to support logical And and Or operators.
 //!
-//! This module contains the functions next_solution_and() and next_solution_or(),
-//! which are called by next_solution() in solution_node.rs.
+//! This module contains the functions next_solution_and() and next_solution_or().
+//! The search steps for And and Or nodes, step_and() and step_or(), are called
+//! by next_solution() in solution_node.rs.
 //!
 // Cleve Lendon 2023
 
@@ -11,6 +12,7 @@ use std::cell::RefCell;
 use crate::*;
 
 use super::goal::Goal;
+use super::solution_node::{Resume, Step};
 use super::substitution_set::*;
 
 /// Calls next_solution() on all subgoals of the And operator.
@@ -24,56 +26,7 @@ use super::substitution_set::*;
 /// [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
 pub fn next_solution_and<'a>(sn: Rc<RefCell<SolutionNode<'a>>>)
                              -> Option<Rc<SubstitutionSet<'a>>> {
-
-    let mut sn_ref = sn.borrow_mut(); // Get a mutable reference.
-
-    // Check for the tail solution.
-    if let Some(tail_sn) = &sn_ref.tail_sn {
-        if let Some(ss) = next_solution(Rc::clone(&tail_sn)) {
-            return Some(ss);
-        }
-    }
-
-    let mut solution = match &sn_ref.head_sn {
-        None => { return None; },
-        Some(head_sn) => { next_solution(Rc::clone(&head_sn)) },
-    };
-
-    loop {
-
-        match solution {
-
-            None => { return None; },
-            Some(ss) => {
-
-                // print_ss(&ss); // For debugging.
-                match &sn_ref.operator_tail {
-                    None => { return Some(ss); },
-                    Some(tail) => {
-
-                        if tail.len() == 0 { return Some(ss); }
-
-                        // Tail solution node has to be an And solution node.
-                        let tail_goal = Goal::OperatorGoal(tail.clone());
-                        let tail_sn = make_solution_node(Rc::new(tail_goal),
-                                                         sn_ref.kb, ss,
-                                                         Rc::clone(&sn));
-                        sn_ref.tail_sn = Some(Rc::clone(&tail_sn));
-                        let tail_solution = next_solution(tail_sn);
-                        if tail_solution.is_some() { return tail_solution; }
-                    },
-                } // match
-            },
-        } // match solution
-
-        // Try another solution.
-        solution = match &sn_ref.head_sn {
-            None => { return None; },
-            Some(head_sn) => { next_solution(Rc::clone(&head_sn)) },
-        };
-
-    } // loop
-
+    return next_solution(sn);
 } // next_solution_and()
 
 /// Calls next_solution() on subgoals of the Or operator.
@@ -87,42 +40,140 @@ pub fn next_solution_and<'a>(sn: Rc<RefCell<SolutionNode<'a>>>)
 /// [SubstitutionSet](../substitution_set/type.SubstitutionSet.html) or None
 pub fn next_solution_or<'a>(sn: Rc<RefCell<SolutionNode<'a>>>)
                             -> Option<Rc<SubstitutionSet<'a>>> {
+    return next_solution(sn);
+} // next_solution_or()
+
+// Does one step in the search for a solution of an And node.
+//
+// The head node is solved first. For each solution of the head,
+// a tail node is made for the remaining subgoals, and solved.
+//
+// Arguments
+//    And solution node
+//    where to resume the search
+//    solution of the child node which was called, if any
+// Return
+//    Step
+pub(crate) fn step_and<'a>(sn: &Rc<RefCell<SolutionNode<'a>>>, resume: Resume,
+                           child_solution: Option<Rc<SubstitutionSet<'a>>>)
+                           -> Step<'a> {
 
     let mut sn_ref = sn.borrow_mut(); // Get a mutable reference.
 
-    // Check for the tail solution.
-    if let Some(tail_sn) = &sn_ref.tail_sn {
-        return next_solution(Rc::clone(&tail_sn));
+    match resume {
+        Resume::Start => {
+            // Check for the tail solution.
+            if let Some(tail_sn) = &sn_ref.tail_sn {
+                return Step::Call(Rc::clone(tail_sn), Resume::AndTail);
+            }
+            return call_head(&sn_ref);
+        },
+        Resume::AndTail => {
+            if child_solution.is_some() { return Step::Return(child_solution); }
+            // Try another solution.
+            return call_head(&sn_ref);
+        },
+        _ => {},
     }
 
-    let solution = match &sn_ref.head_sn {
-        None => { return None; },
-        Some(head_sn) => { next_solution(Rc::clone(&head_sn)) },
+    // Resume::AndHead
+    let ss = match child_solution {
+        None => { return Step::Return(None); },
+        Some(ss) => { ss },
     };
 
-    match solution {
-        None => {
-            match &sn_ref.operator_tail {
-                None => { return solution; },
-                Some(tail) => {
-                    if tail.len() == 0 { return solution; }
+    // print_ss(&ss); // For debugging.
+    match &sn_ref.operator_tail {
+        None => { return Step::Return(Some(ss)); },
+        Some(tail) => {
+
+            if tail.len() == 0 { return Step::Return(Some(ss)); }
+
+            // Tail solution node has to be an And solution node.
+            let tail_goal = Goal::OperatorGoal(tail.clone());
+
+            // If the head goal has no more solutions, this node
+            // is reused for the tail (last-call optimization).
+            let head_done = sn_ref.head_sn.as_ref()
+                                  .map_or(true, no_more_solutions);
+            if sn_ref.reusable && head_done {
+                let tail_goal = match tail {
+                    Operator::And(goals) if goals.len() == 1 => {
+                        goals[0].clone()
+                    },
+                    _ => { tail_goal },
+                };
+                drop(sn_ref);
+                reuse_solution_node(sn, Rc::new(tail_goal), ss);
+                return Step::Restart;
+            }
+
+            let tail_sn = make_child_node(Rc::new(tail_goal),
+                                          sn_ref.kb, ss, Rc::clone(sn),
+                                          sn_ref.var_ids.clone());
+            sn_ref.tail_sn = Some(Rc::clone(&tail_sn));
+            return Step::Call(tail_sn, Resume::AndTail);
+        },
+    } // match
+
+} // step_and()
+
+// Calls the head node of an And node.
+fn call_head<'a>(sn_ref: &SolutionNode<'a>) -> Step<'a> {
+    match &sn_ref.head_sn {
+        None => { return Step::Return(None); },
+        Some(head_sn) => { return Step::Call(Rc::clone(head_sn), Resume::AndHead); },
+    }
+} // call_head()
+
+// Does one step in the search for a solution of an Or node.
+//
+// Checks subgoals until a success is found.
+//
+// Arguments
+//    Or solution node
+//    where to resume the search
+//    solution of the child node which was called, if any
+// Return
+//    Step
+pub(crate) fn step_or<'a>(sn: &Rc<RefCell<SolutionNode<'a>>>, resume: Resume,
+                          child_solution: Option<Rc<SubstitutionSet<'a>>>)
+                          -> Step<'a> {
+
+    let mut sn_ref = sn.borrow_mut(); // Get a mutable reference.
+
+    match resume {
+        Resume::Start => {
+            // Check for the tail solution.
+            if let Some(tail_sn) = &sn_ref.tail_sn {
+                return Step::Call(Rc::clone(tail_sn), Resume::PassOn);
+            }
+            match &sn_ref.head_sn {
+                None => { return Step::Return(None); },
+                Some(head_sn) => {
+                    return Step::Call(Rc::clone(head_sn), Resume::OrHead);
                 },
             }
         },
-        Some(_) => { return solution; },
+        Resume::OrHead => {},
+        _ => { return Step::Return(child_solution); },
     }
 
+    // Resume::OrHead
+    if child_solution.is_some() { return Step::Return(child_solution); }
+
     match &sn_ref.operator_tail {
-        None => { return None; },
+        None => { return Step::Return(None); },
         Some(tail) => {
+            if tail.len() == 0 { return Step::Return(None); }
             let tail_goal = Goal::OperatorGoal(tail.clone());
             let ss = Rc::clone(&sn_ref.ss);
-            let tail_sn = make_solution_node(Rc::new(tail_goal),
-                                             sn_ref.kb, ss,
-                                             Rc::clone(&sn));
+            let tail_sn = make_child_node(Rc::new(tail_goal),
+                                          sn_ref.kb, ss, Rc::clone(sn),
+                                          sn_ref.var_ids.clone());
             sn_ref.tail_sn = Some(Rc::clone(&tail_sn));
-            return next_solution(tail_sn);
+            return Step::Call(tail_sn, Resume::PassOn);
         },
     }
 
-} // next_solution_or()
+} // step_or()
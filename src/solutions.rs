@@ -1,16 +1,79 @@
 //! Functions which search for and format solutions.
+//!
+//! A solution can be returned as a formatted string, such as
+//! `$Who = Leonard, $Whom = Penny`, or as a [Solution](struct.Solution.html),
+//! which holds the term bound to each variable of the query.
 
+use std::collections::HashMap;
 use std::env;
+use std::fmt;
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 use super::goal::Goal;
+use super::error::SuironError;
 use super::time_out::*;
+use super::cancellation::*;
+use super::operator::Operator;
 use super::solution_node::*;
+use super::query_config::query_config;
+use super::substitution_set::SubstitutionSet;
 use super::unifiable::Unifiable;
 
-const S_TIMEOUT: u64 = 1000; // milliseconds
+pub(crate) const S_TIMEOUT: u64 = 1000; // milliseconds
 const NO_MORE: &str = "No more.";
+const CANCELLED: &str = "Query was cancelled.";
+
+thread_local! {
+    // Maximum number of solutions found for one query. None means no limit.
+    static MAX_SOLUTIONS: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Sets the maximum number of solutions which will be found for a query.
+///
+/// A query with a very large number of solutions could use a lot of
+/// time and memory. This setting limits the number of solutions which
+/// [solve_all()](fn.solve_all.html), [try_solve_all()](fn.try_solve_all.html),
+/// [solve_all_bindings()](fn.solve_all_bindings.html) and
+/// [SolutionPage](struct.SolutionPage.html) will produce, regardless of
+/// the maximum requested by the caller. By default, there is no limit.
+///
+/// To find out whether the solutions were cut short, use
+/// [solve_all_result()](fn.solve_all_result.html).
+///
+/// The setting is local to the thread. A query which has its own
+/// [QueryConfig](../query_config/struct.QueryConfig.html), such as
+/// the queries of an [Engine](../engine/struct.Engine.html), uses the
+/// maximum of its configuration instead.
+///
+/// # Arguments
+/// * maximum number of solutions, or None
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// set_max_solutions(Some(1));
+/// let kb = test_kb();
+/// let query = parse_query("loves($Who, $Whom)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+/// let result = solve_all_result(sn, None).unwrap();
+/// assert_eq!(1, result.solutions.len());
+/// assert!(result.truncated);
+/// set_max_solutions(None);
+/// ```
+pub fn set_max_solutions(max: Option<usize>) {
+    MAX_SOLUTIONS.with(|m| m.set(max));
+}
+
+/// Gets the maximum number of solutions for a query.
+/// See [set_max_solutions()](fn.set_max_solutions.html).
+///
+/// # Return
+/// * maximum number of solutions, or None
+pub fn max_solutions() -> Option<usize> {
+    MAX_SOLUTIONS.with(|m| m.get())
+}
 
 /// Finds one solution for the given solution node.
 ///
@@ -36,15 +99,18 @@ const NO_MORE: &str = "No more.";
 /// ```
 pub fn solve<'a>(sn: Rc<RefCell<SolutionNode<'a>>>) -> String {
 
-    let timer = start_query_timer(S_TIMEOUT);
+    let token = cancellation_token(&sn);
+    let timer = start_query_timer(S_TIMEOUT, &token);
 
     let solution = next_solution(Rc::clone(&sn));
     cancel_timer(timer);
 
-    if query_stopped() {
+    if token.is_cancelled() { return CANCELLED.to_string(); }
+    if token.timed_out() {
         return format!("Query timed out after {} \
                         milliseconds.", S_TIMEOUT);
     }
+    if depth_limit_exceeded() { return depth_limit_message(&sn); }
 
     match solution {
         Some(ss) => {
@@ -57,8 +123,648 @@ pub fn solve<'a>(sn: Rc<RefCell<SolutionNode<'a>>>) -> String {
 
 } // solve()
 
+/// Finds one solution for the given solution node, or reports an error.
+///
+/// Unlike [solve()](fn.solve.html), this function does not return
+/// messages such as "No more.". A query which times out produces a
+/// [TimeoutError](../error/enum.SuironError.html#variant.TimeoutError),
+/// a query which goes deeper than the maximum proof depth produces
+/// [DepthLimitExceeded](../error/enum.SuironError.html#variant.DepthLimitExceeded),
+/// a query which is cancelled by its
+/// [CancellationToken](../cancellation/struct.CancellationToken.html) produces
+/// [Cancelled](../error/enum.SuironError.html#variant.Cancelled),
+/// and a query for a predicate which is not in the knowledge base produces
+/// [UnknownPredicate](../error/enum.SuironError.html#variant.UnknownPredicate).
+///
+/// # Arguments
+/// * `sn` - reference to a [SolutionNode](../solution_node/struct.SolutionNode.html)
+/// # Return
+/// * Ok(Some(solution)), Ok(None) if there are no more solutions, or error
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let query = parse_query("loves(Leonard, $Whom)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb); // solution node
+///
+/// match try_solve(Rc::clone(&sn)) {
+///     Ok(Some(solution)) => { println!("{}", solution); },
+///     Ok(None) => { println!("No solutions."); },
+///     Err(err) => { println!("{}", err); },
+/// }
+/// // Prints: $Whom = Penny
+/// ```
+pub fn try_solve<'a>(sn: Rc<RefCell<SolutionNode<'a>>>)
+                     -> Result<Option<String>, SuironError> {
+
+    let query = sn.borrow().goal.clone();
+    check_predicate("try_solve", &sn)?;
+
+    let token = cancellation_token(&sn);
+    let timer = start_query_timer(S_TIMEOUT, &token);
+    let solution = next_solution(Rc::clone(&sn));
+    cancel_timer(timer);
+
+    if token.is_cancelled() {
+        return Err(SuironError::Cancelled(CANCELLED.to_string()));
+    }
+    if token.timed_out() {
+        let err = format!("Query timed out after {} milliseconds.", S_TIMEOUT);
+        return Err(SuironError::TimeoutError(err));
+    }
+    if depth_limit_exceeded() {
+        return Err(SuironError::DepthLimitExceeded(depth_limit_message(&sn)));
+    }
+
+    match solution {
+        Some(ss) => {
+            let result = query.replace_variables(&ss);
+            return Ok(Some(format_solution(&query, &result)));
+        },
+        None => { return Ok(None); },
+    }
+
+} // try_solve()
+
+/// Finds all solutions for the given solution node, or reports an error.
+///
+/// The search stops after `max` solutions, if a maximum is given.
+/// Errors are reported as in [try_solve()](fn.try_solve.html). If the
+/// query times out or exceeds the maximum proof depth, the solutions
+/// which were found are discarded.
+///
+/// # Arguments
+/// * `sn` - reference to a [SolutionNode](../solution_node/struct.SolutionNode.html)
+/// * `max` - maximum number of solutions, or None
+/// # Return
+/// * vector of solutions (possibly empty), or error
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let query = parse_query("loves($Who, $Whom)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb); // solution node
+///
+/// match try_solve_all(sn, Some(10)) {
+///     Ok(solutions) => { for s in solutions { println!("{}", s); } },
+///     Err(err) => { println!("{}", err); },
+/// }
+/// // Prints:
+/// // $Who = Leonard, $Whom = Penny
+/// // $Who = Penny, $Whom = Leonard
+/// ```
+pub fn try_solve_all<'a>(sn: Rc<RefCell<SolutionNode<'a>>>, max: Option<usize>)
+                         -> Result<Vec<String>, SuironError> {
+    check_predicate("try_solve_all", &sn)?;
+    let (solutions, _) = find_solutions(sn, 0, max, S_TIMEOUT, |query, ss| {
+        format_solution(query, &query.replace_variables(ss))
+    })?;
+    return Ok(solutions);
+} // try_solve_all()
+
+/// The terms bound to the variables of a query, for one solution.
+///
+/// Variables are listed in the order in which they first occur in the
+/// query, including variables within complex terms and lists. The
+/// anonymous variable, $_, is not included. A variable which remains
+/// unbound is paired with itself.
+///
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let query = parse_query("grandfather($G, $Child)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+///
+/// if let Ok(Some(solution)) = solve_bindings(sn) {
+///     println!("{}", solution.get("$Child").unwrap());  // Prints: Aethelstan
+///     println!("{}", solution);  // Prints: $G = Alfred, $Child = Aethelstan
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    bindings: Vec<(String, Unifiable)>,
+}
+
+impl Solution {
+
+    /// Gets the bindings of the variables of a query.
+    ///
+    /// # Arguments
+    /// * query
+    /// * substitution set, which holds a solution of the query
+    /// # Return
+    /// * Solution
+    pub fn new(query: &Goal, ss: &SubstitutionSet) -> Self {
+        let mut vars: Vec<&Unifiable> = vec![];
+        collect_goal_vars(query, &mut vars);
+        let bindings = vars.into_iter().map(|var| {
+            let name = if let Unifiable::LogicVar{id: _, name} = var { name } else { "" };
+            (name.to_string(), var.replace_variables(ss))
+        }).collect();
+        return Solution{ bindings };
+    } // new()
+
+    /// Gets the term bound to a variable.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * variable name, eg. \"$Child\"
+    /// # Return
+    /// * term, or None if the query has no such variable
+    pub fn get(&self, name: &str) -> Option<&Unifiable> {
+        self.bindings.iter().find(|(n, _)| n == name).map(|(_, term)| term)
+    }
+
+    /// Gets the names and terms of the variables, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Unifiable)> {
+        self.bindings.iter().map(|(name, term)| (name.as_str(), term))
+    }
+
+    /// Gets the number of variables.
+    pub fn len(&self) -> usize { self.bindings.len() }
+
+    /// Determines whether the query has no variables.
+    pub fn is_empty(&self) -> bool { self.bindings.is_empty() }
+
+    /// Gets the term bound to a variable, converted to a Rust type.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * variable name, eg. \"$N\"
+    /// # Return
+    /// * value, or TypeError if the variable does not exist, or its
+    ///   term cannot be converted
+    /// # Usage
+    /// ```
+    /// use std::rc::Rc;
+    /// use suiron::*;
+    ///
+    /// let mut kb = KnowledgeBase::new();
+    /// add_rules!(&mut kb, parse_rule("data(7, 2.5, [a, b]).").unwrap());
+    /// let query = parse_query("data($N, $F, $L)").unwrap();
+    /// let sn = make_base_node(Rc::new(query), &kb);
+    /// let solution = solve_bindings(sn).unwrap().unwrap();
+    ///
+    /// let n: i64 = solution.get_as("$N").unwrap();
+    /// let f = solution.get_f64("$F").unwrap();
+    /// let list: Vec<String> = solution.get_vec("$L").unwrap();
+    /// ```
+    pub fn get_as<T>(&self, name: &str) -> Result<T, SuironError>
+                     where T: TryFrom<Unifiable, Error = SuironError> {
+        match self.get(name) {
+            Some(term) => { T::try_from(term.clone()) },
+            None => {
+                let err = format!("Solution - No variable named {}", name);
+                Err(SuironError::TypeError(err))
+            },
+        }
+    } // get_as()
+
+    /// Gets the integer bound to a variable. See [get_as()](#method.get_as).
+    pub fn get_i64(&self, name: &str) -> Result<i64, SuironError> {
+        self.get_as(name)
+    }
+
+    /// Gets the number bound to a variable. See [get_as()](#method.get_as).
+    pub fn get_f64(&self, name: &str) -> Result<f64, SuironError> {
+        self.get_as(name)
+    }
+
+    /// Gets the text of the atom or string bound to a variable.
+    /// See [get_as()](#method.get_as).
+    pub fn get_str(&self, name: &str) -> Result<String, SuironError> {
+        self.get_as(name)
+    }
+
+    /// Gets the list bound to a variable, converting each item.
+    /// See [get_as()](#method.get_as).
+    pub fn get_vec<T>(&self, name: &str) -> Result<Vec<T>, SuironError>
+                      where T: TryFrom<Unifiable, Error = SuironError> {
+        self.get_as(name)
+    }
+
+    /// Converts the solution into a map from variable name to term.
+    pub fn into_map(self) -> HashMap<String, Unifiable> {
+        self.bindings.into_iter().collect()
+    }
+
+} // impl Solution
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let strs: Vec<String> = self.bindings.iter()
+                                    .map(|(name, term)| format!("{} = {}", name, term))
+                                    .collect();
+        write!(f, "{}", strs.join(", "))
+    }
+} // fmt::Display
+
+/// Finds one solution for the given solution node, and gets the
+/// bindings of the query's variables.
+///
+/// Errors are reported as in [try_solve()](fn.try_solve.html).
+///
+/// # Arguments
+/// * `sn` - reference to a [SolutionNode](../solution_node/struct.SolutionNode.html)
+/// # Return
+/// * Ok(Some([Solution](struct.Solution.html))), Ok(None) if there are
+///   no more solutions, or error
+pub fn solve_bindings<'a>(sn: Rc<RefCell<SolutionNode<'a>>>)
+                          -> Result<Option<Solution>, SuironError> {
+    check_predicate("solve_bindings", &sn)?;
+    let (mut solutions, _) = find_solutions(sn, 0, Some(1), S_TIMEOUT, Solution::new)?;
+    return Ok(solutions.pop());
+} // solve_bindings()
+
+/// The result of a search for one solution.
+///
+/// See [solve_result()](fn.solve_result.html).
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolveResult {
+    /// A solution, with the bindings of the query's variables.
+    Solution(Solution),
+    /// There are no more solutions.
+    NoMore,
+    /// The query timed out.
+    TimedOut,
+    /// The query was cancelled.
+    Cancelled,
+    /// The query could not be run, eg. its predicate is unknown.
+    Error(SuironError),
+}
+
+impl fmt::Display for SolveResult {
+    /// Formats the result as [solve()](fn.solve.html) does.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveResult::Solution(solution) => { write!(f, "{}", solution) },
+            SolveResult::NoMore => { write!(f, "{}", NO_MORE) },
+            SolveResult::TimedOut => {
+                write!(f, "Query timed out after {} milliseconds.", S_TIMEOUT)
+            },
+            SolveResult::Cancelled => { write!(f, "{}", CANCELLED) },
+            SolveResult::Error(err) => { write!(f, "{}", err) },
+        }
+    }
+} // fmt::Display
+
+/// Finds one solution for the given solution node.
+///
+/// This function is the same as [solve()](fn.solve.html), but returns
+/// a [SolveResult](enum.SolveResult.html) instead of a string, so the
+/// caller does not need to check for messages such as "No more.".
+///
+/// # Arguments
+/// * `sn` - reference to a [SolutionNode](../solution_node/struct.SolutionNode.html)
+/// # Return
+/// * [SolveResult](enum.SolveResult.html)
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let query = parse_query("loves(Leonard, $Whom)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+///
+/// loop {
+///     match solve_result(Rc::clone(&sn)) {
+///         SolveResult::Solution(s) => { println!("{}", s.get("$Whom").unwrap()); },
+///         SolveResult::NoMore => { break; },
+///         SolveResult::TimedOut => { println!("Timed out."); break; },
+///         SolveResult::Cancelled => { println!("Cancelled."); break; },
+///         SolveResult::Error(err) => { println!("{}", err); break; },
+///     }
+/// }
+/// // Prints: Penny
+/// ```
+pub fn solve_result<'a>(sn: Rc<RefCell<SolutionNode<'a>>>) -> SolveResult {
+    match solve_bindings(sn) {
+        Ok(Some(solution)) => { SolveResult::Solution(solution) },
+        Ok(None) => { SolveResult::NoMore },
+        Err(SuironError::TimeoutError(_)) => { SolveResult::TimedOut },
+        Err(SuironError::Cancelled(_)) => { SolveResult::Cancelled },
+        Err(err) => { SolveResult::Error(err) },
+    }
+} // solve_result()
+
+/// Finds all solutions for the given solution node, up to an optional
+/// maximum, and gets the bindings of the query's variables.
+///
+/// Errors are reported as in [try_solve_all()](fn.try_solve_all.html).
+///
+/// # Arguments
+/// * `sn` - reference to a [SolutionNode](../solution_node/struct.SolutionNode.html)
+/// * `max` - maximum number of solutions, or None
+/// # Return
+/// * vector of [Solutions](struct.Solution.html), or error
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let query = parse_query("loves($Who, $Whom)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+///
+/// for solution in solve_all_bindings(sn, None).unwrap() {
+///     println!("{} loves {}.", solution.get("$Who").unwrap(),
+///                              solution.get("$Whom").unwrap());
+/// }
+/// ```
+pub fn solve_all_bindings<'a>(sn: Rc<RefCell<SolutionNode<'a>>>, max: Option<usize>)
+                              -> Result<Vec<Solution>, SuironError> {
+    check_predicate("solve_all_bindings", &sn)?;
+    let (solutions, _) = find_solutions(sn, 0, max, S_TIMEOUT, Solution::new)?;
+    return Ok(solutions);
+} // solve_all_bindings()
+
+/// All the solutions of a query, and a flag which tells whether the
+/// search was stopped by the [max_solutions()](fn.set_max_solutions.html)
+/// setting.
+///
+/// See [solve_all_result()](fn.solve_all_result.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolveAllResult {
+    /// The solutions which were found.
+    pub solutions: Vec<Solution>,
+    /// True if the search stopped at the maximum number of solutions,
+    /// so there may be more solutions.
+    pub truncated: bool,
+}
+
+/// Finds all solutions for the given solution node, up to an optional
+/// maximum, and reports whether they were cut short by the
+/// [max_solutions()](fn.set_max_solutions.html) setting.
+///
+/// Errors are reported as in [try_solve_all()](fn.try_solve_all.html).
+///
+/// # Arguments
+/// * `sn` - reference to a [SolutionNode](../solution_node/struct.SolutionNode.html)
+/// * `max` - maximum number of solutions, or None
+/// # Return
+/// * [SolveAllResult](struct.SolveAllResult.html), or error
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let query = parse_query("loves($Who, $Whom)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+///
+/// let result = solve_all_result(sn, None).unwrap();
+/// for solution in &result.solutions { println!("{}", solution); }
+/// if result.truncated { println!("There may be more solutions."); }
+/// ```
+pub fn solve_all_result<'a>(sn: Rc<RefCell<SolutionNode<'a>>>, max: Option<usize>)
+                            -> Result<SolveAllResult, SuironError> {
+    check_predicate("solve_all_result", &sn)?;
+    let (solutions, truncated) = find_solutions(sn, 0, max, S_TIMEOUT, Solution::new)?;
+    return Ok(SolveAllResult{ solutions, truncated });
+} // solve_all_result()
+
+/// Finds a page of solutions for a solution node.
+///
+/// The first solutions can be skipped, and the search stops when enough
+/// solutions have been found, so a large result set can be read a page
+/// at a time. Skipped solutions must still be searched for, but they
+/// are not formatted.
+///
+/// The solution node remembers where the search stopped. Finding more
+/// solutions from the same node continues with the next page.
+///
+/// Errors are reported as in [try_solve()](fn.try_solve.html). By default,
+/// the query times out after one second. A different time limit can be
+/// set with time_limit().
+///
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// add_rules!(&mut kb, parse_rule("number(1).").unwrap(),
+///                     parse_rule("number(2).").unwrap(),
+///                     parse_rule("number(3).").unwrap(),
+///                     parse_rule("number(4).").unwrap());
+/// let query = parse_query("number($N)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+///
+/// let page = SolutionPage::new(sn).skip_solutions(1).take_solutions(2);
+/// for solution in page.strings().unwrap() { println!("{}", solution); }
+/// // Prints:
+/// // $N = 2
+/// // $N = 3
+/// ```
+#[derive(Debug, Clone)]
+pub struct SolutionPage<'a> {
+    sn: Rc<RefCell<SolutionNode<'a>>>,
+    skip: usize,
+    take: Option<usize>,
+    time_limit: u64,
+}
+
+impl<'a> SolutionPage<'a> {
+
+    /// Starts a page of solutions. By default, no solutions are
+    /// skipped, and all the rest are taken.
+    ///
+    /// # Arguments
+    /// * `sn` - reference to a [SolutionNode](../solution_node/struct.SolutionNode.html)
+    /// # Return
+    /// * SolutionPage
+    pub fn new(sn: Rc<RefCell<SolutionNode<'a>>>) -> Self {
+        SolutionPage{ sn, skip: 0, take: None, time_limit: S_TIMEOUT }
+    }
+
+    /// Sets the number of solutions to skip.
+    pub fn skip_solutions(mut self, n: usize) -> Self {
+        self.skip = n;
+        self
+    }
+
+    /// Sets the maximum number of solutions to take.
+    pub fn take_solutions(mut self, n: usize) -> Self {
+        self.take = Some(n);
+        self
+    }
+
+    /// Sets the time limit of the search, in milliseconds.
+    pub fn time_limit(mut self, milliseconds: u64) -> Self {
+        self.time_limit = milliseconds;
+        self
+    }
+
+    /// Finds the solutions of the page, formatted as by
+    /// [solve()](fn.solve.html).
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * vector of solutions (possibly empty), or error
+    pub fn strings(self) -> Result<Vec<String>, SuironError> {
+        check_predicate("SolutionPage::strings", &self.sn)?;
+        let (solutions, _) = find_solutions(self.sn, self.skip, self.take,
+                                            self.time_limit, |query, ss| {
+            format_solution(query, &query.replace_variables(ss))
+        })?;
+        return Ok(solutions);
+    } // strings()
+
+    /// Finds the solutions of the page, as [Solutions](struct.Solution.html).
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * vector of Solutions (possibly empty), or error
+    pub fn bindings(self) -> Result<Vec<Solution>, SuironError> {
+        check_predicate("SolutionPage::bindings", &self.sn)?;
+        let (solutions, _) = find_solutions(self.sn, self.skip, self.take,
+                                            self.time_limit, Solution::new)?;
+        return Ok(solutions);
+    } // bindings()
+
+} // impl SolutionPage
+
+// Finds solutions for a solution node, up to an optional maximum.
+//
+// The maximum is also limited by the query's maximum number of solutions.
+// (See query_config().)
+//
+// Arguments
+//    solution node
+//    number of solutions to skip
+//    maximum number of solutions, or None
+//    time limit in milliseconds
+//    function which converts the query and a substitution set into a result
+// Return
+//    results, and true if the query's maximum number of solutions
+//    stopped the search,
+//    or timeout error
+pub(crate) fn find_solutions<'a, T>(sn: Rc<RefCell<SolutionNode<'a>>>, skip: usize,
+                                    max: Option<usize>, milliseconds: u64,
+                                    make_result: impl Fn(&Goal, &SubstitutionSet) -> T)
+                                    -> Result<(Vec<T>, bool), SuironError> {
+
+    let query = sn.borrow().goal.clone();
+    let mut results: Vec<T> = vec![];
+    let max = max.unwrap_or(usize::MAX);
+    let cap = query_config(&sn).max_solutions.unwrap_or(usize::MAX);
+    let mut skipped = 0;
+    let token = cancellation_token(&sn);
+    let timer = start_query_timer(milliseconds, &token);
+
+    while results.len() < max.min(cap) {
+        let solution = next_solution(Rc::clone(&sn));
+        if token.is_stopped() || depth_limit_exceeded() { break; }
+        match solution {
+            Some(_) if skipped < skip => { skipped += 1; },
+            Some(ss) => { results.push(make_result(&query, &ss)); },
+            None => { break; }
+        }
+    }
+
+    cancel_timer(timer);
+    if token.is_cancelled() {
+        return Err(SuironError::Cancelled(CANCELLED.to_string()));
+    }
+    if token.timed_out() {
+        let err = format!("Query timed out after {} milliseconds.", milliseconds);
+        return Err(SuironError::TimeoutError(err));
+    }
+    if depth_limit_exceeded() {
+        return Err(SuironError::DepthLimitExceeded(depth_limit_message(&sn)));
+    }
+    let truncated = cap < max && results.len() == cap;
+    return Ok((results, truncated));
+
+} // find_solutions()
+
+// Makes the message for a query which exceeded the maximum proof depth.
+fn depth_limit_message(sn: &Rc<RefCell<SolutionNode>>) -> String {
+    let max = query_config(sn).max_depth.unwrap_or(usize::MAX);
+    return format!("Query exceeded the maximum proof depth of {}.", max);
+} // depth_limit_message()
+
+// Collects the logic variables of a goal, without duplicates.
+//
+// Arguments
+//    goal
+//    variables
+fn collect_goal_vars<'a>(goal: &'a Goal, vars: &mut Vec<&'a Unifiable>) {
+    match goal {
+        Goal::OperatorGoal(op) => {
+            let goals = match op {
+                Operator::And(goals) | Operator::Or(goals) |
+                Operator::Time(goals) | Operator::Not(goals) => { goals },
+            };
+            for g in goals { collect_goal_vars(g, vars); }
+        },
+        Goal::BuiltInGoal(bip) => {
+            if let Some(terms) = &bip.terms {
+                for t in terms { collect_term_vars(t, vars); }
+            }
+        },
+        Goal::ComplexGoal(term) => { collect_term_vars(term, vars); },
+        Goal::Nil => {},
+    }
+} // collect_goal_vars()
+
+// Collects the logic variables of a term, without duplicates.
+//
+// Arguments
+//    term
+//    variables
+fn collect_term_vars<'a>(term: &'a Unifiable, vars: &mut Vec<&'a Unifiable>) {
+    match term {
+        Unifiable::LogicVar{id: _, name: _} if !vars.contains(&term) => {
+            vars.push(term);
+        },
+        Unifiable::SComplex(terms) |
+        Unifiable::SFunction{name: _, terms} => {
+            for t in terms { collect_term_vars(t, vars); }
+        },
+        Unifiable::SLinkedList{term, next, count: _, tail_var: _} => {
+            collect_term_vars(term, vars);
+            collect_term_vars(next, vars);
+        },
+        _ => {},
+    }
+} // collect_term_vars()
+
+// Checks that the predicate of a complex query is in the knowledge base.
+//
+// Arguments
+//    name of calling function
+//    solution node
+// Return
+//    Ok, or UnknownPredicate error
+pub(crate) fn check_predicate(caller: &str, sn: &Rc<RefCell<SolutionNode>>) -> Result<(), SuironError> {
+    let sn = sn.borrow();
+    if let Goal::ComplexGoal(_) = *sn.goal {
+        let key = sn.goal.key()?;
+        if !sn.kb.contains_key(&key) && sn.kb.external(&key).is_none() {
+            let err = format!("{}() - Unknown predicate: {}", caller, key);
+            return Err(SuironError::UnknownPredicate(err));
+        }
+    }
+    return Ok(());
+} // check_predicate()
+
 /// Finds all solutions for the given query.
 ///
+/// If the number of solutions reaches the
+/// [max_solutions()](fn.set_max_solutions.html) setting, the search
+/// stops, and the last string is a message such as "Stopped after 100 solutions.".
+///
 /// # Arguments
 /// * `sn` - reference to a [SolutionNode](../solution_node/struct.SolutionNode.html)
 /// # Return
@@ -84,12 +790,19 @@ pub fn solve_all<'a>(sn: Rc<RefCell<SolutionNode<'a>>>) -> Vec<String> {
     let mut results: Vec<String> = vec![];
 
     let query = sn.borrow().goal.clone();
-    let timer = start_query_timer(S_TIMEOUT);
+    let cap = query_config(&sn).max_solutions.unwrap_or(usize::MAX);
+    let token = cancellation_token(&sn);
+    let timer = start_query_timer(S_TIMEOUT, &token);
 
     loop {
 
+        if results.len() >= cap {
+            results.push(format!("Stopped after {} solutions.", cap));
+            break;
+        }
+
         let solution = next_solution(Rc::clone(&sn));
-        if query_stopped() { break; }
+        if token.is_stopped() || depth_limit_exceeded() { break; }
 
         match solution {
             Some(ss) => {
@@ -103,10 +816,12 @@ pub fn solve_all<'a>(sn: Rc<RefCell<SolutionNode<'a>>>) -> Vec<String> {
     } // loop
 
     cancel_timer(timer);
-    if query_stopped() {
+    if token.is_cancelled() { results.push(CANCELLED.to_string()); }
+    else if token.timed_out() {
         let s = format!("Query timed out after {} milliseconds.", S_TIMEOUT);
         results.push(s);
     }
+    else if depth_limit_exceeded() { results.push(depth_limit_message(&sn)); }
 
     return results;
 
@@ -155,12 +870,17 @@ pub fn format_solution(query: &Goal, result: &Unifiable) -> String {
                 match &q_terms[i] {
                     Unifiable::LogicVar{id: _, name} => {
                         // Output logic variable name and result.
+                        // An atom is shown without quotation marks.
+                        let value = match &r_terms[i] {
+                            Unifiable::Atom(s) => { s.to_string() },
+                            term => { term.to_string() },
+                        };
                         if first {
-                            out += &format!("{} = {}", name, r_terms[i]);
+                            out += &format!("{} = {}", name, value);
                             first = false;
                         }
                         else {
-                            out += &format!(", {} = {}", name, r_terms[i]);
+                            out += &format!(", {} = {}", name, value);
                         }
                     },
                     _ => {},
@@ -194,13 +914,11 @@ pub fn get_stack_size() -> i32 {
 mod test {
 
     use std::rc::Rc;
-    use serial_test::serial;
 
     use crate::*;
     use super::*;
 
     #[test]
-    #[serial]
     fn test_format_solution() {
 
         let kb = test_kb();
@@ -242,10 +960,8 @@ mod test {
     } // test_format_solution()
 
     #[test]
-    #[serial]
     fn test_solve() {
 
-        clear_id();
         let kb = test_kb();
         let query = parse_query("loves(Leonard, $Whom)").unwrap();
         let sn = make_base_node(Rc::new(query), &kb); // solution node
@@ -255,10 +971,24 @@ mod test {
     } // test_solve()
 
     #[test]
-    #[serial]
+    fn test_try_solve() {
+
+        let kb = test_kb();
+        let query = parse_query("loves(Leonard, $Whom)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb); // solution node
+        assert_eq!(Ok(Some("$Whom = Penny".to_string())), try_solve(Rc::clone(&sn)));
+        assert_eq!(Ok(None), try_solve(Rc::clone(&sn)));
+
+        let query = parse_query("hates(Leonard, $Whom)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        let err = try_solve(sn).unwrap_err();
+        assert!(matches!(err, SuironError::UnknownPredicate(_)));
+
+    } // test_try_solve()
+
+    #[test]
     fn test_solve_all() {
 
-        clear_id();
         let kb = test_kb();
         let query = parse_query("loves($Who, $Whom)").unwrap();
         let sn = make_base_node(Rc::new(query), &kb); // solution node
@@ -270,6 +1000,196 @@ mod test {
 
     } // test_solve_all()
 
+    #[test]
+    fn test_try_solve_all() {
+
+        let kb = test_kb();
+        let query = Rc::new(parse_query("loves($Who, $Whom)").unwrap());
+        let sn = make_base_node(Rc::clone(&query), &kb);
+        let results = try_solve_all(sn, None).unwrap();
+        assert_eq!(vec!["$Who = Leonard, $Whom = Penny",
+                        "$Who = Penny, $Whom = Leonard"], results);
+
+        let sn = make_base_node(Rc::clone(&query), &kb);
+        assert_eq!(vec!["$Who = Leonard, $Whom = Penny"], try_solve_all(sn, Some(1)).unwrap());
+        let sn = make_base_node(Rc::clone(&query), &kb);
+        assert!(try_solve_all(sn, Some(0)).unwrap().is_empty());
+
+        let query = parse_query("loves(Howard, $Whom)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!(Ok(vec![]), try_solve_all(sn, None));
+
+        let query = parse_query("hates(Leonard, $Whom)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        let err = try_solve_all(sn, None).unwrap_err();
+        assert!(matches!(err, SuironError::UnknownPredicate(_)));
+
+    } // test_try_solve_all()
+
+    #[test]
+    fn test_solve_bindings() {
+
+        let kb = test_kb();
+        let query = parse_query("grandfather($G, $Child)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        let solution = solve_bindings(Rc::clone(&sn)).unwrap().unwrap();
+        assert_eq!(Some(&atom!("Aethelstan")), solution.get("$Child"));
+        assert_eq!(None, solution.get("$X"));
+        assert_eq!("$G = Alfred, $Child = Aethelstan", solution.to_string());
+        assert_eq!(Ok(None), solve_bindings(sn));
+
+        // Variables in lists and complex terms, and repeated variables.
+        let mut kb = KnowledgeBase::new();
+        add_rules!(&mut kb, parse_rule("pair([$A, $B], f($B, $A)).").unwrap(),
+                            parse_rule("pair([1, 2], f(2, 1)).").unwrap());
+        let query = parse_query("pair([$X, $Y], f($Y, $_))").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        let solutions = solve_all_bindings(sn, None).unwrap();
+        assert_eq!(2, solutions.len());
+        let names: Vec<&str> = solutions[1].iter().map(|(name, _)| name).collect();
+        assert_eq!(vec!["$X", "$Y"], names);
+        let map = solutions[1].clone().into_map();
+        assert_eq!(SInteger(1), map["$X"]);
+        assert_eq!(SInteger(2), map["$Y"]);
+        // An unbound variable is paired with itself.
+        assert!(matches!(solutions[0].get("$X"), Some(LogicVar{id: _, name: _})));
+
+        let query = parse_query("pair($P, $Q)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!(1, solve_all_bindings(sn, Some(1)).unwrap().len());
+
+        let query = parse_query("unknown($P)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert!(solve_bindings(sn).is_err());
+
+    } // test_solve_bindings()
+
+    #[test]
+    fn test_typed_bindings() {
+
+        let mut kb = KnowledgeBase::new();
+        add_rules!(&mut kb, parse_rule("data(7, 2.5, June, \"hi\", [1, 2 | $T]) :- $T = [3].").unwrap());
+        let query = parse_query("data($N, $F, $A, $S, $L)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        let solution = solve_bindings(sn).unwrap().unwrap();
+
+        assert_eq!(Ok(7), solution.get_i64("$N"));
+        assert_eq!(Ok(7.0), solution.get_f64("$N"));
+        assert_eq!(Ok(2.5), solution.get_f64("$F"));
+        assert_eq!(Ok("June".to_string()), solution.get_str("$A"));
+        assert_eq!(Ok("hi".to_string()), solution.get_str("$S"));
+        assert_eq!(Ok(vec![1, 2, 3]), solution.get_vec::<i64>("$L"));
+        assert_eq!(Ok(vec![1.0, 2.0, 3.0]), solution.get_vec::<f64>("$L"));
+
+        let err = solution.get_i64("$F").unwrap_err();
+        assert!(matches!(err, SuironError::TypeError(_)));
+        assert!(solution.get_i64("$Missing").is_err());
+        assert!(solution.get_vec::<i64>("$A").is_err());
+
+    } // test_typed_bindings()
+
+    #[test]
+    fn test_solve_result() {
+
+        let kb = test_kb();
+        let query = parse_query("loves(Leonard, $Whom)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        let result = solve_result(Rc::clone(&sn));
+        match &result {
+            SolveResult::Solution(s) => { assert_eq!(Some(&atom!("Penny")), s.get("$Whom")); },
+            _ => { panic!("Expected a solution: {}", result); },
+        }
+        assert_eq!("$Whom = Penny", result.to_string());
+        assert_eq!(SolveResult::NoMore, solve_result(Rc::clone(&sn)));
+        assert_eq!("No more.", SolveResult::NoMore.to_string());
+        assert_eq!("Query timed out after 1000 milliseconds.",
+                   SolveResult::TimedOut.to_string());
+
+        let query = parse_query("hates(Leonard, $Whom)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        let result = solve_result(sn);
+        assert!(matches!(result, SolveResult::Error(SuironError::UnknownPredicate(_))));
+
+    } // test_solve_result()
+
+    #[test]
+    fn test_solution_page() {
+
+        let mut kb = KnowledgeBase::new();
+        for i in 1..=10 {
+            let rule = parse_rule(&format!("number({}).", i)).unwrap();
+            add_rules!(&mut kb, rule);
+        }
+        let query = Rc::new(parse_query("number($N)").unwrap());
+
+        let sn = make_base_node(Rc::clone(&query), &kb);
+        let page = SolutionPage::new(sn).skip_solutions(3).take_solutions(2);
+        assert_eq!(vec!["$N = 4", "$N = 5"], page.strings().unwrap());
+
+        // Pages from the same node continue the search.
+        let sn = make_base_node(Rc::clone(&query), &kb);
+        let mut pages: Vec<Vec<i64>> = vec![];
+        loop {
+            let page = SolutionPage::new(Rc::clone(&sn)).take_solutions(4);
+            let numbers: Vec<i64> = page.bindings().unwrap().iter()
+                                        .map(|s| s.get_i64("$N").unwrap()).collect();
+            if numbers.is_empty() { break; }
+            pages.push(numbers);
+        }
+        assert_eq!(vec![vec![1, 2, 3, 4], vec![5, 6, 7, 8], vec![9, 10]], pages);
+
+        let sn = make_base_node(Rc::clone(&query), &kb);
+        assert_eq!(10, SolutionPage::new(sn).strings().unwrap().len());
+        let sn = make_base_node(Rc::clone(&query), &kb);
+        assert!(SolutionPage::new(sn).skip_solutions(20).strings().unwrap().is_empty());
+        let sn = make_base_node(Rc::clone(&query), &kb);
+        assert!(SolutionPage::new(sn).take_solutions(0).strings().unwrap().is_empty());
+
+        let query = parse_query("unknown($N)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert!(SolutionPage::new(sn).bindings().is_err());
+
+    } // test_solution_page()
+
+    #[test]
+    fn test_max_solutions() {
+
+        let mut kb = KnowledgeBase::new();
+        for i in 1..=10 {
+            let rule = parse_rule(&format!("number({}).", i)).unwrap();
+            add_rules!(&mut kb, rule);
+        }
+        let query = Rc::new(parse_query("number($N)").unwrap());
+
+        set_max_solutions(Some(3));
+
+        let sn = make_base_node(Rc::clone(&query), &kb);
+        let result = solve_all_result(sn, None).unwrap();
+        assert_eq!(3, result.solutions.len());
+        assert!(result.truncated);
+
+        // A smaller maximum, requested by the caller, is not a truncation.
+        let sn = make_base_node(Rc::clone(&query), &kb);
+        let result = solve_all_result(sn, Some(2)).unwrap();
+        assert_eq!(2, result.solutions.len());
+        assert!(!result.truncated);
+
+        let sn = make_base_node(Rc::clone(&query), &kb);
+        assert_eq!(3, try_solve_all(sn, Some(5)).unwrap().len());
+
+        let sn = make_base_node(Rc::clone(&query), &kb);
+        let results = solve_all(sn);
+        assert_eq!(vec!["$N = 1", "$N = 2", "$N = 3", "Stopped after 3 solutions."],
+                   results);
+
+        set_max_solutions(None);
+        let sn = make_base_node(Rc::clone(&query), &kb);
+        let result = solve_all_result(sn, None).unwrap();
+        assert_eq!(10, result.solutions.len());
+        assert!(!result.truncated);
+
+    } // test_max_solutions()
+
     // NOTE: The default stack for Rust is 2 MB.
     // The following test contains an endless loop, which will
     // overrun the stack before timeout, if the stack size is
@@ -277,7 +1197,6 @@ mod test {
     // ALSO NOTE: None of this works.
     /*
     #[test]
-    #[serial]
     fn test_solve_timeout() {
 
         let stack_size = get_stack_size();
@@ -306,7 +1225,6 @@ mod test {
     // ALSO NOTE: None of this works.
     /*
     #[test]
-    #[serial]
     fn test_solve_all_timeout() {
 
         let stack_size = get_stack_size();
@@ -0,0 +1,213 @@
+//! Predicates whose facts are fetched from an SQLite database.
+//! Requires the `sqlite` feature.
+//!
+//! An [SqlPredicate](struct.SqlPredicate.html) runs an SQL query each
+//! time a goal refers to it, and converts the rows into facts. The
+//! facts are not stored in the knowledge base, so very large tables can
+//! be queried without loading them into memory.
+//!
+//! Arguments of the goal which are bound to atoms (other than null),
+//! strings or numbers are passed to the database as conditions. For
+//! example, if the predicate person/2 is defined by the query
+//! `SELECT name, age FROM people`, the goal `person(June, $Age)` runs:
+//!
+//! <pre>
+//!   SELECT * FROM (SELECT name, age FROM people) WHERE "name" = 'June'
+//! </pre>
+//!
+//! Column values are converted as follows:
+//!
+//! <pre>
+//!   INTEGER       SInteger
+//!   REAL          SFloat
+//!   TEXT, BLOB    Atom
+//!   NULL          Atom (null)
+//! </pre>
+//
+// Cleve Lendon 2023
+
+use std::rc::Rc;
+
+use rusqlite::Connection;
+use rusqlite::types::{Value, ValueRef};
+
+use super::error::SuironError;
+use super::external::ExternalPredicate;
+use super::facts::fact_head;
+use super::knowledge_base::*;
+use super::rule::Rule;
+use super::unifiable::Unifiable;
+
+use crate::atom;
+
+/// A predicate whose facts are the rows of an SQL query.
+///
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let conn = Rc::new(rusqlite::Connection::open_in_memory().unwrap());
+/// conn.execute_batch("CREATE TABLE people (name TEXT, age INTEGER);
+///                     INSERT INTO people VALUES ('June', 61), ('Ola', 23);").unwrap();
+///
+/// let mut kb = KnowledgeBase::new();
+/// let person = SqlPredicate::new(conn, "person", "SELECT name, age FROM people").unwrap();
+/// kb.add_external(&person.predicate_name(), person);
+///
+/// let query = parse_query("person(Ola, $Age)").unwrap();
+/// println!("{}", solve(make_base_node(Rc::new(query), &kb)));  // Prints: $Age = 23
+/// ```
+#[derive(Debug)]
+pub struct SqlPredicate {
+    connection: Rc<Connection>,
+    functor: String,
+    query: String,
+    columns: Vec<String>,
+}
+
+impl SqlPredicate {
+
+    /// Creates an SQL predicate. Its arity is the number of columns
+    /// of the query.
+    ///
+    /// # Arguments
+    /// * database connection
+    /// * functor, eg. \"person\"
+    /// * SQL query, eg. \"SELECT name, age FROM people\"
+    /// # Return
+    /// * SQL predicate, or error if the query is invalid
+    pub fn new(connection: Rc<Connection>, functor: &str,
+               query: &str) -> Result<Self, SuironError> {
+        let columns = {
+            let stmt = connection.prepare(query).map_err(sql_error)?;
+            stmt.column_names().iter().map(|s| s.to_string()).collect()
+        };
+        return Ok(SqlPredicate{ connection, functor: functor.to_string(),
+                                query: query.to_string(), columns });
+    } // new()
+
+    /// Gets the predicate name, eg. \"person/2\".
+    pub fn predicate_name(&self) -> String {
+        format!("{}/{}", self.functor, self.columns.len())
+    }
+
+    // Makes an SQL query, with conditions for the bound arguments of a goal.
+    //
+    // Arguments
+    //    goal (complex term)
+    // Return
+    //    SQL text and parameters
+    fn make_sql(&self, goal: &Unifiable) -> (String, Vec<Value>) {
+
+        let mut conditions: Vec<String> = vec![];
+        let mut params: Vec<Value> = vec![];
+        if let Unifiable::SComplex(terms) = goal {
+            for (column, term) in self.columns.iter().zip(terms.iter().skip(1)) {
+                let value = match term {
+                    // Null is not passed to the database, because it
+                    // could match NULL or the text 'null'.
+                    Unifiable::Atom(s) if s.as_str() == "null" => { continue; },
+                    Unifiable::Atom(s) => { Value::Text(s.to_string()) },
+                    Unifiable::SString(s) => { Value::Text(s.to_string()) },
+                    Unifiable::SInteger(i) => { Value::Integer(*i) },
+                    Unifiable::SFloat(f) => { Value::Real(*f) },
+                    _ => { continue; },
+                };
+                params.push(value);
+                conditions.push(format!("\"{}\" = ?{}",
+                                        column.replace('"', "\"\""), params.len()));
+            }
+        }
+
+        if conditions.is_empty() { return (self.query.clone(), params); }
+        let sql = format!("SELECT * FROM ({}) WHERE {}",
+                          self.query, conditions.join(" AND "));
+        return (sql, params);
+
+    } // make_sql()
+
+} // impl SqlPredicate
+
+impl ExternalPredicate for SqlPredicate {
+
+    /// Runs the SQL query, and converts the rows into facts.
+    fn fetch(&self, goal: &Unifiable) -> Result<Vec<Rule>, SuironError> {
+
+        let (sql, params) = self.make_sql(goal);
+        let mut stmt = self.connection.prepare(&sql).map_err(sql_error)?;
+        let mut rows = stmt.query(rusqlite::params_from_iter(params)).map_err(sql_error)?;
+
+        let mut facts: Vec<Rule> = vec![];
+        while let Some(row) = rows.next().map_err(sql_error)? {
+            let mut args: Vec<Unifiable> = Vec::with_capacity(self.columns.len());
+            for i in 0..self.columns.len() {
+                let term = match row.get_ref(i).map_err(sql_error)? {
+                    ValueRef::Null => { atom!("null") },
+                    ValueRef::Integer(i) => { Unifiable::SInteger(i) },
+                    ValueRef::Real(f) => { Unifiable::SFloat(f) },
+                    ValueRef::Text(s) | ValueRef::Blob(s) => {
+                        atom!(String::from_utf8_lossy(s).as_ref())
+                    },
+                };
+                args.push(term);
+            }
+            facts.push(make_fact(fact_head(&self.functor, args)));
+        }
+        return Ok(facts);
+
+    } // fetch()
+
+} // impl ExternalPredicate
+
+// Converts a database error into a Suiron error.
+fn sql_error(err: rusqlite::Error) -> SuironError {
+    SuironError::IoError(format!("SqlPredicate - {}", err))
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    #[test]
+    fn test_sql_predicate() {
+
+        let conn = Rc::new(rusqlite::Connection::open_in_memory().unwrap());
+        conn.execute_batch("CREATE TABLE people (name TEXT, age INTEGER, height REAL);
+                            INSERT INTO people VALUES ('June', 61, 1.62),
+                                                      ('Ola', 23, 1.8),
+                                                      ('Max', NULL, 1.7);").unwrap();
+
+        let mut kb = KnowledgeBase::new();
+        let person = SqlPredicate::new(Rc::clone(&conn), "person",
+                                       "SELECT name, age, height FROM people").unwrap();
+        assert_eq!("person/3", person.predicate_name());
+        kb.add_external("person/3", person);
+        assert!(kb.external("person/3").is_some());
+        assert!(!kb.contains("person/3"));
+
+        let query = parse_query("person(Ola, $Age, $H)").unwrap();
+        assert_eq!("$Age = 23, $H = 1.8", solve(make_base_node(Rc::new(query), &kb)));
+        let query = parse_query("person($Name, null, $_)").unwrap();
+        assert_eq!("$Name = Max", solve(make_base_node(Rc::new(query), &kb)));
+
+        add_rules!(&mut kb, parse_rule("adult($N) :- person($N, $A, $_), $A >= 30.").unwrap());
+        let query = parse_query("adult($Who)").unwrap();
+        let results = solve_all(make_base_node(Rc::new(query), &kb));
+        assert_eq!(vec!["$Who = June"], results);
+
+        // Rows added later are seen by the next query.
+        conn.execute("INSERT INTO people VALUES ('Ann', 44, 1.6)", []).unwrap();
+        let query = parse_query("adult($Who)").unwrap();
+        let results = solve_all(make_base_node(Rc::new(query), &kb));
+        assert_eq!(vec!["$Who = June", "$Who = Ann"], results);
+
+        let err = SqlPredicate::new(conn, "bad", "SELECT * FROM nowhere");
+        assert!(matches!(err, Err(SuironError::IoError(_))));
+
+    } // test_sql_predicate()
+
+} // test
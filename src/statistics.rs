@@ -0,0 +1,215 @@
+//! Statistics about the contents of a knowledge base.
+//!
+//! Applications can use these statistics to monitor the growth of a
+//! knowledge base, and to decide whether to turn on
+//! [indexing](../knowledge_base/struct.KnowledgeBase.html#method.set_indexing).
+//!
+//! The memory footprint is an estimate. It includes the terms and goals
+//! of the facts and rules, and the text which they own, but not the
+//! overhead of the allocator, or the text of atoms, which is shared.
+//
+// Cleve Lendon 2023
+
+use std::fmt;
+use std::mem::size_of;
+
+use super::goal::Goal;
+use super::knowledge_base::KnowledgeBase;
+use super::operator::Operator;
+use super::rule::Rule;
+use super::unifiable::Unifiable;
+
+/// Statistics for one predicate.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PredicateStats {
+    /// Predicate name, eg. \"loves/2\".
+    pub name: String,
+    /// Number of facts.
+    pub facts: usize,
+    /// Number of rules.
+    pub rules: usize,
+    /// Number of terms in the heads and bodies of the facts and rules.
+    pub terms: usize,
+    /// Approximate memory used, in bytes.
+    pub memory: usize,
+}
+
+/// Statistics for a knowledge base.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KbStats {
+    /// Number of predicates.
+    pub predicates: usize,
+    /// Number of facts.
+    pub facts: usize,
+    /// Number of rules.
+    pub rules: usize,
+    /// Number of terms in all facts and rules.
+    pub terms: usize,
+    /// Approximate memory used, in bytes.
+    pub memory: usize,
+    /// Statistics for each predicate, sorted by name.
+    pub per_predicate: Vec<PredicateStats>,
+}
+
+impl KbStats {
+
+    /// Gets the number of clauses (facts plus rules).
+    pub fn clauses(&self) -> usize { self.facts + self.rules }
+
+} // impl KbStats
+
+/// Gathers statistics about a knowledge base.
+///
+/// # Arguments
+/// * knowledge base
+/// # Return
+/// * [KbStats](struct.KbStats.html)
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let stats = kb_stats(&kb);
+/// println!("{}", stats);
+/// // Prints:
+/// // Predicates: 3, facts: 4, rules: 2, terms: 40, memory: ... bytes
+/// //     father/2 - facts: 2, rules: 0, terms: 8, memory: ... bytes
+/// // ...
+/// ```
+pub fn kb_stats(kb: &KnowledgeBase) -> KbStats {
+
+    let mut stats = KbStats::default();
+    for name in kb.predicates() {
+        let mut ps = PredicateStats{ name: name.to_string(), ..Default::default() };
+        for rule in kb.rules(name) {
+            if rule.body == Goal::Nil { ps.facts += 1; } else { ps.rules += 1; }
+            let (terms, memory) = rule_size(rule);
+            ps.terms += terms;
+            ps.memory += memory;
+        }
+        stats.predicates += 1;
+        stats.facts += ps.facts;
+        stats.rules += ps.rules;
+        stats.terms += ps.terms;
+        stats.memory += ps.memory;
+        stats.per_predicate.push(ps);
+    }
+    return stats;
+
+} // kb_stats()
+
+impl fmt::Display for KbStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Predicates: {}, facts: {}, rules: {}, terms: {}, memory: {} bytes",
+                 self.predicates, self.facts, self.rules, self.terms, self.memory)?;
+        for ps in &self.per_predicate {
+            writeln!(f, "    {} - facts: {}, rules: {}, terms: {}, memory: {} bytes",
+                     ps.name, ps.facts, ps.rules, ps.terms, ps.memory)?;
+        }
+        Ok(())
+    }
+} // fmt::Display
+
+// Counts the terms of a fact or rule, and estimates its size.
+//
+// Arguments
+//    rule
+// Return
+//    (number of terms, bytes)
+fn rule_size(rule: &Rule) -> (usize, usize) {
+    let mut count = (0, size_of::<Rule>());
+    term_size(&rule.head, &mut count);
+    goal_size(&rule.body, &mut count);
+    return count;
+} // rule_size()
+
+// Counts the terms in a term, and adds their sizes.
+// The size of the term itself is included by its owner.
+//
+// Arguments
+//    term
+//    (number of terms, bytes)
+fn term_size(term: &Unifiable, count: &mut (usize, usize)) {
+    count.0 += 1;
+    match term {
+        Unifiable::SString(s) => { count.1 += s.capacity(); },
+        Unifiable::LogicVar{id: _, name} => { count.1 += name.capacity(); },
+        Unifiable::SComplex(terms) => {
+            count.1 += terms.capacity() * size_of::<Unifiable>();
+            for t in terms { term_size(t, count); }
+        },
+        Unifiable::SFunction{name, terms} => {
+            count.1 += name.capacity() + terms.capacity() * size_of::<Unifiable>();
+            for t in terms { term_size(t, count); }
+        },
+        Unifiable::SLinkedList{term, next, count: _, tail_var: _} => {
+            count.1 += 2 * size_of::<Unifiable>();
+            term_size(term, count);
+            term_size(next, count);
+        },
+        _ => {},
+    }
+} // term_size()
+
+// Counts the terms in a goal, and adds their sizes.
+//
+// Arguments
+//    goal
+//    (number of terms, bytes)
+fn goal_size(goal: &Goal, count: &mut (usize, usize)) {
+    match goal {
+        Goal::OperatorGoal(op) => {
+            let goals = match op {
+                Operator::And(goals) | Operator::Or(goals) |
+                Operator::Time(goals) | Operator::Not(goals) => { goals },
+            };
+            count.1 += goals.capacity() * size_of::<Goal>();
+            for g in goals { goal_size(g, count); }
+        },
+        Goal::BuiltInGoal(bip) => {
+            count.1 += bip.functor.capacity();
+            if let Some(terms) = &bip.terms {
+                count.1 += terms.capacity() * size_of::<Unifiable>();
+                for t in terms { term_size(t, count); }
+            }
+        },
+        Goal::ComplexGoal(term) => { term_size(term, count); },
+        Goal::Nil => {},
+    }
+} // goal_size()
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::*;
+
+    #[test]
+    fn test_kb_stats() {
+
+        let kb = test_kb();
+        let stats = kb_stats(&kb);
+        assert_eq!(3, stats.predicates);
+        assert_eq!(4, stats.facts);
+        assert_eq!(2, stats.rules);
+        assert_eq!(6, stats.clauses());
+        // Each fact has 4 terms: the head and 3 atoms.
+        // Each rule has 4 terms in the head, and 4 in each goal.
+        assert_eq!(40, stats.terms);
+
+        let names: Vec<&str> = stats.per_predicate.iter()
+                                    .map(|ps| ps.name.as_str()).collect();
+        assert_eq!(vec!["father/2", "grandfather/2", "loves/2"], names);
+        let father = &stats.per_predicate[0];
+        assert_eq!((2, 0, 8), (father.facts, father.rules, father.terms));
+        let grandfather = &stats.per_predicate[1];
+        assert!(grandfather.memory > father.memory);
+        assert_eq!(stats.memory, stats.per_predicate.iter().map(|ps| ps.memory).sum::<usize>());
+
+        let empty = kb_stats(&KnowledgeBase::new());
+        assert_eq!(KbStats::default(), empty);
+        assert!(stats.to_string().starts_with("Predicates: 3, facts: 4, rules: 2, terms: 40"));
+
+    } // test_kb_stats()
+
+} // test
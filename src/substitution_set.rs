@@ -4,9 +4,26 @@
 //! generates substitution sets, which record logic variable bindings.
 //! A substitution set can be thought of as a solution, or partial
 //! solution, for a given goal.
+//!
+//! Each new binding copies a few nodes of the set's trie. While a query
+//! is being solved, the buffers of nodes which are no longer needed are
+//! kept in a pool, and reused for new nodes, instead of being freed and
+//! allocated again. The pool is freed, all at once, when the search stops.
+//!
+//! Only the buffers of trie nodes are pooled. Terms and bindings are not
+//! allocated from a per-query arena: they are shared through Rc with
+//! goals, rules, solution nodes and the results which are returned to
+//! the caller, so they can outlive the query which created them. An arena
+//! would require a lifetime parameter on Unifiable, Goal and Rule, or the
+//! unstable allocator API (Rc::new_in), and would change the public API
+//! throughout. The trie buffers never leave their substitution set, so
+//! pooling them needs no such change.
 //
 // Cleve Lendon 2023
 
+use std::cell::RefCell;
+use std::mem;
+use std::ops::Index;
 use std::rc::Rc;
 
 use super::unifiable::{*, Unifiable::*};
@@ -17,16 +34,282 @@ use super::unifiable::{*, Unifiable::*};
 ///
 /// [substitution_set](../substitution_set/index.html)
 ///
+/// A substitution set is persistent: binding a variable makes a new
+/// set, and leaves the original unchanged, so that the solver can
+/// backtrack to it. The sets share most of their structure.
+///
+/// SubstitutionSet is an alias of [Substitutions](struct.Substitutions.html).
+/// Its lifetime parameter is not used.
+///
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let ss = SubstitutionSet::new();
+/// let ss2 = ss.bind(3, Rc::new(atom!("Argon")));
+/// assert_eq!(0, ss.len());
+/// assert_eq!(4, ss2.len());
+/// assert_eq!(Some(&atom!("Argon")), ss2[3].as_deref());
+/// assert_eq!(None, ss2[2]);
+/// ```
+pub type SubstitutionSet<'a> = Substitutions;
+
+/// A persistent array of bindings for logic variables.
+/// See [SubstitutionSet](type.SubstitutionSet.html).
+//
 // Note:
-// A vector is heap allocated, so why is the Box necessary?
-// One of the speed bottlenecks of the inference engine is the
-// time it takes to copy an entire substitution set. The size
-// of Unifiable is 56 bytes, while the size of a Box pointer is
-// only 8.
-// Also note:
-// It is not possible to add methods, because SubstitutionSet
-// is an alias of built-in data types.
-pub type SubstitutionSet<'a> = Vec<Option<Rc<Unifiable>>>;
+// One of the speed bottlenecks of the inference engine was the
+// time it took to copy an entire substitution set (a vector) for
+// every new binding. The set is now a trie, with 32 entries per
+// node. A new binding copies only the nodes on the path to its
+// entry, which is O(log n). The entries are Rcs, because the size
+// of Unifiable is 56 bytes, while the size of a pointer is only 8.
+#[derive(Debug, Clone, Default)]
+pub struct Substitutions {
+    root: Option<Rc<SsNode>>,
+    // The root covers 32 << shift entries.
+    shift: usize,
+    len: usize,
+}
+
+const SS_BITS: usize = 5;
+const SS_WIDTH: usize = 1 << SS_BITS;
+const SS_MASK: usize = SS_WIDTH - 1;
+
+// A node of the substitution set trie.
+#[derive(Debug)]
+enum SsNode {
+    Branch(Vec<Option<Rc<SsNode>>>),
+    Leaf(Vec<Option<Rc<Unifiable>>>),
+}
+
+// Maximum number of buffers of each kind in the node pool.
+const POOL_SIZE: usize = 4096;
+
+// Buffers of trie nodes, for reuse.
+#[derive(Default)]
+struct NodePool {
+    branches: Vec<Vec<Option<Rc<SsNode>>>>,
+    leaves: Vec<Vec<Option<Rc<Unifiable>>>>,
+}
+
+thread_local! {
+    // The node pool of the query which is being solved, or None.
+    static NODE_POOL: RefCell<Option<NodePool>> = const { RefCell::new(None) };
+}
+
+// Keeps a pool of trie node buffers while a query is being solved.
+// When the guard is dropped, the pool is freed. Nested guards, for
+// queries within a query, share the outer pool.
+pub(crate) struct NodePoolGuard {
+    owner: bool,
+}
+
+impl NodePoolGuard {
+    pub(crate) fn new() -> Self {
+        let owner = NODE_POOL.with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if pool.is_some() { return false; }
+            *pool = Some(NodePool::default());
+            true
+        });
+        NodePoolGuard{ owner }
+    }
+} // impl NodePoolGuard
+
+impl Drop for NodePoolGuard {
+    fn drop(&mut self) {
+        if self.owner {
+            let pool = NODE_POOL.with(|pool| pool.borrow_mut().take());
+            drop(pool);
+        }
+    }
+} // Drop
+
+// Returns the buffer of a node to the pool, if there is one.
+// The entries are released first, because they may return
+// buffers to the pool too. A node which is dropped while the
+// thread's locals are being destroyed (for example, a node held
+// by another thread local) is simply freed.
+impl Drop for SsNode {
+    fn drop(&mut self) {
+        match self {
+            SsNode::Branch(children) => {
+                let mut children = mem::take(children);
+                children.clear();
+                let _ = NODE_POOL.try_with(|pool| {
+                    if let Some(pool) = pool.borrow_mut().as_mut() {
+                        if pool.branches.len() < POOL_SIZE { pool.branches.push(children); }
+                    }
+                });
+            },
+            SsNode::Leaf(entries) => {
+                let mut entries = mem::take(entries);
+                entries.clear();
+                let _ = NODE_POOL.try_with(|pool| {
+                    if let Some(pool) = pool.borrow_mut().as_mut() {
+                        if pool.leaves.len() < POOL_SIZE { pool.leaves.push(entries); }
+                    }
+                });
+            },
+        }
+    }
+} // Drop
+
+// Gets an empty buffer for a branch node, from the pool if possible.
+fn branch_buffer() -> Vec<Option<Rc<SsNode>>> {
+    let buffer = NODE_POOL.with(|pool| {
+        pool.borrow_mut().as_mut().and_then(|pool| pool.branches.pop())
+    });
+    return buffer.unwrap_or_else(|| Vec::with_capacity(SS_WIDTH));
+}
+
+// Gets an empty buffer for a leaf node, from the pool if possible.
+fn leaf_buffer() -> Vec<Option<Rc<Unifiable>>> {
+    let buffer = NODE_POOL.with(|pool| {
+        pool.borrow_mut().as_mut().and_then(|pool| pool.leaves.pop())
+    });
+    return buffer.unwrap_or_else(|| Vec::with_capacity(SS_WIDTH));
+}
+
+impl Substitutions {
+
+    /// Creates an empty substitution set.
+    pub fn new() -> Self {
+        Substitutions{ root: None, shift: 0, len: 0 }
+    }
+
+    /// Gets the length of the set, which is one more than the
+    /// highest ID of a bound variable.
+    pub fn len(&self) -> usize { self.len }
+
+    /// Determines whether the set is empty.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Gets the entry for a variable ID.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * variable ID
+    /// # Return
+    /// * Some(binding or None), or None if the ID is out of range
+    pub fn get(&self, id: usize) -> Option<&Option<Rc<Unifiable>>> {
+        if id >= self.len { return None; }
+        let mut node = match &self.root {
+            Some(node) => { node },
+            None => { return Some(&None); },
+        };
+        let mut level = self.shift;
+        loop {
+            match &**node {
+                SsNode::Branch(children) => {
+                    match &children[(id >> level) & SS_MASK] {
+                        Some(child) => { node = child; },
+                        None => { return Some(&None); },
+                    }
+                    level -= SS_BITS;
+                },
+                SsNode::Leaf(entries) => { return Some(&entries[id & SS_MASK]); },
+            }
+        }
+    } // get()
+
+    /// Makes a new substitution set, which binds a variable to a term.
+    /// The original set is not changed.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * variable ID
+    /// * term
+    /// # Return
+    /// * new substitution set
+    pub fn bind(&self, id: usize, term: Rc<Unifiable>) -> Self {
+        return self.assoc(id, Some(term));
+    }
+
+    /// Adds an entry to the end of the set.
+    pub fn push(&mut self, entry: Option<Rc<Unifiable>>) {
+        *self = self.assoc(self.len, entry);
+    }
+
+    /// Iterates over the entries of the set, in order of variable ID.
+    pub fn iter(&self) -> impl Iterator<Item = &Option<Rc<Unifiable>>> + '_ {
+        (0..self.len).map(move |id| &self[id])
+    }
+
+    // Makes a new set, with the entry for an ID replaced.
+    //
+    // Arguments
+    //    variable ID
+    //    entry
+    // Return
+    //    new substitution set
+    fn assoc(&self, id: usize, entry: Option<Rc<Unifiable>>) -> Self {
+        let mut root = self.root.clone();
+        let mut shift = self.shift;
+        // Add levels until the trie is large enough.
+        while id >= SS_WIDTH << shift {
+            if root.is_some() {
+                let mut children = branch_buffer();
+                children.resize(SS_WIDTH, None);
+                children[0] = root;
+                root = Some(Rc::new(SsNode::Branch(children)));
+            }
+            shift += SS_BITS;
+        }
+        let root = Some(assoc_node(root.as_deref(), shift, id, entry));
+        return Substitutions{ root, shift, len: self.len.max(id + 1) };
+    } // assoc()
+
+} // impl Substitutions
+
+// Copies the path from a node to the entry for an ID, and replaces the entry.
+//
+// Arguments
+//    node, or None if there is no node yet
+//    level (shift) of the node
+//    variable ID
+//    entry
+// Return
+//    new node
+fn assoc_node(node: Option<&SsNode>, level: usize, id: usize,
+              entry: Option<Rc<Unifiable>>) -> Rc<SsNode> {
+    let index = (id >> level) & SS_MASK;
+    if level == 0 {
+        let mut entries = leaf_buffer();
+        match node {
+            Some(SsNode::Leaf(old)) => { entries.extend(old.iter().cloned()); },
+            _ => { entries.resize(SS_WIDTH, None); },
+        }
+        entries[index] = entry;
+        return Rc::new(SsNode::Leaf(entries));
+    }
+    let mut children = branch_buffer();
+    match node {
+        Some(SsNode::Branch(old)) => { children.extend(old.iter().cloned()); },
+        _ => { children.resize(SS_WIDTH, None); },
+    }
+    let child = assoc_node(children[index].as_deref(), level - SS_BITS, id, entry);
+    children[index] = Some(child);
+    return Rc::new(SsNode::Branch(children));
+} // assoc_node()
+
+impl Index<usize> for Substitutions {
+    type Output = Option<Rc<Unifiable>>;
+    fn index(&self, id: usize) -> &Self::Output {
+        match self.get(id) {
+            Some(entry) => { entry },
+            None => { panic!("SubstitutionSet - Index out of range: {}", id); },
+        }
+    }
+} // Index
+
+impl PartialEq for Substitutions {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+} // PartialEq
 
 /// Is the logic variable bound?
 ///
@@ -45,7 +328,7 @@ pub type SubstitutionSet<'a> = Vec<Option<Rc<Unifiable>>>;
 /// use suiron::*;
 ///
 /// let a = atom!("Promethium");
-/// let x = logic_var!(next_id(), "$X");
+/// let x = logic_var!(1, "$X");
 ///
 /// let ss = empty_ss!();
 /// let ss = x.unify(&a, &ss).unwrap();   // x -> a
@@ -83,10 +366,9 @@ pub fn is_bound(term: &Unifiable, ss: &SubstitutionSet) -> bool {
 /// use std::rc::Rc;
 /// use suiron::*;
 ///
-/// clear_id();
 /// let ne = atom!("Neon");
-/// let x = logic_var!(next_id(), "$X");
-/// let y = logic_var!(next_id(), "$Y");
+/// let x = logic_var!(1, "$X");
+/// let y = logic_var!(2, "$Y");
 /// let ss = empty_ss!();
 ///
 /// // Bind $X_1 -> Neon
@@ -137,9 +419,9 @@ pub fn get_binding<'a>(term: &Unifiable, ss: &'a SubstitutionSet)
 /// use suiron::*;
 ///
 /// let a = atom!("Promethium");
-/// let x = logic_var!(next_id(), "$X");
-/// let y = logic_var!(next_id(), "$Y");
-/// let z = logic_var!(next_id(), "$Z");
+/// let x = logic_var!(1, "$X");
+/// let y = logic_var!(2, "$Y");
+/// let z = logic_var!(3, "$Z");
 ///
 /// let ss = empty_ss!();
 ///
@@ -195,9 +477,9 @@ pub fn is_ground_variable(term: &Unifiable, ss: &SubstitutionSet) -> bool {
 /// use suiron::*;
 ///
 /// let a = atom!("Promethium");
-/// let x = logic_var!(next_id(), "$X");
-/// let y = logic_var!(next_id(), "$Y");
-/// let z = logic_var!(next_id(), "$Z");
+/// let x = logic_var!(1, "$X");
+/// let y = logic_var!(2, "$Y");
+/// let z = logic_var!(3, "$Z");
 ///
 /// let ss = empty_ss!();
 ///
@@ -243,8 +525,8 @@ pub fn get_ground_term<'a>(term: &'a Unifiable, ss: &'a SubstitutionSet)
 /// use suiron::*;
 ///
 /// let zol = parse_complex("music(Dilnaz, Zolotoi)").unwrap();
-/// let x = logic_var!(next_id(), "$X");
-/// let y = logic_var!(next_id(), "$Y");
+/// let x = logic_var!(1, "$X");
+/// let y = logic_var!(2, "$Y");
 /// let ss = empty_ss!();
 /// let ss = x.unify(&zol, &ss).unwrap();  // $X -> music()
 /// let ss = y.unify(&x, &ss).unwrap();    // $Y -> $X -> music()
@@ -298,8 +580,8 @@ pub fn get_complex<'a>(term: &'a Unifiable, ss: &'a SubstitutionSet) -> Option<&
 /// let l1 = slist!(false, ar, kr);  // [Argon, Krypton]
 ///
 /// // Unify $X with [Argon, Krypton].
-/// let x = logic_var!(next_id(), "$X");
-/// let y = logic_var!(next_id(), "$Y");
+/// let x = logic_var!(1, "$X");
+/// let y = logic_var!(2, "$Y");
 /// let ss = empty_ss!();
 /// let ss = x.unify(&l1, &ss).unwrap();  // $X -> [Argon, Krypton]
 /// let ss = y.unify(&x, &ss).unwrap();  // $Y -> $X -> [Argon, Krypton]
@@ -331,8 +613,8 @@ pub fn get_list<'a>(term: &'a Unifiable, ss: &'a SubstitutionSet)
 
 /// Checks whether a term is a constant or bound to a constant.
 ///
-/// If the given term is a simple constant (Atom, SFloat or SInteger),
-/// return it. If the given term is a logic variable, check the
+/// If the given term is a simple constant (Atom, SString, SFloat or
+/// SInteger), return it. If the given term is a logic variable, check the
 /// substitution set to determine whether it is ultimately bound to
 /// a constant. If it is, return the constant. Otherwise, return None.
 ///
@@ -347,8 +629,8 @@ pub fn get_list<'a>(term: &'a Unifiable, ss: &'a SubstitutionSet)
 /// use suiron::*;
 ///
 /// let kr = atom!("Krypton");
-/// let x = logic_var!(next_id(), "$X");
-/// let y = logic_var!(next_id(), "$Y");
+/// let x = logic_var!(1, "$X");
+/// let y = logic_var!(2, "$Y");
 /// let ss = empty_ss!();
 /// let ss = kr.unify(&x, &ss).unwrap(); // $X -> Argon
 /// let ss = y.unify(&x, &ss).unwrap(); // $Y -> $X -> Argon
@@ -362,6 +644,7 @@ pub fn get_constant<'a>(term: &'a Unifiable, ss: &'a SubstitutionSet)
     match *term {
         Unifiable::SFloat(_) |
         Unifiable::SInteger(_) |
+        Unifiable::SString(_) |
         Unifiable::Atom(_) => { return Some(term); },
         Unifiable::LogicVar{id: _, name: _} => {
             match get_ground_term(term, ss) {
@@ -369,6 +652,7 @@ pub fn get_constant<'a>(term: &'a Unifiable, ss: &'a SubstitutionSet)
                     match gt {
                         Unifiable::SFloat(_) |
                         Unifiable::SInteger(_) |
+                        Unifiable::SString(_) |
                         Unifiable::Atom(_) => { return Some(gt); },
                         _ => None,
                     }
@@ -380,11 +664,44 @@ pub fn get_constant<'a>(term: &'a Unifiable, ss: &'a SubstitutionSet)
     }
 } // get_constant()
 
+/// Gets the text of a constant, or of a variable bound to a constant.
+///
+/// For atoms and strings, the text is returned as is. (Strings are not
+/// enclosed in quotation marks.) Numbers are converted to text.
+/// If the term is not a constant, and is not bound to a constant,
+/// None is returned.
+///
+/// # Arguments
+/// * `term`  - [Unifiable](../unifiable/enum.Unifiable.html)
+/// * `ss` - substitution set
+/// # Return
+/// * `Option` - Some(String) or None
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let greeting = SString("Hello".to_string());
+/// let x = logic_var!(1, "$X");
+/// let ss = empty_ss!();
+/// let ss = x.unify(&greeting, &ss).unwrap();
+///
+/// let text = get_text(&x, &ss).unwrap();
+/// println!("{}", text);  // Prints: Hello
+/// ```
+///
+pub fn get_text(term: &Unifiable, ss: &SubstitutionSet) -> Option<String> {
+    match get_constant(term, ss)? {
+        Unifiable::Atom(s) => { return Some(s.to_string()); },
+        Unifiable::SString(s) => { return Some(s.to_string()); },
+        Unifiable::SInteger(i) => { return Some(i.to_string()); },
+        Unifiable::SFloat(f) => { return Some(f.to_string()); },
+        _ => { return None; },
+    }
+} // get_text()
+
 
 /// Formats a substitution set for display. Use for debugging.
-/// # Note
-/// * It is not possible to implement the Display trait for
-///   SubstitutionSet, because it is a type alias.
 /// # Arguments
 /// * `ss` - substitution set
 /// # Return
@@ -394,10 +711,9 @@ pub fn get_constant<'a>(term: &'a Unifiable, ss: &'a SubstitutionSet)
 /// use std::rc::Rc;
 /// use suiron::*;
 ///
-/// clear_id();
 /// let ss = empty_ss!();
 /// let ar = atom!("Argon");
-/// let x = logic_var!(next_id(), "$X");
+/// let x = logic_var!(1, "$X");
 /// let ss = ar.unify(&x, &ss).unwrap();
 /// let f = format_ss(&ss);
 /// println!("{}", f);
@@ -433,10 +749,9 @@ pub fn format_ss(ss: &SubstitutionSet) -> String {
 /// use std::rc::Rc;
 /// use suiron::*;
 ///
-/// clear_id();
 /// let ss = empty_ss!();
 /// let ar = atom!("Argon");
-/// let x = logic_var!(next_id(), "$X");
+/// let x = logic_var!(1, "$X");
 /// let ss = ar.unify(&x, &ss).unwrap();
 /// print_ss(&ss);
 /// ```
@@ -484,6 +799,42 @@ mod test {
 
     } // test_format_ss()
 
+    // Test that binding a variable leaves the original set unchanged.
+    #[test]
+    fn test_persistent_ss() {
+
+        let mut sets = vec![SubstitutionSet::new()];
+        for id in 1..2000 {
+            let ss = sets.last().unwrap().bind(id, Rc::new(SInteger(id as i64)));
+            sets.push(ss);
+        }
+        assert!(sets[0].is_empty());
+        for (n, ss) in sets.iter().enumerate().skip(1) {
+            assert_eq!(n + 1, ss.len());
+            assert_eq!(None, ss[0]);
+            for id in 1..=n {
+                assert_eq!(Some(&SInteger(id as i64)), ss[id].as_deref());
+            }
+            assert_eq!(None, ss.get(n + 1));
+        }
+
+        // Bind a high ID first, then a low one.
+        let ss = SubstitutionSet::new().bind(40000, Rc::new(atom!("high")));
+        let ss2 = ss.bind(7, Rc::new(atom!("low")));
+        assert_eq!(40001, ss2.len());
+        assert_eq!(None, ss[7]);
+        assert_eq!(Some(&atom!("low")), ss2[7].as_deref());
+        assert_eq!(Some(&atom!("high")), ss2[40000].as_deref());
+        assert_eq!(2, ss2.iter().filter(|entry| entry.is_some()).count());
+
+        let mut ss3 = SubstitutionSet::new();
+        ss3.push(None);
+        ss3.push(Some(Rc::new(atom!("low"))));
+        assert_eq!(ss3, SubstitutionSet::new().bind(1, Rc::new(atom!("low"))));
+        assert_ne!(ss3, ss2);
+
+    } // test_persistent_ss()
+
     // Test is_bound(), is_ground_variable(), get_binding(), get_ground_term().
     #[test]
     fn test_bound_and_ground() {
@@ -662,4 +1013,52 @@ mod test {
         else { panic!("Third test - Cannot get ground term (Argon)."); }
     } // test_get_constant
 
+    // Buffers of trie nodes are reused during a query, and freed after it.
+    #[test]
+    fn test_node_pool() {
+
+        let pooled = || super::NODE_POOL.with(|pool| {
+            pool.borrow().as_ref().map(|pool| pool.branches.len() + pool.leaves.len())
+        });
+
+        let guard = super::NodePoolGuard::new();
+        let inner = super::NodePoolGuard::new();
+        let mut ss = SubstitutionSet::new();
+        for id in 0..100 { ss = ss.bind(id, Rc::new(SInteger(id as i64))); }
+        let kept = ss.bind(200, Rc::new(atom!("end")));
+        drop(ss);
+        drop(inner);  // The outer guard owns the pool.
+        assert!(pooled().unwrap() > 0);
+
+        // A set which outlives the pool is unchanged.
+        drop(guard);
+        assert_eq!(None, pooled());
+        assert_eq!(Some(&SInteger(99)), kept[99].as_deref());
+        assert_eq!(Some(&atom!("end")), kept[200].as_deref());
+        assert_eq!(None, kept[150]);
+
+        // Queries use the pool.
+        let kb = test_kb();
+        let query = parse_query("grandfather($X, $Y)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = Alfred, $Y = Aethelstan", solve(sn));
+        assert_eq!(None, pooled());
+
+        // A set which is held by a thread local can be dropped when the
+        // thread ends, whether the pool is destroyed before it or after.
+        thread_local! {
+            static HELD: std::cell::RefCell<Option<SubstitutionSet<'static>>> =
+                const { std::cell::RefCell::new(None) };
+        }
+        let hold = || {
+            let ss = SubstitutionSet::new().bind(40, Rc::new(atom!("held")));
+            HELD.with(|held| { *held.borrow_mut() = Some(ss); });
+        };
+        let pool_first = std::thread::spawn(move || { pooled(); hold(); });
+        assert!(pool_first.join().is_ok());
+        let pool_last = std::thread::spawn(move || { HELD.with(|_| {}); hold(); });
+        assert!(pool_last.join().is_ok());
+
+    } // test_node_pool()
+
 } // test
@@ -0,0 +1,251 @@
+//! Interned text of atoms.
+//!
+//! Atoms are compared and cloned constantly during unification and when
+//! rules are fetched from the knowledge base. To make these operations
+//! cheap, the text of each atom is stored once, in a global symbol table.
+//! A [Symbol](struct.Symbol.html) is a shared reference to that text.
+//!
+//! Because each distinct text is stored only once, two symbols are equal
+//! if and only if they point to the same text. Comparing symbols is a
+//! pointer comparison, and cloning a symbol only increments a reference
+//! count.
+//!
+//! The table holds weak references. When the last symbol for a text is
+//! dropped, the text is removed from the table. Thus, atoms which are
+//! made at runtime, by split_string() or by loading JSON, for example,
+//! do not accumulate in a long-running process.
+//
+// Cleve Lendon 2023
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, Weak};
+
+// The symbol table. Texts are found by their hash codes. Each entry
+// is alive, because it is removed before its last symbol is freed.
+struct SymbolTable {
+    hasher: RandomState,
+    entries: HashMap<u64, Vec<Weak<str>>>,
+}
+
+static SYMBOLS: OnceLock<Mutex<SymbolTable>> = OnceLock::new();
+
+// Locks the symbol table.
+fn symbol_table() -> MutexGuard<'static, SymbolTable> {
+    let table = SYMBOLS.get_or_init(|| {
+        Mutex::new(SymbolTable{ hasher: RandomState::new(), entries: HashMap::new() })
+    });
+    return table.lock().unwrap_or_else(|e| e.into_inner());
+}
+
+/// The interned text of an atom.
+///
+/// A symbol dereferences to a string slice.
+///
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let s1 = Symbol::new("Harold");
+/// let s2 = Symbol::new(&"Harold".to_string());
+/// assert_eq!(s1, s2);
+/// assert_eq!("Harold", s1);
+/// println!("{} has {} letters.", s1, s1.len());
+/// ```
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+
+    /// Gets the symbol for the given text, adding it to the symbol table
+    /// if necessary.
+    ///
+    /// # Arguments
+    /// * text
+    /// # Return
+    /// * symbol
+    pub fn new(text: &str) -> Symbol {
+        let mut table = symbol_table();
+        let code = table.hasher.hash_one(text);
+        let bucket = table.entries.entry(code).or_default();
+        let found = bucket.iter().filter_map(Weak::upgrade).find(|s| &**s == text);
+        if let Some(s) = found { return Symbol(s); }
+        let s: Arc<str> = Arc::from(text);
+        bucket.push(Arc::downgrade(&s));
+        return Symbol(s);
+    } // new()
+
+    /// Gets the text of the symbol.
+    pub fn as_str(&self) -> &str { &self.0 }
+
+} // impl Symbol
+
+// Removes the text from the symbol table when its last symbol is dropped.
+// Other symbols for the text can only be made while the table is locked,
+// so the count is checked again after locking.
+impl Drop for Symbol {
+    fn drop(&mut self) {
+        if Arc::strong_count(&self.0) > 1 { return; }
+        let mut table = symbol_table();
+        if Arc::strong_count(&self.0) > 1 { return; }
+        let code = table.hasher.hash_one(&*self.0);
+        if let Some(bucket) = table.entries.get_mut(&code) {
+            let weak = Arc::downgrade(&self.0);
+            bucket.retain(|s| !s.ptr_eq(&weak));
+            if bucket.is_empty() { table.entries.remove(&code); }
+        }
+    }
+} // Drop
+
+impl Deref for Symbol {
+    type Target = str;
+    fn deref(&self) -> &str { &self.0 }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str { &self.0 }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str { &self.0 }
+}
+
+impl From<&str> for Symbol {
+    fn from(text: &str) -> Symbol { Symbol::new(text) }
+}
+
+impl From<String> for Symbol {
+    fn from(text: String) -> Symbol { Symbol::new(&text) }
+}
+
+// Symbols are unique, so equality is identity.
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Symbol) -> bool { Arc::ptr_eq(&self.0, &other.0) }
+}
+
+impl Eq for Symbol {}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool { &*self.0 == other }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool { &*self.0 == *other }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool { *self.0 == **other }
+}
+
+impl PartialEq<Symbol> for &str {
+    fn eq(&self, other: &Symbol) -> bool { **self == *other.0 }
+}
+
+impl PartialEq<Symbol> for String {
+    fn eq(&self, other: &Symbol) -> bool { **self == *other.0 }
+}
+
+// Hashing is consistent with equality: equal symbols have equal text.
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.0.hash(state); }
+}
+
+// Symbols are ordered by their text.
+impl Ord for Symbol {
+    fn cmp(&self, other: &Symbol) -> Ordering {
+        if self == other { return Ordering::Equal; }
+        self.0.cmp(&other.0)
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Symbol) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+// With the serde feature, a symbol is serialized as a string.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Symbol {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Symbol, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Ok(Symbol::new(&text))
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", &*self.0)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", &*self.0)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use std::hash::BuildHasher;
+    use std::sync::Weak;
+    use crate::*;
+
+    #[test]
+    fn test_symbols() {
+        let s1 = Symbol::new("Tostig");
+        let s2 = Symbol::from("Tostig".to_string());
+        let s3 = Symbol::new("Skule");
+        assert_eq!(s1, s2);
+        assert!(std::ptr::eq(s1.as_str(), s2.as_str()));
+        assert_ne!(s1, s3);
+        assert!(s3 < s1);
+        assert_eq!("Tostig", s1);
+        assert_eq!(s1, "Tostig");
+        assert_eq!("\"Tostig\"", format!("{:?}", s1));
+        assert_eq!(atom!("Tostig"), Unifiable::Atom(s2));
+    }
+
+    // Determines whether a text is in the symbol table.
+    fn interned(text: &str) -> bool {
+        let table = super::symbol_table();
+        let code = table.hasher.hash_one(text);
+        return table.entries.get(&code).is_some_and(|bucket| {
+            bucket.iter().filter_map(Weak::upgrade).any(|s| &*s == text)
+        });
+    }
+
+    // Texts are removed from the table when their symbols are dropped.
+    #[test]
+    fn test_prune_symbols() {
+        let text = "test_prune_symbols: Eadgyth";
+        let s1 = Symbol::new(text);
+        let s2 = s1.clone();
+        let term = scomplex!(atom!("name"), atom!(text));
+        assert!(interned(text));
+        drop(s1);
+        assert!(interned(text));
+        drop(s2);
+        assert!(interned(text));
+        drop(term);
+        assert!(!interned(text));
+
+        // A new symbol for the same text is equal to later ones.
+        let s3 = Symbol::new(text);
+        assert_eq!(s3, Symbol::new(text));
+        assert!(interned(text));
+    }
+
+} // test
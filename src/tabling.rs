@@ -0,0 +1,379 @@
+//! Tabled predicates, which remember their answers.
+//!
+//! A predicate is tabled with the directive `:- table path/2.` or with
+//! [set_tabled()](../knowledge_base/struct.KnowledgeBase.html#method.set_tabled).
+//! When a goal of a tabled predicate is called, all of its answers are
+//! found, and saved in a table. Later calls of the same goal (or a variant
+//! of it, which differs only in the names of its variables) get their
+//! answers from the table.
+//!
+//! While a table is being filled, a recursive call of the same goal gets
+//! the answers which have been found so far. The goal is solved again and
+//! again, until no new answers are found. Thus, left-recursive rules, such
+//! as the following, terminate, even if the graph has cycles:
+//! <pre>
+//!     :- table path/2.
+//!     path($X, $Y) :- path($X, $Z), edge($Z, $Y).
+//!     path($X, $Y) :- edge($X, $Y).
+//! </pre>
+//!
+//! Goals which call each other recursively are completed together. The
+//! tables are kept in the knowledge base, and discarded whenever its facts
+//! and rules are modified.
+//!
+//! A tabled predicate should not have side effects, such as printing, and
+//! should not use the cut (!), because its rules may be solved many times,
+//! and its answers are returned in the order in which they were found.
+//!
+// Cleve Lendon 2023
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use crate::*;
+
+use super::logic_var::*;
+use super::unifiable::Unifiable;
+
+/// The tables of a knowledge base.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Tables {
+    // Tables, by the variant key of their goals.
+    tables: HashMap<String, Table>,
+    // Keys of the tables which are being filled, with the position
+    // of the oldest table on the stack which each one depends on.
+    stack: Vec<(String, usize)>,
+    // Tables which have been filled, but depend on a table which is
+    // still being filled.
+    pending: Vec<String>,
+    // Number of answers which have been added to all tables.
+    answer_count: usize,
+    // Number of times that goals have been solved to fill tables.
+    round: usize,
+}
+
+impl Tables {
+
+    /// Discards all tables.
+    pub(crate) fn clear(&mut self) {
+        *self = Tables::default();
+    }
+
+} // impl Tables
+
+// The answers of one goal.
+#[derive(Debug, Clone, Default)]
+struct Table {
+    answers: Rc<Vec<Rule>>,
+    // Variant keys of the answers, to avoid duplicates.
+    keys: HashSet<String>,
+    complete: bool,
+    filling: bool,
+    // Values of answer_count and round when the table was last filled.
+    filled_at: usize,
+    filled_round: usize,
+    // Position on the stack of the oldest table which this one
+    // depends on, if it is pending.
+    leader: usize,
+}
+
+/// Gets the answers of a goal of a tabled predicate.
+///
+/// If the goal has not been tabled yet, its answers are found and saved.
+/// The answers are facts, which can be unified with the goal.
+///
+/// # Arguments
+/// * knowledge base
+/// * goal (complex term), with bound variables replaced by their values
+/// # Return
+/// * answers (facts)
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// add_rules!(&mut kb, parse_rule("edge(a, b).").unwrap(),
+///                     parse_rule("edge(b, a).").unwrap(),
+///                     parse_rule("path($X, $Y) :- path($X, $Z), edge($Z, $Y).").unwrap(),
+///                     parse_rule("path($X, $Y) :- edge($X, $Y).").unwrap());
+/// kb.set_tabled("path/2");
+///
+/// let goal = parse_complex("path(a, $Y)").unwrap();
+/// for answer in tabled_answers(&kb, &goal).iter() {
+///     println!("{}", answer);
+/// }
+/// // Prints:
+/// // path(a, b).
+/// // path(a, a).
+/// ```
+pub fn tabled_answers(kb: &KnowledgeBase, goal: &Unifiable) -> Rc<Vec<Rule>> {
+
+    let goal = variant(goal, &mut vec![]);
+    let key = goal.to_string();
+
+    {
+        let mut tables = kb.tables().borrow_mut();
+        let (answer_count, round) = (tables.answer_count, tables.round);
+        if let Some(table) = tables.tables.get(&key) {
+            let answers = Rc::clone(&table.answers);
+            if table.complete { return answers; }
+            if table.filling {
+                // A recursive call. The tables above this one on the
+                // stack depend on it, and cannot be completed before it.
+                if let Some(position) = tables.stack.iter().position(|(k, _)| *k == key) {
+                    depend_on(&mut tables, position);
+                }
+                return answers;
+            }
+            // A pending table, which has not changed since it was filled,
+            // or was filled during the current round. (If any table has
+            // changed, its leader will start another round.)
+            if table.filled_at == answer_count || table.filled_round == round {
+                let leader = table.leader;
+                depend_on(&mut tables, leader);
+                return answers;
+            }
+        }
+    }
+    return fill_table(kb, goal, key);
+
+} // tabled_answers()
+
+// Fills the table of a goal, by solving the goal until no new answers
+// are found. If the table does not depend on other tables which are
+// being filled, it is completed, with the tables which depend on it.
+// Otherwise, the goal is solved only once. The oldest table which it
+// depends on (its leader) solves its goal again, until no table changes.
+//
+// Arguments
+//    knowledge base
+//    goal (variant)
+//    key of the goal
+// Return
+//    answers
+fn fill_table(kb: &KnowledgeBase, goal: Unifiable, key: String) -> Rc<Vec<Rule>> {
+
+    let (position, first_pending) = {
+        let mut tables = kb.tables().borrow_mut();
+        let position = tables.stack.len();
+        let first_pending = tables.pending.len();
+        tables.stack.push((key.clone(), position));
+        tables.tables.entry(key.clone()).or_default().filling = true;
+        (position, first_pending)
+    };
+
+    loop {
+        let count = {
+            let mut tables = kb.tables().borrow_mut();
+            tables.round += 1;
+            tables.answer_count
+        };
+        solve_clauses(kb, &goal, &mut |answer| { add_answer(kb, &key, answer); });
+        let tables = kb.tables().borrow();
+        if tables.answer_count == count { break; }
+        if tables.stack.last().is_some_and(|(_, leader)| *leader < position) { break; }
+    }
+
+    let mut tables = kb.tables().borrow_mut();
+    let leader = tables.stack.pop().map_or(position, |(_, leader)| leader);
+    let (answer_count, round) = (tables.answer_count, tables.round);
+
+    if leader < position {
+        // The pending tables which depended on this table, or on the
+        // tables above it, now depend on its leader.
+        let pending: Vec<String> = tables.pending[first_pending..].to_vec();
+        for k in &pending {
+            if let Some(table) = tables.tables.get_mut(k) {
+                if table.leader >= position { table.leader = leader; }
+            }
+        }
+        tables.pending.push(key.clone());
+    }
+    else {
+        let completed: Vec<String> = tables.pending.drain(first_pending..).collect();
+        for k in &completed {
+            if let Some(table) = tables.tables.get_mut(k) { table.complete = true; }
+        }
+    }
+
+    match tables.tables.get_mut(&key) {
+        Some(table) => {
+            table.filling = false;
+            table.filled_at = answer_count;
+            table.filled_round = round;
+            table.complete = leader >= position;
+            table.leader = leader;
+            return Rc::clone(&table.answers);
+        },
+        None => { return Rc::new(vec![]); },  // Should not happen.
+    }
+
+} // fill_table()
+
+// Records that the tables above a position on the stack depend on
+// the table at that position.
+fn depend_on(tables: &mut Tables, position: usize) {
+    for entry in tables.stack.iter_mut().skip(position + 1) {
+        entry.1 = entry.1.min(position);
+    }
+} // depend_on()
+
+// Solves a goal with the facts and rules of its predicate, and
+// passes each solution to a function.
+//
+// Arguments
+//    knowledge base
+//    goal (variant)
+//    function which receives the solutions
+fn solve_clauses(kb: &KnowledgeBase, goal: &Unifiable,
+                 on_answer: &mut impl FnMut(Unifiable)) {
+
+    let predicate_name = match goal.key() {
+        Ok(key) => { key },
+        Err(_) => { return; },
+    };
+
+    // Answers must not be cut off by an iterative-deepening search.
+    let bound = depth_bound();
+    set_depth_bound(None);
+
+    let ids = VarIds::new();
+    let goal = goal.clone().recreate_variables(&mut VarMap::with_ids(&ids));
+    let base = rc_cell!(SolutionNode::with_var_ids(Rc::new(Goal::Nil), kb, ids.clone()));
+
+    for index in 0..count_rules(kb, &predicate_name) {
+        let rule = match fetch_rule(kb, &predicate_name, index, &ids) {
+            Some(rule) => { rule },
+            None => { break; },
+        };
+        let ss = match rule.get_head().unify(&goal, &empty_ss!()) {
+            Some(ss) => { ss },
+            None => { continue; },
+        };
+        let body = rule.get_body();
+        if body == Goal::Nil {
+            on_answer(goal.replace_variables(&ss));
+            continue;
+        }
+        let sn = make_solution_node(Rc::new(body), kb, ss, Rc::clone(&base));
+        while let Some(ss) = next_solution(Rc::clone(&sn)) {
+            on_answer(goal.replace_variables(&ss));
+        }
+    }
+    set_depth_bound(bound);
+
+} // solve_clauses()
+
+// Adds an answer to a table, if it is new.
+//
+// Arguments
+//    knowledge base
+//    key of the table
+//    answer (goal with variables replaced)
+fn add_answer(kb: &KnowledgeBase, key: &str, answer: Unifiable) {
+    let answer = variant(&answer, &mut vec![]);
+    let mut tables = kb.tables().borrow_mut();
+    let table = match tables.tables.get_mut(key) {
+        Some(table) => { table },
+        None => { return; },
+    };
+    if table.keys.insert(answer.to_string()) {
+        Rc::make_mut(&mut table.answers).push(make_fact(answer));
+        tables.answer_count += 1;
+    }
+} // add_answer()
+
+// Renames the variables of a term in order of appearance: $V1, $V2...
+// Terms which differ only in the names of their variables (variants)
+// have the same form, and thus the same key.
+//
+// Arguments
+//    term
+//    IDs of the variables which have been renamed
+// Return
+//    new term
+pub(crate) fn variant(term: &Unifiable, vars: &mut Vec<usize>) -> Unifiable {
+    match term {
+        Unifiable::LogicVar{id, name: _} => {
+            let n = match vars.iter().position(|v| v == id) {
+                Some(n) => { n },
+                None => { vars.push(*id); vars.len() - 1 },
+            };
+            return Unifiable::LogicVar{id: 0, name: format!("$V{}", n + 1)};
+        },
+        Unifiable::SComplex(terms) => {
+            return Unifiable::SComplex(terms.iter().map(|t| variant(t, vars)).collect());
+        },
+        Unifiable::SFunction{name, terms} => {
+            let terms = terms.iter().map(|t| variant(t, vars)).collect();
+            return Unifiable::SFunction{name: name.clone(), terms};
+        },
+        Unifiable::SLinkedList{term, next, count, tail_var} => {
+            return Unifiable::SLinkedList{term: Rc::new(variant(term, vars)),
+                                          next: Rc::new(variant(next, vars)),
+                                          count: *count, tail_var: *tail_var};
+        },
+        _ => { return term.clone(); },
+    }
+} // variant()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+
+    use crate::*;
+
+    fn solve_sorted(kb: &KnowledgeBase, query: &str) -> Vec<String> {
+        let query = parse_query(query).unwrap();
+        let sn = make_base_node(Rc::new(query), kb);
+        let mut results = solve_all(sn);
+        results.sort();
+        return results;
+    }
+
+    // Left-recursive and right-recursive rules terminate on a graph
+    // which has a cycle, if they are tabled.
+    #[test]
+    fn test_tabling() {
+
+        let mut kb = KnowledgeBase::new();
+        add_rules!(&mut kb, parse_rule("edge(a, b).").unwrap(),
+                            parse_rule("edge(b, c).").unwrap(),
+                            parse_rule("edge(c, a).").unwrap(),
+                            parse_rule("edge(c, d).").unwrap());
+        for rule in ["path($X, $Y) :- path($X, $Z), edge($Z, $Y).",
+                     "path($X, $Y) :- edge($X, $Y).",
+                     "path2($X, $Y) :- edge($X, $Z), path2($Z, $Y).",
+                     "path2($X, $Y) :- edge($X, $Y)."] {
+            add_rules!(&mut kb, parse_rule(rule).unwrap());
+        }
+        execute_directive(&mut kb, "table path/2.").unwrap();
+        execute_directive(&mut kb, "table(path2/2).").unwrap();
+        assert!(kb.is_tabled("path/2"));
+        assert!(kb.is_tabled("path2/2"));
+
+        let expected = vec!["$Y = a", "$Y = b", "$Y = c", "$Y = d"];
+        for strategy in [BindingStrategy::Persistent, BindingStrategy::Trail] {
+            set_binding_strategy(strategy);
+            kb.abolish_all_tables();
+            assert_eq!(expected, solve_sorted(&kb, "path(a, $Y)"));
+            assert_eq!(expected, solve_sorted(&kb, "path2(a, $Y)"));
+            assert_eq!(vec!["$X = a", "$X = b", "$X = c"], solve_sorted(&kb, "path($X, c)"));
+            assert!(solve_sorted(&kb, "path(d, $Y)").is_empty());
+            // All 12 paths.
+            assert_eq!(12, solve_sorted(&kb, "path2($X, $Y)").len());
+        }
+        set_binding_strategy(BindingStrategy::Persistent);
+
+        // Modifying the knowledge base discards the tables.
+        assert!(!kb.tables().borrow().tables.is_empty());
+        add_rules!(&mut kb, parse_rule("edge(d, e).").unwrap());
+        assert!(kb.tables().borrow().tables.is_empty());
+        assert_eq!(5, solve_sorted(&kb, "path(a, $Y)").len());
+
+    } // test_tabling()
+
+} // test
@@ -1,35 +1,41 @@
 //! Functions for limiting execution time.
 //!
-//! This module contains a global variable, SUIRON_STOP_QUERY,
-//! and therefore has 'unsafe' code.
+//! The timeout state belongs to each query. It is held by the query's
+//! [CancellationToken](../cancellation/struct.CancellationToken.html).
 
 use std::time::Duration;
 use thread_timer::ThreadTimer;
 
-use super::logic_var::*;
-
-static mut SUIRON_STOP_QUERY: bool = false;
+use super::cancellation::CancellationToken;
 
 /// Create a timer with a timeout in milliseconds.
 ///
-/// When the timer times out, it sets the SUIRON_STOP_QUERY
-/// flag to true, effectively halting a query.
+/// When the timer times out, it stops the query of the given
+/// cancellation token. Other queries are not affected.
 ///
 /// # Arguments
 /// * timeout in milliseconds
+/// * [CancellationToken](../cancellation/struct.CancellationToken.html) of the query
 /// # Return
 /// * ThreadTimer
 /// # Usage
 /// ```
+/// use std::rc::Rc;
 /// use suiron::*;
 ///
-/// let timer = start_query_timer(300);
+/// let kb = test_kb();
+/// let query = parse_query("loves($Who, $Whom)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+///
+/// let token = cancellation_token(&sn);
+/// let timer = start_query_timer(300, &token);
 /// ```
-pub fn start_query_timer(milliseconds: u64) -> ThreadTimer {
-    unsafe { SUIRON_STOP_QUERY = false; }
+pub fn start_query_timer(milliseconds: u64, token: &CancellationToken) -> ThreadTimer {
+    token.clear_time_out();
+    let token = token.clone();
     let timer = ThreadTimer::new();
     timer.start(Duration::from_millis(milliseconds),
-                move || { stop_query(); }).unwrap();
+                move || { token.time_out(); }).unwrap();
     return timer;
 } // start_query_timer()
 
@@ -41,7 +47,7 @@ pub fn start_query_timer(milliseconds: u64) -> ThreadTimer {
 /// ```
 /// use suiron::*;
 ///
-/// let timer = start_query_timer(30);
+/// let timer = start_query_timer(30, &CancellationToken::new());
 /// cancel_timer(timer);
 /// ```
 pub fn cancel_timer(timer: ThreadTimer) {
@@ -51,57 +57,31 @@ pub fn cancel_timer(timer: ThreadTimer) {
     }
 } // cancel_timer()
 
-/// Sets the SUIRON_STOP_QUERY flag to false and LOGIC_VAR_ID to 0.
-///
-/// The SUIRON_STOP_QUERY is checked in count_rules(), in knowledgebase.rs.
-/// Setting it true effectively stops the search for a solution.
-///
-/// In order to keep the substitution set small, the LOGIC_VAR_ID is
-/// reset to 0 at the start of every query.
-pub fn start_query() {
-    unsafe { SUIRON_STOP_QUERY = false; }
-    clear_id();
-}
-
-/// Sets the SUIRON_STOP_QUERY flag to true.
-///
-/// The SUIRON_STOP_QUERY is checked in count_rules(), in knowledgebase.rs.
-/// Setting it `true` effectively stops the search for a solution.
-pub fn stop_query() {
-    unsafe { SUIRON_STOP_QUERY = true; }
-}
-
-/// Returns value of SUIRON_STOP_QUERY.
-///
-/// If the SUIRON_STOP_QUERY is true, it means that the query timed out.
-/// # Return
-/// * true/false
-pub fn query_stopped() -> bool {
-    unsafe { SUIRON_STOP_QUERY }
-}
-
 #[cfg(test)]
 mod test {
 
     use super::*;
     use std::thread;
     use std::time::Duration;
-    use serial_test::serial;
 
     // Test timer.
     // The function sleeps for 40 milliseconds, but the
     // timer times out after 30 milliseconds.
     #[test]
-    #[serial]
     fn test_query_timer() {
-        let timer = start_query_timer(30);
-        let flag = query_stopped();
-        assert_eq!(false, flag, "SUIRON_STOP_QUERY should be false.");
+        let token = CancellationToken::new();
+        let other = CancellationToken::new();
+        let timer = start_query_timer(30, &token);
+        assert_eq!(false, token.timed_out(), "The query should not time out yet.");
         let delay = Duration::from_millis(40);
         thread::sleep(delay); // Rust does sleep, Neil Young.
         cancel_timer(timer);
-        let flag = query_stopped();
-        assert_eq!(true, flag, "SUIRON_STOP_QUERY should be true.");
+        assert_eq!(true, token.timed_out(), "The query should time out.");
+        assert_eq!(false, other.is_stopped(), "Other queries are not stopped.");
+        // A new timer clears the timeout.
+        let timer = start_query_timer(30, &token);
+        assert_eq!(false, token.timed_out());
+        cancel_timer(timer);
     } // test_query_timer()
 
 } // test
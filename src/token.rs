@@ -21,9 +21,13 @@
 //! LParen Subgoal Semicolon Subgoal RParen
 //! </blockquote>
 //!
+//! Each token has a [Span](struct.Span.html), which records its location
+//! in the source text. The span of a branch token covers its children.
+//!
 // Cleve Lendon 2023
 
 use std::fmt;
+use std::ops::Range;
 
 /// Identifies tokens in the token tree and on the parse stack.
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -50,6 +54,50 @@ pub enum TokenType {
     LinkedList,
 }
 
+/// Location of a token in the source text.
+///
+/// `start` and `end` are character indices. The end is exclusive.
+/// Tokens which were not made by the tokenizer have an empty span (0, 0).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+
+    /// Creates a span from a start and end character index.
+    pub fn new(start: usize, end: usize) -> Self { Span{ start, end } }
+
+    /// Combines two spans into one span, which covers both.
+    pub fn join(&self, other: Span) -> Span {
+        Span{ start: self.start.min(other.start), end: self.end.max(other.end) }
+    }
+
+    /// Converts the span to a range of byte indices in the source text.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * source text
+    /// # Return
+    /// * byte range
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let source = "déjà_vu($X)";
+    /// let span = Span::new(0, 7);
+    /// assert_eq!("déjà_vu", &source[span.byte_range(source)]);
+    /// ```
+    pub fn byte_range(&self, source: &str) -> Range<usize> {
+        let byte_index = |n: usize| {
+            source.char_indices().nth(n).map_or(source.len(), |(i, _)| i)
+        };
+        return byte_index(self.start)..byte_index(self.end);
+    } // byte_range()
+
+} // impl Span
+
 /// Used for tokenizing Suiron source.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
@@ -57,17 +105,21 @@ pub enum Token {
     /// * Valid `token_type`s are:<br>Subgoal, Comma, Semicolon, LParen, RParen<br>
     /// * `token_str` holds the item being parsed,<br>
     /// such as `(`, `;`, or `a_subgoal(term1, term2)`.
+    /// * `span` is the location of the item in the source text.
     Leaf {
         token_type: TokenType,
         token_str: String,
+        span: Span,
     },
 
     /// This is a parent node.<br>
     /// * Valid `token_type`s are: Group, And, Or<br>
     /// * `children` is a vector of child tokens
+    /// * `span` covers the spans of the children.
     Branch {
         token_type: TokenType,
         children: Vec<Token>,
+        span: Span,
     },
 }
 
@@ -94,34 +146,40 @@ pub enum Token {
 /// ```
 ///
 pub fn make_leaf_token(symbol: &str) -> Token {
-    let s = symbol.trim();
-    match s {
-        "," => {
-            return Token::Leaf{token_type: TokenType::Comma,
-                               token_str: s.to_string(),};
-        },
-        ";" => {
-            return Token::Leaf{token_type: TokenType::Semicolon,
-                               token_str: s.to_string(),};
-        },
-        "(" => {
-            return Token::Leaf{token_type: TokenType::LParen,
-                               token_str: s.to_string(),};
-        },
-        ")" => {
-            return Token::Leaf{token_type: TokenType::RParen,
-                               token_str: s.to_string(),};
-        },
-        _ => {
-            return Token::Leaf{token_type: TokenType::Subgoal,
-                               token_str: s.to_string(),};
-        },
-    } // match
+    return make_leaf_token_at(symbol, Span::default());
 } // make_leaf_token()
 
+/// Makes a leaf token from the given string and its location in the source.
+///
+/// # Arguments
+/// * `symbol` - string slice
+/// * `span` - location of symbol
+/// # Return
+/// * `leaf token`
+/// # Usages
+/// ```
+/// use suiron::*;
+///
+/// let token = make_leaf_token_at("sister($X, $Y)", Span::new(10, 24));
+/// println!("{:?}", token.get_span());  // Prints: Span { start: 10, end: 24 }
+/// ```
+///
+pub fn make_leaf_token_at(symbol: &str, span: Span) -> Token {
+    let s = symbol.trim();
+    let token_type = match s {
+        "," => { TokenType::Comma },
+        ";" => { TokenType::Semicolon },
+        "(" => { TokenType::LParen },
+        ")" => { TokenType::RParen },
+        _   => { TokenType::Subgoal },
+    };
+    return Token::Leaf{ token_type, token_str: s.to_string(), span };
+} // make_leaf_token_at()
+
 /// Makes a branch token from a vector of child tokens.
 ///
 /// Valid branch tokens types are: Group, And, Or.
+/// The span of the branch token covers the spans of its children.
 ///
 /// # Arguments
 /// * `token_type` - TokenType
@@ -149,7 +207,11 @@ pub fn make_branch_token(token_type: TokenType, children: Vec<Token>)
         let msg = format!("make_branch_token() - Invalid token type: {}", token_type);
         panic!("{}", msg);
     }
-    Token::Branch{ token_type, children }
+    let span = match (children.first(), children.last()) {
+        (Some(first), Some(last)) => { first.get_span().join(last.get_span()) },
+        _ => { Span::default() },
+    };
+    Token::Branch{ token_type, children, span }
 } // make_branch_token()
 
 
@@ -173,8 +235,8 @@ impl Token {
     /// ```
     pub fn number_of_children(&self) -> usize {
         match self {
-            Token::Branch{ token_type: _, children } => { children.len() },
-            Token::Leaf{token_type: _, token_str: _} => {
+            Token::Branch{ children, .. } => { children.len() },
+            Token::Leaf{ .. } => {
                 panic!("number_of_children() - Token must be a branch token.");
             },
         }
@@ -195,11 +257,32 @@ impl Token {
     ///
     pub fn get_type(&self) -> TokenType {
         match self {
-            Token::Leaf{ token_type, token_str: _ } => { *token_type },
-            Token::Branch{ token_type, children: _ } => { *token_type },
+            Token::Leaf{ token_type, .. } => { *token_type },
+            Token::Branch{ token_type, .. } => { *token_type },
         }
     } // get_type()
 
+    /// Gets the location of the token in the source text.
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * `Span`
+    /// # Usage
+    /// ```
+    /// use suiron::*;
+    ///
+    /// let tree = parse_token_tree("a(1), b(2)").unwrap();
+    /// let and = &tree.get_children()[0];
+    /// let b = &and.get_children()[1];
+    /// println!("{:?}", b.get_span());  // Prints: Span { start: 6, end: 10 }
+    /// ```
+    pub fn get_span(&self) -> Span {
+        match self {
+            Token::Leaf{ span, .. } => { *span },
+            Token::Branch{ span, .. } => { *span },
+        }
+    } // get_span()
+
     /// Gets the token string. Valid only for leaf tokens.
     /// # Arguments
     /// * `self`
@@ -216,8 +299,8 @@ impl Token {
     /// ```
     pub fn get_token_str(&self) -> String {
         match self {
-            Token::Leaf{ token_type: _, token_str } => { token_str.clone() },
-            Token::Branch{token_type: _, children: _ } => {
+            Token::Leaf{ token_str, .. } => { token_str.clone() },
+            Token::Branch{ .. } => {
                 panic!("get_token_str() - Token must be a leaf token.");
             },
         }
@@ -243,14 +326,14 @@ impl Token {
     ///
     /// <pre>
     /// The above prints:
-    /// [Leaf { token_type: Subgoal, token_str: "left" }, Leaf { token_type: Subgoal, token_str: "right" }]
+    /// [Leaf { token_type: Subgoal, token_str: "left", span: Span { start: 0, end: 0 } }, Leaf { token_type: Subgoal, token_str: "right", span: Span { start: 0, end: 0 } }]
     /// </pre>
     pub fn get_children(&self) -> Vec<Token> {
         match self {
-            Token::Leaf{ token_type: _, token_str: _ } => {
+            Token::Leaf{ .. } => {
                 panic!("get_token_str() - Token must be a branch token.");
             },
-            Token::Branch{token_type: _, children } => {
+            Token::Branch{ children, .. } => {
                 return children.to_vec();
             },
         }
@@ -291,19 +374,19 @@ fn format_leaf(token_type: TokenType, token_str: String) -> String {
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Token::Leaf{ token_type, token_str } => {
+            Token::Leaf{ token_type, token_str, .. } => {
                 let s = format_leaf(*token_type, token_str.to_string());
                 write!(f, "{}", s)
             },
-            Token::Branch{ token_type, children } => {
+            Token::Branch{ token_type, children, .. } => {
                 let mut s = token_type.to_string().to_uppercase() + " >";
                 for child in children {
                     match child {
-                        Token::Leaf{ token_type, token_str } => {
+                        Token::Leaf{ token_type, token_str, .. } => {
                             s = s + " " +
                                 &format_leaf(*token_type, token_str.to_string());
                         },
-                        Token::Branch{ token_type, children: _} => {
+                        Token::Branch{ token_type, .. } => {
                             s = s + " " + &token_type.to_string().to_uppercase();
                         },
                     }
@@ -391,6 +474,16 @@ mod test {
         t2.get_token_str();
     }
 
+    #[test]
+    fn test_span() {
+        let t1 = make_leaf_token_at("left", Span::new(0, 4));
+        let t2 = make_leaf_token_at("right", Span::new(6, 11));
+        let t3 = make_branch_token(TokenType::And, vec![t1, t2]);
+        assert_eq!(Span::new(0, 11), t3.get_span());
+        assert_eq!(Span::default(), make_leaf_token("left").get_span());
+        assert_eq!(3..9, Span::new(3, 8).byte_range("$X=\"café\""));
+    }
+
     #[test]
     fn test_number_of_children() {
         let t1 = make_leaf_token("left");
@@ -412,8 +505,10 @@ mod test {
         let t1 = make_leaf_token("left");
         let t2 = make_leaf_token("right");
         let t3 = make_branch_token(TokenType::And, vec![t1, t2]);
-        let s = "[Leaf { token_type: Subgoal, token_str: \"left\" }, \
-                  Leaf { token_type: Subgoal, token_str: \"right\" }]";
+        let s = "[Leaf { token_type: Subgoal, token_str: \"left\", \
+                  span: Span { start: 0, end: 0 } }, \
+                  Leaf { token_type: Subgoal, token_str: \"right\", \
+                  span: Span { start: 0, end: 0 } }]";
         let s2 = format!("{:?}", t3.get_children());
         assert_eq!(s, s2);
     }
@@ -2,11 +2,10 @@
 //!
 // Cleve Lendon 2023
 
-use crate::str_to_chars;
-use crate::chars_to_string;
-
+use super::error::SuironError;
 use super::goal::Goal;
 use super::parse_goals::*;
+use super::parse_terms::QuoteScanner;
 use super::operator::Operator;
 use super::token::{*, Token, TokenType};
 use super::parse_stack::*;
@@ -57,19 +56,39 @@ fn invalid_between_terms(ch: char) -> bool {
 /// ```
 ///
 /// The above should print: `can_swim($X), can_fly($X")`
-pub fn generate_goal(to_parse: &str) -> Result<Goal, String> {
-    match tokenize(to_parse) {
-        Ok(tokens) => {
-            // For debugging.
-            let mut base_token = group_tokens(&tokens, 0);
-            base_token = group_and_tokens(base_token);
-            base_token = group_or_tokens(base_token);
-            return token_tree_to_goal(base_token);
-        },
-        Err(err) => { Err(err) },
-    }
+pub fn generate_goal(to_parse: &str) -> Result<Goal, SuironError> {
+    let base_token = parse_token_tree(to_parse)?;
+    return token_tree_to_goal(base_token);
 } // generate_goal()
 
+/// Divides a text string into a tree of tokens.
+///
+/// The base of the tree is a Group token. The span of each token
+/// gives its location in the given string, in characters.
+///
+/// # Arguments
+/// * string to parse
+/// # Return
+/// * [Token](../token/enum.Token.html) or error message
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let s = "can_swim($X); can_fly($X)";
+/// let tree = parse_token_tree(s).unwrap();
+/// println!("{}", tree);  // Prints: GROUP > OR
+/// let or = &tree.get_children()[0];
+/// let can_fly = &or.get_children()[1];
+/// println!("{:?}", can_fly.get_span());  // Prints: Span { start: 14, end: 25 }
+/// ```
+pub fn parse_token_tree(to_parse: &str) -> Result<Token, SuironError> {
+    let tokens = tokenize(to_parse)?;
+    let mut base_token = group_tokens(&tokens, 0);
+    base_token = group_and_tokens(base_token)?;
+    base_token = group_or_tokens(base_token)?;
+    return Ok(base_token);
+} // parse_token_tree()
+
 
 /// tokenize()
 /// Divides the given string into a series of tokens.
@@ -94,41 +113,46 @@ fn tokenize(to_parse: &str) -> Result<Vec<Token>, String> {
         return Err(msg);
     }
 
-    let mut start_index = 0;
-    let chrs = str_to_chars!(s);
-    let length = chrs.len();
+    // Positions in the string are (byte index, char index) pairs.
+    // Byte indices are used to slice the string; char indices
+    // are used for spans.
+    let mut start = (0, 0);
+
+    // Spans are relative to the untrimmed string.
+    let offset = to_parse.chars().count() - to_parse.trim_start().chars().count();
+    let leaf = |start: (usize, usize), end: (usize, usize)| {
+        leaf_token(s, start, end, offset)
+    };
 
     // Find a separator (comma, semicolon), if there is one.
     let mut previous = '#';  // random
 
-    let mut i = start_index;
-    while i < length {
+    // Position of each open group parenthesis, and the number of tokens before it.
+    let mut groups: Vec<((usize, usize), usize)> = vec![];
+
+    for sc in QuoteScanner::new(s.chars()) {
 
         // Get the top of the stack.
         let mut top = peek(&mut parse_stk);
 
-        let mut ch = chrs[i];
-        if no_esc(ch, '"', previous) { // Ignore chars between quotes.
-            let mut j = i + 1;
-            let mut prev = '#';
-            while j < length {
-                ch = chrs[j];
-                if no_esc(ch, '"', prev) {
-                    i = j;
-                    break;
-                }
-                j += 1;
-                prev = ch;
-            }
+        let ch = sc.ch;
+        let here = (sc.byte, sc.index);
+        let next = (sc.byte + ch.len_utf8(), sc.index + 1);
+
+        if sc.quoted { // Ignore chars between quotes.
         }
         else if no_esc(ch, '(', previous) {
             // Is the previous character valid in a functor?
-            if letter_number_hyphen(previous) {
+            // If the parenthesis is inside a subgoal, eg. $X is ($Y + 1) * 2,
+            // it groups an arithmetic expression, not subgoals.
+            let in_subgoal = s[start.0..sc.byte].chars().any(|c| c != ' ');
+            if letter_number_hyphen(previous) || in_subgoal {
                 parse_stk.push(TokenType::Complex);
             } else {
                 parse_stk.push(TokenType::Group);
-                tokens.push(make_leaf_token("("));
-                start_index = i + 1;
+                groups.push((here, tokens.len()));
+                tokens.push(leaf(here, next));
+                start = next;
             }
         }
         else if no_esc(ch, ')', previous) {
@@ -138,9 +162,19 @@ fn tokenize(to_parse: &str) -> Result<Vec<Token>, String> {
             }
             top = pop(&mut parse_stk);
             if top == TokenType::Group {
-                let subgoal = chars_to_string!(chrs[start_index..i]);
-                tokens.push(make_leaf_token(&subgoal));
-                tokens.push(make_leaf_token(")"));
+                let (open, number_of_tokens) = groups.pop().unwrap_or(((0, 0), 0));
+                // If an arithmetic infix follows the parenthesis, eg.
+                // ($X + 1) * 2 > $Y, the group is part of a subgoal.
+                let following = s[next.0..].trim_start_matches(' ').chars().next();
+                if following.is_some_and(|c| c != ',' && c != ';' && c != ')') {
+                    tokens.truncate(number_of_tokens);
+                    start = open;
+                }
+                else {
+                    push_subgoal(&mut tokens, leaf(start, here));
+                    tokens.push(leaf(here, next));
+                    start = next;
+                }
             } else if top != TokenType::Complex {
                 let msg = format!("tokenize() - Unmatched parenthesis: {}", s);
                 return Err(msg);
@@ -165,39 +199,64 @@ fn tokenize(to_parse: &str) -> Result<Vec<Token>, String> {
                     let msg = format!("tokenize() - Invalid character: {}", s);
                     return Err(msg);
                 }
-                if no_esc(ch, ',', previous) {   // And
-                    let subgoal = chars_to_string!(chrs[start_index..i]);
-                    tokens.push(make_leaf_token(&subgoal));
-                    tokens.push(make_leaf_token(","));
-                    start_index = i + 1;
-                } else if no_esc(ch, ';', previous) {   // Or
-                    let subgoal = chars_to_string!(chrs[start_index..i]);
-                    tokens.push(make_leaf_token(&subgoal));
-                    tokens.push(make_leaf_token(";"));
-                    start_index = i + 1;
+                if no_esc(ch, ',', previous) ||    // And
+                   no_esc(ch, ';', previous) {     // Or
+                    push_subgoal(&mut tokens, leaf(start, here));
+                    tokens.push(leaf(here, next));
+                    start = next;
                 }
             }
         } // else
 
         previous = ch;
-        i += 1;
 
-    } // while
+    } // for
 
     if parse_stk.len() > 0 {
         let msg = format!("tokenize() - Invalid term: {}", s);
         return Err(msg);
     }
 
-    if length - start_index > 0 {
-        let subgoal = chars_to_string!(chrs[start_index..length]);
-        tokens.push(make_leaf_token(&subgoal));
+    if start.0 < s.len() {
+        push_subgoal(&mut tokens, leaf(start, (s.len(), s.chars().count())));
     }
 
     return Ok(tokens);
 
 } // tokenize()
 
+// Adds a subgoal token to the list of tokens. A right parenthesis
+// which ends a group is not followed by a subgoal, eg. (a; b), c
+// so in that case, an empty subgoal is ignored.
+//
+// Arguments
+//    list of tokens
+//    subgoal token
+fn push_subgoal(tokens: &mut Vec<Token>, subgoal: Token) {
+    let after_group = tokens.last().map(|t| t.get_type()) == Some(TokenType::RParen);
+    if !(after_group && subgoal.get_token_str().is_empty()) {
+        tokens.push(subgoal);
+    }
+} // push_subgoal()
+
+// Makes a leaf token from a range of characters. Spaces at the
+// start and end of the range are not included in the token's span.
+//
+// Arguments
+//    string
+//    start and end of range, as (byte index, char index)
+//    offset to add to the span
+// Return
+//    leaf token
+fn leaf_token(s: &str, start: (usize, usize), end: (usize, usize),
+              offset: usize) -> Token {
+    let text = &s[start.0..end.0];
+    let leading = text.chars().take_while(|c| c.is_whitespace()).count();
+    let symbol = text.trim();
+    let first = start.1 + leading + offset;
+    return make_leaf_token_at(symbol, Span::new(first, first + symbol.chars().count()));
+} // leaf_token()
+
 
 // Ensures that the character being checked matches the match
 // character, and is not escaped by a backslash. (Eg. \, \[ )
@@ -240,36 +299,41 @@ fn no_esc(check: char, match_char: char, previous: char) -> bool {
 /// * `Token`
 ///
 fn group_tokens(tokens: &Vec<Token>, mut index: usize) -> Token {
+    return group_tokens_from(tokens, &mut index);
+} // group_tokens
+
+// Collects tokens into a group, up to the next unmatched right parenthesis.
+//
+// Arguments
+//    tokens
+//    index of first token; on return, index after the right parenthesis
+// Return
+//    Group token
+fn group_tokens_from(tokens: &[Token], index: &mut usize) -> Token {
 
     let mut new_tokens: Vec<Token> = vec![];
-    let size = tokens.len();
 
-    while index < size {
+    while *index < tokens.len() {
 
-        let token = tokens[index].clone();
+        let token = tokens[*index].clone();
         let the_type = token.get_type();
+        *index += 1;
 
         if the_type == TokenType::LParen {
-            index += 1;
-            // Make a GROUP token.
-            let t = group_tokens(tokens, index);
-            // Skip past tokens already processed.
-            // +1 for right parenthesis
-            index += t.number_of_children() + 1;
-            new_tokens.push(t);
+            // Make a GROUP token. This skips past the right parenthesis.
+            new_tokens.push(group_tokens_from(tokens, index));
         } else if the_type == TokenType::RParen {
             // Add all remaining tokens to the list.
             return make_branch_token(TokenType::Group, new_tokens);
         } else {
             new_tokens.push(token);
         }
-        index += 1;
 
-    } // for
+    } // while
 
     return make_branch_token(TokenType::Group, new_tokens)
 
-} // group_tokens
+} // group_tokens_from
 
 
 /// group_and_tokens()
@@ -279,19 +343,17 @@ fn group_tokens(tokens: &Vec<Token>, mut index: usize) -> Token {
 /// # Arguments
 /// * `token`
 /// # Return
-/// * `token`
-/// # Panics
-/// * If given token is not a branch token.
-fn group_and_tokens(token: Token) -> Token {
+/// * `token` or error, if given token is not a branch token.
+fn group_and_tokens(token: Token) -> Result<Token, SuironError> {
 
     match token {
-        Token::Leaf{ token_type: tt, token_str: _ } => {
+        Token::Leaf{ token_type: tt, .. } => {
             let err = format!(
                 "group_and_tokens() - Requires branch token: {tt}"
             );
-            panic!("{}", err);
+            return Err(SuironError::ParseError(err));
         },
-        Token::Branch{ token_type, children } => {
+        Token::Branch{ token_type, children, .. } => {
 
             let mut new_children: Vec<Token> = vec![];
             let mut and_list: Vec<Token> = vec![];
@@ -318,8 +380,8 @@ fn group_and_tokens(token: Token) -> Token {
                     and_list = vec![];
                 }
                 else if child_type == TokenType::Group {
-                    let mut t = group_and_tokens(child);
-                    t = group_or_tokens(t);
+                    let mut t = group_and_tokens(child)?;
+                    t = group_or_tokens(t)?;
                     and_list.push(t);
                 }
             } // for
@@ -333,7 +395,7 @@ fn group_and_tokens(token: Token) -> Token {
                 );
             }
 
-            make_branch_token(token_type, new_children)
+            Ok(make_branch_token(token_type, new_children))
 
         } // Token::Branch
     } // match
@@ -348,19 +410,17 @@ fn group_and_tokens(token: Token) -> Token {
 /// # Arguments
 /// * `token`
 /// # Return
-/// * `token`
-/// # Panics
-/// * If given token is not a branch token.
-fn group_or_tokens(token: Token) -> Token {
+/// * `token` or error, if given token is not a branch token.
+fn group_or_tokens(token: Token) -> Result<Token, SuironError> {
 
     match token {
-        Token::Leaf{ token_type: tt, token_str: _ } => {
+        Token::Leaf{ token_type: tt, .. } => {
             let err = format!(
                 "group_or_tokens() - Requires branch token: {tt}"
             );
-            panic!("{}", err);
+            return Err(SuironError::ParseError(err));
         },
-        Token::Branch{ token_type, children } => {
+        Token::Branch{ token_type, children, .. } => {
 
             let mut new_children: Vec<Token> = vec![];
             let mut or_list: Vec<Token> = vec![];
@@ -387,7 +447,7 @@ fn group_or_tokens(token: Token) -> Token {
                 );
             }
 
-            make_branch_token(token_type, new_children)
+            Ok(make_branch_token(token_type, new_children))
 
         } // Branch
     } // match
@@ -402,22 +462,19 @@ fn group_or_tokens(token: Token) -> Token {
 /// * `token` - base of token tree
 /// # Return
 /// * `Result` - Ok(Goal) or Err(message)
-/// # Panics
-/// * If leaf token is not a Subgoal.
-/// * If branch token Group does not have 1 child.
-fn token_tree_to_goal(token: Token) -> Result<Goal, String> {
+fn token_tree_to_goal(token: Token) -> Result<Goal, SuironError> {
 
     match token {
 
-        Token::Leaf{ token_type, token_str } => {
+        Token::Leaf{ token_type, token_str, .. } => {
             if token_type == TokenType::Subgoal {
                 return parse_subgoal(&token_str);
             };
             let msg = tttg_error("Invalid. Leaf token must be Subgoal.", "");
-            panic!("{}", msg);
+            return Err(msg.into());
         }, // Leaf
 
-        Token::Branch{ token_type, children: _ } => {
+        Token::Branch{ token_type, .. } => {
 
             if token_type == TokenType::And {
 
@@ -430,7 +487,7 @@ fn token_tree_to_goal(token: Token) -> Result<Goal, String> {
                         let s = child.get_token_str();
                         match parse_subgoal(&s) {
                             Ok(g) => { operands.push(g); },
-                            Err(err) => { return Err(err); },
+                            Err(err) => { return Err(err.into()); },
                         }
                     }
                     else if child_type == TokenType::Group {
@@ -456,7 +513,7 @@ fn token_tree_to_goal(token: Token) -> Result<Goal, String> {
                         let s = child.get_token_str();
                         match parse_subgoal(&s) {
                             Ok(g) => { operands.push(g); },
-                            Err(err) => { return Err(err); },
+                            Err(err) => { return Err(err.into()); },
                         }
                     }
                     else if child_type == TokenType::Group {
@@ -474,7 +531,7 @@ fn token_tree_to_goal(token: Token) -> Result<Goal, String> {
 
                 if token.number_of_children() != 1 {
                     let msg = tttg_error("Group should have 1 child.", "");
-                    panic!("{}", msg);
+                    return Err(msg.into());
                 }
 
                 let children = token.get_children();
@@ -484,7 +541,7 @@ fn token_tree_to_goal(token: Token) -> Result<Goal, String> {
 
             let tt = token_type.to_string();
             let msg = tttg_error("Invalid token type:", &tt);
-            return Err(msg);
+            return Err(msg.into());
 
         }, // Branch
 
@@ -575,9 +632,9 @@ mod test {
 
         let token = group_tokens(&test_tokens, 0);
         assert_eq!(token.to_string(),
-        "GROUP > SUBGOAL(a(1)) COMMA SUBGOAL(b(2)) COMMA GROUP SUBGOAL(d(5))");
+        "GROUP > SUBGOAL(a(1)) COMMA SUBGOAL(b(2)) COMMA GROUP COMMA SUBGOAL(d(5))");
 
-        let token = group_and_tokens(token);
+        let token = group_and_tokens(token).unwrap();
         assert_eq!(token.to_string(), "GROUP > AND");
 
         let child = &token.get_children()[0];
@@ -621,10 +678,10 @@ mod test {
              "GROUP > SUBGOAL(a(1)) COMMA SUBGOAL(b(2)) \
              SEMICOLON SUBGOAL(c(3)) COMMA SUBGOAL(d(4))");
 
-        let token = group_and_tokens(token);
+        let token = group_and_tokens(token).unwrap();
         assert_eq!(token.to_string(), "GROUP > AND SEMICOLON AND");
 
-        let token = group_or_tokens(token);
+        let token = group_or_tokens(token).unwrap();
         assert_eq!(token.to_string(), "GROUP > OR");
 
     } // test_group_tokens2()
@@ -654,8 +711,8 @@ mod test {
 
         let test_tokens = make_test_tokens3();
         let token = group_tokens(&test_tokens, 0);
-        let token = group_and_tokens(token);
-        let token = group_or_tokens(token);
+        let token = group_and_tokens(token).unwrap();
+        let token = group_or_tokens(token).unwrap();
         assert_eq!(token.to_string(), "GROUP > OR");
 
         let child = &token.get_children()[0];
@@ -671,11 +728,11 @@ mod test {
     } // test_group_tokens3()
 
     #[test]
-    #[should_panic]
-    fn test_group_tokens_panic() {
+    fn test_group_tokens_error() {
         let com1 = make_leaf_token(",");
-        group_and_tokens(com1);
-    } // test_group_tokens_panic()
+        assert_eq!("group_and_tokens() - Requires branch token: Comma",
+                   group_and_tokens(com1).unwrap_err());
+    } // test_group_tokens_error()
 
     #[test]
     fn test_tokenize() {
@@ -710,6 +767,54 @@ mod test {
                 assert_eq!(err, "tokenize() - Unmatched bracket: $X = 1, 2, 3]");
             },
         }
+
+        // Parentheses in arithmetic expressions do not group subgoals.
+        let s = "a($X), ($X - 1) * 2 < 10; $Y is ($X + 1) * 2";
+        match tokenize(s) {
+            Ok(tokens) => {
+                let s = tokens_to_string(&tokens);
+                assert_eq!(s,
+                  "a($X) COMMA ($X - 1) * 2 < 10 SEMICOLON $Y is ($X + 1) * 2");
+            },
+            Err(err) => {
+                panic!("Should create tokens: {}", err);
+            },
+        }
     } // test_tokenize()
 
+    #[test]
+    fn test_token_spans() {
+
+        let s = "  a(1), (b(2); c(3)),  d(4) ";
+        let tokens = tokenize(s).unwrap();
+        let spans: Vec<(String, usize, usize)> = tokens.iter().map(|t| {
+            let span = t.get_span();
+            (chars_to_string!(str_to_chars!(s)[span.start..span.end]), span.start, span.end)
+        }).collect();
+        let expected = [("a(1)", 2, 6), (",", 6, 7), ("(", 8, 9), ("b(2)", 9, 13),
+                        (";", 13, 14), ("c(3)", 15, 19), (")", 19, 20), (",", 20, 21),
+                        ("d(4)", 23, 27)];
+        for (span, exp) in spans.iter().zip(expected.iter()) {
+            assert_eq!((exp.0.to_string(), exp.1, exp.2), *span);
+        }
+        assert_eq!(expected.len(), spans.len());
+
+        // Spans are carried through to the token tree.
+        let tree = parse_token_tree(s).unwrap();
+        assert_eq!(Span::new(2, 27), tree.get_span());
+        let and = &tree.get_children()[0];
+        let group = &and.get_children()[1];
+        assert_eq!(Span::new(9, 19), group.get_span());
+        let or = &group.get_children()[0];
+        assert_eq!(Span::new(15, 19), or.get_children()[1].get_span());
+
+
+        // Nested groups.
+        let tree = parse_token_tree("((a; b)), c").unwrap();
+        let and = &tree.get_children()[0];
+        assert_eq!("AND > GROUP SUBGOAL(c)", and.to_string());
+        assert_eq!(Span::new(10, 11), and.get_children()[1].get_span());
+
+    } // test_token_spans()
+
 } // test
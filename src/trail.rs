@@ -0,0 +1,967 @@
+//! Trail-based binding, an alternative to persistent substitution sets.
+//!
+//! By default, the inference engine records the bindings of logic
+//! variables in [substitution sets](../substitution_set/index.html).
+//! Each new binding makes a new set, and the old sets are kept, so
+//! that the solver can backtrack to them.
+//!
+//! The classical alternative, used by the Warren Abstract Machine, is
+//! a single, mutable store of bindings, and a trail, which records the
+//! variables which have been bound. When the solver backtracks to a
+//! choice point, it undoes the bindings which were made after the choice
+//! point, by unwinding the trail.
+//!
+//! The trail strategy is selected by
+//! [set_binding_strategy()](fn.set_binding_strategy.html). A query which
+//! is set up by [make_base_node()](../goal/fn.make_base_node.html) is
+//! then solved by a [TrailSolver](struct.TrailSolver.html). The solutions
+//! are the same, so that the two strategies can be compared. (See the
+//! [benchmark](../benchmark/index.html) module.)
+//!
+//! Built-in predicates, other than unification and cut, are run on a
+//! substitution set which holds the bindings of their arguments.
+//
+// Cleve Lendon 2023
+
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::time::Instant;
+
+use super::built_in_predicates::*;
+use super::cancellation::CancellationToken;
+use super::goal::{Goal, max_var_id};
+use super::knowledge_base::*;
+use super::logic_var::*;
+use super::operator::Operator;
+use super::rule::Rule;
+use super::solution_node::*;
+use super::substitution_set::*;
+use super::query_planner::{query_planner, plan_conjunction};
+use super::tabling::tabled_answers;
+use super::unifiable::*;
+
+use crate::rc_cell;
+
+/// Defines how the bindings of logic variables are recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingStrategy {
+    /// Persistent substitution sets. (Default.)
+    Persistent,
+    /// A mutable binding store, with a trail to undo bindings.
+    Trail,
+}
+
+thread_local! {
+    static BINDING_STRATEGY: Cell<BindingStrategy>
+                             = const { Cell::new(BindingStrategy::Persistent) };
+}
+
+/// Sets the binding strategy for new queries.
+///
+/// The setting is local to the thread. It applies to base nodes made
+/// by [make_base_node()](../goal/fn.make_base_node.html) after it is set.
+///
+/// # Arguments
+/// * [BindingStrategy](enum.BindingStrategy.html)
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// set_binding_strategy(BindingStrategy::Trail);
+/// let query = parse_query("grandfather($X, $Y)").unwrap();
+/// let sn = make_base_node(Rc::new(query), &kb);
+/// set_binding_strategy(BindingStrategy::Persistent);
+/// println!("{}", solve(sn));  // Prints: $X = Alfred, $Y = Aethelstan
+/// ```
+pub fn set_binding_strategy(strategy: BindingStrategy) {
+    BINDING_STRATEGY.with(|bs| bs.set(strategy));
+} // set_binding_strategy()
+
+/// Gets the binding strategy.
+///
+/// See [set_binding_strategy()](fn.set_binding_strategy.html).
+///
+/// # Return
+/// * [BindingStrategy](enum.BindingStrategy.html)
+pub fn binding_strategy() -> BindingStrategy {
+    return BINDING_STRATEGY.with(|bs| bs.get());
+} // binding_strategy()
+
+/// A mutable store of bindings for logic variables, with a trail.
+///
+/// When unification fails, it may leave some bindings in the store.
+/// The caller should undo them, with [undo_to()](#method.undo_to).
+///
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let x = logic_var!(1, "$X");
+/// let y = logic_var!(2, "$Y");
+/// let mut store = BindingStore::new();
+///
+/// let mark = store.mark();
+/// assert!(store.unify(&x, &scomplex!(atom!("f"), y.clone())));
+/// assert!(store.unify(&y, &atom!("a")));
+/// println!("{}", store.resolve(&x));  // Prints: f(a)
+///
+/// store.undo_to(mark);
+/// println!("{}", store.resolve(&x));  // Prints: $X_1
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BindingStore {
+    bindings: Vec<Option<Rc<Unifiable>>>,
+    // IDs of the bound variables, in order of binding.
+    trail: Vec<usize>,
+}
+
+impl BindingStore {
+
+    /// Creates an empty binding store.
+    pub fn new() -> Self {
+        BindingStore{ bindings: vec![], trail: vec![] }
+    }
+
+    /// Gets the current position of the trail.
+    pub fn mark(&self) -> usize { self.trail.len() }
+
+    /// Undoes the bindings which were made after a mark.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * mark, from [mark()](#method.mark)
+    pub fn undo_to(&mut self, mark: usize) {
+        while self.trail.len() > mark {
+            if let Some(id) = self.trail.pop() { self.bindings[id] = None; }
+        }
+    } // undo_to()
+
+    /// Binds a variable, and records it on the trail.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * variable ID
+    /// * term
+    pub fn bind(&mut self, id: usize, term: Rc<Unifiable>) {
+        if id >= self.bindings.len() { self.bindings.resize(id + 1, None); }
+        self.bindings[id] = Some(term);
+        self.trail.push(id);
+    } // bind()
+
+    /// Gets the term which a variable is bound to.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * variable ID
+    /// # Return
+    /// * term, or None if the variable is not bound
+    pub fn lookup(&self, id: usize) -> Option<&Rc<Unifiable>> {
+        self.bindings.get(id)?.as_ref()
+    }
+
+    // Follows the bindings of a variable.
+    //
+    // Arguments
+    //    term
+    // Return
+    //    the last term of the chain of bindings, or None if
+    //    the term is not a bound variable
+    fn dereference(&self, term: &Unifiable) -> Option<Rc<Unifiable>> {
+        let mut result: Option<Rc<Unifiable>> = None;
+        let mut id = match term {
+            Unifiable::LogicVar{id, name: _} => { *id },
+            _ => { return None; },
+        };
+        while let Some(bound) = self.lookup(id) {
+            result = Some(Rc::clone(bound));
+            match &**bound {
+                Unifiable::LogicVar{id: next, name: _} => { id = *next; },
+                _ => { break; },
+            }
+        }
+        return result;
+    } // dereference()
+
+    /// Unifies two terms, recording new bindings on the trail.
+    ///
+    /// The result is the same as that of
+    /// [Unifiable::unify()](../unifiable/enum.Unifiable.html#method.unify),
+    /// for the same left and right terms.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * left term
+    /// * right term
+    /// # Return
+    /// * true if the terms unify
+    pub fn unify(&mut self, left: &Unifiable, right: &Unifiable) -> bool {
+
+        if left == right { return true; }
+
+        match left {
+            // $_ unifies with everything.
+            Unifiable::Anonymous => { true },
+            Unifiable::LogicVar{id, name: _} => {
+                // The unify method of a function evaluates the function.
+                if let Unifiable::SFunction{name: _, terms: _} = right {
+                    return self.unify_with_ss(right, left);
+                }
+                if let Some(bound) = self.lookup(*id) {
+                    let bound = Rc::clone(bound);
+                    return self.unify(&bound, right);
+                }
+                return self.bind_var(*id, right);
+            },
+            Unifiable::SFunction{name: _, terms: _} => {
+                self.unify_with_ss(left, right)
+            },
+            Unifiable::Atom(_) | Unifiable::SString(_) | Unifiable::SFloat(_) |
+            Unifiable::SInteger(_) | Unifiable::SRational(_, _) |
+            Unifiable::SComplex(_) | Unifiable::SLinkedList{term: _, next: _,
+                                                             count: _, tail_var: _} => {
+                match (left, right) {
+                    (_, Unifiable::LogicVar{id: _, name: _}) => { self.unify(right, left) },
+                    (_, Unifiable::Anonymous) => { true },
+                    (Unifiable::SComplex(left_terms), Unifiable::SComplex(right_terms)) => {
+                        if left_terms.len() != right_terms.len() { return false; }
+                        for (l, r) in left_terms.iter().zip(right_terms.iter()) {
+                            if *l == Unifiable::Anonymous || *r == Unifiable::Anonymous {
+                                continue;
+                            }
+                            if !self.unify(l, r) { return false; }
+                        }
+                        true
+                    },
+                    (Unifiable::SLinkedList{term: _, next: _, count: _, tail_var: _},
+                     Unifiable::SLinkedList{term: _, next: _, count: _, tail_var: _}) => {
+                        self.unify_lists(left, right)
+                    },
+                    // Constants are equal only if left == right.
+                    _ => { false },
+                }
+            },
+            #[cfg(feature = "bigint")]
+            Unifiable::SBigInt(_) => {
+                match right {
+                    Unifiable::LogicVar{id: _, name: _} => { self.unify(right, left) },
+                    Unifiable::Anonymous => { true },
+                    _ => { false },
+                }
+            },
+            _ => { false },
+        }
+
+    } // unify()
+
+    // Binds an unbound variable to a term, if the occurs check allows it.
+    //
+    // Arguments
+    //    variable ID
+    //    term
+    // Return
+    //    true if bound
+    fn bind_var(&mut self, id: usize, term: &Unifiable) -> bool {
+        if occurs_check() {
+            let target = self.dereference(term);
+            match target.as_deref().unwrap_or(term) {
+                // The other variable is already bound to this one.
+                Unifiable::LogicVar{id: target_id, name: _} => {
+                    if *target_id == id { return true; }
+                },
+                target => { if self.occurs_in(id, target) { return false; } },
+            }
+        }
+        self.bind(id, Rc::new(term.clone()));
+        return true;
+    } // bind_var()
+
+    // Determines whether a variable occurs in a term.
+    fn occurs_in(&self, id: usize, term: &Unifiable) -> bool {
+        let bound = self.dereference(term);
+        match bound.as_deref().unwrap_or(term) {
+            Unifiable::LogicVar{id: other_id, name: _} => { *other_id == id },
+            Unifiable::SComplex(terms) |
+            Unifiable::SFunction{name: _, terms} => {
+                terms.iter().any(|t| self.occurs_in(id, t))
+            },
+            Unifiable::SLinkedList{term, next, count: _, tail_var: _} => {
+                self.occurs_in(id, term) || self.occurs_in(id, next)
+            },
+            _ => { false },
+        }
+    } // occurs_in()
+
+    // Unifies two lists. This follows the algorithm of Unifiable::unify().
+    fn unify_lists(&mut self, left: &Unifiable, right: &Unifiable) -> bool {
+
+        let mut this_list = left;
+        let mut other_list = right;
+
+        while *this_list != Unifiable::Nil && *other_list != Unifiable::Nil {
+
+            let (this_term, this_next, this_tail_var) = match this_list {
+                Unifiable::SLinkedList{term, next, count: _, tail_var} => {
+                    (term, next, *tail_var)
+                },
+                _ => { return false; },
+            };
+            let (other_term, other_next, other_tail_var) = match other_list {
+                Unifiable::SLinkedList{term, next, count: _, tail_var} => {
+                    (term, next, *tail_var)
+                },
+                _ => { return false; },
+            };
+
+            if this_tail_var && other_tail_var {
+                if **other_term == Unifiable::Anonymous ||
+                   **this_term == Unifiable::Anonymous { return true; }
+                return self.unify(this_term, other_term);
+            }
+            else if this_tail_var {
+                return self.unify(this_term, other_list);
+            }
+            else if other_tail_var {
+                return self.unify(other_term, this_list);
+            }
+            if **this_term == Unifiable::Nil && **other_term == Unifiable::Nil {
+                return true;
+            }
+            if !self.unify(this_term, other_term) { return false; }
+
+            this_list = this_next;
+            other_list = other_next;
+        }
+        return false;
+
+    } // unify_lists()
+
+    // Unifies two terms with Unifiable::unify(), and records the new
+    // bindings. This is used for functions, which are evaluated when
+    // they are unified.
+    fn unify_with_ss(&mut self, left: &Unifiable, right: &Unifiable) -> bool {
+        let ss = Rc::new(self.substitution_set_for(&[left, right]));
+        match left.unify(right, &ss) {
+            Some(new_ss) => { self.record(&new_ss, &[left, right]); true },
+            None => { false },
+        }
+    } // unify_with_ss()
+
+    /// Replaces the bound variables of a term with their bindings.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * term
+    /// # Return
+    /// * new term
+    pub fn resolve(&self, term: &Unifiable) -> Unifiable {
+        let ss = self.substitution_set_for(&[term]);
+        return term.replace_variables(&ss);
+    } // resolve()
+
+    /// Makes a substitution set which holds all the bindings of the store.
+    pub fn to_substitution_set(&self) -> SubstitutionSet<'static> {
+        let mut ss = SubstitutionSet::new();
+        for entry in &self.bindings { ss.push(entry.clone()); }
+        return ss;
+    } // to_substitution_set()
+
+    // Makes a substitution set which holds the bindings of the variables
+    // of some terms, and of the variables in those bindings.
+    //
+    // Arguments
+    //    terms
+    // Return
+    //    substitution set
+    fn substitution_set_for(&self, terms: &[&Unifiable]) -> SubstitutionSet<'static> {
+        let mut ss = SubstitutionSet::new();
+        let mut stack: Vec<Rc<Unifiable>> = vec![];
+        let add_vars = |term: &Unifiable, ss: &mut SubstitutionSet,
+                            stack: &mut Vec<Rc<Unifiable>>| {
+            for_each_var(term, &mut |id| {
+                if let Some(bound) = self.lookup(id) {
+                    if !matches!(ss.get(id), Some(Some(_))) {
+                        *ss = ss.bind(id, Rc::clone(bound));
+                        stack.push(Rc::clone(bound));
+                    }
+                }
+            });
+        };
+        for term in terms { add_vars(term, &mut ss, &mut stack); }
+        while let Some(term) = stack.pop() { add_vars(&term, &mut ss, &mut stack); }
+        return ss;
+    } // substitution_set_for()
+
+    // Records the bindings of a substitution set, for the variables of
+    // some terms, and of the variables in their bindings.
+    //
+    // Arguments
+    //    substitution set
+    //    terms
+    fn record(&mut self, ss: &SubstitutionSet, terms: &[&Unifiable]) {
+        let mut visited: HashSet<usize> = HashSet::new();
+        let mut stack: Vec<Rc<Unifiable>> = vec![];
+        let mut ids: Vec<usize> = vec![];
+        for term in terms { for_each_var(term, &mut |id| ids.push(id)); }
+        loop {
+            while let Some(id) = ids.pop() {
+                if !visited.insert(id) { continue; }
+                if let Some(Some(bound)) = ss.get(id) {
+                    if self.lookup(id).is_none() { self.bind(id, Rc::clone(bound)); }
+                    stack.push(Rc::clone(bound));
+                }
+            }
+            match stack.pop() {
+                Some(term) => { for_each_var(&term, &mut |id| ids.push(id)); },
+                None => { break; },
+            }
+        }
+    } // record()
+
+} // impl BindingStore
+
+// Calls a function for each logic variable in a term.
+fn for_each_var(term: &Unifiable, f: &mut impl FnMut(usize)) {
+    match term {
+        Unifiable::LogicVar{id, name: _} => { f(*id); },
+        Unifiable::SComplex(terms) |
+        Unifiable::SFunction{name: _, terms} => {
+            for t in terms { for_each_var(t, f); }
+        },
+        Unifiable::SLinkedList{term, next, count: _, tail_var: _} => {
+            for_each_var(term, f);
+            for_each_var(next, f);
+        },
+        _ => {},
+    }
+} // for_each_var()
+
+// A continuation: the goals which remain to be solved.
+type Continuation = Option<Rc<Frame>>;
+
+// One step of a continuation.
+#[derive(Debug)]
+struct Frame {
+    step: Step,
+    next: Continuation,
+    // Number of steps in the continuation, including this one.
+    depth: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    // A goal, and the height of the choice point stack for a cut.
+    Goal(Rc<Goal>, usize),
+    // Removes choice points above a height.
+    Cut(usize),
+    // Removes choice points above a height, and fails. (For not().)
+    CutFail(usize),
+    // Prints the time since an instant. (For time().)
+    Elapsed(Instant),
+}
+
+// The clauses of a predicate, which remain to be tried.
+#[derive(Debug)]
+struct Clauses {
+    goal: Unifiable,
+    predicate_name: String,
+    count: usize,
+    candidates: Option<Vec<usize>>,
+    external_rules: Option<Vec<Rule>>,
+    index: usize,
+    continuation: Continuation,
+}
+
+#[derive(Debug)]
+enum Alternative {
+    // Continues with other goals.
+    Resume(Continuation),
+    // Tries the next clause.
+    Clauses(Clauses),
+}
+
+#[derive(Debug)]
+struct ChoicePoint {
+    mark: usize,
+    var_id: usize,
+    alternative: Alternative,
+}
+
+/// Solves a query with a mutable binding store and a trail.
+///
+/// The solver keeps a stack of choice points. When a goal fails, it
+/// returns to the most recent choice point, undoes the bindings which
+/// were made after it, and tries the next alternative. The solver does
+/// not recurse, so deeply recursive rules do not overflow the stack.
+///
+/// Usually, a TrailSolver is made by
+/// [make_base_node()](../goal/fn.make_base_node.html). See
+/// [set_binding_strategy()](fn.set_binding_strategy.html).
+///
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let kb = test_kb();
+/// let query = parse_query("loves($Who, $Whom)").unwrap();
+/// let mut solver = TrailSolver::new(Rc::new(query.clone()), &kb);
+/// while let Some(ss) = solver.next_solution() {
+///     println!("{}", query.replace_variables(&ss));
+/// }
+/// // Prints:
+/// // loves(Leonard, Penny)
+/// // loves(Penny, Leonard)
+/// ```
+#[derive(Debug)]
+pub struct TrailSolver<'a> {
+    kb: &'a KnowledgeBase,
+    store: BindingStore,
+    continuation: Continuation,
+    choice_points: Vec<ChoicePoint>,
+    started: bool,
+    finished: bool,
+    cancellation: Option<CancellationToken>,
+    var_ids: VarIds,
+}
+
+impl<'a> TrailSolver<'a> {
+
+    /// Creates a solver for a query.
+    ///
+    /// # Arguments
+    /// * query (goal)
+    /// * knowledge base
+    /// # Return
+    /// * TrailSolver
+    pub fn new(query: Rc<Goal>, kb: &'a KnowledgeBase) -> Self {
+        let var_ids = VarIds::starting_at(max_var_id(&query));
+        let continuation = push_step(Step::Goal(query, 0), None);
+        TrailSolver{ kb, store: BindingStore::new(), continuation,
+                     choice_points: vec![], started: false, finished: false,
+                     cancellation: None, var_ids }
+    } // new()
+
+    /// Sets the token which cancels the search.
+    /// See [cancellation_token()](../cancellation/fn.cancellation_token.html).
+    ///
+    /// # Arguments
+    /// * `self`
+    /// * [CancellationToken](../cancellation/struct.CancellationToken.html)
+    pub fn set_cancellation(&mut self, token: CancellationToken) {
+        self.cancellation = Some(token);
+    }
+
+    /// Finds the next solution.
+    ///
+    /// # Arguments
+    /// * `self`
+    /// # Return
+    /// * substitution set, or None if there are no more solutions
+    pub fn next_solution(&mut self) -> Option<Rc<SubstitutionSet<'a>>> {
+        if self.finished { return None; }
+        if self.started && !self.backtrack() {
+            self.finished = true;
+            return None;
+        }
+        self.started = true;
+        if self.run() {
+            return Some(Rc::new(self.store.to_substitution_set()));
+        }
+        self.finished = true;
+        return None;
+    } // next_solution()
+
+    // Solves the goals of the continuation, backtracking as necessary.
+    //
+    // Return
+    //    true if a solution was found
+    fn run(&mut self) -> bool {
+        let max = max_depth().unwrap_or(usize::MAX);
+        set_depth_limit_exceeded(false);
+        loop {
+            if let Some(token) = &self.cancellation {
+                if token.is_stopped() { return false; }
+            }
+            let frame = match self.continuation.take() {
+                Some(frame) => { frame },
+                None => { return true; },
+            };
+            // The goals which remain to be solved are nested too deeply.
+            if frame.depth > max {
+                set_depth_limit_exceeded(true);
+                return false;
+            }
+            let (step, next) = match Rc::try_unwrap(frame) {
+                Ok(frame) => { (frame.step, frame.next) },
+                Err(frame) => { (frame.step.clone(), frame.next.clone()) },
+            };
+            self.continuation = next;
+            let ok = match step {
+                Step::Goal(goal, cut_height) => { self.call(goal, cut_height) },
+                Step::Cut(height) => { self.choice_points.truncate(height); true },
+                Step::CutFail(height) => { self.choice_points.truncate(height); false },
+                Step::Elapsed(instant) => { print_elapsed(instant); true },
+            };
+            if !ok && !self.backtrack() { return false; }
+        }
+    } // run()
+
+    // Returns to the most recent choice point, and tries its alternative.
+    //
+    // Return
+    //    true if an alternative succeeded, false if there are none
+    fn backtrack(&mut self) -> bool {
+        while let Some(choice_point) = self.choice_points.pop() {
+            self.store.undo_to(choice_point.mark);
+            self.var_ids.set(choice_point.var_id);
+            match choice_point.alternative {
+                Alternative::Resume(continuation) => {
+                    self.continuation = continuation;
+                    return true;
+                },
+                Alternative::Clauses(clauses) => {
+                    if self.try_clauses(clauses) { return true; }
+                },
+            }
+        }
+        return false;
+    } // backtrack()
+
+    // Pushes a choice point.
+    fn push_choice_point(&mut self, alternative: Alternative) {
+        self.choice_points.push(ChoicePoint{ mark: self.store.mark(),
+                                             var_id: self.var_ids.get(), alternative });
+    }
+
+    // Starts to solve a goal. The goals which follow it are in
+    // self.continuation.
+    //
+    // Arguments
+    //    goal
+    //    height of the choice point stack for a cut
+    // Return
+    //    false if the goal failed
+    fn call(&mut self, goal: Rc<Goal>, cut_height: usize) -> bool {
+
+        let goal = match Rc::try_unwrap(goal) {
+            Ok(goal) => { goal },
+            Err(goal) => { (*goal).clone() },
+        };
+        let continuation = self.continuation.take();
+
+        match goal {
+            Goal::Nil => {
+                self.continuation = continuation;
+                return true;
+            },
+            Goal::OperatorGoal(Operator::And(goals)) => {
+                let goals = if query_planner() {
+                    plan_conjunction(goals, self.kb, |t| self.store.resolve(t))
+                } else { goals };
+                let mut continuation = continuation;
+                for goal in goals.into_iter().rev() {
+                    continuation = push_step(Step::Goal(Rc::new(goal), cut_height),
+                                             continuation);
+                }
+                self.continuation = continuation;
+                return true;
+            },
+            Goal::OperatorGoal(Operator::Or(mut goals)) => {
+                if goals.is_empty() { return false; }
+                let first = goals.remove(0);
+                if !goals.is_empty() {
+                    let rest = Goal::OperatorGoal(Operator::Or(goals));
+                    let alternative = push_step(Step::Goal(Rc::new(rest), cut_height),
+                                                continuation.clone());
+                    self.push_choice_point(Alternative::Resume(alternative));
+                }
+                self.continuation = push_step(Step::Goal(Rc::new(first), cut_height),
+                                              continuation);
+                return true;
+            },
+            Goal::OperatorGoal(Operator::Not(mut goals)) => {
+                if goals.is_empty() { return false; }
+                // not(G) is: G, !, fail ; true
+                let height = self.choice_points.len();
+                self.push_choice_point(Alternative::Resume(continuation));
+                let fail = push_step(Step::CutFail(height), None);
+                self.continuation = push_step(Step::Goal(Rc::new(goals.remove(0)),
+                                                         height + 1), fail);
+                return true;
+            },
+            Goal::OperatorGoal(Operator::Time(mut goals)) => {
+                if goals.is_empty() { return false; }
+                let height = self.choice_points.len();
+                let continuation = push_step(Step::Elapsed(Instant::now()), continuation);
+                let continuation = push_step(Step::Cut(height), continuation);
+                self.continuation = push_step(Step::Goal(Rc::new(goals.remove(0)),
+                                                         cut_height), continuation);
+                return true;
+            },
+            Goal::BuiltInGoal(bip) => {
+                self.continuation = continuation;
+                return self.call_built_in(bip, cut_height);
+            },
+            Goal::ComplexGoal(term) => {
+                let predicate_name = match term.key() {
+                    Ok(key) => { key },
+                    Err(_) => { return false; },
+                };
+                let clauses = self.make_clauses(term, predicate_name, continuation);
+                return self.try_clauses(clauses);
+            },
+        }
+
+    } // call()
+
+    // Solves a built-in predicate.
+    //
+    // Arguments
+    //    built-in predicate
+    //    height of the choice point stack for a cut
+    // Return
+    //    false if the predicate failed
+    fn call_built_in(&mut self, bip: BuiltInPredicate, cut_height: usize) -> bool {
+
+        match (bip.functor.as_str(), &bip.terms) {
+            ("!", _) => {
+                self.choice_points.truncate(cut_height);
+                return true;
+            },
+            ("fail", _) => { return false; },
+            ("unify", Some(terms)) if terms.len() == 2 => {
+                return self.store.unify(&terms[0], &terms[1]);
+            },
+            ("unify_with_occurs_check", Some(terms)) if terms.len() == 2 => {
+                let previous = occurs_check();
+                set_occurs_check(true);
+                let result = self.store.unify(&terms[0], &terms[1]);
+                set_occurs_check(previous);
+                return result;
+            },
+            _ => {},
+        }
+
+        let terms: Vec<Unifiable> = bip.terms.clone().unwrap_or_default();
+        let term_refs: Vec<&Unifiable> = terms.iter().collect();
+        let mut node = SolutionNode::with_var_ids(Rc::new(Goal::Nil), self.kb,
+                                                  self.var_ids.clone());
+        node.ss = Rc::new(self.store.substitution_set_for(&term_refs));
+        match next_solution_bip(rc_cell!(node), bip) {
+            Some(ss) => {
+                self.store.record(&ss, &term_refs);
+                return true;
+            },
+            None => { return false; },
+        }
+
+    } // call_built_in()
+
+    // Gets the facts and rules which may match a goal.
+    fn make_clauses(&self, goal: Unifiable, predicate_name: String,
+                    continuation: Continuation) -> Clauses {
+
+        let mut count = count_rules(self.kb, &predicate_name);
+        let mut candidates = None;
+        let mut external_rules = None;
+
+        if self.kb.is_tabled(&predicate_name) {
+            let answers = tabled_answers(self.kb, &self.store.resolve(&goal));
+            count = answers.len();
+            external_rules = Some(answers.to_vec());
+        }
+        else if count == 0 {
+            if let Some(external) = self.kb.external(&predicate_name) {
+                if let Ok(rules) = external.fetch(&self.store.resolve(&goal)) {
+                    count = rules.len();
+                    external_rules = Some(rules);
+                }
+            }
+        }
+        else if self.kb.indexing() {
+            let ss = self.store.substitution_set_for(&[&goal]);
+            if let Some(indices) = self.kb.candidates(&predicate_name, &goal, &ss) {
+                count = indices.len();
+                candidates = Some(indices);
+            }
+        }
+
+        return Clauses{ goal, predicate_name, count, candidates,
+                        external_rules, index: 0, continuation };
+
+    } // make_clauses()
+
+    // Tries the remaining clauses of a predicate, until one unifies
+    // with the goal. If there are more clauses, a choice point is saved.
+    //
+    // Arguments
+    //    clauses
+    // Return
+    //    true if a clause unified
+    fn try_clauses(&mut self, mut clauses: Clauses) -> bool {
+
+        let cut_height = self.choice_points.len();
+
+        while clauses.index < clauses.count {
+
+            let mark = self.store.mark();
+            let var_id = self.var_ids.get();
+
+            let index = match &clauses.candidates {
+                Some(candidates) => { candidates[clauses.index] },
+                None => { clauses.index },
+            };
+            let rule = match &clauses.external_rules {
+                Some(rules) => {
+                    rules[index].clone().recreate_variables(&mut VarMap::with_ids(&self.var_ids))
+                },
+                None => {
+                    match fetch_rule(self.kb, &clauses.predicate_name, index, &self.var_ids) {
+                        Some(rule) => { rule },
+                        None => { return false; },
+                    }
+                },
+            };
+            clauses.index += 1;
+
+            if self.store.unify(&rule.head, &clauses.goal) {
+                let continuation = match rule.body {
+                    Goal::Nil => { clauses.continuation.clone() },
+                    body => {
+                        push_step(Step::Goal(Rc::new(body), cut_height),
+                                  clauses.continuation.clone())
+                    },
+                };
+                if clauses.index < clauses.count {
+                    self.choice_points.push(ChoicePoint{ mark, var_id,
+                                            alternative: Alternative::Clauses(clauses) });
+                }
+                self.continuation = continuation;
+                return true;
+            }
+
+            self.store.undo_to(mark);
+            self.var_ids.set(var_id);
+        }
+        return false;
+
+    } // try_clauses()
+
+} // impl TrailSolver
+
+// Adds a step to the front of a continuation.
+fn push_step(step: Step, next: Continuation) -> Continuation {
+    let depth = next.as_ref().map_or(0, |frame| frame.depth) + 1;
+    Some(Rc::new(Frame{ step, next, depth }))
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    // Solves a query with both binding strategies.
+    fn solve_both(kb: &KnowledgeBase, query: &str) -> (Vec<String>, Vec<String>) {
+        let goal = Rc::new(parse_query(query).unwrap());
+        let persistent = solve_all(make_base_node(Rc::clone(&goal), kb));
+        set_binding_strategy(BindingStrategy::Trail);
+        let goal = Rc::new(parse_query(query).unwrap());
+        let trail = solve_all(make_base_node(goal, kb));
+        set_binding_strategy(BindingStrategy::Persistent);
+        return (persistent, trail);
+    }
+
+    #[test]
+    fn test_binding_store() {
+
+        let x = logic_var!(1, "$X");
+        let y = logic_var!(2, "$Y");
+        let z = logic_var!(3, "$Z");
+        let mut store = BindingStore::new();
+
+        // Variables of the parsed terms are numbered after $Z.
+        let ids = VarIds::starting_at(3);
+        let list1 = parse_term("[a, $Y | $Z]").unwrap()
+                        .recreate_variables(&mut VarMap::with_ids(&ids));
+        let mark = store.mark();
+        assert!(store.unify(&x, &y));
+        assert!(store.unify(&y, &SInteger(3)));
+        assert_eq!(SInteger(3), store.resolve(&x));
+        assert!(!store.unify(&x, &SInteger(4)));
+        store.undo_to(mark);
+        assert_eq!(x, store.resolve(&x));
+        assert!(store.lookup(1).is_none());
+
+        assert!(store.unify(&z, &list1));
+        assert_eq!(0, store.to_substitution_set().iter().take(3).filter(|e| e.is_some()).count());
+
+        let mut store = BindingStore::new();
+        let f = parse_term("f($A, [1, 2, 3])").unwrap()
+                    .recreate_variables(&mut VarMap::with_ids(&ids));
+        let g = parse_term("f(b, [$H | $T])").unwrap()
+                    .recreate_variables(&mut VarMap::with_ids(&ids));
+        assert!(store.unify(&f, &g));
+        assert_eq!("f(b, [1 | [2, 3]])", store.resolve(&g).to_string());
+
+    } // test_binding_store()
+
+    #[test]
+    fn test_trail_solver() {
+
+        let mut kb = test_kb();
+        add_rules!(&mut kb,
+            parse_rule("ancestor($X, $Y) :- father($X, $Y).").unwrap(),
+            parse_rule("ancestor($X, $Y) :- father($X, $Z), ancestor($Z, $Y).").unwrap(),
+            parse_rule("len([], 0).").unwrap(),
+            parse_rule("len([$_ | $T], $N) :- len($T, $M), $N = $M + 1.").unwrap(),
+            parse_rule("first($X) :- father($X, $_), !.").unwrap(),
+            parse_rule("childless($X) :- loves($X, $_), not(father($X, $_)).").unwrap(),
+            parse_rule("either($X) :- $X = 1 ; $X = 2.").unwrap(),
+            parse_rule("big($X) :- father($X, $_), $X > Edgar.").unwrap());
+
+        for query in ["ancestor($A, $D)", "grandfather($X, $Y)", "len([a, b, c], $N)",
+                      "first($F)", "childless($C)", "either($E)", "big($B)",
+                      "append([1, 2], [3], $L)", "loves(Howard, $X)"] {
+            let (persistent, trail) = solve_both(&kb, query);
+            assert_eq!(persistent, trail, "Query: {}", query);
+        }
+
+        set_binding_strategy(BindingStrategy::Trail);
+        let query = parse_query("ancestor(Alfred, $D)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        set_binding_strategy(BindingStrategy::Persistent);
+        let results = try_solve_all(sn, Some(2)).unwrap();
+        assert_eq!(vec!["$D = Edward", "$D = Aethelstan"], results);
+
+        let mut kb = KnowledgeBase::new();
+        load_kb_from_file(&mut kb, "./tests/qsort.txt");
+        add_rules!(&mut kb,
+            parse_rule("sorted($Out) :- data($List), qsort($List, $Out, []).").unwrap());
+        let query = Rc::new(parse_query("sorted($Out)").unwrap());
+        let persistent = solve(make_base_node(Rc::clone(&query), &kb));
+        set_binding_strategy(BindingStrategy::Trail);
+        let trail = solve(make_base_node(query, &kb));
+        set_binding_strategy(BindingStrategy::Persistent);
+        assert!(persistent.starts_with("$Out = [0 | [2 | [4 |"));
+        assert_eq!(persistent, trail);
+
+        // Deep recursion does not overflow the stack.
+        let mut kb = KnowledgeBase::new();
+        add_rules!(&mut kb,
+            parse_rule("countdown(0).").unwrap(),
+            parse_rule("countdown($N) :- $N > 0, $M = $N - 1, countdown($M).").unwrap());
+        set_binding_strategy(BindingStrategy::Trail);
+        let query = parse_query("countdown(20000)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        set_binding_strategy(BindingStrategy::Persistent);
+        assert_eq!(Ok(Some("".to_string())), try_solve(sn));
+
+    } // test_trail_solver()
+
+} // test
@@ -7,24 +7,75 @@
 //! * A Unifiable term 'owns' its data.
 // Cleve Lendon 2023
 
+use std::cell::Cell;
 use std::fmt;
 use std::rc::Rc;
 
 use crate::empty_ss;
 
+use super::error::SuironError;
 use super::goal::Goal;
 use super::logic_var::*;
-use super::s_linked_list::*;
+use super::parse_terms::*;
 use super::built_in_functions::*;
 use super::built_in_predicates::*;
 use super::substitution_set::*;
+use super::symbol::Symbol;
 
 static NOT_A_NODE_ERR: &str = "unify(): Not an SLinkedList node.";
 static UNKNOWN_UNIFIABLE_ERR: &str = "unifiable.rs - Unknown unifiable.";
 static VAR_ID_0_ERR: &str =
        "unify(): Logic variable has an ID of 0. See: recreate_variables().";
 
+thread_local! {
+    // If true, unification fails if a variable would be bound
+    // to a term which contains that variable.
+    static OCCURS_CHECK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets the occurs check for unification.
+///
+/// Without the occurs check, a variable can be bound to a term which
+/// contains it, as in `$X = f($X)`. This creates a cyclic term, which
+/// never ends when it is printed or its variables are replaced. With
+/// the occurs check, such a unification fails. The check is slower,
+/// so it is off by default, as in most Prologs.
+///
+/// The built-in predicate unify_with_occurs_check/2 does the check,
+/// regardless of this setting.
+///
+/// The setting is local to the thread.
+///
+/// # Arguments
+/// * true to check, false not to check
+/// # Usage
+/// ```
+/// use std::rc::Rc;
+/// use suiron::*;
+///
+/// let x = logic_var!(1, "$X");
+/// let f = scomplex!(atom!("f"), x.clone());
+/// let ss = empty_ss!();
+/// set_occurs_check(true);
+/// assert!(x.unify(&f, &ss).is_none());
+/// set_occurs_check(false);
+/// ```
+pub fn set_occurs_check(on: bool) {
+    OCCURS_CHECK.with(|oc| oc.set(on));
+} // set_occurs_check()
+
+/// Determines whether unification does the occurs check.
+///
+/// See [set_occurs_check()](fn.set_occurs_check.html).
+///
+/// # Return
+/// * true or false
+pub fn occurs_check() -> bool {
+    return OCCURS_CHECK.with(|oc| oc.get());
+} // occurs_check()
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Unifiable {
     /// In a [linked list](../s_linked_list/index.html),
     /// Nil indicates that a node has no next node (i.e. is last).
@@ -33,11 +84,23 @@ pub enum Unifiable {
     /// Use [anon!](../macro.anon.html) to create.
     Anonymous,
     /// A string constant. Use [atom!](../macro.atom.html) to construct.
-    Atom(String),
+    Atom(Symbol),
+    /// A string of text. In Suiron source code, strings are enclosed
+    /// in double quotes, eg. "Hello, World". A string does not unify
+    /// with an atom, even if their text is the same.
+    SString(String),
     /// 64-bit floating point number.
     SFloat(f64),
     /// 64-bit integer.
     SInteger(i64),
+    /// Rational number: numerator and denominator. Eg. 1r3 is one third.<br>
+    /// The fraction is always reduced, and the denominator is greater than 1.
+    /// Use [make_rational()](../built_in_rational/fn.make_rational.html) to construct.
+    SRational(i64, i64),
+    /// Arbitrary-precision integer. Requires the `bigint` feature.<br>
+    /// Integer arithmetic which overflows an SInteger produces an SBigInt.
+    #[cfg(feature = "bigint")]
+    SBigInt(num_bigint::BigInt),
     /// Logic variables have an ID number, which is used as an index
     /// into a [substitution set](../substitution_set/index.html).<br>
     /// A logic variable name should start with a dollar sign,
@@ -47,6 +110,9 @@ pub enum Unifiable {
     /// Complex (or compound) term. Eg. symptom(flu, $Symp).<br>
     /// Implemented as a vector of unifiable terms.
     /// Use [scomplex!](../macro.scomplex.html) to construct.<br>
+    // The arguments are not kept in a SmallVec. Inline storage would make
+    // Unifiable contain itself, and a boxed SmallVec needs one allocation,
+    // as a Vec does, but is larger for terms with few arguments.
     SComplex(Vec<Unifiable>),
     /// Represents a node in a linked list of unifiable terms.<br>
     ///
@@ -58,13 +124,14 @@ pub enum Unifiable {
     /// Examples of lists: [a, b, c, d], [$Head | $Tail]<br>
     /// Use [slist!](../macro.slist.html) to construct.
     SLinkedList{
-        term: Box<Unifiable>,   // The node's item.
-        next: Box<Unifiable>,   // Link to another SLinkedList or Nil.
+        term: Rc<Unifiable>,    // The node's item.
+        next: Rc<Unifiable>,    // Link to another SLinkedList or Nil.
         count: usize,
         tail_var: bool,         // tail variable flag
     },
     /// This variant defines built-in functions, such as add(), join(), etc.<br>
     /// Built-in functions produce a unifiable term from a list of arguments.
+    // See SComplex regarding Vec.
     SFunction{name: String, terms: Vec<Unifiable>},
 }
 
@@ -81,26 +148,29 @@ impl Unifiable {
     /// # Arguments
     /// * self - [SComplex](../unifiable/enum.Unifiable.html#variant.SComplex)
     /// # Return
-    /// * key
-    /// # Panics
-    /// * If self is not a
-    /// [complex](../unifiable/enum.Unifiable.html#variant.SComplex) term.
+    /// * key or error, if self is not a
+    ///   [complex](../unifiable/enum.Unifiable.html#variant.SComplex) term.
     /// # Usage
     /// ```
     /// use suiron::*;
     ///
     /// let cmplx = parse_complex("loves(Chandler, Monica)").unwrap();
-    /// let key = cmplx.key();
+    /// let key = cmplx.key().unwrap();
     /// println!("{}", key);  // Should print: loves/2
     /// ```
-    pub fn key(&self) -> String {
+    pub fn key(&self) -> Result<String, SuironError> {
         match self {
-            Unifiable::SComplex(terms) => {
-                let functor = &terms[0];
+            Unifiable::SComplex(terms) if !terms.is_empty() => {
                 let arity = terms.len() - 1;
-                return format!("{}/{}", functor, arity);
+                match &terms[0] {
+                    Unifiable::Atom(functor) => { return Ok(format!("{}/{}", functor, arity)); },
+                    functor => { return Ok(format!("{}/{}", functor, arity)); },
+                }
             },
-            _ => { panic!("key() - Argument must be SComplex."); }
+            _ => {
+                let err = format!("key() - Requires a complex term: {}", self);
+                return Err(SuironError::ParseError(err));
+            }
         }
     } // key()
 
@@ -138,7 +208,7 @@ impl Unifiable {
     /// use std::rc::Rc;
     /// use suiron::*;
     ///
-    /// let x = logic_var!(next_id(), "$X");
+    /// let x = logic_var!(1, "$X");
     /// let age = SInteger(37);
     /// let ss = empty_ss!();
     /// match x.unify(&age, &ss) {
@@ -183,6 +253,17 @@ impl Unifiable {
                     _ => None,
                 }
             },
+            Unifiable::SString(self_str) => {
+                match other {
+                    Unifiable::SString(other_str) => {
+                        if self_str.eq(other_str) { return Some(Rc::clone(ss)); }
+                        None
+                    },
+                    Unifiable::LogicVar{id: _, name: _} => { other.unify(&self, ss) },
+                    Unifiable::Anonymous => { return Some(Rc::clone(ss)); },
+                    _ => None,
+                }
+            },
             Unifiable::SFloat(self_float) => {
                 match other {
                     Unifiable::SFloat(other_float) => {
@@ -205,6 +286,29 @@ impl Unifiable {
                     _ => None,
                 }
             },
+            Unifiable::SRational(n1, d1) => {
+                match other {
+                    Unifiable::SRational(n2, d2) => {
+                        if n1 == n2 && d1 == d2 { return Some(Rc::clone(ss)); }
+                        None
+                    },
+                    Unifiable::LogicVar{id: _, name: _} => { other.unify(&self, ss) },
+                    Unifiable::Anonymous => { return Some(Rc::clone(ss)); },
+                    _ => None,
+                }
+            },
+            #[cfg(feature = "bigint")]
+            Unifiable::SBigInt(self_int) => {
+                match other {
+                    Unifiable::SBigInt(other_int) => {
+                        if self_int == other_int { return Some(Rc::clone(ss)); }
+                        None
+                    },
+                    Unifiable::LogicVar{id: _, name: _} => { other.unify(&self, ss) },
+                    Unifiable::Anonymous => { return Some(Rc::clone(ss)); },
+                    _ => None,
+                }
+            },
             Unifiable::LogicVar{id, name: _} => {
 
                 let id = *id;
@@ -235,19 +339,17 @@ impl Unifiable {
                     }
                 }
 
-                let mut length_dst = length_src;
-                if id >= length_dst { length_dst = id + 1; }
-
-                let mut new_ss: SubstitutionSet = vec![None; length_dst];
-
-                for (i, item) in ss.iter().enumerate() {
-                    if let Some(item) = item {
-                        new_ss[i] = Some(Rc::clone(&item));
+                if occurs_check() {
+                    let target = dereference(other, ss);
+                    if let Unifiable::LogicVar{id: target_id, name: _} = target {
+                        // The other variable is already bound to this one.
+                        if *target_id == id { return Some(Rc::clone(ss)); }
                     }
+                    else if occurs_in(id, target, ss) { return None; }
                 }
 
-                new_ss[id] = Some(Rc::new(other.clone()));
-                return Some(Rc::new(new_ss));
+                // Make a new substitution set, which shares the old one.
+                return Some(Rc::new(ss.bind(id, Rc::new(other.clone()))));
 
             },
             Unifiable::SComplex(self_terms) => {
@@ -373,6 +475,27 @@ impl Unifiable {
 
     }  // unify()
 
+    /// Unifies with the occurs check, regardless of the setting.
+    ///
+    /// See [set_occurs_check()](../unifiable/fn.set_occurs_check.html).
+    ///
+    /// # Arguments
+    /// * self
+    /// * other unifiable term
+    /// * substitution set
+    /// # Returns
+    /// * [SubstitutionSet](../substitution_set/type.SubstitutionSet.html)
+    /// or None
+    pub fn unify_with_occurs_check<'a>(&'a self, other: &'a Unifiable,
+                                       ss: &'a Rc<SubstitutionSet<'a>>)
+                                       -> Option<Rc<SubstitutionSet<'a>>> {
+        let previous = occurs_check();
+        set_occurs_check(true);
+        let result = self.unify(other, ss);
+        set_occurs_check(previous);
+        return result;
+    } // unify_with_occurs_check()
+
     /// Recreates logic variables to give them unique IDs.
     ///
     /// The scope of a logic variable is the rule in which it is defined.
@@ -416,43 +539,19 @@ impl Unifiable {
         match self {
 
             Unifiable::LogicVar{id: _, name} => {
-                if let Some(id) = recreated_vars.get(&name) {
-                    Unifiable::LogicVar{id: *id, name: name}
-                }
-                else {
-                    let next_id = next_id();
-                    recreated_vars.insert(name.clone(), next_id);
-                    Unifiable::LogicVar{id: next_id, name: name}
-                }
+                let id = recreated_vars.id_of(&name);
+                Unifiable::LogicVar{id, name}
             },
             Unifiable::SComplex(terms) => {
-                let mut new_terms = vec![];
-                for term in terms {
-                    let term = term.recreate_variables(recreated_vars);
-                    new_terms.push(term);
-                }
-                Unifiable::SComplex(new_terms)
+                Unifiable::SComplex(recreate_vars_terms(terms, recreated_vars))
             },
             Unifiable::SLinkedList{term: _, next: _, count: _, tail_var: _} => {
-                let mut this_list = self;
-                let mut new_terms = vec![];
-                let mut vbar = false;  // vertical bar |
-                while let Unifiable::SLinkedList{term: t, next: n,
-                                     count: c, tail_var: tf} = this_list {
-                    new_terms.push(t.recreate_variables(recreated_vars));
-                    if c == 1 && tf { vbar = true; }
-                    this_list = *n;
-                    if this_list == Unifiable::Nil { break; }
-                }
-                return make_linked_list(vbar, new_terms);
+                let list = recreate_vars_list(&Rc::new(self), recreated_vars);
+                return Rc::try_unwrap(list).unwrap_or_else(|list| (*list).clone());
             },
             Unifiable::SFunction{name, terms} => {
-                let mut new_terms: Vec<Unifiable> = vec![];
-                for term in terms {
-                    let term = term.recreate_variables(recreated_vars);
-                    new_terms.push(term);
-                }
-                return Unifiable::SFunction{name, terms: new_terms};
+                let terms = recreate_vars_terms(terms, recreated_vars);
+                return Unifiable::SFunction{name, terms};
             },
             _ => self,
 
@@ -491,9 +590,13 @@ impl Unifiable {
         match self {
             Unifiable::Nil => { Unifiable::Nil },
             Unifiable::Anonymous => { Unifiable::Anonymous },
-            Unifiable::Atom(s) => { Unifiable::Atom(s.to_string()) },
+            Unifiable::Atom(s) => { Unifiable::Atom(s.clone()) },
+            Unifiable::SString(s) => { Unifiable::SString(s.to_string()) },
             Unifiable::SFloat(f) => { Unifiable::SFloat(*f) },
             Unifiable::SInteger(i) => { Unifiable::SInteger(*i) },
+            Unifiable::SRational(n, d) => { Unifiable::SRational(*n, *d) },
+            #[cfg(feature = "bigint")]
+            Unifiable::SBigInt(b) => { Unifiable::SBigInt(b.clone()) },
             Unifiable::LogicVar{id, name} => {
                 let ss_length = ss.len();
                 // If variable is bound.
@@ -506,18 +609,11 @@ impl Unifiable {
                 return Unifiable::LogicVar{id: *id, name: name.to_string()};
             },
             Unifiable::SComplex(terms) => {
-                let mut new_terms: Vec<Unifiable> = vec![];
-                for term in terms {
-                    let new_term = term.replace_variables(ss);
-                    new_terms.push(new_term);
-                }
-                Unifiable::SComplex(new_terms)
+                Unifiable::SComplex(terms.iter().map(|t| t.replace_variables(ss)).collect())
             },
             Unifiable::SLinkedList{term: t, next: n, count: c, tail_var: tf} => {
-                let t2 = t.replace_variables(ss);
-                let n2 = n.replace_variables(ss);
-                Unifiable::SLinkedList{term: Box::new(t2),
-                                       next: Box::new(n2),
+                Unifiable::SLinkedList{term: replace_vars_shared(t, ss),
+                                       next: replace_vars_shared(n, ss),
                                        count: *c,
                                        tail_var: *tf}
             },
@@ -528,6 +624,95 @@ impl Unifiable {
 
 } // impl Unifiable
 
+// Replaces the bound variables of a term, as replace_variables() does.
+// Terms which do not change, such as atoms, numbers and lists of
+// constants, are shared rather than copied.
+//
+// Arguments
+//    term
+//    substitution set
+// Return
+//    new term
+fn replace_vars_shared(term: &Rc<Unifiable>, ss: &SubstitutionSet) -> Rc<Unifiable> {
+    match &**term {
+        Unifiable::LogicVar{id, name: _} => {
+            if let Some(Some(bound)) = ss.get(*id) {
+                return replace_vars_shared(bound, ss);
+            }
+            return Rc::clone(term);
+        },
+        Unifiable::SLinkedList{term: t, next: n, count, tail_var} => {
+            let t2 = replace_vars_shared(t, ss);
+            let n2 = replace_vars_shared(n, ss);
+            if Rc::ptr_eq(t, &t2) && Rc::ptr_eq(n, &n2) { return Rc::clone(term); }
+            return Rc::new(Unifiable::SLinkedList{term: t2, next: n2,
+                                                  count: *count, tail_var: *tail_var});
+        },
+        Unifiable::SComplex(_) | Unifiable::SFunction{name: _, terms: _} => {
+            return Rc::new(term.replace_variables(ss));
+        },
+        _ => { return Rc::clone(term); },
+    }
+} // replace_vars_shared()
+
+// Recreates the logic variables of a list, as recreate_variables() does.
+// The tail of the list after its last variable is shared, not copied.
+//
+// Arguments
+//    list
+//    map of previously recreated variable IDs
+// Return
+//    new list
+fn recreate_vars_list(list: &Rc<Unifiable>, vars: &mut VarMap) -> Rc<Unifiable> {
+
+    // Recreate the terms from first to last, to number the variables in order.
+    let mut nodes: Vec<Rc<Unifiable>> = vec![];
+    let mut new_terms: Vec<Rc<Unifiable>> = vec![];
+    let mut this_list = Rc::clone(list);
+    while let Unifiable::SLinkedList{term, next, count: _, tail_var: _} = &*this_list {
+        let new_term = if has_variables(term) {
+            Rc::new((**term).clone().recreate_variables(vars))
+        } else { Rc::clone(term) };
+        new_terms.push(new_term);
+        let next = Rc::clone(next);
+        nodes.push(this_list);
+        this_list = next;
+    }
+
+    // Rebuild the list from last to first.
+    let mut new_list = this_list;
+    for (node, new_term) in nodes.into_iter().zip(new_terms).rev() {
+        if let Unifiable::SLinkedList{term, next, count, tail_var} = &*node {
+            if Rc::ptr_eq(term, &new_term) && Rc::ptr_eq(next, &new_list) {
+                new_list = node;
+                continue;
+            }
+            new_list = Rc::new(Unifiable::SLinkedList{term: new_term, next: new_list,
+                                                      count: *count, tail_var: *tail_var});
+        }
+    }
+    return new_list;
+
+} // recreate_vars_list()
+
+// Determines whether a term contains logic variables.
+pub(crate) fn has_variables(term: &Unifiable) -> bool {
+    match term {
+        Unifiable::LogicVar{id: _, name: _} => { true },
+        Unifiable::SComplex(terms) |
+        Unifiable::SFunction{name: _, terms} => { terms.iter().any(has_variables) },
+        Unifiable::SLinkedList{term: _, next: _, count: _, tail_var: _} => {
+            let mut this_list = term;
+            while let Unifiable::SLinkedList{term, next, count: _, tail_var: _} = this_list {
+                if has_variables(term) { return true; }
+                this_list = next;
+            }
+            false
+        },
+        _ => { false },
+    }
+} // has_variables()
+
 /// Recreate logic variables in a vector of goals.
 ///
 /// # Arguments
@@ -536,24 +721,65 @@ impl Unifiable {
 /// # Return
 /// * recreated goals
 pub fn recreate_vars_goals(goals: Vec<Goal>, vars: &mut VarMap) -> Vec<Goal> {
-    let mut new_goals: Vec<Goal> = vec![];
-    for g in goals { new_goals.push(g.recreate_variables(vars)); }
-    return new_goals;
+    return goals.into_iter().map(|g| g.recreate_variables(vars)).collect();
 } // recreate_vars_goals()
 
 /// Recreate logic variables in a vector of unifiable terms.
 ///
+/// The vector is reused for the recreated terms, so that no new
+/// vector is allocated.
+///
 /// # Arguments
 /// * terms
 /// * vars - previously recreated logic variables
 /// # Return
 /// * recreated terms
 pub fn recreate_vars_terms(terms: Vec<Unifiable>, vars: &mut VarMap) -> Vec<Unifiable> {
-    let mut new_terms: Vec<Unifiable> = vec![];
-    for t in terms { new_terms.push(t.recreate_variables(vars)); }
-    return new_terms;
+    return terms.into_iter().map(|t| t.recreate_variables(vars)).collect();
 } // recreate_vars_terms()
 
+// Follows the bindings of a logic variable, until it reaches
+// a term which is not a bound variable.
+//
+// Arguments
+//    term
+//    substitution set
+// Return
+//    term
+fn dereference<'b>(term: &'b Unifiable, ss: &'b SubstitutionSet) -> &'b Unifiable {
+    let mut term = term;
+    while let Unifiable::LogicVar{id, name: _} = term {
+        match ss.get(*id) {
+            Some(Some(bound)) => { term = bound; },
+            _ => { break; },
+        }
+    }
+    return term;
+} // dereference()
+
+// Determines whether a logic variable occurs in a term,
+// following the bindings of the term's variables.
+//
+// Arguments
+//    variable ID
+//    term
+//    substitution set
+// Return
+//    true if the variable occurs
+fn occurs_in(id: usize, term: &Unifiable, ss: &SubstitutionSet) -> bool {
+    match dereference(term, ss) {
+        Unifiable::LogicVar{id: other_id, name: _} => { *other_id == id },
+        Unifiable::SComplex(terms) |
+        Unifiable::SFunction{name: _, terms} => {
+            terms.iter().any(|t| occurs_in(id, t, ss))
+        },
+        Unifiable::SLinkedList{term, next, count: _, tail_var: _} => {
+            occurs_in(id, term, ss) || occurs_in(id, next, ss)
+        },
+        _ => { false },
+    }
+} // occurs_in()
+
 // Display trait, to display unifiable terms.
 impl fmt::Display for Unifiable {
 
@@ -561,9 +787,13 @@ impl fmt::Display for Unifiable {
         match &self {
             Unifiable::Nil => { write!(f, "Nil") },
             Unifiable::Anonymous => { write!(f, "$_") },
-            Unifiable::Atom(s) => { write!(f, "{}", s) },
+            Unifiable::Atom(s) => { write!(f, "{}", quote_atom(s)) },
+            Unifiable::SString(s) => { write!(f, "\"{}\"", escape_text(s)) },
             Unifiable::SFloat(fl) => { write!(f, "{}", fl) },
             Unifiable::SInteger(i) => { write!(f, "{}", i) },
+            Unifiable::SRational(n, d) => { write!(f, "{}r{}", n, d) },
+            #[cfg(feature = "bigint")]
+            Unifiable::SBigInt(i) => { write!(f, "{}", i) },
             Unifiable::LogicVar{id, name} => {
                 if *id == 0 { write!(f, "{}", name) }
                 else { write!(f, "{}_{}", name, id) }
@@ -574,7 +804,11 @@ impl fmt::Display for Unifiable {
                 let mut comma = false;
                 for arg in args {
                     if functor {
-                        out = out + &arg.to_string() + "(";
+                        // The functor is not quoted.
+                        match arg {
+                            Unifiable::Atom(s) => { out = out + s + "("; },
+                            _ => { out = out + &arg.to_string() + "("; },
+                        }
                         functor = false;
                     }
                     else { // args
@@ -633,6 +867,8 @@ mod test {
         assert_eq!("$_", s);
         let s = atom!("Saltwater").to_string();
         assert_eq!("Saltwater", s);
+        let s = SString("Saltwater".to_string()).to_string();
+        assert_eq!("\"Saltwater\"", s);
         let s = SFloat(3.14159).to_string();
         assert_eq!("3.14159", s);
         let s = SInteger(67).to_string();
@@ -730,6 +966,58 @@ mod test {
         else { panic!("Failed to unify: $Y = a"); }
     } // test_unify_with_bound_vars()
 
+    /// Test the occurs check.
+    ///     $X = f($X), $X = $Y, $Y = [a, $X]
+    #[test]
+    fn test_occurs_check() {
+
+        let ss = empty_ss!();
+        let x = logic_var!(1, "$X");
+        let y = logic_var!(2, "$Y");
+        let fx = scomplex!(atom!("f"), x.clone());
+        let list = slist!(false, atom!("a"), x.clone());
+
+        assert_ne!(None, x.unify(&fx, &ss));
+        assert_eq!(None, x.unify_with_occurs_check(&fx, &ss));
+        assert_eq!(None, fx.unify_with_occurs_check(&x, &ss));
+        assert!(!occurs_check());
+
+        set_occurs_check(true);
+        assert_eq!(None, x.unify(&fx, &ss));
+        let ss2 = x.unify(&y, &ss).unwrap();
+        assert_eq!(None, y.unify(&list, &ss2));
+        // $Y is bound to $X, so $Y = $X succeeds.
+        assert_ne!(None, y.unify(&x, &ss2));
+        assert_ne!(None, x.unify(&scomplex!(atom!("f"), y.clone()), &ss));
+        set_occurs_check(false);
+
+        let mut kb = KnowledgeBase::new();
+        add_rules!(&mut kb,
+            parse_rule("test($X, $Y) :- unify_with_occurs_check($X, f($Y)).").unwrap());
+        let query = parse_query("test($A, $A)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("No more.", solve(sn));
+        let query = parse_query("test($A, b)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$A = f(b)", solve(sn));
+
+    } // test_occurs_check()
+
+    /// A string should not unify with an atom which has the same text.
+    ///     "Koala" = Koala
+    #[test]
+    fn test_unify_string() {
+        let ss = empty_ss!();
+        let s1 = SString("Koala".to_string());
+        let s2 = SString("Koala".to_string());
+        let s3 = SString("Wombat".to_string());
+        let a  = atom!("Koala");
+        assert_ne!(None, s1.unify(&s2, &ss));
+        assert_eq!(None, s1.unify(&s3, &ss));
+        assert_eq!(None, s1.unify(&a, &ss));
+        assert_eq!(None, a.unify(&s1, &ss));
+    }
+
     /// Test recreate_variables().
     /// This test creates a few variables ($W, $X, $Y, $Z), then calls
     /// recreate_variables() on a complex term and a list which contain
@@ -739,8 +1027,6 @@ mod test {
     #[test]
     fn test_recreate_variables() {
 
-        clear_id();
-
         let a = atom!("a");
         let b = atom!("b");
         let c = atom!("c");
@@ -817,6 +1103,38 @@ mod test {
 
     } // test_replace_variables()
 
+    /// Lists share the nodes which do not change, when variables are
+    /// recreated or replaced.
+    #[test]
+    fn test_list_sharing() {
+
+        let next_of = |list: &Unifiable| -> Rc<Unifiable> {
+            match list {
+                Unifiable::SLinkedList{term: _, next, count: _, tail_var: _} => {
+                    Rc::clone(next)
+                },
+                _ => { panic!("Not a list: {}", list); },
+            }
+        };
+
+        let list1 = parse_term("[$X, a, b, c]").unwrap();
+        let list2 = list1.clone().recreate_variables(&mut VarMap::new());
+        assert_ne!(list1, list2);
+        assert!(Rc::ptr_eq(&next_of(&list1), &next_of(&list2)));
+
+        // $Y is bound to [1, 2, 3].
+        let y = logic_var!(1, "$Y");
+        let list3 = parse_term("[1, 2, 3]").unwrap();
+        let ss = y.unify(&list3, &empty_ss!()).unwrap();
+        let list4 = scomplex!(atom!("f"), y).replace_variables(&ss);
+        if let Unifiable::SComplex(terms) = list4 {
+            assert_eq!(list3, terms[1]);
+            assert!(Rc::ptr_eq(&next_of(&list3), &next_of(&terms[1])));
+        }
+        else { panic!("Not a complex term."); }
+
+    } // test_list_sharing()
+
     /// Test key() function.
     #[test]
     fn test_key() {
@@ -826,24 +1144,22 @@ mod test {
         let y   = atom!("Yttrium");
         let num = SInteger(39);
         let c = scomplex!(el, y, num);
-        let name = c.key();
+        let name = c.key().unwrap();
         assert_eq!(name, "element/2");
 
         // measure -> measure/0
         let m = atom!("measure");
         let c = scomplex!(m);
-        let name = c.key();
+        let name = c.key().unwrap();
         assert_eq!(name, "measure/0");
     }
 
     /// Test key() with invalid argument.
     /// Argument is not a complex term.
     #[test]
-    #[should_panic]
-    fn test_key_panic_1() {
+    fn test_key_error() {
         let tb = atom!("Terbium");
-        //let _name = tb.key();
-        tb.key();
+        assert_eq!("key() - Requires a complex term: Terbium", tb.key().unwrap_err());
     }
 
 } // test
@@ -0,0 +1,399 @@
+//! Functions to support user-defined operators.
+//!
+//! In Suiron source code, operators are declared with the op/3 directive:
+//!
+//! <pre>
+//!   :- op(700, xfx, likes).
+//!   :- op(200, fy, not_really).
+//!
+//!   john likes mary.
+//!   happy($X) :- $X likes wine.
+//! </pre>
+//!
+//! After the declaration above, `john likes mary` is parsed as the
+//! complex term `likes(john, mary)`.
+//!
+//! The operator type defines the position of the operator, and its
+//! associativity:
+//!
+//! <pre>
+//!   xfx, xfy, yfx   infix
+//!   fy, fx          prefix
+//!   xf, yf          postfix
+//! </pre>
+//!
+//! When a term contains several operators, it is divided at the operator
+//! with the highest precedence. For operators with the same precedence,
+//! yfx operators group to the left, and the others group to the right.
+//!
+//! As with Suiron's built-in infixes, an operator must be separated from
+//! its operands by spaces.
+//!
+//! Operators which are declared by a directive belong to the knowledge
+//! base which is being loaded. They apply to the rest of the source, and
+//! do not affect other knowledge bases. (See
+//! [operators()](../knowledge_base/struct.KnowledgeBase.html#method.operators).)
+//! An [Engine](../engine/struct.Engine.html) parses queries with the
+//! operators of its knowledge base. To parse a term with the operators
+//! of a knowledge base, use [with_operators()](fn.with_operators.html).
+//!
+//! Operators which are defined by [add_operator()](fn.add_operator.html)
+//! are kept in a table which is local to the thread.
+//
+// Cleve Lendon 2023
+
+use std::fmt;
+use std::cell::RefCell;
+
+use super::error::SuironError;
+use super::parse_terms::*;
+use super::unifiable::Unifiable;
+
+use crate::atom;
+use crate::str_to_chars;
+use crate::chars_to_string;
+
+/// Types of user-defined operators.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OpType { Xfx, Xfy, Yfx, Fy, Fx, Xf, Yf }
+
+impl OpType {
+
+    /// Gets the operator type from its name, eg. xfx.
+    ///
+    /// # Arguments
+    /// * name of operator type
+    /// # Return
+    /// * operator type or None
+    pub fn from_name(name: &str) -> Option<OpType> {
+        match name {
+            "xfx" => Some(OpType::Xfx),
+            "xfy" => Some(OpType::Xfy),
+            "yfx" => Some(OpType::Yfx),
+            "fy"  => Some(OpType::Fy),
+            "fx"  => Some(OpType::Fx),
+            "xf"  => Some(OpType::Xf),
+            "yf"  => Some(OpType::Yf),
+            _     => None,
+        }
+    } // from_name()
+
+    /// Determines whether the operator type is infix.
+    pub fn is_infix(&self) -> bool {
+        matches!(self, OpType::Xfx | OpType::Xfy | OpType::Yfx)
+    }
+
+    /// Determines whether the operator type is prefix.
+    pub fn is_prefix(&self) -> bool {
+        matches!(self, OpType::Fy | OpType::Fx)
+    }
+
+} // impl OpType
+
+impl fmt::Display for OpType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = format!("{:?}", self).to_lowercase();
+        write!(f, "{}", name)
+    }
+} // fmt::Display
+
+/// Defines a user-defined operator.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UserOperator {
+    pub precedence: u16,
+    pub op_type: OpType,
+    pub name: String,
+}
+
+thread_local! {
+    static USER_OPERATORS: RefCell<Vec<UserOperator>> = const { RefCell::new(vec![]) };
+}
+
+/// Defines an operator. This is Suiron's equivalent of Prolog's op/3.
+///
+/// An operator can have one infix definition, and one prefix or
+/// postfix definition. A new definition replaces the old one.
+/// A precedence of 0 removes the definition.
+///
+/// # Arguments
+/// * precedence, from 0 to 1200
+/// * operator type, eg. xfx
+/// * name of operator
+/// # Return
+/// * Ok or error message
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// add_operator(700, "xfx", "likes").unwrap();
+/// let term = parse_term("john likes mary").unwrap();
+/// println!("{}", term);  // Prints: likes(john, mary)
+/// clear_operators();
+/// ```
+pub fn add_operator(precedence: i64, op_type: &str, name: &str) -> Result<(), String> {
+    return USER_OPERATORS.with(|ops| {
+        define_operator(&mut ops.borrow_mut(), precedence, op_type, name)
+    });
+} // add_operator()
+
+// Adds an operator to a table of operators, or removes it.
+// See add_operator().
+//
+// Arguments
+//    table of operators
+//    precedence, from 0 to 1200
+//    operator type, eg. xfx
+//    name of operator
+// Return
+//    Ok or error message
+pub(crate) fn define_operator(ops: &mut Vec<UserOperator>, precedence: i64,
+                              op_type: &str, name: &str) -> Result<(), String> {
+
+    if !(0..=1200).contains(&precedence) {
+        return Err(format!("add_operator() - Invalid precedence: {}", precedence));
+    }
+    let op_type = match OpType::from_name(op_type) {
+        Some(t) => { t },
+        None => { return Err(format!("add_operator() - Invalid type: {}", op_type)); },
+    };
+    let name = name.trim();
+    if name.is_empty() || name.contains(' ') {
+        return Err(format!("add_operator() - Invalid name: {}", name));
+    }
+
+    // An infix definition replaces an infix definition.
+    // A prefix or postfix definition replaces a prefix or postfix.
+    ops.retain(|op| op.name != name || op.op_type.is_infix() != op_type.is_infix());
+    if precedence > 0 {
+        ops.push(UserOperator{precedence: precedence as u16,
+                              op_type, name: name.to_string()});
+    }
+    return Ok(());
+
+} // define_operator()
+
+/// Removes all user-defined operators.
+pub fn clear_operators() {
+    USER_OPERATORS.with(|ops| ops.borrow_mut().clear());
+}
+
+/// Gets a list of the user-defined operators.
+///
+/// # Return
+/// * vector of operators
+pub fn get_operators() -> Vec<UserOperator> {
+    return USER_OPERATORS.with(|ops| ops.borrow().clone());
+}
+
+/// Calls a function with a given table of operators. The operators of
+/// the thread are set aside until the function returns.
+///
+/// # Arguments
+/// * operators
+/// * function
+/// # Return
+/// * result of the function
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// let mut kb = KnowledgeBase::new();
+/// let source = ":- op(700, xfx, likes).\njohn likes mary.";
+/// load_kb_from_reader(&mut kb, source.as_bytes());
+///
+/// let query = with_operators(kb.operators(), || parse_query("$X likes mary"));
+/// println!("{}", query.unwrap());  // Prints: likes($X_1, mary)
+/// assert!(get_operators().is_empty());
+/// ```
+pub fn with_operators<T>(operators: &[UserOperator], f: impl FnOnce() -> T) -> T {
+    let saved = USER_OPERATORS.with(|ops| ops.replace(operators.to_vec()));
+    let _guard = RestoreOperators(saved);
+    return f();
+} // with_operators()
+
+// Restores the thread's operators when dropped, even after a panic.
+struct RestoreOperators(Vec<UserOperator>);
+
+impl Drop for RestoreOperators {
+    fn drop(&mut self) {
+        let saved = std::mem::take(&mut self.0);
+        USER_OPERATORS.with(|ops| { *ops.borrow_mut() = saved; });
+    }
+}
+
+/// Parses a term which contains a user-defined operator.
+///
+/// If no user-defined operator occurs in the string (outside of
+/// parentheses, brackets and quotes), the function returns None.
+///
+/// # Arguments
+/// * string to parse
+/// # Return
+/// * Some(Ok([SComplex](../unifiable/enum.Unifiable.html#variant.SComplex))),
+///   Some(error message), or None
+/// # Usage
+/// ```
+/// use suiron::*;
+///
+/// add_operator(700, "xfx", "likes").unwrap();
+/// add_operator(200, "fy", "very").unwrap();
+/// let term = parse_operators("john likes very much($X)").unwrap().unwrap();
+/// println!("{}", term);  // Prints: likes(john, very(much($X)))
+/// clear_operators();
+/// ```
+pub fn parse_operators(to_parse: &str) -> Option<Result<Unifiable, SuironError>> {
+
+    let ops = get_operators();
+    if ops.is_empty() { return None; }
+
+    let s = to_parse.trim();
+    let chrs = str_to_chars!(s);
+    let words = split_words(&chrs);
+    let n = words.len();
+    if n < 2 { return None; }
+
+    // Find the principal operator: (index of word, operator).
+    let mut best: Option<(usize, &UserOperator)> = None;
+    for (i, (start, end)) in words.iter().enumerate() {
+        let word = chars_to_string!(chrs[*start..*end]);
+        for op in ops.iter().filter(|op| op.name == word) {
+            let fits = if op.op_type.is_infix() { i > 0 && i < n - 1 }
+                       else if op.op_type.is_prefix() { i == 0 }
+                       else { i == n - 1 };
+            if !fits { continue; }
+            best = match best {
+                None => Some((i, op)),
+                Some((_, b)) if op.precedence > b.precedence => Some((i, op)),
+                // yfx groups to the left, so the rightmost operator is principal.
+                Some((_, b)) if op.precedence == b.precedence &&
+                                op.op_type == OpType::Yfx => Some((i, op)),
+                _ => best,
+            };
+        }
+    } // for
+
+    let (i, op) = best?;
+    let functor = atom!(op.name.as_str());
+    let left  = chars_to_string!(chrs[0..words[i].0]);
+    let right = chars_to_string!(chrs[words[i].1..]);
+
+    let result = if op.op_type.is_infix() {
+        parse_term(&left).and_then(|l| {
+            parse_term(&right).map(|r| Unifiable::SComplex(vec![functor, l, r]))
+        })
+    }
+    else if op.op_type.is_prefix() {
+        parse_term(&right).map(|r| Unifiable::SComplex(vec![functor, r]))
+    }
+    else {
+        parse_term(&left).map(|l| Unifiable::SComplex(vec![functor, l]))
+    };
+    return Some(result);
+
+} // parse_operators()
+
+// Splits a string into words, which are separated by spaces.
+// Spaces within parentheses, brackets and quotes are ignored.
+//
+// Arguments
+//    vector of chars
+// Return
+//    vector of (start, end) indices
+fn split_words(chrs: &[char]) -> Vec<(usize, usize)> {
+
+    let mut words: Vec<(usize, usize)> = vec![];
+    let mut depth = 0;
+    let mut start: Option<usize> = None;
+    let quoted = quoted_text(chrs);
+
+    let mut i = 0;
+    while i < chrs.len() {
+        let ch = chrs[i];
+        if !quoted[i] {
+            if ch == '(' || ch == '[' { depth += 1; }
+            else if ch == ')' || ch == ']' { depth -= 1; }
+            else if ch == ' ' && depth == 0 {
+                if let Some(s) = start { words.push((s, i)); }
+                start = None;
+                i += 1;
+                continue;
+            }
+        }
+        if start.is_none() { start = Some(i); }
+        i += 1;
+    }
+    if let Some(s) = start { words.push((s, chrs.len())); }
+    return words;
+
+} // split_words()
+
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+    use crate::*;
+
+    #[test]
+    fn test_parse_operators() {
+
+        clear_operators();
+        assert_eq!(None, parse_operators("john likes mary"));
+
+        add_operator(700, "xfx", "likes").unwrap();
+        add_operator(200, "xfy", "and").unwrap();
+        add_operator(500, "yfx", "then").unwrap();
+        add_operator(100, "fy", "very").unwrap();
+        add_operator(100, "xf", "indeed").unwrap();
+
+        let parse = |s: &str| parse_term(s).unwrap().to_string();
+        assert_eq!("likes(john, mary)", parse("john likes mary"));
+        assert_eq!("likes(john, and(wine, and(cheese, bread)))",
+                   parse("john likes wine and cheese and bread"));
+        assert_eq!("then(then(a, b), c)", parse("a then b then c"));
+        assert_eq!("likes(very(john), indeed(mary))", parse("very john likes mary indeed"));
+        assert_eq!("likes(f(likes(a, b)), \"x likes y\")",
+                   parse("f(a likes b) likes \"x likes y\""));
+        assert_eq!("likes($X, add($Y, 1))", parse("$X likes $Y + 1"));
+
+        // Precedence 0 removes the operator.
+        add_operator(0, "xfx", "likes").unwrap();
+        assert!(parse_operators("john likes mary").is_none());
+
+        assert!(add_operator(1201, "xfx", "a").is_err());
+        assert!(add_operator(700, "xyz", "a").is_err());
+        clear_operators();
+
+    } // test_parse_operators()
+
+    #[test]
+    fn test_operator_rules() {
+
+        clear_operators();
+        add_operator(700, "xfx", "likes").unwrap();
+
+        let mut kb = KnowledgeBase::new();
+        let rules = ["john likes mary.", "mary likes wine.",
+                     "happy($X) :- $X likes wine."];
+        for r in rules {
+            match parse_rule(r) {
+                Ok(rule) => { add_rules!(&mut kb, rule); },
+                Err(err) => { panic!("Should be no parsing errors. {}", err); },
+            }
+        }
+
+        let query = parse_query("$X likes mary").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = john", solve(sn));
+
+        let query = parse_query("happy($X)").unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        assert_eq!("$X = mary", solve(sn));
+
+        clear_operators();
+
+    } // test_operator_rules()
+
+} // test
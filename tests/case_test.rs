@@ -0,0 +1,49 @@
+// Test the built-in predicates upcase_atom() and downcase_atom().
+//
+// These predicates convert the letters of an atom to upper or lower
+// case. Unicode letters are supported.
+//
+//   shout($Out)   :- upcase_atom(Ça va bien, $Out).     # ÇA VA BIEN
+//   whisper($Out) :- downcase_atom(ДОБРЫЙ, $Out).       # добрый
+//   same($X)      :- $X = yes, downcase_atom(Hello, hello).
+//
+// Cleve Lendon  2023
+
+use suiron::*;
+use std::rc::Rc;
+
+#[test]
+pub fn test_case() {
+
+    let mut kb = KnowledgeBase::new();
+
+    let rules = ["shout($Out) :- upcase_atom(Ça va bien, $Out).",
+                 "whisper($Out) :- downcase_atom(ДОБРЫЙ, $Out).",
+                 "same($X) :- $X = yes, downcase_atom(Hello, hello).",
+                 "unbound($X) :- upcase_atom($Y, $X)."];
+
+    for r in rules {
+        match parse_rule(r) {
+            Ok(rule) => { add_rules!(&mut kb, rule); },
+            Err(err) => { panic!("Should be no parsing errors. {}", err); },
+        }
+    }
+
+    let query = parse_query("shout($X)").unwrap();
+    let sn = make_base_node(Rc::new(query), &kb);
+    assert_eq!("$X = ÇA VA BIEN", solve(sn));
+
+    let query = parse_query("whisper($X)").unwrap();
+    let sn = make_base_node(Rc::new(query), &kb);
+    assert_eq!("$X = добрый", solve(sn));
+
+    let query = parse_query("same($X)").unwrap();
+    let sn = make_base_node(Rc::new(query), &kb);
+    assert_eq!("$X = yes", solve(sn));
+
+    // The first argument must be grounded.
+    let query = parse_query("unbound($X)").unwrap();
+    let sn = make_base_node(Rc::new(query), &kb);
+    assert_eq!("No more.", solve(sn));
+
+} // test_case()
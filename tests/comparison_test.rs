@@ -1,6 +1,6 @@
 // Test comparison predicates.
 //
-// Test the built-in comparison predicates: > >= == <= <
+// Test the built-in comparison predicates: > >= == <= < =:= =\=
 // Eg.: .., $X <= 23,...
 //
 // Cleve Lendon  2023
@@ -171,17 +171,17 @@ pub fn test_comparison() {
     add_rules!(&mut kb, r18, r19, r20, r21, r22, r23, r24);
 
     // ?- test($Z).
-    clear_id();
     let query = query!(atom!("test"), z());
     let sn = make_base_node(Rc::clone(&query), &kb);
 
     let mut results: Vec<String> = vec![];
-    let timer = start_query_timer(S_TIMEOUT);
+    let token = cancellation_token(&sn);
+    let timer = start_query_timer(S_TIMEOUT, &token);
 
     loop {
 
         let solution = next_solution(Rc::clone(&sn));
-        if query_stopped() { break; }
+        if token.timed_out() { break; }
 
         match solution {
            Some(ss) => {
@@ -199,7 +199,7 @@ pub fn test_comparison() {
     } // loop
 
     cancel_timer(timer);
-    if query_stopped() {
+    if token.timed_out() {
         let s = format!("Query timed out after {} milliseconds.", S_TIMEOUT);
         results.push(s);
     }
@@ -213,3 +213,104 @@ pub fn test_comparison() {
     assert_eq!(s, format!("{:?}", results));
 
 } // test_backchaining()
+
+#[test]
+pub fn test_arithmetic_comparison() {
+
+    let mut kb = KnowledgeBase::new();
+
+    let rules = ["double($X, $Y) :- $X * 2 =:= $Y.",
+                 "odd($X) :- $X mod 2 =\\= 0.",
+                 "same($X, $Y) :- $X =:= $Y."];
+
+    for r in rules {
+        match parse_rule(r) {
+            Ok(rule) => { add_rules!(&mut kb, rule); },
+            Err(err) => { panic!("Should be no parsing errors. {}", err); },
+        }
+    }
+
+    let mut results: Vec<String> = vec![];
+    let queries = ["double(3, 6)", "double(3, 6.0)", "double(3, 7)",
+                   "odd(7)", "odd(8)",
+                   "same(1r2, 0.5)", "same(1r3, 2r6)", "same(4, 4)",
+                   "same(Joseph, Joseph)", "same($Z, 1)"];
+
+    for q in queries {
+        let query = parse_query(q).unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        results.push(solve(sn));
+    }
+
+    // Atoms and unbound variables cannot be evaluated.
+    let s = "[\"\", \"\", \"No more.\", \"\", \"No more.\", \
+              \"\", \"\", \"\", \"No more.\", \"No more.\"]";
+    assert_eq!(s, format!("{:?}", results));
+
+} // test_arithmetic_comparison()
+
+#[test]
+pub fn test_compare_expressions() {
+
+    let mut kb = KnowledgeBase::new();
+
+    let rules = ["check($X, $Y) :- $X * 2 + 1 > $Y - 3.",
+                 "in_range($X) :- $X >= 10 / 4, ($X - 1) * 2 < 10.",
+                 "smaller($X, $Y) :- $X <= $Y."];
+
+    for r in rules {
+        match parse_rule(r) {
+            Ok(rule) => { add_rules!(&mut kb, rule); },
+            Err(err) => { panic!("Should be no parsing errors. {}", err); },
+        }
+    }
+
+    let mut results: Vec<String> = vec![];
+    let queries = ["check(4, 11)", "check(4, 12)", "check(4.5, 12)",
+                   "in_range(3)", "in_range(1)", "in_range(6)",
+                   "smaller(1r3, 0.5)", "smaller(2r3, 0.5)",
+                   "smaller(Alice, Bob)", "check($Z, 1)"];
+
+    for q in queries {
+        let query = parse_query(q).unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        results.push(solve(sn));
+    }
+
+    let s = "[\"\", \"No more.\", \"\", \
+              \"\", \"No more.\", \"No more.\", \
+              \"\", \"No more.\", \"\", \"No more.\"]";
+    assert_eq!(s, format!("{:?}", results));
+
+} // test_compare_expressions()
+
+#[test]
+pub fn test_equal_rationals() {
+
+    let mut kb = KnowledgeBase::new();
+
+    let rules = ["identical($X, $Y) :- $X == $Y.",
+                 "third($Y) :- $Y is 1 rdiv 3, $Y == 1r3."];
+
+    for r in rules {
+        match parse_rule(r) {
+            Ok(rule) => { add_rules!(&mut kb, rule); },
+            Err(err) => { panic!("Should be no parsing errors. {}", err); },
+        }
+    }
+
+    let mut results: Vec<String> = vec![];
+    let queries = ["identical(1r3, 1r3)", "identical(1r3, 2r6)",
+                   "identical(1r2, 0.5)", "identical(1r3, 1r4)",
+                   "identical(1r3, third)", "third($X)"];
+
+    for q in queries {
+        let query = parse_query(q).unwrap();
+        let sn = make_base_node(Rc::new(query), &kb);
+        results.push(solve(sn));
+    }
+
+    let s = "[\"\", \"\", \"\", \"No more.\", \"No more.\", \"$X = 1r3\"]";
+    assert_eq!(s, format!("{:?}", results));
+
+} // test_equal_rationals()
@@ -7,9 +7,9 @@ use std::rc::Rc;
 #[test]
 pub fn test_atom() {
 
-    let a1 = Atom("This is an atom.".to_string());
-    let a2 = Atom("This is an atom.".to_string());
-    let a3 = Atom("Just another.".to_string());
+    let a1 = Atom("This is an atom.".into());
+    let a2 = Atom("This is an atom.".into());
+    let a3 = Atom("Just another.".into());
     let anon = Unifiable::Anonymous;
     let ss = empty_ss!();
 
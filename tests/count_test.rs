@@ -93,19 +93,19 @@ pub fn test_count() {
     //print_kb(&kb);
 
     // ?- test_count($X).
-    clear_id();
 
     let x = logic_var!("$X");
     let query = query!(atom!("test_count"), x);
     let sn = make_base_node(Rc::clone(&query), &kb);
 
     let mut results: Vec<i64> = vec![];
-    let timer = start_query_timer(S_TIMEOUT);
+    let token = cancellation_token(&sn);
+    let timer = start_query_timer(S_TIMEOUT, &token);
 
     loop {
 
         let solution = next_solution(Rc::clone(&sn));
-        if query_stopped() { break; }
+        if token.timed_out() { break; }
 
         match solution {
            Some(ss) => {
@@ -121,7 +121,7 @@ pub fn test_count() {
     } // loop
 
     cancel_timer(timer);
-    if query_stopped() {
+    if token.timed_out() {
         let s = format!("Query timed out after {} milliseconds.", S_TIMEOUT);
         println!("{s}");
     }
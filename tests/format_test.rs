@@ -0,0 +1,60 @@
+// Test the built-in predicate format().
+//
+// Format writes formatted text to standard output. These tests parse
+// rules which use format(), and check the text it would produce, by
+// calling format_from_terms() on the parsed arguments.
+//
+//   greet($Name) :- format("~w is ~d years old.~n", [$Name, 37]).
+//   line :- format("~`-t~20|~n").
+//
+// Cleve Lendon  2023
+
+use suiron::*;
+use std::rc::Rc;
+
+#[test]
+pub fn test_format() {
+
+    let mut kb = KnowledgeBase::new();
+
+    let rules = ["greet($Name) :- $Name = Hiroshi, format(\"~w is ~d years old.~n\", [$Name, 37]).",
+                 "line($L) :- format(\"~`-t~20|~n\"), $L = done.",
+                 "price($P) :- $P = 2.5, format(user_output, \"~w~t~10|~t~2f~8+~n\", [Tea, $P])."];
+
+    for r in rules {
+        match parse_rule(r) {
+            Ok(rule) => { add_rules!(&mut kb, rule); },
+            Err(err) => { panic!("Should be no parsing errors. {}", err); },
+        }
+    }
+
+    let query = parse_query("greet($X)").unwrap();
+    let sn = make_base_node(Rc::new(query), &kb);
+    assert_eq!("$X = Hiroshi", solve(sn));
+
+    let query = parse_query("line($X)").unwrap();
+    let sn = make_base_node(Rc::new(query), &kb);
+    assert_eq!("$X = done", solve(sn));
+
+    let query = parse_query("price($X)").unwrap();
+    let sn = make_base_node(Rc::new(query), &kb);
+    assert_eq!("$X = 2.5", solve(sn));
+
+    // Check the formatted text.
+    let ss = empty_ss!();
+    let format = SString("~w is ~d years old.~n".to_string());
+    let args = slist!(false, atom!("Hiroshi"), SInteger(37));
+    let text = format_from_terms(&format, Some(&args), &ss).unwrap();
+    assert_eq!("Hiroshi is 37 years old.\n", text);
+
+    // A single argument need not be in a list.
+    let format = atom!("[~a]");
+    let text = format_from_terms(&format, Some(&atom!("solo")), &ss).unwrap();
+    assert_eq!("[solo]", text);
+
+    let format = SString("~w~t~10|~t~2f~8+".to_string());
+    let args = slist!(false, atom!("Tea"), SFloat(2.5));
+    let text = format_from_terms(&format, Some(&args), &ss).unwrap();
+    assert_eq!("Tea           2.50", text);
+
+} // test_format()
@@ -0,0 +1,39 @@
+// Test the built-in predicate split_string().
+//
+// The split_string predicate splits an atom into a list of atoms.
+//
+//   fields($L) :- split_string("Tom, 32 , Toronto", ",", " ", $L).
+//   # $L = [Tom, 32, Toronto]
+//
+//   path($L) :- split_string("/usr/local/bin", "/", [], $L).
+//   # $L = [, usr, local, bin]
+//
+// Cleve Lendon  2023
+
+use suiron::*;
+use std::rc::Rc;
+
+#[test]
+pub fn test_split_string() {
+
+    let mut kb = KnowledgeBase::new();
+
+    let rules = ["fields($L) :- split_string(\"Tom, 32 , Toronto\", \",\", \" \", $L).",
+                 "path($L) :- split_string(\"/usr/local/bin\", \"/\", [], $L)."];
+
+    for r in rules {
+        match parse_rule(r) {
+            Ok(rule) => { add_rules!(&mut kb, rule); },
+            Err(err) => { panic!("Should be no parsing errors. {}", err); },
+        }
+    }
+
+    let query = parse_query("fields($X)").unwrap();
+    let sn = make_base_node(Rc::new(query), &kb);
+    assert_eq!("$X = [Tom, '32', Toronto]", solve(sn));
+
+    let query = parse_query("path($X)").unwrap();
+    let sn = make_base_node(Rc::new(query), &kb);
+    assert_eq!("$X = ['', usr, local, bin]", solve(sn));
+
+} // test_split_string()